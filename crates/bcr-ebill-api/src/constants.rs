@@ -5,3 +5,7 @@ pub const SAT_TO_BTC_RATE: i64 = 100_000_000;
 pub const MAX_FILE_SIZE_BYTES: usize = 1_000_000; // ~1 MB
 pub const MAX_FILE_NAME_CHARACTERS: usize = 200;
 pub const VALID_FILE_MIME_TYPES: [&str; 3] = ["image/jpeg", "image/png", "application/pdf"];
+
+// Search
+pub const DEFAULT_BILL_SEARCH_PAGE_SIZE: usize = 50;
+pub const MAX_BILL_SEARCH_PAGE_SIZE: usize = 500;
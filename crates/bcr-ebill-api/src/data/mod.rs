@@ -1,8 +1,14 @@
+pub use bcr_ebill_core::api_token;
 pub use bcr_ebill_core::bill;
+pub use bcr_ebill_core::bill_template;
 pub use bcr_ebill_core::company;
 pub use bcr_ebill_core::contact;
+pub use bcr_ebill_core::contact_card;
 pub use bcr_ebill_core::identity;
 pub use bcr_ebill_core::notification;
+pub use bcr_ebill_core::profile;
+pub use bcr_ebill_core::search;
+pub use bcr_ebill_core::webhook;
 
 pub use bcr_ebill_core::File;
 pub use bcr_ebill_core::GeneralSearchFilterItemType;
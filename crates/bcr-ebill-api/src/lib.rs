@@ -1,4 +1,5 @@
 use anyhow::{Result, anyhow};
+use bcr_ebill_core::contact::ContactVerificationPolicy;
 use bitcoin::Network;
 use std::sync::OnceLock;
 
@@ -16,14 +17,25 @@ pub use blockchain::Block;
 pub use blockchain::Blockchain;
 pub use persistence::DbContext;
 pub use persistence::get_db_context;
+pub use persistence::nostr::{NostrOutboxEntry, NostrOutboxStatus, QuarantinedEvent};
 pub use persistence::notification::NotificationFilter;
+pub use service::notification_service::{
+    EmailConfig, EmailTransportConfig, SendgridConfig, SmtpConfig, SmtpTls,
+};
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub bitcoin_network: String,
-    pub nostr_relay: String,
+    /// The relays we publish to and receive events from
+    pub nostr_relays: Vec<String>,
+    /// To how many relays an event has to be published, for the publish to be successful
+    pub nostr_write_quorum: usize,
     pub surreal_db_connection: String,
     pub data_dir: String,
+    /// Events are only sent via email, if this is set
+    pub email: Option<EmailConfig>,
+    /// How issuing and endorsing bills to unverified contacts is handled
+    pub contact_verification_policy: ContactVerificationPolicy,
 }
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
@@ -1,22 +1,29 @@
 use crate::Config;
 use bcr_ebill_persistence::{
-    BackupStoreApi, ContactStoreApi, FileUploadStore, NostrEventOffsetStoreApi,
-    NotificationStoreApi, SurrealBackupStore, SurrealBillChainStore, SurrealBillStore,
-    SurrealCompanyChainStore, SurrealCompanyStore, SurrealContactStore, SurrealDbConfig,
-    SurrealIdentityChainStore, SurrealIdentityStore, SurrealNostrEventOffsetStore,
-    SurrealNotificationStore,
+    ApiTokenStoreApi, BackupStoreApi, BillBatchStoreApi, BillTemplateStoreApi, ContactStoreApi,
+    FileUploadStore, NostrEventOffsetStoreApi, NostrOutboxStoreApi, NostrPendingEventStoreApi,
+    NostrQuarantineStoreApi, NotificationStoreApi, SearchIndexStoreApi, SurrealApiTokenStore,
+    SurrealBackupStore, SurrealBillBatchStore, SurrealBillChainStore, SurrealBillStore,
+    SurrealBillTemplateStore, SurrealCompanyChainStore, SurrealCompanyStore, SurrealContactStore,
+    SurrealDbConfig, SurrealIdentityChainStore, SurrealIdentityStore, SurrealNostrEventOffsetStore,
+    SurrealNostrOutboxStore, SurrealNostrPendingEventStore, SurrealNostrQuarantineStore,
+    SurrealNotificationStore, SurrealSearchIndexStore, SurrealWebhookStore, WebhookStoreApi,
     bill::{BillChainStoreApi, BillStoreApi},
     company::{CompanyChainStoreApi, CompanyStoreApi},
     file_upload::FileUploadStoreApi,
-    get_surreal_db,
+    get_migrated_surreal_db,
     identity::{IdentityChainStoreApi, IdentityStoreApi},
+    is_sqlite_connection,
 };
 use log::error;
 use std::sync::Arc;
 
 pub use bcr_ebill_persistence::Error;
+pub use bcr_ebill_persistence::api_token;
 pub use bcr_ebill_persistence::backup;
 pub use bcr_ebill_persistence::bill;
+pub use bcr_ebill_persistence::bill_batch;
+pub use bcr_ebill_persistence::bill_template;
 pub use bcr_ebill_persistence::company;
 pub use bcr_ebill_persistence::contact;
 pub use bcr_ebill_persistence::db;
@@ -24,6 +31,8 @@ pub use bcr_ebill_persistence::file_upload;
 pub use bcr_ebill_persistence::identity;
 pub use bcr_ebill_persistence::nostr;
 pub use bcr_ebill_persistence::notification;
+pub use bcr_ebill_persistence::search_index;
+pub use bcr_ebill_persistence::webhook;
 
 /// A container for all persistence related dependencies.
 #[derive(Clone)]
@@ -31,22 +40,27 @@ pub struct DbContext {
     pub contact_store: Arc<dyn ContactStoreApi>,
     pub bill_store: Arc<dyn BillStoreApi>,
     pub bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+    pub bill_template_store: Arc<dyn BillTemplateStoreApi>,
+    pub bill_batch_store: Arc<dyn BillBatchStoreApi>,
     pub identity_store: Arc<dyn IdentityStoreApi>,
     pub identity_chain_store: Arc<dyn IdentityChainStoreApi>,
     pub company_chain_store: Arc<dyn CompanyChainStoreApi>,
     pub company_store: Arc<dyn CompanyStoreApi>,
     pub file_upload_store: Arc<dyn FileUploadStoreApi>,
     pub nostr_event_offset_store: Arc<dyn NostrEventOffsetStoreApi>,
+    pub nostr_quarantine_store: Arc<dyn NostrQuarantineStoreApi>,
+    pub nostr_pending_event_store: Arc<dyn NostrPendingEventStoreApi>,
+    pub nostr_outbox_store: Arc<dyn NostrOutboxStoreApi>,
     pub notification_store: Arc<dyn NotificationStoreApi>,
     pub backup_store: Arc<dyn BackupStoreApi>,
+    pub search_index_store: Arc<dyn SearchIndexStoreApi>,
+    pub api_token_store: Arc<dyn ApiTokenStoreApi>,
+    pub webhook_store: Arc<dyn WebhookStoreApi>,
 }
 
-/// Creates a new instance of the DbContext with the given SurrealDB configuration.
+/// Creates a new instance of the DbContext. The backend is picked from the connection string,
+/// `sqlite:` connection strings use SQLite, everything else SurrealDB.
 pub async fn get_db_context(conf: &Config) -> bcr_ebill_persistence::Result<DbContext> {
-    let surreal_db_config = SurrealDbConfig::new(&conf.surreal_db_connection);
-    let db = get_surreal_db(&surreal_db_config).await?;
-
-    let company_store = Arc::new(SurrealCompanyStore::new(db.clone()));
     let file_upload_store =
         Arc::new(FileUploadStore::new(&conf.data_dir, "files", "temp_upload").await?);
 
@@ -54,30 +68,104 @@ pub async fn get_db_context(conf: &Config) -> bcr_ebill_persistence::Result<DbCo
         error!("Error cleaning up temp upload folder for bill: {e}");
     }
 
+    if is_sqlite_connection(&conf.surreal_db_connection) {
+        return get_sqlite_db_context(conf, file_upload_store).await;
+    }
+
+    let surreal_db_config = SurrealDbConfig::new(&conf.surreal_db_connection);
+    let db = get_migrated_surreal_db(&surreal_db_config).await?;
+
+    let company_store = Arc::new(SurrealCompanyStore::new(db.clone()));
     let contact_store = Arc::new(SurrealContactStore::new(db.clone()));
 
     let bill_store = Arc::new(SurrealBillStore::new(db.clone()));
     let bill_blockchain_store = Arc::new(SurrealBillChainStore::new(db.clone()));
+    let bill_template_store = Arc::new(SurrealBillTemplateStore::new(db.clone()));
+    let bill_batch_store = Arc::new(SurrealBillBatchStore::new(db.clone()));
 
     let identity_store = Arc::new(SurrealIdentityStore::new(db.clone()));
     let identity_chain_store = Arc::new(SurrealIdentityChainStore::new(db.clone()));
     let company_chain_store = Arc::new(SurrealCompanyChainStore::new(db.clone()));
 
     let nostr_event_offset_store = Arc::new(SurrealNostrEventOffsetStore::new(db.clone()));
+    let nostr_quarantine_store = Arc::new(SurrealNostrQuarantineStore::new(db.clone()));
+    let nostr_pending_event_store = Arc::new(SurrealNostrPendingEventStore::new(db.clone()));
+    let nostr_outbox_store = Arc::new(SurrealNostrOutboxStore::new(db.clone()));
     let notification_store = Arc::new(SurrealNotificationStore::new(db.clone()));
     let backup_store = Arc::new(SurrealBackupStore::new(db.clone()));
+    let search_index_store = Arc::new(SurrealSearchIndexStore::new(db.clone()));
+    let api_token_store = Arc::new(SurrealApiTokenStore::new(db.clone()));
+    let webhook_store = Arc::new(SurrealWebhookStore::new(db.clone()));
 
     Ok(DbContext {
         contact_store,
         bill_store,
         bill_blockchain_store,
+        bill_template_store,
+        bill_batch_store,
         identity_store,
         identity_chain_store,
         company_chain_store,
         company_store,
         file_upload_store,
         nostr_event_offset_store,
+        nostr_quarantine_store,
+        nostr_pending_event_store,
+        nostr_outbox_store,
         notification_store,
         backup_store,
+        search_index_store,
+        api_token_store,
+        webhook_store,
     })
 }
+
+#[cfg(feature = "sqlite")]
+async fn get_sqlite_db_context(
+    conf: &Config,
+    file_upload_store: Arc<FileUploadStore>,
+) -> bcr_ebill_persistence::Result<DbContext> {
+    use bcr_ebill_persistence::{
+        SqliteApiTokenStore, SqliteBackupStore, SqliteBillBatchStore, SqliteBillChainStore,
+        SqliteBillStore, SqliteBillTemplateStore, SqliteCompanyChainStore, SqliteCompanyStore,
+        SqliteContactStore, SqliteIdentityChainStore, SqliteIdentityStore,
+        SqliteNostrEventOffsetStore, SqliteNostrOutboxStore, SqliteNostrPendingEventStore,
+        SqliteNostrQuarantineStore, SqliteNotificationStore, SqliteSearchIndexStore,
+        SqliteWebhookStore, get_sqlite_db,
+    };
+
+    let db = get_sqlite_db(&conf.surreal_db_connection).await?;
+
+    Ok(DbContext {
+        contact_store: Arc::new(SqliteContactStore::new(db.clone())),
+        bill_store: Arc::new(SqliteBillStore::new(db.clone())),
+        bill_blockchain_store: Arc::new(SqliteBillChainStore::new(db.clone())),
+        bill_template_store: Arc::new(SqliteBillTemplateStore::new(db.clone())),
+        bill_batch_store: Arc::new(SqliteBillBatchStore::new(db.clone())),
+        identity_store: Arc::new(SqliteIdentityStore::new(db.clone())),
+        identity_chain_store: Arc::new(SqliteIdentityChainStore::new(db.clone())),
+        company_chain_store: Arc::new(SqliteCompanyChainStore::new(db.clone())),
+        company_store: Arc::new(SqliteCompanyStore::new(db.clone())),
+        file_upload_store,
+        nostr_event_offset_store: Arc::new(SqliteNostrEventOffsetStore::new(db.clone())),
+        nostr_quarantine_store: Arc::new(SqliteNostrQuarantineStore::new(db.clone())),
+        nostr_pending_event_store: Arc::new(SqliteNostrPendingEventStore::new(db.clone())),
+        nostr_outbox_store: Arc::new(SqliteNostrOutboxStore::new(db.clone())),
+        notification_store: Arc::new(SqliteNotificationStore::new(db.clone())),
+        backup_store: Arc::new(SqliteBackupStore::new(db.clone())),
+        search_index_store: Arc::new(SqliteSearchIndexStore::new(db.clone())),
+        api_token_store: Arc::new(SqliteApiTokenStore::new(db.clone())),
+        webhook_store: Arc::new(SqliteWebhookStore::new(db)),
+    })
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn get_sqlite_db_context(
+    conf: &Config,
+    _file_upload_store: Arc<FileUploadStore>,
+) -> bcr_ebill_persistence::Result<DbContext> {
+    Err(Error::UnsupportedBackend(format!(
+        "{} requires the sqlite feature",
+        conf.surreal_db_connection
+    )))
+}
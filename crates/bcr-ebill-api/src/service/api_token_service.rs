@@ -0,0 +1,252 @@
+use super::{Error, Result};
+use crate::data::api_token::{API_TOKEN_PREFIX, ApiToken, ApiTokenScope};
+use crate::persistence::api_token::ApiTokenStoreApi;
+use crate::util;
+use async_trait::async_trait;
+use log::{error, info};
+use std::sync::Arc;
+
+#[cfg(test)]
+use mockall::automock;
+
+/// The last use of a token is only stored, if the previous one is older than this, so not every
+/// request leads to a write
+const LAST_USED_RESOLUTION_SECONDS: u64 = 60;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ApiTokenServiceApi: Send + Sync {
+    /// Creates a new API token with the given scopes and returns it with its secret, which is
+    /// only available at this point, since only its hash is stored
+    async fn create_token(
+        &self,
+        name: &str,
+        scopes: Vec<ApiTokenScope>,
+        timestamp: u64,
+    ) -> Result<(ApiToken, String)>;
+
+    /// Returns all API tokens
+    async fn list_tokens(&self) -> Result<Vec<ApiToken>>;
+
+    /// Removes the API token with the given id, it can't be used afterwards
+    async fn remove_token(&self, id: &str) -> Result<()>;
+
+    /// Returns the API token for the given secret, if it's valid
+    async fn authenticate(&self, secret: &str, timestamp: u64) -> Result<Option<ApiToken>>;
+}
+
+/// The API token service is responsible for managing the tokens for machine-to-machine access
+/// to the HTTP API
+#[derive(Clone)]
+pub struct ApiTokenService {
+    store: Arc<dyn ApiTokenStoreApi>,
+}
+
+impl ApiTokenService {
+    pub fn new(store: Arc<dyn ApiTokenStoreApi>) -> Self {
+        Self { store }
+    }
+}
+
+/// Hashes the secret of an API token for storing and looking it up
+fn hash_secret(secret: &str) -> String {
+    util::sha256_hash(secret.as_bytes())
+}
+
+#[async_trait]
+impl ApiTokenServiceApi for ApiTokenService {
+    async fn create_token(
+        &self,
+        name: &str,
+        scopes: Vec<ApiTokenScope>,
+        timestamp: u64,
+    ) -> Result<(ApiToken, String)> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::Validation(String::from(
+                "The name of an API token must not be empty",
+            )));
+        }
+        if scopes.is_empty() {
+            return Err(Error::Validation(String::from(
+                "An API token needs at least one scope",
+            )));
+        }
+        let mut unique_scopes: Vec<ApiTokenScope> = vec![];
+        for scope in scopes {
+            if !unique_scopes.contains(&scope) {
+                unique_scopes.push(scope);
+            }
+        }
+
+        let secret = format!(
+            "{API_TOKEN_PREFIX}{}{}",
+            util::get_uuid_v4().simple(),
+            util::get_uuid_v4().simple()
+        );
+        let token = ApiToken {
+            id: util::get_uuid_v4().to_string(),
+            name: name.to_owned(),
+            scopes: unique_scopes,
+            created_at: timestamp,
+            last_used_at: None,
+        };
+        self.store.insert(&token, &hash_secret(&secret)).await?;
+        info!("Created API token {} ({})", token.name, token.id);
+        Ok((token, secret))
+    }
+
+    async fn list_tokens(&self) -> Result<Vec<ApiToken>> {
+        Ok(self.store.list().await?)
+    }
+
+    async fn remove_token(&self, id: &str) -> Result<()> {
+        if !self.store.list().await?.iter().any(|t| t.id == id) {
+            return Err(Error::NotFound);
+        }
+        self.store.remove(id).await?;
+        info!("Removed API token {id}");
+        Ok(())
+    }
+
+    async fn authenticate(&self, secret: &str, timestamp: u64) -> Result<Option<ApiToken>> {
+        if !secret.starts_with(API_TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let token = match self.store.get_by_hash(&hash_secret(secret)).await? {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        let outdated = token
+            .last_used_at
+            .is_none_or(|last_used| timestamp > last_used + LAST_USED_RESOLUTION_SECONDS);
+        if outdated {
+            // a failure to track the last use shouldn't block the request
+            if let Err(e) = self.store.set_last_used(&token.id, timestamp).await {
+                error!("Could not set the last use of API token {}: {e}", token.id);
+            }
+        }
+        Ok(Some(token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::tests::MockApiTokenStoreApiMock;
+    use mockall::predicate::{always, eq};
+
+    fn get_token(last_used_at: Option<u64>) -> ApiToken {
+        ApiToken {
+            id: "some_id".to_string(),
+            name: "erp".to_string(),
+            scopes: vec![ApiTokenScope::BillsWrite],
+            created_at: 1731593928,
+            last_used_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_token_stores_hash_of_secret() {
+        let mut store = MockApiTokenStoreApiMock::new();
+        store
+            .expect_insert()
+            .withf(|token, hash| {
+                token.name == "erp"
+                    && token.scopes == vec![ApiTokenScope::BillsRead]
+                    && !hash.starts_with(API_TOKEN_PREFIX)
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let service = ApiTokenService::new(Arc::new(store));
+
+        let (token, secret) = service
+            .create_token(
+                " erp ",
+                vec![ApiTokenScope::BillsRead, ApiTokenScope::BillsRead],
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.name, "erp");
+        assert!(secret.starts_with(API_TOKEN_PREFIX));
+        assert_ne!(hash_secret(&secret), secret);
+    }
+
+    #[tokio::test]
+    async fn create_token_validates_name_and_scopes() {
+        let service = ApiTokenService::new(Arc::new(MockApiTokenStoreApiMock::new()));
+        assert!(matches!(
+            service
+                .create_token("", vec![ApiTokenScope::Admin], 1731593928)
+                .await,
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            service.create_token("erp", vec![], 1731593928).await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn remove_token_fails_for_unknown_token() {
+        let mut store = MockApiTokenStoreApiMock::new();
+        store.expect_list().returning(|| Ok(vec![get_token(None)]));
+        store
+            .expect_remove()
+            .with(eq("some_id"))
+            .returning(|_| Ok(()));
+        let service = ApiTokenService::new(Arc::new(store));
+
+        assert!(matches!(
+            service.remove_token("other_id").await,
+            Err(Error::NotFound)
+        ));
+        assert!(service.remove_token("some_id").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn authenticate_checks_hash_and_tracks_last_use() {
+        let secret = format!("{API_TOKEN_PREFIX}secret");
+        let hash = hash_secret(&secret);
+        let mut store = MockApiTokenStoreApiMock::new();
+        store.expect_get_by_hash().returning(move |h| {
+            if h == hash {
+                Ok(Some(get_token(Some(1731593900))))
+            } else {
+                Ok(None)
+            }
+        });
+        store
+            .expect_set_last_used()
+            .with(eq("some_id"), always())
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let service = ApiTokenService::new(Arc::new(store));
+
+        // used recently, so the last use isn't updated
+        let token = service.authenticate(&secret, 1731593910).await.unwrap();
+        assert_eq!(token, Some(get_token(Some(1731593900))));
+        assert!(
+            service
+                .authenticate(&secret, 1731594000)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            service
+                .authenticate(&format!("{API_TOKEN_PREFIX}other"), 1731594000)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            service
+                .authenticate("session token", 1731594000)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
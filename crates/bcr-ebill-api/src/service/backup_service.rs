@@ -1,19 +1,68 @@
 use std::{path::Path, sync::Arc};
 
 use crate::{
-    persistence::{backup::BackupStoreApi, db::SurrealDbConfig, identity::IdentityStoreApi},
-    util,
+    data::{
+        File,
+        bill::BillKeys,
+        company::{Company, CompanyKeys},
+        contact::Contact,
+        identity::Identity,
+        notification::Notification,
+    },
+    persistence::{
+        DbContext,
+        api_token::ApiTokenStoreApi,
+        backup::BackupStoreApi,
+        bill::{BillChainStoreApi, BillStoreApi},
+        bill_batch::BillBatchStoreApi,
+        bill_template::BillTemplateStoreApi,
+        company::{CompanyChainStoreApi, CompanyStoreApi},
+        contact::ContactStoreApi,
+        db::SurrealDbConfig,
+        file_upload::FileUploadStoreApi,
+        identity::{IdentityChainStoreApi, IdentityStoreApi},
+        nostr::{
+            NostrEventOffset, NostrEventOffsetStoreApi, NostrOutboxEntry, NostrOutboxStatus,
+            NostrOutboxStoreApi, NostrPendingEventStoreApi, NostrQuarantineStoreApi, PendingEvent,
+            QuarantinedEvent,
+        },
+        notification::{NotificationFilter, NotificationStoreApi},
+        search_index::SearchIndexStoreApi,
+        webhook::WebhookStoreApi,
+    },
+    util::{self, BcrKeys},
 };
+use bcr_ebill_core::api_token::ApiToken;
+use bcr_ebill_core::bill::BillBatchIssue;
+use bcr_ebill_core::bill_template::{BillTemplate, BillTemplateIssue};
+use bcr_ebill_core::blockchain::{
+    Blockchain,
+    bill::{BillBlock, BillBlockchain},
+    company::{CompanyBlock, CompanyBlockchain},
+    identity::{IdentityBlock, IdentityBlockchain},
+};
+use bcr_ebill_core::notification::EventType;
+use bcr_ebill_core::webhook::{Webhook, WebhookDelivery};
 
 use super::{Error, Result};
+use log::{error, info, warn};
 #[cfg(test)]
 use mockall::automock;
+use serde::{Deserialize, Serialize};
 use tokio::{
-    fs::File,
+    fs::File as FsFile,
     io::{AsyncReadExt, AsyncWriteExt},
     sync::watch,
 };
 
+/// The version of the logical backup format, has to be increased on incompatible changes.
+/// Version 2 added templates, the bills issued from batches, webhooks, API tokens and the Nostr
+/// outbox, quarantine and pending events
+const BACKUP_FORMAT_VERSION: u32 = 2;
+
+/// The page size used when reading all notifications for a backup
+const NOTIFICATION_PAGE_SIZE: i64 = 200;
+
 /// Allows to backup and restore the database as an encrypted file.
 #[cfg_attr(test, automock)]
 #[async_trait::async_trait]
@@ -26,28 +75,65 @@ pub trait BackupServiceApi: Send + Sync {
     async fn restore(&self, file: &Path) -> Result<()>;
 }
 
+/// Creates backups by reading all data via the store APIs, so the backup is independent of the
+/// database backend it was created with and can be restored into any other backend.
 pub struct BackupService {
     store: Arc<dyn BackupStoreApi>,
     identity_store: Arc<dyn IdentityStoreApi>,
+    identity_chain_store: Arc<dyn IdentityChainStoreApi>,
+    contact_store: Arc<dyn ContactStoreApi>,
+    company_store: Arc<dyn CompanyStoreApi>,
+    company_chain_store: Arc<dyn CompanyChainStoreApi>,
+    bill_store: Arc<dyn BillStoreApi>,
+    bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+    bill_template_store: Arc<dyn BillTemplateStoreApi>,
+    bill_batch_store: Arc<dyn BillBatchStoreApi>,
+    notification_store: Arc<dyn NotificationStoreApi>,
+    nostr_event_offset_store: Arc<dyn NostrEventOffsetStoreApi>,
+    nostr_outbox_store: Arc<dyn NostrOutboxStoreApi>,
+    nostr_quarantine_store: Arc<dyn NostrQuarantineStoreApi>,
+    nostr_pending_event_store: Arc<dyn NostrPendingEventStoreApi>,
+    file_upload_store: Arc<dyn FileUploadStoreApi>,
+    search_index_store: Arc<dyn SearchIndexStoreApi>,
+    api_token_store: Arc<dyn ApiTokenStoreApi>,
+    webhook_store: Arc<dyn WebhookStoreApi>,
     surreal_db_config: SurrealDbConfig,
     reboot_sender: watch::Sender<bool>,
 }
 
 impl BackupService {
     pub fn new(
-        store: Arc<dyn BackupStoreApi>,
-        identity_store: Arc<dyn IdentityStoreApi>,
+        db: DbContext,
         surreal_db_config: SurrealDbConfig,
         reboot_sender: watch::Sender<bool>,
     ) -> Self {
         Self {
-            store,
-            identity_store,
+            store: db.backup_store,
+            identity_store: db.identity_store,
+            identity_chain_store: db.identity_chain_store,
+            contact_store: db.contact_store,
+            company_store: db.company_store,
+            company_chain_store: db.company_chain_store,
+            bill_store: db.bill_store,
+            bill_blockchain_store: db.bill_blockchain_store,
+            bill_template_store: db.bill_template_store,
+            bill_batch_store: db.bill_batch_store,
+            notification_store: db.notification_store,
+            nostr_event_offset_store: db.nostr_event_offset_store,
+            nostr_outbox_store: db.nostr_outbox_store,
+            nostr_quarantine_store: db.nostr_quarantine_store,
+            nostr_pending_event_store: db.nostr_pending_event_store,
+            file_upload_store: db.file_upload_store,
+            search_index_store: db.search_index_store,
+            api_token_store: db.api_token_store,
+            webhook_store: db.webhook_store,
             surreal_db_config,
             reboot_sender,
         }
     }
 
+    /// Backups created before the logical backup format are SurrealDB exports, which can only be
+    /// imported on connections that support it
     fn validate_surreal_db_connection(&self) -> Result<()> {
         let connection = &self.surreal_db_config.connection_string;
         if connection.starts_with("rocksdb")
@@ -57,37 +143,333 @@ impl BackupService {
             return Ok(());
         }
         Err(Error::Validation(format!(
-            "SurrealDB connection {} does not support exports",
+            "SurrealDB connection {} does not support imports",
             connection
         )))
     }
+
+    async fn export_data(&self, keys: &BcrKeys) -> Result<BackupData> {
+        let mut files: Vec<(String, File)> = vec![];
+
+        let identity = if self.identity_store.exists().await {
+            let identity = self.identity_store.get().await?;
+            let chain = self.identity_chain_store.get_chain().await?;
+            add_files(
+                &mut files,
+                &identity.node_id,
+                [
+                    &identity.profile_picture_file,
+                    &identity.identity_document_file,
+                ],
+            );
+            Some(IdentityBackup {
+                identity,
+                chain: chain.blocks().to_owned(),
+            })
+        } else {
+            None
+        };
+
+        let contacts: Vec<Contact> = self.contact_store.get_map().await?.into_values().collect();
+        for contact in contacts.iter() {
+            add_files(
+                &mut files,
+                &contact.node_id,
+                [
+                    &contact.avatar_file,
+                    &contact.proof_document_file,
+                    &contact.verification_evidence_file,
+                ],
+            );
+        }
+
+        let mut companies = vec![];
+        for (id, (company, company_keys)) in self.company_store.get_all().await? {
+            let chain = self.company_chain_store.get_chain(&id).await?;
+            add_files(
+                &mut files,
+                &id,
+                [&company.proof_of_registration_file, &company.logo_file],
+            );
+            companies.push(CompanyBackup {
+                company,
+                keys: company_keys,
+                chain: chain.blocks().to_owned(),
+            });
+        }
+
+        let mut bills = vec![];
+        for id in self.bill_store.get_ids().await? {
+            let bill_keys = self.bill_store.get_keys(&id).await?;
+            let chain = self.bill_blockchain_store.get_chain(&id).await?;
+            let first_version_bill = chain.get_first_version_bill(&bill_keys)?;
+            for file in first_version_bill.files {
+                files.push((id.clone(), file));
+            }
+            let paid_payment_address = self.bill_store.get_paid_payment_address(&id).await?;
+            bills.push(BillBackup {
+                id,
+                keys: bill_keys,
+                chain: chain.blocks().to_owned(),
+                paid_payment_address,
+            });
+        }
+
+        let mut notifications = vec![];
+        loop {
+            let page = self
+                .notification_store
+                .list(NotificationFilter {
+                    limit: Some(NOTIFICATION_PAGE_SIZE),
+                    offset: Some(notifications.len() as i64),
+                    ..Default::default()
+                })
+                .await?;
+            let page_len = page.len() as i64;
+            notifications.extend(page);
+            if page_len < NOTIFICATION_PAGE_SIZE {
+                break;
+            }
+        }
+
+        let nostr_event_offsets = self
+            .nostr_event_offset_store
+            .get_all()
+            .await?
+            .into_iter()
+            .map(|e| e.into())
+            .collect();
+
+        let mut bill_templates = vec![];
+        for template in self.bill_template_store.list().await? {
+            let issues = self.bill_template_store.list_issues(&template.id).await?;
+            bill_templates.push(BillTemplateBackup { template, issues });
+        }
+
+        let api_tokens = self
+            .api_token_store
+            .list_with_hashes()
+            .await?
+            .into_iter()
+            .map(|(token, token_hash)| ApiTokenBackup { token, token_hash })
+            .collect();
+
+        let nostr_outbox = self
+            .nostr_outbox_store
+            .get_all()
+            .await?
+            .into_iter()
+            .map(|e| e.into())
+            .collect();
+        let quarantined_events = self
+            .nostr_quarantine_store
+            .list()
+            .await?
+            .into_iter()
+            .map(|e| e.into())
+            .collect();
+        let pending_events = self
+            .nostr_pending_event_store
+            .list()
+            .await?
+            .into_iter()
+            .map(|e| e.into())
+            .collect();
+
+        let mut attached_files = vec![];
+        for (id, file) in files {
+            match self
+                .file_upload_store
+                .open_attached_file(&id, &file.name)
+                .await
+            {
+                Ok(bytes) => attached_files.push(AttachedFileBackup {
+                    id,
+                    file_name: file.name,
+                    data: util::base58_encode(&bytes),
+                }),
+                Err(e) => warn!("Could not add file {} of {id} to backup: {e}", file.name),
+            }
+        }
+
+        Ok(BackupData {
+            version: BACKUP_FORMAT_VERSION,
+            private_key: keys.get_private_key_string(),
+            seed_phrase: self.identity_store.get_seedphrase().await?,
+            identity,
+            contacts,
+            companies,
+            bills,
+            notifications,
+            nostr_event_offsets,
+            attached_files,
+            bill_templates,
+            bill_batch_issues: self.bill_batch_store.get_all().await?,
+            webhooks: self.webhook_store.list().await?,
+            webhook_deliveries: self.webhook_store.get_all_deliveries().await?,
+            api_tokens,
+            nostr_outbox,
+            quarantined_events,
+            pending_events,
+        })
+    }
+
+    /// Replaces the database with the validated backup. The current data is exported first and
+    /// written back, if the import fails half way, so a failed restore doesn't leave a
+    /// partially restored database behind
+    async fn import_data(&self, data: BackupData, current_keys: &BcrKeys) -> Result<()> {
+        validate_backup(&data)?;
+        let current_data = self.export_data(current_keys).await?;
+
+        self.store.drop_db(&self.surreal_db_config.database).await?;
+        if let Err(e) = self.write_data(data).await {
+            error!("Could not restore backup, rolling back to the previous data: {e}");
+            self.store.drop_db(&self.surreal_db_config.database).await?;
+            self.write_data(current_data).await?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Writes the backed up data into the empty database
+    async fn write_data(&self, data: BackupData) -> Result<()> {
+        let keys = BcrKeys::from_private_key(&data.private_key)?;
+        self.identity_store
+            .save_key_pair(&keys, &data.seed_phrase)
+            .await?;
+        if let Some(identity_backup) = data.identity {
+            self.identity_store.save(&identity_backup.identity).await?;
+            for block in identity_backup.chain.iter() {
+                self.identity_chain_store.add_block(block).await?;
+            }
+        }
+
+        for contact in data.contacts {
+            self.contact_store
+                .insert(&contact.node_id.clone(), contact)
+                .await?;
+        }
+
+        for company_backup in data.companies {
+            let id = company_backup.company.id.clone();
+            self.company_store.insert(&company_backup.company).await?;
+            self.company_store
+                .save_key_pair(&id, &company_backup.keys)
+                .await?;
+            for block in company_backup.chain.iter() {
+                self.company_chain_store.add_block(&id, block).await?;
+            }
+        }
+
+        for bill_backup in data.bills {
+            self.bill_store
+                .save_keys(&bill_backup.id, &bill_backup.keys)
+                .await?;
+            for block in bill_backup.chain.iter() {
+                self.bill_blockchain_store
+                    .add_block(&bill_backup.id, block)
+                    .await?;
+            }
+            if let Some(payment_address) = bill_backup.paid_payment_address {
+                self.bill_store
+                    .set_to_paid(&bill_backup.id, &payment_address)
+                    .await?;
+            }
+        }
+
+        for notification in data.notifications {
+            self.notification_store.add(notification).await?;
+        }
+
+        for event in data.nostr_event_offsets {
+            self.nostr_event_offset_store
+                .add_event(event.into())
+                .await?;
+        }
+
+        for template_backup in data.bill_templates {
+            self.bill_template_store
+                .insert(&template_backup.template)
+                .await?;
+            for issue in template_backup.issues.iter() {
+                self.bill_template_store.add_issue(issue).await?;
+            }
+        }
+
+        for issue in data.bill_batch_issues.iter() {
+            self.bill_batch_store.add_issue(issue).await?;
+        }
+
+        for webhook in data.webhooks.iter() {
+            self.webhook_store.insert(webhook).await?;
+        }
+        for delivery in data.webhook_deliveries.iter() {
+            self.webhook_store.insert_delivery(delivery).await?;
+        }
+
+        for token_backup in data.api_tokens {
+            self.api_token_store
+                .insert(&token_backup.token, &token_backup.token_hash)
+                .await?;
+        }
+
+        for entry in data.nostr_outbox {
+            self.nostr_outbox_store.insert(&entry.into()).await?;
+        }
+        for event in data.quarantined_events {
+            self.nostr_quarantine_store.add(event.into()).await?;
+        }
+        for event in data.pending_events {
+            self.nostr_pending_event_store.add(event.into()).await?;
+        }
+
+        for file in data.attached_files {
+            let bytes = util::base58_decode(&file.data)
+                .map_err(|e| Error::Validation(format!("Invalid file in backup: {e}")))?;
+            self.file_upload_store
+                .save_attached_file(&bytes, &file.id, &file.file_name)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn import_surreal_export(&self, file_path: &Path, bytes: &[u8]) -> Result<()> {
+        self.validate_surreal_db_connection()?;
+        let out_path = file_path.with_file_name("restore.surql");
+        let mut out = FsFile::create(out_path.as_path()).await?;
+        out.write_all(bytes).await?;
+        self.store.drop_db(&self.surreal_db_config.database).await?;
+        self.store.restore(out_path.as_path()).await?;
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
 impl BackupServiceApi for BackupService {
     async fn backup(&self) -> Result<Vec<u8>> {
-        self.validate_surreal_db_connection()?;
-        let public_key = self.identity_store.get_key_pair().await?.get_public_key();
-        let bytes = self.store.backup().await?;
-        let encrypted_bytes = util::crypto::encrypt_ecies(&bytes, &public_key)?;
+        let keys = self.identity_store.get_key_pair().await?;
+        let data = self.export_data(&keys).await?;
+        let bytes = serde_json::to_vec(&data)?;
+        let encrypted_bytes = util::crypto::encrypt_ecies(&bytes, &keys.get_public_key())?;
         Ok(encrypted_bytes)
     }
 
     async fn restore(&self, file_path: &Path) -> Result<()> {
-        let private_key = self
-            .identity_store
-            .get_key_pair()
-            .await?
-            .get_private_key_string();
+        let keys = self.identity_store.get_key_pair().await?;
         let mut buffer = vec![];
-        let mut file = File::open(file_path).await?;
+        let mut file = FsFile::open(file_path).await?;
         file.read_to_end(&mut buffer).await?;
-        let decrypted_bytes = util::crypto::decrypt_ecies(&buffer, &private_key)?;
-        let out_path = file_path.with_file_name("restore.surql");
-        let mut out = File::create(out_path.as_path()).await?;
-        out.write_all(&decrypted_bytes).await?;
-        self.store.drop_db(&self.surreal_db_config.database).await?;
-        self.store.restore(out_path.as_path()).await?;
+        let decrypted_bytes = util::crypto::decrypt_ecies(&buffer, &keys.get_private_key_string())?;
+        match serde_json::from_slice::<BackupData>(&decrypted_bytes) {
+            Ok(data) => self.import_data(data, &keys).await?,
+            Err(_) => {
+                info!("Backup is not in the logical backup format, importing SurrealDB export");
+                self.import_surreal_export(file_path, &decrypted_bytes)
+                    .await?
+            }
+        }
+        // the search index is rebuilt from the restored data with the next search
+        self.search_index_store.clear().await?;
         self.reboot_sender
             .send(true)
             .expect("Can initiate a reboot");
@@ -95,42 +477,487 @@ impl BackupServiceApi for BackupService {
     }
 }
 
+/// Checks the whole backup, before anything is dropped: the version, the keys, the chains, the
+/// attached files and that the template issues and webhook deliveries belong to backed up
+/// templates and webhooks
+fn validate_backup(data: &BackupData) -> Result<()> {
+    if data.version > BACKUP_FORMAT_VERSION {
+        return Err(Error::Validation(format!(
+            "Backup version {} is not supported",
+            data.version
+        )));
+    }
+    BcrKeys::from_private_key(&data.private_key)?;
+    let invalid = |what: String, e: &dyn std::fmt::Display| {
+        Error::Validation(format!("Invalid {what} in backup: {e}"))
+    };
+
+    if let Some(ref identity_backup) = data.identity {
+        IdentityBlockchain::new_from_blocks(identity_backup.chain.clone())
+            .map_err(|e| invalid("identity chain".to_string(), &e))?;
+    }
+    for company_backup in data.companies.iter() {
+        let id = &company_backup.company.id;
+        CompanyBlockchain::new_from_blocks(company_backup.chain.clone())
+            .map_err(|e| invalid(format!("chain of company {id}"), &e))?;
+    }
+    for bill_backup in data.bills.iter() {
+        let id = &bill_backup.id;
+        BillBlockchain::new_from_blocks(bill_backup.chain.clone())
+            .and_then(|chain| chain.get_first_version_bill(&bill_backup.keys))
+            .map_err(|e| invalid(format!("chain of bill {id}"), &e))?;
+    }
+    for file in data.attached_files.iter() {
+        util::base58_decode(&file.data)
+            .map_err(|e| invalid(format!("file {} of {}", file.file_name, file.id), &e))?;
+    }
+
+    let template_ids: Vec<&str> = data
+        .bill_templates
+        .iter()
+        .map(|t| t.template.id.as_str())
+        .collect();
+    for template_backup in data.bill_templates.iter() {
+        if let Some(issue) = template_backup
+            .issues
+            .iter()
+            .find(|issue| !template_ids.contains(&issue.template_id.as_str()))
+        {
+            return Err(Error::Validation(format!(
+                "Issue of bill {} in backup refers to unknown template {}",
+                issue.bill_id, issue.template_id
+            )));
+        }
+    }
+    if let Some(delivery) = data
+        .webhook_deliveries
+        .iter()
+        .find(|delivery| !data.webhooks.iter().any(|w| w.id == delivery.webhook_id))
+    {
+        return Err(Error::Validation(format!(
+            "Webhook delivery {} in backup refers to unknown webhook {}",
+            delivery.id, delivery.webhook_id
+        )));
+    }
+    Ok(())
+}
+
+/// Adds the given optional files of the entity with the given id to the list of files
+fn add_files<'a>(
+    files: &mut Vec<(String, File)>,
+    id: &str,
+    entity_files: impl IntoIterator<Item = &'a Option<File>>,
+) {
+    for file in entity_files.into_iter().flatten() {
+        files.push((id.to_owned(), file.clone()));
+    }
+}
+
+/// The logical backup containing all data of the local node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BackupData {
+    pub version: u32,
+    pub private_key: String,
+    pub seed_phrase: String,
+    pub identity: Option<IdentityBackup>,
+    pub contacts: Vec<Contact>,
+    pub companies: Vec<CompanyBackup>,
+    pub bills: Vec<BillBackup>,
+    pub notifications: Vec<Notification>,
+    pub nostr_event_offsets: Vec<NostrEventOffsetBackup>,
+    pub attached_files: Vec<AttachedFileBackup>,
+    // added with version 2, backups of version 1 restore without them
+    #[serde(default)]
+    pub bill_templates: Vec<BillTemplateBackup>,
+    #[serde(default)]
+    pub bill_batch_issues: Vec<BillBatchIssue>,
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    #[serde(default)]
+    pub webhook_deliveries: Vec<WebhookDelivery>,
+    #[serde(default)]
+    pub api_tokens: Vec<ApiTokenBackup>,
+    #[serde(default)]
+    pub nostr_outbox: Vec<NostrOutboxEntryBackup>,
+    #[serde(default)]
+    pub quarantined_events: Vec<QuarantinedEventBackup>,
+    #[serde(default)]
+    pub pending_events: Vec<PendingEventBackup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IdentityBackup {
+    pub identity: Identity,
+    pub chain: Vec<IdentityBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CompanyBackup {
+    pub company: Company,
+    pub keys: CompanyKeys,
+    pub chain: Vec<CompanyBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BillBackup {
+    pub id: String,
+    pub keys: BillKeys,
+    pub chain: Vec<BillBlock>,
+    pub paid_payment_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NostrEventOffsetBackup {
+    pub event_id: String,
+    pub time: u64,
+    pub success: bool,
+}
+
+impl From<NostrEventOffset> for NostrEventOffsetBackup {
+    fn from(value: NostrEventOffset) -> Self {
+        Self {
+            event_id: value.event_id,
+            time: value.time,
+            success: value.success,
+        }
+    }
+}
+
+impl From<NostrEventOffsetBackup> for NostrEventOffset {
+    fn from(value: NostrEventOffsetBackup) -> Self {
+        Self {
+            event_id: value.event_id,
+            time: value.time,
+            success: value.success,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BillTemplateBackup {
+    pub template: BillTemplate,
+    pub issues: Vec<BillTemplateIssue>,
+}
+
+/// An API token with the hash of its secret, the secret itself is never stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ApiTokenBackup {
+    pub token: ApiToken,
+    pub token_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NostrOutboxEntryBackup {
+    pub id: String,
+    pub recipient: String,
+    pub recipient_relays: Vec<String>,
+    pub event_type: EventType,
+    pub payload: String,
+    pub status: NostrOutboxStatus,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub sent_at: Option<u64>,
+}
+
+impl From<NostrOutboxEntry> for NostrOutboxEntryBackup {
+    fn from(value: NostrOutboxEntry) -> Self {
+        Self {
+            id: value.id,
+            recipient: value.recipient,
+            recipient_relays: value.recipient_relays,
+            event_type: value.event_type,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            sent_at: value.sent_at,
+        }
+    }
+}
+
+impl From<NostrOutboxEntryBackup> for NostrOutboxEntry {
+    fn from(value: NostrOutboxEntryBackup) -> Self {
+        Self {
+            id: value.id,
+            recipient: value.recipient,
+            recipient_relays: value.recipient_relays,
+            event_type: value.event_type,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            sent_at: value.sent_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QuarantinedEventBackup {
+    pub id: String,
+    pub sender: String,
+    pub event_type: EventType,
+    pub payload: String,
+    pub reason: String,
+    pub received_at: u64,
+}
+
+impl From<QuarantinedEvent> for QuarantinedEventBackup {
+    fn from(value: QuarantinedEvent) -> Self {
+        Self {
+            id: value.id,
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            reason: value.reason,
+            received_at: value.received_at,
+        }
+    }
+}
+
+impl From<QuarantinedEventBackup> for QuarantinedEvent {
+    fn from(value: QuarantinedEventBackup) -> Self {
+        Self {
+            id: value.id,
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            reason: value.reason,
+            received_at: value.received_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingEventBackup {
+    pub id: String,
+    pub bill_id: String,
+    pub node_id: String,
+    pub sender: String,
+    pub event_type: EventType,
+    pub payload: String,
+    pub received_at: u64,
+}
+
+impl From<PendingEvent> for PendingEventBackup {
+    fn from(value: PendingEvent) -> Self {
+        Self {
+            id: value.id,
+            bill_id: value.bill_id,
+            node_id: value.node_id,
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            received_at: value.received_at,
+        }
+    }
+}
+
+impl From<PendingEventBackup> for PendingEvent {
+    fn from(value: PendingEventBackup) -> Self {
+        Self {
+            id: value.id,
+            bill_id: value.bill_id,
+            node_id: value.node_id,
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            received_at: value.received_at,
+        }
+    }
+}
+
+/// An encrypted attached file, stored base58 encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AttachedFileBackup {
+    pub id: String,
+    pub file_name: String,
+    pub data: String,
+}
+
 #[cfg(test)]
 mod tests {
-    use std::env;
+    use std::{collections::HashMap, env, path::PathBuf};
 
     use mockall::predicate::eq;
-    use util::BcrKeys;
 
-    use crate::tests::tests::{MockBackupStoreApiMock, MockIdentityStoreApiMock};
+    use crate::service::notification_service::test_utils::get_mock_db_context;
+    use crate::tests::tests::{
+        MockApiTokenStoreApiMock, MockBackupStoreApiMock, MockBillBatchStoreApiMock,
+        MockBillStoreApiMock, MockBillTemplateStoreApiMock, MockCompanyStoreApiMock,
+        MockContactStoreApiMock, MockIdentityStoreApiMock, MockNostrEventOffsetStoreApiMock,
+        MockNostrOutboxStoreApiMock, MockNostrPendingEventStoreApiMock,
+        MockNostrQuarantineStoreApiMock, MockNotificationStoreApiMock, MockSearchIndexStoreApiMock,
+        MockWebhookStoreApiMock,
+    };
 
     use super::*;
+    use crate::service::contact_service::tests::get_baseline_contact;
 
-    #[tokio::test]
-    async fn test_backup_with_embedded_db() {
-        let mut store = MockBackupStoreApiMock::new();
-        let mut identity_store = MockIdentityStoreApiMock::new();
-        let surreal_db_config = SurrealDbConfig {
-            connection_string: "rocksdb://test".to_string(),
+    fn get_surreal_db_config(connection_string: &str) -> SurrealDbConfig {
+        SurrealDbConfig {
+            connection_string: connection_string.to_string(),
             database: "test".to_string(),
             namespace: "test".to_string(),
-        };
+        }
+    }
 
+    /// An identity store with only keys and no identity
+    fn get_identity_store(keys: BcrKeys) -> MockIdentityStoreApiMock {
+        let mut identity_store = MockIdentityStoreApiMock::new();
         identity_store
             .expect_get_key_pair()
-            .returning(|| Ok(BcrKeys::new()))
-            .once();
+            .returning(move || Ok(keys.clone()));
+        identity_store.expect_exists().returning(|| false);
+        identity_store
+            .expect_get_seedphrase()
+            .returning(|| Ok("seed".to_string()));
+        identity_store
+    }
 
-        store
-            .expect_backup()
-            .returning(|| Ok(vec![0, 1, 0, 1, 0, 0, 1, 0]))
-            .once();
+    /// Returns a context with an identity that has only keys and no other data
+    fn get_empty_context(keys: BcrKeys) -> DbContext {
+        let identity_store = get_identity_store(keys);
+        let mut contact_store = MockContactStoreApiMock::new();
+        contact_store
+            .expect_get_map()
+            .returning(|| Ok(HashMap::new()));
+        let mut company_store = MockCompanyStoreApiMock::new();
+        company_store
+            .expect_get_all()
+            .returning(|| Ok(HashMap::new()));
+        let mut bill_store = MockBillStoreApiMock::new();
+        bill_store.expect_get_ids().returning(|| Ok(vec![]));
+        let mut notification_store = MockNotificationStoreApiMock::new();
+        notification_store.expect_list().returning(|_| Ok(vec![]));
+        let mut nostr_event_offset_store = MockNostrEventOffsetStoreApiMock::new();
+        nostr_event_offset_store
+            .expect_get_all()
+            .returning(|| Ok(vec![]));
+        let mut search_index_store = MockSearchIndexStoreApiMock::new();
+        search_index_store.expect_clear().returning(|| Ok(()));
+        let mut bill_template_store = MockBillTemplateStoreApiMock::new();
+        bill_template_store.expect_list().returning(|| Ok(vec![]));
+        let mut bill_batch_store = MockBillBatchStoreApiMock::new();
+        bill_batch_store.expect_get_all().returning(|| Ok(vec![]));
+        let mut webhook_store = MockWebhookStoreApiMock::new();
+        webhook_store.expect_list().returning(|| Ok(vec![]));
+        webhook_store
+            .expect_get_all_deliveries()
+            .returning(|| Ok(vec![]));
+        let mut api_token_store = MockApiTokenStoreApiMock::new();
+        api_token_store
+            .expect_list_with_hashes()
+            .returning(|| Ok(vec![]));
+        let mut nostr_outbox_store = MockNostrOutboxStoreApiMock::new();
+        nostr_outbox_store.expect_get_all().returning(|| Ok(vec![]));
+        let mut nostr_quarantine_store = MockNostrQuarantineStoreApiMock::new();
+        nostr_quarantine_store
+            .expect_list()
+            .returning(|| Ok(vec![]));
+        let mut nostr_pending_event_store = MockNostrPendingEventStoreApiMock::new();
+        nostr_pending_event_store
+            .expect_list()
+            .returning(|| Ok(vec![]));
+
+        let mut ctx = get_mock_db_context();
+        ctx.identity_store = Arc::new(identity_store);
+        ctx.contact_store = Arc::new(contact_store);
+        ctx.company_store = Arc::new(company_store);
+        ctx.bill_store = Arc::new(bill_store);
+        ctx.notification_store = Arc::new(notification_store);
+        ctx.nostr_event_offset_store = Arc::new(nostr_event_offset_store);
+        ctx.search_index_store = Arc::new(search_index_store);
+        ctx.bill_template_store = Arc::new(bill_template_store);
+        ctx.bill_batch_store = Arc::new(bill_batch_store);
+        ctx.webhook_store = Arc::new(webhook_store);
+        ctx.api_token_store = Arc::new(api_token_store);
+        ctx.nostr_outbox_store = Arc::new(nostr_outbox_store);
+        ctx.nostr_quarantine_store = Arc::new(nostr_quarantine_store);
+        ctx.nostr_pending_event_store = Arc::new(nostr_pending_event_store);
+        ctx
+    }
+
+    fn get_backup_data(keys: &BcrKeys) -> BackupData {
+        BackupData {
+            version: BACKUP_FORMAT_VERSION,
+            private_key: keys.get_private_key_string(),
+            seed_phrase: "seed".to_string(),
+            identity: None,
+            contacts: vec![],
+            companies: vec![],
+            bills: vec![],
+            notifications: vec![],
+            nostr_event_offsets: vec![],
+            attached_files: vec![],
+            bill_templates: vec![],
+            bill_batch_issues: vec![],
+            webhooks: vec![],
+            webhook_deliveries: vec![],
+            api_tokens: vec![],
+            nostr_outbox: vec![],
+            quarantined_events: vec![],
+            pending_events: vec![],
+        }
+    }
+
+    fn get_webhook(id: &str) -> Webhook {
+        Webhook {
+            id: id.to_string(),
+            url: "https://example.com/hook".to_string(),
+            secret: "secret".to_string(),
+            event_types: vec![EventType::BillSigned],
+            created_at: 1000,
+        }
+    }
 
+    /// Writes the encrypted backup to a temp file and returns its path
+    async fn write_backup_file(data: &BackupData, keys: &BcrKeys, name: &str) -> PathBuf {
+        let encrypted_bytes =
+            util::crypto::encrypt_ecies(&serde_json::to_vec(data).unwrap(), &keys.get_public_key())
+                .unwrap();
+        let file_path = env::temp_dir().join(name);
+        let mut test_file = FsFile::create(file_path.as_path()).await.unwrap();
+        test_file.write_all(&encrypted_bytes).await.unwrap();
+        file_path
+    }
+
+    #[tokio::test]
+    async fn test_backup_with_embedded_db() {
+        let keys = BcrKeys::new();
+        let ctx = get_empty_context(keys.clone());
+        let (tx, _) = watch::channel(false);
+        let service = BackupService::new(ctx, get_surreal_db_config("rocksdb://test"), tx);
+
+        let result = service.backup().await;
+        assert!(result.is_ok());
+        let decrypted =
+            util::crypto::decrypt_ecies(&result.unwrap(), &keys.get_private_key_string()).unwrap();
+        let data: BackupData = serde_json::from_slice(&decrypted).unwrap();
+        assert_eq!(data.version, BACKUP_FORMAT_VERSION);
+        assert_eq!(data.private_key, keys.get_private_key_string());
+        assert_eq!(data.seed_phrase, "seed".to_string());
+        assert!(data.identity.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_backup_with_external_socket_db() {
+        let ctx = get_empty_context(BcrKeys::new());
+        let mut store = MockBackupStoreApiMock::new();
+        // the database export is not used for backups anymore
+        store.expect_backup().never();
         let (tx, _) = watch::channel(false);
         let service = BackupService::new(
-            Arc::new(store),
-            Arc::new(identity_store),
-            surreal_db_config,
+            DbContext {
+                backup_store: Arc::new(store),
+                ..ctx
+            },
+            get_surreal_db_config("ws://localhost:8000"),
             tx,
         );
 
@@ -139,39 +966,239 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_backup_with_external_socket_db_should_fail() {
+    async fn test_restore_logical_backup_with_external_socket_db() {
+        let keys = BcrKeys::new();
+        let mut data = get_backup_data(&keys);
+        data.nostr_event_offsets = vec![NostrEventOffsetBackup {
+            event_id: "event".to_string(),
+            time: 1000,
+            success: true,
+        }];
+        data.webhooks = vec![get_webhook("hook")];
+        data.bill_batch_issues = vec![BillBatchIssue {
+            batch_id: "batch".to_string(),
+            row_hash: "row".to_string(),
+            bill_id: "1234".to_string(),
+            issued_at: 1000,
+        }];
+        data.api_tokens = vec![ApiTokenBackup {
+            token: ApiToken {
+                id: "token".to_string(),
+                name: "token".to_string(),
+                scopes: vec![],
+                created_at: 1000,
+                last_used_at: None,
+            },
+            token_hash: "hash".to_string(),
+        }];
+        data.pending_events = vec![PendingEventBackup {
+            id: "pending".to_string(),
+            bill_id: "1234".to_string(),
+            node_id: "node_id".to_string(),
+            sender: "sender".to_string(),
+            event_type: EventType::BillSigned,
+            payload: "{}".to_string(),
+            received_at: 1000,
+        }];
+        let file_path = write_backup_file(&data, &keys, "test_logical_backup.ecies").await;
+
         let mut store = MockBackupStoreApiMock::new();
-        let mut identity_store = MockIdentityStoreApiMock::new();
-        let surreal_db_config = SurrealDbConfig {
-            connection_string: "ws://localhost:8000".to_string(),
-            database: "test".to_string(),
-            namespace: "test".to_string(),
-        };
+        store
+            .expect_drop_db()
+            .with(eq("test"))
+            .returning(|_| Ok(()))
+            .once();
+        store.expect_restore().never();
 
-        identity_store.expect_get_key_pair().never();
-        store.expect_backup().never();
+        let mut identity_store = get_identity_store(keys.clone());
+        identity_store
+            .expect_save_key_pair()
+            .withf(|_, seed| seed == "seed")
+            .returning(|_, _| Ok(()))
+            .once();
+
+        let mut nostr_event_offset_store = MockNostrEventOffsetStoreApiMock::new();
+        nostr_event_offset_store
+            .expect_get_all()
+            .returning(|| Ok(vec![]));
+        nostr_event_offset_store
+            .expect_add_event()
+            .withf(|e| e.event_id == "event" && e.time == 1000)
+            .returning(|_| Ok(()))
+            .once();
+
+        let mut webhook_store = MockWebhookStoreApiMock::new();
+        webhook_store.expect_list().returning(|| Ok(vec![]));
+        webhook_store
+            .expect_get_all_deliveries()
+            .returning(|| Ok(vec![]));
+        webhook_store
+            .expect_insert()
+            .withf(|w| w.id == "hook")
+            .returning(|_| Ok(()))
+            .once();
+        let mut bill_batch_store = MockBillBatchStoreApiMock::new();
+        bill_batch_store.expect_get_all().returning(|| Ok(vec![]));
+        bill_batch_store
+            .expect_add_issue()
+            .withf(|issue| issue.batch_id == "batch" && issue.bill_id == "1234")
+            .returning(|_| Ok(()))
+            .once();
+        let mut api_token_store = MockApiTokenStoreApiMock::new();
+        api_token_store
+            .expect_list_with_hashes()
+            .returning(|| Ok(vec![]));
+        api_token_store
+            .expect_insert()
+            .withf(|token, hash| token.id == "token" && hash == "hash")
+            .returning(|_, _| Ok(()))
+            .once();
+        let mut nostr_pending_event_store = MockNostrPendingEventStoreApiMock::new();
+        nostr_pending_event_store
+            .expect_list()
+            .returning(|| Ok(vec![]));
+        nostr_pending_event_store
+            .expect_add()
+            .withf(|e| e.id == "pending")
+            .returning(|_| Ok(()))
+            .once();
+
+        let mut search_index_store = MockSearchIndexStoreApiMock::new();
+        search_index_store
+            .expect_clear()
+            .returning(|| Ok(()))
+            .once();
 
+        let (tx, mut rx) = watch::channel(false);
+        let service = BackupService::new(
+            DbContext {
+                backup_store: Arc::new(store),
+                identity_store: Arc::new(identity_store),
+                search_index_store: Arc::new(search_index_store),
+                nostr_event_offset_store: Arc::new(nostr_event_offset_store),
+                webhook_store: Arc::new(webhook_store),
+                bill_batch_store: Arc::new(bill_batch_store),
+                api_token_store: Arc::new(api_token_store),
+                nostr_pending_event_store: Arc::new(nostr_pending_event_store),
+                ..get_empty_context(keys)
+            },
+            get_surreal_db_config("ws://localhost:8000"),
+            tx,
+        );
+
+        let result = service.restore(&file_path).await;
+        assert!(result.is_ok());
+        let should_reboot = *rx.borrow_and_update();
+        assert!(should_reboot);
+    }
+
+    #[tokio::test]
+    async fn test_restore_validates_backup_before_dropping_db() {
+        let keys = BcrKeys::new();
+        let mut data = get_backup_data(&keys);
+        data.webhook_deliveries = vec![WebhookDelivery::new(
+            "delivery",
+            "unknown hook",
+            EventType::BillSigned,
+            "{}",
+            1000,
+        )];
+        let file_path = write_backup_file(&data, &keys, "test_invalid_backup.ecies").await;
+
+        let mut store = MockBackupStoreApiMock::new();
+        store.expect_drop_db().never();
         let (tx, _) = watch::channel(false);
         let service = BackupService::new(
-            Arc::new(store),
-            Arc::new(identity_store),
-            surreal_db_config,
+            DbContext {
+                backup_store: Arc::new(store),
+                ..get_empty_context(keys.clone())
+            },
+            get_surreal_db_config("ws://localhost:8000"),
             tx,
         );
+        assert!(matches!(
+            service.restore(&file_path).await,
+            Err(Error::Validation(_))
+        ));
 
-        let result = service.backup().await;
-        assert!(result.is_err());
+        let mut data = get_backup_data(&keys);
+        data.attached_files = vec![AttachedFileBackup {
+            id: "node_id".to_string(),
+            file_name: "file.pdf".to_string(),
+            data: "not base58!".to_string(),
+        }];
+        let file_path = write_backup_file(&data, &keys, "test_invalid_file_backup.ecies").await;
+        assert!(matches!(
+            service.restore(&file_path).await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restore_rolls_back_failed_import() {
+        let keys = BcrKeys::new();
+        let mut data = get_backup_data(&keys);
+        data.contacts = vec![get_baseline_contact()];
+        let file_path = write_backup_file(&data, &keys, "test_rollback_backup.ecies").await;
+
+        // the db is dropped for the import and again for the rollback
+        let mut store = MockBackupStoreApiMock::new();
+        store.expect_drop_db().returning(|_| Ok(())).times(2);
+
+        let mut identity_store = get_identity_store(keys.clone());
+        identity_store
+            .expect_save_key_pair()
+            .returning(|_, _| Ok(()))
+            .times(2);
+        let mut contact_store = MockContactStoreApiMock::new();
+        contact_store
+            .expect_get_map()
+            .returning(|| Ok(HashMap::new()));
+        contact_store
+            .expect_insert()
+            .returning(|_, _| {
+                Err(crate::persistence::Error::NoSuchEntity(
+                    "contact".to_string(),
+                    "contact".to_string(),
+                ))
+            })
+            .once();
+
+        // the existing webhook is written back
+        let mut webhook_store = MockWebhookStoreApiMock::new();
+        webhook_store
+            .expect_list()
+            .returning(|| Ok(vec![get_webhook("existing")]));
+        webhook_store
+            .expect_get_all_deliveries()
+            .returning(|| Ok(vec![]));
+        webhook_store
+            .expect_insert()
+            .withf(|w| w.id == "existing")
+            .returning(|_| Ok(()))
+            .once();
+
+        let (tx, rx) = watch::channel(false);
+        let service = BackupService::new(
+            DbContext {
+                backup_store: Arc::new(store),
+                identity_store: Arc::new(identity_store),
+                contact_store: Arc::new(contact_store),
+                webhook_store: Arc::new(webhook_store),
+                ..get_empty_context(keys)
+            },
+            get_surreal_db_config("ws://localhost:8000"),
+            tx,
+        );
+
+        assert!(service.restore(&file_path).await.is_err());
+        assert!(!*rx.borrow());
     }
 
     #[tokio::test]
     async fn test_restore_with_embedded_db() {
         let mut store = MockBackupStoreApiMock::new();
         let mut identity_store = MockIdentityStoreApiMock::new();
-        let surreal_db_config = SurrealDbConfig {
-            connection_string: "rocksdb://test".to_string(),
-            database: "test".to_string(),
-            namespace: "test".to_string(),
-        };
 
         let keys = BcrKeys::new();
 
@@ -192,7 +1219,7 @@ DEFINE TABLE bill_chain TYPE ANY SCHEMALESS PERMISSIONS NONE;";
 
         let temp_dir = env::temp_dir();
         let file_path = temp_dir.join("test.surql");
-        let mut test_file = File::create(file_path.as_path()).await.unwrap();
+        let mut test_file = FsFile::create(file_path.as_path()).await.unwrap();
         test_file.write_all(&encrypted_bytes).await.unwrap();
 
         identity_store
@@ -208,11 +1235,21 @@ DEFINE TABLE bill_chain TYPE ANY SCHEMALESS PERMISSIONS NONE;";
 
         store.expect_restore().returning(|_| Ok(())).once();
 
+        let mut search_index_store = MockSearchIndexStoreApiMock::new();
+        search_index_store
+            .expect_clear()
+            .returning(|| Ok(()))
+            .once();
+
         let (tx, mut rx) = watch::channel(false);
         let service = BackupService::new(
-            Arc::new(store),
-            Arc::new(identity_store),
-            surreal_db_config,
+            DbContext {
+                backup_store: Arc::new(store),
+                identity_store: Arc::new(identity_store),
+                search_index_store: Arc::new(search_index_store),
+                ..get_mock_db_context()
+            },
+            get_surreal_db_config("rocksdb://test"),
             tx,
         );
 
@@ -221,4 +1258,38 @@ DEFINE TABLE bill_chain TYPE ANY SCHEMALESS PERMISSIONS NONE;";
         let should_reboot = *rx.borrow_and_update();
         assert!(should_reboot);
     }
+
+    #[tokio::test]
+    async fn test_restore_surreal_export_with_external_socket_db_should_fail() {
+        let mut store = MockBackupStoreApiMock::new();
+        let mut identity_store = MockIdentityStoreApiMock::new();
+        let keys = BcrKeys::new();
+
+        let encrypted_bytes =
+            util::crypto::encrypt_ecies(b"OPTION IMPORT;", &keys.get_public_key()).unwrap();
+        let file_path = env::temp_dir().join("test_socket.surql");
+        let mut test_file = FsFile::create(file_path.as_path()).await.unwrap();
+        test_file.write_all(&encrypted_bytes).await.unwrap();
+
+        identity_store
+            .expect_get_key_pair()
+            .returning(move || Ok(keys.clone()))
+            .once();
+        store.expect_drop_db().never();
+        store.expect_restore().never();
+
+        let (tx, _) = watch::channel(false);
+        let service = BackupService::new(
+            DbContext {
+                backup_store: Arc::new(store),
+                identity_store: Arc::new(identity_store),
+                ..get_mock_db_context()
+            },
+            get_surreal_db_config("ws://localhost:8000"),
+            tx,
+        );
+
+        let result = service.restore(&file_path).await;
+        assert!(result.is_err());
+    }
 }
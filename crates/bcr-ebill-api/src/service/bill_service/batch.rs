@@ -0,0 +1,385 @@
+use super::error::Error;
+use super::service::BillService;
+use super::validation::validate_bill_to_issue;
+use super::{BillServiceApi, Result};
+use crate::data::{
+    bill::{BillBatchIssue, BillBatchResult, BillBatchRowResult},
+    contact::IdentityPublicData,
+};
+use crate::util::{self, BcrKeys};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A bill to issue as part of a batch, with the same fields as a single bill to issue, except
+/// for files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillBatchRow {
+    /// 0 for a promissory note, where the drawer is the drawee, 1 for a self-drafted bill,
+    /// where the drawer is the payee, and 2 for a bill between three parties
+    #[serde(rename = "type")]
+    pub t: u64,
+    pub country_of_issuing: String,
+    pub city_of_issuing: String,
+    pub issue_date: String,
+    pub maturity_date: String,
+    /// Ignored for promissory notes
+    #[serde(default)]
+    pub drawee: String,
+    /// Ignored for self-drafted bills
+    #[serde(default)]
+    pub payee: String,
+    pub sum: String,
+    pub currency: String,
+    pub country_of_payment: String,
+    pub city_of_payment: String,
+    pub language: String,
+}
+
+/// A row, which passed validation, together with its resolved counterparties
+struct ValidBatchRow {
+    row: BillBatchRow,
+    row_hash: String,
+    drawee: IdentityPublicData,
+    payee: IdentityPublicData,
+    sum: u64,
+}
+
+/// Reads the rows of a batch, which is either a JSON list, or a CSV file with a header row.
+/// Rows, which can't be read, are returned with the reason
+pub(super) fn read_batch(data: &[u8]) -> Result<Vec<std::result::Result<BillBatchRow, String>>> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| Error::BatchFile(String::from("The file has to be UTF-8 encoded")))?;
+    // spreadsheet applications often start their CSV exports with a byte order mark
+    let text = text.trim_start_matches('\u{feff}');
+    let rows: Vec<std::result::Result<BillBatchRow, String>> = if text.trim_start().starts_with('[')
+    {
+        let values: Vec<serde_json::Value> = serde_json::from_str(text)
+            .map_err(|e| Error::BatchFile(format!("Invalid JSON list: {e}")))?;
+        values
+            .into_iter()
+            .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+            .collect()
+    } else {
+        csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes())
+            .deserialize()
+            .map(|row| row.map_err(|e| e.to_string()))
+            .collect()
+    };
+    if rows.is_empty() {
+        return Err(Error::BatchFile(String::from(
+            "The file doesn't contain any bills",
+        )));
+    }
+    Ok(rows)
+}
+
+/// Returns the hash of the fields of the row, which identifies it within its batch. Identical
+/// rows are told apart by the number of identical rows before them, so a batch can contain the
+/// same bill more than once
+pub(super) fn get_row_hash(
+    row: &BillBatchRow,
+    seen_rows: &mut HashMap<String, usize>,
+) -> Result<String> {
+    let fields = serde_json::to_string(row)
+        .map_err(|e| Error::BatchFile(format!("The row can't be hashed: {e}")))?;
+    let identical_rows = seen_rows.entry(fields.clone()).or_default();
+    let row_hash = util::sha256_hash(format!("{fields}#{identical_rows}").as_bytes());
+    *identical_rows += 1;
+    Ok(row_hash)
+}
+
+impl BillService {
+    pub(super) async fn issue_batch(
+        &self,
+        file_upload_id: &str,
+        batch_id: Option<&str>,
+        dry_run: bool,
+        all_or_nothing: bool,
+        drawer_public_data: &IdentityPublicData,
+        drawer_keys: &BcrKeys,
+        timestamp: u64,
+    ) -> Result<BillBatchResult> {
+        let (_, data) = self
+            .file_upload_store
+            .read_temp_upload_files(file_upload_id)
+            .await
+            .map_err(|_| Error::NoFileForFileUploadId)?
+            .into_iter()
+            .next()
+            .ok_or(Error::NoFileForFileUploadId)?;
+        // without a batch id, only issuing the same upload again skips the rows issued before
+        let batch_id = batch_id.unwrap_or(file_upload_id);
+        let issued_rows: HashMap<String, String> = self
+            .bill_batch_store
+            .list_issues(batch_id)
+            .await?
+            .into_iter()
+            .map(|issue| (issue.row_hash, issue.bill_id))
+            .collect();
+
+        let mut seen_rows = HashMap::new();
+        let mut rows = vec![];
+        for (idx, row) in read_batch(&data)?.into_iter().enumerate() {
+            let mut result = BillBatchRowResult {
+                row: idx + 1,
+                bill_id: None,
+                errors: vec![],
+                warnings: vec![],
+            };
+            let valid_row = match row {
+                Ok(row) => {
+                    let row_hash = get_row_hash(&row, &mut seen_rows)?;
+                    match issued_rows.get(&row_hash) {
+                        // rows issued by an earlier attempt are neither validated, nor issued
+                        // again
+                        Some(bill_id) => {
+                            result.bill_id = Some(bill_id.clone());
+                            None
+                        }
+                        None => {
+                            self.validate_batch_row(row, row_hash, drawer_public_data, &mut result)
+                                .await?
+                        }
+                    }
+                }
+                Err(e) => {
+                    result.errors.push(e);
+                    None
+                }
+            };
+            rows.push((result, valid_row));
+        }
+
+        let has_invalid_rows = rows.iter().any(|(result, _)| !result.errors.is_empty());
+        if dry_run || (all_or_nothing && has_invalid_rows) {
+            return Ok(BillBatchResult {
+                dry_run,
+                issued: false,
+                rows: rows.into_iter().map(|(result, _)| result).collect(),
+            });
+        }
+
+        let mut failed = false;
+        let mut results = vec![];
+        for (mut result, valid_row) in rows {
+            let Some(valid_row) = valid_row else {
+                results.push(result);
+                continue;
+            };
+            // the bills issued before can't be taken back, but the remaining ones aren't issued
+            if failed && all_or_nothing {
+                result.errors.push(String::from(
+                    "Not issued, since issuing an earlier row failed",
+                ));
+                results.push(result);
+                continue;
+            }
+            let row_hash = valid_row.row_hash.clone();
+            match self
+                .issue_batch_row(valid_row, drawer_public_data, drawer_keys, timestamp)
+                .await
+            {
+                Ok(bill_id) => {
+                    result.bill_id = Some(bill_id.clone());
+                    let issue = BillBatchIssue {
+                        batch_id: batch_id.to_owned(),
+                        row_hash,
+                        bill_id,
+                        issued_at: timestamp,
+                    };
+                    // the row stays failed, since issuing the batch again would issue it twice
+                    if let Err(e) = self.bill_batch_store.add_issue(&issue).await {
+                        error!(
+                            "Error storing the bill issued for row {} of batch {batch_id}: {e}",
+                            result.row
+                        );
+                        result.errors.push(e.to_string());
+                        failed = true;
+                    }
+                }
+                Err(e) => {
+                    error!("Error issuing row {} of batch {batch_id}: {e}", result.row);
+                    result.errors.push(e.to_string());
+                    failed = true;
+                }
+            }
+            results.push(result);
+        }
+
+        let issued = results
+            .iter()
+            .all(|result| result.bill_id.is_some() && result.errors.is_empty());
+        if issued {
+            info!("Issued {} bills from batch {batch_id}", results.len());
+            // clean up the temporary file upload, logging any errors
+            if let Err(e) = self
+                .file_upload_store
+                .remove_temp_upload_folder(file_upload_id)
+                .await
+            {
+                error!("Error while cleaning up temporary file uploads for {file_upload_id}: {e}");
+            }
+        }
+
+        Ok(BillBatchResult {
+            dry_run,
+            issued,
+            rows: results,
+        })
+    }
+
+    async fn issue_batch_row(
+        &self,
+        valid_row: ValidBatchRow,
+        drawer_public_data: &IdentityPublicData,
+        drawer_keys: &BcrKeys,
+        timestamp: u64,
+    ) -> Result<String> {
+        let row = valid_row.row;
+        let bill = self
+            .issue_bill(
+                row.country_of_issuing,
+                row.city_of_issuing,
+                row.issue_date,
+                row.maturity_date,
+                valid_row.drawee,
+                valid_row.payee,
+                valid_row.sum,
+                row.currency,
+                row.country_of_payment,
+                row.city_of_payment,
+                row.language,
+                None,
+                drawer_public_data.clone(),
+                drawer_keys.clone(),
+                timestamp,
+            )
+            .await?;
+        Ok(bill.id)
+    }
+
+    /// Validates the row with the same rules as a single bill to issue and collects all
+    /// reasons, why it can't be issued, in the given result
+    async fn validate_batch_row(
+        &self,
+        row: BillBatchRow,
+        row_hash: String,
+        drawer_public_data: &IdentityPublicData,
+        result: &mut BillBatchRowResult,
+    ) -> Result<Option<ValidBatchRow>> {
+        let bill_to_issue = match validate_bill_to_issue(
+            row.t,
+            &row.sum,
+            &row.issue_date,
+            &row.maturity_date,
+            &row.drawee,
+            &row.payee,
+            &drawer_public_data.node_id,
+        ) {
+            Ok(bill_to_issue) => bill_to_issue,
+            Err(errors) => {
+                result.errors.extend(errors);
+                return Ok(None);
+            }
+        };
+
+        let drawee = match bill_to_issue.drawee {
+            None => Some(drawer_public_data.clone()),
+            Some(ref node_id) => {
+                self.get_batch_counterparty(node_id, "drawee", result)
+                    .await?
+            }
+        };
+        let payee = match bill_to_issue.payee {
+            None => Some(drawer_public_data.clone()),
+            Some(ref node_id) => {
+                self.get_batch_counterparty(node_id, "payee", result)
+                    .await?
+            }
+        };
+        let (Some(drawee), Some(payee)) = (drawee, payee) else {
+            return Ok(None);
+        };
+        match self
+            .check_counterparty_verification(&[drawee.node_id.clone(), payee.node_id.clone()])
+            .await
+        {
+            Ok(warnings) => result.warnings = warnings,
+            Err(e @ Error::CounterpartyNotVerified(_)) => {
+                result.errors.push(e.to_string());
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(Some(ValidBatchRow {
+            row,
+            row_hash,
+            drawee,
+            payee,
+            sum: bill_to_issue.sum,
+        }))
+    }
+
+    async fn get_batch_counterparty(
+        &self,
+        node_id: &str,
+        role: &str,
+        result: &mut BillBatchRowResult,
+    ) -> Result<Option<IdentityPublicData>> {
+        match self.contact_store.get(node_id).await? {
+            Some(contact) => Ok(Some(contact.into())),
+            None => {
+                result
+                    .errors
+                    .push(format!("Can not get {role} identity from contacts."));
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_batch_csv() {
+        let csv = "\u{feff}type,country_of_issuing,city_of_issuing,issue_date,maturity_date,drawee,payee,sum,currency,country_of_payment,city_of_payment,language\n\
+            1,AT,Vienna,2025-01-01,2025-04-01,drawee,,500,sat,AT,Vienna,en-UK\n\
+            2, AT ,\"Vienna, 1st\",2025-01-01,2025-04-01,drawee,payee,600,sat,AT,Vienna,en-UK\n\
+            x,AT,Vienna,2025-01-01,2025-04-01,drawee,payee,600,sat,AT,Vienna,en-UK\n";
+        let rows = read_batch(csv.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 3);
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.t, 1);
+        assert_eq!(first.payee, "");
+        let second = rows[1].as_ref().unwrap();
+        assert_eq!(second.country_of_issuing, "AT");
+        assert_eq!(second.city_of_issuing, "Vienna, 1st");
+        assert!(rows[2].is_err());
+    }
+
+    #[test]
+    fn read_batch_json() {
+        let json = r#"[
+            {"type": 0, "country_of_issuing": "AT", "city_of_issuing": "Vienna", "issue_date": "2025-01-01", "maturity_date": "2025-04-01", "payee": "payee", "sum": "500", "currency": "sat", "country_of_payment": "AT", "city_of_payment": "Vienna", "language": "en-UK"},
+            {"type": 0}
+        ]"#;
+        let rows = read_batch(json.as_bytes()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].as_ref().unwrap().drawee, "");
+        assert!(rows[1].is_err());
+    }
+
+    #[test]
+    fn read_batch_fails_for_empty_or_broken_files() {
+        assert!(read_batch(b"[]").is_err());
+        assert!(read_batch(b"type,sum\n").is_err());
+        assert!(read_batch(b"[{").is_err());
+        assert!(read_batch(&[0xff, 0xfe]).is_err());
+    }
+}
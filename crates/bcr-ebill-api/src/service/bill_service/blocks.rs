@@ -285,6 +285,7 @@ impl BillService {
         let try_add_block = blockchain.try_add_block(new_block.clone());
         if try_add_block && blockchain.is_chain_valid() {
             self.blockchain_store.add_block(bill_id, &new_block).await?;
+            self.update_bill_state(bill_id, blockchain).await;
             Ok(())
         } else {
             Err(Error::Blockchain(blockchain::Error::BlockchainInvalid))
@@ -0,0 +1,130 @@
+use super::error::Error;
+use super::service::BillService;
+use super::{BillServiceApi, Result};
+use crate::blockchain::Blockchain;
+use crate::blockchain::bill::bundle::{BillChainBundle, BillChainBundleFile};
+
+impl BillService {
+    /// Creates a bundle of the given bill's chain and its decrypted attached files, disclosing
+    /// either the bill keys, or only the keys of the given blocks. The files are only bundled, if
+    /// the issue block, which references them, is disclosed
+    pub(super) async fn create_chain_bundle(
+        &self,
+        bill_id: &str,
+        disclosed_block_ids: Option<&[u64]>,
+        current_identity_node_id: &str,
+        current_timestamp: u64,
+    ) -> Result<BillChainBundle> {
+        if !self.store.exists(bill_id).await {
+            return Err(Error::NotFound);
+        }
+        let chain = self.blockchain_store.get_chain(bill_id).await?;
+        let bill_keys = self.store.get_keys(bill_id).await?;
+        // only participants of the bill can export it
+        if !chain
+            .get_all_nodes_from_bill(&bill_keys)?
+            .iter()
+            .any(|node_id| node_id == current_identity_node_id)
+        {
+            return Err(Error::NotFound);
+        }
+
+        let issue_block_disclosed =
+            disclosed_block_ids.is_none_or(|ids| ids.contains(&chain.get_first_block().id));
+        let mut files = vec![];
+        if issue_block_disclosed {
+            let bill = chain.get_first_version_bill(&bill_keys)?;
+            for file in bill.files.iter() {
+                let content = self
+                    .open_and_decrypt_attached_file(bill_id, &file.name, &bill_keys.private_key)
+                    .await?;
+                files.push(BillChainBundleFile::new(&file.name, &file.hash, &content));
+            }
+        }
+
+        let bundle = BillChainBundle::new(
+            &chain,
+            &bill_keys,
+            disclosed_block_ids,
+            files,
+            current_timestamp,
+        )?;
+        Ok(bundle)
+    }
+}
+
+/// Serializes the bundle as pretty-printed JSON, so it can be read by auditors as well
+pub fn write_chain_bundle(bundle: &BillChainBundle) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(bundle).map_err(|e| Error::Export(e.to_string()))
+}
+
+/// Parses a bundle from its JSON representation
+pub fn read_chain_bundle(bytes: &[u8]) -> Result<BillChainBundle> {
+    serde_json::from_slice(bytes).map_err(|e| Error::Export(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::bill_service::test_utils::{
+        get_baseline_identity, get_ctx, get_genesis_chain, get_service,
+    };
+    use crate::tests::tests::{TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP};
+    use bcr_ebill_core::bill::BillKeys;
+
+    #[tokio::test]
+    async fn create_chain_bundle_fails_for_non_participant() {
+        let mut ctx = get_ctx();
+        ctx.bill_store.expect_exists().returning(|_| true);
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(|_| Ok(get_genesis_chain(None)));
+        ctx.bill_store.expect_get_keys().returning(|_| {
+            Ok(BillKeys {
+                private_key: TEST_PRIVATE_KEY_SECP.to_owned(),
+                public_key: TEST_PUB_KEY_SECP.to_owned(),
+            })
+        });
+        let service = get_service(ctx);
+
+        let result = service
+            .create_chain_bundle("1234", None, "some other node id", 1731593930)
+            .await;
+        assert!(matches!(result, Err(Error::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn export_and_verify_chain_bundle_baseline() {
+        let mut ctx = get_ctx();
+        ctx.bill_store.expect_exists().returning(|_| true);
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(|_| Ok(get_genesis_chain(None)));
+        ctx.bill_store.expect_get_keys().returning(|_| {
+            Ok(BillKeys {
+                private_key: TEST_PRIVATE_KEY_SECP.to_owned(),
+                public_key: TEST_PUB_KEY_SECP.to_owned(),
+            })
+        });
+        let service = get_service(ctx);
+        let node_id = get_baseline_identity().identity.node_id;
+
+        let bundle = service
+            .export_chain_bundle("1234", None, &node_id, 1731593930)
+            .await
+            .unwrap();
+        assert_eq!(read_chain_bundle(&bundle).unwrap().exported_at, 1731593930);
+        let verification = service.verify_chain_bundle(&bundle).unwrap();
+        assert!(verification.chain_valid);
+        assert_eq!(verification.blocks.len(), 1);
+        assert!(verification.blocks[0].data.is_some());
+
+        let bundle = service
+            .export_chain_bundle("1234", Some(vec![2]), &node_id, 1731593930)
+            .await
+            .unwrap();
+        let verification = service.verify_chain_bundle(&bundle).unwrap();
+        assert!(verification.is_valid());
+        assert!(verification.blocks[0].data.is_none());
+    }
+}
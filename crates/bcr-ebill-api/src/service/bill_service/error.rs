@@ -120,6 +120,16 @@ pub enum Error {
     #[error("Bill is requested to pay")]
     BillIsRequestedToPay,
 
+    /// error returned if the counterparty of an issue or endorsement is not a verified contact
+    /// and the contact verification policy refuses unverified counterparties
+    #[error("Counterparty {0} is not a verified contact")]
+    CounterpartyNotVerified(String),
+
+    /// error returned if a search cursor is used with a different sort order than the one it
+    /// was created for
+    #[error("The search cursor was created for a different sort order")]
+    SearchCursorSortMismatch,
+
     /// error returned if the given file upload id is not a temp file we have
     #[error("No file found for file upload id")]
     NoFileForFileUploadId,
@@ -145,4 +155,16 @@ pub enum Error {
 
     #[error("io error {0}")]
     Io(#[from] std::io::Error),
+
+    /// errors stemming from writing an export of bills
+    #[error("Export error: {0}")]
+    Export(String),
+
+    /// errors stemming from reading a file of bills to issue as a batch
+    #[error("Batch file error: {0}")]
+    BatchFile(String),
+
+    /// errors stemming from rendering a bill as PDF
+    #[error("PDF error: {0}")]
+    Pdf(String),
 }
@@ -0,0 +1,427 @@
+use super::error::Error;
+use super::service::BillService;
+use super::{BillServiceApi, Result};
+use crate::data::{
+    bill::{BillExportFormat, BillSearchQuery, BillStateFilter, BitcreditBillResult, Endorsement},
+    identity::Identity,
+};
+use crate::util::{self, date::DEFAULT_DATE_TIME_FORMAT};
+use futures::future::try_join_all;
+use rust_xlsxwriter::{Workbook, XlsxError};
+use serde::Serialize;
+use std::borrow::Cow;
+
+const BILL_COLUMNS: [&str; 26] = [
+    "id",
+    "status",
+    "drawee_name",
+    "drawee_node_id",
+    "drawer_name",
+    "drawer_node_id",
+    "payee_name",
+    "payee_node_id",
+    "holder_name",
+    "holder_node_id",
+    "sum",
+    "currency",
+    "issue_date",
+    "maturity_date",
+    "time_of_drawing",
+    "country_of_issuing",
+    "city_of_issuing",
+    "country_of_payment",
+    "city_of_payment",
+    "language",
+    "accepted",
+    "paid",
+    "in_recourse",
+    "waiting_for_payment",
+    "endorsements_count",
+    "endorsements",
+];
+
+const ENDORSEMENT_COLUMNS: [&str; 9] = [
+    "bill_id",
+    "endorsee_name",
+    "endorsee_node_id",
+    "endorsee_address",
+    "signer_name",
+    "signer_node_id",
+    "signatory_name",
+    "signing_time",
+    "signing_address",
+];
+
+/// A bill as it's exported, with its full endorsement history
+#[derive(Debug, Clone, Serialize)]
+pub struct BillExportRow {
+    pub id: String,
+    pub status: String,
+    pub drawee_name: String,
+    pub drawee_node_id: String,
+    pub drawer_name: String,
+    pub drawer_node_id: String,
+    pub payee_name: String,
+    pub payee_node_id: String,
+    pub holder_name: String,
+    pub holder_node_id: String,
+    pub sum: String,
+    pub currency: String,
+    pub issue_date: String,
+    pub maturity_date: String,
+    pub time_of_drawing: String,
+    pub country_of_issuing: String,
+    pub city_of_issuing: String,
+    pub country_of_payment: String,
+    pub city_of_payment: String,
+    pub language: String,
+    pub accepted: bool,
+    pub paid: bool,
+    pub in_recourse: bool,
+    pub waiting_for_payment: bool,
+    pub endorsements: Vec<BillExportEndorsement>,
+}
+
+/// An endorsement of an exported bill
+#[derive(Debug, Clone, Serialize)]
+pub struct BillExportEndorsement {
+    pub endorsee_name: String,
+    pub endorsee_node_id: String,
+    pub endorsee_address: String,
+    pub signer_name: String,
+    pub signer_node_id: String,
+    pub signatory_name: Option<String>,
+    pub signing_time: String,
+    pub signing_address: String,
+}
+
+impl BillExportRow {
+    pub fn new(bill: BitcreditBillResult, endorsements: Vec<Endorsement>) -> Self {
+        let status = bill_status(&bill).to_owned();
+        let holder = bill.endorsee.as_ref().unwrap_or(&bill.payee);
+        Self {
+            status,
+            holder_name: holder.name.clone(),
+            holder_node_id: holder.node_id.clone(),
+            id: bill.id,
+            drawee_name: bill.drawee.name,
+            drawee_node_id: bill.drawee.node_id,
+            drawer_name: bill.drawer.name,
+            drawer_node_id: bill.drawer.node_id,
+            payee_name: bill.payee.name,
+            payee_node_id: bill.payee.node_id,
+            sum: bill.sum,
+            currency: bill.currency,
+            issue_date: bill.issue_date,
+            maturity_date: bill.maturity_date,
+            time_of_drawing: format_timestamp(bill.time_of_drawing),
+            country_of_issuing: bill.country_of_issuing,
+            city_of_issuing: bill.city_of_issuing,
+            country_of_payment: bill.country_of_payment,
+            city_of_payment: bill.city_of_payment,
+            language: bill.language,
+            accepted: bill.accepted,
+            paid: bill.paid,
+            in_recourse: bill.in_recourse,
+            waiting_for_payment: bill.waiting_for_payment,
+            endorsements: endorsements
+                .into_iter()
+                .map(BillExportEndorsement::from)
+                .collect(),
+        }
+    }
+
+    /// The values of the bill columns - the endorsements are summarised in a single column
+    fn columns(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.status.clone(),
+            self.drawee_name.clone(),
+            self.drawee_node_id.clone(),
+            self.drawer_name.clone(),
+            self.drawer_node_id.clone(),
+            self.payee_name.clone(),
+            self.payee_node_id.clone(),
+            self.holder_name.clone(),
+            self.holder_node_id.clone(),
+            self.sum.clone(),
+            self.currency.clone(),
+            self.issue_date.clone(),
+            self.maturity_date.clone(),
+            self.time_of_drawing.clone(),
+            self.country_of_issuing.clone(),
+            self.city_of_issuing.clone(),
+            self.country_of_payment.clone(),
+            self.city_of_payment.clone(),
+            self.language.clone(),
+            self.accepted.to_string(),
+            self.paid.to_string(),
+            self.in_recourse.to_string(),
+            self.waiting_for_payment.to_string(),
+            self.endorsements.len().to_string(),
+            self.endorsements
+                .iter()
+                .map(|e| {
+                    format!(
+                        "{} ({}) at {}",
+                        e.endorsee_name, e.endorsee_node_id, e.signing_time
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("; "),
+        ]
+    }
+}
+
+impl BillExportEndorsement {
+    fn columns(&self, bill_id: &str) -> Vec<String> {
+        vec![
+            bill_id.to_owned(),
+            self.endorsee_name.clone(),
+            self.endorsee_node_id.clone(),
+            self.endorsee_address.clone(),
+            self.signer_name.clone(),
+            self.signer_node_id.clone(),
+            self.signatory_name.clone().unwrap_or_default(),
+            self.signing_time.clone(),
+            self.signing_address.clone(),
+        ]
+    }
+}
+
+impl From<Endorsement> for BillExportEndorsement {
+    fn from(value: Endorsement) -> Self {
+        Self {
+            endorsee_name: value.pay_to_the_order_of.name,
+            endorsee_node_id: value.pay_to_the_order_of.node_id,
+            endorsee_address: value.pay_to_the_order_of.postal_address.to_string(),
+            signer_name: value.signed.data.name,
+            signer_node_id: value.signed.data.node_id,
+            signatory_name: value.signed.signatory.map(|s| s.name),
+            signing_time: format_timestamp(value.signing_timestamp),
+            signing_address: value.signing_address.to_string(),
+        }
+    }
+}
+
+/// The most advanced status the bill reached
+fn bill_status(bill: &BitcreditBillResult) -> &'static str {
+    if bill.paid {
+        "paid"
+    } else if bill.in_recourse {
+        "in_recourse"
+    } else if bill.waiting_for_payment {
+        "waiting_for_payment"
+    } else if bill.requested_to_pay {
+        "requested_to_pay"
+    } else if bill.accepted {
+        "accepted"
+    } else if bill.requested_to_accept {
+        "requested_to_accept"
+    } else {
+        "issued"
+    }
+}
+
+/// Prefixes values, which spreadsheet applications would interpret as a formula, with a `'`,
+/// since names and addresses are controlled by the counterparties of a bill
+fn neutralize_formula(value: &str) -> Cow<'_, str> {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        Cow::Owned(format!("'{value}"))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    util::date::seconds(timestamp)
+        .format(DEFAULT_DATE_TIME_FORMAT)
+        .to_string()
+}
+
+impl BillService {
+    /// Collects all bills of the given identity matching the query, in the query's sort order,
+    /// ignoring its page
+    pub(super) async fn get_bills_for_export(
+        &self,
+        query: &BillSearchQuery,
+        local_identity: &Identity,
+        current_identity_node_id: &str,
+        current_timestamp: u64,
+    ) -> Result<Vec<BillExportRow>> {
+        self.build_missing_bill_states().await?;
+        let mut filter = BillStateFilter {
+            identity_node_id: current_identity_node_id.to_owned(),
+            timestamp: current_timestamp,
+            query: query.clone(),
+        };
+        filter.query.cursor = None;
+        filter.query.limit = None;
+        let states = self.store.search_bill_states(&filter).await?;
+
+        let tasks = states.iter().map(|state| async move {
+            let bill = self
+                .get_full_bill(
+                    &state.bill_id,
+                    local_identity,
+                    current_identity_node_id,
+                    current_timestamp,
+                )
+                .await?;
+            let endorsements = self
+                .get_endorsements(&state.bill_id, current_identity_node_id)
+                .await?;
+            Ok::<BillExportRow, Error>(BillExportRow::new(bill, endorsements))
+        });
+        try_join_all(tasks).await
+    }
+}
+
+/// Writes the given bills in the given format
+pub(super) fn write_export(rows: &[BillExportRow], format: BillExportFormat) -> Result<Vec<u8>> {
+    match format {
+        BillExportFormat::Csv => write_csv(rows),
+        BillExportFormat::JsonLines => write_json_lines(rows),
+        BillExportFormat::Xlsx => write_xlsx(rows).map_err(|e| Error::Export(e.to_string())),
+    }
+}
+
+fn write_csv(rows: &[BillExportRow]) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record(BILL_COLUMNS)
+        .map_err(|e| Error::Export(e.to_string()))?;
+    for row in rows {
+        writer
+            .write_record(
+                row.columns()
+                    .iter()
+                    .map(|c| neutralize_formula(c).into_owned()),
+            )
+            .map_err(|e| Error::Export(e.to_string()))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| Error::Export(e.to_string()))
+}
+
+/// One bill per line, with the endorsements as a nested list
+fn write_json_lines(rows: &[BillExportRow]) -> Result<Vec<u8>> {
+    let mut result = vec![];
+    for row in rows {
+        serde_json::to_writer(&mut result, row).map_err(|e| Error::Export(e.to_string()))?;
+        result.push(b'\n');
+    }
+    Ok(result)
+}
+
+/// A workbook with a sheet for the bills and one for all their endorsements
+fn write_xlsx(rows: &[BillExportRow]) -> std::result::Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+
+    let bills_sheet = workbook.add_worksheet().set_name("Bills")?;
+    for (col, header) in BILL_COLUMNS.iter().enumerate() {
+        bills_sheet.write_string(0, col as u16, *header)?;
+    }
+    for (row_idx, row) in rows.iter().enumerate() {
+        for (col, value) in row.columns().iter().enumerate() {
+            bills_sheet.write_string(row_idx as u32 + 1, col as u16, neutralize_formula(value))?;
+        }
+    }
+
+    let endorsements_sheet = workbook.add_worksheet().set_name("Endorsements")?;
+    for (col, header) in ENDORSEMENT_COLUMNS.iter().enumerate() {
+        endorsements_sheet.write_string(0, col as u16, *header)?;
+    }
+    let mut row_idx = 1;
+    for row in rows {
+        for endorsement in row.endorsements.iter() {
+            for (col, value) in endorsement.columns(&row.id).iter().enumerate() {
+                endorsements_sheet.write_string(row_idx, col as u16, neutralize_formula(value))?;
+            }
+            row_idx += 1;
+        }
+    }
+
+    workbook.save_to_buffer()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::bill_service::test_utils::{
+        get_baseline_bill_result, get_baseline_endorsement,
+    };
+
+    fn get_row() -> BillExportRow {
+        let mut bill = get_baseline_bill_result("1234");
+        bill.payee.name = "Hans, Müller".to_owned();
+        bill.payee.node_id = "payee".to_owned();
+        BillExportRow::new(bill, vec![get_baseline_endorsement()])
+    }
+
+    #[test]
+    fn export_csv() {
+        let csv =
+            String::from_utf8(write_export(&[get_row()], BillExportFormat::Csv).unwrap()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("id,status,drawee_name"));
+        assert!(lines[1].starts_with("1234,accepted,"));
+        // values with separators are quoted
+        assert!(lines[1].contains("\"Hans, Müller\""));
+        assert!(lines[1].ends_with("endorsee (endorsee_node_id) at 2024-11-14 14:18:50"));
+    }
+
+    #[test]
+    fn export_csv_neutralizes_formulas() {
+        let mut bill = get_baseline_bill_result("1234");
+        bill.payee.name = "=HYPERLINK(\"http://evil.example\")".to_owned();
+        bill.drawee.name = "@SUM(A1)".to_owned();
+        bill.city_of_issuing = "+Vienna".to_owned();
+        bill.city_of_payment = "-Vienna".to_owned();
+        bill.drawer.name = "\tDrawer".to_owned();
+        let row = BillExportRow::new(bill, vec![]);
+
+        let csv = String::from_utf8(write_export(&[row.clone()], BillExportFormat::Csv).unwrap())
+            .unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[2], "'@SUM(A1)");
+        assert_eq!(&record[4], "'\tDrawer");
+        assert_eq!(&record[6], "'=HYPERLINK(\"http://evil.example\")");
+        assert_eq!(&record[16], "'+Vienna");
+        assert_eq!(&record[18], "'-Vienna");
+        assert_eq!(&record[0], "1234");
+
+        // JSON isn't interpreted by spreadsheet applications and is kept as it is
+        let jsonl = write_export(&[row], BillExportFormat::JsonLines).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(String::from_utf8(jsonl).unwrap().trim()).unwrap();
+        assert_eq!(line["drawee_name"], "@SUM(A1)");
+    }
+
+    #[test]
+    fn export_json_lines() {
+        let jsonl = write_export(&[get_row(), get_row()], BillExportFormat::JsonLines).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(jsonl)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], "1234");
+        assert_eq!(lines[0]["holder_node_id"], "payee");
+        assert_eq!(
+            lines[0]["endorsements"][0]["endorsee_node_id"],
+            "endorsee_node_id"
+        );
+    }
+
+    #[test]
+    fn export_xlsx() {
+        let xlsx = write_export(&[get_row()], BillExportFormat::Xlsx).unwrap();
+        // xlsx files are zip archives
+        assert_eq!(&xlsx[0..2], b"PK");
+    }
+}
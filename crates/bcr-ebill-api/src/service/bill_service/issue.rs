@@ -13,6 +13,8 @@ use bcr_ebill_core::{
 use log::error;
 
 impl BillService {
+    /// Issues the bill. The counterparties have to be checked against the contact verification
+    /// policy before
     #[allow(clippy::too_many_arguments)]
     pub(super) async fn issue_bill(
         &self,
@@ -97,6 +99,7 @@ impl BillService {
 
         let block = chain.get_first_block();
         self.blockchain_store.add_block(&bill.id, block).await?;
+        self.update_bill_state(&bill.id, &chain).await;
 
         self.add_identity_and_company_chain_blocks_for_signed_bill_action(
             &drawer_public_data,
@@ -1,29 +1,38 @@
 use crate::blockchain::bill::BillBlockchain;
+use crate::blockchain::bill::bundle::BillChainVerification;
 use crate::data::{
     File,
     bill::{
-        BillCombinedBitcoinKey, BillKeys, BillsBalanceOverview, BillsFilterRole, BitcreditBill,
-        BitcreditBillResult, Endorsement, LightBitcreditBillResult, PastEndorsee, RecourseReason,
+        BillBatchResult, BillCombinedBitcoinKey, BillExportFormat, BillKeys, BillSearchQuery,
+        BillSearchResult, BillsBalanceOverview, BitcreditBill, BitcreditBillResult, Endorsement,
+        IssuedBill, LightBitcreditBillResult, PastEndorsee, RecourseReason,
     },
     contact::IdentityPublicData,
     identity::Identity,
 };
 use crate::util::BcrKeys;
 use async_trait::async_trait;
+pub use bundle::{read_chain_bundle, write_chain_bundle};
 pub use error::Error;
 #[cfg(test)]
 use mockall::automock;
+pub use validation::{BillToIssue, validate_bill_to_issue};
 
 /// Generic result type
 pub type Result<T> = std::result::Result<T, error::Error>;
 
+mod batch;
 mod blocks;
+mod bundle;
 mod data_fetching;
 pub mod error;
+mod export;
 mod issue;
 mod payment;
+mod pdf;
 mod propagation;
 pub mod service;
+mod state;
 #[cfg(test)]
 pub mod test_utils;
 mod validation;
@@ -55,20 +64,35 @@ pub trait BillServiceApi: Send + Sync {
         current_identity_node_id: &str,
     ) -> Result<BillsBalanceOverview>;
 
-    /// Search for bills
+    /// Search for bills matching the given query, returning one page of results and the
+    /// cursor for the next page
     async fn search_bills(
         &self,
-        currency: &str,
-        search_term: &Option<String>,
-        date_range_from: Option<u64>,
-        date_range_to: Option<u64>,
-        role: &BillsFilterRole,
+        query: &BillSearchQuery,
         current_identity_node_id: &str,
-    ) -> Result<Vec<LightBitcreditBillResult>>;
+    ) -> Result<BillSearchResult>;
+
+    /// Exports all bills matching the given query in the given format, including their
+    /// endorsements
+    async fn export_bills(
+        &self,
+        query: &BillSearchQuery,
+        format: BillExportFormat,
+        current_identity_node_id: &str,
+    ) -> Result<Vec<u8>>;
 
     /// Gets all bills
     async fn get_bills(&self, current_identity_node_id: &str) -> Result<Vec<BitcreditBillResult>>;
 
+    /// Gets all bills in the light version, without replaying their chains
+    async fn get_light_bills(
+        &self,
+        current_identity_node_id: &str,
+    ) -> Result<Vec<LightBitcreditBillResult>>;
+
+    /// Adds all bills to the search index
+    async fn rebuild_search_index(&self) -> Result<()>;
+
     /// Gets all bills from all identities
     async fn get_bills_from_all_identities(&self) -> Result<Vec<BitcreditBillResult>>;
 
@@ -89,6 +113,30 @@ pub trait BillServiceApi: Send + Sync {
         current_timestamp: u64,
     ) -> Result<BitcreditBillResult>;
 
+    /// Renders the current state of the given bill as printable PDF
+    async fn get_bill_pdf(
+        &self,
+        bill_id: &str,
+        local_identity: &Identity,
+        current_identity_node_id: &str,
+        current_timestamp: u64,
+    ) -> Result<Vec<u8>>;
+
+    /// Exports the chain of the given bill with its attached files as a self-contained JSON
+    /// bundle, which can be verified offline. If block ids are given, only these blocks are
+    /// disclosed, otherwise the bill keys are part of the bundle
+    async fn export_chain_bundle(
+        &self,
+        bill_id: &str,
+        disclosed_block_ids: Option<Vec<u64>>,
+        current_identity_node_id: &str,
+        current_timestamp: u64,
+    ) -> Result<Vec<u8>>;
+
+    /// Verifies the given JSON bill chain bundle, checking the chain, every block's hash and
+    /// signature and the hashes of the bundled files
+    fn verify_chain_bundle(&self, bundle: &[u8]) -> Result<BillChainVerification>;
+
     /// Gets the bill for the given bill id
     async fn get_bill(&self, bill_id: &str) -> Result<BitcreditBill>;
 
@@ -113,7 +161,8 @@ pub trait BillServiceApi: Send + Sync {
         bill_public_key: &str,
     ) -> Result<File>;
 
-    /// issues a new bill
+    /// issues a new bill, after checking its counterparties against the contact verification
+    /// policy
     #[allow(clippy::too_many_arguments)]
     async fn issue_new_bill(
         &self,
@@ -132,7 +181,25 @@ pub trait BillServiceApi: Send + Sync {
         drawer_public_data: IdentityPublicData,
         drawer_keys: BcrKeys,
         timestamp: u64,
-    ) -> Result<BitcreditBill>;
+    ) -> Result<IssuedBill>;
+
+    /// Issues the bills defined in the CSV or JSON file of the given temporary file upload,
+    /// drawn by the given drawer. Every row is validated like a single bill to issue and the
+    /// valid rows are issued, the invalid ones are returned with their errors. With
+    /// `all_or_nothing`, the bills are only issued, if all rows are valid, and the remaining
+    /// rows aren't issued, once issuing a row fails. The rows issued for the batch with the
+    /// given id, which defaults to the upload id, are skipped, so a corrected file can be
+    /// issued again. With `dry_run`, only the validation is done
+    async fn issue_bill_batch(
+        &self,
+        file_upload_id: &str,
+        batch_id: Option<&str>,
+        dry_run: bool,
+        all_or_nothing: bool,
+        drawer_public_data: &IdentityPublicData,
+        drawer_keys: &BcrKeys,
+        timestamp: u64,
+    ) -> Result<BillBatchResult>;
 
     /// executes the given bill action
     async fn execute_bill_action(
@@ -144,6 +211,23 @@ pub trait BillServiceApi: Send + Sync {
         timestamp: u64,
     ) -> Result<BillBlockchain>;
 
+    /// Endorses the bill to the given endorsee and returns the warnings of the contact
+    /// verification policy for the endorsee
+    async fn endorse_bill(
+        &self,
+        bill_id: &str,
+        endorsee: IdentityPublicData,
+        signer_public_data: &IdentityPublicData,
+        signer_keys: &BcrKeys,
+        timestamp: u64,
+    ) -> Result<Vec<String>>;
+
+    /// Checks the counterparties of an issue or endorsement against the contact verification
+    /// policy. Returns a warning for every counterparty, which is not a verified contact, if the
+    /// policy is to warn, and fails, if the policy is to refuse. Our identity and companies are
+    /// always accepted
+    async fn check_counterparty_verification(&self, node_ids: &[String]) -> Result<Vec<String>>;
+
     /// Check payment status of bills that are requested to pay and not expired and not paid yet, updating their
     /// paid status if they were paid
     async fn check_bills_payment(&self) -> Result<()>;
@@ -181,6 +265,7 @@ pub mod tests {
     use crate::{
         persistence,
         service::company_service::tests::get_baseline_company_data,
+        service::contact_service::tests::get_baseline_contact,
         tests::tests::{
             TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP, empty_address, empty_identity_public_data,
             identity_public_data_only_node_id, init_test_cfg,
@@ -188,6 +273,9 @@ pub mod tests {
         util,
     };
     use bcr_ebill_core::{
+        bill::{
+            BillBatchIssue, BillRole, BillSearchCursor, BillSortField, BillStateKey, SortDirection,
+        },
         blockchain::{
             Blockchain,
             bill::{
@@ -201,14 +289,18 @@ pub mod tests {
             },
         },
         constants::PAYMENT_DEADLINE_SECONDS,
+        contact::{ContactVerificationPolicy, ContactVerificationStatus},
         notification::ActionType,
+        search::SearchField,
     };
     use core::str;
     use mockall::predicate::{always, eq, function};
     use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
     use test_utils::{
         accept_block, get_baseline_bill, get_baseline_identity, get_ctx, get_genesis_chain,
-        get_service, offer_to_sell_block, request_to_accept_block, request_to_pay_block,
+        get_service, mock_bill_states, offer_to_sell_block, request_to_accept_block,
+        request_to_pay_block,
     };
     use util::crypto::BcrKeys;
 
@@ -231,6 +323,14 @@ pub mod tests {
         bill3.payee = identity_public_data_only_node_id(company_node_id.clone());
         bill3.drawee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
 
+        let company_id = company_node_id.clone();
+        ctx.company_store.expect_get_all().returning(move || {
+            Ok(HashMap::from([(
+                company_id.clone(),
+                get_baseline_company_data().1,
+            )]))
+        });
+        mock_bill_states(&mut ctx.bill_store);
         ctx.bill_store.expect_get_ids().returning(|| {
             Ok(vec![
                 String::from("1234"),
@@ -297,6 +397,14 @@ pub mod tests {
         bill3.payee = identity_public_data_only_node_id(company_node_id.clone());
         bill3.drawee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
 
+        let company_id = company_node_id.clone();
+        ctx.company_store.expect_get_all().returning(move || {
+            Ok(HashMap::from([(
+                company_id.clone(),
+                get_baseline_company_data().1,
+            )]))
+        });
+        mock_bill_states(&mut ctx.bill_store);
         ctx.bill_store.expect_get_ids().returning(|| {
             Ok(vec![
                 String::from("1234"),
@@ -323,82 +431,24 @@ pub mod tests {
         let service = get_service(ctx);
         let res_all_comp = service
             .search_bills(
-                "sat",
-                &None,
-                None,
-                None,
-                &BillsFilterRole::All,
+                &BillSearchQuery {
+                    ..Default::default()
+                },
                 &company_node_id,
             )
             .await;
         assert!(res_all_comp.is_ok());
-        assert_eq!(res_all_comp.as_ref().unwrap().len(), 2);
+        assert_eq!(res_all_comp.as_ref().unwrap().bills.len(), 2);
         let res_all = service
             .search_bills(
-                "sat",
-                &None,
-                None,
-                None,
-                &BillsFilterRole::All,
+                &BillSearchQuery {
+                    ..Default::default()
+                },
                 &identity.identity.node_id,
             )
             .await;
         assert!(res_all.is_ok());
-        assert_eq!(res_all.as_ref().unwrap().len(), 3);
-
-        let res_term = service
-            .search_bills(
-                "sat",
-                &Some(String::from("hayek")),
-                None,
-                None,
-                &BillsFilterRole::All,
-                &identity.identity.node_id,
-            )
-            .await;
-        assert!(res_term.is_ok());
-        assert_eq!(res_term.as_ref().unwrap().len(), 1);
-
-        let from_ts = util::date::date_string_to_i64_timestamp("2030-05-01", None).unwrap();
-        let to_ts = util::date::date_string_to_i64_timestamp("2030-05-30", None).unwrap();
-        let res_fromto = service
-            .search_bills(
-                "sat",
-                &None,
-                Some(from_ts as u64),
-                Some(to_ts as u64),
-                &BillsFilterRole::All,
-                &identity.identity.node_id,
-            )
-            .await;
-        assert!(res_fromto.is_ok());
-        assert_eq!(res_fromto.as_ref().unwrap().len(), 2);
-
-        let res_role = service
-            .search_bills(
-                "sat",
-                &None,
-                None,
-                None,
-                &BillsFilterRole::Payer,
-                &identity.identity.node_id,
-            )
-            .await;
-        assert!(res_role.is_ok());
-        assert_eq!(res_role.as_ref().unwrap().len(), 1);
-
-        let res_comb = service
-            .search_bills(
-                "sat",
-                &Some(String::from("hayek")),
-                Some(from_ts as u64),
-                Some(to_ts as u64),
-                &BillsFilterRole::Payee,
-                &identity.identity.node_id,
-            )
-            .await;
-        assert!(res_comb.is_ok());
-        assert_eq!(res_comb.as_ref().unwrap().len(), 1);
+        assert_eq!(res_all.as_ref().unwrap().bills.len(), 3);
     }
 
     #[tokio::test]
@@ -447,7 +497,8 @@ pub mod tests {
                 1731593928,
             )
             .await
-            .unwrap();
+            .unwrap()
+            .bill;
 
         assert_eq!(bill.files.first().unwrap().name, expected_file_name);
     }
@@ -498,12 +549,83 @@ pub mod tests {
                 1731593928,
             )
             .await
-            .unwrap();
+            .unwrap()
+            .bill;
 
         assert_eq!(bill.files.first().unwrap().name, expected_file_name);
         assert_eq!(bill.drawer.node_id, drawer.0);
     }
 
+    #[tokio::test]
+    async fn issue_bill_fails_for_unverified_counterparty_if_policy_refuses() {
+        let ctx = get_ctx();
+        let mut service = get_service(ctx);
+        service.contact_verification_policy = ContactVerificationPolicy::Refuse;
+
+        let drawer = get_baseline_identity();
+        let res = service
+            .issue_new_bill(
+                String::from("UK"),
+                String::from("London"),
+                String::from("2030-01-01"),
+                String::from("2030-04-01"),
+                identity_public_data_only_node_id(TEST_PUB_KEY_SECP.to_owned()),
+                IdentityPublicData::new(drawer.identity.clone()).unwrap(),
+                100,
+                String::from("sat"),
+                String::from("AT"),
+                String::from("Vienna"),
+                String::from("en-UK"),
+                None,
+                IdentityPublicData::new(drawer.identity).unwrap(),
+                drawer.key_pair,
+                1731593928,
+            )
+            .await;
+        match res {
+            Err(Error::CounterpartyNotVerified(node_id)) => {
+                assert_eq!(node_id, TEST_PUB_KEY_SECP)
+            }
+            _ => panic!("expected a different result"),
+        };
+    }
+
+    #[tokio::test]
+    async fn check_counterparty_verification_warns_for_unverified_contacts() {
+        let mut ctx = get_ctx();
+        ctx.contact_store.expect_get().returning(|node_id| {
+            let mut contact = get_baseline_contact();
+            if node_id == TEST_PUB_KEY_SECP {
+                contact.verification_status = ContactVerificationStatus::InPerson;
+            }
+            Ok(Some(contact))
+        });
+        let mut service = get_service(ctx);
+        service.contact_verification_policy = ContactVerificationPolicy::Warn;
+
+        let unverified = BcrKeys::new().get_public_key();
+        let warnings = service
+            .check_counterparty_verification(&[
+                TEST_PUB_KEY_SECP.to_owned(),
+                unverified.clone(),
+                get_baseline_identity().identity.node_id,
+            ])
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains(&unverified));
+    }
+
+    #[tokio::test]
+    async fn check_counterparty_verification_is_skipped_if_policy_is_off() {
+        let service = get_service(get_ctx());
+        let warnings = service
+            .check_counterparty_verification(&[BcrKeys::new().get_public_key()])
+            .await
+            .unwrap();
+        assert!(warnings.is_empty());
+    }
+
     #[tokio::test]
     async fn save_encrypt_open_decrypt_compare_hashes() {
         let mut ctx = get_ctx();
@@ -629,6 +751,14 @@ pub mod tests {
         bill2.drawer = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
         bill2.payee = identity_public_data_only_node_id(company_node_id.clone());
 
+        let company_id = company_node_id.clone();
+        ctx.company_store.expect_get_all().returning(move || {
+            Ok(HashMap::from([(
+                company_id.clone(),
+                get_baseline_company_data().1,
+            )]))
+        });
+        mock_bill_states(&mut ctx.bill_store);
         ctx.bill_blockchain_store
             .expect_get_chain()
             .withf(|id| id == "1234")
@@ -670,6 +800,7 @@ pub mod tests {
     #[tokio::test]
     async fn get_bills_baseline() {
         let mut ctx = get_ctx();
+        mock_bill_states(&mut ctx.bill_store);
         let mut bill = get_baseline_bill("1234");
         bill.payee = IdentityPublicData::new(get_baseline_identity().identity).unwrap();
 
@@ -700,9 +831,356 @@ pub mod tests {
         assert_eq!(returned_bills[0].id, "1234".to_string());
     }
 
+    #[tokio::test]
+    async fn export_bills_baseline() {
+        let mut ctx = get_ctx();
+        mock_bill_states(&mut ctx.bill_store);
+        let mut bill = get_baseline_bill("1234");
+        bill.payee = IdentityPublicData::new(get_baseline_identity().identity).unwrap();
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(move |_| Ok(get_genesis_chain(Some(bill.clone()))));
+        ctx.bill_store
+            .expect_get_ids()
+            .returning(|| Ok(vec!["1234".to_string()]));
+        ctx.bill_store.expect_exists().returning(|_| true);
+        ctx.notification_service
+            .expect_get_active_bill_notification()
+            .returning(|_| None);
+
+        let service = get_service(ctx);
+        let res = service
+            .export_bills(
+                &BillSearchQuery::default(),
+                BillExportFormat::JsonLines,
+                &get_baseline_identity().identity.node_id,
+            )
+            .await
+            .unwrap();
+        let lines: Vec<serde_json::Value> = str::from_utf8(&res)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["id"], "1234");
+        assert_eq!(lines[0]["status"], "issued");
+        assert_eq!(
+            lines[0]["payee_node_id"],
+            get_baseline_identity().identity.node_id
+        );
+    }
+
+    const BATCH_HEADER: &str = "type,country_of_issuing,city_of_issuing,issue_date,maturity_date,drawee,payee,sum,currency,country_of_payment,city_of_payment,language";
+
+    /// Returns the hash of the first row of the given batch file
+    fn get_first_row_hash(batch: &str) -> String {
+        let row = batch::read_batch(batch.as_bytes())
+            .unwrap()
+            .remove(0)
+            .unwrap();
+        batch::get_row_hash(&row, &mut HashMap::new()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn issue_bill_batch_issues_valid_rows() {
+        let mut ctx = get_ctx();
+        let batch = format!(
+            "{BATCH_HEADER}\n\
+            1,AT,Vienna,2030-01-01,2030-04-01,drawee,,100,sat,AT,Vienna,en-UK\n\
+            1,AT,Vienna,01.01.2030,2030-04-01,drawee,,lots,sat,AT,Vienna,en-UK\n"
+        );
+        let first_row_hash = get_first_row_hash(&batch);
+        ctx.file_upload_store
+            .expect_read_temp_upload_files()
+            .returning(move |_| Ok(vec![("bills.csv".to_string(), batch.as_bytes().to_vec())]));
+        // the upload is kept, so the corrected file can be issued again
+        ctx.file_upload_store
+            .expect_remove_temp_upload_folder()
+            .never();
+        ctx.bill_batch_store
+            .expect_list_issues()
+            .with(eq("1234"))
+            .returning(|_| Ok(vec![]));
+        ctx.bill_batch_store
+            .expect_add_issue()
+            .withf(move |issue| issue.batch_id == "1234" && issue.row_hash == first_row_hash)
+            .returning(|_| Ok(()))
+            .times(1);
+        ctx.bill_store
+            .expect_save_keys()
+            .returning(|_, _| Ok(()))
+            .times(1);
+        ctx.notification_service
+            .expect_send_bill_is_signed_event()
+            .returning(|_| Ok(()))
+            .times(1);
+        let service = get_service(ctx);
+        let drawer = get_baseline_identity();
+
+        let result = service
+            .issue_bill_batch(
+                "1234",
+                None,
+                false,
+                false,
+                &IdentityPublicData::new(drawer.identity).unwrap(),
+                &drawer.key_pair,
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert!(!result.issued);
+        assert_eq!(result.rows.len(), 2);
+        assert!(result.rows[0].errors.is_empty());
+        assert!(result.rows[0].bill_id.is_some());
+        assert!(result.rows[1].bill_id.is_none());
+        assert_eq!(
+            result.rows[1].errors,
+            vec![
+                "invalid sum: lots".to_string(),
+                "invalid issue date".to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn issue_bill_batch_all_or_nothing_issues_nothing_if_a_row_is_invalid() {
+        let mut ctx = get_ctx();
+        let batch = format!(
+            "{BATCH_HEADER}\n\
+            1,AT,Vienna,2030-01-01,2030-04-01,drawee,,100,sat,AT,Vienna,en-UK\n\
+            1,AT,Vienna,01.01.2030,2030-04-01,drawee,,lots,sat,AT,Vienna,en-UK\n"
+        );
+        ctx.file_upload_store
+            .expect_read_temp_upload_files()
+            .returning(move |_| Ok(vec![("bills.csv".to_string(), batch.as_bytes().to_vec())]));
+        ctx.bill_batch_store
+            .expect_list_issues()
+            .returning(|_| Ok(vec![]));
+        ctx.bill_batch_store.expect_add_issue().never();
+        ctx.bill_store.expect_save_keys().never();
+        let service = get_service(ctx);
+        let drawer = get_baseline_identity();
+
+        let result = service
+            .issue_bill_batch(
+                "1234",
+                None,
+                false,
+                true,
+                &IdentityPublicData::new(drawer.identity).unwrap(),
+                &drawer.key_pair,
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert!(!result.issued);
+        assert!(result.rows[0].errors.is_empty());
+        assert!(result.rows[0].bill_id.is_none());
+        assert_eq!(result.rows[1].errors.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn issue_bill_batch_baseline() {
+        let mut ctx = get_ctx();
+        // identical rows are different bills
+        let batch = format!(
+            "{BATCH_HEADER}\n\
+            1,AT,Vienna,2030-01-01,2030-04-01,drawee,,100,sat,AT,Vienna,en-UK\n\
+            1,AT,Vienna,2030-01-01,2030-04-01,drawee,,100,sat,AT,Vienna,en-UK\n"
+        );
+        ctx.file_upload_store
+            .expect_read_temp_upload_files()
+            .returning(move |_| Ok(vec![("bills.csv".to_string(), batch.as_bytes().to_vec())]));
+        ctx.file_upload_store
+            .expect_remove_temp_upload_folder()
+            .returning(|_| Ok(()))
+            .times(1);
+        ctx.bill_batch_store
+            .expect_list_issues()
+            .returning(|_| Ok(vec![]));
+        let row_hashes = Arc::new(Mutex::new(HashSet::new()));
+        let stored_row_hashes = row_hashes.clone();
+        ctx.bill_batch_store
+            .expect_add_issue()
+            .returning(move |issue| {
+                stored_row_hashes
+                    .lock()
+                    .unwrap()
+                    .insert(issue.row_hash.clone());
+                Ok(())
+            })
+            .times(2);
+        ctx.bill_store
+            .expect_save_keys()
+            .returning(|_, _| Ok(()))
+            .times(2);
+        ctx.notification_service
+            .expect_send_bill_is_signed_event()
+            .returning(|_| Ok(()))
+            .times(2);
+        let service = get_service(ctx);
+        let drawer = get_baseline_identity();
+
+        let result = service
+            .issue_bill_batch(
+                "1234",
+                None,
+                false,
+                false,
+                &IdentityPublicData::new(drawer.identity).unwrap(),
+                &drawer.key_pair,
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert!(result.issued);
+        assert!(result.rows.iter().all(|row| row.bill_id.is_some()));
+        assert_eq!(row_hashes.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn issue_bill_batch_skips_rows_issued_before() {
+        let mut ctx = get_ctx();
+        let batch = format!(
+            "{BATCH_HEADER}\n\
+            1,AT,Vienna,2030-01-01,2030-04-01,drawee,,100,sat,AT,Vienna,en-UK\n\
+            1,AT,Vienna,2030-02-01,2030-05-01,drawee,,200,sat,AT,Vienna,en-UK\n"
+        );
+        let first_row_hash = get_first_row_hash(&batch);
+        ctx.file_upload_store
+            .expect_read_temp_upload_files()
+            .returning(move |_| Ok(vec![("bills.csv".to_string(), batch.as_bytes().to_vec())]));
+        ctx.file_upload_store
+            .expect_remove_temp_upload_folder()
+            .returning(|_| Ok(()))
+            .times(1);
+        // the rows were issued from an earlier upload of the same batch
+        ctx.bill_batch_store
+            .expect_list_issues()
+            .with(eq("batch"))
+            .returning(move |_| {
+                Ok(vec![BillBatchIssue {
+                    batch_id: "batch".to_string(),
+                    row_hash: first_row_hash.clone(),
+                    bill_id: "issued before".to_string(),
+                    issued_at: 1731593000,
+                }])
+            });
+        ctx.bill_batch_store
+            .expect_add_issue()
+            .withf(|issue| issue.batch_id == "batch")
+            .returning(|_| Ok(()))
+            .times(1);
+        ctx.bill_store
+            .expect_save_keys()
+            .returning(|_, _| Ok(()))
+            .times(1);
+        ctx.notification_service
+            .expect_send_bill_is_signed_event()
+            .returning(|_| Ok(()))
+            .times(1);
+        let service = get_service(ctx);
+        let drawer = get_baseline_identity();
+
+        let result = service
+            .issue_bill_batch(
+                "1234",
+                Some("batch"),
+                false,
+                false,
+                &IdentityPublicData::new(drawer.identity).unwrap(),
+                &drawer.key_pair,
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert!(result.issued);
+        assert_eq!(result.rows[0].bill_id, Some("issued before".to_string()));
+        assert!(result.rows[1].bill_id.is_some());
+    }
+
+    #[test]
+    fn validate_bill_to_issue_resolves_roles_of_drawer() {
+        let drawer = "drawer";
+        assert_eq!(
+            validate_bill_to_issue(0, "100", "2030-01-01", "2030-04-01", "", "payee", drawer),
+            Ok(BillToIssue {
+                sum: 100,
+                drawee: None,
+                payee: Some("payee".to_string()),
+            })
+        );
+        assert_eq!(
+            validate_bill_to_issue(1, "100", "2030-01-01", "2030-04-01", "drawee", "", drawer)
+                .unwrap()
+                .payee,
+            None
+        );
+        assert_eq!(
+            validate_bill_to_issue(1, "100", "2030-01-01", "2030-04-01", drawer, "", drawer),
+            Err(vec!["Drawee can't be Payee at the same time".to_string()])
+        );
+    }
+
+    #[test]
+    fn validate_bill_to_issue_collects_all_errors() {
+        assert_eq!(
+            validate_bill_to_issue(2, "lots", "01.01.2030", "", "same", "same", "drawer"),
+            Err(vec![
+                "invalid sum: lots".to_string(),
+                "invalid issue date".to_string(),
+                "invalid maturity date".to_string(),
+                "Drawee can't be Payee at the same time".to_string(),
+            ])
+        );
+        assert_eq!(
+            validate_bill_to_issue(3, "100", "2030-01-01", "2030-04-01", "a", "b", "drawer"),
+            Err(vec!["Invalid bill type found: 3".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn issue_bill_batch_dry_run() {
+        let mut ctx = get_ctx();
+        let batch = r#"[{"type": 1, "country_of_issuing": "AT", "city_of_issuing": "Vienna", "issue_date": "2030-01-01", "maturity_date": "2030-04-01", "drawee": "drawee", "sum": "100", "currency": "sat", "country_of_payment": "AT", "city_of_payment": "Vienna", "language": "en-UK"}]"#;
+        ctx.file_upload_store
+            .expect_read_temp_upload_files()
+            .returning(move |_| Ok(vec![("bills.json".to_string(), batch.as_bytes().to_vec())]));
+        ctx.file_upload_store
+            .expect_remove_temp_upload_folder()
+            .never();
+        ctx.bill_batch_store
+            .expect_list_issues()
+            .returning(|_| Ok(vec![]));
+        ctx.bill_batch_store.expect_add_issue().never();
+        ctx.bill_store.expect_save_keys().never();
+        let service = get_service(ctx);
+        let drawer = get_baseline_identity();
+
+        let result = service
+            .issue_bill_batch(
+                "1234",
+                None,
+                true,
+                false,
+                &IdentityPublicData::new(drawer.identity).unwrap(),
+                &drawer.key_pair,
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert!(result.dry_run);
+        assert!(!result.issued);
+        assert!(result.rows[0].errors.is_empty());
+        assert!(result.rows[0].bill_id.is_none());
+    }
+
     #[tokio::test]
     async fn get_bills_baseline_company() {
         let mut ctx = get_ctx();
+        mock_bill_states(&mut ctx.bill_store);
         let company_node_id = BcrKeys::new().get_public_key();
         let mut bill = get_baseline_bill("1234");
         bill.payee = IdentityPublicData::new(get_baseline_identity().identity).unwrap();
@@ -736,6 +1214,7 @@ pub mod tests {
     #[tokio::test]
     async fn get_bills_req_to_pay() {
         let mut ctx = get_ctx();
+        mock_bill_states(&mut ctx.bill_store);
         let mut bill = get_baseline_bill("1234");
         bill.payee = IdentityPublicData::new(get_baseline_identity().identity).unwrap();
         ctx.bill_blockchain_store
@@ -798,6 +1277,254 @@ pub mod tests {
         assert!(res.unwrap().is_empty());
     }
 
+    #[tokio::test]
+    async fn get_light_bills_from_state() {
+        let mut ctx = get_ctx();
+        mock_bill_states(&mut ctx.bill_store);
+        let mut bill = get_baseline_bill("1234");
+        bill.sum = 1500;
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .times(1)
+            .returning(move |_| Ok(get_genesis_chain(Some(bill.clone()))));
+        ctx.bill_store
+            .expect_get_ids()
+            .returning(|| Ok(vec!["1234".to_string()]));
+        ctx.notification_service
+            .expect_get_active_bill_notification()
+            .with(eq("1234"))
+            .returning(|_| None);
+
+        let service = get_service(ctx);
+
+        // the chain is only replayed once, when the state is built
+        for _ in 0..2 {
+            let res = service
+                .get_light_bills(&get_baseline_identity().identity.node_id)
+                .await
+                .unwrap();
+            assert_eq!(res.len(), 1);
+            assert_eq!(res[0].id, "1234".to_string());
+            assert_eq!(res[0].sum, "1500".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn build_missing_bill_states_for_added_company() {
+        let mut ctx = get_ctx();
+        let identity_node_id = get_baseline_identity().identity.node_id;
+        let company_node_id = BcrKeys::new().get_public_key();
+        let mut bill = get_baseline_bill("1234");
+        bill.payee = identity_public_data_only_node_id(company_node_id.clone());
+
+        let company_id = company_node_id.clone();
+        ctx.company_store.expect_get_all().returning(move || {
+            Ok(HashMap::from([(
+                company_id.clone(),
+                get_baseline_company_data().1,
+            )]))
+        });
+        // both bills only have a state for the personal identity, but the company is only a
+        // participant of the first one
+        let company_id = company_node_id.clone();
+        ctx.bill_store
+            .expect_get_bill_state_keys()
+            .returning(move || {
+                Ok(vec![
+                    BillStateKey {
+                        bill_id: "1234".to_string(),
+                        identity_node_id: identity_node_id.clone(),
+                        participants: vec![identity_node_id.clone(), company_id.clone()],
+                    },
+                    BillStateKey {
+                        bill_id: "4321".to_string(),
+                        identity_node_id: identity_node_id.clone(),
+                        participants: vec![identity_node_id.clone()],
+                    },
+                ])
+            });
+        ctx.bill_store
+            .expect_get_ids()
+            .returning(|| Ok(vec!["1234".to_string(), "4321".to_string()]));
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .with(eq("1234"))
+            .times(1)
+            .returning(move |_| Ok(get_genesis_chain(Some(bill.clone()))));
+        ctx.bill_store
+            .expect_save_bill_states()
+            .withf(move |id, states| {
+                id == "1234" && states.iter().any(|s| s.identity_node_id == company_node_id)
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = get_service(ctx);
+        service.build_missing_bill_states().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn search_bills_passes_query_to_store_and_creates_next_cursor() {
+        let node_id = get_baseline_identity().identity.node_id;
+        let mut states = vec![];
+        for id in ["1111", "2222", "3333"] {
+            let chain = get_genesis_chain(Some(get_baseline_bill(id)));
+            states.extend(
+                get_service(get_ctx())
+                    .get_bill_states(id, &chain)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let mut ctx = get_ctx();
+        ctx.bill_store.expect_get_ids().returning(|| Ok(vec![]));
+        // one more than the limit is fetched, to know if there is a next page
+        let first_page_states = states.clone();
+        ctx.bill_store
+            .expect_search_bill_states()
+            .withf(|filter| {
+                filter.query.limit == Some(3)
+                    && filter.query.sort == BillSortField::Sum
+                    && filter.query.direction == SortDirection::Ascending
+                    && filter.query.sum_from == Some(100)
+                    && filter
+                        .query
+                        .cursor
+                        .as_ref()
+                        .is_some_and(|c| c.sort_value == 100 && c.bill_id == "0000")
+            })
+            .times(1)
+            .returning(move |_| Ok(first_page_states.clone()));
+        ctx.bill_store
+            .expect_search_bill_states()
+            .withf(|filter| filter.query.limit == Some(4))
+            .times(1)
+            .returning(move |_| Ok(states.clone()));
+        ctx.notification_service
+            .expect_get_active_bill_notification()
+            .returning(|_| None);
+        let service = get_service(ctx);
+
+        let mut query = BillSearchQuery {
+            sum_from: Some(100),
+            sort: BillSortField::Sum,
+            direction: SortDirection::Ascending,
+            cursor: Some(BillSearchCursor {
+                sort: BillSortField::Sum,
+                direction: SortDirection::Ascending,
+                sort_value: 100,
+                bill_id: "0000".to_string(),
+            }),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = service.search_bills(&query, &node_id).await.unwrap();
+        assert_eq!(
+            page.bills
+                .iter()
+                .map(|b| b.id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["1111", "2222"]
+        );
+        let next_cursor =
+            BillSearchCursor::decode(&page.next_cursor.expect("has a next page")).unwrap();
+        assert_eq!(next_cursor.bill_id, "2222");
+        assert!(next_cursor.is_for(BillSortField::Sum, SortDirection::Ascending));
+
+        query.limit = Some(3);
+        let last_page = service.search_bills(&query, &node_id).await.unwrap();
+        assert_eq!(last_page.bills.len(), 3);
+        assert!(last_page.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn search_bills_rejects_cursor_of_other_sort_order() {
+        let mut ctx = get_ctx();
+        ctx.bill_store.expect_search_bill_states().never();
+        let service = get_service(ctx);
+
+        let query = BillSearchQuery {
+            sort: BillSortField::Sum,
+            direction: SortDirection::Descending,
+            cursor: Some(BillSearchCursor {
+                sort: BillSortField::Sum,
+                direction: SortDirection::Ascending,
+                sort_value: 100,
+                bill_id: "1111".to_string(),
+            }),
+            ..Default::default()
+        };
+        let res = service
+            .search_bills(&query, &get_baseline_identity().identity.node_id)
+            .await;
+        assert!(matches!(res, Err(Error::SearchCursorSortMismatch)));
+    }
+
+    #[tokio::test]
+    async fn get_bill_states_waiting_for_payment() {
+        let ctx = get_ctx();
+        let identity = get_baseline_identity();
+        let buyer_node_id = BcrKeys::new().get_public_key();
+        let mut bill = get_baseline_bill("1234");
+        bill.drawee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        bill.payee = IdentityPublicData::new(identity.identity.clone()).unwrap();
+        let mut chain = get_genesis_chain(Some(bill));
+        assert!(chain.try_add_block(offer_to_sell_block(
+            "1234",
+            chain.get_latest_block(),
+            &buyer_node_id,
+            &identity.identity.node_id,
+        )));
+        let offer_timestamp = chain.get_latest_block().timestamp;
+
+        let service = get_service(ctx);
+        let states = service.get_bill_states("1234", &chain).await.unwrap();
+        assert_eq!(states.len(), 1);
+        let state = &states[0];
+        assert_eq!(state.identity_node_id, identity.identity.node_id);
+        assert_eq!(state.role, BillRole::Payee);
+        assert_eq!(state.holder_node_id, identity.identity.node_id);
+        assert_eq!(state.block_height, 2);
+        assert_eq!(state.buyer.as_ref().unwrap().node_id, buyer_node_id);
+        assert!(state.is_waiting_for_payment(offer_timestamp));
+        assert!(!state.is_waiting_for_payment(offer_timestamp + PAYMENT_DEADLINE_SECONDS + 1));
+        assert!(!state.is_in_recourse(offer_timestamp));
+    }
+
+    #[tokio::test]
+    async fn rebuild_search_index_indexes_all_bills() {
+        let mut ctx = get_ctx();
+        let mut bill = get_baseline_bill("1234");
+        bill.drawee.name = "Müller GmbH".to_owned();
+        ctx.bill_store
+            .expect_get_ids()
+            .returning(|| Ok(vec!["1234".to_owned()]));
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(move |_| Ok(get_genesis_chain(Some(bill.clone()))));
+        let indexed = Arc::new(Mutex::new(vec![]));
+        let indexed_clone = indexed.clone();
+        ctx.search_index_store
+            .expect_index_document()
+            .returning(move |doc| {
+                indexed_clone.lock().unwrap().push(doc.clone());
+                Ok(())
+            });
+
+        let service = get_service(ctx);
+        service.rebuild_search_index().await.unwrap();
+        let indexed = indexed.lock().unwrap();
+        assert_eq!(indexed.len(), 1);
+        assert_eq!(indexed[0].item_id, "1234");
+        assert!(
+            indexed[0]
+                .terms
+                .iter()
+                .any(|t| t.field == SearchField::Drawee && t.term == "muller")
+        );
+    }
+
     #[tokio::test]
     async fn get_detail_bill_baseline() {
         let mut ctx = get_ctx();
@@ -1410,6 +2137,68 @@ pub mod tests {
         assert!(res.as_ref().unwrap().blocks()[2].op_code == BillOpCode::Sell);
     }
 
+    #[tokio::test]
+    async fn sell_bitcredit_bill_fails_for_unverified_buyer_if_policy_refuses() {
+        let mut ctx = get_ctx();
+        let identity = get_baseline_identity();
+        let mut bill = get_baseline_bill("some id");
+        bill.payee = identity_public_data_only_node_id(identity.identity.node_id.clone());
+        let buyer = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        let buyer_clone = buyer.clone();
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(move |_| {
+                let mut chain = get_genesis_chain(Some(bill.clone()));
+                let offer_to_sell = BillBlock::create_block_for_offer_to_sell(
+                    "some id".to_string(),
+                    chain.get_latest_block(),
+                    &BillOfferToSellBlockData {
+                        seller: bill.payee.clone().into(),
+                        buyer: buyer_clone.clone().into(),
+                        currency: "sat".to_owned(),
+                        sum: 15000,
+                        payment_address: "1234paymentaddress".to_owned(),
+                        signatory: None,
+                        signing_timestamp: 1731593927,
+                        signing_address: empty_address(),
+                    },
+                    &BcrKeys::new(),
+                    None,
+                    &BcrKeys::from_private_key(TEST_PRIVATE_KEY_SECP).unwrap(),
+                    1731593927,
+                )
+                .unwrap();
+                chain.try_add_block(offer_to_sell);
+                Ok(chain)
+            });
+        ctx.notification_service
+            .expect_send_bill_is_sold_event()
+            .never();
+
+        let mut service = get_service(ctx);
+        service.contact_verification_policy = ContactVerificationPolicy::Refuse;
+        let buyer_node_id = buyer.node_id.clone();
+
+        let res = service
+            .execute_bill_action(
+                "some id",
+                BillAction::Sell(
+                    buyer,
+                    15000,
+                    "sat".to_string(),
+                    "1234paymentaddress".to_string(),
+                ),
+                &IdentityPublicData::new(identity.identity.clone()).unwrap(),
+                &identity.key_pair,
+                1731593928,
+            )
+            .await;
+        match res {
+            Err(Error::CounterpartyNotVerified(node_id)) => assert_eq!(node_id, buyer_node_id),
+            _ => panic!("expected a different result"),
+        };
+    }
+
     #[tokio::test]
     async fn sell_bitcredit_bill_fails_if_sell_data_is_invalid() {
         let mut ctx = get_ctx();
@@ -1558,6 +2347,62 @@ pub mod tests {
         assert!(res.unwrap().blocks()[1].op_code == BillOpCode::Endorse);
     }
 
+    #[tokio::test]
+    async fn endorse_bill_returns_warnings_for_unverified_endorsee() {
+        let mut ctx = get_ctx();
+        let identity = get_baseline_identity();
+        let mut bill = get_baseline_bill("some id");
+        bill.payee = identity_public_data_only_node_id(identity.identity.node_id.clone());
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(move |_| Ok(get_genesis_chain(Some(bill.clone()))));
+        ctx.notification_service
+            .expect_send_bill_is_endorsed_event()
+            .returning(|_| Ok(()));
+        let mut service = get_service(ctx);
+        service.contact_verification_policy = ContactVerificationPolicy::Warn;
+
+        let endorsee = BcrKeys::new().get_public_key();
+        let warnings = service
+            .endorse_bill(
+                "some id",
+                identity_public_data_only_node_id(endorsee.clone()),
+                &IdentityPublicData::new(identity.identity.clone()).unwrap(),
+                &identity.key_pair,
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains(&endorsee));
+    }
+
+    #[tokio::test]
+    async fn endorse_bitcredit_bill_fails_for_unverified_endorsee_if_policy_refuses() {
+        let mut ctx = get_ctx();
+        let identity = get_baseline_identity();
+        let mut bill = get_baseline_bill("some id");
+        bill.payee = identity_public_data_only_node_id(identity.identity.node_id.clone());
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(move |_| Ok(get_genesis_chain(Some(bill.clone()))));
+        let mut service = get_service(ctx);
+        service.contact_verification_policy = ContactVerificationPolicy::Refuse;
+
+        let res = service
+            .execute_bill_action(
+                "some id",
+                BillAction::Endorse(identity_public_data_only_node_id(
+                    BcrKeys::new().get_public_key(),
+                )),
+                &IdentityPublicData::new(identity.identity.clone()).unwrap(),
+                &identity.key_pair,
+                1731593928,
+            )
+            .await;
+        assert!(matches!(res, Err(Error::CounterpartyNotVerified(_))));
+    }
+
     #[tokio::test]
     async fn endorse_bitcredit_bill_fails_if_waiting_for_offer_to_sell() {
         let mut ctx = get_ctx();
@@ -2882,4 +3727,68 @@ pub mod tests {
         assert_eq!(res.as_ref().unwrap().blocks().len(), 3);
         assert_eq!(res.unwrap().blocks()[2].op_code, BillOpCode::Recourse);
     }
+
+    #[tokio::test]
+    async fn recourse_bitcredit_bill_fails_for_unverified_recoursee_if_policy_refuses() {
+        let mut ctx = get_ctx();
+        let identity = get_baseline_identity();
+        let mut bill = get_baseline_bill("some id");
+        bill.drawee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        bill.payee = IdentityPublicData::new(identity.identity.clone()).unwrap();
+        let recoursee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        let recoursee_clone = recoursee.clone();
+        let identity_clone = identity.identity.clone();
+
+        ctx.bill_store.expect_is_paid().returning(|_| Ok(false));
+        ctx.bill_blockchain_store
+            .expect_get_chain()
+            .returning(move |_| {
+                let mut chain = get_genesis_chain(Some(bill.clone()));
+                let req_to_recourse = BillBlock::create_block_for_request_recourse(
+                    "some id".to_string(),
+                    chain.get_latest_block(),
+                    &BillRequestRecourseBlockData {
+                        recourser: IdentityPublicData::new(identity_clone.clone())
+                            .unwrap()
+                            .into(),
+                        recoursee: recoursee_clone.clone().into(),
+                        sum: 15000,
+                        currency: "sat".to_string(),
+                        signatory: None,
+                        signing_timestamp: 1731593927,
+                        signing_address: empty_address(),
+                    },
+                    &BcrKeys::new(),
+                    None,
+                    &BcrKeys::from_private_key(TEST_PRIVATE_KEY_SECP).unwrap(),
+                    1731593927,
+                )
+                .unwrap();
+                chain.try_add_block(req_to_recourse);
+                Ok(chain)
+            });
+        ctx.notification_service
+            .expect_send_bill_recourse_paid_event()
+            .never();
+
+        let mut service = get_service(ctx);
+        service.contact_verification_policy = ContactVerificationPolicy::Refuse;
+        let recoursee_node_id = recoursee.node_id.clone();
+
+        let res = service
+            .execute_bill_action(
+                "some id",
+                BillAction::Recourse(recoursee, 15000, "sat".to_string()),
+                &IdentityPublicData::new(identity.identity.clone()).unwrap(),
+                &identity.key_pair,
+                1731593928,
+            )
+            .await;
+        match res {
+            Err(Error::CounterpartyNotVerified(node_id)) => {
+                assert_eq!(node_id, recoursee_node_id)
+            }
+            _ => panic!("expected a different result"),
+        };
+    }
 }
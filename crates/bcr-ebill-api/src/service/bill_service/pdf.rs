@@ -0,0 +1,391 @@
+use super::error::Error;
+use super::service::BillService;
+use super::{BillServiceApi, Result};
+use crate::blockchain::Blockchain;
+use crate::data::{
+    bill::{BitcreditBillResult, Endorsement},
+    contact::IdentityPublicData,
+    identity::Identity,
+};
+use crate::util::{
+    self,
+    date::DEFAULT_DATE_FORMAT,
+    numbers_to_words::{self, Language},
+};
+use printpdf::{
+    Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point,
+    Rect, Rgb,
+};
+use qrcode::QrCode;
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const LINE_HEIGHT: f32 = 6.0;
+const QR_CODE_SIZE: f32 = 40.0;
+/// The x positions of the columns of the endorsement table
+const ENDORSEMENT_COLUMNS: [f32; 5] = [MARGIN, 30.0, 80.0, 130.0, 155.0];
+/// DejaVu Sans is embedded, since the built-in PDF fonts only cover WinAnsi, which garbles
+/// names and places in most other scripts
+const FONT_REGULAR: &[u8] = include_bytes!("../../../resources/fonts/DejaVuSans.ttf");
+const FONT_BOLD: &[u8] = include_bytes!("../../../resources/fonts/DejaVuSans-Bold.ttf");
+
+impl BillService {
+    /// Renders the current state of the given bill as PDF, with the bill on the front page and
+    /// its endorsements on the back
+    pub(super) async fn render_bill_pdf(
+        &self,
+        bill_id: &str,
+        local_identity: &Identity,
+        current_identity_node_id: &str,
+        current_timestamp: u64,
+    ) -> Result<Vec<u8>> {
+        let bill = self
+            .get_detail(
+                bill_id,
+                local_identity,
+                current_identity_node_id,
+                current_timestamp,
+            )
+            .await?;
+        let endorsements = self
+            .get_endorsements(bill_id, current_identity_node_id)
+            .await?;
+        let chain = self.blockchain_store.get_chain(bill_id).await?;
+        let chain_hash = chain.get_latest_block().hash.clone();
+        write_bill_pdf(&bill, &endorsements, &chain_hash)
+    }
+}
+
+/// The content of the QR code on the bill, referencing the bill and the state of its chain it
+/// was printed at
+pub fn bill_qr_code_content(bill_id: &str, chain_hash: &str) -> String {
+    format!("bitcredit:{bill_id}?chain_hash={chain_hash}")
+}
+
+/// Writes the bill as PDF with embedded fonts, so no resources need to be fetched
+pub(super) fn write_bill_pdf(
+    bill: &BitcreditBillResult,
+    endorsements: &[Endorsement],
+    chain_hash: &str,
+) -> Result<Vec<u8>> {
+    let (doc, page, layer) = PdfDocument::new(
+        format!("Bill of exchange {}", bill.id),
+        Mm(PAGE_WIDTH),
+        Mm(PAGE_HEIGHT),
+        "Front",
+    );
+    let fonts = Fonts {
+        regular: doc
+            .add_external_font(FONT_REGULAR)
+            .map_err(|e| Error::Pdf(e.to_string()))?,
+        bold: doc
+            .add_external_font(FONT_BOLD)
+            .map_err(|e| Error::Pdf(e.to_string()))?,
+    };
+
+    let front = doc.get_page(page).get_layer(layer);
+    write_front(&front, &fonts, bill)?;
+    write_qr_code(&front, &fonts, &bill.id, chain_hash)?;
+    write_endorsements(&doc, &fonts, endorsements);
+
+    doc.save_to_bytes().map_err(|e| Error::Pdf(e.to_string()))
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+}
+
+fn write_front(layer: &PdfLayerReference, fonts: &Fonts, bill: &BitcreditBillResult) -> Result<()> {
+    let sum = bill
+        .sum
+        .parse::<u64>()
+        .map_err(|_| Error::Pdf(format!("invalid sum: {}", bill.sum)))?;
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    layer.use_text("BILL OF EXCHANGE", 20.0, Mm(MARGIN), Mm(y), &fonts.bold);
+    y -= LINE_HEIGHT;
+    layer.use_text(
+        format!("Bill {}", bill.id),
+        8.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.regular,
+    );
+    y -= 2.0 * LINE_HEIGHT;
+
+    layer.use_text(
+        format!(
+            "{}, {}, {}",
+            bill.city_of_issuing, bill.country_of_issuing, bill.issue_date
+        ),
+        11.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.regular,
+    );
+    y -= 2.0 * LINE_HEIGHT;
+
+    layer.use_text(
+        format!(
+            "Against this bill of exchange, pay on {}",
+            bill.maturity_date
+        ),
+        11.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.regular,
+    );
+    y -= LINE_HEIGHT;
+    layer.use_text(
+        format!("to the order of {}", bill.payee.name),
+        11.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.bold,
+    );
+    y -= LINE_HEIGHT;
+    layer.use_text(
+        format!("the sum of {} {}", bill.sum, bill.currency),
+        11.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.bold,
+    );
+    y -= LINE_HEIGHT;
+    layer.use_text(
+        format!(
+            "({})",
+            numbers_to_words::sum_to_words(
+                &sum,
+                &bill.currency,
+                Language::from_code(&bill.language)
+            )
+        ),
+        11.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.regular,
+    );
+    y -= LINE_HEIGHT;
+    layer.use_text(
+        format!(
+            "payable in {}, {}",
+            bill.city_of_payment, bill.country_of_payment
+        ),
+        11.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.regular,
+    );
+    y -= 3.0 * LINE_HEIGHT;
+
+    y = write_party(layer, fonts, "Drawee", &bill.drawee, y);
+    y -= LINE_HEIGHT;
+    y = write_party(layer, fonts, "Drawer", &bill.drawer, y);
+    y -= LINE_HEIGHT;
+    y = write_party(layer, fonts, "Payee", &bill.payee, y);
+    if let Some(ref endorsee) = bill.endorsee {
+        y -= LINE_HEIGHT;
+        y = write_party(layer, fonts, "Current holder", endorsee, y);
+    }
+    y -= LINE_HEIGHT;
+
+    let status = if bill.paid {
+        "Paid"
+    } else if bill.accepted {
+        "Accepted by the drawee"
+    } else {
+        "Not accepted"
+    };
+    layer.use_text(status, 11.0, Mm(MARGIN), Mm(y), &fonts.bold);
+    Ok(())
+}
+
+/// Writes the name, address and node id of a party of the bill, returning the next y position
+fn write_party(
+    layer: &PdfLayerReference,
+    fonts: &Fonts,
+    label: &str,
+    party: &IdentityPublicData,
+    y: f32,
+) -> f32 {
+    let mut y = y;
+    layer.use_text(label, 9.0, Mm(MARGIN), Mm(y), &fonts.bold);
+    y -= LINE_HEIGHT;
+    layer.use_text(&party.name, 11.0, Mm(MARGIN), Mm(y), &fonts.regular);
+    y -= LINE_HEIGHT;
+    layer.use_text(
+        party.postal_address.to_string(),
+        9.0,
+        Mm(MARGIN),
+        Mm(y),
+        &fonts.regular,
+    );
+    y -= LINE_HEIGHT;
+    layer.use_text(&party.node_id, 7.0, Mm(MARGIN), Mm(y), &fonts.regular);
+    y - LINE_HEIGHT
+}
+
+/// Draws the QR code in the bottom right corner of the page, one square per dark module
+fn write_qr_code(
+    layer: &PdfLayerReference,
+    fonts: &Fonts,
+    bill_id: &str,
+    chain_hash: &str,
+) -> Result<()> {
+    let code = QrCode::new(bill_qr_code_content(bill_id, chain_hash).as_bytes())
+        .map_err(|e| Error::Pdf(e.to_string()))?;
+    let width = code.width();
+    let module_size = QR_CODE_SIZE / width as f32;
+    let left = PAGE_WIDTH - MARGIN - QR_CODE_SIZE;
+    let top = MARGIN + QR_CODE_SIZE;
+
+    layer.set_fill_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+    for (idx, color) in code.to_colors().iter().enumerate() {
+        if *color != qrcode::Color::Dark {
+            continue;
+        }
+        let x = left + (idx % width) as f32 * module_size;
+        let y = top - (idx / width) as f32 * module_size;
+        layer.add_rect(Rect::new(
+            Mm(x),
+            Mm(y - module_size),
+            Mm(x + module_size),
+            Mm(y),
+        ));
+    }
+    layer.use_text(
+        format!("Chain {chain_hash}"),
+        6.0,
+        Mm(MARGIN),
+        Mm(MARGIN),
+        &fonts.regular,
+    );
+    Ok(())
+}
+
+/// Writes the endorsements as a table on the back, continuing on further pages if necessary
+fn write_endorsements(doc: &PdfDocumentReference, fonts: &Fonts, endorsements: &[Endorsement]) {
+    let (page, layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Back");
+    let mut layer = doc.get_page(page).get_layer(layer);
+    let mut y = PAGE_HEIGHT - MARGIN;
+    layer.use_text("ENDORSEMENTS", 16.0, Mm(MARGIN), Mm(y), &fonts.bold);
+    y -= 2.0 * LINE_HEIGHT;
+    if endorsements.is_empty() {
+        layer.use_text("No endorsements", 11.0, Mm(MARGIN), Mm(y), &fonts.regular);
+        return;
+    }
+    write_endorsement_row(
+        &layer,
+        &fonts.bold,
+        ["#", "Pay to the order of", "Signed by", "Date", "Place"].map(String::from),
+        y,
+    );
+    y -= 2.0;
+    layer.add_line(Line {
+        points: vec![
+            (Point::new(Mm(MARGIN), Mm(y)), false),
+            (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false),
+        ],
+        is_closed: false,
+    });
+    y -= LINE_HEIGHT;
+
+    // the endorsements are ordered latest first, but the back of a bill is read from the top
+    for (idx, endorsement) in endorsements.iter().rev().enumerate() {
+        if y < MARGIN {
+            let (page, next_layer) =
+                doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Back (continued)");
+            layer = doc.get_page(page).get_layer(next_layer);
+            y = PAGE_HEIGHT - MARGIN;
+        }
+        let signed_by = match endorsement.signed.signatory {
+            Some(ref signatory) => {
+                format!("{} ({})", endorsement.signed.data.name, signatory.name)
+            }
+            None => endorsement.signed.data.name.clone(),
+        };
+        write_endorsement_row(
+            &layer,
+            &fonts.regular,
+            [
+                (idx + 1).to_string(),
+                endorsement.pay_to_the_order_of.name.clone(),
+                signed_by,
+                util::date::seconds(endorsement.signing_timestamp)
+                    .format(DEFAULT_DATE_FORMAT)
+                    .to_string(),
+                endorsement.signing_address.city.clone(),
+            ],
+            y,
+        );
+        y -= LINE_HEIGHT;
+    }
+}
+
+fn write_endorsement_row(
+    layer: &PdfLayerReference,
+    font: &IndirectFontRef,
+    values: [String; 5],
+    y: f32,
+) {
+    for (x, value) in ENDORSEMENT_COLUMNS.iter().zip(values) {
+        layer.use_text(value, 9.0, Mm(*x), Mm(y), font);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::bill_service::test_utils::{
+        get_baseline_bill_result, get_baseline_endorsement,
+    };
+
+    #[test]
+    fn qr_code_content_references_bill_and_chain() {
+        assert_eq!(
+            bill_qr_code_content("1234", "hash"),
+            "bitcredit:1234?chain_hash=hash"
+        );
+    }
+
+    #[test]
+    fn write_bill_pdf_baseline() {
+        let bill = get_baseline_bill_result("1234");
+        let pdf = write_bill_pdf(&bill, &[], "hash").unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn write_bill_pdf_with_non_ascii_data() {
+        let mut bill = get_baseline_bill_result("1234");
+        bill.drawee.name = "Jürgen Müller".to_owned();
+        bill.payee.name = "Ελληνική Τράπεζα".to_owned();
+        bill.drawer.name = "Иван Петров".to_owned();
+        bill.city_of_issuing = "Łódź".to_owned();
+        let mut endorsement = get_baseline_endorsement();
+        endorsement.pay_to_the_order_of.name = "Čapek s.r.o.".to_owned();
+        let pdf = write_bill_pdf(&bill, &[endorsement], "hash").unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+        // the text is written with glyph ids of the embedded font, not with a built-in font
+        assert!(pdf.windows(10).any(|w| w == b"Identity-H"));
+        assert!(!pdf.windows(9).any(|w| w == b"Helvetica"));
+    }
+
+    #[test]
+    fn write_bill_pdf_with_endorsements_over_multiple_pages() {
+        let bill = get_baseline_bill_result("1234");
+        let endorsements: Vec<Endorsement> = (0..60).map(|_| get_baseline_endorsement()).collect();
+        let pdf = write_bill_pdf(&bill, &endorsements, "hash").unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn write_bill_pdf_fails_for_invalid_sum() {
+        let mut bill = get_baseline_bill_result("1234");
+        bill.sum = "invalid".to_owned();
+        assert!(write_bill_pdf(&bill, &[], "hash").is_err());
+    }
+}
@@ -1,27 +1,36 @@
 use super::super::notification_service::NotificationServiceApi;
+use super::bundle::{read_chain_bundle, write_chain_bundle};
 use super::error::Error;
+use super::export::write_export;
 use super::{BillAction, BillServiceApi, Result};
 use crate::blockchain::Blockchain;
 use crate::blockchain::bill::block::BillIdentityBlockData;
+use crate::blockchain::bill::bundle::BillChainVerification;
 use crate::blockchain::bill::{BillBlockchain, BillOpCode};
+use crate::constants::{DEFAULT_BILL_SEARCH_PAGE_SIZE, MAX_BILL_SEARCH_PAGE_SIZE};
 use crate::data::{
     File,
     bill::{
-        BillCombinedBitcoinKey, BillKeys, BillRole, BillsBalance, BillsBalanceOverview,
-        BillsFilterRole, BitcreditBill, BitcreditBillResult, Endorsement, LightBitcreditBillResult,
-        LightSignedBy, PastEndorsee,
+        BillBatchResult, BillCombinedBitcoinKey, BillExportFormat, BillKeys, BillRole,
+        BillSearchCursor, BillSearchQuery, BillSearchResult, BillStateFilter, BillsBalance,
+        BillsBalanceOverview, BitcreditBill, BitcreditBillResult, Endorsement, IssuedBill,
+        LightBitcreditBillResult, LightSignedBy, PastEndorsee,
+    },
+    contact::{
+        ContactType, ContactVerificationPolicy, IdentityPublicData, LightIdentityPublicData,
     },
-    contact::{ContactType, IdentityPublicData, LightIdentityPublicData},
     identity::Identity,
 };
 use crate::external::bitcoin::BitcoinClientApi;
 use crate::get_config;
 use crate::persistence::bill::BillChainStoreApi;
 use crate::persistence::bill::BillStoreApi;
+use crate::persistence::bill_batch::BillBatchStoreApi;
 use crate::persistence::company::{CompanyChainStoreApi, CompanyStoreApi};
 use crate::persistence::contact::ContactStoreApi;
 use crate::persistence::file_upload::FileUploadStoreApi;
 use crate::persistence::identity::{IdentityChainStoreApi, IdentityStoreApi};
+use crate::persistence::search_index::SearchIndexStoreApi;
 use crate::util::BcrKeys;
 use crate::{external, util};
 use async_trait::async_trait;
@@ -30,7 +39,7 @@ use bcr_ebill_core::constants::{
 };
 use bcr_ebill_core::notification::ActionType;
 use futures::future::try_join_all;
-use log::{error, info};
+use log::{error, info, warn};
 use std::collections::HashSet;
 use std::sync::Arc;
 
@@ -48,6 +57,9 @@ pub struct BillService {
     pub company_blockchain_store: Arc<dyn CompanyChainStoreApi>,
     pub contact_store: Arc<dyn ContactStoreApi>,
     pub company_store: Arc<dyn CompanyStoreApi>,
+    pub search_index_store: Arc<dyn SearchIndexStoreApi>,
+    pub bill_batch_store: Arc<dyn BillBatchStoreApi>,
+    pub contact_verification_policy: ContactVerificationPolicy,
 }
 
 impl BillService {
@@ -62,6 +74,9 @@ impl BillService {
         company_blockchain_store: Arc<dyn CompanyChainStoreApi>,
         contact_store: Arc<dyn ContactStoreApi>,
         company_store: Arc<dyn CompanyStoreApi>,
+        search_index_store: Arc<dyn SearchIndexStoreApi>,
+        bill_batch_store: Arc<dyn BillBatchStoreApi>,
+        contact_verification_policy: ContactVerificationPolicy,
     ) -> Self {
         Self {
             store,
@@ -74,6 +89,9 @@ impl BillService {
             company_blockchain_store,
             contact_store,
             company_store,
+            search_index_store,
+            bill_batch_store,
+            contact_verification_policy,
         }
     }
 
@@ -84,23 +102,21 @@ impl BillService {
         chain_identity: BillIdentityBlockData,
         identity: &Identity,
     ) -> IdentityPublicData {
-        let (email, nostr_relay) = match chain_identity.node_id {
-            ref v if *v == identity.node_id => {
-                (Some(identity.email.clone()), identity.nostr_relay.clone())
-            }
+        let (email, nostr_relays) = match chain_identity.node_id {
+            ref v if *v == identity.node_id => (
+                Some(identity.email.clone()),
+                identity.nostr_relay.clone().into_iter().collect(),
+            ),
             ref other_node_id => {
                 if let Ok(Some(contact)) = self.contact_store.get(other_node_id).await {
-                    (
-                        Some(contact.email.clone()),
-                        contact.nostr_relays.first().cloned(),
-                    )
+                    (Some(contact.email.clone()), contact.nostr_relays.clone())
                 } else if let Ok(company) = self.company_store.get(other_node_id).await {
                     (
                         Some(company.email.clone()),
-                        identity.nostr_relay.clone(), // if it's a local company, we take our relay
+                        identity.nostr_relay.clone().into_iter().collect(), // if it's a local company, we take our relay
                     )
                 } else {
-                    (None, None)
+                    (None, vec![])
                 }
             }
         };
@@ -110,7 +126,7 @@ impl BillService {
             name: chain_identity.name,
             postal_address: chain_identity.postal_address,
             email,
-            nostr_relay,
+            nostr_relays,
         }
     }
 
@@ -180,6 +196,115 @@ impl BillService {
         }
         Ok(())
     }
+
+    /// Executes the given bill action and returns the resulting chain, together with the
+    /// warnings of the contact verification policy for the counterparty the action names, i.e.
+    /// the endorsee, buyer, mint or recoursee
+    async fn execute_action(
+        &self,
+        bill_id: &str,
+        bill_action: BillAction,
+        signer_public_data: &IdentityPublicData,
+        signer_keys: &BcrKeys,
+        timestamp: u64,
+    ) -> Result<(BillBlockchain, Vec<String>)> {
+        // fetch data
+        let identity = self.identity_store.get_full().await?;
+        let mut blockchain = self.blockchain_store.get_chain(bill_id).await?;
+        let bill_keys = self.store.get_keys(bill_id).await?;
+        let bill = self
+            .get_last_version_bill(&blockchain, &bill_keys, &identity.identity)
+            .await?;
+
+        // validate
+        self.validate_bill_action(
+            &blockchain,
+            &bill,
+            &bill_keys,
+            timestamp,
+            &signer_public_data.node_id,
+            &bill_action,
+        )
+        .await?;
+        let warnings = match bill_action {
+            BillAction::Endorse(ref counterparty)
+            | BillAction::OfferToSell(ref counterparty, ..)
+            | BillAction::Sell(ref counterparty, ..)
+            | BillAction::Mint(ref counterparty, ..)
+            | BillAction::RequestRecourse(ref counterparty, _)
+            | BillAction::Recourse(ref counterparty, ..) => {
+                self.check_counterparty_verification(&[counterparty.node_id.clone()])
+                    .await?
+            }
+            _ => vec![],
+        };
+
+        // create and sign blocks
+        self.create_blocks_for_bill_action(
+            &bill,
+            &mut blockchain,
+            &bill_keys,
+            &bill_action,
+            signer_public_data,
+            signer_keys,
+            &identity,
+            timestamp,
+        )
+        .await?;
+
+        // notify
+        self.notify_for_block_action(&blockchain, &bill_keys, &bill_action, &identity.identity)
+            .await?;
+
+        // propagate
+        let self_clone = self.clone();
+        let latest_block = blockchain.get_latest_block().clone();
+        let bill_id_clone = bill_id.to_owned();
+        if let Err(e) = self_clone
+            .propagate_block(&bill_id_clone, &latest_block)
+            .await
+        {
+            error!("Error propagating block: {e}");
+        }
+
+        match bill_action {
+            BillAction::Endorse(endorsee) => {
+                if let Err(e) = self_clone
+                    .propagate_bill_for_node_id(&bill_id_clone, &endorsee.node_id)
+                    .await
+                {
+                    error!("Error propagating bill for node_id: {e}");
+                }
+            }
+            BillAction::Sell(buyer, _, _, _) => {
+                if let Err(e) = self_clone
+                    .propagate_bill_for_node_id(&bill_id_clone, &buyer.node_id)
+                    .await
+                {
+                    error!("Error propagating bill for node_id: {e}");
+                }
+            }
+            BillAction::Mint(mint, _, _) => {
+                if let Err(e) = self_clone
+                    .propagate_bill_for_node_id(&bill_id_clone, &mint.node_id)
+                    .await
+                {
+                    error!("Error propagating bill for node_id: {e}");
+                }
+            }
+            BillAction::Recourse(recoursee, _, _) => {
+                if let Err(e) = self_clone
+                    .propagate_bill_for_node_id(&bill_id_clone, &recoursee.node_id)
+                    .await
+                {
+                    error!("Error propagating bill for node_id: {e}");
+                }
+            }
+            _ => (),
+        };
+
+        Ok((blockchain, warnings))
+    }
 }
 
 #[async_trait]
@@ -189,22 +314,22 @@ impl BillServiceApi for BillService {
         _currency: &str,
         current_identity_node_id: &str,
     ) -> Result<BillsBalanceOverview> {
-        let bills = self.get_bills(current_identity_node_id).await?;
+        self.build_missing_bill_states().await?;
+        let states = self
+            .store
+            .search_bill_states(&BillStateFilter::for_identity(current_identity_node_id))
+            .await?;
 
         let mut payer_sum = 0;
         let mut payee_sum = 0;
         let mut contingent_sum = 0;
 
-        for bill in bills {
-            if let Ok(sum) = util::currency::parse_sum(&bill.sum) {
-                if let Some(bill_role) = bill.get_bill_role_for_node_id(current_identity_node_id) {
-                    match bill_role {
-                        BillRole::Payee => payee_sum += sum,
-                        BillRole::Payer => payer_sum += sum,
-                        BillRole::Contingent => contingent_sum += sum,
-                    };
-                }
-            }
+        for state in states {
+            match state.role {
+                BillRole::Payee => payee_sum += state.sum,
+                BillRole::Payer => payer_sum += state.sum,
+                BillRole::Contingent => contingent_sum += state.sum,
+            };
         }
 
         Ok(BillsBalanceOverview {
@@ -222,73 +347,41 @@ impl BillServiceApi for BillService {
 
     async fn search_bills(
         &self,
-        _currency: &str,
-        search_term: &Option<String>,
-        date_range_from: Option<u64>,
-        date_range_to: Option<u64>,
-        role: &BillsFilterRole,
+        query: &BillSearchQuery,
         current_identity_node_id: &str,
-    ) -> Result<Vec<LightBitcreditBillResult>> {
-        let bills = self.get_bills(current_identity_node_id).await?;
-        let mut result = vec![];
-
-        // for now we do the search here - with the quick-fetch table, we can search in surrealDB
-        // directly
-        for bill in bills {
-            // if the bill wasn't issued between from and to, we kick them out
-            if let Some(issue_date_ts) =
-                util::date::date_string_to_i64_timestamp(&bill.issue_date, None)
-            {
-                if let Some(from) = date_range_from {
-                    if from > issue_date_ts as u64 {
-                        continue;
-                    }
-                }
-                if let Some(to) = date_range_to {
-                    if to < issue_date_ts as u64 {
-                        continue;
-                    }
-                }
-            }
-
-            let bill_role = match bill.get_bill_role_for_node_id(current_identity_node_id) {
-                Some(bill_role) => bill_role,
-                None => continue, // node is not in bill - don't add
-            };
-
-            match role {
-                BillsFilterRole::All => (), // we take all
-                BillsFilterRole::Payer => {
-                    if bill_role != BillRole::Payer {
-                        // payer selected, but node not payer
-                        continue;
-                    }
-                }
-                BillsFilterRole::Payee => {
-                    if bill_role != BillRole::Payee {
-                        // payee selected, but node not payee
-                        continue;
-                    }
-                }
-                BillsFilterRole::Contingent => {
-                    if bill_role != BillRole::Contingent {
-                        // contingent selected, but node not
-                        // contingent
-                        continue;
-                    }
-                }
-            };
-
-            if let Some(st) = search_term {
-                if !bill.search_bill_for_search_term(st) {
-                    continue;
-                }
-            }
-
-            result.push(bill.into());
+    ) -> Result<BillSearchResult> {
+        if query
+            .cursor
+            .as_ref()
+            .is_some_and(|c| !c.is_for(query.sort, query.direction))
+        {
+            return Err(Error::SearchCursorSortMismatch);
         }
-
-        Ok(result)
+        self.build_missing_bill_states().await?;
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_BILL_SEARCH_PAGE_SIZE)
+            .clamp(1, MAX_BILL_SEARCH_PAGE_SIZE);
+        let mut filter = BillStateFilter {
+            identity_node_id: current_identity_node_id.to_owned(),
+            timestamp: util::date::now().timestamp() as u64,
+            query: query.clone(),
+        };
+        // fetch one more, to know if there is a next page
+        filter.query.limit = Some(limit + 1);
+        let mut states = self.store.search_bill_states(&filter).await?;
+        let next_cursor = if states.len() > limit {
+            states.truncate(limit);
+            states
+                .last()
+                .map(|s| BillSearchCursor::for_state(s, query.sort, query.direction).encode())
+        } else {
+            None
+        };
+        Ok(BillSearchResult {
+            bills: self.get_light_bills_for_states(states).await,
+            next_cursor,
+        })
     }
 
     async fn get_bills_from_all_identities(&self) -> Result<Vec<BitcreditBillResult>> {
@@ -313,16 +406,39 @@ impl BillServiceApi for BillService {
         Ok(bills)
     }
 
+    async fn export_bills(
+        &self,
+        query: &BillSearchQuery,
+        format: BillExportFormat,
+        current_identity_node_id: &str,
+    ) -> Result<Vec<u8>> {
+        let identity = self.identity_store.get().await?;
+        let rows = self
+            .get_bills_for_export(
+                query,
+                &identity,
+                current_identity_node_id,
+                util::date::now().timestamp() as u64,
+            )
+            .await?;
+        write_export(&rows, format)
+    }
+
     async fn get_bills(&self, current_identity_node_id: &str) -> Result<Vec<BitcreditBillResult>> {
-        let bill_ids = self.store.get_ids().await?;
+        self.build_missing_bill_states().await?;
+        // the bill states only exist for bills the identity is a participant of
+        let states = self
+            .store
+            .search_bill_states(&BillStateFilter::for_identity(current_identity_node_id))
+            .await?;
         let identity = self.identity_store.get().await?;
         let current_timestamp = util::date::now().timestamp() as u64;
 
-        let tasks = bill_ids.iter().map(|id| {
+        let tasks = states.iter().map(|state| {
             let identity_clone = identity.clone();
             async move {
                 self.get_full_bill(
-                    id,
+                    &state.bill_id,
                     &identity_clone,
                     current_identity_node_id,
                     current_timestamp,
@@ -332,14 +448,23 @@ impl BillServiceApi for BillService {
         });
         let bills = try_join_all(tasks).await?;
 
-        Ok(bills
-            .into_iter()
-            .filter(|b| {
-                b.bill_participants
-                    .iter()
-                    .any(|p| p == current_identity_node_id)
-            })
-            .collect())
+        Ok(bills)
+    }
+
+    async fn get_light_bills(
+        &self,
+        current_identity_node_id: &str,
+    ) -> Result<Vec<LightBitcreditBillResult>> {
+        self.build_missing_bill_states().await?;
+        let states = self
+            .store
+            .search_bill_states(&BillStateFilter::for_identity(current_identity_node_id))
+            .await?;
+        Ok(self.get_light_bills_for_states(states).await)
+    }
+
+    async fn rebuild_search_index(&self) -> Result<()> {
+        self.index_all_bills().await
     }
 
     async fn get_combined_bitcoin_key_for_bill(
@@ -398,6 +523,45 @@ impl BillServiceApi for BillService {
         Ok(res)
     }
 
+    async fn get_bill_pdf(
+        &self,
+        bill_id: &str,
+        local_identity: &Identity,
+        current_identity_node_id: &str,
+        current_timestamp: u64,
+    ) -> Result<Vec<u8>> {
+        self.render_bill_pdf(
+            bill_id,
+            local_identity,
+            current_identity_node_id,
+            current_timestamp,
+        )
+        .await
+    }
+
+    async fn export_chain_bundle(
+        &self,
+        bill_id: &str,
+        disclosed_block_ids: Option<Vec<u64>>,
+        current_identity_node_id: &str,
+        current_timestamp: u64,
+    ) -> Result<Vec<u8>> {
+        let bundle = self
+            .create_chain_bundle(
+                bill_id,
+                disclosed_block_ids.as_deref(),
+                current_identity_node_id,
+                current_timestamp,
+            )
+            .await?;
+        write_chain_bundle(&bundle)
+    }
+
+    fn verify_chain_bundle(&self, bundle: &[u8]) -> Result<BillChainVerification> {
+        let bundle = read_chain_bundle(bundle)?;
+        Ok(bundle.verify())
+    }
+
     async fn get_bill(&self, bill_id: &str) -> Result<BitcreditBill> {
         let chain = self.blockchain_store.get_chain(bill_id).await?;
         let bill_keys = self.store.get_keys(bill_id).await?;
@@ -466,20 +630,47 @@ impl BillServiceApi for BillService {
         drawer_public_data: IdentityPublicData,
         drawer_keys: BcrKeys,
         timestamp: u64,
-    ) -> Result<BitcreditBill> {
-        self.issue_bill(
-            country_of_issuing,
-            city_of_issuing,
-            issue_date,
-            maturity_date,
-            drawee,
-            payee,
-            sum,
-            currency,
-            country_of_payment,
-            city_of_payment,
-            language,
+    ) -> Result<IssuedBill> {
+        let warnings = self
+            .check_counterparty_verification(&[drawee.node_id.clone(), payee.node_id.clone()])
+            .await?;
+        let bill = self
+            .issue_bill(
+                country_of_issuing,
+                city_of_issuing,
+                issue_date,
+                maturity_date,
+                drawee,
+                payee,
+                sum,
+                currency,
+                country_of_payment,
+                city_of_payment,
+                language,
+                file_upload_id,
+                drawer_public_data,
+                drawer_keys,
+                timestamp,
+            )
+            .await?;
+        Ok(IssuedBill { bill, warnings })
+    }
+
+    async fn issue_bill_batch(
+        &self,
+        file_upload_id: &str,
+        batch_id: Option<&str>,
+        dry_run: bool,
+        all_or_nothing: bool,
+        drawer_public_data: &IdentityPublicData,
+        drawer_keys: &BcrKeys,
+        timestamp: u64,
+    ) -> Result<BillBatchResult> {
+        self.issue_batch(
             file_upload_id,
+            batch_id,
+            dry_run,
+            all_or_nothing,
             drawer_public_data,
             drawer_keys,
             timestamp,
@@ -495,90 +686,67 @@ impl BillServiceApi for BillService {
         signer_keys: &BcrKeys,
         timestamp: u64,
     ) -> Result<BillBlockchain> {
-        // fetch data
-        let identity = self.identity_store.get_full().await?;
-        let mut blockchain = self.blockchain_store.get_chain(bill_id).await?;
-        let bill_keys = self.store.get_keys(bill_id).await?;
-        let bill = self
-            .get_last_version_bill(&blockchain, &bill_keys, &identity.identity)
+        let (blockchain, warnings) = self
+            .execute_action(
+                bill_id,
+                bill_action,
+                signer_public_data,
+                signer_keys,
+                timestamp,
+            )
             .await?;
+        for warning in warnings {
+            warn!("Executed action on bill {bill_id}: {warning}");
+        }
+        Ok(blockchain)
+    }
 
-        // validate
-        self.validate_bill_action(
-            &blockchain,
-            &bill,
-            &bill_keys,
-            timestamp,
-            &signer_public_data.node_id,
-            &bill_action,
-        )
-        .await?;
-
-        // create and sign blocks
-        self.create_blocks_for_bill_action(
-            &bill,
-            &mut blockchain,
-            &bill_keys,
-            &bill_action,
-            signer_public_data,
-            signer_keys,
-            &identity,
-            timestamp,
-        )
-        .await?;
-
-        // notify
-        self.notify_for_block_action(&blockchain, &bill_keys, &bill_action, &identity.identity)
+    async fn endorse_bill(
+        &self,
+        bill_id: &str,
+        endorsee: IdentityPublicData,
+        signer_public_data: &IdentityPublicData,
+        signer_keys: &BcrKeys,
+        timestamp: u64,
+    ) -> Result<Vec<String>> {
+        let (_, warnings) = self
+            .execute_action(
+                bill_id,
+                BillAction::Endorse(endorsee),
+                signer_public_data,
+                signer_keys,
+                timestamp,
+            )
             .await?;
+        Ok(warnings)
+    }
 
-        // propagate
-        let self_clone = self.clone();
-        let latest_block = blockchain.get_latest_block().clone();
-        let bill_id_clone = bill_id.to_owned();
-        if let Err(e) = self_clone
-            .propagate_block(&bill_id_clone, &latest_block)
-            .await
-        {
-            error!("Error propagating block: {e}");
+    async fn check_counterparty_verification(&self, node_ids: &[String]) -> Result<Vec<String>> {
+        if self.contact_verification_policy == ContactVerificationPolicy::Off {
+            return Ok(vec![]);
         }
 
-        match bill_action {
-            BillAction::Endorse(endorsee) => {
-                if let Err(e) = self_clone
-                    .propagate_bill_for_node_id(&bill_id_clone, &endorsee.node_id)
-                    .await
-                {
-                    error!("Error propagating bill for node_id: {e}");
-                }
-            }
-            BillAction::Sell(buyer, _, _, _) => {
-                if let Err(e) = self_clone
-                    .propagate_bill_for_node_id(&bill_id_clone, &buyer.node_id)
-                    .await
-                {
-                    error!("Error propagating bill for node_id: {e}");
-                }
+        let identity = self.identity_store.get().await?;
+        let companies = self.company_store.get_all().await?;
+        let mut warnings = vec![];
+        for node_id in node_ids {
+            if *node_id == identity.node_id || companies.contains_key(node_id) {
+                continue;
             }
-            BillAction::Mint(mint, _, _) => {
-                if let Err(e) = self_clone
-                    .propagate_bill_for_node_id(&bill_id_clone, &mint.node_id)
-                    .await
-                {
-                    error!("Error propagating bill for node_id: {e}");
-                }
+            let verified = self
+                .contact_store
+                .get(node_id)
+                .await?
+                .is_some_and(|contact| contact.is_verified());
+            if verified {
+                continue;
             }
-            BillAction::Recourse(recoursee, _, _) => {
-                if let Err(e) = self_clone
-                    .propagate_bill_for_node_id(&bill_id_clone, &recoursee.node_id)
-                    .await
-                {
-                    error!("Error propagating bill for node_id: {e}");
-                }
+            if self.contact_verification_policy == ContactVerificationPolicy::Refuse {
+                return Err(Error::CounterpartyNotVerified(node_id.to_owned()));
             }
-            _ => (),
-        };
-
-        Ok(blockchain)
+            warnings.push(format!("Counterparty {node_id} is not a verified contact"));
+        }
+        Ok(warnings)
     }
 
     async fn check_bills_payment(&self) -> Result<()> {
@@ -0,0 +1,233 @@
+use super::Result;
+use super::service::BillService;
+use crate::service::search_service::update_search_index;
+use crate::util;
+use bcr_ebill_core::{
+    bill::{BillRole, BillState, LightBitcreditBillResult},
+    blockchain::{
+        Blockchain,
+        bill::{
+            BillBlockchain, BillOpCode, OfferToSellWaitingForPayment, RecourseWaitingForPayment,
+        },
+    },
+    constants::{PAYMENT_DEADLINE_SECONDS, RECOURSE_DEADLINE_SECONDS},
+    search::SearchDocument,
+};
+use log::{error, info};
+use std::collections::{HashMap, HashSet};
+
+impl BillService {
+    /// Computes the materialised state of the given bill for every local identity (personal
+    /// and companies), which is a participant of the bill
+    pub(super) async fn get_bill_states(
+        &self,
+        bill_id: &str,
+        chain: &BillBlockchain,
+    ) -> Result<Vec<BillState>> {
+        let bill_keys = self.store.get_keys(bill_id).await?;
+        let identity = self.identity_store.get().await?;
+        let bill = self
+            .get_last_version_bill(chain, &bill_keys, &identity)
+            .await?;
+        let first_version_bill = chain.get_first_version_bill(&bill_keys)?;
+        let participants = chain.get_all_nodes_from_bill(&bill_keys)?;
+        let local_node_ids = self.get_local_node_ids().await?;
+
+        // the waiting states are computed as of the latest block and stored with their deadline,
+        // so they expire without having to recompute the state
+        let latest_block = chain.get_latest_block();
+        let (waiting_for_payment_until, buyer, seller) = match chain
+            .is_last_offer_to_sell_block_waiting_for_payment(&bill_keys, latest_block.timestamp)?
+        {
+            OfferToSellWaitingForPayment::Yes(payment_info) => (
+                Some(latest_block.timestamp + PAYMENT_DEADLINE_SECONDS),
+                Some(payment_info.buyer.into()),
+                Some(payment_info.seller.into()),
+            ),
+            OfferToSellWaitingForPayment::No => (None, None, None),
+        };
+        let in_recourse_until = match chain.is_last_request_to_recourse_block_waiting_for_payment(
+            &bill_keys,
+            latest_block.timestamp,
+        )? {
+            RecourseWaitingForPayment::Yes(_) => {
+                Some(latest_block.timestamp + RECOURSE_DEADLINE_SECONDS)
+            }
+            RecourseWaitingForPayment::No => None,
+        };
+        let accepted = chain.block_with_operation_code_exists(BillOpCode::Accept);
+        let paid = if chain.block_with_operation_code_exists(BillOpCode::RequestToPay) {
+            self.store.is_paid(bill_id).await?
+        } else {
+            false
+        };
+        let holder_node_id = match bill.endorsee {
+            None => bill.payee.node_id.clone(),
+            Some(ref endorsee) => endorsee.node_id.clone(),
+        };
+
+        let mut states = vec![];
+        for node_id in local_node_ids
+            .into_iter()
+            .filter(|n| participants.contains(n))
+        {
+            let role = if bill.drawee.node_id == node_id {
+                BillRole::Payer
+            } else if bill.payee.node_id == node_id || holder_node_id == node_id {
+                BillRole::Payee
+            } else {
+                // part of the bill, but neither payer, nor payee - part of the risk chain
+                BillRole::Contingent
+            };
+            states.push(BillState {
+                bill_id: bill_id.to_owned(),
+                identity_node_id: node_id,
+                role,
+                drawee: bill.drawee.clone().into(),
+                drawer: bill.drawer.clone().into(),
+                payee: bill.payee.clone().into(),
+                endorsee: bill.endorsee.clone().map(|e| e.into()),
+                holder_node_id: holder_node_id.clone(),
+                buyer: buyer.clone(),
+                seller: seller.clone(),
+                sum: bill.sum,
+                currency: bill.currency.clone(),
+                issue_date: bill.issue_date.clone(),
+                issue_timestamp: util::date::date_string_to_i64_timestamp(&bill.issue_date, None)
+                    .map(|ts| ts as u64),
+                time_of_drawing: first_version_bill.signing_timestamp,
+                time_of_maturity: util::date::date_string_to_i64_timestamp(
+                    &bill.maturity_date,
+                    None,
+                )
+                .unwrap_or(0) as u64,
+                accepted,
+                paid,
+                waiting_for_payment_until,
+                in_recourse_until,
+                has_attachments: !bill.files.is_empty(),
+                participants: participants.clone(),
+                block_height: chain.block_height() as u64,
+            });
+        }
+        Ok(states)
+    }
+
+    /// Recomputes and persists the state of the given bill after a block was added. Since the
+    /// block is already persisted at this point, failures are only logged and the stale state
+    /// is removed, so it's rebuilt the next time bills are listed
+    pub(super) async fn update_bill_state(&self, bill_id: &str, chain: &BillBlockchain) {
+        let result: Result<()> = match self.get_bill_states(bill_id, chain).await {
+            Ok(states) => self
+                .store
+                .save_bill_states(bill_id, &states)
+                .await
+                .map_err(|e| e.into()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!("Could not update the state of bill {bill_id}: {e}");
+            if let Err(e) = self.store.remove_bill_states(bill_id).await {
+                error!("Could not remove the stale state of bill {bill_id}: {e}");
+            }
+        }
+        match self.get_search_document(bill_id, chain).await {
+            Ok(document) => update_search_index(self.search_index_store.as_ref(), &document).await,
+            Err(e) => error!("Could not create the search document of bill {bill_id}: {e}"),
+        }
+    }
+
+    /// Creates the search document of the given bill from its latest version
+    async fn get_search_document(
+        &self,
+        bill_id: &str,
+        chain: &BillBlockchain,
+    ) -> Result<SearchDocument> {
+        let bill_keys = self.store.get_keys(bill_id).await?;
+        let identity = self.identity_store.get().await?;
+        let bill = self
+            .get_last_version_bill(chain, &bill_keys, &identity)
+            .await?;
+        let participants = chain.get_all_nodes_from_bill(&bill_keys)?;
+        Ok(SearchDocument::for_bill(&bill, &participants))
+    }
+
+    /// Adds all bills to the search index
+    pub(super) async fn index_all_bills(&self) -> Result<()> {
+        for bill_id in self.store.get_ids().await? {
+            let chain = self.blockchain_store.get_chain(&bill_id).await?;
+            let document = self.get_search_document(&bill_id, &chain).await?;
+            self.search_index_store.index_document(&document).await?;
+        }
+        Ok(())
+    }
+
+    /// The node ids of the personal identity and of all local companies
+    async fn get_local_node_ids(&self) -> Result<HashSet<String>> {
+        let mut local_node_ids = HashSet::from([self.identity_store.get().await?.node_id]);
+        local_node_ids.extend(self.company_store.get_all().await?.into_keys());
+        Ok(local_node_ids)
+    }
+
+    /// Builds the states of all bills, which are missing a state for one of the local identities
+    /// participating in them, e.g. after an update, a restored backup, or after a company, which
+    /// is part of existing bills, was added. States of identities, which are no longer local,
+    /// are rebuilt as well
+    pub(super) async fn build_missing_bill_states(&self) -> Result<()> {
+        let local_node_ids = self.get_local_node_ids().await?;
+        // the local identities each bill has a state for and the participants of the bill
+        let mut built: HashMap<String, (HashSet<String>, HashSet<String>)> = HashMap::new();
+        for key in self.store.get_bill_state_keys().await? {
+            let (identities, participants) = built.entry(key.bill_id).or_default();
+            identities.insert(key.identity_node_id);
+            participants.extend(key.participants);
+        }
+        for bill_id in self.store.get_ids().await? {
+            let up_to_date = built
+                .get(&bill_id)
+                .is_some_and(|(identities, participants)| {
+                    identities.iter().all(|n| local_node_ids.contains(n))
+                        && local_node_ids
+                            .iter()
+                            .filter(|n| participants.contains(*n))
+                            .all(|n| identities.contains(n))
+                });
+            if up_to_date {
+                continue;
+            }
+            info!("Building the state of bill {bill_id} from its chain");
+            let chain = self.blockchain_store.get_chain(&bill_id).await?;
+            let states = self.get_bill_states(&bill_id, &chain).await?;
+            self.store.save_bill_states(&bill_id, &states).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the light bills for the given bill states, with their active notifications
+    pub(super) async fn get_light_bills_for_states(
+        &self,
+        states: Vec<BillState>,
+    ) -> Vec<LightBitcreditBillResult> {
+        let mut result = Vec::with_capacity(states.len());
+        for state in states {
+            let active_notification = self
+                .notification_service
+                .get_active_bill_notification(&state.bill_id)
+                .await;
+            result.push(LightBitcreditBillResult {
+                id: state.bill_id,
+                drawee: state.drawee,
+                drawer: state.drawer,
+                payee: state.payee,
+                endorsee: state.endorsee,
+                active_notification,
+                sum: util::currency::sum_to_string(state.sum),
+                currency: state.currency,
+                issue_date: state.issue_date,
+                time_of_drawing: state.time_of_drawing,
+                time_of_maturity: state.time_of_maturity,
+            });
+        }
+        result
+    }
+}
@@ -8,14 +8,16 @@ use crate::{
         notification_service::MockNotificationServiceApi,
     },
     tests::tests::{
-        MockBillChainStoreApiMock, MockBillStoreApiMock, MockCompanyChainStoreApiMock,
-        MockCompanyStoreApiMock, MockContactStoreApiMock, MockFileUploadStoreApiMock,
-        MockIdentityChainStoreApiMock, MockIdentityStoreApiMock, TEST_PRIVATE_KEY_SECP,
-        TEST_PUB_KEY_SECP, empty_address, empty_bitcredit_bill, empty_identity,
-        empty_identity_public_data, identity_public_data_only_node_id,
+        MockBillBatchStoreApiMock, MockBillChainStoreApiMock, MockBillStoreApiMock,
+        MockCompanyChainStoreApiMock, MockCompanyStoreApiMock, MockContactStoreApiMock,
+        MockFileUploadStoreApiMock, MockIdentityChainStoreApiMock, MockIdentityStoreApiMock,
+        MockSearchIndexStoreApiMock, TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP, empty_address,
+        empty_bitcredit_bill, empty_identity, empty_identity_public_data,
+        identity_public_data_only_node_id,
     },
     util,
 };
+use bcr_ebill_core::bill::{BillState, BillStateFilter, BillStateKey, LightSignedBy};
 use bcr_ebill_core::blockchain::{
     Blockchain,
     bill::{
@@ -27,10 +29,15 @@ use bcr_ebill_core::blockchain::{
     },
     identity::IdentityBlockchain,
 };
+use bcr_ebill_core::contact::{
+    ContactType, ContactVerificationPolicy, LightIdentityPublicData,
+    LightIdentityPublicDataWithAddress,
+};
 use core::str;
 use external::bitcoin::MockBitcoinClientApi;
 use service::BillService;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use util::crypto::BcrKeys;
 
 pub struct MockBillContext {
@@ -43,6 +50,8 @@ pub struct MockBillContext {
     pub company_store: MockCompanyStoreApiMock,
     pub file_upload_store: MockFileUploadStoreApiMock,
     pub notification_service: MockNotificationServiceApi,
+    pub search_index_store: MockSearchIndexStoreApiMock,
+    pub bill_batch_store: MockBillBatchStoreApiMock,
 }
 
 pub fn get_baseline_identity() -> IdentityWithAll {
@@ -72,6 +81,68 @@ pub fn get_baseline_bill(bill_id: &str) -> BitcreditBill {
     bill
 }
 
+pub fn get_baseline_bill_result(bill_id: &str) -> BitcreditBillResult {
+    BitcreditBillResult {
+        id: bill_id.to_owned(),
+        time_of_drawing: 1731593928,
+        time_of_maturity: 0,
+        country_of_issuing: "AT".to_owned(),
+        city_of_issuing: "Vienna".to_owned(),
+        drawee: empty_identity_public_data(),
+        drawer: empty_identity_public_data(),
+        payee: empty_identity_public_data(),
+        endorsee: None,
+        currency: "sat".to_owned(),
+        sum: "500".to_owned(),
+        maturity_date: "2099-11-12".to_owned(),
+        issue_date: "2024-11-14".to_owned(),
+        country_of_payment: "AT".to_owned(),
+        city_of_payment: "Vienna".to_owned(),
+        language: "en".to_owned(),
+        accepted: true,
+        endorsed: true,
+        requested_to_pay: false,
+        requested_to_accept: true,
+        paid: false,
+        waiting_for_payment: false,
+        buyer: None,
+        seller: None,
+        in_recourse: false,
+        recourser: None,
+        recoursee: None,
+        link_for_buy: "".to_owned(),
+        link_to_pay: "".to_owned(),
+        link_to_pay_recourse: "".to_owned(),
+        address_to_pay: "".to_owned(),
+        mempool_link_for_address_to_pay: "".to_owned(),
+        files: vec![],
+        active_notification: None,
+        bill_participants: vec![],
+        endorsements_count: 1,
+    }
+}
+
+pub fn get_baseline_endorsement() -> Endorsement {
+    Endorsement {
+        pay_to_the_order_of: LightIdentityPublicDataWithAddress {
+            t: ContactType::Person,
+            name: "endorsee".to_owned(),
+            node_id: "endorsee_node_id".to_owned(),
+            postal_address: empty_address(),
+        },
+        signed: LightSignedBy {
+            data: LightIdentityPublicData {
+                t: ContactType::Person,
+                name: "payee".to_owned(),
+                node_id: "payee".to_owned(),
+            },
+            signatory: None,
+        },
+        signing_timestamp: 1731593930,
+        signing_address: empty_address(),
+    }
+}
+
 pub fn get_genesis_chain(bill: Option<BitcreditBill>) -> BillBlockchain {
     let bill = bill.unwrap_or(get_baseline_bill("some id"));
     BillBlockchain::new(
@@ -129,6 +200,22 @@ pub fn get_service(mut ctx: MockBillContext) -> BillService {
     ctx.bill_blockchain_store
         .expect_add_block()
         .returning(|_, _| Ok(()));
+    ctx.company_store
+        .expect_get_all()
+        .returning(|| Ok(HashMap::new()));
+    ctx.bill_store.expect_is_paid().returning(|_| Ok(false));
+    ctx.bill_store
+        .expect_save_bill_states()
+        .returning(|_, _| Ok(()));
+    ctx.bill_store
+        .expect_remove_bill_states()
+        .returning(|_| Ok(()));
+    ctx.bill_store
+        .expect_get_bill_state_keys()
+        .returning(|| Ok(vec![]));
+    ctx.bill_store
+        .expect_search_bill_states()
+        .returning(|_| Ok(vec![]));
     ctx.bill_store.expect_get_keys().returning(|_| {
         Ok(BillKeys {
             private_key: TEST_PRIVATE_KEY_SECP.to_owned(),
@@ -141,6 +228,9 @@ pub fn get_service(mut ctx: MockBillContext) -> BillService {
     ctx.identity_store
         .expect_get_full()
         .returning(|| Ok(get_baseline_identity()));
+    ctx.search_index_store
+        .expect_index_document()
+        .returning(|_| Ok(()));
     BillService::new(
         Arc::new(ctx.bill_store),
         Arc::new(ctx.bill_blockchain_store),
@@ -152,9 +242,57 @@ pub fn get_service(mut ctx: MockBillContext) -> BillService {
         Arc::new(ctx.company_chain_store),
         Arc::new(ctx.contact_store),
         Arc::new(ctx.company_store),
+        Arc::new(ctx.search_index_store),
+        Arc::new(ctx.bill_batch_store),
+        ContactVerificationPolicy::Off,
     )
 }
 
+/// Keeps the bill states saved through the mocked store in memory and returns all states of
+/// the searched identity, so the state computation can be tested through the listing functions.
+/// The search criteria are applied by the store and tested there
+pub fn mock_bill_states(bill_store: &mut MockBillStoreApiMock) {
+    let states: Arc<Mutex<HashMap<String, Vec<BillState>>>> = Arc::new(Mutex::new(HashMap::new()));
+    let saved = states.clone();
+    bill_store
+        .expect_save_bill_states()
+        .returning(move |id, new_states| {
+            saved
+                .lock()
+                .unwrap()
+                .insert(id.to_owned(), new_states.to_vec());
+            Ok(())
+        });
+    let keys = states.clone();
+    bill_store.expect_get_bill_state_keys().returning(move || {
+        Ok(keys
+            .lock()
+            .unwrap()
+            .values()
+            .flatten()
+            .map(|s| BillStateKey {
+                bill_id: s.bill_id.clone(),
+                identity_node_id: s.identity_node_id.clone(),
+                participants: s.participants.clone(),
+            })
+            .collect())
+    });
+    bill_store
+        .expect_search_bill_states()
+        .returning(move |filter: &BillStateFilter| {
+            let mut result: Vec<BillState> = states
+                .lock()
+                .unwrap()
+                .values()
+                .flatten()
+                .filter(|s| s.identity_node_id == filter.identity_node_id)
+                .cloned()
+                .collect();
+            result.sort_by(|a, b| a.bill_id.cmp(&b.bill_id));
+            Ok(result)
+        });
+}
+
 pub fn get_ctx() -> MockBillContext {
     MockBillContext {
         bill_store: MockBillStoreApiMock::new(),
@@ -166,6 +304,8 @@ pub fn get_ctx() -> MockBillContext {
         contact_store: MockContactStoreApiMock::new(),
         company_store: MockCompanyStoreApiMock::new(),
         notification_service: MockNotificationServiceApi::new(),
+        search_index_store: MockSearchIndexStoreApiMock::new(),
+        bill_batch_store: MockBillBatchStoreApiMock::new(),
     }
 }
 
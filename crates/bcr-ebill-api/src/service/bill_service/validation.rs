@@ -1,4 +1,5 @@
 use super::{BillAction, Result, error::Error, service::BillService};
+use crate::util;
 use bcr_ebill_core::{
     bill::{BillKeys, BitcreditBill, RecourseReason},
     blockchain::{
@@ -11,6 +12,65 @@ use bcr_ebill_core::{
     constants::{ACCEPT_DEADLINE_SECONDS, PAYMENT_DEADLINE_SECONDS, RECOURSE_DEADLINE_SECONDS},
 };
 
+/// The fields of a bill to issue, which passed [validate_bill_to_issue]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BillToIssue {
+    pub sum: u64,
+    /// The node id of the drawee, None if the drawer is the drawee
+    pub drawee: Option<String>,
+    /// The node id of the payee, None if the drawer is the payee
+    pub payee: Option<String>,
+}
+
+/// Validates the fields of a bill to issue, which can be checked without looking up its
+/// counterparties, and returns all reasons it can't be issued. Single bills and the rows of a
+/// batch are validated with it, so they follow the same rules
+pub fn validate_bill_to_issue(
+    t: u64,
+    sum: &str,
+    issue_date: &str,
+    maturity_date: &str,
+    drawee: &str,
+    payee: &str,
+    drawer_node_id: &str,
+) -> std::result::Result<BillToIssue, Vec<String>> {
+    let mut errors = vec![];
+    let sum = match sum.parse::<u64>() {
+        Ok(sum) => Some(sum),
+        Err(_) => {
+            errors.push(format!("invalid sum: {sum}"));
+            None
+        }
+    };
+    if util::date::date_string_to_i64_timestamp(issue_date, None).is_none() {
+        errors.push(String::from("invalid issue date"));
+    }
+    if util::date::date_string_to_i64_timestamp(maturity_date, None).is_none() {
+        errors.push(String::from("invalid maturity date"));
+    }
+
+    let (drawee, payee) = match t {
+        // Drawer is drawee
+        0 => (None, Some(payee.to_owned())),
+        // Drawer is payee
+        1 => (Some(drawee.to_owned()), None),
+        // Drawer is neither drawee nor payee
+        2 => (Some(drawee.to_owned()), Some(payee.to_owned())),
+        t => {
+            errors.push(format!("Invalid bill type found: {t}"));
+            return Err(errors);
+        }
+    };
+    if drawee.as_deref().unwrap_or(drawer_node_id) == payee.as_deref().unwrap_or(drawer_node_id) {
+        errors.push(String::from("Drawee can't be Payee at the same time"));
+    }
+
+    match sum {
+        Some(sum) if errors.is_empty() => Ok(BillToIssue { sum, drawee, payee }),
+        _ => Err(errors),
+    }
+}
+
 impl BillService {
     pub(super) async fn validate_bill_action(
         &self,
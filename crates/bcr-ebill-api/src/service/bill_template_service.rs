@@ -0,0 +1,677 @@
+use super::bill_service::BillServiceApi;
+use super::{Error, Result};
+use crate::data::{
+    bill::IssuedBill,
+    bill_template::{BillTemplate, BillTemplateIssue},
+    contact::IdentityPublicData,
+};
+use crate::persistence::{
+    bill_template::BillTemplateStoreApi, company::CompanyStoreApi, contact::ContactStoreApi,
+    identity::IdentityStoreApi,
+};
+use crate::util::{self, BcrKeys};
+use async_trait::async_trait;
+use log::{error, info};
+use std::sync::Arc;
+
+#[cfg(test)]
+use mockall::automock;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait BillTemplateServiceApi: Send + Sync {
+    /// Validates and stores the given template. Its id and creation time are assigned here and
+    /// its schedule starts without any occurrences done
+    async fn create_template(&self, template: BillTemplate, timestamp: u64)
+    -> Result<BillTemplate>;
+
+    /// Validates and stores the given template in place of the one with the same id. The
+    /// occurrences done are kept, unless the interval or the start date of the schedule changed
+    async fn update_template(&self, template: BillTemplate) -> Result<BillTemplate>;
+
+    /// Returns the template with the given id
+    async fn get_template(&self, id: &str) -> Result<BillTemplate>;
+
+    /// Returns all templates
+    async fn list_templates(&self) -> Result<Vec<BillTemplate>>;
+
+    /// Removes the template with the given id, bills issued from it stay untouched
+    async fn remove_template(&self, id: &str) -> Result<()>;
+
+    /// Issues a bill from the given template. The issue date defaults to the date of the given
+    /// timestamp and the sum to the one of the template
+    async fn issue_bill_from_template(
+        &self,
+        template_id: &str,
+        sum: Option<u64>,
+        issue_date: Option<String>,
+        file_upload_id: Option<String>,
+        timestamp: u64,
+    ) -> Result<IssuedBill>;
+
+    /// Returns the bills issued from the given template, newest first
+    async fn list_issued_bills(&self, template_id: &str) -> Result<Vec<BillTemplateIssue>>;
+
+    /// Returns the template issue of the given bill, if it was issued from a template
+    async fn get_template_issue_for_bill(&self, bill_id: &str)
+    -> Result<Option<BillTemplateIssue>>;
+
+    /// Issues a bill for every template with a schedule, that is due at the given timestamp
+    async fn issue_due_bills(&self, timestamp: u64) -> Result<()>;
+}
+
+/// The bill template service issues bills from stored templates, on demand or on their schedule
+#[derive(Clone)]
+pub struct BillTemplateService {
+    store: Arc<dyn BillTemplateStoreApi>,
+    bill_service: Arc<dyn BillServiceApi>,
+    identity_store: Arc<dyn IdentityStoreApi>,
+    company_store: Arc<dyn CompanyStoreApi>,
+    contact_store: Arc<dyn ContactStoreApi>,
+}
+
+impl BillTemplateService {
+    pub fn new(
+        store: Arc<dyn BillTemplateStoreApi>,
+        bill_service: Arc<dyn BillServiceApi>,
+        identity_store: Arc<dyn IdentityStoreApi>,
+        company_store: Arc<dyn CompanyStoreApi>,
+        contact_store: Arc<dyn ContactStoreApi>,
+    ) -> Self {
+        Self {
+            store,
+            bill_service,
+            identity_store,
+            company_store,
+            contact_store,
+        }
+    }
+
+    async fn validate(&self, template: &BillTemplate) -> Result<()> {
+        if template.name.trim().is_empty() {
+            return Err(Error::Validation(String::from(
+                "The name of a template can't be empty",
+            )));
+        }
+        if template.drawee_node_id == template.payee_node_id {
+            return Err(Error::Validation(String::from(
+                "Drawee can't be Payee at the same time",
+            )));
+        }
+        if template.sum == 0 {
+            return Err(Error::Validation(String::from(
+                "The sum has to be positive",
+            )));
+        }
+        if template.term_days == 0 {
+            return Err(Error::Validation(String::from(
+                "The term has to be at least one day",
+            )));
+        }
+        if let Some(ref schedule) = template.schedule {
+            if schedule.occurrence_date(0).is_none() {
+                return Err(Error::Validation(String::from(
+                    "invalid start date of the schedule",
+                )));
+            }
+            if let Some(ref end_date) = schedule.end_date {
+                if util::date::date_string_to_i64_timestamp(end_date, None).is_none() {
+                    return Err(Error::Validation(String::from(
+                        "invalid end date of the schedule",
+                    )));
+                }
+                // both dates are validated to be in the same format, so they sort as strings
+                if end_date < &schedule.start_date {
+                    return Err(Error::Validation(String::from(
+                        "The end date of the schedule can't be before its start date",
+                    )));
+                }
+            }
+        }
+        let (drawer, _) = self.get_drawer(&template.drawer_node_id).await?;
+        self.get_counterparty(&template.drawee_node_id, &drawer)
+            .await?;
+        self.get_counterparty(&template.payee_node_id, &drawer)
+            .await?;
+        Ok(())
+    }
+
+    /// The drawer has to be the identity, or a company, the identity is a signatory of
+    async fn get_drawer(&self, drawer_node_id: &str) -> Result<(IdentityPublicData, BcrKeys)> {
+        let identity = self.identity_store.get_full().await?;
+        if identity.identity.node_id == drawer_node_id {
+            return match IdentityPublicData::new(identity.identity) {
+                Some(public_data) => Ok((public_data, identity.key_pair)),
+                None => Err(Error::Validation(String::from(
+                    "Drawer is not a bill issuer - does not have a postal address set",
+                ))),
+            };
+        }
+        match self.company_store.get_all().await?.remove(drawer_node_id) {
+            Some((company, keys)) if company.signatories.contains(&identity.identity.node_id) => {
+                let keys = BcrKeys::from_private_key(&keys.private_key)?;
+                Ok((IdentityPublicData::from(company), keys))
+            }
+            _ => Err(Error::Validation(format!(
+                "Drawer {drawer_node_id} is neither the identity, nor one of its companies"
+            ))),
+        }
+    }
+
+    /// Drawee and payee are either the drawer itself, or one of the contacts
+    async fn get_counterparty(
+        &self,
+        node_id: &str,
+        drawer: &IdentityPublicData,
+    ) -> Result<IdentityPublicData> {
+        if node_id == drawer.node_id {
+            return Ok(drawer.clone());
+        }
+        match self.contact_store.get(node_id).await? {
+            Some(contact) => Ok(contact.into()),
+            None => Err(Error::Validation(format!(
+                "Can not get identity {node_id} from contacts."
+            ))),
+        }
+    }
+
+    async fn issue(
+        &self,
+        template: &BillTemplate,
+        sum: u64,
+        issue_date: String,
+        file_upload_id: Option<String>,
+        occurrence: Option<u32>,
+        timestamp: u64,
+    ) -> Result<IssuedBill> {
+        let maturity_date = template
+            .maturity_date(&issue_date)
+            .ok_or_else(|| Error::Validation(String::from("invalid issue date")))?;
+        let (drawer, drawer_keys) = self.get_drawer(&template.drawer_node_id).await?;
+        let drawee = self
+            .get_counterparty(&template.drawee_node_id, &drawer)
+            .await?;
+        let payee = self
+            .get_counterparty(&template.payee_node_id, &drawer)
+            .await?;
+
+        // the occurrence is marked as done before issuing, so if anything fails after the bill
+        // was issued, e.g. storing the issue, it's not issued a second time on the next run
+        if let Some(occurrence) = occurrence {
+            self.set_occurrences_done(template, occurrence + 1).await?;
+        }
+
+        let issued = match self
+            .bill_service
+            .issue_new_bill(
+                template.country_of_issuing.clone(),
+                template.city_of_issuing.clone(),
+                issue_date,
+                maturity_date,
+                drawee,
+                payee,
+                sum,
+                template.currency.clone(),
+                template.country_of_payment.clone(),
+                template.city_of_payment.clone(),
+                template.language.clone(),
+                file_upload_id,
+                drawer,
+                drawer_keys,
+                timestamp,
+            )
+            .await
+        {
+            Ok(issued) => issued,
+            Err(e) => {
+                // no bill was issued, so the occurrence is retried with the next run
+                if let Some(ref schedule) = template.schedule
+                    && occurrence.is_some()
+                    && let Err(e) = self
+                        .set_occurrences_done(template, schedule.occurrences_done)
+                        .await
+                {
+                    error!(
+                        "Could not reset the schedule of template {}: {e}",
+                        template.id
+                    );
+                }
+                return Err(e.into());
+            }
+        };
+        self.store
+            .add_issue(&BillTemplateIssue {
+                template_id: template.id.clone(),
+                bill_id: issued.bill.id.clone(),
+                occurrence,
+                issued_at: timestamp,
+            })
+            .await?;
+        info!(
+            "Issued bill {} from template {}",
+            issued.bill.id, template.id
+        );
+        Ok(issued)
+    }
+
+    /// Stores the occurrences done of the template's schedule. The template is read again, so
+    /// changes made to it in the meantime aren't overwritten, and left as is, if its schedule
+    /// was changed, since the occurrences refer to the schedule of the given template
+    async fn set_occurrences_done(
+        &self,
+        template: &BillTemplate,
+        occurrences_done: u32,
+    ) -> Result<()> {
+        let Some(mut stored) = self.store.get(&template.id).await? else {
+            return Ok(());
+        };
+        match (&mut stored.schedule, &template.schedule) {
+            (Some(stored_schedule), Some(schedule))
+                if stored_schedule.interval == schedule.interval
+                    && stored_schedule.start_date == schedule.start_date =>
+            {
+                stored_schedule.occurrences_done = occurrences_done;
+                self.store.update(&stored).await?;
+            }
+            _ => info!(
+                "The schedule of template {} changed, its occurrences are not updated",
+                template.id
+            ),
+        }
+        Ok(())
+    }
+
+    /// Issues the bill for the due occurrence of the template's schedule, if there is one. The
+    /// schedule is advanced before issuing, so an occurrence is issued at most once, and reset,
+    /// if issuing the bill fails, so it's retried with the next run
+    async fn issue_due_bill(
+        &self,
+        template: BillTemplate,
+        today: &str,
+        timestamp: u64,
+    ) -> Result<()> {
+        let Some(occurrence) = template
+            .schedule
+            .as_ref()
+            .and_then(|schedule| schedule.due_occurrence(today))
+        else {
+            return Ok(());
+        };
+        self.issue(
+            &template,
+            template.sum,
+            today.to_owned(),
+            None,
+            Some(occurrence),
+            timestamp,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BillTemplateServiceApi for BillTemplateService {
+    async fn create_template(
+        &self,
+        mut template: BillTemplate,
+        timestamp: u64,
+    ) -> Result<BillTemplate> {
+        self.validate(&template).await?;
+        template.id = util::get_uuid_v4().to_string();
+        template.created_at = timestamp;
+        if let Some(ref mut schedule) = template.schedule {
+            schedule.occurrences_done = 0;
+        }
+        self.store.insert(&template).await?;
+        Ok(template)
+    }
+
+    async fn update_template(&self, mut template: BillTemplate) -> Result<BillTemplate> {
+        let existing = self.get_template(&template.id).await?;
+        self.validate(&template).await?;
+        template.created_at = existing.created_at;
+        if let Some(ref mut schedule) = template.schedule {
+            schedule.occurrences_done = match existing.schedule {
+                Some(existing_schedule)
+                    if existing_schedule.interval == schedule.interval
+                        && existing_schedule.start_date == schedule.start_date =>
+                {
+                    existing_schedule.occurrences_done
+                }
+                _ => 0,
+            };
+        }
+        self.store.update(&template).await?;
+        Ok(template)
+    }
+
+    async fn get_template(&self, id: &str) -> Result<BillTemplate> {
+        self.store.get(id).await?.ok_or(Error::NotFound)
+    }
+
+    async fn list_templates(&self) -> Result<Vec<BillTemplate>> {
+        Ok(self.store.list().await?)
+    }
+
+    async fn remove_template(&self, id: &str) -> Result<()> {
+        self.get_template(id).await?;
+        self.store.remove(id).await?;
+        Ok(())
+    }
+
+    async fn issue_bill_from_template(
+        &self,
+        template_id: &str,
+        sum: Option<u64>,
+        issue_date: Option<String>,
+        file_upload_id: Option<String>,
+        timestamp: u64,
+    ) -> Result<IssuedBill> {
+        let template = self.get_template(template_id).await?;
+        let sum = sum.unwrap_or(template.sum);
+        if sum == 0 {
+            return Err(Error::Validation(String::from(
+                "The sum has to be positive",
+            )));
+        }
+        let issue_date = issue_date
+            .unwrap_or_else(|| util::date::format_date_string(util::date::seconds(timestamp)));
+        self.issue(&template, sum, issue_date, file_upload_id, None, timestamp)
+            .await
+    }
+
+    async fn list_issued_bills(&self, template_id: &str) -> Result<Vec<BillTemplateIssue>> {
+        Ok(self.store.list_issues(template_id).await?)
+    }
+
+    async fn get_template_issue_for_bill(
+        &self,
+        bill_id: &str,
+    ) -> Result<Option<BillTemplateIssue>> {
+        Ok(self.store.get_issue_for_bill(bill_id).await?)
+    }
+
+    async fn issue_due_bills(&self, timestamp: u64) -> Result<()> {
+        let today = util::date::format_date_string(util::date::seconds(timestamp));
+        for template in self.store.list().await? {
+            let template_id = template.id.clone();
+            if let Err(e) = self.issue_due_bill(template, &today, timestamp).await {
+                error!("Could not issue the due bill of template {template_id}: {e}");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::bill_template::{BillTemplateInterval, BillTemplateSchedule};
+    use crate::service::bill_service::MockBillServiceApi;
+    use crate::service::bill_service::test_utils::{get_baseline_bill, get_baseline_identity};
+    use crate::service::contact_service::tests::get_baseline_contact;
+    use crate::tests::tests::{
+        MockBillTemplateStoreApiMock, MockCompanyStoreApiMock, MockContactStoreApiMock,
+        MockIdentityStoreApiMock, TEST_NODE_ID_SECP,
+    };
+    use mockall::{Sequence, predicate::eq};
+    use std::collections::HashMap;
+
+    // 2025-01-15
+    const TIMESTAMP: u64 = 1736942400;
+
+    fn get_template() -> BillTemplate {
+        BillTemplate {
+            id: "template".to_owned(),
+            name: "Monthly rent".to_owned(),
+            drawer_node_id: get_baseline_identity().identity.node_id,
+            drawee_node_id: TEST_NODE_ID_SECP.to_owned(),
+            payee_node_id: get_baseline_identity().identity.node_id,
+            sum: 500,
+            currency: "sat".to_owned(),
+            country_of_issuing: "AT".to_owned(),
+            city_of_issuing: "Vienna".to_owned(),
+            country_of_payment: "AT".to_owned(),
+            city_of_payment: "Vienna".to_owned(),
+            language: "en-UK".to_owned(),
+            term_days: 30,
+            schedule: Some(BillTemplateSchedule {
+                interval: BillTemplateInterval::Monthly,
+                start_date: "2024-12-01".to_owned(),
+                end_date: None,
+                occurrences_done: 0,
+            }),
+            created_at: 1731593928,
+        }
+    }
+
+    fn get_service(
+        store: MockBillTemplateStoreApiMock,
+        bill_service: MockBillServiceApi,
+    ) -> BillTemplateService {
+        let mut identity_store = MockIdentityStoreApiMock::new();
+        identity_store
+            .expect_get_full()
+            .returning(|| Ok(get_baseline_identity()));
+        let mut company_store = MockCompanyStoreApiMock::new();
+        company_store
+            .expect_get_all()
+            .returning(|| Ok(HashMap::new()));
+        let mut contact_store = MockContactStoreApiMock::new();
+        contact_store
+            .expect_get()
+            .with(eq(TEST_NODE_ID_SECP))
+            .returning(|_| Ok(Some(get_baseline_contact())));
+        contact_store.expect_get().returning(|_| Ok(None));
+        BillTemplateService::new(
+            Arc::new(store),
+            Arc::new(bill_service),
+            Arc::new(identity_store),
+            Arc::new(company_store),
+            Arc::new(contact_store),
+        )
+    }
+
+    #[tokio::test]
+    async fn create_template_validates_counterparties() {
+        let mut store = MockBillTemplateStoreApiMock::new();
+        store.expect_insert().returning(|_| Ok(())).times(1);
+        let service = get_service(store, MockBillServiceApi::new());
+
+        let mut template = get_template();
+        template.drawee_node_id = "unknown".to_owned();
+        assert!(matches!(
+            service.create_template(template, TIMESTAMP).await,
+            Err(Error::Validation(_))
+        ));
+
+        let mut template = get_template();
+        template.payee_node_id = template.drawee_node_id.clone();
+        assert!(matches!(
+            service.create_template(template, TIMESTAMP).await,
+            Err(Error::Validation(_))
+        ));
+
+        let created = service
+            .create_template(get_template(), TIMESTAMP)
+            .await
+            .unwrap();
+        assert_ne!(created.id, "template");
+        assert_eq!(created.created_at, TIMESTAMP);
+    }
+
+    #[tokio::test]
+    async fn issue_due_bills_issues_latest_occurrence_and_advances_schedule() {
+        let mut store = MockBillTemplateStoreApiMock::new();
+        store.expect_list().returning(|| Ok(vec![get_template()]));
+        store.expect_get().returning(|_| Ok(Some(get_template())));
+        store
+            .expect_add_issue()
+            .withf(|issue| issue.bill_id == "bill" && issue.occurrence == Some(1))
+            .returning(|_| Ok(()))
+            .times(1);
+        store
+            .expect_update()
+            .withf(|template| template.schedule.as_ref().unwrap().occurrences_done == 2)
+            .returning(|_| Ok(()))
+            .times(1);
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_issue_new_bill()
+            .withf(
+                |_, _, issue_date, maturity_date, _, payee, sum, _, _, _, _, _, _, _, _| {
+                    issue_date == "2025-01-15"
+                        && maturity_date == "2025-02-14"
+                        && payee.node_id == get_baseline_identity().identity.node_id
+                        && *sum == 500
+                },
+            )
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _, _, _| {
+                Ok(IssuedBill {
+                    bill: get_baseline_bill("bill"),
+                    warnings: vec![],
+                })
+            })
+            .times(1);
+        let service = get_service(store, bill_service);
+
+        service.issue_due_bills(TIMESTAMP).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn issue_due_bills_advances_schedule_before_issuing() {
+        let mut seq = Sequence::new();
+        let mut store = MockBillTemplateStoreApiMock::new();
+        store.expect_list().returning(|| Ok(vec![get_template()]));
+        store.expect_get().returning(|_| Ok(Some(get_template())));
+        store
+            .expect_update()
+            .withf(|template| template.schedule.as_ref().unwrap().occurrences_done == 2)
+            .returning(|_| Ok(()))
+            .times(1)
+            .in_sequence(&mut seq);
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_issue_new_bill()
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _, _, _| {
+                Ok(IssuedBill {
+                    bill: get_baseline_bill("bill"),
+                    warnings: vec![],
+                })
+            })
+            .times(1)
+            .in_sequence(&mut seq);
+        // storing the issue fails after the bill was issued
+        store
+            .expect_add_issue()
+            .returning(|_| Err(crate::persistence::Error::InsertFailed("bill".to_owned())))
+            .times(1)
+            .in_sequence(&mut seq);
+        let service = get_service(store, bill_service);
+
+        service.issue_due_bills(TIMESTAMP).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn issue_due_bills_retries_occurrence_if_issuing_fails() {
+        let mut seq = Sequence::new();
+        let mut store = MockBillTemplateStoreApiMock::new();
+        store.expect_list().returning(|| Ok(vec![get_template()]));
+        store.expect_get().returning(|_| Ok(Some(get_template())));
+        store
+            .expect_update()
+            .withf(|template| template.schedule.as_ref().unwrap().occurrences_done == 2)
+            .returning(|_| Ok(()))
+            .times(1)
+            .in_sequence(&mut seq);
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_issue_new_bill()
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _, _, _| {
+                Err(crate::service::bill_service::Error::InvalidOperation)
+            })
+            .times(1)
+            .in_sequence(&mut seq);
+        // the advance is reset, so the occurrence is due again with the next run
+        store
+            .expect_update()
+            .withf(|template| template.schedule.as_ref().unwrap().occurrences_done == 0)
+            .returning(|_| Ok(()))
+            .times(1)
+            .in_sequence(&mut seq);
+        store.expect_add_issue().never();
+        let service = get_service(store, bill_service);
+
+        service.issue_due_bills(TIMESTAMP).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn issue_due_bills_keeps_changes_made_to_the_template_meanwhile() {
+        let mut store = MockBillTemplateStoreApiMock::new();
+        store.expect_list().returning(|| Ok(vec![get_template()]));
+        store.expect_get().returning(|_| {
+            let mut template = get_template();
+            template.name = "Changed".to_owned();
+            Ok(Some(template))
+        });
+        store
+            .expect_update()
+            .withf(|template| {
+                template.name == "Changed"
+                    && template.schedule.as_ref().unwrap().occurrences_done == 2
+            })
+            .returning(|_| Ok(()))
+            .times(1);
+        store.expect_add_issue().returning(|_| Ok(()));
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_issue_new_bill()
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _, _, _| {
+                Ok(IssuedBill {
+                    bill: get_baseline_bill("bill"),
+                    warnings: vec![],
+                })
+            })
+            .times(1);
+        let service = get_service(store, bill_service);
+
+        service.issue_due_bills(TIMESTAMP).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn issue_due_bills_issues_occurrences_of_a_changed_schedule() {
+        // a bill was issued for occurrence 1 of the previous schedule, before the schedule was
+        // changed and its occurrences reset
+        let mut store = MockBillTemplateStoreApiMock::new();
+        store.expect_list().returning(|| Ok(vec![get_template()]));
+        store.expect_get().returning(|_| Ok(Some(get_template())));
+        store.expect_list_issues().returning(|_| {
+            Ok(vec![BillTemplateIssue {
+                template_id: "template".to_owned(),
+                bill_id: "old bill".to_owned(),
+                occurrence: Some(1),
+                issued_at: TIMESTAMP - 60 * 60 * 24 * 30,
+            }])
+        });
+        store.expect_update().returning(|_| Ok(()));
+        store
+            .expect_add_issue()
+            .withf(|issue| issue.bill_id == "bill" && issue.occurrence == Some(1))
+            .returning(|_| Ok(()))
+            .times(1);
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_issue_new_bill()
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _, _, _| {
+                Ok(IssuedBill {
+                    bill: get_baseline_bill("bill"),
+                    warnings: vec![],
+                })
+            })
+            .times(1);
+        let service = get_service(store, bill_service);
+
+        service.issue_due_bills(TIMESTAMP).await.unwrap();
+    }
+}
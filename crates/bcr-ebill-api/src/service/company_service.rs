@@ -1,4 +1,5 @@
 use super::Result;
+use super::search_service::{remove_from_search_index, update_search_index};
 use crate::blockchain::Blockchain;
 use crate::blockchain::company::{
     CompanyAddSignatoryBlockData, CompanyBlock, CompanyBlockchain, CompanyCreateBlockData,
@@ -12,20 +13,28 @@ use crate::data::{
     File, OptionalPostalAddress, PostalAddress,
     company::{Company, CompanyKeys},
     contact::{Contact, ContactType},
+    contact_card::{CONTACT_CARD_VERSION, ContactCard, ContactCardContent},
 };
+use crate::get_config;
 use crate::persistence::company::{CompanyChainStoreApi, CompanyStoreApi};
 use crate::persistence::identity::IdentityChainStoreApi;
 use crate::util::BcrKeys;
 use crate::{
     persistence::{
         contact::ContactStoreApi, file_upload::FileUploadStoreApi, identity::IdentityStoreApi,
+        search_index::SearchIndexStoreApi,
     },
     util,
 };
 use async_trait::async_trait;
+use bcr_ebill_core::GeneralSearchFilterItemType;
+use bcr_ebill_core::search::SearchDocument;
 use log::{error, info};
+#[cfg(test)]
+use mockall::automock;
 use std::sync::Arc;
 
+#[cfg_attr(test, automock)]
 #[async_trait]
 pub trait CompanyServiceApi: Send + Sync {
     /// List signatories for company
@@ -42,6 +51,9 @@ pub trait CompanyServiceApi: Send + Sync {
     /// Get a company and it's keys by id
     async fn get_company_and_keys_by_id(&self, id: &str) -> Result<(Company, CompanyKeys)>;
 
+    /// Returns the contact card of the company, signed with its keys
+    async fn get_contact_card(&self, id: &str) -> Result<ContactCard>;
+
     /// Create a new company
     async fn create_company(
         &self,
@@ -117,6 +129,7 @@ pub struct CompanyService {
     contact_store: Arc<dyn ContactStoreApi>,
     identity_blockchain_store: Arc<dyn IdentityChainStoreApi>,
     company_blockchain_store: Arc<dyn CompanyChainStoreApi>,
+    search_index_store: Arc<dyn SearchIndexStoreApi>,
 }
 
 impl CompanyService {
@@ -127,6 +140,7 @@ impl CompanyService {
         contact_store: Arc<dyn ContactStoreApi>,
         identity_blockchain_store: Arc<dyn IdentityChainStoreApi>,
         company_blockchain_store: Arc<dyn CompanyChainStoreApi>,
+        search_index_store: Arc<dyn SearchIndexStoreApi>,
     ) -> Self {
         Self {
             store,
@@ -135,6 +149,7 @@ impl CompanyService {
             contact_store,
             identity_blockchain_store,
             company_blockchain_store,
+            search_index_store,
         }
     }
 
@@ -208,6 +223,22 @@ impl CompanyServiceApi for CompanyService {
         Ok(company)
     }
 
+    async fn get_contact_card(&self, id: &str) -> Result<ContactCard> {
+        let (company, keys) = self.get_company_and_keys_by_id(id).await?;
+        let content = ContactCardContent {
+            version: CONTACT_CARD_VERSION,
+            t: ContactType::Company,
+            node_id: company.id,
+            name: company.name,
+            postal_address: company.postal_address,
+            email: company.email,
+            nostr_relays: get_config().nostr_relays.clone(),
+            issued_at: util::date::now().timestamp() as u64,
+        };
+        let keys = BcrKeys::from_private_key(&keys.private_key)?;
+        Ok(ContactCard::sign(content, &keys)?)
+    }
+
     async fn create_company(
         &self,
         name: String,
@@ -266,6 +297,11 @@ impl CompanyServiceApi for CompanyService {
             signatories: vec![full_identity.identity.node_id.clone()], // add caller as signatory
         };
         self.store.insert(&company).await?;
+        update_search_index(
+            self.search_index_store.as_ref(),
+            &SearchDocument::for_company(&company),
+        )
+        .await;
 
         let company_chain = CompanyBlockchain::new(
             &CompanyCreateBlockData::from(company.clone()),
@@ -426,6 +462,11 @@ impl CompanyServiceApi for CompanyService {
         }
 
         self.store.update(id, &company).await?;
+        update_search_index(
+            self.search_index_store.as_ref(),
+            &SearchDocument::for_company(&company),
+        )
+        .await;
 
         let previous_block = self.company_blockchain_store.get_latest_block(id).await?;
         let new_block = CompanyBlock::create_block_for_update(
@@ -494,6 +535,11 @@ impl CompanyServiceApi for CompanyService {
         }
         company.signatories.push(signatory_node_id.clone());
         self.store.update(id, &company).await?;
+        update_search_index(
+            self.search_index_store.as_ref(),
+            &SearchDocument::for_company(&company),
+        )
+        .await;
 
         let previous_block = self.company_blockchain_store.get_latest_block(id).await?;
         let new_block = CompanyBlock::create_block_for_add_signatory(
@@ -562,11 +608,22 @@ impl CompanyServiceApi for CompanyService {
 
         company.signatories.retain(|i| i != &signatory_node_id);
         self.store.update(id, &company).await?;
+        update_search_index(
+            self.search_index_store.as_ref(),
+            &SearchDocument::for_company(&company),
+        )
+        .await;
 
         if full_identity.identity.node_id == signatory_node_id {
             info!("Removing self from company {id}");
             let _ = self.file_upload_store.delete_attached_files(id).await;
             self.store.remove(id).await?;
+            remove_from_search_index(
+                self.search_index_store.as_ref(),
+                GeneralSearchFilterItemType::Company,
+                id,
+            )
+            .await;
         }
 
         let previous_block = self.company_blockchain_store.get_latest_block(id).await?;
@@ -658,8 +715,9 @@ pub mod tests {
         tests::tests::{
             MockCompanyChainStoreApiMock, MockCompanyStoreApiMock, MockContactStoreApiMock,
             MockFileUploadStoreApiMock, MockIdentityChainStoreApiMock, MockIdentityStoreApiMock,
-            TEST_NODE_ID_SECP, TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP, empty_address,
-            empty_identity, empty_optional_address,
+            MockSearchIndexStoreApiMock, TEST_NODE_ID_SECP, TEST_PRIVATE_KEY_SECP,
+            TEST_PUB_KEY_SECP, empty_address, empty_identity, empty_optional_address,
+            init_test_cfg,
         },
     };
     use mockall::predicate::{always, eq};
@@ -674,6 +732,13 @@ pub mod tests {
         mock_identity_chain_storage: MockIdentityChainStoreApiMock,
         mock_company_chain_storage: MockCompanyChainStoreApiMock,
     ) -> CompanyService {
+        let mut search_index_store = MockSearchIndexStoreApiMock::new();
+        search_index_store
+            .expect_index_document()
+            .returning(|_| Ok(()));
+        search_index_store
+            .expect_remove_document()
+            .returning(|_, _| Ok(()));
         CompanyService::new(
             Arc::new(mock_storage),
             Arc::new(mock_file_upload_storage),
@@ -681,6 +746,7 @@ pub mod tests {
             Arc::new(mock_contacts_storage),
             Arc::new(mock_identity_chain_storage),
             Arc::new(mock_company_chain_storage),
+            Arc::new(search_index_store),
         )
     }
 
@@ -854,6 +920,40 @@ pub mod tests {
         assert!(res.is_err());
     }
 
+    #[tokio::test]
+    async fn get_contact_card_is_signed_by_company() {
+        init_test_cfg();
+        let (
+            mut storage,
+            file_upload_store,
+            identity_store,
+            contact_store,
+            identity_chain_store,
+            company_chain_store,
+        ) = get_storages();
+        storage.expect_exists().returning(|_| true);
+        storage
+            .expect_get()
+            .returning(|_| Ok(get_baseline_company_data().1.0));
+        storage
+            .expect_get_key_pair()
+            .returning(|_| Ok(get_baseline_company_data().1.1));
+        let service = get_service(
+            storage,
+            file_upload_store,
+            identity_store,
+            contact_store,
+            identity_chain_store,
+            company_chain_store,
+        );
+
+        let card = service.get_contact_card(TEST_PUB_KEY_SECP).await.unwrap();
+        assert_eq!(card.content.node_id, TEST_PUB_KEY_SECP);
+        assert_eq!(card.content.t, ContactType::Company);
+        assert_eq!(card.content.email, "company@example.com");
+        assert!(card.verify().unwrap());
+    }
+
     #[tokio::test]
     async fn get_company_by_id_propagates_persistence_errors() {
         let (
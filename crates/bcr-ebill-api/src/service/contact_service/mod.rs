@@ -0,0 +1,1474 @@
+mod vcard;
+
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::{
+    data::{
+        File, GeneralSearchFilterItemType, OptionalPostalAddress, PostalAddress,
+        contact::{
+            Contact, ContactImportResult, ContactTrustLevel, ContactType,
+            ContactVerificationStatus, IdentityPublicData, RejectedContactImport,
+        },
+        contact_card::{CONTACT_CARD_VERSION, ContactCard},
+        profile::PublicProfile,
+    },
+    get_config,
+    persistence::{
+        contact::ContactStoreApi, file_upload::FileUploadStoreApi, identity::IdentityStoreApi,
+        search_index::SearchIndexStoreApi,
+    },
+    util,
+};
+
+use super::Result;
+use super::profile_service::ProfileServiceApi;
+use super::search_service::{remove_from_search_index, update_search_index};
+use bcr_ebill_core::search::SearchDocument;
+use log::{info, warn};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ContactServiceApi: Send + Sync {
+    /// Searches contacts for the search term
+    async fn search(&self, search_term: &str) -> Result<Vec<Contact>>;
+    /// Returns all contacts in short form
+    async fn get_contacts(&self) -> Result<Vec<Contact>>;
+
+    /// Returns the contact details for the given node_id
+    async fn get_contact(&self, node_id: &str) -> Result<Contact>;
+
+    /// Returns the contact by node id
+    async fn get_identity_by_node_id(&self, node_id: &str) -> Result<Option<IdentityPublicData>>;
+
+    /// Deletes the contact with the given node_id.
+    async fn delete(&self, node_id: &str) -> Result<()>;
+
+    /// Updates the contact with the given data.
+    async fn update_contact(
+        &self,
+        node_id: &str,
+        name: Option<String>,
+        email: Option<String>,
+        postal_address: OptionalPostalAddress,
+        date_of_birth_or_registration: Option<String>,
+        country_of_birth_or_registration: Option<String>,
+        city_of_birth_or_registration: Option<String>,
+        identification_number: Option<String>,
+        avatar_file_upload_id: Option<String>,
+        proof_document_file_upload_id: Option<String>,
+    ) -> Result<()>;
+
+    /// Sets how the identity of the contact was verified and how much it's trusted. An uploaded
+    /// file can be attached as evidence of the verification, which replaces the previous one.
+    /// Setting the contact to unverified removes the evidence
+    async fn set_verification(
+        &self,
+        node_id: &str,
+        verification_status: ContactVerificationStatus,
+        trust_level: ContactTrustLevel,
+        evidence_file_upload_id: Option<String>,
+    ) -> Result<Contact>;
+
+    /// Adds a new contact
+    async fn add_contact(
+        &self,
+        node_id: &str,
+        t: ContactType,
+        name: String,
+        email: String,
+        postal_address: PostalAddress,
+        date_of_birth_or_registration: Option<String>,
+        country_of_birth_or_registration: Option<String>,
+        city_of_birth_or_registration: Option<String>,
+        identification_number: Option<String>,
+        avatar_file_upload_id: Option<String>,
+        proof_document_file_upload_id: Option<String>,
+    ) -> Result<Contact>;
+
+    /// Adds the identity or company with the given node id or npub as contact, with the details
+    /// of its public profile and the given email address. The contact then follows the profile,
+    /// until its details are edited locally
+    async fn add_contact_from_profile(
+        &self,
+        node_id_or_npub: &str,
+        email: String,
+    ) -> Result<Contact>;
+
+    /// Adds the identity or company of the given signed contact card URI (`bcr:...`) as
+    /// contact, after verifying that it was signed by the keys of its node id. A known contact
+    /// is updated with the details of the card instead
+    async fn add_contact_from_card(&self, uri: &str) -> Result<Contact>;
+
+    /// Updates the contact with the given node id with the details of its latest public
+    /// profile, even if they were edited locally. The contact follows the profile afterwards
+    async fn refresh_contact(&self, node_id: &str) -> Result<Contact>;
+
+    /// Updates all contacts, which follow their profile, with its latest details. Contacts,
+    /// which were edited locally, are left as they are
+    async fn refresh_contacts(&self) -> Result<()>;
+
+    /// Exports all contacts as vCard 4.0 file
+    async fn export_vcards(&self) -> Result<String>;
+
+    /// Imports the contacts of the given vCard file. Contacts, which are already known or
+    /// occur more than once, are skipped and reported as duplicates, as well as entries
+    /// without a valid node id or a complete postal address
+    async fn import_vcards(&self, vcards: &str) -> Result<ContactImportResult>;
+
+    /// Returns whether a given npub (as hex) is in our contact list.
+    #[allow(dead_code)]
+    async fn is_known_npub(&self, npub: &str) -> Result<bool>;
+
+    /// opens and decrypts the attached file from the given contact
+    async fn open_and_decrypt_file(
+        &self,
+        id: &str,
+        file_name: &str,
+        private_key: &str,
+    ) -> Result<Vec<u8>>;
+}
+
+/// The contact service is responsible for managing the local contacts
+#[derive(Clone)]
+pub struct ContactService {
+    store: Arc<dyn ContactStoreApi>,
+    file_upload_store: Arc<dyn FileUploadStoreApi>,
+    identity_store: Arc<dyn IdentityStoreApi>,
+    search_index_store: Arc<dyn SearchIndexStoreApi>,
+    profile_service: Arc<dyn ProfileServiceApi>,
+}
+
+impl ContactService {
+    pub fn new(
+        store: Arc<dyn ContactStoreApi>,
+        file_upload_store: Arc<dyn FileUploadStoreApi>,
+        identity_store: Arc<dyn IdentityStoreApi>,
+        search_index_store: Arc<dyn SearchIndexStoreApi>,
+        profile_service: Arc<dyn ProfileServiceApi>,
+    ) -> Self {
+        Self {
+            store,
+            file_upload_store,
+            identity_store,
+            search_index_store,
+            profile_service,
+        }
+    }
+
+    /// Applies the details of the profile to the contact and stores it. The contact follows the
+    /// profile afterwards. Profiles older than the known details are rejected
+    async fn apply_profile(&self, mut contact: Contact, profile: PublicProfile) -> Result<Contact> {
+        if is_older_than_details(&contact, profile.published_at) {
+            return Err(super::Error::Validation(format!(
+                "The profile of {} is older than the known details",
+                contact.node_id
+            )));
+        }
+        let before = contact.clone();
+        contact.details_issued_at = Some(profile.published_at);
+        contact.t = profile.t;
+        contact.name = profile.name;
+        // contacts need a full address, so incomplete ones don't replace the known one
+        if let Some(postal_address) = profile.postal_address.to_full_postal_address() {
+            contact.postal_address = postal_address;
+        }
+        reset_verification_if_changed(&before, &mut contact);
+        if !profile.nostr_relays.is_empty() {
+            contact.nostr_relays = profile.nostr_relays;
+        }
+        contact.follows_profile = true;
+        let search_document = SearchDocument::for_contact(&contact);
+        self.store.update(&contact.node_id, contact.clone()).await?;
+        update_search_index(self.search_index_store.as_ref(), &search_document).await;
+        Ok(contact)
+    }
+
+    async fn process_upload_file(
+        &self,
+        upload_id: &Option<String>,
+        id: &str,
+        public_key: &str,
+    ) -> Result<Option<File>> {
+        if let Some(upload_id) = upload_id {
+            let files = self
+                .file_upload_store
+                .read_temp_upload_files(upload_id)
+                .await
+                .map_err(|_| crate::service::Error::NoFileForFileUploadId)?;
+            if !files.is_empty() {
+                let (file_name, file_bytes) = &files[0];
+                let file = self
+                    .encrypt_and_save_uploaded_file(file_name, file_bytes, id, public_key)
+                    .await?;
+                return Ok(Some(file));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn encrypt_and_save_uploaded_file(
+        &self,
+        file_name: &str,
+        file_bytes: &[u8],
+        node_id: &str,
+        public_key: &str,
+    ) -> Result<File> {
+        let file_hash = util::sha256_hash(file_bytes);
+        let encrypted = util::crypto::encrypt_ecies(file_bytes, public_key)?;
+        self.file_upload_store
+            .save_attached_file(&encrypted, node_id, file_name)
+            .await?;
+        info!("Saved contact file {file_name} with hash {file_hash} for contact {node_id}");
+        Ok(File {
+            name: file_name.to_owned(),
+            hash: file_hash,
+        })
+    }
+}
+
+/// Whether a card or profile issued at the given time is older than the details of the contact
+fn is_older_than_details(contact: &Contact, issued_at: u64) -> bool {
+    contact
+        .details_issued_at
+        .is_some_and(|details_issued_at| issued_at < details_issued_at)
+}
+
+/// Resets the verification of the contact, if the type, name or postal address it was verified
+/// with changed. The node id, and with it the keys, of a contact never change
+fn reset_verification_if_changed(before: &Contact, contact: &mut Contact) {
+    if !contact.is_verified() {
+        return;
+    }
+    if before.t != contact.t
+        || before.name != contact.name
+        || before.postal_address != contact.postal_address
+    {
+        contact.verification_status = ContactVerificationStatus::Unverified;
+        contact.verification_evidence_file = None;
+        info!(
+            "Reset verification of contact {}, since its details changed",
+            contact.node_id
+        );
+    }
+}
+
+#[async_trait]
+impl ContactServiceApi for ContactService {
+    async fn search(&self, search_term: &str) -> Result<Vec<Contact>> {
+        let contacts = self.store.search(search_term).await?;
+        Ok(contacts)
+    }
+
+    async fn get_contacts(&self) -> Result<Vec<Contact>> {
+        let contact_map = self.store.get_map().await?;
+        let contact_list: Vec<Contact> = contact_map.into_values().collect();
+        Ok(contact_list)
+    }
+
+    async fn get_contact(&self, node_id: &str) -> Result<Contact> {
+        let res = self.store.get(node_id).await?;
+        match res {
+            None => Err(super::Error::NotFound),
+            Some(contact) => Ok(contact),
+        }
+    }
+
+    async fn get_identity_by_node_id(&self, node_id: &str) -> Result<Option<IdentityPublicData>> {
+        let res = self.store.get(node_id).await?;
+        Ok(res.map(|c| c.into()))
+    }
+
+    async fn delete(&self, node_id: &str) -> Result<()> {
+        self.store.delete(node_id).await?;
+        remove_from_search_index(
+            self.search_index_store.as_ref(),
+            GeneralSearchFilterItemType::Contact,
+            node_id,
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn update_contact(
+        &self,
+        node_id: &str,
+        name: Option<String>,
+        email: Option<String>,
+        postal_address: OptionalPostalAddress,
+        date_of_birth_or_registration: Option<String>,
+        country_of_birth_or_registration: Option<String>,
+        city_of_birth_or_registration: Option<String>,
+        identification_number: Option<String>,
+        avatar_file_upload_id: Option<String>,
+        proof_document_file_upload_id: Option<String>,
+    ) -> Result<()> {
+        let mut contact = match self.store.get(node_id).await? {
+            Some(contact) => contact,
+            None => {
+                return Err(super::Error::NotFound);
+            }
+        };
+        let mut changed = false;
+
+        let identity_public_key = self.identity_store.get_key_pair().await?.get_public_key();
+
+        // local edits of the details, which come from the profile, must not be overwritten by
+        // the next refresh
+        if name.is_some()
+            || postal_address.country.is_some()
+            || postal_address.city.is_some()
+            || postal_address.zip.is_some()
+            || postal_address.address.is_some()
+        {
+            contact.follows_profile = false;
+        }
+
+        if let Some(ref name_to_set) = name {
+            contact.name = name_to_set.clone();
+            changed = true;
+        }
+
+        if let Some(ref email_to_set) = email {
+            contact.email = email_to_set.clone();
+            changed = true;
+        }
+
+        if let Some(ref postal_address_city_to_set) = postal_address.city {
+            contact.postal_address.city = postal_address_city_to_set.clone();
+            changed = true;
+        }
+
+        if let Some(ref postal_address_country_to_set) = postal_address.country {
+            contact.postal_address.country = postal_address_country_to_set.clone();
+            changed = true;
+        }
+
+        util::update_optional_field(
+            &mut contact.postal_address.zip,
+            &postal_address.zip,
+            &mut changed,
+        );
+
+        if let Some(ref postal_address_address_to_set) = postal_address.address {
+            contact.postal_address.address = postal_address_address_to_set.clone();
+            changed = true;
+        }
+
+        util::update_optional_field(
+            &mut contact.date_of_birth_or_registration,
+            &date_of_birth_or_registration,
+            &mut changed,
+        );
+
+        util::update_optional_field(
+            &mut contact.country_of_birth_or_registration,
+            &country_of_birth_or_registration,
+            &mut changed,
+        );
+
+        util::update_optional_field(
+            &mut contact.city_of_birth_or_registration,
+            &city_of_birth_or_registration,
+            &mut changed,
+        );
+
+        util::update_optional_field(
+            &mut contact.identification_number,
+            &identification_number,
+            &mut changed,
+        );
+
+        if !changed && avatar_file_upload_id.is_none() && proof_document_file_upload_id.is_none() {
+            return Ok(());
+        }
+
+        let avatar_file = self
+            .process_upload_file(&avatar_file_upload_id, node_id, &identity_public_key)
+            .await?;
+        // only override the picture, if there is a new one
+        if avatar_file.is_some() {
+            contact.avatar_file = avatar_file;
+        }
+        let proof_document_file = self
+            .process_upload_file(
+                &proof_document_file_upload_id,
+                node_id,
+                &identity_public_key,
+            )
+            .await?;
+        // only override the document, if there is a new one
+        if proof_document_file.is_some() {
+            contact.proof_document_file = proof_document_file;
+        }
+
+        let search_document = SearchDocument::for_contact(&contact);
+        self.store.update(node_id, contact).await?;
+        update_search_index(self.search_index_store.as_ref(), &search_document).await;
+
+        Ok(())
+    }
+
+    async fn set_verification(
+        &self,
+        node_id: &str,
+        verification_status: ContactVerificationStatus,
+        trust_level: ContactTrustLevel,
+        evidence_file_upload_id: Option<String>,
+    ) -> Result<Contact> {
+        let mut contact = self.get_contact(node_id).await?;
+        let identity_public_key = self.identity_store.get_key_pair().await?.get_public_key();
+        let evidence_file = self
+            .process_upload_file(&evidence_file_upload_id, node_id, &identity_public_key)
+            .await?;
+
+        contact.verification_status = verification_status;
+        contact.trust_level = trust_level;
+        if verification_status == ContactVerificationStatus::Unverified {
+            contact.verification_evidence_file = None;
+        } else if evidence_file.is_some() {
+            contact.verification_evidence_file = evidence_file;
+        }
+        self.store.update(node_id, contact.clone()).await?;
+        info!(
+            "Set verification of contact {node_id} to {verification_status:?} with trust level {trust_level:?}"
+        );
+        Ok(contact)
+    }
+
+    async fn add_contact(
+        &self,
+        node_id: &str,
+        t: ContactType,
+        name: String,
+        email: String,
+        postal_address: PostalAddress,
+        date_of_birth_or_registration: Option<String>,
+        country_of_birth_or_registration: Option<String>,
+        city_of_birth_or_registration: Option<String>,
+        identification_number: Option<String>,
+        avatar_file_upload_id: Option<String>,
+        proof_document_file_upload_id: Option<String>,
+    ) -> Result<Contact> {
+        if util::crypto::validate_pub_key(node_id).is_err() {
+            return Err(super::Error::Validation(format!(
+                "Not a valid secp256k1 key: {node_id}",
+            )));
+        }
+
+        let identity_public_key = self.identity_store.get_key_pair().await?.get_public_key();
+        let avatar_file = self
+            .process_upload_file(&avatar_file_upload_id, node_id, &identity_public_key)
+            .await?;
+
+        let proof_document_file = self
+            .process_upload_file(
+                &proof_document_file_upload_id,
+                node_id,
+                &identity_public_key,
+            )
+            .await?;
+
+        let contact = Contact {
+            node_id: node_id.to_owned(),
+            t,
+            name,
+            email,
+            postal_address,
+            date_of_birth_or_registration,
+            country_of_birth_or_registration,
+            city_of_birth_or_registration,
+            identification_number,
+            avatar_file,
+            proof_document_file,
+            nostr_relays: get_config().nostr_relays.clone(), // Use the configured relays for now
+            verification_status: ContactVerificationStatus::Unverified,
+            trust_level: ContactTrustLevel::Unknown,
+            verification_evidence_file: None,
+            follows_profile: false,
+            details_issued_at: None,
+        };
+
+        self.store.insert(node_id, contact.clone()).await?;
+        update_search_index(
+            self.search_index_store.as_ref(),
+            &SearchDocument::for_contact(&contact),
+        )
+        .await;
+        Ok(contact)
+    }
+
+    async fn add_contact_from_profile(
+        &self,
+        node_id_or_npub: &str,
+        email: String,
+    ) -> Result<Contact> {
+        if email.trim().is_empty() {
+            return Err(super::Error::Validation(
+                "The email address of the contact is required".to_string(),
+            ));
+        }
+        let profile = self.profile_service.resolve(node_id_or_npub).await?;
+        if self.store.get(&profile.node_id).await?.is_some() {
+            return Err(super::Error::Validation(format!(
+                "{} is already a contact",
+                profile.node_id
+            )));
+        }
+        let postal_address = profile
+            .postal_address
+            .to_full_postal_address()
+            .ok_or_else(|| {
+                super::Error::Validation(format!(
+                    "The profile of {} has no complete postal address",
+                    profile.node_id
+                ))
+            })?;
+        let contact = Contact {
+            node_id: profile.node_id,
+            t: profile.t,
+            name: profile.name,
+            email,
+            postal_address,
+            date_of_birth_or_registration: None,
+            country_of_birth_or_registration: None,
+            city_of_birth_or_registration: None,
+            identification_number: None,
+            avatar_file: None,
+            proof_document_file: None,
+            nostr_relays: profile.nostr_relays,
+            verification_status: ContactVerificationStatus::Unverified,
+            trust_level: ContactTrustLevel::Unknown,
+            verification_evidence_file: None,
+            follows_profile: true,
+            details_issued_at: Some(profile.published_at),
+        };
+
+        self.store.insert(&contact.node_id, contact.clone()).await?;
+        update_search_index(
+            self.search_index_store.as_ref(),
+            &SearchDocument::for_contact(&contact),
+        )
+        .await;
+        info!("Added contact {} from its profile", contact.node_id);
+        Ok(contact)
+    }
+
+    async fn add_contact_from_card(&self, uri: &str) -> Result<Contact> {
+        let card = ContactCard::from_str(uri).map_err(super::Error::Validation)?;
+        let content = &card.content;
+        if content.version != CONTACT_CARD_VERSION {
+            return Err(super::Error::Validation(format!(
+                "Unsupported contact card version {}",
+                content.version
+            )));
+        }
+        if util::crypto::validate_pub_key(&content.node_id).is_err() {
+            return Err(super::Error::Validation(format!(
+                "Not a valid secp256k1 key: {}",
+                content.node_id
+            )));
+        }
+        if !card.verify().unwrap_or(false) {
+            return Err(super::Error::Validation(format!(
+                "Contact card of {} was not signed by its node id",
+                content.node_id
+            )));
+        }
+
+        let content = card.content;
+        if let Some(mut contact) = self.store.get(&content.node_id).await? {
+            if is_older_than_details(&contact, content.issued_at) {
+                return Err(super::Error::Validation(format!(
+                    "Contact card of {} is older than the known details",
+                    content.node_id
+                )));
+            }
+            let before = contact.clone();
+            contact.details_issued_at = Some(content.issued_at);
+            contact.t = content.t;
+            contact.name = content.name;
+            contact.email = content.email;
+            contact.postal_address = content.postal_address;
+            reset_verification_if_changed(&before, &mut contact);
+            if !content.nostr_relays.is_empty() {
+                contact.nostr_relays = content.nostr_relays;
+            }
+            self.store.update(&contact.node_id, contact.clone()).await?;
+            update_search_index(
+                self.search_index_store.as_ref(),
+                &SearchDocument::for_contact(&contact),
+            )
+            .await;
+            info!("Updated contact {} from its contact card", contact.node_id);
+            return Ok(contact);
+        }
+
+        let contact = Contact {
+            node_id: content.node_id,
+            t: content.t,
+            name: content.name,
+            email: content.email,
+            postal_address: content.postal_address,
+            date_of_birth_or_registration: None,
+            country_of_birth_or_registration: None,
+            city_of_birth_or_registration: None,
+            identification_number: None,
+            avatar_file: None,
+            proof_document_file: None,
+            nostr_relays: content.nostr_relays,
+            verification_status: ContactVerificationStatus::Unverified,
+            trust_level: ContactTrustLevel::Unknown,
+            verification_evidence_file: None,
+            follows_profile: false,
+            details_issued_at: Some(content.issued_at),
+        };
+        self.store.insert(&contact.node_id, contact.clone()).await?;
+        update_search_index(
+            self.search_index_store.as_ref(),
+            &SearchDocument::for_contact(&contact),
+        )
+        .await;
+        info!("Added contact {} from its contact card", contact.node_id);
+        Ok(contact)
+    }
+
+    async fn refresh_contact(&self, node_id: &str) -> Result<Contact> {
+        let contact = self.get_contact(node_id).await?;
+        let profile = self.profile_service.resolve(node_id).await?;
+        self.apply_profile(contact, profile).await
+    }
+
+    async fn refresh_contacts(&self) -> Result<()> {
+        for (node_id, contact) in self.store.get_map().await? {
+            if !contact.follows_profile {
+                continue;
+            }
+            let refreshed = match self.profile_service.resolve(&node_id).await {
+                // a card imported since the profile was published is kept
+                Ok(profile) if is_older_than_details(&contact, profile.published_at) => Ok(()),
+                Ok(profile) => self.apply_profile(contact, profile).await.map(|_| ()),
+                // contacts don't have to publish a profile
+                Err(super::Error::NotFound) => Ok(()),
+                Err(e) => Err(e),
+            };
+            // one failing contact must not keep the others from being refreshed
+            if let Err(e) = refreshed {
+                warn!("Could not refresh the profile of contact {node_id}: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn export_vcards(&self) -> Result<String> {
+        let mut contacts = self.get_contacts().await?;
+        contacts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(vcard::write_vcards(&contacts))
+    }
+
+    async fn import_vcards(&self, vcards: &str) -> Result<ContactImportResult> {
+        let mut known: HashSet<String> = self.store.get_map().await?.into_keys().collect();
+        let mut result = ContactImportResult::default();
+        for (idx, entry) in vcard::read_vcards(vcards).into_iter().enumerate() {
+            let name = entry.name.clone();
+            let reject = |reason: String| RejectedContactImport {
+                position: idx + 1,
+                name: name.clone(),
+                reason,
+            };
+            let mut contact = match entry.into_contact() {
+                Ok(contact) => contact,
+                Err(reason) => {
+                    result.rejected.push(reject(reason));
+                    continue;
+                }
+            };
+            if util::crypto::validate_pub_key(&contact.node_id).is_err() {
+                result.rejected.push(reject(format!(
+                    "Not a valid secp256k1 key: {}",
+                    contact.node_id
+                )));
+                continue;
+            }
+            if !known.insert(contact.node_id.clone()) {
+                result.duplicates.push(contact.node_id);
+                continue;
+            }
+            if contact.nostr_relays.is_empty() {
+                contact.nostr_relays = get_config().nostr_relays.clone();
+            }
+
+            self.store.insert(&contact.node_id, contact.clone()).await?;
+            update_search_index(
+                self.search_index_store.as_ref(),
+                &SearchDocument::for_contact(&contact),
+            )
+            .await;
+            result.imported.push(contact.node_id);
+        }
+        info!(
+            "Imported {} contacts from vCards, skipped {} duplicates and {} invalid entries",
+            result.imported.len(),
+            result.duplicates.len(),
+            result.rejected.len()
+        );
+        Ok(result)
+    }
+
+    async fn is_known_npub(&self, npub: &str) -> Result<bool> {
+        let node_id_list: Vec<String> = self.store.get_map().await?.into_keys().collect();
+        Ok(node_id_list
+            .iter()
+            .any(|node_id| util::crypto::is_node_id_nostr_hex_npub(node_id, npub)))
+    }
+
+    async fn open_and_decrypt_file(
+        &self,
+        id: &str,
+        file_name: &str,
+        private_key: &str,
+    ) -> Result<Vec<u8>> {
+        let read_file = self
+            .file_upload_store
+            .open_attached_file(id, file_name)
+            .await?;
+        let decrypted = util::crypto::decrypt_ecies(&read_file, private_key)?;
+        Ok(decrypted)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::service::profile_service::{MockProfileServiceApi, tests::get_profile};
+    use crate::tests::tests::{
+        MockContactStoreApiMock, MockFileUploadStoreApiMock, MockIdentityStoreApiMock,
+        MockSearchIndexStoreApiMock, TEST_NODE_ID_SECP, TEST_NODE_ID_SECP_AS_NPUB_HEX,
+        empty_address, empty_optional_address, init_test_cfg,
+    };
+    use mockall::predicate::eq;
+    use std::collections::HashMap;
+    use util::BcrKeys;
+
+    pub fn get_baseline_contact() -> Contact {
+        Contact {
+            t: ContactType::Person,
+            node_id: TEST_NODE_ID_SECP.to_owned(),
+            name: "some_name".to_string(),
+            email: "some_mail@example.com".to_string(),
+            postal_address: empty_address(),
+            date_of_birth_or_registration: None,
+            country_of_birth_or_registration: None,
+            city_of_birth_or_registration: None,
+            identification_number: None,
+            avatar_file: None,
+            proof_document_file: None,
+            nostr_relays: vec![],
+            verification_status: ContactVerificationStatus::Unverified,
+            trust_level: ContactTrustLevel::Unknown,
+            verification_evidence_file: None,
+            follows_profile: false,
+            details_issued_at: None,
+        }
+    }
+
+    fn get_service(
+        mock_storage: MockContactStoreApiMock,
+        mock_file_upload_storage: MockFileUploadStoreApiMock,
+        mock_identity_storage: MockIdentityStoreApiMock,
+    ) -> ContactService {
+        get_service_with_profiles(
+            mock_storage,
+            mock_file_upload_storage,
+            mock_identity_storage,
+            MockProfileServiceApi::new(),
+        )
+    }
+
+    fn get_service_with_profiles(
+        mock_storage: MockContactStoreApiMock,
+        mock_file_upload_storage: MockFileUploadStoreApiMock,
+        mock_identity_storage: MockIdentityStoreApiMock,
+        mock_profile_service: MockProfileServiceApi,
+    ) -> ContactService {
+        let mut search_index_store = MockSearchIndexStoreApiMock::new();
+        search_index_store
+            .expect_index_document()
+            .returning(|_| Ok(()));
+        search_index_store
+            .expect_remove_document()
+            .returning(|_, _| Ok(()));
+        ContactService::new(
+            Arc::new(mock_storage),
+            Arc::new(mock_file_upload_storage),
+            Arc::new(mock_identity_storage),
+            Arc::new(search_index_store),
+            Arc::new(mock_profile_service),
+        )
+    }
+
+    fn get_storages() -> (
+        MockContactStoreApiMock,
+        MockFileUploadStoreApiMock,
+        MockIdentityStoreApiMock,
+    ) {
+        (
+            MockContactStoreApiMock::new(),
+            MockFileUploadStoreApiMock::new(),
+            MockIdentityStoreApiMock::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn get_contacts_baseline() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get_map().returning(|| {
+            let mut contact = get_baseline_contact();
+            contact.name = "Minka".to_string();
+            let mut map = HashMap::new();
+            map.insert(TEST_NODE_ID_SECP.to_string(), contact);
+            Ok(map)
+        });
+        let result = get_service(store, file_upload_store, identity_store)
+            .get_contacts()
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(result.as_ref().unwrap().first().unwrap().name, *"Minka");
+        assert_eq!(
+            result.as_ref().unwrap().first().unwrap().node_id,
+            *TEST_NODE_ID_SECP
+        );
+    }
+
+    #[tokio::test]
+    async fn get_identity_by_node_id_baseline() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|_| {
+            let mut contact = get_baseline_contact();
+            contact.name = "Minka".to_string();
+            Ok(Some(contact))
+        });
+        let result = get_service(store, file_upload_store, identity_store)
+            .get_identity_by_node_id(TEST_NODE_ID_SECP)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(result.as_ref().unwrap().as_ref().unwrap().name, *"Minka");
+    }
+
+    #[tokio::test]
+    async fn delete_contact() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_delete().returning(|_| Ok(()));
+        let result = get_service(store, file_upload_store, identity_store)
+            .delete("some_name")
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn update_contact_calls_store() {
+        let (mut store, file_upload_store, mut identity_store) = get_storages();
+        identity_store
+            .expect_get_key_pair()
+            .returning(|| Ok(BcrKeys::new()));
+        store.expect_get().returning(|_| {
+            let contact = get_baseline_contact();
+            Ok(Some(contact))
+        });
+        store.expect_update().returning(|_, _| Ok(()));
+        let result = get_service(store, file_upload_store, identity_store)
+            .update_contact(
+                TEST_NODE_ID_SECP,
+                Some("new_name".to_string()),
+                None,
+                empty_optional_address(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn set_verification_calls_store() {
+        let (mut store, file_upload_store, mut identity_store) = get_storages();
+        identity_store
+            .expect_get_key_pair()
+            .returning(|| Ok(BcrKeys::new()));
+        store
+            .expect_get()
+            .returning(|_| Ok(Some(get_baseline_contact())));
+        store
+            .expect_update()
+            .withf(|_, contact| {
+                contact.verification_status == ContactVerificationStatus::InPerson
+                    && contact.trust_level == ContactTrustLevel::High
+            })
+            .returning(|_, _| Ok(()));
+        let result = get_service(store, file_upload_store, identity_store)
+            .set_verification(
+                TEST_NODE_ID_SECP,
+                ContactVerificationStatus::InPerson,
+                ContactTrustLevel::High,
+                None,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_verified());
+    }
+
+    #[tokio::test]
+    async fn set_verification_to_unverified_removes_evidence() {
+        let (mut store, file_upload_store, mut identity_store) = get_storages();
+        identity_store
+            .expect_get_key_pair()
+            .returning(|| Ok(BcrKeys::new()));
+        store.expect_get().returning(|_| {
+            let mut contact = get_baseline_contact();
+            contact.verification_status = ContactVerificationStatus::Document;
+            contact.verification_evidence_file = Some(File {
+                name: "passport.pdf".to_string(),
+                hash: "hash".to_string(),
+            });
+            Ok(Some(contact))
+        });
+        store
+            .expect_update()
+            .withf(|_, contact| contact.verification_evidence_file.is_none())
+            .returning(|_, _| Ok(()));
+        let result = get_service(store, file_upload_store, identity_store)
+            .set_verification(
+                TEST_NODE_ID_SECP,
+                ContactVerificationStatus::Unverified,
+                ContactTrustLevel::Low,
+                None,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert!(!result.unwrap().is_verified());
+    }
+
+    #[tokio::test]
+    async fn add_contact_calls_store() {
+        init_test_cfg();
+        let (mut store, file_upload_store, mut identity_store) = get_storages();
+        identity_store
+            .expect_get_key_pair()
+            .returning(|| Ok(BcrKeys::new()));
+        store.expect_insert().returning(|_, _| Ok(()));
+        let result = get_service(store, file_upload_store, identity_store)
+            .add_contact(
+                TEST_NODE_ID_SECP,
+                ContactType::Person,
+                "some_name".to_string(),
+                "some_email@example.com".to_string(),
+                empty_address(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn is_known_npub_calls_store() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get_map().returning(|| {
+            let contact = get_baseline_contact();
+            let mut map = HashMap::new();
+            map.insert(TEST_NODE_ID_SECP.to_string(), contact);
+            Ok(map)
+        });
+        let result = get_service(store, file_upload_store, identity_store)
+            .is_known_npub(TEST_NODE_ID_SECP_AS_NPUB_HEX)
+            .await;
+        assert!(result.is_ok());
+        assert!(result.as_ref().unwrap());
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_profile_inserts_contact() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|_| Ok(None));
+        store
+            .expect_insert()
+            .withf(|node_id, contact| {
+                node_id == TEST_NODE_ID_SECP
+                    && contact.name == "Minka"
+                    && contact.email == "minka@example.com"
+                    && contact.postal_address.city == "Vienna"
+                    && contact.nostr_relays == vec!["ws://localhost:8080".to_string()]
+                    && contact.follows_profile
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service
+            .expect_resolve()
+            .with(eq(TEST_NODE_ID_SECP))
+            .returning(|node_id| Ok(get_profile(node_id)));
+        let contact =
+            get_service_with_profiles(store, file_upload_store, identity_store, profile_service)
+                .add_contact_from_profile(TEST_NODE_ID_SECP, "minka@example.com".to_string())
+                .await
+                .unwrap();
+        assert_eq!(contact.node_id, TEST_NODE_ID_SECP);
+        assert_eq!(contact.t, ContactType::Person);
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_profile_requires_full_address() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|_| Ok(None));
+        store.expect_insert().never();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service.expect_resolve().returning(|node_id| {
+            let mut profile = get_profile(node_id);
+            profile.postal_address = empty_optional_address();
+            Ok(profile)
+        });
+        let result =
+            get_service_with_profiles(store, file_upload_store, identity_store, profile_service)
+                .add_contact_from_profile(TEST_NODE_ID_SECP, "minka@example.com".to_string())
+                .await;
+        assert!(matches!(result, Err(crate::service::Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_profile_rejects_known_contact_and_missing_email() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store
+            .expect_get()
+            .returning(|_| Ok(Some(get_baseline_contact())));
+        store.expect_insert().never();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service
+            .expect_resolve()
+            .returning(|node_id| Ok(get_profile(node_id)));
+        let service =
+            get_service_with_profiles(store, file_upload_store, identity_store, profile_service);
+
+        let result = service
+            .add_contact_from_profile(TEST_NODE_ID_SECP, "minka@example.com".to_string())
+            .await;
+        assert!(matches!(result, Err(crate::service::Error::Validation(_))));
+        let result = service
+            .add_contact_from_profile(TEST_NODE_ID_SECP, " ".to_string())
+            .await;
+        assert!(matches!(result, Err(crate::service::Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn refresh_contact_applies_profile() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store
+            .expect_get()
+            .returning(|_| Ok(Some(get_baseline_contact())));
+        store
+            .expect_update()
+            .withf(|_, contact| {
+                contact.name == "Minka"
+                    && contact.email == "some_mail@example.com"
+                    && contact.postal_address.address == "Kärntner Straße 1"
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service
+            .expect_resolve()
+            .returning(|node_id| Ok(get_profile(node_id)));
+        let contact =
+            get_service_with_profiles(store, file_upload_store, identity_store, profile_service)
+                .refresh_contact(TEST_NODE_ID_SECP)
+                .await
+                .unwrap();
+        assert_eq!(contact.name, "Minka");
+    }
+
+    #[tokio::test]
+    async fn refresh_contact_resets_verification_if_details_changed() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|_| {
+            let mut contact = get_baseline_contact();
+            contact.verification_status = ContactVerificationStatus::InPerson;
+            Ok(Some(contact))
+        });
+        store
+            .expect_update()
+            .withf(|_, contact| {
+                contact.verification_status == ContactVerificationStatus::Unverified
+                    && contact.verification_evidence_file.is_none()
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service
+            .expect_resolve()
+            .returning(|node_id| Ok(get_profile(node_id)));
+        get_service_with_profiles(store, file_upload_store, identity_store, profile_service)
+            .refresh_contact(TEST_NODE_ID_SECP)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn refresh_contact_keeps_verification_if_details_are_unchanged() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|node_id| {
+            let profile = get_profile(node_id);
+            let mut contact = get_baseline_contact();
+            contact.t = profile.t;
+            contact.name = profile.name;
+            contact.postal_address = profile.postal_address.to_full_postal_address().unwrap();
+            contact.verification_status = ContactVerificationStatus::Document;
+            Ok(Some(contact))
+        });
+        store
+            .expect_update()
+            .withf(|_, contact| contact.verification_status == ContactVerificationStatus::Document)
+            .returning(|_, _| Ok(()))
+            .once();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service
+            .expect_resolve()
+            .returning(|node_id| Ok(get_profile(node_id)));
+        get_service_with_profiles(store, file_upload_store, identity_store, profile_service)
+            .refresh_contact(TEST_NODE_ID_SECP)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn refresh_contacts_skips_contacts_without_profile() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get_map().returning(|| {
+            let mut contact = get_baseline_contact();
+            contact.follows_profile = true;
+            Ok(HashMap::from([(TEST_NODE_ID_SECP.to_string(), contact)]))
+        });
+        store.expect_update().never();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service
+            .expect_resolve()
+            .returning(|_| Err(crate::service::Error::NotFound));
+        get_service_with_profiles(store, file_upload_store, identity_store, profile_service)
+            .refresh_contacts()
+            .await
+            .expect("refresh failed");
+    }
+
+    #[tokio::test]
+    async fn refresh_contacts_keeps_local_edits_and_continues_after_errors() {
+        let edited = BcrKeys::new().get_public_key();
+        let failing = BcrKeys::new().get_public_key();
+        let following = BcrKeys::new().get_public_key();
+        let contacts: HashMap<String, Contact> = [
+            (edited.clone(), false),
+            (failing.clone(), true),
+            (following.clone(), true),
+        ]
+        .into_iter()
+        .map(|(node_id, follows_profile)| {
+            let mut contact = get_baseline_contact();
+            contact.node_id = node_id.clone();
+            contact.follows_profile = follows_profile;
+            (node_id, contact)
+        })
+        .collect();
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store
+            .expect_get_map()
+            .returning(move || Ok(contacts.clone()));
+        let failing_id = failing.clone();
+        store
+            .expect_update()
+            .withf(move |node_id, _| node_id == failing_id)
+            .returning(|_, _| {
+                Err(crate::persistence::Error::NoSuchEntity(
+                    "contact".to_string(),
+                    "failing".to_string(),
+                ))
+            })
+            .once();
+        let following_id = following.clone();
+        store
+            .expect_update()
+            .withf(move |node_id, contact| {
+                node_id == following_id && contact.name == "Minka" && contact.follows_profile
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        let mut profile_service = MockProfileServiceApi::new();
+        profile_service
+            .expect_resolve()
+            .withf(move |node_id| node_id != edited)
+            .returning(|node_id| Ok(get_profile(node_id)))
+            .times(2);
+        get_service_with_profiles(store, file_upload_store, identity_store, profile_service)
+            .refresh_contacts()
+            .await
+            .expect("refresh failed");
+    }
+
+    #[tokio::test]
+    async fn update_contact_stops_following_profile() {
+        let (mut store, file_upload_store, mut identity_store) = get_storages();
+        store.expect_get().returning(|_| {
+            let mut contact = get_baseline_contact();
+            contact.follows_profile = true;
+            Ok(Some(contact))
+        });
+        store
+            .expect_update()
+            .withf(|_, contact| contact.name == "Local name" && !contact.follows_profile)
+            .returning(|_, _| Ok(()))
+            .once();
+        identity_store
+            .expect_get_key_pair()
+            .returning(|| Ok(BcrKeys::new()));
+        get_service(store, file_upload_store, identity_store)
+            .update_contact(
+                TEST_NODE_ID_SECP,
+                Some("Local name".to_string()),
+                None,
+                empty_optional_address(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("contact was not updated");
+    }
+
+    fn get_card_uri(keys: &BcrKeys) -> String {
+        get_card_uri_issued_at(keys, 2000)
+    }
+
+    fn get_card_uri_issued_at(keys: &BcrKeys, issued_at: u64) -> String {
+        let content = crate::data::contact_card::ContactCardContent {
+            version: CONTACT_CARD_VERSION,
+            t: ContactType::Company,
+            node_id: keys.get_public_key(),
+            name: "Hayek Ltd".to_string(),
+            postal_address: PostalAddress {
+                country: "AT".to_string(),
+                city: "Vienna".to_string(),
+                zip: None,
+                address: "Kärntner Straße 1".to_string(),
+            },
+            email: "office@example.com".to_string(),
+            nostr_relays: vec!["ws://localhost:8080".to_string()],
+            issued_at,
+        };
+        ContactCard::sign(content, keys).unwrap().to_uri()
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_card_inserts_verified_contact() {
+        let keys = BcrKeys::new();
+        let node_id = keys.get_public_key();
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|_| Ok(None));
+        store
+            .expect_insert()
+            .withf(move |id, contact| {
+                id == node_id
+                    && contact.t == ContactType::Company
+                    && contact.email == "office@example.com"
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        let contact = get_service(store, file_upload_store, identity_store)
+            .add_contact_from_card(&get_card_uri(&keys))
+            .await
+            .unwrap();
+        assert_eq!(contact.name, "Hayek Ltd");
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_card_updates_known_contact() {
+        let keys = BcrKeys::new();
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store
+            .expect_get()
+            .returning(|_| Ok(Some(get_baseline_contact())));
+        store.expect_insert().never();
+        store
+            .expect_update()
+            .withf(|_, contact| {
+                contact.name == "Hayek Ltd" && contact.details_issued_at == Some(2000)
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        get_service(store, file_upload_store, identity_store)
+            .add_contact_from_card(&get_card_uri(&keys))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_card_resets_verification_if_details_changed() {
+        let keys = BcrKeys::new();
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|_| {
+            let mut contact = get_baseline_contact();
+            contact.verification_status = ContactVerificationStatus::InPerson;
+            Ok(Some(contact))
+        });
+        store
+            .expect_update()
+            .withf(|_, contact| {
+                contact.verification_status == ContactVerificationStatus::Unverified
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        get_service(store, file_upload_store, identity_store)
+            .add_contact_from_card(&get_card_uri(&keys))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_card_rejects_older_card() {
+        let keys = BcrKeys::new();
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get().returning(|_| {
+            let mut contact = get_baseline_contact();
+            contact.details_issued_at = Some(2000);
+            Ok(Some(contact))
+        });
+        store.expect_update().never();
+        let result = get_service(store, file_upload_store, identity_store)
+            .add_contact_from_card(&get_card_uri_issued_at(&keys, 1999))
+            .await;
+        assert!(matches!(result, Err(crate::service::Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn add_contact_from_card_rejects_invalid_signature() {
+        let keys = BcrKeys::new();
+        let mut card = ContactCard::from_str(&get_card_uri(&keys)).unwrap();
+        card.content.name = "Someone else".to_string();
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_insert().never();
+        let service = get_service(store, file_upload_store, identity_store);
+
+        let result = service.add_contact_from_card(&card.to_uri()).await;
+        assert!(matches!(result, Err(crate::service::Error::Validation(_))));
+        let result = service.add_contact_from_card("bcr:invalid").await;
+        assert!(matches!(result, Err(crate::service::Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn export_vcards_writes_all_contacts() {
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get_map().returning(|| {
+            let mut company = get_baseline_contact();
+            company.node_id = BcrKeys::new().get_public_key();
+            company.t = ContactType::Company;
+            Ok(HashMap::from([
+                (TEST_NODE_ID_SECP.to_string(), get_baseline_contact()),
+                (company.node_id.clone(), company),
+            ]))
+        });
+        let vcards = get_service(store, file_upload_store, identity_store)
+            .export_vcards()
+            .await
+            .unwrap();
+        assert_eq!(vcards.matches("BEGIN:VCARD").count(), 2);
+        assert!(vcards.contains(&format!("X-BCR-NODE-ID:{TEST_NODE_ID_SECP}")));
+    }
+
+    #[tokio::test]
+    async fn import_vcards_reports_duplicates_and_invalid_node_ids() {
+        init_test_cfg();
+        let new_node_id = BcrKeys::new().get_public_key();
+        let vcard = |node_id: &str| {
+            format!(
+                "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Minka\r\nEMAIL:minka@example.com\r\nADR:;;Street 1;Vienna;;1010;AT\r\nX-BCR-NODE-ID:{node_id}\r\nEND:VCARD\r\n"
+            )
+        };
+        let vcards = [
+            vcard(&new_node_id),
+            vcard(TEST_NODE_ID_SECP),
+            vcard(&new_node_id),
+            vcard("invalid"),
+        ]
+        .concat();
+
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get_map().returning(|| {
+            Ok(HashMap::from([(
+                TEST_NODE_ID_SECP.to_string(),
+                get_baseline_contact(),
+            )]))
+        });
+        let expected_node_id = new_node_id.clone();
+        store
+            .expect_insert()
+            .withf(move |node_id, contact| {
+                node_id == expected_node_id
+                    && contact.postal_address.city == "Vienna"
+                    && !contact.nostr_relays.is_empty()
+            })
+            .returning(|_, _| Ok(()))
+            .once();
+        let result = get_service(store, file_upload_store, identity_store)
+            .import_vcards(&vcards)
+            .await
+            .unwrap();
+
+        assert_eq!(result.imported, vec![new_node_id.clone()]);
+        assert_eq!(
+            result.duplicates,
+            vec![TEST_NODE_ID_SECP.to_string(), new_node_id]
+        );
+        assert_eq!(result.rejected.len(), 1);
+        assert_eq!(result.rejected[0].position, 4);
+        assert_eq!(result.rejected[0].name, Some("Minka".to_string()));
+    }
+
+    #[tokio::test]
+    async fn import_vcards_rejects_cards_without_email() {
+        init_test_cfg();
+        let vcards = format!(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Minka\r\nADR:;;Street 1;Vienna;;1010;AT\r\nX-BCR-NODE-ID:{}\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Minka\r\nEMAIL: \r\nADR:;;Street 1;Vienna;;1010;AT\r\nX-BCR-NODE-ID:{}\r\nEND:VCARD\r\n",
+            BcrKeys::new().get_public_key(),
+            BcrKeys::new().get_public_key()
+        );
+
+        let (mut store, file_upload_store, identity_store) = get_storages();
+        store.expect_get_map().returning(|| Ok(HashMap::new()));
+        store.expect_insert().never();
+        let result = get_service(store, file_upload_store, identity_store)
+            .import_vcards(&vcards)
+            .await
+            .unwrap();
+
+        assert!(result.imported.is_empty());
+        assert_eq!(result.rejected.len(), 2);
+        assert!(
+            result
+                .rejected
+                .iter()
+                .all(|rejected| rejected.reason == "EMAIL is missing")
+        );
+    }
+}
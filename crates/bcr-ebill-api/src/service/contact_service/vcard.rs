@@ -0,0 +1,340 @@
+use crate::data::{
+    PostalAddress,
+    contact::{Contact, ContactTrustLevel, ContactType, ContactVerificationStatus},
+};
+
+const CRLF: &str = "\r\n";
+/// Content lines longer than this many octets are folded, as recommended by RFC 6350
+const MAX_LINE_OCTETS: usize = 75;
+
+const NODE_ID: &str = "X-BCR-NODE-ID";
+const NOSTR_RELAY: &str = "X-BCR-NOSTR-RELAY";
+const IDENTIFICATION_NUMBER: &str = "X-BCR-IDENTIFICATION-NUMBER";
+const COUNTRY_OF_BIRTH_OR_REGISTRATION: &str = "X-BCR-COUNTRY-OF-BIRTH-OR-REGISTRATION";
+const CITY_OF_BIRTH_OR_REGISTRATION: &str = "X-BCR-CITY-OF-BIRTH-OR-REGISTRATION";
+
+/// A contact read from a vCard, which isn't validated yet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct VCardEntry {
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    /// The street address, city, postal code and country of the first `ADR`
+    pub address: Option<(String, String, String, String)>,
+    pub date_of_birth_or_registration: Option<String>,
+    pub country_of_birth_or_registration: Option<String>,
+    pub city_of_birth_or_registration: Option<String>,
+    pub identification_number: Option<String>,
+    pub node_id: Option<String>,
+    pub nostr_relays: Vec<String>,
+}
+
+impl VCardEntry {
+    /// Maps the entry to a contact, failing with the reason, if a required field is missing.
+    /// The node id isn't validated here
+    pub fn into_contact(self) -> Result<Contact, String> {
+        let node_id = non_empty(self.node_id).ok_or(format!("{NODE_ID} is missing"))?;
+        let name = non_empty(self.name).ok_or("FN is missing")?;
+        let email = non_empty(self.email).ok_or("EMAIL is missing")?;
+        let t = match self.kind.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("individual") => ContactType::Person,
+            Some("org") => ContactType::Company,
+            Some(kind) => return Err(format!("KIND {kind} is not supported")),
+        };
+        let postal_address = match self.address {
+            Some((address, city, zip, country))
+                if !address.is_empty() && !city.is_empty() && !country.is_empty() =>
+            {
+                PostalAddress {
+                    country,
+                    city,
+                    zip: non_empty(Some(zip)),
+                    address,
+                }
+            }
+            _ => return Err("ADR with street address, city and country is missing".to_owned()),
+        };
+        Ok(Contact {
+            t,
+            node_id,
+            name,
+            email,
+            postal_address,
+            date_of_birth_or_registration: non_empty(self.date_of_birth_or_registration),
+            country_of_birth_or_registration: non_empty(self.country_of_birth_or_registration),
+            city_of_birth_or_registration: non_empty(self.city_of_birth_or_registration),
+            identification_number: non_empty(self.identification_number),
+            avatar_file: None,
+            proof_document_file: None,
+            nostr_relays: self.nostr_relays,
+            verification_status: ContactVerificationStatus::Unverified,
+            trust_level: ContactTrustLevel::Unknown,
+            verification_evidence_file: None,
+            follows_profile: false,
+            details_issued_at: None,
+        })
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+/// Writes the contacts as vCard 4.0. The node id, relays and the fields without a standard
+/// property are written as `X-BCR-` extensions
+pub(super) fn write_vcards(contacts: &[Contact]) -> String {
+    let mut result = String::new();
+    for contact in contacts {
+        let kind = match contact.t {
+            ContactType::Person => "individual",
+            ContactType::Company => "org",
+        };
+        let address = &contact.postal_address;
+        let mut lines = vec![
+            "BEGIN:VCARD".to_owned(),
+            "VERSION:4.0".to_owned(),
+            format!("KIND:{kind}"),
+            format!("FN:{}", escape(&contact.name)),
+        ];
+        if contact.t == ContactType::Company {
+            lines.push(format!("ORG:{}", escape(&contact.name)));
+        }
+        if !contact.email.is_empty() {
+            lines.push(format!("EMAIL:{}", escape(&contact.email)));
+        }
+        lines.push(format!(
+            "ADR:;;{};{};;{};{}",
+            escape(&address.address),
+            escape(&address.city),
+            escape(address.zip.as_deref().unwrap_or_default()),
+            escape(&address.country)
+        ));
+        let optional_fields = [
+            ("BDAY", &contact.date_of_birth_or_registration),
+            (
+                COUNTRY_OF_BIRTH_OR_REGISTRATION,
+                &contact.country_of_birth_or_registration,
+            ),
+            (
+                CITY_OF_BIRTH_OR_REGISTRATION,
+                &contact.city_of_birth_or_registration,
+            ),
+            (IDENTIFICATION_NUMBER, &contact.identification_number),
+        ];
+        for (property, value) in optional_fields {
+            if let Some(value) = value {
+                lines.push(format!("{property}:{}", escape(value)));
+            }
+        }
+        lines.push(format!("{NODE_ID}:{}", escape(&contact.node_id)));
+        for relay in &contact.nostr_relays {
+            lines.push(format!("{NOSTR_RELAY}:{}", escape(relay)));
+        }
+        lines.push("END:VCARD".to_owned());
+
+        for line in lines {
+            result.push_str(&fold(&line));
+            result.push_str(CRLF);
+        }
+    }
+    result
+}
+
+/// Reads all vCards of the input. Unknown properties and parameters are ignored, the
+/// version isn't checked, so address book exports of older versions can be imported as well
+pub(super) fn read_vcards(input: &str) -> Vec<VCardEntry> {
+    let mut entries = vec![];
+    let mut current: Option<VCardEntry> = None;
+    for line in unfold(input) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // drop the group prefix and the parameters, e.g. `item1.EMAIL;TYPE=work`
+        let name = name.split(';').next().unwrap_or_default();
+        let name = name.rsplit('.').next().unwrap_or_default().to_uppercase();
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VCARD") => {
+                current = Some(VCardEntry::default());
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VCARD") => {
+                entries.extend(current.take());
+            }
+            ("KIND", Some(entry)) => entry.kind = Some(unescape(value)),
+            ("FN", Some(entry)) => entry.name = Some(unescape(value)),
+            ("EMAIL", Some(entry)) if entry.email.is_none() => entry.email = Some(unescape(value)),
+            ("ADR", Some(entry)) if entry.address.is_none() => {
+                // post office box; extended address; street; city; region; postal code; country
+                let components: Vec<String> = split_components(value);
+                let component = |idx: usize| components.get(idx).cloned().unwrap_or_default();
+                entry.address = Some((component(2), component(3), component(5), component(6)));
+            }
+            ("BDAY", Some(entry)) => entry.date_of_birth_or_registration = Some(unescape(value)),
+            (COUNTRY_OF_BIRTH_OR_REGISTRATION, Some(entry)) => {
+                entry.country_of_birth_or_registration = Some(unescape(value))
+            }
+            (CITY_OF_BIRTH_OR_REGISTRATION, Some(entry)) => {
+                entry.city_of_birth_or_registration = Some(unescape(value))
+            }
+            (IDENTIFICATION_NUMBER, Some(entry)) => {
+                entry.identification_number = Some(unescape(value))
+            }
+            (NODE_ID, Some(entry)) => entry.node_id = Some(unescape(value).trim().to_owned()),
+            (NOSTR_RELAY, Some(entry)) => entry.nostr_relays.push(unescape(value)),
+            _ => (),
+        }
+    }
+    entries
+}
+
+/// Escapes a text value as defined in RFC 6350, section 3.4
+fn escape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            ',' => result.push_str("\\,"),
+            ';' => result.push_str("\\;"),
+            '\n' => result.push_str("\\n"),
+            '\r' => (),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(escaped) => result.push(escaped),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Splits a structured value at unescaped semicolons and unescapes the components
+fn split_components(value: &str) -> Vec<String> {
+    let mut components = vec![];
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            ';' => components.push(unescape(&std::mem::take(&mut current))),
+            c => current.push(c),
+        }
+    }
+    components.push(unescape(&current));
+    components
+}
+
+/// Folds the line into lines of at most `MAX_LINE_OCTETS` octets, without splitting characters
+fn fold(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut line_octets = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            result.push_str(CRLF);
+            result.push(' ');
+            line_octets = 1;
+        }
+        result.push(c);
+        line_octets += c.len_utf8();
+    }
+    result
+}
+
+/// Joins folded lines, which continue with a leading space or tab
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in input.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::contact_service::tests::get_baseline_contact;
+
+    #[test]
+    fn contacts_roundtrip() {
+        let mut person = get_baseline_contact();
+        person.postal_address = PostalAddress {
+            country: "AT".to_owned(),
+            city: "Vienna".to_owned(),
+            zip: Some("1010".to_owned()),
+            address: "Kärntner Straße 1; Top 3, 2nd floor".to_owned(),
+        };
+        person.identification_number = Some("AT-123".to_owned());
+        person.date_of_birth_or_registration = Some("1899-05-08".to_owned());
+        person.nostr_relays = vec![
+            "wss://relay.example.com".to_owned(),
+            "wss://other.example.com".to_owned(),
+        ];
+        let mut company = person.clone();
+        company.t = ContactType::Company;
+        company.name = "A company with a very long name, which doesn't fit on a single line".into();
+        company.postal_address.zip = None;
+        company.identification_number = None;
+
+        let vcards = write_vcards(&[person.clone(), company.clone()]);
+        assert!(vcards.split(CRLF).all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(vcards.contains("KIND:org\r\n"));
+        assert!(vcards.contains("X-BCR-IDENTIFICATION-NUMBER:AT-123\r\n"));
+
+        let contacts: Vec<Contact> = read_vcards(&vcards)
+            .into_iter()
+            .map(|entry| entry.into_contact().unwrap())
+            .collect();
+        assert_eq!(contacts.len(), 2);
+        for (read, written) in contacts.iter().zip([person, company]) {
+            assert_eq!(read.t, written.t);
+            assert_eq!(read.name, written.name);
+            assert_eq!(read.email, written.email);
+            assert_eq!(read.postal_address, written.postal_address);
+            assert_eq!(read.identification_number, written.identification_number);
+            assert_eq!(
+                read.date_of_birth_or_registration,
+                written.date_of_birth_or_registration
+            );
+            assert_eq!(read.node_id, written.node_id);
+            assert_eq!(read.nostr_relays, written.nostr_relays);
+        }
+    }
+
+    #[test]
+    fn reads_vcards_of_other_address_books() {
+        let input = "BEGIN:VCARD\nVERSION:3.0\nN:Mises;Ludwig;;;\nFN:Ludwig Mises\n\
+            item1.EMAIL;TYPE=INTERNET,WORK:ludwig@example.com\n\
+            ADR;TYPE=HOME:;;Kärntner Straße 1;Vienna;;1010;AT\n\
+            X-BCR-NODE-ID:03205b8dec12bc9e879f5b517aa32192a2550e88adcee3e54ec2c7294802568fe\n 2\n\
+            END:VCARD\nBEGIN:VCARD\nVERSION:4.0\nFN:Without node id\nEND:VCARD\n";
+        let entries = read_vcards(input);
+        assert_eq!(entries.len(), 2);
+
+        let contact = entries[0].clone().into_contact().unwrap();
+        assert_eq!(contact.t, ContactType::Person);
+        assert_eq!(contact.email, "ludwig@example.com");
+        assert_eq!(contact.postal_address.zip, Some("1010".to_owned()));
+        assert_eq!(
+            contact.node_id,
+            "03205b8dec12bc9e879f5b517aa32192a2550e88adcee3e54ec2c7294802568fe2"
+        );
+        assert!(entries[1].clone().into_contact().is_err());
+    }
+}
@@ -12,6 +12,10 @@ pub trait FileUploadServiceApi: Send + Sync {
     /// validates the given uploaded file
     async fn validate_attached_file(&self, file: &dyn util::file::UploadFileHandler) -> Result<()>;
 
+    /// validates the given uploaded data file, e.g. a list of bills to issue, which is read
+    /// instead of being attached, so only its size and name are checked and not its content type
+    async fn validate_data_file(&self, file: &dyn util::file::UploadFileHandler) -> Result<()>;
+
     /// uploads files
     async fn upload_files(
         &self,
@@ -31,11 +35,8 @@ impl FileUploadService {
     pub fn new(file_upload_store: Arc<dyn FileUploadStoreApi>) -> Self {
         Self { file_upload_store }
     }
-}
 
-#[async_trait]
-impl FileUploadServiceApi for FileUploadService {
-    async fn validate_attached_file(&self, file: &dyn util::file::UploadFileHandler) -> Result<()> {
+    fn validate_size_and_name(&self, file: &dyn util::file::UploadFileHandler) -> Result<String> {
         if file.len() > MAX_FILE_SIZE_BYTES as u64 {
             return Err(Error::Validation(format!(
                 "Maximum file size is {} bytes",
@@ -56,6 +57,14 @@ impl FileUploadServiceApi for FileUploadService {
                 MAX_FILE_NAME_CHARACTERS
             )));
         }
+        Ok(name)
+    }
+}
+
+#[async_trait]
+impl FileUploadServiceApi for FileUploadService {
+    async fn validate_attached_file(&self, file: &dyn util::file::UploadFileHandler) -> Result<()> {
+        let name = self.validate_size_and_name(file)?;
 
         let detected_type = match file.detect_content_type().await.map_err(|e| {
             error!("Could not detect content type for file {name}: {e}");
@@ -77,6 +86,11 @@ impl FileUploadServiceApi for FileUploadService {
         Ok(())
     }
 
+    async fn validate_data_file(&self, file: &dyn util::file::UploadFileHandler) -> Result<()> {
+        self.validate_size_and_name(file)?;
+        Ok(())
+    }
+
     async fn upload_files(
         &self,
         files: Vec<&dyn util::file::UploadFileHandler>,
@@ -254,6 +268,22 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[tokio::test]
+    async fn validate_data_file_checks_file_size_but_not_type() {
+        let mut file = MockUploadFileHandler::new();
+        file.expect_len()
+            .returning(move || MAX_FILE_SIZE_BYTES as u64 * 2);
+        let service = get_service(MockFileUploadStoreApiMock::new());
+        assert!(service.validate_data_file(&file).await.is_err());
+
+        let mut file = MockUploadFileHandler::new();
+        file.expect_len().returning(move || 100);
+        file.expect_name()
+            .returning(move || Some(String::from("bills.csv")));
+        file.expect_detect_content_type().never();
+        assert!(service.validate_data_file(&file).await.is_ok());
+    }
+
     #[tokio::test]
     async fn validate_attached_file_checks_file_name() {
         let mut file = MockUploadFileHandler::new();
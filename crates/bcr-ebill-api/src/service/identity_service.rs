@@ -6,6 +6,8 @@ use crate::blockchain::Blockchain;
 use crate::blockchain::identity::{IdentityBlock, IdentityBlockchain, IdentityUpdateBlockData};
 use crate::data::{
     File, OptionalPostalAddress,
+    contact::ContactType,
+    contact_card::{CONTACT_CARD_VERSION, ContactCard, ContactCardContent},
     identity::{Identity, IdentityWithAll},
 };
 use crate::persistence::file_upload::FileUploadStoreApi;
@@ -36,6 +38,8 @@ pub trait IdentityServiceApi: Send + Sync {
     async fn get_identity(&self) -> Result<Identity>;
     /// Checks if the identity has been created
     async fn identity_exists(&self) -> bool;
+    /// Returns the contact card of the identity, signed with its keys
+    async fn get_contact_card(&self) -> Result<ContactCard>;
     /// Creates the identity
     async fn create_identity(
         &self,
@@ -267,6 +271,31 @@ impl IdentityServiceApi for IdentityService {
         self.store.exists().await
     }
 
+    async fn get_contact_card(&self) -> Result<ContactCard> {
+        let identity = self.store.get().await?;
+        let keys = self.store.get_key_pair().await?;
+        // contacts need a full address, so a card without one couldn't be imported
+        let postal_address = identity
+            .postal_address
+            .to_full_postal_address()
+            .ok_or_else(|| {
+                super::Error::Validation(
+                    "The identity needs a complete postal address for a contact card".to_string(),
+                )
+            })?;
+        let content = ContactCardContent {
+            version: CONTACT_CARD_VERSION,
+            t: ContactType::Person,
+            node_id: identity.node_id,
+            name: identity.name,
+            postal_address,
+            email: identity.email,
+            nostr_relays: get_config().nostr_relays.clone(),
+            issued_at: util::date::now().timestamp() as u64,
+        };
+        Ok(ContactCard::sign(content, &keys)?)
+    }
+
     async fn create_identity(
         &self,
         name: String,
@@ -310,7 +339,7 @@ impl IdentityServiceApi for IdentityService {
             identification_number,
             profile_picture_file,
             identity_document_file,
-            nostr_relay: Some(get_config().nostr_relay.to_owned()),
+            nostr_relay: get_config().nostr_relays.first().cloned(),
         };
 
         // create new identity chain and persist it
@@ -557,6 +586,46 @@ mod tests {
         assert_eq!(res.unwrap(), identity);
     }
 
+    #[tokio::test]
+    async fn get_contact_card_is_signed_by_identity() {
+        init_test_cfg();
+        let keys = BcrKeys::new();
+        let node_id = keys.get_public_key();
+        let mut storage = MockIdentityStoreApiMock::new();
+        storage.expect_get().returning(move || {
+            let mut identity = empty_identity();
+            identity.node_id = node_id.clone();
+            identity.name = "Minka".to_string();
+            identity.postal_address = OptionalPostalAddress {
+                country: Some("AT".to_string()),
+                city: Some("Vienna".to_string()),
+                zip: None,
+                address: Some("Kärntner Straße 1".to_string()),
+            };
+            Ok(identity)
+        });
+        storage
+            .expect_get_key_pair()
+            .returning(move || Ok(keys.clone()));
+
+        let card = get_service(storage).get_contact_card().await.unwrap();
+        assert_eq!(card.content.name, "Minka");
+        assert_eq!(card.content.postal_address.city, "Vienna");
+        assert!(card.verify().unwrap());
+    }
+
+    #[tokio::test]
+    async fn get_contact_card_requires_full_address() {
+        let mut storage = MockIdentityStoreApiMock::new();
+        storage.expect_get().returning(|| Ok(empty_identity()));
+        storage
+            .expect_get_key_pair()
+            .returning(|| Ok(BcrKeys::new()));
+
+        let res = get_service(storage).get_contact_card().await;
+        assert!(matches!(res, Err(crate::service::Error::Validation(_))));
+    }
+
     #[tokio::test]
     async fn get_identity_propagates_errors() {
         let mut storage = MockIdentityStoreApiMock::new();
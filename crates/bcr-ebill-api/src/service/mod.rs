@@ -1,34 +1,46 @@
+pub mod api_token_service;
 pub mod backup_service;
 pub mod bill_service;
+pub mod bill_template_service;
 pub mod company_service;
 pub mod contact_service;
 pub mod file_upload_service;
 pub mod identity_service;
+pub mod nostr_outbox_service;
 pub mod notification_service;
+pub mod profile_service;
+pub mod quarantine_service;
 pub mod search_service;
+pub mod webhook_service;
 
 use super::Config;
 use crate::external::bitcoin::BitcoinClient;
 use crate::persistence::DbContext;
 use crate::util;
 use crate::{blockchain, external};
+use api_token_service::{ApiTokenService, ApiTokenServiceApi};
 use backup_service::{BackupService, BackupServiceApi};
 use bcr_ebill_persistence::db::SurrealDbConfig;
 use bill_service::{BillServiceApi, service::BillService};
+use bill_template_service::{BillTemplateService, BillTemplateServiceApi};
 use company_service::{CompanyService, CompanyServiceApi};
 use contact_service::{ContactService, ContactServiceApi};
 use file_upload_service::{FileUploadService, FileUploadServiceApi};
 use identity_service::{IdentityService, IdentityServiceApi};
 use log::error;
+use nostr_outbox_service::{NostrOutboxService, NostrOutboxServiceApi};
 use notification_service::push_notification::{PushApi, PushService};
 use notification_service::{
-    NostrConsumer, NotificationServiceApi, create_nostr_client, create_nostr_consumer,
-    create_notification_service,
+    NostrConsumer, NotificationServiceApi, SenderAuthorization, create_nostr_client,
+    create_nostr_consumer, create_notification_service,
 };
+use profile_service::{ProfileService, ProfileServiceApi};
+use quarantine_service::{QuarantineService, QuarantineServiceApi};
 use search_service::{SearchService, SearchServiceApi};
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::{RwLock, broadcast, watch};
+use webhook_service::{WebhookService, WebhookServiceApi};
 
 /// Generic result type
 pub type Result<T> = std::result::Result<T, Error>;
@@ -75,6 +87,10 @@ pub enum Error {
     #[error("Io error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// errors when serializing/deserializing json, e.g. for backups
+    #[error("Json error: {0}")]
+    Json(#[from] serde_json::Error),
+
     /// error returned if the given file upload id is not a temp file we have
     #[error("No file found for file upload id")]
     NoFileForFileUploadId,
@@ -96,6 +112,12 @@ pub struct ServiceContext {
     pub push_service: Arc<dyn PushApi>,
     pub current_identity: Arc<RwLock<SwitchIdentityState>>,
     pub backup_service: Arc<dyn BackupServiceApi>,
+    pub api_token_service: Arc<dyn ApiTokenServiceApi>,
+    pub webhook_service: Arc<dyn WebhookServiceApi>,
+    pub quarantine_service: Arc<dyn QuarantineServiceApi>,
+    pub nostr_outbox_service: Arc<dyn NostrOutboxServiceApi>,
+    pub profile_service: Arc<dyn ProfileServiceApi>,
+    pub bill_template_service: Arc<dyn BillTemplateServiceApi>,
 }
 
 /// A structure describing the currently selected identity between the personal and multiple
@@ -139,16 +161,48 @@ pub async fn create_service_context(
     db: DbContext,
     reboot_sender: watch::Sender<bool>,
 ) -> Result<ServiceContext> {
+    let backup_service = BackupService::new(
+        db.clone(),
+        SurrealDbConfig::new(&config.surreal_db_connection),
+        reboot_sender.clone(),
+    );
+
+    let nostr_client = create_nostr_client(&config, db.identity_store.clone()).await?;
+    let profile_service = Arc::new(ProfileService::new(
+        Arc::new(nostr_client.clone()),
+        db.identity_store.clone(),
+        db.company_store.clone(),
+        config.nostr_relays.clone(),
+    ));
+
     let contact_service = Arc::new(ContactService::new(
         db.contact_store.clone(),
         db.file_upload_store.clone(),
         db.identity_store.clone(),
+        db.search_index_store.clone(),
+        profile_service.clone(),
     ));
     let bitcoin_client = Arc::new(BitcoinClient::new());
 
-    let nostr_client = create_nostr_client(&config, db.identity_store.clone()).await?;
-    let notification_service =
-        create_notification_service(nostr_client.clone(), db.notification_store.clone()).await?;
+    let webhook_service = Arc::new(WebhookService::new(db.webhook_store));
+
+    let nostr_outbox_service =
+        NostrOutboxService::new(db.nostr_outbox_store, Arc::new(nostr_client.clone()));
+    let notification_service = create_notification_service(
+        Box::new(nostr_outbox_service.clone()),
+        db.notification_store.clone(),
+        webhook_service.clone(),
+        db.bill_store.clone(),
+        db.bill_blockchain_store.clone(),
+    )
+    .await?;
+
+    let bill_store = db.bill_store.clone();
+    let sender_authorization = Arc::new(SenderAuthorization::new(
+        db.contact_store.clone(),
+        bill_store.clone(),
+        db.bill_blockchain_store.clone(),
+    ));
 
     let bill_service = Arc::new(BillService::new(
         db.bill_store,
@@ -161,7 +215,17 @@ pub async fn create_service_context(
         db.company_chain_store.clone(),
         db.contact_store.clone(),
         db.company_store.clone(),
+        db.search_index_store.clone(),
+        db.bill_batch_store.clone(),
+        config.contact_verification_policy,
     ));
+    let bill_template_service = BillTemplateService::new(
+        db.bill_template_store,
+        bill_service.clone(),
+        db.identity_store.clone(),
+        db.company_store.clone(),
+        db.contact_store.clone(),
+    );
     let identity_service = IdentityService::new(
         db.identity_store.clone(),
         db.file_upload_store.clone(),
@@ -169,12 +233,13 @@ pub async fn create_service_context(
     );
 
     let company_service = CompanyService::new(
-        db.company_store,
+        db.company_store.clone(),
         db.file_upload_store.clone(),
         db.identity_store.clone(),
         db.contact_store,
         db.identity_chain_store,
         db.company_chain_store,
+        db.search_index_store.clone(),
     );
     let file_upload_service = FileUploadService::new(db.file_upload_store);
 
@@ -182,24 +247,31 @@ pub async fn create_service_context(
 
     let nostr_consumer = create_nostr_consumer(
         nostr_client,
-        contact_service.clone(),
+        sender_authorization,
         db.nostr_event_offset_store.clone(),
+        db.nostr_quarantine_store.clone(),
+        db.nostr_pending_event_store.clone(),
         db.notification_store.clone(),
         push_service.clone(),
+        webhook_service.clone(),
+        bill_store,
+        db.bill_blockchain_store.clone(),
+        db.identity_store.clone(),
+        db.company_store.clone(),
+        config.email.as_ref(),
     )
     .await?;
 
+    let quarantine_service =
+        QuarantineService::new(db.nostr_quarantine_store, nostr_consumer.clone());
+
+    let api_token_service = ApiTokenService::new(db.api_token_store);
+
     let search_service = SearchService::new(
         bill_service.clone(),
         contact_service.clone(),
         Arc::new(company_service.clone()),
-    );
-
-    let backup_service = BackupService::new(
-        db.backup_store.clone(),
-        db.identity_store.clone(),
-        SurrealDbConfig::new(&config.surreal_db_connection),
-        reboot_sender.clone(),
+        db.search_index_store,
     );
 
     Ok(ServiceContext {
@@ -219,5 +291,11 @@ pub async fn create_service_context(
             company: None,
         })),
         backup_service: Arc::new(backup_service),
+        api_token_service: Arc::new(api_token_service),
+        webhook_service,
+        quarantine_service: Arc::new(quarantine_service),
+        nostr_outbox_service: Arc::new(nostr_outbox_service),
+        profile_service,
+        bill_template_service: Arc::new(bill_template_service),
     })
 }
@@ -0,0 +1,267 @@
+use super::Result;
+use super::notification_service::{
+    self, EventEnvelope, NostrPublisherApi, NotificationJsonTransportApi,
+};
+use crate::data::contact::IdentityPublicData;
+use crate::persistence::nostr::{NostrOutboxEntry, NostrOutboxStatus, NostrOutboxStoreApi};
+use crate::util;
+use crate::util::retry::{self, retry_delay};
+use async_trait::async_trait;
+use log::{info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+use mockall::automock;
+
+/// How long publishing an event may take, before the attempt counts as failed
+const PUBLISH_TIMEOUT_SECONDS: u64 = 30;
+/// How many due events are retried per run
+const RETRY_BATCH_SIZE: u64 = 100;
+/// How many pending and failed events are returned for the outbox overview
+const UNSENT_LIST_SIZE: u64 = 100;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait NostrOutboxServiceApi: Send + Sync {
+    /// Returns the latest events, which weren't confirmed by a relay yet, or which were
+    /// given up, newest first
+    async fn list_unsent(&self) -> Result<Vec<NostrOutboxEntry>>;
+
+    /// Retries sending all pending events, that are due at the given timestamp
+    async fn retry_due_sends(&self, timestamp: u64) -> Result<()>;
+}
+
+/// The outbox stores every outgoing Nostr event before it's published and retries
+/// publishing it with exponential backoff, until a relay confirms it
+#[derive(Clone)]
+pub struct NostrOutboxService {
+    store: Arc<dyn NostrOutboxStoreApi>,
+    publisher: Arc<dyn NostrPublisherApi>,
+}
+
+impl NostrOutboxService {
+    pub fn new(store: Arc<dyn NostrOutboxStoreApi>, publisher: Arc<dyn NostrPublisherApi>) -> Self {
+        Self { store, publisher }
+    }
+
+    /// Attempts to publish the event once and stores the outcome. Failed attempts are retried
+    /// with exponential backoff until `retry::MAX_ATTEMPTS` is reached
+    async fn attempt_send(
+        &self,
+        mut entry: NostrOutboxEntry,
+        timestamp: u64,
+    ) -> Result<NostrOutboxEntry> {
+        entry.attempts += 1;
+        let published = tokio::time::timeout(
+            Duration::from_secs(PUBLISH_TIMEOUT_SECONDS),
+            self.publisher
+                .publish(&entry.recipient, &entry.recipient_relays, &entry.payload),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            Err(notification_service::Error::NostrNotConfirmed(
+                "publishing timed out".to_owned(),
+            ))
+        });
+        match published {
+            Ok(()) => {
+                entry.status = NostrOutboxStatus::Sent;
+                entry.sent_at = Some(timestamp);
+                entry.last_error = None;
+            }
+            Err(e) if entry.attempts >= retry::MAX_ATTEMPTS => {
+                warn!(
+                    "Giving up sending Nostr event {} to {} after {} attempts: {e}",
+                    entry.id, entry.recipient, entry.attempts
+                );
+                entry.status = NostrOutboxStatus::Failed;
+                entry.last_error = Some(e.to_string());
+            }
+            Err(e) => {
+                warn!(
+                    "Could not send Nostr event {} to {}, retrying later: {e}",
+                    entry.id, entry.recipient
+                );
+                entry.next_attempt_at = timestamp + retry_delay(entry.attempts);
+                entry.last_error = Some(e.to_string());
+            }
+        }
+        self.store.update(&entry).await?;
+        Ok(entry)
+    }
+}
+
+#[async_trait]
+impl NotificationJsonTransportApi for NostrOutboxService {
+    /// Stores the event in the outbox and attempts to send it right away. Succeeds once the
+    /// event is stored, failed attempts are retried by the outbox job
+    async fn send(
+        &self,
+        recipient: &IdentityPublicData,
+        event: EventEnvelope,
+    ) -> notification_service::Result<()> {
+        let timestamp = util::date::now().timestamp() as u64;
+        let mut entry = NostrOutboxEntry::new(
+            &util::get_uuid_v4().to_string(),
+            &recipient.node_id,
+            recipient.nostr_relays.clone(),
+            event.event_type.clone(),
+            &serde_json::to_string(&event)?,
+            timestamp,
+        );
+        // the first attempt is made right away, so the outbox job must not send it again
+        // while it's in flight
+        entry.next_attempt_at = retry::first_retry_at(timestamp, PUBLISH_TIMEOUT_SECONDS);
+        self.store.insert(&entry).await?;
+        if let Err(e) = self.attempt_send(entry, timestamp).await {
+            warn!("Could not store the send attempt of a Nostr event: {e}");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NostrOutboxServiceApi for NostrOutboxService {
+    async fn list_unsent(&self) -> Result<Vec<NostrOutboxEntry>> {
+        Ok(self.store.list_unsent(UNSENT_LIST_SIZE).await?)
+    }
+
+    async fn retry_due_sends(&self, timestamp: u64) -> Result<()> {
+        let entries = self.store.get_due(timestamp, RETRY_BATCH_SIZE).await?;
+        for entry in entries {
+            let entry = self.attempt_send(entry, timestamp).await?;
+            info!(
+                "Retried sending Nostr event {} ({} attempts): {:?}",
+                entry.id, entry.attempts, entry.status
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::notification::EventType;
+    use crate::service::notification_service::MockNostrPublisherApi;
+    use crate::tests::tests::{MockNostrOutboxStoreApiMock, identity_public_data_only_node_id};
+    use mockall::predicate::eq;
+    use serde_json::json;
+
+    fn get_entry(attempts: u32) -> NostrOutboxEntry {
+        let mut entry = NostrOutboxEntry::new(
+            "entry",
+            "recipient",
+            vec!["ws://localhost:8080".to_owned()],
+            EventType::BillSigned,
+            "{}",
+            1000,
+        );
+        entry.attempts = attempts;
+        entry
+    }
+
+    fn failing_publisher() -> MockNostrPublisherApi {
+        let mut publisher = MockNostrPublisherApi::new();
+        publisher.expect_publish().returning(|_, _, _| {
+            Err(notification_service::Error::NostrNotConfirmed(
+                "connection refused".to_owned(),
+            ))
+        });
+        publisher
+    }
+
+    #[tokio::test]
+    async fn send_stores_event_before_publishing() {
+        let mut store = MockNostrOutboxStoreApiMock::new();
+        store
+            .expect_insert()
+            .withf(|entry| {
+                entry.recipient == "recipient"
+                    && entry.status == NostrOutboxStatus::Pending
+                    && entry.attempts == 0
+                    && entry.next_attempt_at > entry.created_at
+            })
+            .returning(|_| Ok(()))
+            .once();
+        store
+            .expect_update()
+            .withf(|entry| {
+                entry.status == NostrOutboxStatus::Sent
+                    && entry.attempts == 1
+                    && entry.sent_at.is_some()
+            })
+            .returning(|_| Ok(()))
+            .once();
+        let mut publisher = MockNostrPublisherApi::new();
+        publisher
+            .expect_publish()
+            .withf(|recipient, _, message| recipient == "recipient" && message.contains("1234"))
+            .returning(|_, _, _| Ok(()))
+            .once();
+        let service = NostrOutboxService::new(Arc::new(store), Arc::new(publisher));
+
+        let event = EventEnvelope {
+            event_type: EventType::BillSigned,
+            version: "1.0".to_owned(),
+            node_id: "recipient".to_owned(),
+            data: json!({ "bill_id": "1234" }),
+        };
+        service
+            .send(
+                &identity_public_data_only_node_id("recipient".to_owned()),
+                event,
+            )
+            .await
+            .expect("event was not sent");
+    }
+
+    #[tokio::test]
+    async fn failed_sends_are_retried_with_backoff() {
+        let mut store = MockNostrOutboxStoreApiMock::new();
+        store.expect_update().returning(|_| Ok(()));
+        let service = NostrOutboxService::new(Arc::new(store), Arc::new(failing_publisher()));
+
+        let entry = service.attempt_send(get_entry(0), 1000).await.unwrap();
+        assert_eq!(entry.status, NostrOutboxStatus::Pending);
+        assert_eq!(entry.attempts, 1);
+        assert_eq!(entry.next_attempt_at, 1030);
+        assert!(entry.last_error.unwrap().contains("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn sends_are_given_up_after_max_attempts() {
+        let mut store = MockNostrOutboxStoreApiMock::new();
+        store.expect_update().returning(|_| Ok(()));
+        let service = NostrOutboxService::new(Arc::new(store), Arc::new(failing_publisher()));
+
+        let entry = service
+            .attempt_send(get_entry(retry::MAX_ATTEMPTS - 1), 1000)
+            .await
+            .unwrap();
+        assert_eq!(entry.status, NostrOutboxStatus::Failed);
+        assert_eq!(entry.attempts, retry::MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn retry_due_sends_publishes_pending_ones() {
+        let mut store = MockNostrOutboxStoreApiMock::new();
+        store
+            .expect_get_due()
+            .with(eq(2000), eq(RETRY_BATCH_SIZE))
+            .returning(|_, _| Ok(vec![get_entry(1)]));
+        store
+            .expect_update()
+            .withf(|entry| entry.status == NostrOutboxStatus::Sent && entry.attempts == 2)
+            .returning(|_| Ok(()))
+            .once();
+        let mut publisher = MockNostrPublisherApi::new();
+        publisher
+            .expect_publish()
+            .returning(|_, _, _| Ok(()))
+            .once();
+        let service = NostrOutboxService::new(Arc::new(store), Arc::new(publisher));
+        service.retry_due_sends(2000).await.expect("retry failed");
+    }
+}
@@ -2,33 +2,230 @@ use super::{NotificationType, Result};
 use std::sync::Arc;
 
 use crate::{
+    blockchain::{Blockchain, bill::BillOpCode},
     data::notification::Notification,
-    persistence::notification::NotificationStoreApi,
+    persistence::{
+        bill::{BillChainStoreApi, BillStoreApi},
+        nostr::{NostrPendingEventStoreApi, PendingEvent},
+        notification::NotificationStoreApi,
+    },
     service::notification_service::event::{BillActionEventPayload, Event},
+    util,
 };
 
 use super::{
-    EventEnvelope, EventType, handler::NotificationHandlerApi, push_notification::PushApi,
+    EventEnvelope, EventType,
+    bill_chain_sync::{BillChainSyncApi, get_attached_chain_bundle},
+    handler::NotificationHandlerApi,
+    push_notification::PushApi,
 };
 use async_trait::async_trait;
+use log::{error, info, warn};
+
+/// How long an event is kept pending, waiting for the chain of its bill to confirm it, before
+/// it's surfaced as unverified
+const PENDING_EVENT_MAX_AGE_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// The result of checking a bill event against our copy of the bill's chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChainCheck {
+    Consistent,
+    /// We don't know the bill, or it has no block matching the event yet
+    Behind,
+}
 
 #[derive(Clone)]
 pub struct BillActionEventHandler {
     notification_store: Arc<dyn NotificationStoreApi>,
     push_service: Arc<dyn PushApi>,
+    bill_store: Arc<dyn BillStoreApi>,
+    bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+    chain_sync: Arc<dyn BillChainSyncApi>,
+    pending_event_store: Arc<dyn NostrPendingEventStoreApi>,
 }
 
 impl BillActionEventHandler {
     pub fn new(
         notification_store: Arc<dyn NotificationStoreApi>,
         push_service: Arc<dyn PushApi>,
+        bill_store: Arc<dyn BillStoreApi>,
+        bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+        chain_sync: Arc<dyn BillChainSyncApi>,
+        pending_event_store: Arc<dyn NostrPendingEventStoreApi>,
     ) -> Self {
         Self {
             notification_store,
             push_service,
+            bill_store,
+            bill_blockchain_store,
+            chain_sync,
+            pending_event_store,
+        }
+    }
+
+    /// The op codes of the block the action the event was sent for added to the chain. None
+    /// for events, that aren't tied to a block, like payments and quotes.
+    fn expected_op_codes(&self, event_type: &EventType) -> Option<&'static [BillOpCode]> {
+        match event_type {
+            EventType::BillSigned => Some(&[BillOpCode::Issue]),
+            EventType::BillAccepted => Some(&[BillOpCode::Accept]),
+            EventType::BillAcceptanceRequested | EventType::BillAcceptanceTimeout => {
+                Some(&[BillOpCode::RequestToAccept])
+            }
+            EventType::BillAcceptanceRejected => Some(&[BillOpCode::RejectToAccept]),
+            EventType::BillPaymentRequested | EventType::BillPaymentTimeout => {
+                Some(&[BillOpCode::RequestToPay])
+            }
+            EventType::BillPaymentRejected => Some(&[BillOpCode::RejectToPay]),
+            EventType::BillAcceptanceRecourse
+            | EventType::BillPaymentRecourse
+            | EventType::BillRecourseTimeout => Some(&[BillOpCode::RequestRecourse]),
+            EventType::BillRecourseRejected => Some(&[BillOpCode::RejectToPayRecourse]),
+            EventType::BillRecoursePaid => Some(&[BillOpCode::Recourse]),
+            EventType::BillSellOffered => Some(&[BillOpCode::OfferToSell]),
+            EventType::BillBuyingRejected => Some(&[BillOpCode::RejectToBuy]),
+            EventType::BillSold => Some(&[BillOpCode::Sell]),
+            EventType::BillEndorsed => Some(&[BillOpCode::Endorse]),
+            EventType::BillMintingRequested => Some(&[BillOpCode::Mint]),
+            EventType::BillPaid | EventType::BillNewQuote | EventType::BillQuoteApproved => None,
         }
     }
 
+    /// Checks, that we know the bill and that its chain has a block for the action of the
+    /// event. Events for older actions are still consistent, since blocks are never removed.
+    /// Whether the sender is allowed to send the event, was already checked by the sender
+    /// authorization
+    async fn check_chain(&self, event: &Event<BillActionEventPayload>) -> Result<ChainCheck> {
+        let bill_id = &event.data.bill_id;
+        if !self.bill_store.exists(bill_id).await {
+            return Ok(ChainCheck::Behind);
+        }
+        let chain = self.bill_blockchain_store.get_chain(bill_id).await?;
+        if let Some(op_codes) = self.expected_op_codes(&event.event_type)
+            && !chain
+                .blocks()
+                .iter()
+                .any(|block| op_codes.contains(&block.op_code))
+        {
+            return Ok(ChainCheck::Behind);
+        }
+        Ok(ChainCheck::Consistent)
+    }
+
+    /// Whether the event is consistent with our chain. If the chain is behind and the sender
+    /// attached the chain of the bill, our chain is synced from it and checked again.
+    async fn is_consistent_with_chain(
+        &self,
+        event: &Event<BillActionEventPayload>,
+        envelope: &EventEnvelope,
+    ) -> Result<bool> {
+        let bill_id = &event.data.bill_id;
+        if self.check_chain(event).await? == ChainCheck::Consistent {
+            return Ok(true);
+        }
+        let Some(bundle) = get_attached_chain_bundle(envelope) else {
+            return Ok(false);
+        };
+        if let Err(e) = self.chain_sync.sync_chain(bill_id, &bundle).await {
+            error!("Could not sync chain of bill {bill_id}: {e}");
+        }
+        Ok(self.check_chain(event).await? == ChainCheck::Consistent)
+    }
+
+    /// Creates the notification for the event and pushes it to connected clients. Events our
+    /// chain doesn't confirm are flagged as unverified in the payload
+    async fn notify(
+        &self,
+        event: &Event<BillActionEventPayload>,
+        node_id: &str,
+        verified: bool,
+    ) -> Result<()> {
+        let mut payload = serde_json::to_value(&event.data)?;
+        if let Some(payload) = payload.as_object_mut() {
+            payload.insert("verified".to_owned(), verified.into());
+        }
+        // create notification
+        let notification = Notification::new_bill_notification(
+            &event.data.bill_id,
+            node_id,
+            &self.event_description(&event.event_type),
+            Some(payload),
+        );
+
+        // mark Bill event as done if any active one exists
+        if let Some(currently_active) = self
+            .notification_store
+            .get_latest_by_reference(&event.data.bill_id, NotificationType::Bill)
+            .await?
+        {
+            self.notification_store
+                .mark_as_done(&currently_active.id)
+                .await?;
+        }
+
+        // save new notification to database
+        self.notification_store.add(notification.clone()).await?;
+
+        // send push notification to connected clients
+        self.push_service
+            .send(serde_json::to_value(notification)?)
+            .await;
+        Ok(())
+    }
+
+    /// Keeps an event, our chain doesn't confirm yet, so it can be retried once the chain
+    /// caught up
+    async fn add_pending(
+        &self,
+        event: &Event<BillActionEventPayload>,
+        envelope: &EventEnvelope,
+        node_id: &str,
+        sender: &str,
+    ) -> Result<()> {
+        self.pending_event_store
+            .add(PendingEvent {
+                id: util::get_uuid_v4().to_string(),
+                bill_id: event.data.bill_id.to_owned(),
+                node_id: node_id.to_owned(),
+                sender: sender.to_owned(),
+                event_type: event.event_type.to_owned(),
+                payload: serde_json::to_string(envelope)?,
+                received_at: util::date::now().timestamp() as u64,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Handles a pending event, if our chain confirms it by now, or surfaces it as unverified,
+    /// once it expired. Returns whether the event is done, i.e. handled or unreadable
+    async fn retry_pending_event(&self, pending: &PendingEvent, timestamp: u64) -> Result<bool> {
+        let envelope: EventEnvelope = match serde_json::from_str(&pending.payload) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                error!("Dropping unreadable pending event {}: {e}", pending.id);
+                return Ok(true);
+            }
+        };
+        let Some(event): Option<Event<BillActionEventPayload>> = envelope.clone().try_into().ok()
+        else {
+            error!("Dropping pending event {}, it's no bill event", pending.id);
+            return Ok(true);
+        };
+        if self.is_consistent_with_chain(&event, &envelope).await? {
+            self.notify(&event, &pending.node_id, true).await?;
+            return Ok(true);
+        }
+        if pending.received_at + PENDING_EVENT_MAX_AGE_SECONDS <= timestamp {
+            warn!(
+                "Surfacing {} event for bill {} as unverified, the chain didn't catch up",
+                pending.event_type, pending.bill_id
+            );
+            self.notify(&event, &pending.node_id, false).await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
     fn event_description(&self, event_type: &EventType) -> String {
         match event_type {
             EventType::BillSigned => "Bill has been signed".to_string(),
@@ -62,36 +259,409 @@ impl NotificationHandlerApi for BillActionEventHandler {
         true
     }
 
-    async fn handle_event(&self, event: EventEnvelope, node_id: &str) -> Result<()> {
-        let event: Option<Event<BillActionEventPayload>> = event.try_into().ok();
-        if let Some(event) = event {
-            // create notification
-            let notification = Notification::new_bill_notification(
-                &event.data.bill_id,
-                node_id,
-                &self.event_description(&event.event_type),
-                Some(serde_json::to_value(&event.data)?),
-            );
-
-            // mark Bill event as done if any active one exists
-            if let Some(currently_active) = self
-                .notification_store
-                .get_latest_by_reference(&event.data.bill_id, NotificationType::Bill)
-                .await?
-            {
-                self.notification_store
-                    .mark_as_done(&currently_active.id)
+    async fn handle_event(&self, event: EventEnvelope, node_id: &str, sender: &str) -> Result<()> {
+        let bill_event: Option<Event<BillActionEventPayload>> = event.clone().try_into().ok();
+        if let Some(bill_event) = bill_event {
+            // only surface events, that our chain confirms, keep the others until it does
+            if self.is_consistent_with_chain(&bill_event, &event).await? {
+                self.notify(&bill_event, node_id, true).await?;
+            } else {
+                info!(
+                    "Keeping {} event for bill {} pending, the chain is behind",
+                    bill_event.event_type, bill_event.data.bill_id
+                );
+                self.add_pending(&bill_event, &event, node_id, sender)
                     .await?;
             }
+        }
+        Ok(())
+    }
 
-            // save new notification to database
-            self.notification_store.add(notification.clone()).await?;
-
-            // send push notification to connected clients
-            self.push_service
-                .send(serde_json::to_value(notification)?)
-                .await;
+    async fn retry_pending_events(&self, timestamp: u64) -> Result<()> {
+        for pending in self.pending_event_store.list().await? {
+            match self.retry_pending_event(&pending, timestamp).await {
+                Ok(true) => self.pending_event_store.remove(&pending.id).await?,
+                Ok(false) => (),
+                Err(e) => error!("Could not retry pending event {}: {e}", pending.id),
+            }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::bill::{BillBlockchain, bundle::BillChainBundle};
+    use crate::data::{bill::BillKeys, notification::ActionType};
+    use crate::service::bill_service::test_utils::{
+        get_baseline_bill, get_genesis_chain, request_to_pay_block,
+    };
+    use crate::service::notification_service::bill_chain_sync::{
+        BundleBillChainSync, CHAIN_BUNDLE_FIELD, MockBillChainSyncApi,
+    };
+    use crate::service::notification_service::push_notification::MockPushApi;
+    use crate::tests::tests::{
+        MockBillChainStoreApiMock, MockBillStoreApiMock, MockNostrPendingEventStoreApiMock,
+        MockNotificationStoreApiMock, TEST_NODE_ID_SECP, TEST_NODE_ID_SECP_AS_NPUB_HEX,
+        TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP,
+    };
+    use crate::util::BcrKeys;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn get_event(event_type: EventType, action_type: ActionType) -> EventEnvelope {
+        Event::new(
+            event_type,
+            "node_id",
+            BillActionEventPayload {
+                bill_id: "1234".to_string(),
+                action_type,
+                sum: Some(500),
+            },
+        )
+        .try_into()
+        .expect("could not create envelope")
+    }
+
+    fn get_bill_keys() -> BillKeys {
+        BillKeys {
+            private_key: TEST_PRIVATE_KEY_SECP.to_owned(),
+            public_key: TEST_PUB_KEY_SECP.to_owned(),
+        }
+    }
+
+    /// A chain with us as payee, that has a request to pay block, if requested
+    fn get_chain(id: &str, request_to_pay: bool) -> BillBlockchain {
+        let mut bill = get_baseline_bill(id);
+        bill.payee.node_id = TEST_NODE_ID_SECP.to_owned();
+        let mut chain = get_genesis_chain(Some(bill));
+        if request_to_pay {
+            chain.try_add_block(request_to_pay_block(id, chain.get_latest_block()));
+        }
+        chain
+    }
+
+    /// An event, the sender attached the chain of the bill to, which has a request to pay block
+    fn get_event_with_chain(event_type: EventType, action_type: ActionType) -> EventEnvelope {
+        let mut event = get_event(event_type, action_type);
+        let bundle = BillChainBundle::new(
+            &get_chain("1234", true),
+            &get_bill_keys(),
+            None,
+            vec![],
+            1000,
+        )
+        .expect("could not create bundle");
+        event.data[CHAIN_BUNDLE_FIELD] = serde_json::to_value(bundle).unwrap();
+        event
+    }
+
+    fn get_bill_store(exists: bool) -> MockBillStoreApiMock {
+        let mut bill_store = MockBillStoreApiMock::new();
+        bill_store.expect_exists().returning(move |_| exists);
+        bill_store
+    }
+
+    /// A chain with us as payee, that has a request to pay block once requested
+    fn get_chain_store(request_to_pay: Arc<AtomicBool>) -> MockBillChainStoreApiMock {
+        let mut chain_store = MockBillChainStoreApiMock::new();
+        chain_store
+            .expect_get_chain()
+            .returning(move |id| Ok(get_chain(id, request_to_pay.load(Ordering::SeqCst))));
+        chain_store
+    }
+
+    fn expect_notification(notification_store: &mut MockNotificationStoreApiMock, times: usize) {
+        expect_verified_notification(notification_store, times, true);
+    }
+
+    fn expect_verified_notification(
+        notification_store: &mut MockNotificationStoreApiMock,
+        times: usize,
+        verified: bool,
+    ) {
+        notification_store
+            .expect_get_latest_by_reference()
+            .returning(|_, _| Ok(None))
+            .times(times);
+        notification_store
+            .expect_add()
+            .withf(move |notification| {
+                notification.active
+                    && notification
+                        .payload
+                        .as_ref()
+                        .and_then(|payload| payload.get("verified"))
+                        == Some(&serde_json::Value::Bool(verified))
+            })
+            .returning(|_| Ok(()))
+            .times(times);
+    }
+
+    fn get_handler(
+        notification_store: MockNotificationStoreApiMock,
+        bill_store: MockBillStoreApiMock,
+        chain_store: MockBillChainStoreApiMock,
+        chain_sync: MockBillChainSyncApi,
+        pending_event_store: MockNostrPendingEventStoreApiMock,
+    ) -> BillActionEventHandler {
+        let mut push_service = MockPushApi::new();
+        push_service.expect_send().returning(|_| ());
+        BillActionEventHandler::new(
+            Arc::new(notification_store),
+            Arc::new(push_service),
+            Arc::new(bill_store),
+            Arc::new(chain_store),
+            Arc::new(chain_sync),
+            Arc::new(pending_event_store),
+        )
+    }
+
+    /// A pending event store, that keeps the events in memory
+    fn get_pending_event_store(
+        events: Arc<Mutex<Vec<PendingEvent>>>,
+    ) -> MockNostrPendingEventStoreApiMock {
+        let mut pending_event_store = MockNostrPendingEventStoreApiMock::new();
+        let added = events.clone();
+        pending_event_store.expect_add().returning(move |event| {
+            added.lock().unwrap().push(event);
+            Ok(())
+        });
+        let listed = events.clone();
+        pending_event_store
+            .expect_list()
+            .returning(move || Ok(listed.lock().unwrap().clone()));
+        pending_event_store.expect_remove().returning(move |id| {
+            events.lock().unwrap().retain(|event| event.id != id);
+            Ok(())
+        });
+        pending_event_store
+    }
+
+    #[tokio::test]
+    async fn test_creates_notification_for_consistent_event() {
+        let mut notification_store = MockNotificationStoreApiMock::new();
+        expect_notification(&mut notification_store, 2);
+        let mut chain_sync = MockBillChainSyncApi::new();
+        chain_sync.expect_sync_chain().never();
+        let mut pending_event_store = MockNostrPendingEventStoreApiMock::new();
+        pending_event_store.expect_add().never();
+        let handler = get_handler(
+            notification_store,
+            get_bill_store(true),
+            get_chain_store(Arc::new(AtomicBool::new(true))),
+            chain_sync,
+            pending_event_store,
+        );
+        handler
+            .handle_event(
+                get_event(EventType::BillPaymentRequested, ActionType::PayBill),
+                "node_id",
+                TEST_NODE_ID_SECP_AS_NPUB_HEX,
+            )
+            .await
+            .expect("event was not handled");
+
+        // events for older actions are confirmed by older blocks
+        handler
+            .handle_event(
+                get_event(EventType::BillSigned, ActionType::CheckBill),
+                "node_id",
+                TEST_NODE_ID_SECP_AS_NPUB_HEX,
+            )
+            .await
+            .expect("event was not handled");
+    }
+
+    #[tokio::test]
+    async fn test_syncs_chain_that_is_behind() {
+        let mut notification_store = MockNotificationStoreApiMock::new();
+        expect_notification(&mut notification_store, 1);
+        let request_to_pay = Arc::new(AtomicBool::new(false));
+        let synced = request_to_pay.clone();
+        let mut chain_sync = MockBillChainSyncApi::new();
+        chain_sync
+            .expect_sync_chain()
+            .withf(|bill_id, bundle| bill_id == "1234" && bundle.bill_id == "1234")
+            .returning(move |_, _| {
+                synced.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+            .once();
+        let mut pending_event_store = MockNostrPendingEventStoreApiMock::new();
+        pending_event_store.expect_add().never();
+        let handler = get_handler(
+            notification_store,
+            get_bill_store(true),
+            get_chain_store(request_to_pay),
+            chain_sync,
+            pending_event_store,
+        );
+        handler
+            .handle_event(
+                get_event_with_chain(EventType::BillPaymentRequested, ActionType::PayBill),
+                "node_id",
+                TEST_NODE_ID_SECP_AS_NPUB_HEX,
+            )
+            .await
+            .expect("event was not handled");
+    }
+
+    #[tokio::test]
+    async fn test_syncs_unknown_bill_from_attached_chain() {
+        let mut notification_store = MockNotificationStoreApiMock::new();
+        expect_notification(&mut notification_store, 1);
+
+        // in-memory bill stores, that start out without the bill
+        let keys: Arc<Mutex<Option<BillKeys>>> = Arc::new(Mutex::new(None));
+        let blocks = Arc::new(Mutex::new(vec![]));
+        let mut bill_store = MockBillStoreApiMock::new();
+        let known = keys.clone();
+        bill_store
+            .expect_exists()
+            .returning(move |_| known.lock().unwrap().is_some());
+        bill_store
+            .expect_save_keys()
+            .returning(move |_, bill_keys| {
+                *keys.lock().unwrap() = Some(bill_keys.to_owned());
+                Ok(())
+            });
+        let mut chain_store = MockBillChainStoreApiMock::new();
+        let stored = blocks.clone();
+        chain_store.expect_get_chain().returning(move |_| {
+            Ok(BillBlockchain::new_from_blocks(stored.lock().unwrap().clone()).unwrap())
+        });
+        chain_store.expect_add_block().returning(move |_, block| {
+            blocks.lock().unwrap().push(block.to_owned());
+            Ok(())
+        });
+        let bill_store = Arc::new(bill_store);
+        let chain_store = Arc::new(chain_store);
+
+        let mut pending_event_store = MockNostrPendingEventStoreApiMock::new();
+        pending_event_store.expect_add().never();
+        let mut push_service = MockPushApi::new();
+        push_service.expect_send().returning(|_| ());
+        let handler = BillActionEventHandler::new(
+            Arc::new(notification_store),
+            Arc::new(push_service),
+            bill_store.clone(),
+            chain_store.clone(),
+            Arc::new(BundleBillChainSync::new(bill_store, chain_store)),
+            Arc::new(pending_event_store),
+        );
+        handler
+            .handle_event(
+                get_event_with_chain(EventType::BillPaymentRequested, ActionType::PayBill),
+                "node_id",
+                TEST_NODE_ID_SECP_AS_NPUB_HEX,
+            )
+            .await
+            .expect("event was not handled");
+    }
+
+    #[tokio::test]
+    async fn test_keeps_event_pending_until_chain_catches_up() {
+        let mut notification_store = MockNotificationStoreApiMock::new();
+        expect_notification(&mut notification_store, 1);
+        let mut chain_sync = MockBillChainSyncApi::new();
+        chain_sync.expect_sync_chain().never();
+        let request_to_pay = Arc::new(AtomicBool::new(false));
+        let pending = Arc::new(Mutex::new(vec![]));
+        let handler = get_handler(
+            notification_store,
+            get_bill_store(true),
+            get_chain_store(request_to_pay.clone()),
+            chain_sync,
+            get_pending_event_store(pending.clone()),
+        );
+
+        // the chain is behind, so the event is kept
+        handler
+            .handle_event(
+                get_event(EventType::BillPaymentRequested, ActionType::PayBill),
+                "node_id",
+                TEST_NODE_ID_SECP_AS_NPUB_HEX,
+            )
+            .await
+            .expect("event was not handled");
+        assert_eq!(pending.lock().unwrap().len(), 1);
+        assert_eq!(pending.lock().unwrap()[0].node_id, "node_id");
+
+        // it stays pending, as long as the chain is behind
+        let now = util::date::now().timestamp() as u64;
+        handler
+            .retry_pending_events(now)
+            .await
+            .expect("pending events were not retried");
+        assert_eq!(pending.lock().unwrap().len(), 1);
+
+        // once the chain caught up, the notification is created
+        request_to_pay.store(true, Ordering::SeqCst);
+        handler
+            .retry_pending_events(now)
+            .await
+            .expect("pending events were not retried");
+        assert!(pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_surfaces_pending_event_as_unverified_if_chain_never_catches_up() {
+        let mut notification_store = MockNotificationStoreApiMock::new();
+        expect_verified_notification(&mut notification_store, 1, false);
+        let mut chain_sync = MockBillChainSyncApi::new();
+        chain_sync.expect_sync_chain().never();
+        let pending = Arc::new(Mutex::new(vec![]));
+        // the bill is unknown
+        let handler = get_handler(
+            notification_store,
+            get_bill_store(false),
+            get_chain_store(Arc::new(AtomicBool::new(false))),
+            chain_sync,
+            get_pending_event_store(pending.clone()),
+        );
+        handler
+            .handle_event(
+                get_event(EventType::BillSigned, ActionType::CheckBill),
+                "node_id",
+                TEST_NODE_ID_SECP_AS_NPUB_HEX,
+            )
+            .await
+            .expect("event was not handled");
+        let received_at = pending.lock().unwrap()[0].received_at;
+
+        handler
+            .retry_pending_events(received_at + PENDING_EVENT_MAX_AGE_SECONDS - 1)
+            .await
+            .expect("pending events were not retried");
+        assert_eq!(pending.lock().unwrap().len(), 1);
+
+        handler
+            .retry_pending_events(received_at + PENDING_EVENT_MAX_AGE_SECONDS)
+            .await
+            .expect("pending events were not retried");
+        assert!(pending.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quotes_are_not_tied_to_a_block() {
+        let mut notification_store = MockNotificationStoreApiMock::new();
+        expect_notification(&mut notification_store, 1);
+        let handler = get_handler(
+            notification_store,
+            get_bill_store(true),
+            get_chain_store(Arc::new(AtomicBool::new(false))),
+            MockBillChainSyncApi::new(),
+            MockNostrPendingEventStoreApiMock::new(),
+        );
+        handler
+            .handle_event(
+                get_event(EventType::BillNewQuote, ActionType::CheckQuote),
+                "node_id",
+                &BcrKeys::new().get_nostr_npub_as_hex(),
+            )
+            .await
+            .expect("event was not handled");
+    }
+}
@@ -0,0 +1,279 @@
+use super::transport::NotificationJsonTransportApi;
+use super::{Error, EventEnvelope, Result};
+use crate::blockchain::Blockchain;
+use crate::blockchain::bill::bundle::{BillChainBundle, BillChainDisclosure};
+use crate::data::bill::BillKeys;
+use crate::data::contact::IdentityPublicData;
+use crate::persistence::bill::{BillChainStoreApi, BillStoreApi};
+use crate::util;
+use async_trait::async_trait;
+use log::{error, info};
+#[cfg(test)]
+use mockall::automock;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// The field of a bill event's data, which carries the bundled chain of the bill
+pub const CHAIN_BUNDLE_FIELD: &str = "chain";
+
+/// Returns the chain bundle, the sender attached to the event, if there is one
+pub fn get_attached_chain_bundle(event: &EventEnvelope) -> Option<BillChainBundle> {
+    event
+        .data
+        .get(CHAIN_BUNDLE_FIELD)
+        .and_then(|bundle| serde_json::from_value(bundle.clone()).ok())
+}
+
+/// Checks, that the bundle is valid, belongs to the given bill and discloses its keys, and
+/// returns the keys
+fn verify_bundle<'a>(bill_id: &str, bundle: &'a BillChainBundle) -> Result<&'a BillKeys> {
+    if bundle.bill_id != bill_id {
+        return Err(Error::ChainSync(format!(
+            "the bundle is for bill {}, not for bill {bill_id}",
+            bundle.bill_id
+        )));
+    }
+    let BillChainDisclosure::Full { ref bill_keys } = bundle.disclosure else {
+        return Err(Error::ChainSync(format!(
+            "the bundle of bill {bill_id} doesn't disclose the bill keys"
+        )));
+    };
+    if !bundle.verify().is_valid() {
+        return Err(Error::ChainSync(format!(
+            "the bundled chain of bill {bill_id} is invalid"
+        )));
+    }
+    Ok(bill_keys)
+}
+
+/// Returns the node ids of the participants of the bill, according to the bundle, if it's valid
+pub fn get_participants_from_bundle(
+    bill_id: &str,
+    bundle: &BillChainBundle,
+) -> Result<Vec<String>> {
+    let bill_keys = verify_bundle(bill_id, bundle)?;
+    Ok(bundle.chain.get_all_nodes_from_bill(bill_keys)?)
+}
+
+/// Brings our copy of a bill's chain up to date, when an incoming event refers to blocks
+/// we don't have yet.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait BillChainSyncApi: Send + Sync {
+    /// Syncs the chain of the bill with the given id from the bundle the sender of the event
+    /// attached. Returns once the sync is done, so the chain can be checked again afterwards.
+    async fn sync_chain(&self, bill_id: &str, bundle: &BillChainBundle) -> Result<()>;
+}
+
+/// Syncs bill chains from the bundles attached to incoming events. A bundle is only accepted,
+/// if it's valid and extends the chain we already have
+pub struct BundleBillChainSync {
+    bill_store: Arc<dyn BillStoreApi>,
+    bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+}
+
+impl BundleBillChainSync {
+    pub fn new(
+        bill_store: Arc<dyn BillStoreApi>,
+        bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+    ) -> Self {
+        Self {
+            bill_store,
+            bill_blockchain_store,
+        }
+    }
+}
+
+#[async_trait]
+impl BillChainSyncApi for BundleBillChainSync {
+    async fn sync_chain(&self, bill_id: &str, bundle: &BillChainBundle) -> Result<()> {
+        let bill_keys = verify_bundle(bill_id, bundle)?;
+        let blocks = bundle.chain.blocks();
+        let known_blocks = if self.bill_store.exists(bill_id).await {
+            let chain = self.bill_blockchain_store.get_chain(bill_id).await?;
+            let local_blocks = chain.blocks();
+            if local_blocks.len() > blocks.len()
+                || local_blocks
+                    .iter()
+                    .zip(blocks.iter())
+                    .any(|(local, bundled)| local.hash != bundled.hash)
+            {
+                return Err(Error::ChainSync(format!(
+                    "the bundled chain of bill {bill_id} doesn't extend ours"
+                )));
+            }
+            local_blocks.len()
+        } else {
+            self.bill_store.save_keys(bill_id, bill_keys).await?;
+            0
+        };
+        for block in &blocks[known_blocks..] {
+            self.bill_blockchain_store.add_block(bill_id, block).await?;
+        }
+        info!(
+            "Synced {} blocks of bill {bill_id}",
+            blocks.len() - known_blocks
+        );
+        Ok(())
+    }
+}
+
+/// Attaches the bundled chain of the bill to every bill event sent via the wrapped transport,
+/// so the recipient can sync the chain from it. The bundle discloses the bill keys, so it's
+/// only sent to the recipients of the event, never to webhooks
+pub struct BillChainAttachingTransport {
+    transport: Box<dyn NotificationJsonTransportApi>,
+    bill_store: Arc<dyn BillStoreApi>,
+    bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+}
+
+impl BillChainAttachingTransport {
+    pub fn new(
+        transport: Box<dyn NotificationJsonTransportApi>,
+        bill_store: Arc<dyn BillStoreApi>,
+        bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+    ) -> Self {
+        Self {
+            transport,
+            bill_store,
+            bill_blockchain_store,
+        }
+    }
+
+    async fn get_chain_bundle(&self, bill_id: &str) -> Result<Option<BillChainBundle>> {
+        if !self.bill_store.exists(bill_id).await {
+            return Ok(None);
+        }
+        let chain = self.bill_blockchain_store.get_chain(bill_id).await?;
+        let bill_keys = self.bill_store.get_keys(bill_id).await?;
+        let bundle = BillChainBundle::new(
+            &chain,
+            &bill_keys,
+            None,
+            vec![],
+            util::date::now().timestamp() as u64,
+        )?;
+        Ok(Some(bundle))
+    }
+}
+
+#[async_trait]
+impl NotificationJsonTransportApi for BillChainAttachingTransport {
+    async fn send(&self, recipient: &IdentityPublicData, mut event: EventEnvelope) -> Result<()> {
+        let bill_id = event
+            .data
+            .get("bill_id")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        if let (Some(bill_id), Some(data)) = (bill_id, event.data.as_object_mut()) {
+            // the event is still sent without the chain, the recipient keeps it pending then
+            match self.get_chain_bundle(&bill_id).await {
+                Ok(Some(bundle)) => {
+                    data.insert(CHAIN_BUNDLE_FIELD.to_owned(), serde_json::to_value(bundle)?);
+                }
+                Ok(None) => (),
+                Err(e) => error!("Could not bundle the chain of bill {bill_id}: {e}"),
+            }
+        }
+        self.transport.send(recipient, event).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::bill_service::test_utils::get_genesis_chain;
+    use crate::service::notification_service::transport::MockNotificationJsonTransportApi;
+    use crate::tests::tests::{
+        MockBillChainStoreApiMock, MockBillStoreApiMock, TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP,
+        empty_identity_public_data,
+    };
+    use serde_json::json;
+
+    fn get_bill_keys() -> BillKeys {
+        BillKeys {
+            private_key: TEST_PRIVATE_KEY_SECP.to_owned(),
+            public_key: TEST_PUB_KEY_SECP.to_owned(),
+        }
+    }
+
+    fn get_bundle() -> BillChainBundle {
+        BillChainBundle::new(
+            &get_genesis_chain(None),
+            &get_bill_keys(),
+            None,
+            vec![],
+            1000,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sync_chain_stores_unknown_bill() {
+        let bundle = get_bundle();
+        let bill_id = bundle.bill_id.clone();
+        let mut bill_store = MockBillStoreApiMock::new();
+        bill_store.expect_exists().returning(|_| false);
+        bill_store
+            .expect_save_keys()
+            .withf(|_, keys| keys.public_key == TEST_PUB_KEY_SECP)
+            .returning(|_, _| Ok(()))
+            .times(1);
+        let mut chain_store = MockBillChainStoreApiMock::new();
+        chain_store
+            .expect_add_block()
+            .returning(|_, _| Ok(()))
+            .times(1);
+        BundleBillChainSync::new(Arc::new(bill_store), Arc::new(chain_store))
+            .sync_chain(&bill_id, &bundle)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn sync_chain_rejects_bundle_of_other_bill_or_without_keys() {
+        let sync = BundleBillChainSync::new(
+            Arc::new(MockBillStoreApiMock::new()),
+            Arc::new(MockBillChainStoreApiMock::new()),
+        );
+        assert!(sync.sync_chain("other", &get_bundle()).await.is_err());
+
+        let mut bundle = get_bundle();
+        bundle.disclosure = BillChainDisclosure::Selective { block_keys: vec![] };
+        let bill_id = bundle.bill_id.clone();
+        assert!(sync.sync_chain(&bill_id, &bundle).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn attaching_transport_adds_chain_of_known_bill() {
+        let mut bill_store = MockBillStoreApiMock::new();
+        bill_store.expect_exists().returning(|_| true);
+        bill_store
+            .expect_get_keys()
+            .returning(|_| Ok(get_bill_keys()));
+        let mut chain_store = MockBillChainStoreApiMock::new();
+        chain_store
+            .expect_get_chain()
+            .returning(|_| Ok(get_genesis_chain(None)));
+        let mut transport = MockNotificationJsonTransportApi::new();
+        transport
+            .expect_send()
+            .withf(|_, event| get_attached_chain_bundle(event).is_some())
+            .returning(|_, _| Ok(()))
+            .times(1);
+        let event = EventEnvelope {
+            event_type: bcr_ebill_core::notification::EventType::BillSigned,
+            version: "1.0".to_string(),
+            node_id: "node_id".to_string(),
+            data: json!({ "bill_id": "1234", "action_type": "CheckBill", "sum": 500 }),
+        };
+        BillChainAttachingTransport::new(
+            Box::new(transport),
+            Arc::new(bill_store),
+            Arc::new(chain_store),
+        )
+        .send(&empty_identity_public_data(), event)
+        .await
+        .unwrap();
+    }
+}
@@ -5,33 +5,56 @@ use async_trait::async_trait;
 
 use super::event::{BillActionEventPayload, Event};
 use super::transport::NotificationJsonTransportApi;
-use super::{NotificationServiceApi, Result};
+use super::{EventEnvelope, NotificationServiceApi, Result};
 use crate::data::{
     bill::BitcreditBill,
     contact::IdentityPublicData,
-    notification::{Notification, NotificationType},
+    notification::{EmailPreference, Notification, NotificationType},
 };
 use crate::persistence::notification::{NotificationFilter, NotificationStoreApi};
+use crate::service::webhook_service::WebhookServiceApi;
 use bcr_ebill_core::notification::{ActionType, EventType};
 
 /// A default implementation of the NotificationServiceApi that can
-/// send events via json and email transports.
-#[allow(dead_code)]
+/// send events via json transports. Every bill action is also
+/// dispatched to the subscribed webhooks once.
 pub struct DefaultNotificationService {
     notification_transport: Box<dyn NotificationJsonTransportApi>,
     notification_store: Arc<dyn NotificationStoreApi>,
+    webhook_service: Arc<dyn WebhookServiceApi>,
 }
 
 impl DefaultNotificationService {
     pub fn new(
         notification_transport: Box<dyn NotificationJsonTransportApi>,
         notification_store: Arc<dyn NotificationStoreApi>,
+        webhook_service: Arc<dyn WebhookServiceApi>,
     ) -> Self {
         Self {
             notification_transport,
             notification_store,
+            webhook_service,
         }
     }
+
+    async fn send_event(&self, recipient: &IdentityPublicData, event: EventEnvelope) -> Result<()> {
+        self.send_action_events(vec![(recipient, event)]).await
+    }
+
+    /// Sends the events of a single bill action to their recipients. The action is dispatched
+    /// to the webhooks only once, with the event of the first recipient
+    async fn send_action_events(
+        &self,
+        events: Vec<(&IdentityPublicData, EventEnvelope)>,
+    ) -> Result<()> {
+        if let Some((_, event)) = events.first() {
+            self.webhook_service.dispatch(event).await;
+        }
+        for (recipient, event) in events {
+            self.notification_transport.send(recipient, event).await?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -57,15 +80,11 @@ impl NotificationServiceApi for DefaultNotificationService {
             },
         );
 
-        self.notification_transport
-            .send(&bill.drawee, payer_event.try_into()?)
-            .await?;
-
-        self.notification_transport
-            .send(&bill.payee, payee_event.try_into()?)
-            .await?;
-
-        Ok(())
+        self.send_action_events(vec![
+            (&bill.drawee, payer_event.try_into()?),
+            (&bill.payee, payee_event.try_into()?),
+        ])
+        .await
     }
 
     async fn send_bill_is_accepted_event(&self, bill: &BitcreditBill) -> Result<()> {
@@ -79,9 +98,7 @@ impl NotificationServiceApi for DefaultNotificationService {
             },
         );
 
-        self.notification_transport
-            .send(&bill.payee, event.try_into()?)
-            .await?;
+        self.send_event(&bill.payee, event.try_into()?).await?;
         Ok(())
     }
 
@@ -95,9 +112,7 @@ impl NotificationServiceApi for DefaultNotificationService {
                 sum: Some(bill.sum),
             },
         );
-        self.notification_transport
-            .send(&bill.drawee, event.try_into()?)
-            .await?;
+        self.send_event(&bill.drawee, event.try_into()?).await?;
         Ok(())
     }
 
@@ -111,9 +126,7 @@ impl NotificationServiceApi for DefaultNotificationService {
                 sum: Some(bill.sum),
             },
         );
-        self.notification_transport
-            .send(&bill.drawee, event.try_into()?)
-            .await?;
+        self.send_event(&bill.drawee, event.try_into()?).await?;
         Ok(())
     }
 
@@ -128,9 +141,7 @@ impl NotificationServiceApi for DefaultNotificationService {
             },
         );
 
-        self.notification_transport
-            .send(&bill.payee, event.try_into()?)
-            .await?;
+        self.send_event(&bill.payee, event.try_into()?).await?;
         Ok(())
     }
 
@@ -145,8 +156,7 @@ impl NotificationServiceApi for DefaultNotificationService {
             },
         );
 
-        self.notification_transport
-            .send(bill.endorsee.as_ref().unwrap(), event.try_into()?)
+        self.send_event(bill.endorsee.as_ref().unwrap(), event.try_into()?)
             .await?;
         Ok(())
     }
@@ -166,9 +176,7 @@ impl NotificationServiceApi for DefaultNotificationService {
                 sum,
             },
         );
-        self.notification_transport
-            .send(buyer, event.try_into()?)
-            .await?;
+        self.send_event(buyer, event.try_into()?).await?;
         Ok(())
     }
 
@@ -187,9 +195,7 @@ impl NotificationServiceApi for DefaultNotificationService {
                 sum,
             },
         );
-        self.notification_transport
-            .send(buyer, event.try_into()?)
-            .await?;
+        self.send_event(buyer, event.try_into()?).await?;
         Ok(())
     }
 
@@ -208,9 +214,7 @@ impl NotificationServiceApi for DefaultNotificationService {
                 sum,
             },
         );
-        self.notification_transport
-            .send(recoursee, event.try_into()?)
-            .await?;
+        self.send_event(recoursee, event.try_into()?).await?;
         Ok(())
     }
 
@@ -224,8 +228,7 @@ impl NotificationServiceApi for DefaultNotificationService {
                 sum: Some(bill.sum),
             },
         );
-        self.notification_transport
-            .send(bill.endorsee.as_ref().unwrap(), event.try_into()?)
+        self.send_event(bill.endorsee.as_ref().unwrap(), event.try_into()?)
             .await?;
         Ok(())
     }
@@ -243,12 +246,12 @@ impl NotificationServiceApi for DefaultNotificationService {
                 action_type: ActionType::CheckBill,
                 sum,
             };
-            for recipient in recipients {
+            let mut events = Vec::with_capacity(recipients.len());
+            for recipient in recipients.iter() {
                 let event = Event::new(event_type.to_owned(), &recipient.node_id, payload.clone());
-                self.notification_transport
-                    .send(&recipient, event.try_into()?)
-                    .await?;
+                events.push((recipient, event.try_into()?));
             }
+            self.send_action_events(events).await?;
         }
         Ok(())
     }
@@ -270,12 +273,12 @@ impl NotificationServiceApi for DefaultNotificationService {
                 action_type: ActionType::CheckBill,
                 sum,
             };
-            for (_, recipient) in unique {
+            let mut events = Vec::with_capacity(unique.len());
+            for recipient in unique.values() {
                 let event = Event::new(event_type.to_owned(), &recipient.node_id, payload.clone());
-                self.notification_transport
-                    .send(&recipient, event.try_into()?)
-                    .await?;
+                events.push((recipient, event.try_into()?));
             }
+            self.send_action_events(events).await?;
         }
         Ok(())
     }
@@ -297,9 +300,7 @@ impl NotificationServiceApi for DefaultNotificationService {
                     sum,
                 },
             );
-            self.notification_transport
-                .send(recipient, event.try_into()?)
-                .await?;
+            self.send_event(recipient, event.try_into()?).await?;
         }
         Ok(())
     }
@@ -361,6 +362,36 @@ impl NotificationServiceApi for DefaultNotificationService {
             .await?;
         Ok(())
     }
+
+    async fn get_email_preference(&self, node_id: &str) -> Result<EmailPreference> {
+        let preference = self
+            .notification_store
+            .get_email_preference(node_id)
+            .await?
+            .unwrap_or(EmailPreference {
+                node_id: node_id.to_owned(),
+                enabled: false,
+                updated_at: 0,
+            });
+        Ok(preference)
+    }
+
+    async fn set_email_preference(
+        &self,
+        node_id: &str,
+        enabled: bool,
+        timestamp: u64,
+    ) -> Result<EmailPreference> {
+        let preference = EmailPreference {
+            node_id: node_id.to_owned(),
+            enabled,
+            updated_at: timestamp,
+        };
+        self.notification_store
+            .set_email_preference(&preference)
+            .await?;
+        Ok(preference)
+    }
 }
 
 #[cfg(test)]
@@ -370,16 +401,81 @@ mod tests {
 
     use mockall::predicate::eq;
 
-    use crate::service::contact_service::MockContactServiceApi;
     use crate::service::notification_service::create_nostr_consumer;
     use crate::service::notification_service::push_notification::MockPushApi;
+    use crate::service::notification_service::sender_authorization::MockSenderAuthorizationApi;
     use crate::service::notification_service::transport::MockNotificationJsonTransportApi;
+    use crate::service::webhook_service::MockWebhookServiceApi;
 
     use super::super::test_utils::{
         get_identity_public_data, get_mock_nostr_client, get_test_bitcredit_bill,
     };
     use super::*;
-    use crate::tests::tests::{MockNostrEventOffsetStoreApiMock, MockNotificationStoreApiMock};
+    use crate::tests::tests::{
+        MockBillChainStoreApiMock, MockBillStoreApiMock, MockCompanyStoreApiMock,
+        MockIdentityStoreApiMock, MockNostrEventOffsetStoreApiMock,
+        MockNostrPendingEventStoreApiMock, MockNostrQuarantineStoreApiMock,
+        MockNotificationStoreApiMock,
+    };
+
+    fn get_webhook_service() -> Arc<MockWebhookServiceApi> {
+        let mut webhook_service = MockWebhookServiceApi::new();
+        webhook_service.expect_dispatch().returning(|_| ());
+        Arc::new(webhook_service)
+    }
+
+    #[tokio::test]
+    async fn test_sent_events_are_dispatched_to_webhooks_once_per_action() {
+        let mut mock = MockNotificationJsonTransportApi::new();
+        mock.expect_send().times(2).returning(|_, _| Ok(()));
+        let mut webhook_service = MockWebhookServiceApi::new();
+        webhook_service
+            .expect_dispatch()
+            .withf(|e| e.event_type == EventType::BillSigned)
+            .times(1)
+            .returning(|_| ());
+        let service = DefaultNotificationService::new(
+            Box::new(mock),
+            Arc::new(MockNotificationStoreApiMock::new()),
+            Arc::new(webhook_service),
+        );
+
+        service
+            .send_bill_is_signed_event(&get_test_bill())
+            .await
+            .expect("failed to send event");
+    }
+
+    #[tokio::test]
+    async fn test_email_preference_defaults_to_disabled() {
+        let mut mock_store = MockNotificationStoreApiMock::new();
+        mock_store
+            .expect_get_email_preference()
+            .returning(|_| Ok(None));
+        mock_store
+            .expect_set_email_preference()
+            .withf(|p| p.node_id == "node_id" && p.enabled && p.updated_at == 1000)
+            .times(1)
+            .returning(|_| Ok(()));
+        let service = DefaultNotificationService::new(
+            Box::new(MockNotificationJsonTransportApi::new()),
+            Arc::new(mock_store),
+            get_webhook_service(),
+        );
+
+        assert!(
+            !service
+                .get_email_preference("node_id")
+                .await
+                .unwrap()
+                .enabled
+        );
+        let preference = service
+            .set_email_preference("node_id", true, 1000)
+            .await
+            .unwrap();
+        assert!(preference.enabled);
+    }
 
     #[tokio::test]
     async fn test_send_request_to_action_rejected_event() {
@@ -418,6 +514,7 @@ mod tests {
         let service = DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         };
 
         service
@@ -477,6 +574,7 @@ mod tests {
         let service = DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         };
 
         service
@@ -515,6 +613,7 @@ mod tests {
         let service = DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         };
 
         service
@@ -554,6 +653,7 @@ mod tests {
         let service = DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         };
 
         service
@@ -588,6 +688,7 @@ mod tests {
         let service = DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         };
 
         service
@@ -613,6 +714,7 @@ mod tests {
         let service = DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         };
 
         service
@@ -652,6 +754,7 @@ mod tests {
         let service = DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         };
 
         service
@@ -827,6 +930,7 @@ mod tests {
         let service = DefaultNotificationService::new(
             Box::new(MockNotificationJsonTransportApi::new()),
             Arc::new(mock_store),
+            get_webhook_service(),
         );
 
         let res = service
@@ -848,6 +952,7 @@ mod tests {
         let service = DefaultNotificationService::new(
             Box::new(MockNotificationJsonTransportApi::new()),
             Arc::new(mock_store),
+            get_webhook_service(),
         );
 
         service
@@ -874,6 +979,7 @@ mod tests {
         DefaultNotificationService {
             notification_transport: Box::new(mock),
             notification_store: Arc::new(MockNotificationStoreApiMock::new()),
+            webhook_service: get_webhook_service(),
         }
     }
 
@@ -898,16 +1004,29 @@ mod tests {
     #[tokio::test]
     async fn test_create_nostr_consumer() {
         let client = get_mock_nostr_client().await;
-        let contact_service = Arc::new(MockContactServiceApi::new());
+        let sender_authorization = Arc::new(MockSenderAuthorizationApi::new());
         let store = Arc::new(MockNostrEventOffsetStoreApiMock::new());
+        let quarantine_store = Arc::new(MockNostrQuarantineStoreApiMock::new());
+        let pending_event_store = Arc::new(MockNostrPendingEventStoreApiMock::new());
         let notification_store = Arc::new(MockNotificationStoreApiMock::new());
         let push_service = Arc::new(MockPushApi::new());
+        let webhook_service = Arc::new(MockWebhookServiceApi::new());
+        let bill_store = Arc::new(MockBillStoreApiMock::new());
+        let bill_blockchain_store = Arc::new(MockBillChainStoreApiMock::new());
         let _ = create_nostr_consumer(
             client,
-            contact_service,
+            sender_authorization,
             store,
+            quarantine_store,
+            pending_event_store,
             notification_store,
             push_service,
+            webhook_service,
+            bill_store,
+            bill_blockchain_store,
+            Arc::new(MockIdentityStoreApiMock::new()),
+            Arc::new(MockCompanyStoreApiMock::new()),
+            None,
         )
         .await;
     }
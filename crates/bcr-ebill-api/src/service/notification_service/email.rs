@@ -1,13 +1,18 @@
-use super::Result;
+use super::event::BillActionEventPayload;
+use super::{EventEnvelope, Result};
 use async_trait::async_trait;
+use bcr_ebill_core::notification::EventType;
 #[cfg(test)]
 use mockall::automock;
+use std::sync::Arc;
+
+pub use super::email_sendgrid::SendgridConfig;
+pub use super::email_smtp::{SmtpConfig, SmtpTls};
 
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait NotificationEmailTransportApi: Send + Sync {
     /// Generically send an email message to different email transports.
-    #[allow(dead_code)]
     async fn send(&self, event: EmailMessage) -> Result<()>;
 }
 
@@ -19,3 +24,147 @@ pub struct EmailMessage {
     pub subject: String,
     pub body: String,
 }
+
+/// The sender address and the transport emails are sent with
+#[derive(Debug, Clone)]
+pub struct EmailConfig {
+    pub from: String,
+    pub transport: EmailTransportConfig,
+}
+
+#[derive(Debug, Clone)]
+pub enum EmailTransportConfig {
+    Sendgrid(SendgridConfig),
+    Smtp(SmtpConfig),
+}
+
+/// Sends events as plain text emails from the configured address
+#[derive(Clone)]
+pub struct EmailEventSender {
+    from: String,
+    transport: Arc<dyn NotificationEmailTransportApi>,
+}
+
+impl EmailEventSender {
+    pub fn new(from: &str, transport: Arc<dyn NotificationEmailTransportApi>) -> Self {
+        Self {
+            from: from.to_owned(),
+            transport,
+        }
+    }
+
+    pub async fn send_event(&self, to: &str, event: &EventEnvelope) -> Result<()> {
+        let (subject, body) = render_event(event);
+        self.transport
+            .send(EmailMessage {
+                from: self.from.clone(),
+                to: to.to_owned(),
+                subject,
+                body,
+            })
+            .await
+    }
+}
+
+/// Returns the subject and the body of the email for the given event
+fn render_event(event: &EventEnvelope) -> (String, String) {
+    let summary = event_summary(&event.event_type);
+    match serde_json::from_value::<BillActionEventPayload>(event.data.clone()) {
+        Ok(payload) => {
+            let mut body = format!(
+                "{summary}.\n\nBill: {}\nAction: {:?}\n",
+                payload.bill_id, payload.action_type
+            );
+            if let Some(sum) = payload.sum {
+                body.push_str(&format!("Sum: {sum} sat\n"));
+            }
+            (format!("Bitcredit: {summary} ({})", payload.bill_id), body)
+        }
+        Err(_) => (format!("Bitcredit: {summary}"), format!("{summary}.\n")),
+    }
+}
+
+fn event_summary(event_type: &EventType) -> &'static str {
+    match event_type {
+        EventType::BillSigned => "A bill was issued to you",
+        EventType::BillAccepted => "A bill was accepted",
+        EventType::BillAcceptanceRequested => "You were requested to accept a bill",
+        EventType::BillAcceptanceRejected => "The acceptance of a bill was rejected",
+        EventType::BillAcceptanceTimeout => "A request to accept a bill timed out",
+        EventType::BillAcceptanceRecourse => "You were requested to accept a bill in recourse",
+        EventType::BillPaymentRequested => "You were requested to pay a bill",
+        EventType::BillPaymentRejected => "The payment of a bill was rejected",
+        EventType::BillPaymentRecourse => "You were requested to pay a bill in recourse",
+        EventType::BillRecourseRejected => "A recourse for a bill was rejected",
+        EventType::BillRecourseTimeout => "A recourse request for a bill timed out",
+        EventType::BillPaymentTimeout => "A request to pay a bill timed out",
+        EventType::BillSellOffered => "A bill was offered to you for sale",
+        EventType::BillBuyingRejected => "The sale of a bill was rejected",
+        EventType::BillPaid => "A bill was paid",
+        EventType::BillRecoursePaid => "A recourse for a bill was paid",
+        EventType::BillEndorsed => "A bill was endorsed to you",
+        EventType::BillSold => "A bill was sold to you",
+        EventType::BillMintingRequested => "A bill was requested to be minted",
+        EventType::BillNewQuote => "There is a new quote for a bill",
+        EventType::BillQuoteApproved => "A quote for a bill was approved",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn get_event(data: serde_json::Value) -> EventEnvelope {
+        EventEnvelope {
+            event_type: EventType::BillPaymentRequested,
+            version: "1.0".to_string(),
+            node_id: "node_id".to_string(),
+            data,
+        }
+    }
+
+    #[test]
+    fn test_render_bill_action_event() {
+        let (subject, body) = render_event(&get_event(
+            json!({ "bill_id": "1234", "action_type": "PayBill", "sum": 500 }),
+        ));
+        assert_eq!(
+            subject,
+            "Bitcredit: You were requested to pay a bill (1234)"
+        );
+        assert_eq!(
+            body,
+            "You were requested to pay a bill.\n\nBill: 1234\nAction: PayBill\nSum: 500 sat\n"
+        );
+    }
+
+    #[test]
+    fn test_render_unknown_payload() {
+        let (subject, body) = render_event(&get_event(json!({ "other": true })));
+        assert_eq!(subject, "Bitcredit: You were requested to pay a bill");
+        assert_eq!(body, "You were requested to pay a bill.\n");
+    }
+
+    #[tokio::test]
+    async fn test_send_event() {
+        let mut transport = MockNotificationEmailTransportApi::new();
+        transport
+            .expect_send()
+            .withf(|m| {
+                m.from == "node@example.com"
+                    && m.to == "payer@example.com"
+                    && m.body.contains("Bill: 1234")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        let sender = EmailEventSender::new("node@example.com", Arc::new(transport));
+        sender
+            .send_event(
+                "payer@example.com",
+                &get_event(json!({ "bill_id": "1234", "action_type": "PayBill", "sum": null })),
+            )
+            .await
+            .expect("email was not sent");
+    }
+}
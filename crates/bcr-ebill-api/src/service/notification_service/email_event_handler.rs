@@ -0,0 +1,236 @@
+use super::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{EventEnvelope, EventType, email::EmailEventSender, handler::NotificationHandlerApi};
+use crate::persistence::{
+    company::CompanyStoreApi, identity::IdentityStoreApi, notification::NotificationStoreApi,
+};
+use async_trait::async_trait;
+use log::error;
+
+/// How long sending an email may take, before it's given up
+const EMAIL_TIMEOUT_SECONDS: u64 = 30;
+
+/// Sends the events received for the local identity, or one of its companies, to their email
+/// address, if they opted in. Emails are sent in the background, so a slow email transport can't
+/// hold up the handling of events
+#[derive(Clone)]
+pub struct EmailEventHandler {
+    email_sender: EmailEventSender,
+    notification_store: Arc<dyn NotificationStoreApi>,
+    identity_store: Arc<dyn IdentityStoreApi>,
+    company_store: Arc<dyn CompanyStoreApi>,
+}
+
+impl EmailEventHandler {
+    pub fn new(
+        email_sender: EmailEventSender,
+        notification_store: Arc<dyn NotificationStoreApi>,
+        identity_store: Arc<dyn IdentityStoreApi>,
+        company_store: Arc<dyn CompanyStoreApi>,
+    ) -> Self {
+        Self {
+            email_sender,
+            notification_store,
+            identity_store,
+            company_store,
+        }
+    }
+
+    /// Returns the email address of the local identity, or company with the given node id, if
+    /// it opted in. Other node ids are never emailed, even if there is a preference for them
+    async fn opted_in_email(&self, node_id: &str) -> Result<Option<String>> {
+        match self
+            .notification_store
+            .get_email_preference(node_id)
+            .await?
+        {
+            Some(preference) if preference.enabled => (),
+            _ => return Ok(None),
+        }
+        let identity = self.identity_store.get().await?;
+        let email = if identity.node_id == node_id {
+            identity.email
+        } else if self.company_store.exists(node_id).await {
+            self.company_store.get(node_id).await?.email
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(email).filter(|email| !email.is_empty()))
+    }
+}
+
+#[async_trait]
+impl NotificationHandlerApi for EmailEventHandler {
+    fn handles_event(&self, _event_type: &EventType) -> bool {
+        true
+    }
+
+    async fn handle_event(
+        &self,
+        event: EventEnvelope,
+        _node_id: &str,
+        _sender: &str,
+    ) -> Result<()> {
+        // email is just an additional channel, so errors are only logged
+        let email = match self.opted_in_email(&event.node_id).await {
+            Ok(Some(email)) => email,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                error!(
+                    "Could not get the email preference of {}: {e}",
+                    event.node_id
+                );
+                return Ok(());
+            }
+        };
+        let email_sender = self.email_sender.clone();
+        tokio::spawn(async move {
+            let sent = tokio::time::timeout(
+                Duration::from_secs(EMAIL_TIMEOUT_SECONDS),
+                email_sender.send_event(&email, &event),
+            )
+            .await;
+            match sent {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => error!(
+                    "Could not send {} email to {}: {e}",
+                    event.event_type, event.node_id
+                ),
+                Err(_) => error!(
+                    "Sending {} email to {} timed out",
+                    event.event_type, event.node_id
+                ),
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::email::{EmailMessage, MockNotificationEmailTransportApi};
+    use super::super::test_utils::create_test_event;
+    use super::*;
+    use crate::data::notification::EmailPreference;
+    use crate::service::company_service::tests::get_baseline_company_data;
+    use crate::tests::tests::{
+        MockCompanyStoreApiMock, MockIdentityStoreApiMock, MockNotificationStoreApiMock,
+        TEST_PUB_KEY_SECP, empty_identity,
+    };
+    use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+    fn get_store(enabled_for: &'static str) -> MockNotificationStoreApiMock {
+        let mut store = MockNotificationStoreApiMock::new();
+        store
+            .expect_get_email_preference()
+            .returning(move |node_id| {
+                Ok(Some(EmailPreference {
+                    node_id: node_id.to_owned(),
+                    enabled: node_id == enabled_for,
+                    updated_at: 1000,
+                }))
+            });
+        store
+    }
+
+    fn get_identity_store() -> MockIdentityStoreApiMock {
+        let mut identity_store = MockIdentityStoreApiMock::new();
+        identity_store.expect_get().returning(|| {
+            let mut identity = empty_identity();
+            identity.node_id = "local".to_string();
+            identity.email = "local@example.com".to_string();
+            Ok(identity)
+        });
+        identity_store
+    }
+
+    fn get_sender() -> (EmailEventSender, UnboundedReceiver<EmailMessage>) {
+        let (sender, receiver) = unbounded_channel();
+        let mut transport = MockNotificationEmailTransportApi::new();
+        transport.expect_send().returning(move |message| {
+            sender.send(message).unwrap();
+            Ok(())
+        });
+        (
+            EmailEventSender::new("node@example.com", Arc::new(transport)),
+            receiver,
+        )
+    }
+
+    fn get_envelope(node_id: &str) -> EventEnvelope {
+        let mut envelope: EventEnvelope =
+            create_test_event(&EventType::BillPaid).try_into().unwrap();
+        envelope.node_id = node_id.to_string();
+        envelope
+    }
+
+    #[tokio::test]
+    async fn test_emails_opted_in_local_identity() {
+        let (email_sender, mut sent) = get_sender();
+        let handler = EmailEventHandler::new(
+            email_sender,
+            Arc::new(get_store("local")),
+            Arc::new(get_identity_store()),
+            Arc::new(MockCompanyStoreApiMock::new()),
+        );
+
+        handler
+            .handle_event(get_envelope("local"), "local", "sender")
+            .await
+            .expect("event was not handled");
+
+        let message = sent.recv().await.unwrap();
+        assert_eq!(message.to, "local@example.com");
+        assert_eq!(message.from, "node@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_emails_opted_in_local_company() {
+        let (email_sender, mut sent) = get_sender();
+        let mut company_store = MockCompanyStoreApiMock::new();
+        company_store.expect_exists().returning(|_| true);
+        company_store
+            .expect_get()
+            .returning(|_| Ok(get_baseline_company_data().1.0));
+        let handler = EmailEventHandler::new(
+            email_sender,
+            Arc::new(get_store(TEST_PUB_KEY_SECP)),
+            Arc::new(get_identity_store()),
+            Arc::new(company_store),
+        );
+
+        handler
+            .handle_event(get_envelope(TEST_PUB_KEY_SECP), "local", "sender")
+            .await
+            .expect("event was not handled");
+
+        assert_eq!(sent.recv().await.unwrap().to, "company@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_does_not_email_opted_out_or_other_nodes() {
+        let mut transport = MockNotificationEmailTransportApi::new();
+        transport.expect_send().never();
+        let mut company_store = MockCompanyStoreApiMock::new();
+        company_store.expect_exists().returning(|_| false);
+        let handler = EmailEventHandler::new(
+            EmailEventSender::new("node@example.com", Arc::new(transport)),
+            Arc::new(get_store("contact")),
+            Arc::new(get_identity_store()),
+            Arc::new(company_store),
+        );
+
+        // the local identity didn't opt in
+        handler
+            .handle_event(get_envelope("local"), "local", "sender")
+            .await
+            .expect("event was not handled");
+        // a preference for a node, which isn't local, is ignored
+        handler
+            .handle_event(get_envelope("contact"), "local", "sender")
+            .await
+            .expect("event was not handled");
+    }
+}
@@ -1,12 +1,13 @@
 use super::{NotificationEmailTransportApi, Result, email::EmailMessage};
 use async_trait::async_trait;
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{Value, json};
 
 #[derive(Debug, Clone)]
 pub struct SendgridConfig {
-    api_key: String,
-    url: String,
+    pub api_key: String,
+    /// The base URL of the API, e.g. https://api.sendgrid.com
+    pub url: String,
 }
 
 pub struct SendgridTransport {
@@ -16,7 +17,6 @@ pub struct SendgridTransport {
 
 impl SendgridTransport {
     /// Creates a new instance of the SendgridTransport.
-    #[allow(dead_code)]
     pub fn new(config: &SendgridConfig) -> Self {
         let client = reqwest::Client::new();
         Self {
@@ -32,7 +32,7 @@ impl SendgridTransport {
             .post(url)
             .json(&message)
             .bearer_auth(&self.config.api_key);
-        let _ = request.send().await?;
+        request.send().await?.error_for_status()?;
         Ok(())
     }
 }
@@ -59,7 +59,7 @@ impl TryFrom<EmailMessage> for SendgridMessage {
     fn try_from(message: EmailMessage) -> Result<Self> {
         let from = SendgridAddress::new(message.from);
         let to = SendgridAddress::new(message.to);
-        let personalizations = vec![serde_json::to_value(&from)?, serde_json::to_value(to)?];
+        let personalizations = vec![json!({ "to": [serde_json::to_value(to)?] })];
         let m = SendgridMessage {
             personalizations,
             from,
@@ -92,7 +92,6 @@ struct SendgridContent {
 
 impl SendgridContent {
     /// Text content email
-    #[allow(dead_code)]
     pub fn text(value: String) -> Self {
         Self {
             content_type: "text/plain".to_string(),
@@ -125,7 +124,8 @@ mod tests {
             .expect("Failed to convert email message");
 
         assert_eq!(&m.from.email, &message.from);
-        assert!(m.personalizations.len() == 2);
+        assert_eq!(m.personalizations.len(), 1);
+        assert_eq!(m.personalizations[0]["to"][0]["email"], message.to);
         assert_eq!(&m.subject, &message.subject);
         assert_eq!(m.content.first().expect("No content").value, message.body);
     }
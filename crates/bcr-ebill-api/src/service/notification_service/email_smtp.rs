@@ -0,0 +1,154 @@
+use super::{NotificationEmailTransportApi, Result, email::EmailMessage};
+use async_trait::async_trait;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::header::ContentType,
+    transport::smtp::authentication::Credentials,
+};
+
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: SmtpTls,
+}
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Unencrypted, only meant for local SMTP catchers
+    None,
+    /// Upgrades the connection with STARTTLS, usually on port 587
+    StartTls,
+    /// Implicit TLS, usually on port 465
+    Tls,
+}
+
+/// Sends emails via any SMTP server
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(config: &SmtpConfig) -> Result<Self> {
+        let builder = match config.tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            SmtpTls::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        };
+        let mut builder = builder.port(config.port);
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self {
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationEmailTransportApi for SmtpTransport {
+    async fn send(&self, message: EmailMessage) -> Result<()> {
+        let email = Message::builder()
+            .from(message.from.parse()?)
+            .to(message.to.parse()?)
+            .subject(message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body)?;
+        self.transport.send(email).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::get_test_email_message;
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+
+    /// A local SMTP catcher, which accepts a single mail and passes on the received
+    /// envelope and data
+    async fn start_smtp_catcher() -> (u16, oneshot::Receiver<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut received = vec![];
+            let mut in_data = false;
+            writer.write_all(b"220 localhost ready\r\n").await.unwrap();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let reply: &[u8] = if in_data {
+                    if line == "." {
+                        in_data = false;
+                        b"250 queued\r\n"
+                    } else {
+                        received.push(line);
+                        continue;
+                    }
+                } else if line.starts_with("EHLO") {
+                    b"250 localhost\r\n"
+                } else if line == "DATA" {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if line == "QUIT" {
+                    writer.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    received.push(line);
+                    b"250 ok\r\n"
+                };
+                writer.write_all(reply).await.unwrap();
+            }
+            let _ = sender.send(received);
+        });
+        (port, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_sends_mail() {
+        let (port, receiver) = start_smtp_catcher().await;
+        let transport = SmtpTransport::new(&SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            username: None,
+            password: None,
+            tls: SmtpTls::None,
+        })
+        .unwrap();
+        let message = get_test_email_message();
+        transport
+            .send(message.clone())
+            .await
+            .expect("mail was not sent");
+        drop(transport);
+
+        let received = receiver.await.unwrap();
+        assert!(received.contains(&format!("MAIL FROM:<{}>", message.from)));
+        assert!(received.contains(&format!("RCPT TO:<{}>", message.to)));
+        assert!(received.contains(&format!("Subject: {}", message.subject)));
+        assert!(received.contains(&message.body));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_invalid_address() {
+        let transport = SmtpTransport::new(&SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port: 2525,
+            username: None,
+            password: None,
+            tls: SmtpTls::None,
+        })
+        .unwrap();
+        let mut message = get_test_email_message();
+        message.to = "not an address".to_string();
+        assert!(transport.send(message).await.is_err());
+    }
+}
@@ -16,8 +16,14 @@ pub trait NotificationHandlerApi: Send + Sync {
     /// have checked the event type before calling this method. The actual implementation
     /// should be able to deserialize the data into its T type because the EventType
     /// determines the T type. Identity represents the active identity that is receiving
-    /// the event.
-    async fn handle_event(&self, event: EventEnvelope, node_id: &str) -> Result<()>;
+    /// the event, sender is the hex encoded Nostr public key of the node that sent it.
+    async fn handle_event(&self, event: EventEnvelope, node_id: &str, sender: &str) -> Result<()>;
+
+    /// Retries the events, the handler couldn't handle yet and kept for later. Called
+    /// periodically with the current timestamp.
+    async fn retry_pending_events(&self, _timestamp: u64) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Logs all events that are received and registered in the event_types.
@@ -32,9 +38,9 @@ impl NotificationHandlerApi for LoggingEventHandler {
         self.event_types.contains(event_type)
     }
 
-    async fn handle_event(&self, event: EventEnvelope, identity: &str) -> Result<()> {
+    async fn handle_event(&self, event: EventEnvelope, identity: &str, sender: &str) -> Result<()> {
         info!("########### EVENT RECEIVED #############");
-        info!("Received event: {event:?} for identity: {identity}");
+        info!("Received event: {event:?} for identity: {identity} from: {sender}");
         info!("########################################");
         Ok(())
     }
@@ -62,7 +68,7 @@ mod tests {
 
         // handler should run successfully
         event_handler
-            .handle_event(envelope, "identity")
+            .handle_event(envelope, "identity", "sender")
             .await
             .expect("event was not handled");
 
@@ -4,41 +4,64 @@ use crate::Config;
 use crate::data::{
     bill::BitcreditBill,
     contact::IdentityPublicData,
-    notification::{Notification, NotificationType},
+    notification::{EmailPreference, Notification, NotificationType},
+};
+use crate::persistence::bill::{BillChainStoreApi, BillStoreApi};
+use crate::persistence::company::CompanyStoreApi;
+use crate::persistence::nostr::{
+    NostrEventOffsetStoreApi, NostrPendingEventStoreApi, NostrQuarantineStoreApi,
 };
-use crate::persistence::nostr::NostrEventOffsetStoreApi;
 use crate::persistence::notification::{NotificationFilter, NotificationStoreApi};
 use crate::persistence::{self, identity::IdentityStoreApi};
 use crate::util::{self};
 use async_trait::async_trait;
 use bill_action_event_handler::BillActionEventHandler;
+use bill_chain_sync::{BillChainAttachingTransport, BundleBillChainSync};
 use default_service::DefaultNotificationService;
+use email::EmailEventSender;
+use email_event_handler::EmailEventHandler;
+use email_sendgrid::SendgridTransport;
+use email_smtp::SmtpTransport;
 use handler::{LoggingEventHandler, NotificationHandlerApi};
 #[cfg(test)]
 use mockall::automock;
 use push_notification::PushApi;
 use thiserror::Error;
+use webhook_event_handler::WebhookEventHandler;
 
 #[cfg(test)]
 pub mod test_utils;
 
 pub mod bill_action_event_handler;
+pub mod bill_chain_sync;
 pub mod default_service;
 mod email;
+pub mod email_event_handler;
 mod email_sendgrid;
+mod email_smtp;
 mod event;
 mod handler;
 mod nostr;
 pub mod push_notification;
+mod relay_health;
+mod sender_authorization;
 mod transport;
+pub mod webhook_event_handler;
 
 use bcr_ebill_core::notification::{ActionType, EventType};
-pub use email::NotificationEmailTransportApi;
+pub use email::{
+    EmailConfig, EmailTransportConfig, NotificationEmailTransportApi, SendgridConfig, SmtpConfig,
+    SmtpTls,
+};
 pub use event::EventEnvelope;
 pub use nostr::{NostrClient, NostrConfig, NostrConsumer};
-pub use transport::NotificationJsonTransportApi;
+pub use relay_health::RelayStats;
+pub use sender_authorization::{SenderAuthorization, SenderAuthorizationApi, SenderCheck};
+#[cfg(test)]
+pub use transport::{MockNostrProfileApi, MockNostrPublisherApi};
+pub use transport::{NostrProfileApi, NostrPublisherApi, NotificationJsonTransportApi};
 
-use super::contact_service::ContactServiceApi;
+use super::webhook_service::WebhookServiceApi;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -52,17 +75,41 @@ pub enum Error {
     #[error("http client error: {0}")]
     HttpClient(#[from] reqwest::Error),
 
+    #[error("smtp error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    #[error("invalid email address: {0}")]
+    EmailAddress(#[from] lettre::address::AddressError),
+
+    #[error("invalid email message: {0}")]
+    EmailMessage(#[from] lettre::error::Error),
+
     #[error("nostr key error: {0}")]
     NostrKey(#[from] nostr_sdk::key::Error),
 
     #[error("nostr client error: {0}")]
     NostrClient(#[from] nostr_sdk::client::Error),
 
+    #[error("nostr event error: {0}")]
+    NostrEvent(#[from] nostr_sdk::event::builder::Error),
+
+    #[error("nostr message was not confirmed by any relay: {0}")]
+    NostrNotConfirmed(String),
+
+    #[error("nostr message was confirmed by {confirmed} relays, the write quorum is {quorum}")]
+    NostrQuorumNotReached { confirmed: usize, quorum: usize },
+
     #[error("crypto util error: {0}")]
     CryptoUtil(#[from] util::crypto::Error),
 
     #[error("Persistence error: {0}")]
     Persistence(#[from] persistence::Error),
+
+    #[error("Blockchain error: {0}")]
+    Blockchain(#[from] crate::blockchain::Error),
+
+    #[error("bill chain sync error: {0}")]
+    ChainSync(String),
 }
 
 /// Creates a new nostr client configured with the current identity user.
@@ -76,43 +123,96 @@ pub async fn create_nostr_client(
         Ok(identity) => identity.get_nostr_name(),
         _ => "New user".to_owned(),
     };
-    let config = NostrConfig::new(keys, vec![config.nostr_relay.clone()], nostr_name);
+    let config = NostrConfig::new(keys, config.nostr_relays.clone(), nostr_name)
+        .with_write_quorum(config.nostr_write_quorum);
     NostrClient::new(&config).await
 }
 
-/// Creates a new notification service that will send events via the given Nostr json transport.
+/// Creates a new notification service that will send events via the given json transport, e.g.
+/// the Nostr outbox, and dispatch them to the subscribed webhooks. Bill events are sent with
+/// the bundled chain of the bill attached, so the recipients can sync it.
 pub async fn create_notification_service(
-    client: NostrClient,
+    transport: Box<dyn NotificationJsonTransportApi>,
     notification_store: Arc<dyn NotificationStoreApi>,
+    webhook_service: Arc<dyn WebhookServiceApi>,
+    bill_store: Arc<dyn BillStoreApi>,
+    bill_blockchain_store: Arc<dyn BillChainStoreApi>,
 ) -> Result<Arc<dyn NotificationServiceApi>> {
     Ok(Arc::new(DefaultNotificationService::new(
-        Box::new(client),
+        Box::new(BillChainAttachingTransport::new(
+            transport,
+            bill_store,
+            bill_blockchain_store,
+        )),
         notification_store,
+        webhook_service,
     )))
 }
 
+fn create_email_transport(
+    config: &EmailTransportConfig,
+) -> Result<Arc<dyn NotificationEmailTransportApi>> {
+    Ok(match config {
+        EmailTransportConfig::Sendgrid(config) => Arc::new(SendgridTransport::new(config)),
+        EmailTransportConfig::Smtp(config) => Arc::new(SmtpTransport::new(config)?),
+    })
+}
+
 /// Creates a new nostr consumer that will listen for incoming events and handle them
-/// with the given handlers. The consumer is just set up here and needs to be started
-/// via the run method later.
+/// with the given handlers. Events from senders, who aren't authorized to send them, are put
+/// into quarantine instead. If email is configured, events are also sent to the email address of
+/// the local identity, or company they are for, if it opted in. The consumer is just set up here
+/// and needs to be started via the run method later.
+#[allow(clippy::too_many_arguments)]
 pub async fn create_nostr_consumer(
     client: NostrClient,
-    contact_service: Arc<dyn ContactServiceApi>,
+    sender_authorization: Arc<dyn SenderAuthorizationApi>,
     nostr_event_offset_store: Arc<dyn NostrEventOffsetStoreApi>,
+    nostr_quarantine_store: Arc<dyn NostrQuarantineStoreApi>,
+    nostr_pending_event_store: Arc<dyn NostrPendingEventStoreApi>,
     notification_store: Arc<dyn NotificationStoreApi>,
     push_service: Arc<dyn PushApi>,
+    webhook_service: Arc<dyn WebhookServiceApi>,
+    bill_store: Arc<dyn BillStoreApi>,
+    bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+    identity_store: Arc<dyn IdentityStoreApi>,
+    company_store: Arc<dyn CompanyStoreApi>,
+    email_config: Option<&EmailConfig>,
 ) -> Result<NostrConsumer> {
     // register the logging event handler for all events for now. Later we will probably
     // setup the handlers outside and pass them to the consumer via this functions arguments.
-    let handlers: Vec<Box<dyn NotificationHandlerApi>> = vec![
+    let mut handlers: Vec<Box<dyn NotificationHandlerApi>> = vec![
         Box::new(LoggingEventHandler {
             event_types: EventType::all(),
         }),
         Box::new(BillActionEventHandler::new(
-            notification_store,
+            notification_store.clone(),
             push_service,
+            bill_store.clone(),
+            bill_blockchain_store.clone(),
+            Arc::new(BundleBillChainSync::new(bill_store, bill_blockchain_store)),
+            nostr_pending_event_store,
         )),
+        Box::new(WebhookEventHandler::new(webhook_service)),
     ];
-    let consumer = NostrConsumer::new(client, contact_service, handlers, nostr_event_offset_store);
+    if let Some(email_config) = email_config {
+        handlers.push(Box::new(EmailEventHandler::new(
+            EmailEventSender::new(
+                &email_config.from,
+                create_email_transport(&email_config.transport)?,
+            ),
+            notification_store,
+            identity_store,
+            company_store,
+        )));
+    }
+    let consumer = NostrConsumer::new(
+        client,
+        sender_authorization,
+        handlers,
+        nostr_event_offset_store,
+        nostr_quarantine_store,
+    );
     Ok(consumer)
 }
 
@@ -258,4 +358,15 @@ pub trait NotificationServiceApi: Send + Sync {
         block_height: i32,
         action: ActionType,
     ) -> Result<()>;
+
+    /// Returns the email preference of the given identity, disabled if it never set one
+    async fn get_email_preference(&self, node_id: &str) -> Result<EmailPreference>;
+
+    /// Opts the given identity in or out of receiving the events sent to it via email
+    async fn set_email_preference(
+        &self,
+        node_id: &str,
+        enabled: bool,
+        timestamp: u64,
+    ) -> Result<EmailPreference>;
 }
@@ -1,30 +1,56 @@
 use async_trait::async_trait;
-use log::{error, trace, warn};
+use log::{error, info, trace, warn};
 use nostr_sdk::Timestamp;
 use nostr_sdk::prelude::*;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
+use tokio::time;
 
-use crate::service::contact_service::ContactServiceApi;
-use crate::util::{BcrKeys, crypto};
-use bcr_ebill_persistence::NostrEventOffset;
+use crate::util::{self, BcrKeys, crypto};
+use bcr_ebill_persistence::{NostrEventOffset, QuarantinedEvent};
 
 use super::handler::NotificationHandlerApi;
-use super::{EventEnvelope, NotificationJsonTransportApi, Result};
+use super::relay_health::{RelayHealth, RelayStats};
+use super::sender_authorization::{SenderAuthorizationApi, SenderCheck};
+use super::transport::{NostrProfileApi, NostrPublisherApi};
+use super::{Error, EventEnvelope, NotificationJsonTransportApi, Result};
 use crate::data::contact::IdentityPublicData;
-use bcr_ebill_persistence::NostrEventOffsetStoreApi;
+use crate::data::profile::PublicProfile;
+use bcr_ebill_persistence::{NostrEventOffsetStoreApi, NostrQuarantineStoreApi};
+
+/// All our events arrive via one subscription, which is replaced when back-filling
+const SUBSCRIPTION_ID: &str = "bcr-events";
+/// How often the connection state of the relays is checked
+const RELAY_CHECK_INTERVAL_SECONDS: u64 = 30;
+/// The identifier of our profile events, so they are replaced by newer ones on the relays
+const PROFILE_IDENTIFIER: &str = "bcr-profile";
+/// How long we wait for the relays to return profile events
+const PROFILE_FETCH_TIMEOUT_SECONDS: u64 = 10;
 
 #[derive(Clone, Debug)]
 pub struct NostrConfig {
     keys: BcrKeys,
     relays: Vec<String>,
     name: String,
+    write_quorum: usize,
 }
 
 impl NostrConfig {
     pub fn new(keys: BcrKeys, relays: Vec<String>, name: String) -> Self {
-        Self { keys, relays, name }
+        Self {
+            keys,
+            relays,
+            name,
+            write_quorum: 1,
+        }
+    }
+
+    /// Sets to how many relays an event has to be published, for the publish to be successful
+    pub fn with_write_quorum(mut self, write_quorum: usize) -> Self {
+        self.write_quorum = write_quorum;
+        self
     }
 
     #[allow(dead_code)]
@@ -41,6 +67,7 @@ impl NostrConfig {
 ///     keys: BcrKeys::new(),
 ///     relays: vec!["wss://relay.example.com".to_string()],
 ///     name: "My Company".to_string(),
+///     write_quorum: 1,
 /// };
 /// let transport = NostrClient::new(&config).await.unwrap();
 /// transport.send(&recipient, event).await.unwrap();
@@ -52,6 +79,9 @@ impl NostrConfig {
 pub struct NostrClient {
     pub keys: BcrKeys,
     pub client: Client,
+    relays: Vec<String>,
+    write_quorum: usize,
+    health: Arc<RelayHealth>,
 }
 
 impl NostrClient {
@@ -63,7 +93,12 @@ impl NostrClient {
             .signer(keys.get_nostr_keys().clone())
             .opts(options)
             .build();
-        for relay in &config.relays {
+        let relays: Vec<String> = config
+            .relays
+            .iter()
+            .map(|relay| normalize_relay_url(relay))
+            .collect();
+        for relay in &relays {
             client.add_relay(relay).await?;
         }
         client.connect().await;
@@ -71,15 +106,41 @@ impl NostrClient {
             .name(&config.name)
             .display_name(&config.name);
         client.set_metadata(&metadata).await?;
-        Ok(Self { keys, client })
+        Ok(Self {
+            keys,
+            client,
+            health: Arc::new(RelayHealth::new(&relays)),
+            relays,
+            write_quorum: config.write_quorum.max(1),
+        })
     }
 
-    /// Subscribe to some nostr events with a filter
+    /// Subscribe to some nostr events with a filter on all relays
     pub async fn subscribe(&self, subscription: Filter) -> Result<()> {
-        self.client.subscribe(subscription, None).await?;
+        self.client
+            .subscribe_with_id(SubscriptionId::new(SUBSCRIPTION_ID), subscription, None)
+            .await?;
         Ok(())
     }
 
+    /// Replaces the subscription on the given relay, e.g. to back-fill events after an outage
+    pub async fn subscribe_to(&self, relay: &str, subscription: Filter) -> Result<()> {
+        self.client
+            .subscribe_with_id_to(
+                vec![relay],
+                SubscriptionId::new(SUBSCRIPTION_ID),
+                subscription,
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the health of the relays we publish to
+    pub fn relay_stats(&self) -> Vec<RelayStats> {
+        self.health.list()
+    }
+
     /// Unwrap envelope from private direct message
     pub async fn unwrap_envelope(
         &self,
@@ -100,60 +161,166 @@ impl NostrClient {
         }
         result
     }
+
+    /// The relays a message to a recipient is published to. These are the recipient's
+    /// relays, or ours if it has none. Unhealthy relays are skipped, unless we need them to
+    /// reach the write quorum.
+    async fn publish_relays(&self, recipient_relays: &[String]) -> Result<Vec<String>> {
+        let relays: Vec<String> = if recipient_relays.is_empty() {
+            self.relays.clone()
+        } else {
+            recipient_relays
+                .iter()
+                .map(|relay| normalize_relay_url(relay))
+                .collect()
+        };
+        for relay in &relays {
+            // relays have to be in the pool to publish to them
+            if self.client.add_relay(relay).await? {
+                self.client.connect_relay(relay).await?;
+            }
+        }
+        let by_preference = self.health.by_preference(&relays);
+        let healthy = self.health.healthy_count(&by_preference);
+        Ok(by_preference
+            .into_iter()
+            .take(healthy.max(self.write_quorum))
+            .collect())
+    }
 }
 
 #[async_trait]
-impl NotificationJsonTransportApi for NostrClient {
-    async fn send(&self, recipient: &IdentityPublicData, event: EventEnvelope) -> Result<()> {
-        if let Ok(npub) = crypto::get_nostr_npub_as_hex_from_node_id(&recipient.node_id) {
-            let public_key = PublicKey::from_str(&npub)?;
-            let message = serde_json::to_string(&event)?;
-            if let Some(relay) = &recipient.nostr_relay {
-                if let Err(e) = self
-                    .client
-                    .send_private_msg_to(vec![relay], public_key, message, None)
-                    .await
-                {
-                    error!("Error sending Nostr message: {e}")
-                };
-            } else if let Err(e) = self
-                .client
-                .send_private_msg(public_key, message, None)
-                .await
-            {
-                error!("Error sending Nostr message: {e}")
-            }
-        } else {
-            error!(
-                "Try to send Nostr message but Nostr npub not found in contact {}",
-                recipient.name
-            );
+impl NostrPublisherApi for NostrClient {
+    async fn publish(&self, recipient: &str, relays: &[String], message: &str) -> Result<()> {
+        let npub = crypto::get_nostr_npub_as_hex_from_node_id(recipient)?;
+        let public_key = PublicKey::from_str(&npub)?;
+        let relays = self.publish_relays(relays).await?;
+        let started = Instant::now();
+        let output = self
+            .client
+            .send_private_msg_to(relays.clone(), public_key, message, None)
+            .await?;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let timestamp = util::date::now().timestamp() as u64;
+        for relay in &output.success {
+            self.health
+                .record_success(&relay.to_string(), latency_ms, timestamp);
+        }
+        for (relay, e) in &output.failed {
+            warn!("Could not publish Nostr message to {relay}: {e}");
+            self.health
+                .record_failure(&relay.to_string(), &e.to_string(), timestamp);
+        }
+        if output.success.is_empty() {
+            return Err(Error::NostrNotConfirmed(
+                output
+                    .failed
+                    .values()
+                    .next()
+                    .cloned()
+                    .unwrap_or_else(|| "no relay to publish to".to_owned()),
+            ));
+        }
+        let quorum = self.write_quorum.min(relays.len());
+        if output.success.len() < quorum {
+            return Err(Error::NostrQuorumNotReached {
+                confirmed: output.success.len(),
+                quorum,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NostrProfileApi for NostrClient {
+    async fn publish_profile(&self, profile: &PublicProfile, keys: &BcrKeys) -> Result<()> {
+        let event = EventBuilder::new(
+            Kind::ApplicationSpecificData,
+            serde_json::to_string(profile)?,
+        )
+        .tag(Tag::identifier(PROFILE_IDENTIFIER))
+        .sign_with_keys(&keys.get_nostr_keys())?;
+        let output = self.client.send_event(event).await?;
+        if output.success.is_empty() {
+            return Err(Error::NostrNotConfirmed(format!(
+                "profile of {} was not published",
+                profile.node_id
+            )));
         }
         Ok(())
     }
+
+    async fn fetch_profile(&self, npub: &str) -> Result<Option<PublicProfile>> {
+        let author = PublicKey::from_hex(npub)?;
+        let filter = Filter::new()
+            .author(author)
+            .kind(Kind::ApplicationSpecificData)
+            .identifier(PROFILE_IDENTIFIER);
+        let events = self
+            .client
+            .fetch_events(filter, Duration::from_secs(PROFILE_FETCH_TIMEOUT_SECONDS))
+            .await?;
+        // relays could return events of other authors, or with broken signatures
+        let latest = events
+            .into_iter()
+            .filter(|event| event.pubkey == author && event.verify().is_ok())
+            .max_by_key(|event| event.created_at);
+        match latest {
+            Some(event) => Ok(Some(serde_json::from_str(&event.content)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationJsonTransportApi for NostrClient {
+    async fn send(&self, recipient: &IdentityPublicData, event: EventEnvelope) -> Result<()> {
+        let message = serde_json::to_string(&event)?;
+        self.publish(&recipient.node_id, &recipient.nostr_relays, &message)
+            .await
+    }
+}
+
+/// Brings relay URLs into the form nostr_sdk uses, so we can compare them
+fn normalize_relay_url(relay: &str) -> String {
+    RelayUrl::parse(relay)
+        .map(|url| url.to_string())
+        .unwrap_or_else(|_| relay.to_owned())
+}
+
+fn event_filter(public_key: PublicKey, since: Timestamp) -> Filter {
+    // only private messages sent to our pubkey
+    Filter::new()
+        .pubkey(public_key)
+        .kind(Kind::GiftWrap)
+        .since(since)
 }
 
 #[derive(Clone)]
 pub struct NostrConsumer {
     client: NostrClient,
     event_handlers: Arc<Vec<Box<dyn NotificationHandlerApi>>>,
-    contact_service: Arc<dyn ContactServiceApi>,
+    sender_authorization: Arc<dyn SenderAuthorizationApi>,
     offset_store: Arc<dyn NostrEventOffsetStoreApi>,
+    quarantine_store: Arc<dyn NostrQuarantineStoreApi>,
 }
 
 impl NostrConsumer {
     #[allow(dead_code)]
     pub fn new(
         client: NostrClient,
-        contact_service: Arc<dyn ContactServiceApi>,
+        sender_authorization: Arc<dyn SenderAuthorizationApi>,
         event_handlers: Vec<Box<dyn NotificationHandlerApi>>,
         offset_store: Arc<dyn NostrEventOffsetStoreApi>,
+        quarantine_store: Arc<dyn NostrQuarantineStoreApi>,
     ) -> Self {
         Self {
             client,
             event_handlers: Arc::new(event_handlers),
-            contact_service,
+            sender_authorization,
             offset_store,
+            quarantine_store,
         }
     }
 
@@ -162,55 +329,113 @@ impl NostrConsumer {
         // move dependencies into thread scope
         let client = self.client.clone();
         let event_handlers = self.event_handlers.clone();
-        let _contact_service = self.contact_service.clone();
+        let sender_authorization = self.sender_authorization.clone();
         let offset_store = self.offset_store.clone();
+        let quarantine_store = self.quarantine_store.clone();
 
         // continue where we left off
         let offset_ts = get_offset(&offset_store).await;
         let public_key = client.keys.get_nostr_keys().public_key();
         let node_id = client.keys.get_public_key();
 
-        // subscribe only to private messages sent to our pubkey
         client
-            .subscribe(
-                Filter::new()
-                    .pubkey(public_key)
-                    .kind(Kind::GiftWrap)
-                    .since(offset_ts),
-            )
+            .subscribe(event_filter(public_key, offset_ts))
             .await
             .expect("Failed to subscribe to Nostr events");
 
         // run subscription in a tokio task
         let handle = tokio::spawn(async move {
-            client
-                .client
-                .handle_notifications(|note| async {
-                    if let Some((envelope, sender, event_id, time)) =
-                        client.unwrap_envelope(note).await
-                    {
-                        if !offset_store.is_processed(&event_id.to_hex()).await? {
-                            if let Ok(sender_npub) = sender.to_bech32() {
-                                let sender_node_id = sender.to_hex();
-                                trace!("Received event: {envelope:?} from {sender_npub:?} (hex: {sender_node_id})");
-                                // We use hex here, so we can compare it with our node_ids
-                                // TODO: re-enable after presentation: if contact_service.is_known_npub(&sender_node_id).await? {
-                                    trace!("Processing event: {envelope:?}");
-                                    handle_event(envelope, &node_id, &event_handlers).await?;
-                                // }
-                            }
+            let monitor = monitor_relays(client.clone(), offset_store.clone());
+            let notifications = client.client.handle_notifications(|note| async {
+                if let Some((envelope, sender, event_id, time)) = client.unwrap_envelope(note).await
+                {
+                    // errors are handled per event, so they never stop the consumer
+                    match offset_store.is_processed(&event_id.to_hex()).await {
+                        Ok(true) => (),
+                        Ok(false) => {
+                            process_event(
+                                envelope,
+                                sender,
+                                event_id,
+                                &node_id,
+                                &sender_authorization,
+                                &event_handlers,
+                                &quarantine_store,
+                            )
+                            .await;
 
                             // store the new event offset
                             add_offset(&offset_store, event_id, time, true).await;
                         }
-                    };
-                    Ok(false)
-                })
-                .await
-                .expect("Nostr notification handler failed");
+                        // it's re-delivered by the next back-fill, since there is no offset
+                        Err(e) => error!(
+                            "Skipping event {event_id}, could not check if it was processed: {e}"
+                        ),
+                    }
+                };
+                Ok(false)
+            });
+            tokio::select! {
+                result = notifications => {
+                    if let Err(e) = result {
+                        error!("Nostr notification handler stopped: {e}");
+                    }
+                }
+                _ = monitor => (),
+            }
         });
         Ok(handle)
     }
+
+    /// Returns the health of the relays we publish to
+    pub fn relay_stats(&self) -> Vec<RelayStats> {
+        self.client.relay_stats()
+    }
+
+    /// Passes an event, that was released from the quarantine, on to the event handlers
+    pub async fn handle_released_event(&self, event: EventEnvelope, sender: &str) -> Result<()> {
+        handle_event(
+            event,
+            &self.client.keys.get_public_key(),
+            sender,
+            &self.event_handlers,
+        )
+        .await
+    }
+
+    /// Lets the event handlers retry the events they kept for later
+    pub async fn retry_pending_events(&self, timestamp: u64) -> Result<()> {
+        for handler in self.event_handlers.iter() {
+            handler.retry_pending_events(timestamp).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps track of the connection state of our relays. Relays reconnect by themselves, but we
+/// back-fill the events we missed during an outage from our last offset.
+async fn monitor_relays(client: NostrClient, offset_store: Arc<dyn NostrEventOffsetStoreApi>) {
+    let public_key = client.keys.get_nostr_keys().public_key();
+    let mut interval = time::interval(Duration::from_secs(RELAY_CHECK_INTERVAL_SECONDS));
+    loop {
+        interval.tick().await;
+        for (url, relay) in client.client.relays().await {
+            let url = url.to_string();
+            if !client.relays.contains(&url) {
+                continue;
+            }
+            if client.health.set_connected(&url, relay.is_connected()) {
+                let since = get_offset(&offset_store).await;
+                info!("Nostr relay {url} reconnected, back-filling events since {since}");
+                if let Err(e) = client
+                    .subscribe_to(&url, event_filter(public_key, since))
+                    .await
+                {
+                    error!("Could not back-fill events from Nostr relay {url}: {e}");
+                }
+            }
+        }
+    }
 }
 
 async fn get_offset(db: &Arc<dyn NostrEventOffsetStoreApi>) -> Timestamp {
@@ -239,6 +464,72 @@ async fn add_offset(
     .ok();
 }
 
+async fn quarantine_event(
+    db: &Arc<dyn NostrQuarantineStoreApi>,
+    event_id: EventId,
+    sender: String,
+    envelope: EventEnvelope,
+    reason: String,
+) {
+    let payload = match serde_json::to_string(&envelope) {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!("Could not serialize quarantined event: {e}");
+            return;
+        }
+    };
+    db.add(QuarantinedEvent {
+        id: event_id.to_hex(),
+        sender,
+        event_type: envelope.event_type,
+        payload,
+        reason,
+        received_at: util::date::now().timestamp() as u64,
+    })
+    .await
+    .map_err(|e| error!("Could not quarantine event: {e}"))
+    .ok();
+}
+
+/// Checks the sender of an event and passes the event on to the handlers, or puts it into
+/// quarantine. Events, whose sender can't be checked, are quarantined as well, so they can
+/// still be released later
+async fn process_event(
+    envelope: EventEnvelope,
+    sender: PublicKey,
+    event_id: EventId,
+    node_id: &str,
+    sender_authorization: &Arc<dyn SenderAuthorizationApi>,
+    event_handlers: &Arc<Vec<Box<dyn NotificationHandlerApi>>>,
+    quarantine_store: &Arc<dyn NostrQuarantineStoreApi>,
+) {
+    // We use hex here, so we can compare it with our node_ids
+    let sender_node_id = sender.to_hex();
+    trace!("Received event: {envelope:?} from {sender_node_id}");
+    let reason = match sender_authorization
+        .check_sender(&sender_node_id, &envelope)
+        .await
+    {
+        Ok(SenderCheck::Authorized) => {
+            trace!("Processing event: {envelope:?}");
+            if let Err(e) = handle_event(envelope, node_id, &sender_node_id, event_handlers).await {
+                error!("Could not handle event {event_id}: {e}");
+            }
+            return;
+        }
+        Ok(SenderCheck::Rejected(reason)) => reason,
+        Err(e) => {
+            error!("Could not check the sender of event {event_id}: {e}");
+            format!("the sender could not be checked: {e}")
+        }
+    };
+    warn!(
+        "Quarantined {:?} event from {sender_node_id}: {reason}",
+        envelope.event_type
+    );
+    quarantine_event(quarantine_store, event_id, sender_node_id, envelope, reason).await;
+}
+
 fn extract_event_envelope(rumor: UnsignedEvent) -> Option<EventEnvelope> {
     if rumor.kind == Kind::PrivateDirectMessage {
         match serde_json::from_str::<EventEnvelope>(rumor.content.as_str()) {
@@ -257,13 +548,17 @@ fn extract_event_envelope(rumor: UnsignedEvent) -> Option<EventEnvelope> {
 async fn handle_event(
     event: EventEnvelope,
     node_id: &str,
+    sender: &str,
     handlers: &Arc<Vec<Box<dyn NotificationHandlerApi>>>,
 ) -> Result<()> {
     let event_type = &event.event_type;
     let mut times = 0;
     for handler in handlers.iter() {
         if handler.handles_event(event_type) {
-            match handler.handle_event(event.to_owned(), node_id).await {
+            match handler
+                .handle_event(event.to_owned(), node_id, sender)
+                .await
+            {
                 Ok(_) => times += 1,
                 Err(e) => error!("Nostr event handler failed: {e}"),
             }
@@ -285,17 +580,16 @@ mod tests {
     use tokio::time;
 
     use super::super::test_utils::get_mock_relay;
-    use super::{NostrClient, NostrConfig, NostrConsumer};
+    use super::{Error, NostrClient, NostrConfig, NostrConsumer};
     use crate::persistence::nostr::NostrEventOffset;
     use crate::service::notification_service::event::Event;
-    use crate::service::{
-        contact_service::MockContactServiceApi,
-        notification_service::{
-            EventType, NotificationJsonTransportApi, handler::MockNotificationHandlerApi,
-            test_utils::*,
-        },
+    use crate::service::notification_service::{
+        EventType, NotificationJsonTransportApi,
+        handler::MockNotificationHandlerApi,
+        sender_authorization::{MockSenderAuthorizationApi, SenderCheck},
+        test_utils::*,
     };
-    use crate::tests::tests::MockNostrEventOffsetStoreApiMock;
+    use crate::tests::tests::{MockNostrEventOffsetStoreApiMock, MockNostrQuarantineStoreApiMock};
     use crate::util::BcrKeys;
 
     /// When testing with the mock relay we need to be careful. It is always
@@ -314,6 +608,7 @@ mod tests {
             keys: keys1.clone(),
             relays: vec![url.to_string()],
             name: "BcrDamus1".to_string(),
+            write_quorum: 1,
         };
         let client1 = NostrClient::new(&config1)
             .await
@@ -323,6 +618,7 @@ mod tests {
             keys: keys2.clone(),
             relays: vec![url.to_string()],
             name: "BcrDamus2".to_string(),
+            write_quorum: 1,
         };
         let client2 = NostrClient::new(&config2)
             .await
@@ -334,12 +630,14 @@ mod tests {
         let mut event = create_test_event(&EventType::BillSigned);
         event.node_id = contact.node_id.to_owned();
 
-        // expect the receiver to check if the sender contact is known
-        let mut contact_service = MockContactServiceApi::new();
-        contact_service
-            .expect_is_known_npub()
-            .with(predicate::eq(keys1.get_nostr_npub_as_hex()))
-            .returning(|_| Ok(true));
+        let expected_sender = keys1.get_nostr_npub_as_hex();
+
+        // expect the receiver to check if the sender is authorized
+        let mut sender_authorization = MockSenderAuthorizationApi::new();
+        sender_authorization
+            .expect_check_sender()
+            .withf(move |sender, _| sender == keys1.get_nostr_npub_as_hex())
+            .returning(|_, _| Ok(SenderCheck::Authorized));
 
         // expect a handler that is subscribed to the event type w sent
         let mut handler = MockNotificationHandlerApi::new();
@@ -352,7 +650,7 @@ mod tests {
         let expected_event: Event<TestEventPayload> = event.clone();
         handler
             .expect_handle_event()
-            .withf(move |e, i| {
+            .withf(move |e, i, s| {
                 let expected = expected_event.clone();
                 let received: Event<TestEventPayload> =
                     e.clone().try_into().expect("could not convert event");
@@ -360,9 +658,10 @@ mod tests {
                 let valid_receiver = received.node_id == expected.node_id;
                 let valid_payload = received.data.foo == expected.data.foo;
                 let valid_identity = i == keys2.get_public_key();
-                valid_type && valid_receiver && valid_payload && valid_identity
+                let valid_sender = s == expected_sender;
+                valid_type && valid_receiver && valid_payload && valid_identity && valid_sender
             })
-            .returning(|_, _| Ok(()));
+            .returning(|_, _, _| Ok(()));
 
         let mut offset_store = MockNostrEventOffsetStoreApiMock::new();
 
@@ -386,12 +685,17 @@ mod tests {
             .returning(|_| Ok(()))
             .once();
 
+        // and nothing to be quarantined
+        let mut quarantine_store = MockNostrQuarantineStoreApiMock::new();
+        quarantine_store.expect_add().never();
+
         // we start the consumer
         let consumer = NostrConsumer::new(
             client2,
-            Arc::new(contact_service),
+            Arc::new(sender_authorization),
             vec![Box::new(handler)],
             Arc::new(offset_store),
+            Arc::new(quarantine_store),
         );
         let handle = consumer
             .start()
@@ -408,4 +712,198 @@ mod tests {
         time::sleep(Duration::from_millis(100)).await;
         handle.abort();
     }
+
+    #[tokio::test]
+    async fn test_quarantines_event_of_unauthorized_sender() {
+        let relay = get_mock_relay().await;
+        let url = relay.url();
+
+        let keys1 = BcrKeys::new();
+        let keys2 = BcrKeys::new();
+
+        // given two clients
+        let client1 = NostrClient::new(&NostrConfig {
+            keys: keys1.clone(),
+            relays: vec![url.to_string()],
+            name: "BcrDamus1".to_string(),
+            write_quorum: 1,
+        })
+        .await
+        .expect("failed to create nostr client 1");
+        let client2 = NostrClient::new(&NostrConfig {
+            keys: keys2.clone(),
+            relays: vec![url.to_string()],
+            name: "BcrDamus2".to_string(),
+            write_quorum: 1,
+        })
+        .await
+        .expect("failed to create nostr client 2");
+
+        let contact =
+            get_identity_public_data(&keys2.get_public_key(), "payee@example.com", Some(&url));
+        let mut event = create_test_event(&EventType::BillSigned);
+        event.node_id = contact.node_id.to_owned();
+
+        // the sender is not authorized to send the event
+        let mut sender_authorization = MockSenderAuthorizationApi::new();
+        sender_authorization
+            .expect_check_sender()
+            .returning(|_, _| Ok(SenderCheck::Rejected("unknown sender".to_string())));
+
+        // so it must not reach the handlers
+        let mut handler = MockNotificationHandlerApi::new();
+        handler.expect_handles_event().returning(|_| true);
+        handler.expect_handle_event().never();
+
+        let mut offset_store = MockNostrEventOffsetStoreApiMock::new();
+        offset_store.expect_current_offset().returning(|| Ok(1000));
+        offset_store.expect_is_processed().returning(|_| Ok(false));
+        offset_store
+            .expect_add_event()
+            .withf(|e: &NostrEventOffset| e.success)
+            .returning(|_| Ok(()))
+            .once();
+
+        // but is put into quarantine instead
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let mut quarantine_store = MockNostrQuarantineStoreApiMock::new();
+        quarantine_store.expect_add().returning(move |e| {
+            sender.send(e).unwrap();
+            Ok(())
+        });
+
+        let consumer = NostrConsumer::new(
+            client2,
+            Arc::new(sender_authorization),
+            vec![Box::new(handler)],
+            Arc::new(offset_store),
+            Arc::new(quarantine_store),
+        );
+        let handle = consumer
+            .start()
+            .await
+            .expect("failed to start nostr consumer");
+
+        client1
+            .send(&contact, event.try_into().expect("could not convert event"))
+            .await
+            .expect("failed to send event");
+
+        let quarantined = time::timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .expect("event was not quarantined")
+            .unwrap();
+        handle.abort();
+
+        assert_eq!(quarantined.sender, keys1.get_nostr_npub_as_hex());
+        assert_eq!(quarantined.event_type, EventType::BillSigned);
+        assert_eq!(quarantined.reason, "unknown sender");
+        assert!(quarantined.payload.contains("BillSigned"));
+    }
+
+    #[tokio::test]
+    async fn test_keeps_consuming_if_sender_check_fails() {
+        let relay = get_mock_relay().await;
+        let url = relay.url();
+
+        let keys1 = BcrKeys::new();
+        let keys2 = BcrKeys::new();
+
+        let client1 = NostrClient::new(&NostrConfig {
+            keys: keys1.clone(),
+            relays: vec![url.to_string()],
+            name: "BcrDamus1".to_string(),
+            write_quorum: 1,
+        })
+        .await
+        .expect("failed to create nostr client 1");
+        let client2 = NostrClient::new(&NostrConfig {
+            keys: keys2.clone(),
+            relays: vec![url.to_string()],
+            name: "BcrDamus2".to_string(),
+            write_quorum: 1,
+        })
+        .await
+        .expect("failed to create nostr client 2");
+
+        let contact =
+            get_identity_public_data(&keys2.get_public_key(), "payee@example.com", Some(&url));
+        let mut failing = create_test_event(&EventType::BillSigned);
+        failing.node_id = contact.node_id.to_owned();
+        let mut working = create_test_event(&EventType::BillAccepted);
+        working.node_id = contact.node_id.to_owned();
+
+        // the sender check fails for the first event only
+        let mut sender_authorization = MockSenderAuthorizationApi::new();
+        sender_authorization
+            .expect_check_sender()
+            .returning(|_, event| match event.event_type {
+                EventType::BillSigned => Err(Error::NostrNotConfirmed("db down".to_string())),
+                _ => Ok(SenderCheck::Authorized),
+            });
+
+        // so only the second event reaches the handlers
+        let (handled_sender, mut handled) = tokio::sync::mpsc::unbounded_channel();
+        let mut handler = MockNotificationHandlerApi::new();
+        handler.expect_handles_event().returning(|_| true);
+        handler.expect_handle_event().returning(move |event, _, _| {
+            handled_sender.send(event.event_type).unwrap();
+            Ok(())
+        });
+
+        let mut offset_store = MockNostrEventOffsetStoreApiMock::new();
+        offset_store.expect_current_offset().returning(|| Ok(1000));
+        offset_store.expect_is_processed().returning(|_| Ok(false));
+        offset_store.expect_add_event().returning(|_| Ok(()));
+
+        // and the first one is put into quarantine
+        let (quarantined_sender, mut quarantined) = tokio::sync::mpsc::unbounded_channel();
+        let mut quarantine_store = MockNostrQuarantineStoreApiMock::new();
+        quarantine_store.expect_add().returning(move |e| {
+            quarantined_sender.send(e).unwrap();
+            Ok(())
+        });
+
+        let consumer = NostrConsumer::new(
+            client2,
+            Arc::new(sender_authorization),
+            vec![Box::new(handler)],
+            Arc::new(offset_store),
+            Arc::new(quarantine_store),
+        );
+        let handle = consumer
+            .start()
+            .await
+            .expect("failed to start nostr consumer");
+
+        client1
+            .send(
+                &contact,
+                failing.try_into().expect("could not convert event"),
+            )
+            .await
+            .expect("failed to send event");
+        client1
+            .send(
+                &contact,
+                working.try_into().expect("could not convert event"),
+            )
+            .await
+            .expect("failed to send event");
+
+        let handled_type = time::timeout(Duration::from_secs(1), handled.recv())
+            .await
+            .expect("event was not handled")
+            .unwrap();
+        let quarantined = time::timeout(Duration::from_secs(1), quarantined.recv())
+            .await
+            .expect("event was not quarantined")
+            .unwrap();
+        assert!(!handle.is_finished());
+        handle.abort();
+
+        assert_eq!(handled_type, EventType::BillAccepted);
+        assert_eq!(quarantined.event_type, EventType::BillSigned);
+        assert!(quarantined.reason.contains("db down"));
+    }
 }
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// After this many failures in a row, a relay is considered unhealthy until it works again
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// The health of a single relay, as we experienced it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayStats {
+    pub url: String,
+    pub connected: bool,
+    /// The duration of the latest successful publish in milliseconds
+    pub latency_ms: Option<u64>,
+    pub last_success: Option<u64>,
+    pub last_failure: Option<u64>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+impl RelayStats {
+    /// Relays are added to the client and connected before we track them
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            connected: true,
+            latency_ms: None,
+            last_success: None,
+            last_failure: None,
+            last_error: None,
+            consecutive_failures: 0,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures < MAX_CONSECUTIVE_FAILURES
+    }
+}
+
+/// Keeps track of the health of our relays, so publishing can prefer the ones that work
+#[derive(Debug, Default)]
+pub struct RelayHealth {
+    relays: Mutex<HashMap<String, RelayStats>>,
+}
+
+impl RelayHealth {
+    pub fn new(urls: &[String]) -> Self {
+        Self {
+            relays: Mutex::new(
+                urls.iter()
+                    .map(|url| (url.to_owned(), RelayStats::new(url)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, RelayStats>> {
+        self.relays.lock().expect("relay health lock poisoned")
+    }
+
+    pub fn record_success(&self, url: &str, latency_ms: u64, timestamp: u64) {
+        let mut relays = self.lock();
+        let stats = relays
+            .entry(url.to_owned())
+            .or_insert_with(|| RelayStats::new(url));
+        stats.latency_ms = Some(latency_ms);
+        stats.last_success = Some(timestamp);
+        stats.consecutive_failures = 0;
+    }
+
+    pub fn record_failure(&self, url: &str, error: &str, timestamp: u64) {
+        let mut relays = self.lock();
+        let stats = relays
+            .entry(url.to_owned())
+            .or_insert_with(|| RelayStats::new(url));
+        stats.last_failure = Some(timestamp);
+        stats.last_error = Some(error.to_owned());
+        stats.consecutive_failures += 1;
+    }
+
+    /// Sets the connection state of the relay and returns true, if it just reconnected
+    pub fn set_connected(&self, url: &str, connected: bool) -> bool {
+        let mut relays = self.lock();
+        let stats = relays
+            .entry(url.to_owned())
+            .or_insert_with(|| RelayStats::new(url));
+        let reconnected = connected && !stats.connected;
+        stats.connected = connected;
+        reconnected
+    }
+
+    /// Returns the given relays, healthy ones first and the ones with lower latency before
+    /// the others
+    pub fn by_preference(&self, urls: &[String]) -> Vec<String> {
+        let relays = self.lock();
+        let mut sorted: Vec<(bool, u64, &String)> = urls
+            .iter()
+            .map(|url| match relays.get(url) {
+                Some(stats) => (
+                    !stats.is_healthy(),
+                    stats.latency_ms.unwrap_or(u64::MAX),
+                    url,
+                ),
+                None => (false, u64::MAX, url),
+            })
+            .collect();
+        // stable, so relays we know nothing about keep their order
+        sorted.sort_by_key(|(unhealthy, latency, _)| (*unhealthy, *latency));
+        sorted
+            .into_iter()
+            .map(|(_, _, url)| url.to_owned())
+            .collect()
+    }
+
+    /// Returns how many of the given relays are healthy
+    pub fn healthy_count(&self, urls: &[String]) -> usize {
+        let relays = self.lock();
+        urls.iter()
+            .filter(|url| relays.get(*url).is_none_or(|stats| stats.is_healthy()))
+            .count()
+    }
+
+    /// Returns the stats of all relays, sorted by URL
+    pub fn list(&self) -> Vec<RelayStats> {
+        let mut stats: Vec<RelayStats> = self.lock().values().cloned().collect();
+        stats.sort_by(|a, b| a.url.cmp(&b.url));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls() -> Vec<String> {
+        vec![
+            "wss://relay1.example.com".to_string(),
+            "wss://relay2.example.com".to_string(),
+            "wss://relay3.example.com".to_string(),
+        ]
+    }
+
+    #[test]
+    fn test_tracks_successes_and_failures() {
+        let health = RelayHealth::new(&urls());
+        health.record_failure("wss://relay1.example.com", "timeout", 100);
+        health.record_success("wss://relay2.example.com", 42, 200);
+
+        let stats = health.list();
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].last_failure, Some(100));
+        assert_eq!(stats[0].last_error, Some("timeout".to_string()));
+        assert_eq!(stats[0].consecutive_failures, 1);
+        assert_eq!(stats[1].latency_ms, Some(42));
+        assert_eq!(stats[1].last_success, Some(200));
+
+        // a success resets the failures
+        health.record_success("wss://relay1.example.com", 10, 300);
+        assert_eq!(health.list()[0].consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_becomes_unhealthy_after_consecutive_failures() {
+        let health = RelayHealth::new(&urls());
+        for i in 0..MAX_CONSECUTIVE_FAILURES {
+            assert!(health.list()[0].is_healthy());
+            health.record_failure("wss://relay1.example.com", "refused", i as u64);
+        }
+        assert!(!health.list()[0].is_healthy());
+    }
+
+    #[test]
+    fn test_prefers_healthy_and_fast_relays() {
+        let health = RelayHealth::new(&urls());
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            health.record_failure("wss://relay1.example.com", "refused", 100);
+        }
+        health.record_success("wss://relay2.example.com", 300, 100);
+        health.record_success("wss://relay3.example.com", 20, 100);
+        assert_eq!(health.healthy_count(&urls()), 2);
+        assert_eq!(
+            health.by_preference(&urls()),
+            vec![
+                "wss://relay3.example.com".to_string(),
+                "wss://relay2.example.com".to_string(),
+                "wss://relay1.example.com".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detects_reconnects() {
+        let health = RelayHealth::new(&urls());
+        assert!(!health.set_connected("wss://relay1.example.com", true));
+        assert!(!health.set_connected("wss://relay1.example.com", false));
+        assert!(health.set_connected("wss://relay1.example.com", true));
+    }
+}
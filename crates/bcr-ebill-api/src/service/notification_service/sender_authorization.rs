@@ -0,0 +1,307 @@
+use super::bill_chain_sync::{get_attached_chain_bundle, get_participants_from_bundle};
+use super::{EventEnvelope, Result};
+use crate::persistence::bill::{BillChainStoreApi, BillStoreApi};
+use crate::persistence::contact::ContactStoreApi;
+use crate::util::crypto;
+use async_trait::async_trait;
+use bcr_ebill_core::notification::EventType;
+#[cfg(test)]
+use mockall::automock;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Who is allowed to send us an event of a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderPolicy {
+    /// The sender has to be one of our contacts
+    KnownContact,
+    /// The sender has to be one of our contacts, or a participant of the bill the event
+    /// refers to, according to the bill's chain
+    ContactOrBillParticipant,
+}
+
+impl SenderPolicy {
+    pub fn for_event_type(event_type: &EventType) -> Self {
+        match event_type {
+            // quotes are sent by mints, which are not part of the bill chain before they buy it
+            EventType::BillNewQuote | EventType::BillQuoteApproved => Self::KnownContact,
+            _ => Self::ContactOrBillParticipant,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SenderCheck {
+    Authorized,
+    /// The sender isn't allowed to send the event, with the reason why
+    Rejected(String),
+}
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait SenderAuthorizationApi: Send + Sync {
+    /// Checks the sender, given as hex encoded Nostr public key, against the sender policy
+    /// of the event's type
+    async fn check_sender(&self, sender: &str, event: &EventEnvelope) -> Result<SenderCheck>;
+}
+
+/// Checks senders against our contacts and the chains of our bills
+pub struct SenderAuthorization {
+    contact_store: Arc<dyn ContactStoreApi>,
+    bill_store: Arc<dyn BillStoreApi>,
+    bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+}
+
+impl SenderAuthorization {
+    pub fn new(
+        contact_store: Arc<dyn ContactStoreApi>,
+        bill_store: Arc<dyn BillStoreApi>,
+        bill_blockchain_store: Arc<dyn BillChainStoreApi>,
+    ) -> Self {
+        Self {
+            contact_store,
+            bill_store,
+            bill_blockchain_store,
+        }
+    }
+
+    /// Looks up the contacts with the node ids, that have the sender's npub
+    async fn is_contact(&self, sender: &str) -> Result<bool> {
+        for node_id in crypto::get_node_ids_from_nostr_hex_npub(sender) {
+            if self.contact_store.get(&node_id).await?.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Checks the chain of the bill, or, if we don't know the bill yet, the valid chain the
+    /// sender attached to the event
+    async fn is_bill_participant(
+        &self,
+        sender: &str,
+        bill_id: &str,
+        event: &EventEnvelope,
+    ) -> Result<bool> {
+        let participants = if self.bill_store.exists(bill_id).await {
+            let chain = self.bill_blockchain_store.get_chain(bill_id).await?;
+            let bill_keys = self.bill_store.get_keys(bill_id).await?;
+            chain.get_all_nodes_from_bill(&bill_keys)?
+        } else {
+            match get_attached_chain_bundle(event)
+                .and_then(|bundle| get_participants_from_bundle(bill_id, &bundle).ok())
+            {
+                Some(participants) => participants,
+                None => return Ok(false),
+            }
+        };
+        Ok(participants
+            .iter()
+            .any(|node_id| crypto::is_node_id_nostr_hex_npub(node_id, sender)))
+    }
+}
+
+#[async_trait]
+impl SenderAuthorizationApi for SenderAuthorization {
+    async fn check_sender(&self, sender: &str, event: &EventEnvelope) -> Result<SenderCheck> {
+        if self.is_contact(sender).await? {
+            return Ok(SenderCheck::Authorized);
+        }
+        let check = match SenderPolicy::for_event_type(&event.event_type) {
+            SenderPolicy::KnownContact => {
+                SenderCheck::Rejected("the sender is not a contact".to_owned())
+            }
+            SenderPolicy::ContactOrBillParticipant => {
+                match event.data.get("bill_id").and_then(Value::as_str) {
+                    Some(bill_id) if self.is_bill_participant(sender, bill_id, event).await? => {
+                        SenderCheck::Authorized
+                    }
+                    Some(bill_id) => SenderCheck::Rejected(format!(
+                        "the sender is neither a contact, nor a participant of bill {bill_id}"
+                    )),
+                    None => SenderCheck::Rejected(
+                        "the sender is not a contact and the event refers to no bill".to_owned(),
+                    ),
+                }
+            }
+        };
+        Ok(check)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::bill::bundle::BillChainBundle;
+    use crate::data::bill::BillKeys;
+    use crate::service::bill_service::test_utils::{get_baseline_bill, get_genesis_chain};
+    use crate::service::contact_service::tests::get_baseline_contact;
+    use crate::service::notification_service::bill_chain_sync::CHAIN_BUNDLE_FIELD;
+    use crate::tests::tests::{
+        MockBillChainStoreApiMock, MockBillStoreApiMock, MockContactStoreApiMock,
+        TEST_NODE_ID_SECP, TEST_NODE_ID_SECP_AS_NPUB_HEX, TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP,
+    };
+    use serde_json::json;
+
+    fn get_event(event_type: EventType) -> EventEnvelope {
+        EventEnvelope {
+            event_type,
+            version: "1.0".to_string(),
+            node_id: "node_id".to_string(),
+            data: json!({ "bill_id": "1234", "action_type": "CheckBill", "sum": 500 }),
+        }
+    }
+
+    fn get_contact_store(known: bool) -> MockContactStoreApiMock {
+        let mut contact_store = MockContactStoreApiMock::new();
+        contact_store.expect_get().returning(move |node_id| {
+            Ok((known && node_id == TEST_NODE_ID_SECP).then(get_baseline_contact))
+        });
+        contact_store
+    }
+
+    fn get_bill_stores(
+        exists: bool,
+        participant: bool,
+    ) -> (MockBillStoreApiMock, MockBillChainStoreApiMock) {
+        let mut bill_store = MockBillStoreApiMock::new();
+        bill_store.expect_exists().returning(move |_| exists);
+        bill_store.expect_get_keys().returning(|_| {
+            Ok(BillKeys {
+                private_key: TEST_PRIVATE_KEY_SECP.to_owned(),
+                public_key: TEST_PUB_KEY_SECP.to_owned(),
+            })
+        });
+        let mut chain_store = MockBillChainStoreApiMock::new();
+        chain_store.expect_get_chain().returning(move |_| {
+            let mut bill = get_baseline_bill("1234");
+            if participant {
+                bill.payee.node_id = TEST_NODE_ID_SECP.to_owned();
+            }
+            Ok(get_genesis_chain(Some(bill)))
+        });
+        (bill_store, chain_store)
+    }
+
+    fn get_authorization(
+        known_contact: bool,
+        bill_exists: bool,
+        participant: bool,
+    ) -> SenderAuthorization {
+        let (bill_store, chain_store) = get_bill_stores(bill_exists, participant);
+        SenderAuthorization::new(
+            Arc::new(get_contact_store(known_contact)),
+            Arc::new(bill_store),
+            Arc::new(chain_store),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_contacts_are_authorized() {
+        let authorization = get_authorization(true, false, false);
+        for event_type in [EventType::BillSigned, EventType::BillNewQuote] {
+            assert_eq!(
+                authorization
+                    .check_sender(TEST_NODE_ID_SECP_AS_NPUB_HEX, &get_event(event_type))
+                    .await
+                    .unwrap(),
+                SenderCheck::Authorized
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bill_participants_are_authorized() {
+        let authorization = get_authorization(false, true, true);
+        assert_eq!(
+            authorization
+                .check_sender(
+                    TEST_NODE_ID_SECP_AS_NPUB_HEX,
+                    &get_event(EventType::BillPaymentRequested)
+                )
+                .await
+                .unwrap(),
+            SenderCheck::Authorized
+        );
+    }
+
+    #[tokio::test]
+    async fn test_participants_of_attached_chain_are_authorized_for_unknown_bills() {
+        let authorization = get_authorization(false, false, false);
+        let mut bill = get_baseline_bill("1234");
+        bill.payee.node_id = TEST_NODE_ID_SECP.to_owned();
+        let bill_keys = BillKeys {
+            private_key: TEST_PRIVATE_KEY_SECP.to_owned(),
+            public_key: TEST_PUB_KEY_SECP.to_owned(),
+        };
+        let bundle = BillChainBundle::new(
+            &get_genesis_chain(Some(bill)),
+            &bill_keys,
+            None,
+            vec![],
+            1000,
+        )
+        .unwrap();
+        let mut event = get_event(EventType::BillPaymentRequested);
+        event.data[CHAIN_BUNDLE_FIELD] = serde_json::to_value(bundle).unwrap();
+        assert_eq!(
+            authorization
+                .check_sender(TEST_NODE_ID_SECP_AS_NPUB_HEX, &event)
+                .await
+                .unwrap(),
+            SenderCheck::Authorized
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bill_participants_need_to_be_contacts_for_quotes() {
+        let authorization = get_authorization(false, true, true);
+        assert!(matches!(
+            authorization
+                .check_sender(
+                    TEST_NODE_ID_SECP_AS_NPUB_HEX,
+                    &get_event(EventType::BillNewQuote)
+                )
+                .await
+                .unwrap(),
+            SenderCheck::Rejected(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_strangers_are_rejected() {
+        let not_participant = get_authorization(false, true, false);
+        assert!(matches!(
+            not_participant
+                .check_sender(
+                    TEST_NODE_ID_SECP_AS_NPUB_HEX,
+                    &get_event(EventType::BillSigned)
+                )
+                .await
+                .unwrap(),
+            SenderCheck::Rejected(_)
+        ));
+
+        let unknown_bill = get_authorization(false, false, true);
+        assert!(matches!(
+            unknown_bill
+                .check_sender(
+                    TEST_NODE_ID_SECP_AS_NPUB_HEX,
+                    &get_event(EventType::BillSigned)
+                )
+                .await
+                .unwrap(),
+            SenderCheck::Rejected(_)
+        ));
+
+        let mut no_bill = get_event(EventType::BillSigned);
+        no_bill.data = json!({ "foo": "bar" });
+        assert!(matches!(
+            unknown_bill
+                .check_sender(TEST_NODE_ID_SECP_AS_NPUB_HEX, &no_bill)
+                .await
+                .unwrap(),
+            SenderCheck::Rejected(_)
+        ));
+    }
+}
@@ -2,11 +2,14 @@ use crate::{
     data::{bill::BitcreditBill, contact::IdentityPublicData},
     persistence::DbContext,
     tests::tests::{
-        MockBackupStoreApiMock, MockBillChainStoreApiMock, MockBillStoreApiMock,
+        MockApiTokenStoreApiMock, MockBackupStoreApiMock, MockBillBatchStoreApiMock,
+        MockBillChainStoreApiMock, MockBillStoreApiMock, MockBillTemplateStoreApiMock,
         MockCompanyChainStoreApiMock, MockCompanyStoreApiMock, MockContactStoreApiMock,
         MockFileUploadStoreApiMock, MockIdentityChainStoreApiMock, MockIdentityStoreApiMock,
-        MockNostrEventOffsetStoreApiMock, MockNotificationStoreApiMock, empty_bitcredit_bill,
-        identity_public_data_only_node_id,
+        MockNostrEventOffsetStoreApiMock, MockNostrOutboxStoreApiMock,
+        MockNostrPendingEventStoreApiMock, MockNostrQuarantineStoreApiMock,
+        MockNotificationStoreApiMock, MockSearchIndexStoreApiMock, MockWebhookStoreApiMock,
+        empty_bitcredit_bill, identity_public_data_only_node_id,
     },
     util::BcrKeys,
 };
@@ -64,7 +67,7 @@ impl NotificationHandlerApi for TestEventHandler<TestEventPayload> {
         }
     }
 
-    async fn handle_event(&self, event: EventEnvelope, _: &str) -> Result<()> {
+    async fn handle_event(&self, event: EventEnvelope, _: &str, _: &str) -> Result<()> {
         *self.called.lock().await = true;
         let event: Event<TestEventPayload> = event.try_into()?;
         *self.received_event.lock().await = Some(event);
@@ -103,7 +106,10 @@ pub fn get_identity_public_data(
 ) -> IdentityPublicData {
     let mut identity = identity_public_data_only_node_id(node_id.to_owned());
     identity.email = Some(email.to_owned());
-    identity.nostr_relay = nostr_relay.map(|nostr_relay| nostr_relay.to_owned());
+    identity.nostr_relays = nostr_relay
+        .map(|nostr_relay| nostr_relay.to_owned())
+        .into_iter()
+        .collect();
     identity
 }
 
@@ -145,13 +151,21 @@ pub fn get_mock_db_context() -> DbContext {
         contact_store: Arc::new(MockContactStoreApiMock::new()),
         bill_store: Arc::new(MockBillStoreApiMock::new()),
         bill_blockchain_store: Arc::new(MockBillChainStoreApiMock::new()),
+        bill_template_store: Arc::new(MockBillTemplateStoreApiMock::new()),
+        bill_batch_store: Arc::new(MockBillBatchStoreApiMock::new()),
         identity_store: Arc::new(MockIdentityStoreApiMock::new()),
         identity_chain_store: Arc::new(MockIdentityChainStoreApiMock::new()),
         company_store: Arc::new(MockCompanyStoreApiMock::new()),
         company_chain_store: Arc::new(MockCompanyChainStoreApiMock::new()),
         file_upload_store: Arc::new(MockFileUploadStoreApiMock::new()),
         nostr_event_offset_store: Arc::new(MockNostrEventOffsetStoreApiMock::new()),
+        nostr_quarantine_store: Arc::new(MockNostrQuarantineStoreApiMock::new()),
+        nostr_pending_event_store: Arc::new(MockNostrPendingEventStoreApiMock::new()),
+        nostr_outbox_store: Arc::new(MockNostrOutboxStoreApiMock::new()),
         notification_store: Arc::new(MockNotificationStoreApiMock::new()),
         backup_store: Arc::new(MockBackupStoreApiMock::new()),
+        search_index_store: Arc::new(MockSearchIndexStoreApiMock::new()),
+        api_token_store: Arc::new(MockApiTokenStoreApiMock::new()),
+        webhook_store: Arc::new(MockWebhookStoreApiMock::new()),
     }
 }
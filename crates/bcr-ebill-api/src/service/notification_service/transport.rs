@@ -1,5 +1,7 @@
 use super::{EventEnvelope, Result};
 use crate::data::contact::IdentityPublicData;
+use crate::data::profile::PublicProfile;
+use crate::util::BcrKeys;
 use async_trait::async_trait;
 use log::info;
 #[cfg(test)]
@@ -11,6 +13,27 @@ pub trait NotificationJsonTransportApi: Send + Sync {
     async fn send(&self, recipient: &IdentityPublicData, event: EventEnvelope) -> Result<()>;
 }
 
+/// Publishes json messages to a Nostr node id
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait NostrPublisherApi: Send + Sync {
+    /// Publishes the message encrypted for the recipient to the given relays, or ours if there
+    /// are none. Fails, if no relay confirmed it.
+    async fn publish(&self, recipient: &str, relays: &[String], message: &str) -> Result<()>;
+}
+
+/// Publishes and fetches the signed public profiles of identities and companies
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait NostrProfileApi: Send + Sync {
+    /// Publishes the profile to our relays, signed with the given keys
+    async fn publish_profile(&self, profile: &PublicProfile, keys: &BcrKeys) -> Result<()>;
+
+    /// Returns the latest profile, which was published and signed by the given Nostr public
+    /// key (as hex), if there is one
+    async fn fetch_profile(&self, npub: &str) -> Result<Option<PublicProfile>>;
+}
+
 /// A dummy transport that logs all events that are sent as json.
 pub struct LoggingNotificationJsonTransport;
 
@@ -0,0 +1,60 @@
+use super::Result;
+use std::sync::Arc;
+
+use super::{EventEnvelope, EventType, handler::NotificationHandlerApi};
+use crate::service::webhook_service::WebhookServiceApi;
+use async_trait::async_trait;
+
+/// Forwards all received events to the subscribed webhooks
+#[derive(Clone)]
+pub struct WebhookEventHandler {
+    webhook_service: Arc<dyn WebhookServiceApi>,
+}
+
+impl WebhookEventHandler {
+    pub fn new(webhook_service: Arc<dyn WebhookServiceApi>) -> Self {
+        Self { webhook_service }
+    }
+}
+
+#[async_trait]
+impl NotificationHandlerApi for WebhookEventHandler {
+    fn handles_event(&self, _event_type: &EventType) -> bool {
+        true
+    }
+
+    async fn handle_event(
+        &self,
+        event: EventEnvelope,
+        _node_id: &str,
+        _sender: &str,
+    ) -> Result<()> {
+        self.webhook_service.dispatch(&event).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_utils::create_test_event;
+    use super::*;
+    use crate::service::webhook_service::MockWebhookServiceApi;
+
+    #[tokio::test]
+    async fn test_dispatches_received_events() {
+        let mut webhook_service = MockWebhookServiceApi::new();
+        webhook_service
+            .expect_dispatch()
+            .withf(|e| e.event_type == EventType::BillPaid)
+            .times(1)
+            .returning(|_| ());
+        let handler = WebhookEventHandler::new(Arc::new(webhook_service));
+
+        let envelope: EventEnvelope = create_test_event(&EventType::BillPaid).try_into().unwrap();
+        assert!(handler.handles_event(&EventType::BillPaid));
+        handler
+            .handle_event(envelope, "node_id", "sender")
+            .await
+            .expect("event was not handled");
+    }
+}
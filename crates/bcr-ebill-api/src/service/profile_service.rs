@@ -0,0 +1,330 @@
+use super::notification_service::NostrProfileApi;
+use super::{Error, Result};
+use crate::data::{
+    OptionalPostalAddress,
+    company::{Company, CompanyKeys},
+    contact::ContactType,
+    identity::Identity,
+    profile::{PROFILE_VERSION, PublicProfile},
+};
+use crate::persistence::{company::CompanyStoreApi, identity::IdentityStoreApi};
+use crate::util::{self, BcrKeys};
+use async_trait::async_trait;
+use log::{error, info};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+use mockall::automock;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ProfileServiceApi: Send + Sync {
+    /// Fetches the latest public profile of the given node id or npub from the relays and
+    /// verifies, that it was signed by the keys of the node id it describes
+    async fn resolve(&self, node_id_or_npub: &str) -> Result<PublicProfile>;
+
+    /// Publishes the profiles of our identity and our companies, which changed since they
+    /// were published last
+    async fn publish_profiles(&self, timestamp: u64) -> Result<()>;
+}
+
+/// The profile service publishes the public profiles of our identity and companies via Nostr
+/// and resolves the profiles of others
+#[derive(Clone)]
+pub struct ProfileService {
+    nostr: Arc<dyn NostrProfileApi>,
+    identity_store: Arc<dyn IdentityStoreApi>,
+    company_store: Arc<dyn CompanyStoreApi>,
+    relays: Vec<String>,
+    /// The profiles published since the start, by node id
+    published: Arc<Mutex<HashMap<String, PublicProfile>>>,
+}
+
+impl ProfileService {
+    pub fn new(
+        nostr: Arc<dyn NostrProfileApi>,
+        identity_store: Arc<dyn IdentityStoreApi>,
+        company_store: Arc<dyn CompanyStoreApi>,
+        relays: Vec<String>,
+    ) -> Self {
+        Self {
+            nostr,
+            identity_store,
+            company_store,
+            relays,
+            published: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn identity_profile(&self, identity: Identity, timestamp: u64) -> PublicProfile {
+        PublicProfile {
+            version: PROFILE_VERSION,
+            t: ContactType::Person,
+            node_id: identity.node_id,
+            name: identity.name,
+            postal_address: identity.postal_address,
+            nostr_relays: self.relays.clone(),
+            proof_of_registration_hash: None,
+            published_at: timestamp,
+        }
+    }
+
+    fn company_profile(&self, company: Company, timestamp: u64) -> PublicProfile {
+        PublicProfile {
+            version: PROFILE_VERSION,
+            t: ContactType::Company,
+            node_id: company.id,
+            name: company.name,
+            postal_address: OptionalPostalAddress {
+                country: Some(company.postal_address.country),
+                city: Some(company.postal_address.city),
+                zip: company.postal_address.zip,
+                address: Some(company.postal_address.address),
+            },
+            nostr_relays: self.relays.clone(),
+            proof_of_registration_hash: company.proof_of_registration_file.map(|f| f.hash),
+            published_at: timestamp,
+        }
+    }
+
+    /// Returns our profiles together with the keys to sign them with
+    async fn own_profiles(&self, timestamp: u64) -> Result<Vec<(PublicProfile, BcrKeys)>> {
+        let mut profiles = Vec::new();
+        if self.identity_store.exists().await {
+            let identity = self.identity_store.get().await?;
+            let keys = self.identity_store.get_key_pair().await?;
+            profiles.push((self.identity_profile(identity, timestamp), keys));
+        }
+        for (company, CompanyKeys { private_key, .. }) in
+            self.company_store.get_all().await?.into_values()
+        {
+            let keys = BcrKeys::from_private_key(&private_key)?;
+            profiles.push((self.company_profile(company, timestamp), keys));
+        }
+        Ok(profiles)
+    }
+
+    fn is_published(&self, profile: &PublicProfile) -> bool {
+        self.published
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&profile.node_id)
+            .is_some_and(|published| published.has_same_content(profile))
+    }
+}
+
+#[async_trait]
+impl ProfileServiceApi for ProfileService {
+    async fn resolve(&self, node_id_or_npub: &str) -> Result<PublicProfile> {
+        let npub = util::crypto::get_nostr_npub_as_hex_from_node_id_or_npub(node_id_or_npub)
+            .map_err(|_| {
+                Error::Validation(format!("Not a valid node id or npub: {node_id_or_npub}"))
+            })?;
+        let profile = self
+            .nostr
+            .fetch_profile(&npub)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if profile.version != PROFILE_VERSION {
+            return Err(Error::Validation(format!(
+                "Unsupported profile version {}",
+                profile.version
+            )));
+        }
+        // the Nostr key only proves the x coordinate, so the node id has to match it
+        if !util::crypto::is_node_id_nostr_hex_npub(&profile.node_id, &npub) {
+            return Err(Error::Validation(format!(
+                "Profile of {} was not signed by its node id",
+                profile.node_id
+            )));
+        }
+        if util::crypto::validate_pub_key(node_id_or_npub).is_ok()
+            && profile.node_id != node_id_or_npub
+        {
+            return Err(Error::Validation(format!(
+                "Profile is for node id {}, not {node_id_or_npub}",
+                profile.node_id
+            )));
+        }
+        Ok(profile)
+    }
+
+    async fn publish_profiles(&self, timestamp: u64) -> Result<()> {
+        for (profile, keys) in self.own_profiles(timestamp).await? {
+            if self.is_published(&profile) {
+                continue;
+            }
+            match self.nostr.publish_profile(&profile, &keys).await {
+                Ok(()) => {
+                    info!("Published profile of {}", profile.node_id);
+                    self.published
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(profile.node_id.clone(), profile);
+                }
+                Err(e) => error!("Could not publish profile of {}: {e}", profile.node_id),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::data::PostalAddress;
+    use crate::service::notification_service::MockNostrProfileApi;
+    use crate::tests::tests::{
+        MockCompanyStoreApiMock, MockIdentityStoreApiMock, TEST_NODE_ID_SECP,
+        TEST_NODE_ID_SECP_AS_NPUB_HEX, empty_identity,
+    };
+    use mockall::predicate::eq;
+
+    pub fn get_profile(node_id: &str) -> PublicProfile {
+        PublicProfile {
+            version: PROFILE_VERSION,
+            t: ContactType::Person,
+            node_id: node_id.to_owned(),
+            name: "Minka".to_owned(),
+            postal_address: OptionalPostalAddress {
+                country: Some("AT".to_owned()),
+                city: Some("Vienna".to_owned()),
+                zip: Some("1010".to_owned()),
+                address: Some("Kärntner Straße 1".to_owned()),
+            },
+            nostr_relays: vec!["ws://localhost:8080".to_owned()],
+            proof_of_registration_hash: None,
+            published_at: 1000,
+        }
+    }
+
+    fn get_service(
+        nostr: MockNostrProfileApi,
+        identity_store: MockIdentityStoreApiMock,
+        company_store: MockCompanyStoreApiMock,
+    ) -> ProfileService {
+        ProfileService::new(
+            Arc::new(nostr),
+            Arc::new(identity_store),
+            Arc::new(company_store),
+            vec!["ws://localhost:8080".to_owned()],
+        )
+    }
+
+    fn get_resolving_service(profile: PublicProfile) -> ProfileService {
+        let mut nostr = MockNostrProfileApi::new();
+        nostr
+            .expect_fetch_profile()
+            .with(eq(TEST_NODE_ID_SECP_AS_NPUB_HEX))
+            .returning(move |_| Ok(Some(profile.clone())));
+        get_service(
+            nostr,
+            MockIdentityStoreApiMock::new(),
+            MockCompanyStoreApiMock::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn resolve_returns_verified_profile() {
+        let service = get_resolving_service(get_profile(TEST_NODE_ID_SECP));
+        let profile = service.resolve(TEST_NODE_ID_SECP).await.unwrap();
+        assert_eq!(profile, get_profile(TEST_NODE_ID_SECP));
+    }
+
+    #[tokio::test]
+    async fn resolve_rejects_profile_of_other_node_id() {
+        let keys = BcrKeys::new();
+        let service = get_resolving_service(get_profile(&keys.get_public_key()));
+        assert!(matches!(
+            service.resolve(TEST_NODE_ID_SECP).await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn resolve_rejects_unsupported_version() {
+        let mut profile = get_profile(TEST_NODE_ID_SECP);
+        profile.version = PROFILE_VERSION + 1;
+        let service = get_resolving_service(profile);
+        assert!(matches!(
+            service.resolve(TEST_NODE_ID_SECP).await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn resolve_unknown_profile_is_not_found() {
+        let mut nostr = MockNostrProfileApi::new();
+        nostr.expect_fetch_profile().returning(|_| Ok(None));
+        let service = get_service(
+            nostr,
+            MockIdentityStoreApiMock::new(),
+            MockCompanyStoreApiMock::new(),
+        );
+        assert!(matches!(
+            service.resolve(TEST_NODE_ID_SECP).await,
+            Err(Error::NotFound)
+        ));
+        assert!(matches!(
+            service.resolve("invalid").await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn publish_profiles_publishes_changed_profiles_only() {
+        let identity_keys = BcrKeys::new();
+        let company_keys = BcrKeys::new();
+        let mut identity_store = MockIdentityStoreApiMock::new();
+        identity_store.expect_exists().returning(|| true);
+        let node_id = identity_keys.get_public_key();
+        identity_store.expect_get().returning(move || {
+            let mut identity = empty_identity();
+            identity.node_id = node_id.clone();
+            identity.name = "Minka".to_owned();
+            Ok(identity)
+        });
+        identity_store
+            .expect_get_key_pair()
+            .returning(move || Ok(identity_keys.clone()));
+        let mut company_store = MockCompanyStoreApiMock::new();
+        let company_id = company_keys.get_public_key();
+        let private_key = company_keys.get_private_key_string();
+        company_store.expect_get_all().returning(move || {
+            let company = Company {
+                id: company_id.clone(),
+                name: "Company".to_owned(),
+                country_of_registration: None,
+                city_of_registration: None,
+                postal_address: PostalAddress {
+                    country: "AT".to_owned(),
+                    city: "Vienna".to_owned(),
+                    zip: None,
+                    address: "Street 1".to_owned(),
+                },
+                email: "company@example.com".to_owned(),
+                registration_number: None,
+                registration_date: None,
+                proof_of_registration_file: None,
+                logo_file: None,
+                signatories: vec![],
+            };
+            let keys = CompanyKeys {
+                private_key: private_key.clone(),
+                public_key: company_id.clone(),
+            };
+            Ok(HashMap::from([(company_id.clone(), (company, keys))]))
+        });
+        let mut nostr = MockNostrProfileApi::new();
+        // both profiles are published on the first run only, since they don't change
+        nostr
+            .expect_publish_profile()
+            .returning(|_, _| Ok(()))
+            .times(2);
+        let service = get_service(nostr, identity_store, company_store);
+
+        service.publish_profiles(1000).await.unwrap();
+        service.publish_profiles(2000).await.unwrap();
+    }
+}
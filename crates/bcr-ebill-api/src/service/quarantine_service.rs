@@ -0,0 +1,206 @@
+use super::notification_service::{self, EventEnvelope, NostrConsumer};
+use super::{Error, Result};
+use crate::persistence::nostr::{NostrQuarantineStoreApi, QuarantinedEvent};
+use async_trait::async_trait;
+use log::info;
+use std::sync::Arc;
+
+#[cfg(test)]
+use mockall::automock;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait QuarantineServiceApi: Send + Sync {
+    /// Returns all quarantined Nostr events, newest first
+    async fn list_events(&self) -> Result<Vec<QuarantinedEvent>>;
+
+    /// Handles the quarantined event with the given id, as if its sender was authorized,
+    /// and removes it from the quarantine
+    async fn release_event(&self, id: &str) -> Result<()>;
+
+    /// Removes the quarantined event with the given id without handling it
+    async fn discard_event(&self, id: &str) -> Result<()>;
+}
+
+/// The quarantine service lets users review Nostr events from senders, who weren't
+/// authorized to send them
+#[derive(Clone)]
+pub struct QuarantineService {
+    store: Arc<dyn NostrQuarantineStoreApi>,
+    nostr_consumer: NostrConsumer,
+}
+
+impl QuarantineService {
+    pub fn new(store: Arc<dyn NostrQuarantineStoreApi>, nostr_consumer: NostrConsumer) -> Self {
+        Self {
+            store,
+            nostr_consumer,
+        }
+    }
+
+    async fn get_event(&self, id: &str) -> Result<QuarantinedEvent> {
+        self.store.get(id).await?.ok_or(Error::NotFound)
+    }
+}
+
+#[async_trait]
+impl QuarantineServiceApi for QuarantineService {
+    async fn list_events(&self) -> Result<Vec<QuarantinedEvent>> {
+        Ok(self.store.list().await?)
+    }
+
+    async fn release_event(&self, id: &str) -> Result<()> {
+        let event = self.get_event(id).await?;
+        let envelope: EventEnvelope =
+            serde_json::from_str(&event.payload).map_err(notification_service::Error::from)?;
+        self.nostr_consumer
+            .handle_released_event(envelope, &event.sender)
+            .await?;
+        self.store.remove(id).await?;
+        info!("Released quarantined event {id} from {}", event.sender);
+        Ok(())
+    }
+
+    async fn discard_event(&self, id: &str) -> Result<()> {
+        self.get_event(id).await?;
+        self.store.remove(id).await?;
+        info!("Discarded quarantined event {id}");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::notification::EventType;
+    use crate::service::notification_service::{
+        SenderAuthorizationApi, test_utils::get_mock_nostr_client,
+    };
+    use crate::tests::tests::{MockNostrEventOffsetStoreApiMock, MockNostrQuarantineStoreApiMock};
+    use mockall::predicate::eq;
+    use serde_json::json;
+
+    struct AuthorizeNobody;
+
+    #[async_trait]
+    impl SenderAuthorizationApi for AuthorizeNobody {
+        async fn check_sender(
+            &self,
+            _sender: &str,
+            _event: &EventEnvelope,
+        ) -> notification_service::Result<notification_service::SenderCheck> {
+            Ok(notification_service::SenderCheck::Rejected(
+                "nobody".to_owned(),
+            ))
+        }
+    }
+
+    fn get_quarantined_event(payload: &str) -> QuarantinedEvent {
+        QuarantinedEvent {
+            id: "event_id".to_owned(),
+            sender: "sender".to_owned(),
+            event_type: EventType::BillSigned,
+            payload: payload.to_owned(),
+            reason: "the sender is not a contact".to_owned(),
+            received_at: 1731593928,
+        }
+    }
+
+    async fn get_service(store: MockNostrQuarantineStoreApiMock) -> QuarantineService {
+        let consumer = NostrConsumer::new(
+            get_mock_nostr_client().await,
+            Arc::new(AuthorizeNobody),
+            vec![],
+            Arc::new(MockNostrEventOffsetStoreApiMock::new()),
+            Arc::new(MockNostrQuarantineStoreApiMock::new()),
+        );
+        QuarantineService::new(Arc::new(store), consumer)
+    }
+
+    #[tokio::test]
+    async fn test_list_events() {
+        let mut store = MockNostrQuarantineStoreApiMock::new();
+        store
+            .expect_list()
+            .returning(|| Ok(vec![get_quarantined_event("{}")]));
+        let events = get_service(store).await.list_events().await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "event_id");
+    }
+
+    #[tokio::test]
+    async fn test_release_event() {
+        let payload = json!({
+            "event_type": "BillSigned",
+            "version": "1.0",
+            "node_id": "node_id",
+            "data": { "bill_id": "1234" }
+        })
+        .to_string();
+        let mut store = MockNostrQuarantineStoreApiMock::new();
+        store
+            .expect_get()
+            .with(eq("event_id"))
+            .returning(move |_| Ok(Some(get_quarantined_event(&payload))));
+        store
+            .expect_remove()
+            .with(eq("event_id"))
+            .returning(|_| Ok(()))
+            .once();
+        get_service(store)
+            .await
+            .release_event("event_id")
+            .await
+            .expect("event was not released");
+    }
+
+    #[tokio::test]
+    async fn test_release_invalid_event_keeps_it() {
+        let mut store = MockNostrQuarantineStoreApiMock::new();
+        store
+            .expect_get()
+            .returning(|_| Ok(Some(get_quarantined_event("not json"))));
+        store.expect_remove().never();
+        assert!(
+            get_service(store)
+                .await
+                .release_event("event_id")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discard_event() {
+        let mut store = MockNostrQuarantineStoreApiMock::new();
+        store
+            .expect_get()
+            .returning(|_| Ok(Some(get_quarantined_event("{}"))));
+        store
+            .expect_remove()
+            .with(eq("event_id"))
+            .returning(|_| Ok(()))
+            .once();
+        get_service(store)
+            .await
+            .discard_event("event_id")
+            .await
+            .expect("event was not discarded");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_event_is_not_found() {
+        let mut store = MockNostrQuarantineStoreApiMock::new();
+        store.expect_get().returning(|_| Ok(None));
+        store.expect_remove().never();
+        let service = get_service(store).await;
+        assert!(matches!(
+            service.release_event("unknown").await,
+            Err(Error::NotFound)
+        ));
+        assert!(matches!(
+            service.discard_event("unknown").await,
+            Err(Error::NotFound)
+        ));
+    }
+}
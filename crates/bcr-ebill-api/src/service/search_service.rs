@@ -4,8 +4,12 @@ use super::{
     contact_service::ContactServiceApi,
 };
 use crate::data::GeneralSearchResult;
-use crate::data::{GeneralSearchFilterItemType, bill::BillsFilterRole};
+use crate::data::{GeneralSearchFilterItemType, bill::BillSearchQuery};
+use crate::persistence::search_index::SearchIndexStoreApi;
 use async_trait::async_trait;
+use bcr_ebill_core::search::{SEARCH_INDEX_VERSION, SearchDocument, rank, tokenize};
+use log::{error, info};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[async_trait]
@@ -26,6 +30,7 @@ pub struct SearchService {
     bill_service: Arc<dyn BillServiceApi>,
     contact_service: Arc<dyn ContactServiceApi>,
     company_service: Arc<dyn CompanyServiceApi>,
+    search_index_store: Arc<dyn SearchIndexStoreApi>,
 }
 
 impl SearchService {
@@ -33,56 +38,383 @@ impl SearchService {
         bill_service: Arc<dyn BillServiceApi>,
         contact_service: Arc<dyn ContactServiceApi>,
         company_service: Arc<dyn CompanyServiceApi>,
+        search_index_store: Arc<dyn SearchIndexStoreApi>,
     ) -> Self {
         Self {
             bill_service,
             contact_service,
             company_service,
+            search_index_store,
         }
     }
-}
 
-#[async_trait]
-impl SearchServiceApi for SearchService {
-    async fn search(
+    /// Builds the search index from scratch, if it was never built, built with a different
+    /// version, or marked as outdated
+    async fn build_index_if_outdated(&self) -> Result<()> {
+        if self.search_index_store.get_index_version().await? == SEARCH_INDEX_VERSION {
+            return Ok(());
+        }
+        info!("Building the search index");
+        self.search_index_store.clear().await?;
+        for contact in self.contact_service.get_contacts().await? {
+            self.search_index_store
+                .index_document(&SearchDocument::for_contact(&contact))
+                .await?;
+        }
+        for company in self.company_service.get_list_of_companies().await? {
+            self.search_index_store
+                .index_document(&SearchDocument::for_company(&company))
+                .await?;
+        }
+        self.bill_service.rebuild_search_index().await?;
+        self.search_index_store
+            .set_index_version(SEARCH_INDEX_VERSION)
+            .await?;
+        Ok(())
+    }
+
+    /// Searches without the index, for search terms without any words the index could match,
+    /// like an empty term. Every item of the given types, which contains the term, is returned
+    async fn search_without_index(
         &self,
         search_term: &str,
-        currency: &str,
         item_types: &[GeneralSearchFilterItemType],
         current_identity_node_id: &str,
     ) -> Result<GeneralSearchResult> {
         let search_term_lc = search_term.to_lowercase();
         let bills = if item_types.contains(&GeneralSearchFilterItemType::Bill) {
+            let query = BillSearchQuery {
+                search_term: Some(search_term_lc.clone()),
+                ..Default::default()
+            };
             self.bill_service
-                .search_bills(
-                    currency,
-                    &Some(search_term_lc.clone()),
-                    None,
-                    None,
-                    &BillsFilterRole::All,
-                    current_identity_node_id,
-                )
+                .search_bills(&query, current_identity_node_id)
                 .await?
+                .bills
         } else {
             vec![]
         };
-
         let contacts = if item_types.contains(&GeneralSearchFilterItemType::Contact) {
             self.contact_service.search(&search_term_lc).await?
         } else {
             vec![]
         };
-
         let companies = if item_types.contains(&GeneralSearchFilterItemType::Company) {
             self.company_service.search(&search_term_lc).await?
         } else {
             vec![]
         };
-
         Ok(GeneralSearchResult {
             bills,
             contacts,
             companies,
+            hits: vec![],
         })
     }
 }
+
+#[async_trait]
+impl SearchServiceApi for SearchService {
+    async fn search(
+        &self,
+        search_term: &str,
+        _currency: &str,
+        item_types: &[GeneralSearchFilterItemType],
+        current_identity_node_id: &str,
+    ) -> Result<GeneralSearchResult> {
+        let tokens = tokenize(search_term);
+        if tokens.is_empty() {
+            return self
+                .search_without_index(search_term, item_types, current_identity_node_id)
+                .await;
+        }
+        self.build_index_if_outdated().await?;
+        let postings = self.search_index_store.search(&tokens, item_types).await?;
+        let mut hits = rank(&tokens, &postings);
+        let has_hits_of = |item_type: GeneralSearchFilterItemType| {
+            item_types.contains(&item_type) && hits.iter().any(|h| h.item_type == item_type)
+        };
+
+        // the index contains all bills, contacts and companies, so the hits are narrowed down to
+        // the ones visible to the current identity
+        let mut bills = if has_hits_of(GeneralSearchFilterItemType::Bill) {
+            self.bill_service
+                .get_light_bills(current_identity_node_id)
+                .await?
+                .into_iter()
+                .map(|b| (b.id.clone(), b))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let mut contacts = if has_hits_of(GeneralSearchFilterItemType::Contact) {
+            self.contact_service
+                .get_contacts()
+                .await?
+                .into_iter()
+                .map(|c| (c.node_id.clone(), c))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let mut companies = if has_hits_of(GeneralSearchFilterItemType::Company) {
+            self.company_service
+                .get_list_of_companies()
+                .await?
+                .into_iter()
+                .map(|c| (c.id.clone(), c))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut result = GeneralSearchResult {
+            bills: vec![],
+            contacts: vec![],
+            companies: vec![],
+            hits: vec![],
+        };
+        hits.retain(|hit| match hit.item_type {
+            GeneralSearchFilterItemType::Bill => bills
+                .remove(&hit.item_id)
+                .map(|b| result.bills.push(b))
+                .is_some(),
+            GeneralSearchFilterItemType::Contact => contacts
+                .remove(&hit.item_id)
+                .map(|c| result.contacts.push(c))
+                .is_some(),
+            GeneralSearchFilterItemType::Company => companies
+                .remove(&hit.item_id)
+                .map(|c| result.companies.push(c))
+                .is_some(),
+        });
+        result.hits = hits;
+        Ok(result)
+    }
+}
+
+/// Replaces the given document in the search index. Since the change itself is already
+/// persisted at this point, failures are only logged and the index is marked as outdated, so
+/// it's rebuilt with the next search
+pub(crate) async fn update_search_index(
+    store: &dyn SearchIndexStoreApi,
+    document: &SearchDocument,
+) {
+    if let Err(e) = store.index_document(document).await {
+        error!(
+            "Could not index {:?} {}: {e}",
+            document.item_type, document.item_id
+        );
+        mark_search_index_outdated(store).await;
+    }
+}
+
+/// Removes the given item from the search index, marking the index as outdated on failure
+pub(crate) async fn remove_from_search_index(
+    store: &dyn SearchIndexStoreApi,
+    item_type: GeneralSearchFilterItemType,
+    item_id: &str,
+) {
+    if let Err(e) = store.remove_document(item_type, item_id).await {
+        error!("Could not remove {item_type:?} {item_id} from the search index: {e}");
+        mark_search_index_outdated(store).await;
+    }
+}
+
+async fn mark_search_index_outdated(store: &dyn SearchIndexStoreApi) {
+    if let Err(e) = store.set_index_version(0).await {
+        error!("Could not mark the search index as outdated: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::bill::BillSearchResult;
+    use crate::service::{
+        bill_service::MockBillServiceApi, company_service::MockCompanyServiceApi,
+        contact_service::MockContactServiceApi, contact_service::tests::get_baseline_contact,
+    };
+    use crate::tests::tests::MockSearchIndexStoreApiMock;
+    use bcr_ebill_core::search::{SearchField, SearchPosting};
+    use mockall::predicate::eq;
+
+    fn get_service(
+        bill_service: MockBillServiceApi,
+        contact_service: MockContactServiceApi,
+        company_service: MockCompanyServiceApi,
+        search_index_store: MockSearchIndexStoreApiMock,
+    ) -> SearchService {
+        SearchService::new(
+            Arc::new(bill_service),
+            Arc::new(contact_service),
+            Arc::new(company_service),
+            Arc::new(search_index_store),
+        )
+    }
+
+    fn posting(item_type: GeneralSearchFilterItemType, item_id: &str, term: &str) -> SearchPosting {
+        SearchPosting {
+            item_type,
+            item_id: item_id.to_owned(),
+            field: SearchField::Name,
+            term: term.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn search_only_returns_visible_items_by_rank() {
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_get_light_bills()
+            .returning(|_| Ok(vec![]));
+        let mut contact_service = MockContactServiceApi::new();
+        contact_service.expect_get_contacts().returning(|| {
+            let mut first = get_baseline_contact();
+            first.node_id = "first".to_owned();
+            let mut second = get_baseline_contact();
+            second.node_id = "second".to_owned();
+            Ok(vec![first, second])
+        });
+        let mut search_index_store = MockSearchIndexStoreApiMock::new();
+        search_index_store
+            .expect_get_index_version()
+            .returning(|| Ok(SEARCH_INDEX_VERSION));
+        search_index_store
+            .expect_search()
+            .withf(|tokens, _| tokens.to_vec() == vec!["muller".to_owned()])
+            .returning(|_, _| {
+                Ok(vec![
+                    posting(GeneralSearchFilterItemType::Contact, "first", "mullerova"),
+                    posting(GeneralSearchFilterItemType::Contact, "second", "muller"),
+                    // not visible to the current identity
+                    posting(GeneralSearchFilterItemType::Bill, "some bill", "muller"),
+                ])
+            });
+
+        let service = get_service(
+            bill_service,
+            contact_service,
+            MockCompanyServiceApi::new(),
+            search_index_store,
+        );
+        let result = service
+            .search(
+                "Müller",
+                "sat",
+                &[
+                    GeneralSearchFilterItemType::Bill,
+                    GeneralSearchFilterItemType::Contact,
+                ],
+                "node_id",
+            )
+            .await
+            .unwrap();
+        assert!(result.bills.is_empty());
+        assert_eq!(result.contacts.len(), 2);
+        // the exact match is ranked before the prefix match
+        assert_eq!(result.contacts[0].node_id, "second");
+        assert_eq!(result.hits.len(), 2);
+        assert!(result.hits[0].score > result.hits[1].score);
+    }
+
+    #[tokio::test]
+    async fn search_rebuilds_outdated_index() {
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_rebuild_search_index()
+            .returning(|| Ok(()))
+            .once();
+        let mut contact_service = MockContactServiceApi::new();
+        contact_service
+            .expect_get_contacts()
+            .returning(|| Ok(vec![get_baseline_contact()]));
+        let mut company_service = MockCompanyServiceApi::new();
+        company_service
+            .expect_get_list_of_companies()
+            .returning(|| Ok(vec![]));
+        let mut search_index_store = MockSearchIndexStoreApiMock::new();
+        search_index_store
+            .expect_get_index_version()
+            .returning(|| Ok(0));
+        search_index_store
+            .expect_clear()
+            .returning(|| Ok(()))
+            .once();
+        search_index_store
+            .expect_index_document()
+            .withf(|doc| doc.item_type == GeneralSearchFilterItemType::Contact)
+            .returning(|_| Ok(()))
+            .once();
+        search_index_store
+            .expect_set_index_version()
+            .with(eq(SEARCH_INDEX_VERSION))
+            .returning(|_| Ok(()))
+            .once();
+        search_index_store
+            .expect_search()
+            .returning(|_, _| Ok(vec![]));
+
+        let service = get_service(
+            bill_service,
+            contact_service,
+            company_service,
+            search_index_store,
+        );
+        let result = service
+            .search(
+                "nothing",
+                "sat",
+                &[GeneralSearchFilterItemType::Contact],
+                "node_id",
+            )
+            .await
+            .unwrap();
+        assert!(result.contacts.is_empty());
+        assert!(result.hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_without_words_returns_all_items_without_index() {
+        let mut bill_service = MockBillServiceApi::new();
+        bill_service
+            .expect_search_bills()
+            .withf(|query, _| query.search_term.as_deref() == Some(""))
+            .returning(|_, _| {
+                Ok(BillSearchResult {
+                    bills: vec![],
+                    next_cursor: None,
+                })
+            })
+            .once();
+        let mut contact_service = MockContactServiceApi::new();
+        contact_service
+            .expect_search()
+            .withf(|term| term.is_empty())
+            .returning(|_| Ok(vec![get_baseline_contact()]))
+            .once();
+        // neither the index is used, nor companies searched, since they weren't requested
+        let service = get_service(
+            bill_service,
+            contact_service,
+            MockCompanyServiceApi::new(),
+            MockSearchIndexStoreApiMock::new(),
+        );
+        let result = service
+            .search(
+                "",
+                "sat",
+                &[
+                    GeneralSearchFilterItemType::Bill,
+                    GeneralSearchFilterItemType::Contact,
+                ],
+                "node_id",
+            )
+            .await
+            .unwrap();
+        assert!(result.bills.is_empty());
+        assert_eq!(result.contacts.len(), 1);
+        assert!(result.companies.is_empty());
+        assert!(result.hits.is_empty());
+    }
+}
@@ -0,0 +1,551 @@
+use super::notification_service::EventEnvelope;
+use super::{Error, Result};
+use crate::data::notification::EventType;
+use crate::data::webhook::{Webhook, WebhookDelivery, WebhookDeliveryStatus};
+use crate::persistence::webhook::WebhookStoreApi;
+use crate::util;
+use crate::util::retry::{self, retry_delay};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use log::{error, info, warn};
+use reqwest::header::CONTENT_TYPE;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+use mockall::automock;
+
+/// The HMAC-SHA256 signature of `<timestamp>.<body>` with the webhook's secret, as
+/// `sha256=<hex>`
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Bcr-Signature";
+/// The timestamp the delivery attempt was signed at, receivers should reject old ones
+pub const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Bcr-Timestamp";
+pub const WEBHOOK_EVENT_HEADER: &str = "X-Bcr-Event";
+/// The id of the delivery, which stays the same for retries, so receivers can deduplicate
+pub const WEBHOOK_DELIVERY_HEADER: &str = "X-Bcr-Delivery";
+
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+/// How many due deliveries are retried per run
+const RETRY_BATCH_SIZE: u64 = 100;
+/// How many deliveries of a webhook are returned for its delivery log
+const DELIVERY_LOG_SIZE: u64 = 100;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait WebhookServiceApi: Send + Sync {
+    /// Subscribes the given URL to the given event types
+    async fn create_webhook(
+        &self,
+        url: &str,
+        secret: &str,
+        event_types: Vec<EventType>,
+        timestamp: u64,
+    ) -> Result<Webhook>;
+
+    /// Returns all webhooks
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>>;
+
+    /// Removes the webhook with the given id together with its delivery log
+    async fn remove_webhook(&self, id: &str) -> Result<()>;
+
+    /// Returns the latest deliveries of the webhook with the given id, newest first
+    async fn list_deliveries(&self, webhook_id: &str) -> Result<Vec<WebhookDelivery>>;
+
+    /// Queues the event for all webhooks subscribed to its type and delivers it in the
+    /// background. Never fails, so webhooks can't break the action that caused the event
+    async fn dispatch(&self, event: &EventEnvelope);
+
+    /// Retries all pending deliveries, that are due at the given timestamp
+    async fn retry_due_deliveries(&self, timestamp: u64) -> Result<()>;
+}
+
+/// The webhook service POSTs bill events to subscribed external systems
+#[derive(Clone)]
+pub struct WebhookService {
+    store: Arc<dyn WebhookStoreApi>,
+    http: reqwest::Client,
+}
+
+impl WebhookService {
+    pub fn new(store: Arc<dyn WebhookStoreApi>) -> Self {
+        Self {
+            store,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn queue(
+        &self,
+        event: &EventEnvelope,
+        timestamp: u64,
+    ) -> Result<Vec<(Webhook, WebhookDelivery)>> {
+        let webhooks: Vec<Webhook> = self
+            .store
+            .list()
+            .await?
+            .into_iter()
+            .filter(|w| w.subscribes_to(&event.event_type))
+            .collect();
+        if webhooks.is_empty() {
+            return Ok(vec![]);
+        }
+        let payload = serde_json::to_string(event)?;
+        let mut queued = Vec::with_capacity(webhooks.len());
+        for webhook in webhooks {
+            let mut delivery = WebhookDelivery::new(
+                &util::get_uuid_v4().to_string(),
+                &webhook.id,
+                event.event_type.clone(),
+                &payload,
+                timestamp,
+            );
+            // the first attempt is made right away, so the retry job must not send it again
+            // while it's in flight
+            delivery.next_attempt_at = retry::first_retry_at(timestamp, REQUEST_TIMEOUT_SECONDS);
+            self.store.insert_delivery(&delivery).await?;
+            queued.push((webhook, delivery));
+        }
+        Ok(queued)
+    }
+
+    /// Attempts the delivery once and stores the outcome. Failed attempts are retried with
+    /// exponential backoff until `retry::MAX_ATTEMPTS` is reached
+    async fn attempt_delivery(
+        &self,
+        webhook: &Webhook,
+        mut delivery: WebhookDelivery,
+        timestamp: u64,
+    ) -> Result<WebhookDelivery> {
+        delivery.attempts += 1;
+        let failure = match self.post(webhook, &delivery, timestamp).await {
+            Ok(status) => {
+                delivery.last_response_status = Some(status);
+                if (200..300).contains(&status) {
+                    None
+                } else {
+                    Some(format!("Receiver responded with status {status}"))
+                }
+            }
+            Err(e) => {
+                delivery.last_response_status = None;
+                Some(e.to_string())
+            }
+        };
+        match failure {
+            None => {
+                delivery.status = WebhookDeliveryStatus::Delivered;
+                delivery.delivered_at = Some(timestamp);
+                delivery.last_error = None;
+            }
+            Some(e) if delivery.attempts >= retry::MAX_ATTEMPTS => {
+                warn!(
+                    "Giving up delivery {} to webhook {} after {} attempts: {e}",
+                    delivery.id, webhook.id, delivery.attempts
+                );
+                delivery.status = WebhookDeliveryStatus::Failed;
+                delivery.last_error = Some(e);
+            }
+            Some(e) => {
+                delivery.next_attempt_at = timestamp + retry_delay(delivery.attempts);
+                delivery.last_error = Some(e);
+            }
+        }
+        self.store.update_delivery(&delivery).await?;
+        Ok(delivery)
+    }
+
+    async fn post(
+        &self,
+        webhook: &Webhook,
+        delivery: &WebhookDelivery,
+        timestamp: u64,
+    ) -> std::result::Result<u16, reqwest::Error> {
+        let signature = sign(
+            &webhook.secret,
+            &format!("{timestamp}.{}", delivery.payload),
+        );
+        let response = self
+            .http
+            .post(&webhook.url)
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+            .header(CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_SIGNATURE_HEADER, format!("sha256={signature}"))
+            .header(WEBHOOK_TIMESTAMP_HEADER, timestamp.to_string())
+            .header(WEBHOOK_EVENT_HEADER, delivery.event_type.as_str())
+            .header(WEBHOOK_DELIVERY_HEADER, &delivery.id)
+            .body(delivery.payload.clone())
+            .send()
+            .await?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Returns the hex encoded HMAC-SHA256 of the message with the given secret
+fn sign(secret: &str, message: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(message.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[async_trait]
+impl WebhookServiceApi for WebhookService {
+    async fn create_webhook(
+        &self,
+        url: &str,
+        secret: &str,
+        event_types: Vec<EventType>,
+        timestamp: u64,
+    ) -> Result<Webhook> {
+        let url = url.trim();
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => (),
+            _ => {
+                return Err(Error::Validation(format!(
+                    "The webhook URL {url} is not a valid http(s) URL"
+                )));
+            }
+        }
+        if secret.is_empty() {
+            return Err(Error::Validation(String::from(
+                "The secret of a webhook must not be empty",
+            )));
+        }
+        if event_types.is_empty() {
+            return Err(Error::Validation(String::from(
+                "A webhook needs at least one event type",
+            )));
+        }
+        let mut unique_event_types: Vec<EventType> = vec![];
+        for event_type in event_types {
+            if !unique_event_types.contains(&event_type) {
+                unique_event_types.push(event_type);
+            }
+        }
+
+        let webhook = Webhook {
+            id: util::get_uuid_v4().to_string(),
+            url: url.to_owned(),
+            secret: secret.to_owned(),
+            event_types: unique_event_types,
+            created_at: timestamp,
+        };
+        self.store.insert(&webhook).await?;
+        info!("Created webhook {} for {}", webhook.id, webhook.url);
+        Ok(webhook)
+    }
+
+    async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        Ok(self.store.list().await?)
+    }
+
+    async fn remove_webhook(&self, id: &str) -> Result<()> {
+        if self.store.get(id).await?.is_none() {
+            return Err(Error::NotFound);
+        }
+        self.store.remove(id).await?;
+        info!("Removed webhook {id}");
+        Ok(())
+    }
+
+    async fn list_deliveries(&self, webhook_id: &str) -> Result<Vec<WebhookDelivery>> {
+        if self.store.get(webhook_id).await?.is_none() {
+            return Err(Error::NotFound);
+        }
+        Ok(self
+            .store
+            .list_deliveries(webhook_id, DELIVERY_LOG_SIZE)
+            .await?)
+    }
+
+    async fn dispatch(&self, event: &EventEnvelope) {
+        let timestamp = util::date::now().timestamp() as u64;
+        let queued = match self.queue(event, timestamp).await {
+            Ok(queued) => queued,
+            Err(e) => {
+                error!(
+                    "Could not queue {} event for webhooks: {e}",
+                    event.event_type
+                );
+                return;
+            }
+        };
+        if queued.is_empty() {
+            return;
+        }
+        // every delivery is attempted in its own task, so a slow receiver can't delay the others
+        // beyond the request timeout, after which they would be due for a retry
+        for (webhook, delivery) in queued {
+            let service = self.clone();
+            tokio::spawn(async move {
+                let id = delivery.id.clone();
+                if let Err(e) = service
+                    .attempt_delivery(&webhook, delivery, timestamp)
+                    .await
+                {
+                    error!("Could not store the attempt of webhook delivery {id}: {e}");
+                }
+            });
+        }
+    }
+
+    async fn retry_due_deliveries(&self, timestamp: u64) -> Result<()> {
+        let deliveries = self
+            .store
+            .get_due_deliveries(timestamp, RETRY_BATCH_SIZE)
+            .await?;
+        for delivery in deliveries {
+            match self.store.get(&delivery.webhook_id).await? {
+                Some(webhook) => {
+                    let delivery = self.attempt_delivery(&webhook, delivery, timestamp).await?;
+                    info!(
+                        "Retried webhook delivery {} ({} attempts): {:?}",
+                        delivery.id, delivery.attempts, delivery.status
+                    );
+                }
+                None => warn!(
+                    "Skipping delivery {} of removed webhook {}",
+                    delivery.id, delivery.webhook_id
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::tests::MockWebhookStoreApiMock;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+    struct ReceivedRequest {
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    /// A local stand-in for a webhook receiver, which answers every request with the given
+    /// status and passes on the requests it received
+    async fn start_receiver(status: u16) -> (String, UnboundedReceiver<ReceivedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let request = read_request(&mut stream).await;
+                let response = format!(
+                    "HTTP/1.1 {status} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = sender.send(request);
+            }
+        });
+        (url, receiver)
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> ReceivedRequest {
+        let mut data = vec![];
+        let mut buf = [0u8; 1024];
+        loop {
+            let read = stream.read(&mut buf).await.unwrap();
+            assert!(read > 0, "request ended early");
+            data.extend_from_slice(&buf[..read]);
+            let text = String::from_utf8_lossy(&data).into_owned();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let headers: HashMap<String, String> = text[..end]
+                    .lines()
+                    .skip(1)
+                    .filter_map(|line| line.split_once(": "))
+                    .map(|(name, value)| (name.to_lowercase(), value.to_owned()))
+                    .collect();
+                let length: usize = headers
+                    .get("content-length")
+                    .and_then(|l| l.parse().ok())
+                    .unwrap_or(0);
+                if text.len() >= end + 4 + length {
+                    return ReceivedRequest {
+                        headers,
+                        body: text[end + 4..end + 4 + length].to_owned(),
+                    };
+                }
+            }
+        }
+    }
+
+    fn get_webhook(url: &str, event_types: Vec<EventType>) -> Webhook {
+        Webhook {
+            id: "hook".to_string(),
+            url: url.to_string(),
+            secret: "secret".to_string(),
+            event_types,
+            created_at: 1731593928,
+        }
+    }
+
+    fn get_event() -> EventEnvelope {
+        EventEnvelope {
+            event_type: EventType::BillSigned,
+            version: "1.0".to_string(),
+            node_id: "node_id".to_string(),
+            data: json!({ "bill_id": "1234", "action_type": "CheckBill", "sum": 500 }),
+        }
+    }
+
+    #[test]
+    fn sign_creates_hmac_sha256() {
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[tokio::test]
+    async fn create_webhook_validates_input() {
+        let mut store = MockWebhookStoreApiMock::new();
+        store.expect_insert().times(1).returning(|_| Ok(()));
+        let service = WebhookService::new(Arc::new(store));
+
+        for (url, secret, event_types) in [
+            ("ftp://localhost/hook", "secret", vec![EventType::BillPaid]),
+            ("not a url", "secret", vec![EventType::BillPaid]),
+            ("https://localhost/hook", "", vec![EventType::BillPaid]),
+            ("https://localhost/hook", "secret", vec![]),
+        ] {
+            assert!(matches!(
+                service
+                    .create_webhook(url, secret, event_types, 1731593928)
+                    .await,
+                Err(Error::Validation(_))
+            ));
+        }
+        let webhook = service
+            .create_webhook(
+                " https://localhost/hook ",
+                "secret",
+                vec![EventType::BillPaid, EventType::BillPaid],
+                1731593928,
+            )
+            .await
+            .unwrap();
+        assert_eq!(webhook.url, "https://localhost/hook");
+        assert_eq!(webhook.event_types, vec![EventType::BillPaid]);
+    }
+
+    #[tokio::test]
+    async fn dispatch_delivers_signed_event_to_subscribed_webhooks() {
+        let (url, mut receiver) = start_receiver(200).await;
+        let (updated_sender, mut updated) = unbounded_channel();
+        let mut store = MockWebhookStoreApiMock::new();
+        let hook_url = url.clone();
+        store.expect_list().returning(move || {
+            let mut other = get_webhook(&hook_url, vec![EventType::BillPaid]);
+            other.id = "other".to_string();
+            Ok(vec![
+                get_webhook(&hook_url, vec![EventType::BillSigned]),
+                other,
+            ])
+        });
+        store
+            .expect_insert_delivery()
+            .withf(|d| {
+                d.webhook_id == "hook"
+                    && d.status == WebhookDeliveryStatus::Pending
+                    && d.next_attempt_at > d.created_at
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        store.expect_update_delivery().returning(move |d| {
+            updated_sender.send(d.clone()).unwrap();
+            Ok(())
+        });
+        let service = WebhookService::new(Arc::new(store));
+
+        service.dispatch(&get_event()).await;
+
+        let request = receiver.recv().await.unwrap();
+        let timestamp = request.headers.get("x-bcr-timestamp").unwrap();
+        assert_eq!(
+            request.headers.get("x-bcr-signature").unwrap(),
+            &format!(
+                "sha256={}",
+                sign("secret", &format!("{timestamp}.{}", request.body))
+            )
+        );
+        assert_eq!(request.headers.get("x-bcr-event").unwrap(), "BillSigned");
+        let body: EventEnvelope = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body.event_type, EventType::BillSigned);
+        assert_eq!(body.data, get_event().data);
+
+        let delivery = updated.recv().await.unwrap();
+        assert_eq!(delivery.status, WebhookDeliveryStatus::Delivered);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.last_response_status, Some(200));
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_with_backoff() {
+        let (url, _receiver) = start_receiver(500).await;
+        let mut store = MockWebhookStoreApiMock::new();
+        store.expect_update_delivery().returning(|_| Ok(()));
+        let service = WebhookService::new(Arc::new(store));
+        let webhook = get_webhook(&url, vec![EventType::BillSigned]);
+        let delivery = WebhookDelivery::new("d1", "hook", EventType::BillSigned, "{}", 1000);
+
+        let delivery = service
+            .attempt_delivery(&webhook, delivery, 1000)
+            .await
+            .unwrap();
+        assert_eq!(delivery.status, WebhookDeliveryStatus::Pending);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.next_attempt_at, 1030);
+        assert_eq!(delivery.last_response_status, Some(500));
+
+        let mut delivery = delivery;
+        delivery.attempts = retry::MAX_ATTEMPTS - 1;
+        let delivery = service
+            .attempt_delivery(&webhook, delivery, 2000)
+            .await
+            .unwrap();
+        assert_eq!(delivery.status, WebhookDeliveryStatus::Failed);
+        assert!(delivery.last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn retry_due_deliveries_delivers_pending_ones() {
+        let (url, mut receiver) = start_receiver(204).await;
+        let mut store = MockWebhookStoreApiMock::new();
+        store.expect_get_due_deliveries().returning(|_, _| {
+            let mut delivery =
+                WebhookDelivery::new("d1", "hook", EventType::BillSigned, "{}", 1000);
+            delivery.attempts = 2;
+            Ok(vec![delivery])
+        });
+        store
+            .expect_get()
+            .returning(move |_| Ok(Some(get_webhook(&url, vec![EventType::BillSigned]))));
+        store
+            .expect_update_delivery()
+            .withf(|d| {
+                d.status == WebhookDeliveryStatus::Delivered
+                    && d.attempts == 3
+                    && d.delivered_at == Some(2000)
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        let service = WebhookService::new(Arc::new(store));
+
+        service.retry_due_deliveries(2000).await.unwrap();
+        let request = receiver.recv().await.unwrap();
+        assert_eq!(request.headers.get("x-bcr-delivery").unwrap(), "d1");
+        assert_eq!(request.body, "{}");
+    }
+}
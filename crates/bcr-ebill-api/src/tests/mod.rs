@@ -4,22 +4,28 @@ pub mod tests {
     use crate::{CONFIG, data::bill::BillKeys};
     use async_trait::async_trait;
     use bcr_ebill_core::{
-        OptionalPostalAddress, PostalAddress,
-        bill::BitcreditBill,
+        GeneralSearchFilterItemType, OptionalPostalAddress, PostalAddress,
+        api_token::ApiToken,
+        bill::{BillBatchIssue, BillState, BillStateFilter, BillStateKey, BitcreditBill},
+        bill_template::{BillTemplate, BillTemplateIssue},
         blockchain::{
             bill::{BillBlock, BillBlockchain, BillOpCode},
             company::{CompanyBlock, CompanyBlockchain},
-            identity::IdentityBlock,
+            identity::{IdentityBlock, IdentityBlockchain},
         },
         company::{Company, CompanyKeys},
-        contact::{Contact, ContactType, IdentityPublicData},
+        contact::{Contact, ContactType, ContactVerificationPolicy, IdentityPublicData},
         identity::{Identity, IdentityWithAll},
-        notification::{ActionType, Notification, NotificationType},
+        notification::{ActionType, EmailPreference, Notification, NotificationType},
+        search::{SearchDocument, SearchPosting},
         util::crypto::BcrKeys,
+        webhook::{Webhook, WebhookDelivery},
     };
     use bcr_ebill_persistence::{
-        BackupStoreApi, ContactStoreApi, NostrEventOffset, NostrEventOffsetStoreApi,
-        NotificationStoreApi, Result,
+        ApiTokenStoreApi, BackupStoreApi, BillBatchStoreApi, BillTemplateStoreApi, ContactStoreApi,
+        NostrEventOffset, NostrEventOffsetStoreApi, NostrOutboxEntry, NostrOutboxStoreApi,
+        NostrPendingEventStoreApi, NostrQuarantineStoreApi, NotificationStoreApi, PendingEvent,
+        QuarantinedEvent, Result, SearchIndexStoreApi, WebhookStoreApi,
         bill::{BillChainStoreApi, BillStoreApi},
         company::{CompanyChainStoreApi, CompanyStoreApi},
         file_upload::FileUploadStoreApi,
@@ -66,6 +72,7 @@ pub mod tests {
             async fn get_keys(&self, id: &str) -> Result<BillKeys>;
             async fn is_paid(&self, id: &str) -> Result<bool>;
             async fn set_to_paid(&self, id: &str, payment_address: &str) -> Result<()>;
+            async fn get_paid_payment_address(&self, id: &str) -> Result<Option<String>>;
             async fn get_bill_ids_waiting_for_payment(&self) -> Result<Vec<String>>;
             async fn get_bill_ids_waiting_for_sell_payment(&self) -> Result<Vec<String>>;
             async fn get_bill_ids_waiting_for_recourse_payment(&self) -> Result<Vec<String>>;
@@ -74,6 +81,10 @@ pub mod tests {
                 op_code: HashSet<BillOpCode>,
                 since: u64,
             ) -> Result<Vec<String>>;
+            async fn save_bill_states(&self, id: &str, states: &[BillState]) -> Result<()>;
+            async fn remove_bill_states(&self, id: &str) -> Result<()>;
+            async fn get_bill_state_keys(&self) -> Result<Vec<BillStateKey>>;
+            async fn search_bill_states(&self, filter: &BillStateFilter) -> Result<Vec<BillState>>;
         }
     }
 
@@ -140,6 +151,65 @@ pub mod tests {
         impl IdentityChainStoreApi for IdentityChainStoreApiMock {
             async fn get_latest_block(&self) -> Result<IdentityBlock>;
             async fn add_block(&self, block: &IdentityBlock) -> Result<()>;
+            async fn get_chain(&self) -> Result<IdentityBlockchain>;
+        }
+    }
+
+    mockall::mock! {
+        pub ApiTokenStoreApiMock {}
+
+        #[async_trait]
+        impl ApiTokenStoreApi for ApiTokenStoreApiMock {
+            async fn insert(&self, token: &ApiToken, token_hash: &str) -> Result<()>;
+            async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>>;
+            async fn list(&self) -> Result<Vec<ApiToken>>;
+            async fn list_with_hashes(&self) -> Result<Vec<(ApiToken, String)>>;
+            async fn remove(&self, id: &str) -> Result<()>;
+            async fn set_last_used(&self, id: &str, timestamp: u64) -> Result<()>;
+        }
+    }
+
+    mockall::mock! {
+        pub WebhookStoreApiMock {}
+
+        #[async_trait]
+        impl WebhookStoreApi for WebhookStoreApiMock {
+            async fn insert(&self, webhook: &Webhook) -> Result<()>;
+            async fn get(&self, id: &str) -> Result<Option<Webhook>>;
+            async fn list(&self) -> Result<Vec<Webhook>>;
+            async fn remove(&self, id: &str) -> Result<()>;
+            async fn insert_delivery(&self, delivery: &WebhookDelivery) -> Result<()>;
+            async fn update_delivery(&self, delivery: &WebhookDelivery) -> Result<()>;
+            async fn get_due_deliveries(&self, timestamp: u64, limit: u64) -> Result<Vec<WebhookDelivery>>;
+            async fn list_deliveries(&self, webhook_id: &str, limit: u64) -> Result<Vec<WebhookDelivery>>;
+            async fn get_all_deliveries(&self) -> Result<Vec<WebhookDelivery>>;
+        }
+    }
+
+    mockall::mock! {
+        pub BillTemplateStoreApiMock {}
+
+        #[async_trait]
+        impl BillTemplateStoreApi for BillTemplateStoreApiMock {
+            async fn insert(&self, template: &BillTemplate) -> Result<()>;
+            async fn update(&self, template: &BillTemplate) -> Result<()>;
+            async fn get(&self, id: &str) -> Result<Option<BillTemplate>>;
+            async fn list(&self) -> Result<Vec<BillTemplate>>;
+            async fn remove(&self, id: &str) -> Result<()>;
+            async fn add_issue(&self, issue: &BillTemplateIssue) -> Result<()>;
+            async fn list_issues(&self, template_id: &str) -> Result<Vec<BillTemplateIssue>>;
+            async fn get_issue_for_bill(&self, bill_id: &str) -> Result<Option<BillTemplateIssue>>;
+        }
+    }
+
+    mockall::mock! {
+        pub BillBatchStoreApiMock {}
+
+        #[async_trait]
+        impl BillBatchStoreApi for BillBatchStoreApiMock {
+            async fn add_issue(&self, issue: &BillBatchIssue) -> Result<()>;
+            async fn list_issues(&self, batch_id: &str) -> Result<Vec<BillBatchIssue>>;
+            async fn get_all(&self) -> Result<Vec<BillBatchIssue>>;
         }
     }
 
@@ -151,6 +221,43 @@ pub mod tests {
             async fn current_offset(&self) -> Result<u64>;
             async fn is_processed(&self, event_id: &str) -> Result<bool>;
             async fn add_event(&self, data: NostrEventOffset) -> Result<()>;
+            async fn get_all(&self) -> Result<Vec<NostrEventOffset>>;
+        }
+    }
+
+    mockall::mock! {
+        pub NostrQuarantineStoreApiMock {}
+
+        #[async_trait]
+        impl NostrQuarantineStoreApi for NostrQuarantineStoreApiMock {
+            async fn add(&self, event: QuarantinedEvent) -> Result<()>;
+            async fn get(&self, id: &str) -> Result<Option<QuarantinedEvent>>;
+            async fn list(&self) -> Result<Vec<QuarantinedEvent>>;
+            async fn remove(&self, id: &str) -> Result<()>;
+        }
+    }
+
+    mockall::mock! {
+        pub NostrPendingEventStoreApiMock {}
+
+        #[async_trait]
+        impl NostrPendingEventStoreApi for NostrPendingEventStoreApiMock {
+            async fn add(&self, event: PendingEvent) -> Result<()>;
+            async fn list(&self) -> Result<Vec<PendingEvent>>;
+            async fn remove(&self, id: &str) -> Result<()>;
+        }
+    }
+
+    mockall::mock! {
+        pub NostrOutboxStoreApiMock {}
+
+        #[async_trait]
+        impl NostrOutboxStoreApi for NostrOutboxStoreApiMock {
+            async fn insert(&self, entry: &NostrOutboxEntry) -> Result<()>;
+            async fn update(&self, entry: &NostrOutboxEntry) -> Result<()>;
+            async fn get_due(&self, timestamp: u64, limit: u64) -> Result<Vec<NostrOutboxEntry>>;
+            async fn list_unsent(&self, limit: u64) -> Result<Vec<NostrOutboxEntry>>;
+            async fn get_all(&self) -> Result<Vec<NostrOutboxEntry>>;
         }
     }
 
@@ -183,6 +290,30 @@ pub mod tests {
                 block_height: i32,
                 action_type: ActionType,
             ) -> Result<bool>;
+            async fn get_email_preference(&self, node_id: &str) -> Result<Option<EmailPreference>>;
+            async fn set_email_preference(&self, preference: &EmailPreference) -> Result<()>;
+        }
+    }
+
+    mockall::mock! {
+        pub SearchIndexStoreApiMock {}
+
+        #[async_trait]
+        impl SearchIndexStoreApi for SearchIndexStoreApiMock {
+            async fn index_document(&self, document: &SearchDocument) -> Result<()>;
+            async fn remove_document(
+                &self,
+                item_type: GeneralSearchFilterItemType,
+                item_id: &str,
+            ) -> Result<()>;
+            async fn search(
+                &self,
+                tokens: &[String],
+                item_types: &[GeneralSearchFilterItemType],
+            ) -> Result<Vec<SearchPosting>>;
+            async fn clear(&self) -> Result<()>;
+            async fn get_index_version(&self) -> Result<u32>;
+            async fn set_index_version(&self, version: u32) -> Result<()>;
         }
     }
 
@@ -217,9 +348,12 @@ pub mod tests {
             None => {
                 crate::init(crate::Config {
                     bitcoin_network: "mainnet".to_string(),
-                    nostr_relay: "ws://localhost:8080".to_string(),
+                    nostr_relays: vec!["ws://localhost:8080".to_string()],
+                    nostr_write_quorum: 1,
                     surreal_db_connection: "ws://localhost:8800".to_string(),
                     data_dir: ".".to_string(),
+                    email: None,
+                    contact_verification_policy: ContactVerificationPolicy::Off,
                 })
                 .unwrap();
             }
@@ -267,7 +401,7 @@ pub mod tests {
             name: "".to_string(),
             postal_address: empty_address(),
             email: None,
-            nostr_relay: None,
+            nostr_relays: vec![],
         }
     }
 
@@ -278,7 +412,7 @@ pub mod tests {
             name: "".to_string(),
             postal_address: empty_address(),
             email: None,
-            nostr_relay: None,
+            nostr_relays: vec![],
         }
     }
 
@@ -1,6 +1,8 @@
 pub mod currency;
 pub mod file;
 pub mod numbers_to_words;
+pub mod qr_code;
+pub mod retry;
 
 pub use bcr_ebill_core::util::crypto;
 pub use bcr_ebill_core::util::date;
@@ -1,5 +1,36 @@
 use std::iter::successors;
 
+/// The languages amounts can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+    Italian,
+}
+
+impl Language {
+    /// Parses ISO 639 codes and language tags like `de-AT`, falling back to English for unknown
+    /// languages
+    pub fn from_code(code: &str) -> Self {
+        let primary = code
+            .trim()
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match primary.as_str() {
+            "de" | "deu" | "ger" => Language::German,
+            "fr" | "fra" | "fre" => Language::French,
+            "es" | "spa" => Language::Spanish,
+            "it" | "ita" => Language::Italian,
+            _ => Language::English,
+        }
+    }
+}
+
 const ONES: [&str; 20] = [
     "zero",
     "one",
@@ -66,6 +97,512 @@ fn format_num(num: &u64, div: u64, order: &str) -> String {
     }
 }
 
+/// Writes the given number in words in the given language
+pub fn encode_in(num: &u64, language: Language) -> String {
+    match language {
+        Language::English => encode(num),
+        Language::German => encode_de(*num),
+        Language::French => encode_fr(*num),
+        Language::Spanish => encode_es(*num, false),
+        Language::Italian => encode_it(*num),
+    }
+}
+
+/// Writes the given sum in words in the given language, followed by the currency, which is
+/// declined according to the sum, e.g. `one satoshi`, `zwei Millionen Satoshi`, or
+/// `un millón de satoshis`
+pub fn sum_to_words(sum: &u64, currency: &str, language: Language) -> String {
+    let number = match (language, sum) {
+        (Language::German, 1) => "ein".to_string(),
+        (Language::Italian, 1) => "un".to_string(),
+        // numbers ending with one are shortened before nouns in Spanish, e.g. `veintiún`
+        (Language::Spanish, _) => encode_es(*sum, true),
+        _ => encode_in(sum, language),
+    };
+    let singular = match language {
+        // zero takes the singular in French
+        Language::French => *sum <= 1,
+        _ => *sum == 1,
+    };
+    let currency = match currency_words(currency, language) {
+        Some((one, many)) => {
+            if singular {
+                one.to_string()
+            } else {
+                many.to_string()
+            }
+        }
+        None => currency.to_string(),
+    };
+    // round millions and above are followed by a preposition in Romance languages
+    let preposition = if *sum >= MILLION && sum.is_multiple_of(MILLION) {
+        match language {
+            Language::French | Language::Spanish => Some("de"),
+            Language::Italian => Some("di"),
+            Language::English | Language::German => None,
+        }
+    } else {
+        None
+    };
+    match preposition {
+        Some(preposition) => format!("{number} {preposition} {currency}"),
+        None => format!("{number} {currency}"),
+    }
+}
+
+/// The singular and plural of the given currency in the given language, if it's known
+fn currency_words(currency: &str, language: Language) -> Option<(&'static str, &'static str)> {
+    let words = match (currency.to_lowercase().as_str(), language) {
+        ("sat", Language::English) => ("satoshi", "satoshis"),
+        ("sat", Language::German) => ("Satoshi", "Satoshi"),
+        ("sat", Language::French) | ("sat", Language::Spanish) => ("satoshi", "satoshis"),
+        ("sat", Language::Italian) => ("satoshi", "satoshi"),
+        ("btc", Language::English) => ("bitcoin", "bitcoins"),
+        ("btc", Language::German) => ("Bitcoin", "Bitcoin"),
+        ("btc", Language::French) | ("btc", Language::Spanish) => ("bitcoin", "bitcoins"),
+        ("btc", Language::Italian) => ("bitcoin", "bitcoin"),
+        _ => return None,
+    };
+    Some(words)
+}
+
+const MILLION: u64 = 1_000_000;
+
+/// Encodes the large orders, which are written as separate words in all languages except
+/// English, e.g. `zwei Millionen`, using the given function for the count of each order and
+/// for the part below a million
+fn encode_orders(
+    num: u64,
+    orders: &[(u64, &str, &str)],
+    one: &str,
+    encode_count: impl Fn(u64) -> String,
+    encode_below_million: impl Fn(u64) -> String,
+) -> String {
+    let mut words: Vec<String> = vec![];
+    let mut rest = num;
+    for (value, singular, plural) in orders {
+        let count = rest / value;
+        rest %= value;
+        match count {
+            0 => (),
+            1 => words.push(format!("{one} {singular}")),
+            _ => words.push(format!("{} {plural}", encode_count(count))),
+        }
+    }
+    if rest > 0 {
+        words.push(encode_below_million(rest));
+    }
+    words.join(" ")
+}
+
+const DE_ONES: [&str; 20] = [
+    "null",
+    "eins",
+    "zwei",
+    "drei",
+    "vier",
+    "fünf",
+    "sechs",
+    "sieben",
+    "acht",
+    "neun",
+    "zehn",
+    "elf",
+    "zwölf",
+    "dreizehn",
+    "vierzehn",
+    "fünfzehn",
+    "sechzehn",
+    "siebzehn",
+    "achtzehn",
+    "neunzehn",
+];
+const DE_TENS: [&str; 10] = [
+    "null", "zehn", "zwanzig", "dreißig", "vierzig", "fünfzig", "sechzig", "siebzig", "achtzig",
+    "neunzig",
+];
+const DE_ORDERS: [(u64, &str, &str); 5] = [
+    (1_000_000_000_000_000_000, "Trillion", "Trillionen"),
+    (1_000_000_000_000_000, "Billiarde", "Billiarden"),
+    (1_000_000_000_000, "Billion", "Billionen"),
+    (1_000_000_000, "Milliarde", "Milliarden"),
+    (MILLION, "Million", "Millionen"),
+];
+
+/// German numbers below a million are written as one word, e.g. `einundzwanzigtausend`
+fn encode_de(num: u64) -> String {
+    if num == 0 {
+        return DE_ONES[0].to_string();
+    }
+    encode_orders(
+        num,
+        &DE_ORDERS,
+        "eine",
+        |count| de_below_thousand(count, false),
+        |rest| {
+            let (thousands, rest) = (rest / 1000, rest % 1000);
+            let mut result = String::new();
+            if thousands > 0 {
+                result.push_str(&de_below_thousand(thousands, true));
+                result.push_str("tausend");
+            }
+            if rest > 0 {
+                result.push_str(&de_below_thousand(rest, false));
+            }
+            result
+        },
+    )
+}
+
+/// `prefix` is set, if the number is followed by another part of the word, in which case one
+/// is written as `ein`
+fn de_below_thousand(num: u64, prefix: bool) -> String {
+    let (hundreds, rest) = (num / 100, num % 100);
+    let mut result = String::new();
+    if hundreds > 0 {
+        result.push_str(if hundreds == 1 {
+            "ein"
+        } else {
+            DE_ONES[hundreds as usize]
+        });
+        result.push_str("hundert");
+    }
+    match rest {
+        0 => (),
+        1 if prefix => result.push_str("ein"),
+        1..=19 => result.push_str(DE_ONES[rest as usize]),
+        _ => {
+            let (tens, ones) = ((rest / 10) as usize, (rest % 10) as usize);
+            if ones > 0 {
+                result.push_str(if ones == 1 { "ein" } else { DE_ONES[ones] });
+                result.push_str("und");
+            }
+            result.push_str(DE_TENS[tens]);
+        }
+    }
+    result
+}
+
+const FR_ONES: [&str; 20] = [
+    "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf", "dix", "onze",
+    "douze", "treize", "quatorze", "quinze", "seize", "dix-sept", "dix-huit", "dix-neuf",
+];
+const FR_TENS: [&str; 7] = [
+    "zéro",
+    "dix",
+    "vingt",
+    "trente",
+    "quarante",
+    "cinquante",
+    "soixante",
+];
+const FR_ORDERS: [(u64, &str, &str); 5] = [
+    (1_000_000_000_000_000_000, "trillion", "trillions"),
+    (1_000_000_000_000_000, "billiard", "billiards"),
+    (1_000_000_000_000, "billion", "billions"),
+    (1_000_000_000, "milliard", "milliards"),
+    (MILLION, "million", "millions"),
+];
+
+fn encode_fr(num: u64) -> String {
+    if num == 0 {
+        return FR_ONES[0].to_string();
+    }
+    encode_orders(
+        num,
+        &FR_ORDERS,
+        "un",
+        |count| fr_below_thousand(count, true),
+        |rest| {
+            let (thousands, rest) = (rest / 1000, rest % 1000);
+            let mut words: Vec<String> = vec![];
+            match thousands {
+                0 => (),
+                1 => words.push("mille".to_string()),
+                // `mille` is invariant and `cents` and `vingts` lose their plural before it
+                _ => words.push(format!("{} mille", fr_below_thousand(thousands, false))),
+            }
+            if rest > 0 {
+                words.push(fr_below_thousand(rest, true));
+            }
+            words.join(" ")
+        },
+    )
+}
+
+/// `last` is set, if the number ends the amount, or is followed by a noun, in which case
+/// `cent` and `quatre-vingt` take the plural
+fn fr_below_thousand(num: u64, last: bool) -> String {
+    let (hundreds, rest) = (num / 100, num % 100);
+    let mut words: Vec<String> = vec![];
+    match hundreds {
+        0 => (),
+        1 => words.push("cent".to_string()),
+        _ if rest == 0 && last => words.push(format!("{} cents", FR_ONES[hundreds as usize])),
+        _ => words.push(format!("{} cent", FR_ONES[hundreds as usize])),
+    }
+    if rest > 0 {
+        words.push(fr_below_hundred(rest, last));
+    }
+    words.join(" ")
+}
+
+fn fr_below_hundred(num: u64, last: bool) -> String {
+    match num {
+        0..=19 => FR_ONES[num as usize].to_string(),
+        20..=69 => {
+            let (tens, ones) = ((num / 10) as usize, (num % 10) as usize);
+            match ones {
+                0 => FR_TENS[tens].to_string(),
+                1 => format!("{} et un", FR_TENS[tens]),
+                _ => format!("{}-{}", FR_TENS[tens], FR_ONES[ones]),
+            }
+        }
+        71 => "soixante et onze".to_string(),
+        70..=79 => format!("soixante-{}", FR_ONES[(num - 60) as usize]),
+        80 if last => "quatre-vingts".to_string(),
+        80 => "quatre-vingt".to_string(),
+        _ => format!("quatre-vingt-{}", FR_ONES[(num - 80) as usize]),
+    }
+}
+
+const ES_ONES: [&str; 30] = [
+    "cero",
+    "uno",
+    "dos",
+    "tres",
+    "cuatro",
+    "cinco",
+    "seis",
+    "siete",
+    "ocho",
+    "nueve",
+    "diez",
+    "once",
+    "doce",
+    "trece",
+    "catorce",
+    "quince",
+    "dieciséis",
+    "diecisiete",
+    "dieciocho",
+    "diecinueve",
+    "veinte",
+    "veintiuno",
+    "veintidós",
+    "veintitrés",
+    "veinticuatro",
+    "veinticinco",
+    "veintiséis",
+    "veintisiete",
+    "veintiocho",
+    "veintinueve",
+];
+const ES_TENS: [&str; 10] = [
+    "cero",
+    "diez",
+    "veinte",
+    "treinta",
+    "cuarenta",
+    "cincuenta",
+    "sesenta",
+    "setenta",
+    "ochenta",
+    "noventa",
+];
+const ES_HUNDREDS: [&str; 10] = [
+    "cero",
+    "ciento",
+    "doscientos",
+    "trescientos",
+    "cuatrocientos",
+    "quinientos",
+    "seiscientos",
+    "setecientos",
+    "ochocientos",
+    "novecientos",
+];
+/// Spanish uses the long scale, so a `billón` is a million millions
+const ES_ORDERS: [(u64, &str, &str); 3] = [
+    (1_000_000_000_000_000_000, "trillón", "trillones"),
+    (1_000_000_000_000, "billón", "billones"),
+    (MILLION, "millón", "millones"),
+];
+
+/// `apocope` is set, if the number is followed by a noun, in which case numbers ending with one
+/// are shortened, e.g. `veintiún`
+fn encode_es(num: u64, apocope: bool) -> String {
+    if num == 0 {
+        return ES_ONES[0].to_string();
+    }
+    encode_orders(
+        num,
+        &ES_ORDERS,
+        "un",
+        |count| es_below_million(count, true),
+        |rest| es_below_million(rest, apocope),
+    )
+}
+
+fn es_below_million(num: u64, apocope: bool) -> String {
+    let (thousands, rest) = (num / 1000, num % 1000);
+    let mut words: Vec<String> = vec![];
+    match thousands {
+        0 => (),
+        1 => words.push("mil".to_string()),
+        _ => words.push(format!("{} mil", es_below_thousand(thousands, true))),
+    }
+    if rest > 0 {
+        words.push(es_below_thousand(rest, apocope));
+    }
+    words.join(" ")
+}
+
+fn es_below_thousand(num: u64, apocope: bool) -> String {
+    let (hundreds, rest) = (num / 100, num % 100);
+    match (hundreds, rest) {
+        (0, _) => es_below_hundred(rest, apocope),
+        (1, 0) => "cien".to_string(),
+        (_, 0) => ES_HUNDREDS[hundreds as usize].to_string(),
+        _ => format!(
+            "{} {}",
+            ES_HUNDREDS[hundreds as usize],
+            es_below_hundred(rest, apocope)
+        ),
+    }
+}
+
+fn es_below_hundred(num: u64, apocope: bool) -> String {
+    match num {
+        1 if apocope => "un".to_string(),
+        21 if apocope => "veintiún".to_string(),
+        0..=29 => ES_ONES[num as usize].to_string(),
+        _ => {
+            let (tens, ones) = ((num / 10) as usize, (num % 10) as usize);
+            match ones {
+                0 => ES_TENS[tens].to_string(),
+                1 if apocope => format!("{} y un", ES_TENS[tens]),
+                _ => format!("{} y {}", ES_TENS[tens], ES_ONES[ones]),
+            }
+        }
+    }
+}
+
+const IT_ONES: [&str; 20] = [
+    "zero",
+    "uno",
+    "due",
+    "tre",
+    "quattro",
+    "cinque",
+    "sei",
+    "sette",
+    "otto",
+    "nove",
+    "dieci",
+    "undici",
+    "dodici",
+    "tredici",
+    "quattordici",
+    "quindici",
+    "sedici",
+    "diciassette",
+    "diciotto",
+    "diciannove",
+];
+const IT_TENS: [&str; 10] = [
+    "zero",
+    "dieci",
+    "venti",
+    "trenta",
+    "quaranta",
+    "cinquanta",
+    "sessanta",
+    "settanta",
+    "ottanta",
+    "novanta",
+];
+const IT_ORDERS: [(u64, &str, &str); 5] = [
+    (1_000_000_000_000_000_000, "trilione", "trilioni"),
+    (1_000_000_000_000_000, "biliardo", "biliardi"),
+    (1_000_000_000_000, "bilione", "bilioni"),
+    (1_000_000_000, "miliardo", "miliardi"),
+    (MILLION, "milione", "milioni"),
+];
+
+/// Italian numbers below a million are written as one word, e.g. `duemilaventitré`
+fn encode_it(num: u64) -> String {
+    if num == 0 {
+        return IT_ONES[0].to_string();
+    }
+    encode_orders(
+        num,
+        &IT_ORDERS,
+        "un",
+        |count| it_accent(it_below_thousand(count)),
+        |rest| {
+            let (thousands, rest) = (rest / 1000, rest % 1000);
+            let mut result = String::new();
+            match thousands {
+                0 => (),
+                1 => result.push_str("mille"),
+                _ => {
+                    result.push_str(&it_below_thousand(thousands));
+                    result.push_str("mila");
+                }
+            }
+            if rest > 0 {
+                result.push_str(&it_below_thousand(rest));
+            }
+            it_accent(result)
+        },
+    )
+}
+
+/// Compound numbers ending with three take an accent, e.g. `ventitré`
+fn it_accent(word: String) -> String {
+    match word.strip_suffix("tre") {
+        Some(prefix) if !prefix.is_empty() => format!("{prefix}tré"),
+        _ => word,
+    }
+}
+
+fn it_below_thousand(num: u64) -> String {
+    let (hundreds, rest) = (num / 100, num % 100);
+    let mut result = String::new();
+    match hundreds {
+        0 => (),
+        1 => result.push_str("cento"),
+        _ => {
+            result.push_str(IT_ONES[hundreds as usize]);
+            result.push_str("cento");
+        }
+    }
+    if rest > 0 {
+        result.push_str(&it_below_hundred(rest));
+    }
+    result
+}
+
+fn it_below_hundred(num: u64) -> String {
+    match num {
+        0..=19 => IT_ONES[num as usize].to_string(),
+        _ => {
+            let (tens, ones) = ((num / 10) as usize, (num % 10) as usize);
+            match ones {
+                0 => IT_TENS[tens].to_string(),
+                // the tens lose their last vowel before a vowel, e.g. `ventuno`
+                1 | 8 => format!(
+                    "{}{}",
+                    &IT_TENS[tens][..IT_TENS[tens].len() - 1],
+                    IT_ONES[ones]
+                ),
+                _ => format!("{}{}", IT_TENS[tens], IT_ONES[ones]),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,4 +630,127 @@ mod tests {
         let result = encode(&123_324_324);
         assert_eq!("one hundred twenty-three million three hundred twenty-four thousand three hundred twenty-four".to_string(), result);
     }
+
+    #[test]
+    fn language_from_code() {
+        assert_eq!(Language::from_code("de"), Language::German);
+        assert_eq!(Language::from_code("de-AT"), Language::German);
+        assert_eq!(Language::from_code("FR"), Language::French);
+        assert_eq!(Language::from_code("spa"), Language::Spanish);
+        assert_eq!(Language::from_code("it_IT"), Language::Italian);
+        assert_eq!(Language::from_code("en"), Language::English);
+        assert_eq!(Language::from_code("unknown"), Language::English);
+        assert_eq!(Language::from_code(""), Language::English);
+    }
+
+    #[test]
+    fn numbers_to_words_german() {
+        assert_eq!(encode_in(&0, Language::German), "null");
+        assert_eq!(encode_in(&1, Language::German), "eins");
+        assert_eq!(encode_in(&21, Language::German), "einundzwanzig");
+        assert_eq!(encode_in(&101, Language::German), "einhunderteins");
+        assert_eq!(
+            encode_in(&1_999, Language::German),
+            "eintausendneunhundertneunundneunzig"
+        );
+        assert_eq!(encode_in(&1_000_000, Language::German), "eine Million");
+        assert_eq!(
+            encode_in(&2_301_000, Language::German),
+            "zwei Millionen dreihunderteintausend"
+        );
+    }
+
+    #[test]
+    fn numbers_to_words_french() {
+        assert_eq!(encode_in(&21, Language::French), "vingt et un");
+        assert_eq!(encode_in(&71, Language::French), "soixante et onze");
+        assert_eq!(encode_in(&77, Language::French), "soixante-dix-sept");
+        assert_eq!(encode_in(&80, Language::French), "quatre-vingts");
+        assert_eq!(encode_in(&81, Language::French), "quatre-vingt-un");
+        assert_eq!(encode_in(&99, Language::French), "quatre-vingt-dix-neuf");
+        assert_eq!(encode_in(&200, Language::French), "deux cents");
+        assert_eq!(encode_in(&201, Language::French), "deux cent un");
+        assert_eq!(encode_in(&80_000, Language::French), "quatre-vingt mille");
+        assert_eq!(
+            encode_in(&200_000_000, Language::French),
+            "deux cents millions"
+        );
+        assert_eq!(encode_in(&1_001_000, Language::French), "un million mille");
+    }
+
+    #[test]
+    fn numbers_to_words_spanish() {
+        assert_eq!(encode_in(&1, Language::Spanish), "uno");
+        assert_eq!(encode_in(&16, Language::Spanish), "dieciséis");
+        assert_eq!(encode_in(&31, Language::Spanish), "treinta y uno");
+        assert_eq!(encode_in(&100, Language::Spanish), "cien");
+        assert_eq!(encode_in(&101, Language::Spanish), "ciento uno");
+        assert_eq!(encode_in(&500, Language::Spanish), "quinientos");
+        assert_eq!(encode_in(&21_000, Language::Spanish), "veintiún mil");
+        assert_eq!(
+            encode_in(&2_500_000, Language::Spanish),
+            "dos millones quinientos mil"
+        );
+        assert_eq!(encode_in(&1_000_000_000, Language::Spanish), "mil millones");
+        assert_eq!(
+            encode_in(&1_000_000_000_000, Language::Spanish),
+            "un billón"
+        );
+    }
+
+    #[test]
+    fn numbers_to_words_italian() {
+        assert_eq!(encode_in(&1, Language::Italian), "uno");
+        assert_eq!(encode_in(&23, Language::Italian), "ventitré");
+        assert_eq!(encode_in(&28, Language::Italian), "ventotto");
+        assert_eq!(encode_in(&1_000, Language::Italian), "mille");
+        assert_eq!(encode_in(&2_003, Language::Italian), "duemilatré");
+        assert_eq!(encode_in(&23_000, Language::Italian), "ventitremila");
+        assert_eq!(encode_in(&3_000_000, Language::Italian), "tre milioni");
+    }
+
+    #[test]
+    fn sum_to_words_declines_currency() {
+        assert_eq!(sum_to_words(&1, "sat", Language::English), "one satoshi");
+        assert_eq!(
+            sum_to_words(&500, "sat", Language::English),
+            "five hundred satoshis"
+        );
+        assert_eq!(sum_to_words(&1, "sat", Language::German), "ein Satoshi");
+        assert_eq!(sum_to_words(&2, "sat", Language::German), "zwei Satoshi");
+        assert_eq!(sum_to_words(&0, "sat", Language::French), "zéro satoshi");
+        assert_eq!(sum_to_words(&2, "sat", Language::French), "deux satoshis");
+        assert_eq!(
+            sum_to_words(&2_000_000, "sat", Language::French),
+            "deux millions de satoshis"
+        );
+        assert_eq!(
+            sum_to_words(&21, "sat", Language::Spanish),
+            "veintiún satoshis"
+        );
+        assert_eq!(
+            sum_to_words(&1_000_000, "sat", Language::Spanish),
+            "un millón de satoshis"
+        );
+        assert_eq!(sum_to_words(&1, "sat", Language::Italian), "un satoshi");
+        assert_eq!(
+            sum_to_words(&1_000_000, "sat", Language::Italian),
+            "un milione di satoshi"
+        );
+        assert_eq!(sum_to_words(&2, "BTC", Language::English), "two bitcoins");
+        assert_eq!(sum_to_words(&2, "eur", Language::English), "two eur");
+    }
+
+    #[test]
+    fn numbers_to_words_max() {
+        for language in [
+            Language::English,
+            Language::German,
+            Language::French,
+            Language::Spanish,
+            Language::Italian,
+        ] {
+            assert!(!encode_in(&u64::MAX, language).is_empty());
+        }
+    }
 }
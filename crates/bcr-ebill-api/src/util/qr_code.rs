@@ -0,0 +1,69 @@
+use crate::service::{Error, Result};
+use qrcode::{Color, QrCode, render::svg};
+
+/// The size of a QR code module in pixels in PNG images
+const PNG_MODULE_SIZE: usize = 8;
+/// The width of the light border around the code in modules, as required by the QR spec
+const QUIET_ZONE_MODULES: usize = 4;
+/// The minimum width and height of SVG images
+const SVG_MIN_SIZE: u32 = 256;
+
+fn encode(content: &str) -> Result<QrCode> {
+    QrCode::new(content.as_bytes())
+        .map_err(|e| Error::Validation(format!("Could not create QR code: {e}")))
+}
+
+/// Renders the content as QR code SVG image
+pub fn to_svg(content: &str) -> Result<String> {
+    Ok(encode(content)?
+        .render::<svg::Color>()
+        .min_dimensions(SVG_MIN_SIZE, SVG_MIN_SIZE)
+        .build())
+}
+
+/// Renders the content as grayscale QR code PNG image
+pub fn to_png(content: &str) -> Result<Vec<u8>> {
+    let code = encode(content)?;
+    let width = code.width();
+    let size = (width + 2 * QUIET_ZONE_MODULES) * PNG_MODULE_SIZE;
+    let mut pixels = vec![u8::MAX; size * size];
+    for (idx, color) in code.to_colors().iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let left = (idx % width + QUIET_ZONE_MODULES) * PNG_MODULE_SIZE;
+        let top = (idx / width + QUIET_ZONE_MODULES) * PNG_MODULE_SIZE;
+        for y in top..top + PNG_MODULE_SIZE {
+            pixels[y * size + left..y * size + left + PNG_MODULE_SIZE].fill(0);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, size as u32, size as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| {
+                writer.write_image_data(&pixels)?;
+                writer.finish()
+            })
+            .map_err(|e| Error::Validation(format!("Could not create QR code image: {e}")))?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_svg_and_png() {
+        let svg = to_svg("bcr:test").unwrap();
+        assert!(svg.contains("<svg"));
+
+        let png = to_png("bcr:test").unwrap();
+        assert!(png.starts_with(&[0x89, b'P', b'N', b'G']));
+    }
+}
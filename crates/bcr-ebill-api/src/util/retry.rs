@@ -0,0 +1,31 @@
+//! Exponential backoff for outgoing deliveries, like webhooks and Nostr events, whose first
+//! attempt is made right away and which are retried by the job runner after failures
+
+/// After this many failed attempts, a delivery is given up
+pub const MAX_ATTEMPTS: u32 = 8;
+/// The delay before the first retry, it's doubled for every further one
+const BASE_DELAY_SECONDS: u64 = 30;
+
+/// The delay before the next attempt, after the given number of failed attempts
+pub fn retry_delay(attempts: u32) -> u64 {
+    BASE_DELAY_SECONDS * 2u64.pow(attempts.saturating_sub(1))
+}
+
+/// When a new delivery becomes due for the retry job. It's left to its first attempt for twice
+/// the request timeout, so the outcome of the first attempt is stored, before the retry job
+/// could send it a second time
+pub fn first_retry_at(timestamp: u64, request_timeout_seconds: u64) -> u64 {
+    timestamp + 2 * request_timeout_seconds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles() {
+        assert_eq!(retry_delay(1), 30);
+        assert_eq!(retry_delay(2), 60);
+        assert_eq!(retry_delay(7), 1920);
+    }
+}
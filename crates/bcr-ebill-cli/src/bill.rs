@@ -0,0 +1,558 @@
+use crate::client::{ApiClient, Error, Result, save_file};
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum BillCommand {
+    /// Issue a new bill, with the given files attached
+    Issue {
+        #[arg(long = "type", value_enum)]
+        t: BillType,
+        #[arg(long)]
+        country_of_issuing: String,
+        #[arg(long)]
+        city_of_issuing: String,
+        /// The issue date, e.g. 2025-01-22
+        #[arg(long)]
+        issue_date: String,
+        /// The maturity date, e.g. 2025-03-22
+        #[arg(long)]
+        maturity_date: String,
+        /// The node id of the payee
+        #[arg(long)]
+        payee: String,
+        /// The node id of the drawee
+        #[arg(long)]
+        drawee: String,
+        #[arg(long)]
+        sum: String,
+        #[arg(default_value_t = String::from("sat"), long)]
+        currency: String,
+        #[arg(long)]
+        country_of_payment: String,
+        #[arg(long)]
+        city_of_payment: String,
+        #[arg(default_value_t = String::from("en"), long)]
+        language: String,
+        /// A file to attach to the bill, can be given multiple times
+        #[arg(long = "file")]
+        files: Vec<PathBuf>,
+    },
+    /// List all bills
+    List {
+        /// Only list the fields needed for an overview
+        #[arg(long)]
+        light: bool,
+    },
+    /// Show the bill with the given id
+    Show { bill_id: String },
+    /// List the endorsements of the bill with the given id
+    Endorsements { bill_id: String },
+    /// Search bills, one page at a time
+    Search {
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[command(flatten)]
+        sort: SortArgs,
+        /// The `next_cursor` of the previous page, to fetch the next one
+        #[arg(long)]
+        cursor: Option<String>,
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Export the bills matching the filter to the given file
+    Export {
+        output: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[command(flatten)]
+        sort: SortArgs,
+    },
+    /// Write the bill with the given id as printable PDF to the given file
+    Pdf { bill_id: String, output: PathBuf },
+    /// Write a verifiable bundle of the bill's chain to the given file
+    ChainBundle {
+        bill_id: String,
+        output: PathBuf,
+        /// The id of a block to disclose, can be given multiple times, all if not set
+        #[arg(long = "block")]
+        disclosed_block_ids: Vec<u64>,
+    },
+    /// Check the payment state of all bills
+    CheckPayment,
+    /// Endorse the bill to the given node id
+    Endorse {
+        bill_id: String,
+        #[arg(long)]
+        endorsee: String,
+    },
+    /// Accept the bill as drawee
+    Accept { bill_id: String },
+    /// Request the drawee to accept the bill
+    RequestToAccept { bill_id: String },
+    /// Request the drawee to pay the bill
+    RequestToPay {
+        bill_id: String,
+        #[arg(default_value_t = String::from("sat"), long)]
+        currency: String,
+    },
+    /// Offer to sell the bill to the given node id
+    OfferToSell {
+        bill_id: String,
+        #[arg(long)]
+        buyer: String,
+        #[arg(long)]
+        sum: String,
+        #[arg(default_value_t = String::from("sat"), long)]
+        currency: String,
+    },
+    /// Request the given mint to mint the bill
+    RequestToMint {
+        bill_id: String,
+        #[arg(long)]
+        mint_node: String,
+    },
+    /// Mint the bill with the given mint
+    Mint {
+        bill_id: String,
+        #[arg(long)]
+        mint_node: String,
+        #[arg(long)]
+        sum: String,
+        #[arg(default_value_t = String::from("sat"), long)]
+        currency: String,
+    },
+    /// Reject a request on the bill
+    Reject {
+        bill_id: String,
+        #[arg(long, value_enum)]
+        action: RejectAction,
+    },
+    /// Request recourse from the given node id, for a bill that wasn't paid
+    RequestRecourseForPayment {
+        bill_id: String,
+        #[arg(long)]
+        recoursee: String,
+        #[arg(long)]
+        sum: String,
+        #[arg(default_value_t = String::from("sat"), long)]
+        currency: String,
+    },
+    /// Request recourse from the given node id, for a bill that wasn't accepted
+    RequestRecourseForAcceptance {
+        bill_id: String,
+        #[arg(long)]
+        recoursee: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BillType {
+    PromissoryNote,
+    SelfDrafted,
+    ThreeParties,
+}
+
+impl BillType {
+    fn as_web(&self) -> u64 {
+        match self {
+            BillType::PromissoryNote => 0,
+            BillType::SelfDrafted => 1,
+            BillType::ThreeParties => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+    Xlsx,
+}
+
+impl ExportFormat {
+    fn as_web(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "Csv",
+            ExportFormat::JsonLines => "JsonLines",
+            ExportFormat::Xlsx => "Xlsx",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum RejectAction {
+    Accept,
+    Pay,
+    Buy,
+    PayRecourse,
+}
+
+impl RejectAction {
+    fn path(&self) -> &'static str {
+        match self {
+            RejectAction::Accept => "/bill/reject_to_accept",
+            RejectAction::Pay => "/bill/reject_to_pay",
+            RejectAction::Buy => "/bill/reject_to_buy",
+            RejectAction::PayRecourse => "/bill/reject_to_pay_recourse",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Role {
+    All,
+    Payer,
+    Payee,
+    Contingent,
+}
+
+impl Role {
+    fn as_web(&self) -> &'static str {
+        match self {
+            Role::All => "All",
+            Role::Payer => "Payer",
+            Role::Payee => "Payee",
+            Role::Contingent => "Contingent",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SortField {
+    TimeOfDrawing,
+    IssueDate,
+    MaturityDate,
+    Sum,
+}
+
+impl SortField {
+    fn as_web(&self) -> &'static str {
+        match self {
+            SortField::TimeOfDrawing => "TimeOfDrawing",
+            SortField::IssueDate => "IssueDate",
+            SortField::MaturityDate => "MaturityDate",
+            SortField::Sum => "Sum",
+        }
+    }
+}
+
+/// The filter for searching and exporting bills, every part is ignored if not set
+#[derive(Args)]
+pub struct FilterArgs {
+    #[arg(long)]
+    search_term: Option<String>,
+    #[arg(long, value_enum, default_value_t = Role::All)]
+    role: Role,
+    #[arg(long)]
+    currency: Option<String>,
+    /// Issue dates from, e.g. 2025-01-01, requires --issued-to
+    #[arg(long, requires = "issued_to")]
+    issued_from: Option<String>,
+    #[arg(long, requires = "issued_from")]
+    issued_to: Option<String>,
+    /// Maturity dates from, e.g. 2025-01-01, requires --maturity-to
+    #[arg(long, requires = "maturity_to")]
+    maturity_from: Option<String>,
+    #[arg(long, requires = "maturity_from")]
+    maturity_to: Option<String>,
+    #[arg(long)]
+    sum_from: Option<String>,
+    #[arg(long)]
+    sum_to: Option<String>,
+    #[arg(long)]
+    accepted: Option<bool>,
+    #[arg(long)]
+    paid: Option<bool>,
+    #[arg(long)]
+    in_recourse: Option<bool>,
+    #[arg(long)]
+    waiting_for_payment: Option<bool>,
+    /// Only bills the given node id is, or was a party of
+    #[arg(long)]
+    counterparty_node_id: Option<String>,
+    #[arg(long)]
+    has_attachments: Option<bool>,
+}
+
+impl FilterArgs {
+    fn to_json(&self) -> Value {
+        let range = |from: &Option<String>, to: &Option<String>| match (from, to) {
+            (Some(from), Some(to)) => json!({ "from": from, "to": to }),
+            _ => Value::Null,
+        };
+        let sum_range = if self.sum_from.is_some() || self.sum_to.is_some() {
+            json!({ "from": self.sum_from, "to": self.sum_to })
+        } else {
+            Value::Null
+        };
+        json!({
+            "search_term": self.search_term,
+            "date_range": range(&self.issued_from, &self.issued_to),
+            "maturity_date_range": range(&self.maturity_from, &self.maturity_to),
+            "sum_range": sum_range,
+            "role": self.role.as_web(),
+            "currency": self.currency,
+            "status": {
+                "accepted": self.accepted,
+                "paid": self.paid,
+                "in_recourse": self.in_recourse,
+                "waiting_for_payment": self.waiting_for_payment,
+            },
+            "counterparty_node_id": self.counterparty_node_id,
+            "has_attachments": self.has_attachments,
+        })
+    }
+}
+
+/// The sorting of searched and exported bills, the node's default if not set
+#[derive(Args)]
+pub struct SortArgs {
+    #[arg(long, value_enum)]
+    sort_by: Option<SortField>,
+    /// Sort in descending order
+    #[arg(long, requires = "sort_by")]
+    descending: bool,
+}
+
+impl SortArgs {
+    fn to_json(&self) -> Value {
+        match self.sort_by {
+            Some(field) => json!({
+                "field": field.as_web(),
+                "direction": if self.descending { "Descending" } else { "Ascending" },
+            }),
+            None => Value::Null,
+        }
+    }
+}
+
+impl BillCommand {
+    pub async fn run(self, client: &ApiClient) -> Result<Value> {
+        match self {
+            BillCommand::Issue {
+                t,
+                country_of_issuing,
+                city_of_issuing,
+                issue_date,
+                maturity_date,
+                payee,
+                drawee,
+                sum,
+                currency,
+                country_of_payment,
+                city_of_payment,
+                language,
+                files,
+            } => {
+                let file_upload_id = if files.is_empty() {
+                    None
+                } else {
+                    let uploaded = client.upload("/bill/upload_files", "files", &files).await?;
+                    let file_upload_id = uploaded
+                        .get("file_upload_id")
+                        .and_then(|id| id.as_str())
+                        .map(String::from)
+                        .ok_or_else(|| {
+                            Error::InvalidArgument(String::from("Files could not be uploaded"))
+                        })?;
+                    Some(file_upload_id)
+                };
+                client
+                    .post(
+                        "/bill/issue",
+                        &json!({
+                            "type": t.as_web(),
+                            "country_of_issuing": country_of_issuing,
+                            "city_of_issuing": city_of_issuing,
+                            "issue_date": issue_date,
+                            "maturity_date": maturity_date,
+                            "payee": payee,
+                            "drawee": drawee,
+                            "sum": sum,
+                            "currency": currency,
+                            "country_of_payment": country_of_payment,
+                            "city_of_payment": city_of_payment,
+                            "language": language,
+                            "file_upload_id": file_upload_id,
+                        }),
+                    )
+                    .await
+            }
+            BillCommand::List { light } => {
+                if light {
+                    client.get("/bill/list/light").await
+                } else {
+                    client.get("/bill/list").await
+                }
+            }
+            BillCommand::Show { bill_id } => client.get(&format!("/bill/detail/{bill_id}")).await,
+            BillCommand::Endorsements { bill_id } => {
+                client.get(&format!("/bill/endorsements/{bill_id}")).await
+            }
+            BillCommand::Search {
+                filter,
+                sort,
+                cursor,
+                limit,
+            } => {
+                client
+                    .post(
+                        "/bill/search",
+                        &json!({
+                            "filter": filter.to_json(),
+                            "sort": sort.to_json(),
+                            "cursor": cursor,
+                            "limit": limit,
+                        }),
+                    )
+                    .await
+            }
+            BillCommand::Export {
+                output,
+                format,
+                filter,
+                sort,
+            } => {
+                let export = client
+                    .download_with(
+                        "/bill/export",
+                        &json!({
+                            "filter": filter.to_json(),
+                            "sort": sort.to_json(),
+                            "format": format.as_web(),
+                        }),
+                    )
+                    .await?;
+                save_file(&output, &export).await
+            }
+            BillCommand::Pdf { bill_id, output } => {
+                let pdf = client.download(&format!("/bill/pdf/{bill_id}")).await?;
+                save_file(&output, &pdf).await
+            }
+            BillCommand::ChainBundle {
+                bill_id,
+                output,
+                disclosed_block_ids,
+            } => {
+                let disclosed_block_ids = if disclosed_block_ids.is_empty() {
+                    None
+                } else {
+                    Some(disclosed_block_ids)
+                };
+                let bundle = client
+                    .download_with(
+                        &format!("/bill/chain_bundle/{bill_id}"),
+                        &json!({ "disclosed_block_ids": disclosed_block_ids }),
+                    )
+                    .await?;
+                save_file(&output, &bundle).await
+            }
+            BillCommand::CheckPayment => client.get("/bill/check_payment").await,
+            BillCommand::Endorse { bill_id, endorsee } => {
+                client
+                    .put(
+                        "/bill/endorse",
+                        &json!({ "bill_id": bill_id, "endorsee": endorsee }),
+                    )
+                    .await
+            }
+            BillCommand::Accept { bill_id } => {
+                client
+                    .put("/bill/accept", &json!({ "bill_id": bill_id }))
+                    .await
+            }
+            BillCommand::RequestToAccept { bill_id } => {
+                client
+                    .put("/bill/request_to_accept", &json!({ "bill_id": bill_id }))
+                    .await
+            }
+            BillCommand::RequestToPay { bill_id, currency } => {
+                client
+                    .put(
+                        "/bill/request_to_pay",
+                        &json!({ "bill_id": bill_id, "currency": currency }),
+                    )
+                    .await
+            }
+            BillCommand::OfferToSell {
+                bill_id,
+                buyer,
+                sum,
+                currency,
+            } => {
+                client
+                    .put(
+                        "/bill/offer_to_sell",
+                        &json!({
+                            "bill_id": bill_id,
+                            "buyer": buyer,
+                            "sum": sum,
+                            "currency": currency,
+                        }),
+                    )
+                    .await
+            }
+            BillCommand::RequestToMint { bill_id, mint_node } => {
+                client
+                    .put(
+                        "/bill/request_to_mint",
+                        &json!({ "bill_id": bill_id, "mint_node": mint_node }),
+                    )
+                    .await
+            }
+            BillCommand::Mint {
+                bill_id,
+                mint_node,
+                sum,
+                currency,
+            } => {
+                client
+                    .put(
+                        "/bill/mint",
+                        &json!({
+                            "bill_id": bill_id,
+                            "mint_node": mint_node,
+                            "sum": sum,
+                            "currency": currency,
+                        }),
+                    )
+                    .await
+            }
+            BillCommand::Reject { bill_id, action } => {
+                client
+                    .put(action.path(), &json!({ "bill_id": bill_id }))
+                    .await
+            }
+            BillCommand::RequestRecourseForPayment {
+                bill_id,
+                recoursee,
+                sum,
+                currency,
+            } => {
+                client
+                    .put(
+                        "/bill/request_recourse_for_payment",
+                        &json!({
+                            "bill_id": bill_id,
+                            "recoursee": recoursee,
+                            "sum": sum,
+                            "currency": currency,
+                        }),
+                    )
+                    .await
+            }
+            BillCommand::RequestRecourseForAcceptance { bill_id, recoursee } => {
+                client
+                    .put(
+                        "/bill/request_recourse_for_acceptance",
+                        &json!({ "bill_id": bill_id, "recoursee": recoursee }),
+                    )
+                    .await
+            }
+        }
+    }
+}
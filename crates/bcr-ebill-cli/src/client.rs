@@ -0,0 +1,164 @@
+use reqwest::multipart::{Form, Part};
+use reqwest::{Method, RequestBuilder, Response};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Generic result type
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Generic error type
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Errors from sending the request, e.g. if the node is not reachable
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
+
+    /// Errors returned by the node
+    #[error("Node returned {status}: {message}")]
+    Api { status: u16, message: String },
+
+    /// Errors from reading or writing files
+    #[error("Io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Errors from invalid arguments
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+}
+
+/// A thin client for the HTTP API of a running node
+pub struct ApiClient {
+    base_url: String,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: format!("{}/api", base_url.trim_end_matches('/')),
+            http: reqwest::Client::new(),
+            token: None,
+        }
+    }
+
+    /// Sends the given token as bearer token with every request, either an API token, or the
+    /// token of a session
+    pub fn set_token(&mut self, token: &str) {
+        self.token = Some(token.to_owned());
+    }
+
+    /// Logs in with the node's password and uses the session for all further requests
+    pub async fn login(&mut self, password: &str) -> Result<()> {
+        let session = self
+            .post("/auth/login", &serde_json::json!({ "password": password }))
+            .await?;
+        let token = session
+            .get("token")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| Error::Api {
+                status: 200,
+                message: String::from("The login response contains no session token"),
+            })?;
+        self.set_token(token);
+        Ok(())
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .http
+            .request(method, format!("{}{path}", self.base_url));
+        match self.token {
+            Some(ref token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Value> {
+        Self::json(self.request(Method::GET, path)).await
+    }
+
+    pub async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        Self::json(self.request(Method::POST, path).json(body)).await
+    }
+
+    pub async fn put(&self, path: &str, body: &Value) -> Result<Value> {
+        Self::json(self.request(Method::PUT, path).json(body)).await
+    }
+
+    pub async fn delete(&self, path: &str) -> Result<Value> {
+        Self::json(self.request(Method::DELETE, path)).await
+    }
+
+    /// Uploads the given files as multipart form, with every file in the given field
+    pub async fn upload(&self, path: &str, field: &str, files: &[PathBuf]) -> Result<Value> {
+        let mut form = Form::new();
+        for file in files {
+            form = form.part(field.to_owned(), file_part(file).await?);
+        }
+        Self::json(self.request(Method::POST, path).multipart(form)).await
+    }
+
+    /// Fetches a binary file, e.g. a PDF, or a backup
+    pub async fn download(&self, path: &str) -> Result<Vec<u8>> {
+        Self::bytes(self.request(Method::GET, path)).await
+    }
+
+    /// Fetches a binary file, that's created for the given JSON body, e.g. an export
+    pub async fn download_with(&self, path: &str, body: &Value) -> Result<Vec<u8>> {
+        Self::bytes(self.request(Method::POST, path).json(body)).await
+    }
+
+    async fn json(request: RequestBuilder) -> Result<Value> {
+        let response = Self::send(request).await?;
+        let bytes = response.bytes().await?;
+        if bytes.is_empty() {
+            return Ok(Value::Null);
+        }
+        // not every endpoint returns JSON, e.g. the status endpoint
+        Ok(serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())))
+    }
+
+    async fn bytes(request: RequestBuilder) -> Result<Vec<u8>> {
+        let response = Self::send(request).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    async fn send(request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        // API errors are returned as `{ "error": ..., "message": ... }`
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from))
+            .unwrap_or(body);
+        Err(Error::Api {
+            status: status.as_u16(),
+            message,
+        })
+    }
+}
+
+async fn file_part(path: &Path) -> Result<Part> {
+    let bytes = tokio::fs::read(path).await?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| Error::InvalidArgument(format!("{} is not a file", path.display())))?;
+    Ok(Part::bytes(bytes).file_name(file_name))
+}
+
+/// Writes a downloaded file and returns a JSON summary of it, so the output stays scriptable
+pub async fn save_file(path: &Path, bytes: &[u8]) -> Result<Value> {
+    tokio::fs::write(path, bytes).await?;
+    Ok(serde_json::json!({
+        "file": path.display().to_string(),
+        "size": bytes.len(),
+    }))
+}
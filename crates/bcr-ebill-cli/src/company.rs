@@ -0,0 +1,149 @@
+use crate::client::{ApiClient, Result};
+use crate::{AddressArgs, OptionalAddressArgs, with_fields};
+use clap::Subcommand;
+use serde_json::{Value, json};
+
+#[derive(Subcommand)]
+pub enum CompanyCommand {
+    /// List all companies the identity is a signatory of
+    List,
+    /// Show the company with the given id
+    Show { id: String },
+    /// List the signatories of the company with the given id
+    Signatories { id: String },
+    /// Create a new company, with the identity as its first signatory
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[command(flatten)]
+        postal_address: AddressArgs,
+        #[arg(long)]
+        country_of_registration: Option<String>,
+        #[arg(long)]
+        city_of_registration: Option<String>,
+        #[arg(long)]
+        registration_number: Option<String>,
+        #[arg(long)]
+        registration_date: Option<String>,
+    },
+    /// Change the given fields of a company
+    Edit {
+        id: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        email: Option<String>,
+        #[command(flatten)]
+        postal_address: OptionalAddressArgs,
+        #[arg(long)]
+        country_of_registration: Option<String>,
+        #[arg(long)]
+        city_of_registration: Option<String>,
+        #[arg(long)]
+        registration_number: Option<String>,
+        #[arg(long)]
+        registration_date: Option<String>,
+    },
+    /// Add the given node id as signatory of a company
+    AddSignatory {
+        id: String,
+        #[arg(long)]
+        signatory_node_id: String,
+    },
+    /// Remove the given node id as signatory of a company
+    RemoveSignatory {
+        id: String,
+        #[arg(long)]
+        signatory_node_id: String,
+    },
+}
+
+impl CompanyCommand {
+    pub async fn run(self, client: &ApiClient) -> Result<Value> {
+        match self {
+            CompanyCommand::List => client.get("/company/list").await,
+            CompanyCommand::Show { id } => client.get(&format!("/company/{id}")).await,
+            CompanyCommand::Signatories { id } => {
+                client.get(&format!("/company/signatories/{id}")).await
+            }
+            CompanyCommand::Create {
+                name,
+                email,
+                postal_address,
+                country_of_registration,
+                city_of_registration,
+                registration_number,
+                registration_date,
+            } => {
+                client
+                    .post(
+                        "/company/create",
+                        &with_fields(
+                            json!({
+                                "name": name,
+                                "email": email,
+                                "country_of_registration": country_of_registration,
+                                "city_of_registration": city_of_registration,
+                                "registration_number": registration_number,
+                                "registration_date": registration_date,
+                            }),
+                            postal_address.to_json(),
+                        ),
+                    )
+                    .await
+            }
+            CompanyCommand::Edit {
+                id,
+                name,
+                email,
+                postal_address,
+                country_of_registration,
+                city_of_registration,
+                registration_number,
+                registration_date,
+            } => {
+                client
+                    .put(
+                        "/company/edit",
+                        &with_fields(
+                            json!({
+                                "id": id,
+                                "name": name,
+                                "email": email,
+                                "country_of_registration": country_of_registration,
+                                "city_of_registration": city_of_registration,
+                                "registration_number": registration_number,
+                                "registration_date": registration_date,
+                            }),
+                            postal_address.to_json(),
+                        ),
+                    )
+                    .await
+            }
+            CompanyCommand::AddSignatory {
+                id,
+                signatory_node_id,
+            } => {
+                client
+                    .put(
+                        "/company/add_signatory",
+                        &json!({ "id": id, "signatory_node_id": signatory_node_id }),
+                    )
+                    .await
+            }
+            CompanyCommand::RemoveSignatory {
+                id,
+                signatory_node_id,
+            } => {
+                client
+                    .put(
+                        "/company/remove_signatory",
+                        &json!({ "id": id, "signatory_node_id": signatory_node_id }),
+                    )
+                    .await
+            }
+        }
+    }
+}
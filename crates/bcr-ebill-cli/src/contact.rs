@@ -0,0 +1,121 @@
+use crate::client::{ApiClient, Result};
+use crate::{AddressArgs, OptionalAddressArgs, PartyType, with_fields};
+use clap::Subcommand;
+use serde_json::{Value, json};
+
+#[derive(Subcommand)]
+pub enum ContactCommand {
+    /// List all contacts
+    List,
+    /// Show the contact with the given node id
+    Show { node_id: String },
+    /// Create a new contact
+    Create {
+        #[arg(long = "type", value_enum, default_value_t = PartyType::Person)]
+        t: PartyType,
+        #[arg(long)]
+        node_id: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[command(flatten)]
+        postal_address: AddressArgs,
+        #[arg(long)]
+        date_of_birth_or_registration: Option<String>,
+        #[arg(long)]
+        country_of_birth_or_registration: Option<String>,
+        #[arg(long)]
+        city_of_birth_or_registration: Option<String>,
+        #[arg(long)]
+        identification_number: Option<String>,
+    },
+    /// Change the given fields of a contact
+    Edit {
+        node_id: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        email: Option<String>,
+        #[command(flatten)]
+        postal_address: OptionalAddressArgs,
+        #[arg(long)]
+        date_of_birth_or_registration: Option<String>,
+        #[arg(long)]
+        country_of_birth_or_registration: Option<String>,
+        #[arg(long)]
+        city_of_birth_or_registration: Option<String>,
+        #[arg(long)]
+        identification_number: Option<String>,
+    },
+    /// Remove the contact with the given node id
+    Remove { node_id: String },
+}
+
+impl ContactCommand {
+    pub async fn run(self, client: &ApiClient) -> Result<Value> {
+        match self {
+            ContactCommand::List => client.get("/contacts/list").await,
+            ContactCommand::Show { node_id } => {
+                client.get(&format!("/contacts/detail/{node_id}")).await
+            }
+            ContactCommand::Create {
+                t,
+                node_id,
+                name,
+                email,
+                postal_address,
+                date_of_birth_or_registration,
+                country_of_birth_or_registration,
+                city_of_birth_or_registration,
+                identification_number,
+            } => client
+                .post(
+                    "/contacts/create",
+                    &with_fields(
+                        json!({
+                            "type": t.as_web(),
+                            "node_id": node_id,
+                            "name": name,
+                            "email": email,
+                            "date_of_birth_or_registration": date_of_birth_or_registration,
+                            "country_of_birth_or_registration": country_of_birth_or_registration,
+                            "city_of_birth_or_registration": city_of_birth_or_registration,
+                            "identification_number": identification_number,
+                        }),
+                        postal_address.to_json(),
+                    ),
+                )
+                .await,
+            ContactCommand::Edit {
+                node_id,
+                name,
+                email,
+                postal_address,
+                date_of_birth_or_registration,
+                country_of_birth_or_registration,
+                city_of_birth_or_registration,
+                identification_number,
+            } => client
+                .put(
+                    "/contacts/edit",
+                    &with_fields(
+                        json!({
+                            "node_id": node_id,
+                            "name": name,
+                            "email": email,
+                            "date_of_birth_or_registration": date_of_birth_or_registration,
+                            "country_of_birth_or_registration": country_of_birth_or_registration,
+                            "city_of_birth_or_registration": city_of_birth_or_registration,
+                            "identification_number": identification_number,
+                        }),
+                        postal_address.to_json(),
+                    ),
+                )
+                .await,
+            ContactCommand::Remove { node_id } => {
+                client.delete(&format!("/contacts/remove/{node_id}")).await
+            }
+        }
+    }
+}
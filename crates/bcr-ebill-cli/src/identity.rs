@@ -0,0 +1,121 @@
+use crate::client::{ApiClient, Result, save_file};
+use crate::{OptionalAddressArgs, with_fields};
+use clap::Subcommand;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum IdentityCommand {
+    /// Create the identity of this node
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[command(flatten)]
+        postal_address: OptionalAddressArgs,
+        #[arg(long)]
+        date_of_birth: Option<String>,
+        #[arg(long)]
+        country_of_birth: Option<String>,
+        #[arg(long)]
+        city_of_birth: Option<String>,
+        #[arg(long)]
+        identification_number: Option<String>,
+    },
+    /// Show the identity of this node
+    Show,
+    /// Show the currently active identity, which is either the personal one, or a company
+    Active,
+    /// Switch the active identity to the given personal or company node id
+    Switch {
+        node_id: String,
+        /// Set, if the node id is a company
+        #[arg(long)]
+        company: bool,
+    },
+    /// Print the seed phrase of the identity
+    Seed,
+    /// Recover the identity from its seed phrase
+    Recover {
+        #[arg(long, env = "BCR_EBILL_SEED_PHRASE", hide_env_values = true)]
+        seed_phrase: String,
+    },
+}
+
+impl IdentityCommand {
+    pub async fn run(self, client: &ApiClient) -> Result<Value> {
+        match self {
+            IdentityCommand::Create {
+                name,
+                email,
+                postal_address,
+                date_of_birth,
+                country_of_birth,
+                city_of_birth,
+                identification_number,
+            } => {
+                client
+                    .post(
+                        "/identity/create",
+                        &with_fields(
+                            json!({
+                                "name": name,
+                                "email": email,
+                                "date_of_birth": date_of_birth,
+                                "country_of_birth": country_of_birth,
+                                "city_of_birth": city_of_birth,
+                                "identification_number": identification_number,
+                            }),
+                            postal_address.to_json(),
+                        ),
+                    )
+                    .await
+            }
+            IdentityCommand::Show => client.get("/identity/detail").await,
+            IdentityCommand::Active => client.get("/identity/active").await,
+            IdentityCommand::Switch { node_id, company } => {
+                client
+                    .put(
+                        "/identity/switch",
+                        &json!({
+                            "type": if company { 1 } else { 0 },
+                            "node_id": node_id,
+                        }),
+                    )
+                    .await
+            }
+            IdentityCommand::Seed => client.get("/identity/seed/backup").await,
+            IdentityCommand::Recover { seed_phrase } => {
+                client
+                    .put(
+                        "/identity/seed/recover",
+                        &json!({ "seed_phrase": seed_phrase }),
+                    )
+                    .await
+            }
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommand {
+    /// Write an encrypted backup of the node's data to the given file
+    Create { output: PathBuf },
+    /// Restore the node's data from an encrypted backup, the node restarts afterwards
+    Restore { file: PathBuf },
+}
+
+impl BackupCommand {
+    pub async fn run(self, client: &ApiClient) -> Result<Value> {
+        match self {
+            BackupCommand::Create { output } => {
+                let backup = client.download("/identity/backup").await?;
+                save_file(&output, &backup).await
+            }
+            BackupCommand::Restore { file } => {
+                client.upload("/identity/restore", "file", &[file]).await
+            }
+        }
+    }
+}
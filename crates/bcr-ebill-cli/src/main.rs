@@ -0,0 +1,266 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use client::{ApiClient, Result};
+use serde_json::{Value, json};
+use std::process::ExitCode;
+
+mod bill;
+mod client;
+mod company;
+mod contact;
+mod identity;
+
+/// Command-line client for a running E-Bills node, using its HTTP API. Every command prints the
+/// node's response as JSON, so it can be used in scripts
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// The URL of the node's HTTP API
+    #[arg(default_value_t = String::from("http://127.0.0.1:8000"), long, env = "BCR_EBILL_URL")]
+    url: String,
+    /// The node's password, if it requires a login
+    #[arg(long, env = "BCR_EBILL_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
+    /// An API token to use instead of the password
+    #[arg(
+        long,
+        env = "BCR_EBILL_TOKEN",
+        hide_env_values = true,
+        conflicts_with = "password"
+    )]
+    token: Option<String>,
+    /// Print the JSON output on a single line
+    #[arg(long)]
+    compact: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the status of the node
+    Status,
+    /// Create, recover and switch identities
+    #[command(subcommand)]
+    Identity(identity::IdentityCommand),
+    /// Manage contacts
+    #[command(subcommand)]
+    Contact(contact::ContactCommand),
+    /// Manage companies and their signatories
+    #[command(subcommand)]
+    Company(company::CompanyCommand),
+    /// Issue, list, search and act on bills
+    #[command(subcommand)]
+    Bill(bill::BillCommand),
+    /// Search bills, contacts and companies
+    Search {
+        search_term: String,
+        #[arg(default_value_t = String::from("sat"), long)]
+        currency: String,
+        /// The types of items to search, all if not set
+        #[arg(long = "type", value_enum)]
+        item_types: Vec<SearchItemType>,
+    },
+    /// Back up and restore the node
+    #[command(subcommand)]
+    Backup(identity::BackupCommand),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SearchItemType {
+    Bill,
+    Contact,
+    Company,
+}
+
+impl SearchItemType {
+    fn as_web(&self) -> &'static str {
+        match self {
+            SearchItemType::Bill => "Bill",
+            SearchItemType::Contact => "Contact",
+            SearchItemType::Company => "Company",
+        }
+    }
+}
+
+/// A postal address, of which every part is optional, e.g. when editing
+#[derive(Args)]
+pub struct OptionalAddressArgs {
+    #[arg(long)]
+    pub country: Option<String>,
+    #[arg(long)]
+    pub city: Option<String>,
+    #[arg(long)]
+    pub zip: Option<String>,
+    #[arg(long)]
+    pub address: Option<String>,
+}
+
+impl OptionalAddressArgs {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "country": self.country,
+            "city": self.city,
+            "zip": self.zip,
+            "address": self.address,
+        })
+    }
+}
+
+/// A postal address, which is mandatory, e.g. for contacts and companies
+#[derive(Args)]
+pub struct AddressArgs {
+    #[arg(long)]
+    pub country: String,
+    #[arg(long)]
+    pub city: String,
+    #[arg(long)]
+    pub zip: Option<String>,
+    #[arg(long)]
+    pub address: String,
+}
+
+impl AddressArgs {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "country": self.country,
+            "city": self.city,
+            "zip": self.zip,
+            "address": self.address,
+        })
+    }
+}
+
+/// Adds the fields of the given JSON object to the payload, e.g. for flattened postal addresses
+pub fn with_fields(mut payload: Value, fields: Value) -> Value {
+    if let (Some(payload_fields), Value::Object(fields)) = (payload.as_object_mut(), fields) {
+        payload_fields.extend(fields);
+    }
+    payload
+}
+
+/// Whether a party is a person, or a company
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PartyType {
+    Person,
+    Company,
+}
+
+impl PartyType {
+    pub fn as_web(&self) -> u64 {
+        match self {
+            PartyType::Person => 0,
+            PartyType::Company => 1,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut client = ApiClient::new(&cli.url);
+    if let Some(ref token) = cli.token {
+        client.set_token(token);
+    } else if let Some(ref password) = cli.password {
+        if let Err(e) = client.login(password).await {
+            eprintln!("Could not log in: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    match run(cli.command, &client).await {
+        Ok(output) => {
+            let printed = if cli.compact {
+                serde_json::to_string(&output)
+            } else {
+                serde_json::to_string_pretty(&output)
+            };
+            match printed {
+                Ok(printed) => {
+                    println!("{printed}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => {
+                    eprintln!("Could not print the output: {e}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command, client: &ApiClient) -> Result<Value> {
+    match command {
+        Command::Status => client.get("/status").await,
+        Command::Identity(command) => command.run(client).await,
+        Command::Contact(command) => command.run(client).await,
+        Command::Company(command) => command.run(client).await,
+        Command::Bill(command) => command.run(client).await,
+        Command::Search {
+            search_term,
+            currency,
+            item_types,
+        } => {
+            let item_types: Vec<&str> = if item_types.is_empty() {
+                vec!["Bill", "Contact", "Company"]
+            } else {
+                item_types.iter().map(|t| t.as_web()).collect()
+            };
+            client
+                .post(
+                    "/search",
+                    &json!({
+                        "filter": {
+                            "search_term": search_term,
+                            "currency": currency,
+                            "item_types": item_types,
+                        }
+                    }),
+                )
+                .await
+        }
+        Command::Backup(command) => command.run(client).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn adds_flattened_fields() {
+        let payload = with_fields(
+            json!({ "name": "name" }),
+            json!({ "country": "AT", "city": "Vienna" }),
+        );
+        assert_eq!(
+            payload,
+            json!({ "name": "name", "country": "AT", "city": "Vienna" })
+        );
+    }
+
+    #[test]
+    fn parses_nested_commands() {
+        let cli = Cli::try_parse_from([
+            "bcr-ebill-cli",
+            "--url",
+            "http://localhost:8001",
+            "bill",
+            "endorse",
+            "some_bill",
+            "--endorsee",
+            "some_node",
+        ])
+        .unwrap();
+        assert_eq!(cli.url, "http://localhost:8001");
+        assert!(matches!(cli.command, Command::Bill(_)));
+    }
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The prefix of API token secrets, so they can be told apart from session tokens
+pub const API_TOKEN_PREFIX: &str = "bcr_";
+
+/// The permissions an API token can be given, write scopes include reading the same data and
+/// admin includes all scopes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApiTokenScope {
+    #[serde(rename = "bills:read")]
+    BillsRead,
+    #[serde(rename = "bills:write")]
+    BillsWrite,
+    #[serde(rename = "contacts:read")]
+    ContactsRead,
+    #[serde(rename = "contacts:write")]
+    ContactsWrite,
+    #[serde(rename = "companies:read")]
+    CompaniesRead,
+    #[serde(rename = "companies:write")]
+    CompaniesWrite,
+    #[serde(rename = "identity:read")]
+    IdentityRead,
+    #[serde(rename = "notifications:read")]
+    NotificationsRead,
+    #[serde(rename = "notifications:write")]
+    NotificationsWrite,
+    /// Access to everything, except the endpoints which require a re-authenticated session, like
+    /// the seed phrase, private keys, backups and API tokens
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl ApiTokenScope {
+    pub const ALL: [ApiTokenScope; 10] = [
+        ApiTokenScope::BillsRead,
+        ApiTokenScope::BillsWrite,
+        ApiTokenScope::ContactsRead,
+        ApiTokenScope::ContactsWrite,
+        ApiTokenScope::CompaniesRead,
+        ApiTokenScope::CompaniesWrite,
+        ApiTokenScope::IdentityRead,
+        ApiTokenScope::NotificationsRead,
+        ApiTokenScope::NotificationsWrite,
+        ApiTokenScope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiTokenScope::BillsRead => "bills:read",
+            ApiTokenScope::BillsWrite => "bills:write",
+            ApiTokenScope::ContactsRead => "contacts:read",
+            ApiTokenScope::ContactsWrite => "contacts:write",
+            ApiTokenScope::CompaniesRead => "companies:read",
+            ApiTokenScope::CompaniesWrite => "companies:write",
+            ApiTokenScope::IdentityRead => "identity:read",
+            ApiTokenScope::NotificationsRead => "notifications:read",
+            ApiTokenScope::NotificationsWrite => "notifications:write",
+            ApiTokenScope::Admin => "admin",
+        }
+    }
+
+    /// Returns true, if a token with this scope is allowed to do what the given scope allows
+    pub fn includes(&self, scope: ApiTokenScope) -> bool {
+        if *self == scope || *self == ApiTokenScope::Admin {
+            return true;
+        }
+        matches!(
+            (self, scope),
+            (ApiTokenScope::BillsWrite, ApiTokenScope::BillsRead)
+                | (ApiTokenScope::ContactsWrite, ApiTokenScope::ContactsRead)
+                | (ApiTokenScope::CompaniesWrite, ApiTokenScope::CompaniesRead)
+                | (
+                    ApiTokenScope::NotificationsWrite,
+                    ApiTokenScope::NotificationsRead
+                )
+        )
+    }
+}
+
+impl fmt::Display for ApiTokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ApiTokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ApiTokenScope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("Unknown API token scope: {s}"))
+    }
+}
+
+/// A named token for machine-to-machine access to the HTTP API. Only the hash of its secret is
+/// stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+}
+
+impl ApiToken {
+    pub fn has_scope(&self, scope: ApiTokenScope) -> bool {
+        self.scopes.iter().any(|s| s.includes(scope))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_string_roundtrip() {
+        for scope in ApiTokenScope::ALL {
+            assert_eq!(ApiTokenScope::from_str(scope.as_str()), Ok(scope));
+            assert_eq!(
+                serde_json::to_string(&scope).unwrap(),
+                format!("\"{}\"", scope.as_str())
+            );
+        }
+        assert!(ApiTokenScope::from_str("bills:delete").is_err());
+    }
+
+    #[test]
+    fn has_scope() {
+        let token = ApiToken {
+            id: "id".to_string(),
+            name: "erp".to_string(),
+            scopes: vec![ApiTokenScope::BillsWrite, ApiTokenScope::ContactsRead],
+            created_at: 1731593928,
+            last_used_at: None,
+        };
+        assert!(token.has_scope(ApiTokenScope::BillsWrite));
+        assert!(token.has_scope(ApiTokenScope::BillsRead));
+        assert!(token.has_scope(ApiTokenScope::ContactsRead));
+        assert!(!token.has_scope(ApiTokenScope::ContactsWrite));
+        assert!(!token.has_scope(ApiTokenScope::Admin));
+
+        let admin = ApiToken {
+            scopes: vec![ApiTokenScope::Admin],
+            ..token
+        };
+        assert!(
+            ApiTokenScope::ALL
+                .into_iter()
+                .all(|scope| admin.has_scope(scope))
+        );
+    }
+}
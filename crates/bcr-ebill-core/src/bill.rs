@@ -3,7 +3,7 @@ use super::{
     contact::{IdentityPublicData, LightIdentityPublicData, LightIdentityPublicDataWithAddress},
     notification::Notification,
 };
-use crate::util::date::date_string_to_i64_timestamp;
+use crate::util::{base58_decode, base58_encode, date::date_string_to_i64_timestamp};
 use borsh_derive::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
@@ -85,69 +85,6 @@ pub struct BitcreditBillResult {
     pub endorsements_count: u64,
 }
 
-impl BitcreditBillResult {
-    /// Returns the role of the given node_id in the bill, or None if the node_id is not a
-    /// participant in the bill
-    pub fn get_bill_role_for_node_id(&self, node_id: &str) -> Option<BillRole> {
-        // Node id is not part of the bill
-        if !self.bill_participants.iter().any(|bp| bp == node_id) {
-            return None;
-        }
-
-        // Node id is the payer
-        if self.drawee.node_id == *node_id {
-            return Some(BillRole::Payer);
-        }
-
-        // Node id is payee / endorsee
-        if self.payee.node_id == *node_id
-            || self.endorsee.as_ref().map(|e| e.node_id.as_str()) == Some(node_id)
-        {
-            return Some(BillRole::Payee);
-        }
-
-        // Node id is part of the bill, but neither payer, nor payee - they are part of the risk
-        // chain
-        Some(BillRole::Contingent)
-    }
-
-    // Search in the participants for the search term
-    pub fn search_bill_for_search_term(&self, search_term: &str) -> bool {
-        let search_term_lc = search_term.to_lowercase();
-        if self.payee.name.to_lowercase().contains(&search_term_lc) {
-            return true;
-        }
-
-        if self.drawer.name.to_lowercase().contains(&search_term_lc) {
-            return true;
-        }
-
-        if self.drawee.name.to_lowercase().contains(&search_term_lc) {
-            return true;
-        }
-
-        if let Some(ref endorsee) = self.endorsee {
-            if endorsee.name.to_lowercase().contains(&search_term_lc) {
-                return true;
-            }
-        }
-
-        if let Some(ref buyer) = self.buyer {
-            if buyer.name.to_lowercase().contains(&search_term_lc) {
-                return true;
-            }
-        }
-
-        if let Some(ref seller) = self.seller {
-            if seller.name.to_lowercase().contains(&search_term_lc) {
-                return true;
-            }
-        }
-
-        false
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct LightBitcreditBillResult {
     pub id: String,
@@ -194,7 +131,7 @@ pub struct BillsBalance {
     pub sum: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BillRole {
     Payee,
     Payer,
@@ -206,14 +143,368 @@ pub struct BillCombinedBitcoinKey {
     pub private_key: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum BillsFilterRole {
+    #[default]
     All,
     Payer,
     Payee,
     Contingent,
 }
 
+impl BillsFilterRole {
+    /// The role to filter for, `None` for all roles
+    pub fn bill_role(&self) -> Option<BillRole> {
+        match self {
+            BillsFilterRole::All => None,
+            BillsFilterRole::Payer => Some(BillRole::Payer),
+            BillsFilterRole::Payee => Some(BillRole::Payee),
+            BillsFilterRole::Contingent => Some(BillRole::Contingent),
+        }
+    }
+}
+
+/// The materialised state of a bill from the perspective of one local identity (personal or
+/// company). It's updated whenever a block is added, so bills can be listed and searched
+/// without decrypting and replaying their chains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillState {
+    pub bill_id: String,
+    /// The local identity this state was computed for
+    pub identity_node_id: String,
+    pub role: BillRole,
+    pub drawee: LightIdentityPublicData,
+    pub drawer: LightIdentityPublicData,
+    pub payee: LightIdentityPublicData,
+    pub endorsee: Option<LightIdentityPublicData>,
+    /// The node id of the current holder of the bill
+    pub holder_node_id: String,
+    /// Buyer of a pending offer to sell
+    pub buyer: Option<LightIdentityPublicData>,
+    /// Seller of a pending offer to sell
+    pub seller: Option<LightIdentityPublicData>,
+    pub sum: u64,
+    pub currency: String,
+    pub issue_date: String,
+    /// The issue date as a timestamp, if it could be parsed
+    pub issue_timestamp: Option<u64>,
+    pub time_of_drawing: u64,
+    pub time_of_maturity: u64,
+    pub accepted: bool,
+    pub paid: bool,
+    /// Until when the bill is waiting for the payment of an offer to sell
+    pub waiting_for_payment_until: Option<u64>,
+    /// Until when the bill is waiting for the payment of a request to recourse
+    pub in_recourse_until: Option<u64>,
+    pub has_attachments: bool,
+    /// The node ids of all parties, which ever took part in the bill
+    pub participants: Vec<String>,
+    /// The height of the chain this state was computed from
+    pub block_height: u64,
+}
+
+impl BillState {
+    /// Checks if the bill is waiting for a sell payment at the given timestamp
+    pub fn is_waiting_for_payment(&self, timestamp: u64) -> bool {
+        self.waiting_for_payment_until
+            .is_some_and(|until| timestamp <= until)
+    }
+
+    /// Checks if the bill is waiting for a recourse payment at the given timestamp
+    pub fn is_in_recourse(&self, timestamp: u64) -> bool {
+        self.in_recourse_until
+            .is_some_and(|until| timestamp <= until)
+    }
+
+    /// The lowercased names of all parties of the bill, one per line, used for searching
+    pub fn search_text(&self) -> String {
+        [
+            Some(&self.payee),
+            Some(&self.drawer),
+            Some(&self.drawee),
+            self.endorsee.as_ref(),
+            self.buyer.as_ref(),
+            self.seller.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|p| p.name.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("\n")
+    }
+}
+
+/// The key of a materialised bill state - the bill and the local identity it was computed for,
+/// together with the participants of the bill at that point
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BillStateKey {
+    pub bill_id: String,
+    pub identity_node_id: String,
+    pub participants: Vec<String>,
+}
+
+/// Filter on the status of bills - each flag is ignored if not set, otherwise only bills with,
+/// or without the given status are returned
+#[derive(Debug, Clone, Default)]
+pub struct BillStatusFilter {
+    pub accepted: Option<bool>,
+    pub paid: Option<bool>,
+    pub in_recourse: Option<bool>,
+    pub waiting_for_payment: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BillSortField {
+    #[default]
+    TimeOfDrawing,
+    IssueDate,
+    MaturityDate,
+    Sum,
+}
+
+impl BillSortField {
+    /// The value of the given state, bills are sorted by
+    pub fn value_of(&self, state: &BillState) -> u64 {
+        match self {
+            BillSortField::TimeOfDrawing => state.time_of_drawing,
+            BillSortField::IssueDate => state.issue_timestamp.unwrap_or(0),
+            BillSortField::MaturityDate => state.time_of_maturity,
+            BillSortField::Sum => state.sum,
+        }
+    }
+
+    /// The stable name, which is used in search cursors
+    pub fn name(&self) -> &'static str {
+        match self {
+            BillSortField::TimeOfDrawing => "time_of_drawing",
+            BillSortField::IssueDate => "issue_date",
+            BillSortField::MaturityDate => "maturity_date",
+            BillSortField::Sum => "sum",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        match value {
+            "time_of_drawing" => Some(BillSortField::TimeOfDrawing),
+            "issue_date" => Some(BillSortField::IssueDate),
+            "maturity_date" => Some(BillSortField::MaturityDate),
+            "sum" => Some(BillSortField::Sum),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    #[default]
+    Descending,
+}
+
+impl SortDirection {
+    /// The stable name, which is used in search cursors
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "asc",
+            SortDirection::Descending => "desc",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        match value {
+            "asc" => Some(SortDirection::Ascending),
+            "desc" => Some(SortDirection::Descending),
+            _ => None,
+        }
+    }
+}
+
+/// The position after which the next page of a bill search starts - the sort value and the id
+/// of the last returned bill, encoded as an opaque string for clients. It also contains the sort
+/// order it was created for, since it's only valid for the same sort order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BillSearchCursor {
+    pub sort: BillSortField,
+    pub direction: SortDirection,
+    pub sort_value: u64,
+    pub bill_id: String,
+}
+
+impl BillSearchCursor {
+    pub fn for_state(state: &BillState, sort: BillSortField, direction: SortDirection) -> Self {
+        Self {
+            sort,
+            direction,
+            sort_value: sort.value_of(state),
+            bill_id: state.bill_id.clone(),
+        }
+    }
+
+    /// Checks if the cursor was created for the given sort order
+    pub fn is_for(&self, sort: BillSortField, direction: SortDirection) -> bool {
+        self.sort == sort && self.direction == direction
+    }
+
+    pub fn encode(&self) -> String {
+        base58_encode(
+            format!(
+                "{}:{}:{}:{}",
+                self.sort.name(),
+                self.direction.name(),
+                self.sort_value,
+                self.bill_id
+            )
+            .as_bytes(),
+        )
+    }
+
+    /// Decodes the given cursor, returning `None` if it's invalid
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let decoded = String::from_utf8(base58_decode(cursor).ok()?).ok()?;
+        let mut parts = decoded.splitn(4, ':');
+        let sort = BillSortField::from_name(parts.next()?)?;
+        let direction = SortDirection::from_name(parts.next()?)?;
+        let sort_value = parts.next()?.parse().ok()?;
+        let bill_id = parts.next()?;
+        if bill_id.is_empty() {
+            return None;
+        }
+        Some(Self {
+            sort,
+            direction,
+            sort_value,
+            bill_id: bill_id.to_owned(),
+        })
+    }
+}
+
+/// A structured query for bills - all criteria are combined, unset criteria are ignored
+#[derive(Debug, Clone, Default)]
+pub struct BillSearchQuery {
+    /// Case-insensitive search term for the names of the parties
+    pub search_term: Option<String>,
+    pub role: BillsFilterRole,
+    /// Only bills issued at, or after the given timestamp
+    pub issued_from: Option<u64>,
+    /// Only bills issued at, or before the given timestamp
+    pub issued_to: Option<u64>,
+    /// Only bills maturing at, or after the given timestamp
+    pub maturity_from: Option<u64>,
+    /// Only bills maturing at, or before the given timestamp
+    pub maturity_to: Option<u64>,
+    /// Only bills with a sum of at least the given amount
+    pub sum_from: Option<u64>,
+    /// Only bills with a sum of at most the given amount
+    pub sum_to: Option<u64>,
+    pub currency: Option<String>,
+    pub status: BillStatusFilter,
+    /// Only bills the given node id is, or was a party of
+    pub counterparty_node_id: Option<String>,
+    pub has_attachments: Option<bool>,
+    pub sort: BillSortField,
+    pub direction: SortDirection,
+    /// Only bills after the given position in the sort order
+    pub cursor: Option<BillSearchCursor>,
+    /// The maximum number of bills to return, all if not set
+    pub limit: Option<usize>,
+}
+
+/// A page of bills, with the cursor to fetch the next page, if there is one
+#[derive(Debug, Clone)]
+pub struct BillSearchResult {
+    pub bills: Vec<LightBitcreditBillResult>,
+    pub next_cursor: Option<String>,
+}
+
+/// The file formats bills can be exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BillExportFormat {
+    Csv,
+    JsonLines,
+    Xlsx,
+}
+
+impl BillExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            BillExportFormat::Csv => "text/csv",
+            BillExportFormat::JsonLines => "application/jsonl",
+            BillExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            BillExportFormat::Csv => "csv",
+            BillExportFormat::JsonLines => "jsonl",
+            BillExportFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// A newly issued bill, together with warnings about its counterparties, e.g. if they aren't
+/// verified contacts
+#[derive(Debug, Clone)]
+pub struct IssuedBill {
+    pub bill: BitcreditBill,
+    pub warnings: Vec<String>,
+}
+
+/// The outcome of issuing a batch of bills from a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BillBatchResult {
+    pub dry_run: bool,
+    /// Whether the bills of all rows were issued
+    pub issued: bool,
+    pub rows: Vec<BillBatchRowResult>,
+}
+
+/// The outcome of a single row of a batch of bills
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BillBatchRowResult {
+    /// The number of the row, starting with 1 for the first bill
+    pub row: usize,
+    /// The id of the issued bill, if it was issued
+    pub bill_id: Option<String>,
+    /// The reasons the row can't be issued
+    pub errors: Vec<String>,
+    /// Warnings about the counterparties, e.g. if they aren't verified contacts
+    pub warnings: Vec<String>,
+}
+
+/// A bill, which was issued for a row of a batch, so the row isn't issued again, if the batch
+/// is issued again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillBatchIssue {
+    pub batch_id: String,
+    /// The hash of the fields of the row, so the row is recognized in a corrected file, even
+    /// if other rows were added, removed or reordered
+    pub row_hash: String,
+    pub bill_id: String,
+    pub issued_at: u64,
+}
+
+/// Filter for the materialised bill states of a local identity
+#[derive(Debug, Clone, Default)]
+pub struct BillStateFilter {
+    pub identity_node_id: String,
+    /// The timestamp the waiting for payment and recourse states are evaluated at
+    pub timestamp: u64,
+    pub query: BillSearchQuery,
+}
+
+impl BillStateFilter {
+    /// A filter for all bill states of the given identity
+    pub fn for_identity(identity_node_id: &str) -> Self {
+        Self {
+            identity_node_id: identity_node_id.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PastEndorsee {
     pub pay_to_the_order_of: LightIdentityPublicData,
@@ -235,3 +526,41 @@ pub struct LightSignedBy {
     pub data: LightIdentityPublicData,
     pub signatory: Option<LightIdentityPublicData>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_cursor_roundtrip() {
+        let cursor = BillSearchCursor {
+            sort: BillSortField::MaturityDate,
+            direction: SortDirection::Ascending,
+            sort_value: 1731593928,
+            bill_id: "some:bill".to_string(),
+        };
+        assert_eq!(BillSearchCursor::decode(&cursor.encode()), Some(cursor));
+    }
+
+    #[test]
+    fn search_cursor_decode_invalid() {
+        assert_eq!(BillSearchCursor::decode("not base58!"), None);
+        assert_eq!(
+            BillSearchCursor::decode(&base58_encode(b"sum:asc:abc:bill")),
+            None
+        );
+        assert_eq!(
+            BillSearchCursor::decode(&base58_encode(b"sum:asc:12:")),
+            None
+        );
+        assert_eq!(
+            BillSearchCursor::decode(&base58_encode(b"name:asc:12:bill")),
+            None
+        );
+        assert_eq!(
+            BillSearchCursor::decode(&base58_encode(b"sum:up:12:bill")),
+            None
+        );
+        assert_eq!(BillSearchCursor::decode(&base58_encode(b"12:bill")), None);
+    }
+}
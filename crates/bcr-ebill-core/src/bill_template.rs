@@ -0,0 +1,191 @@
+use crate::util::date::DEFAULT_DATE_FORMAT;
+use chrono::{Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Stored details of a bill, which is issued repeatedly to the same counterparties, either on
+/// demand or on a recurring schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillTemplate {
+    pub id: String,
+    pub name: String,
+    /// The node id of the identity or company, which issues the bills
+    pub drawer_node_id: String,
+    /// The drawee, which is the drawer for promissory notes
+    pub drawee_node_id: String,
+    /// The payee, which is the drawer for self-drafted bills
+    pub payee_node_id: String,
+    pub sum: u64,
+    pub currency: String,
+    pub country_of_issuing: String,
+    pub city_of_issuing: String,
+    pub country_of_payment: String,
+    pub city_of_payment: String,
+    pub language: String,
+    /// The days between the issue date and the maturity date of the bills
+    pub term_days: u32,
+    /// Bills are only issued automatically, if the template has a schedule
+    pub schedule: Option<BillTemplateSchedule>,
+    pub created_at: u64,
+}
+
+impl BillTemplate {
+    /// Returns the maturity date of a bill issued at the given date, or None, if the date is
+    /// invalid
+    pub fn maturity_date(&self, issue_date: &str) -> Option<String> {
+        let maturity_date =
+            parse_date(issue_date)?.checked_add_days(Days::new(self.term_days as u64))?;
+        Some(maturity_date.format(DEFAULT_DATE_FORMAT).to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BillTemplateInterval {
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+/// The schedule bills are issued from a template with by the job runner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillTemplateSchedule {
+    pub interval: BillTemplateInterval,
+    /// The date the first bill is issued at, the following ones are issued at the same day of
+    /// the following intervals
+    pub start_date: String,
+    /// No bills are issued after this date
+    pub end_date: Option<String>,
+    /// How many occurrences of the schedule are done, including skipped ones
+    pub occurrences_done: u32,
+}
+
+impl BillTemplateSchedule {
+    /// Returns the date of the given occurrence, starting with 0 for the start date. Monthly
+    /// occurrences keep the day of the start date and use the last day of shorter months
+    pub fn occurrence_date(&self, occurrence: u32) -> Option<NaiveDate> {
+        let start_date = parse_date(&self.start_date)?;
+        match self.interval {
+            BillTemplateInterval::Weekly => {
+                start_date.checked_add_days(Days::new(7 * occurrence as u64))
+            }
+            BillTemplateInterval::Monthly => start_date.checked_add_months(Months::new(occurrence)),
+            BillTemplateInterval::Quarterly => {
+                start_date.checked_add_months(Months::new(3 * occurrence))
+            }
+            BillTemplateInterval::Yearly => {
+                start_date.checked_add_months(Months::new(12 * occurrence))
+            }
+        }
+    }
+
+    /// Returns the date the next bill is issued at, or None, if the schedule ended
+    pub fn next_issue_date(&self) -> Option<String> {
+        self.within_end_date(self.occurrence_date(self.occurrences_done)?)
+            .map(|date| date.format(DEFAULT_DATE_FORMAT).to_string())
+    }
+
+    /// Returns the latest occurrence, which is not done and due at the given date. Older
+    /// occurrences, which were missed, e.g. since the node was offline, are skipped, so only a
+    /// single bill is issued for them
+    pub fn due_occurrence(&self, date: &str) -> Option<u32> {
+        let date = parse_date(date)?;
+        let mut due = None;
+        let mut occurrence = self.occurrences_done;
+        while let Some(occurrence_date) = self.occurrence_date(occurrence) {
+            if occurrence_date > date || self.within_end_date(occurrence_date).is_none() {
+                break;
+            }
+            due = Some(occurrence);
+            occurrence += 1;
+        }
+        due
+    }
+
+    fn within_end_date(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self.end_date.as_deref().and_then(parse_date) {
+            Some(end_date) if date > end_date => None,
+            _ => Some(date),
+        }
+    }
+}
+
+/// A bill, which was issued from a template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillTemplateIssue {
+    pub template_id: String,
+    pub bill_id: String,
+    /// The occurrence of the schedule the bill was issued for, None if it was issued on demand
+    pub occurrence: Option<u32>,
+    pub issued_at: u64,
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, DEFAULT_DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_schedule(interval: BillTemplateInterval, start_date: &str) -> BillTemplateSchedule {
+        BillTemplateSchedule {
+            interval,
+            start_date: start_date.to_owned(),
+            end_date: None,
+            occurrences_done: 0,
+        }
+    }
+
+    #[test]
+    fn monthly_schedule_keeps_the_day_of_the_start_date() {
+        let mut schedule = get_schedule(BillTemplateInterval::Monthly, "2025-01-31");
+        assert_eq!(schedule.next_issue_date(), Some("2025-01-31".to_owned()));
+        schedule.occurrences_done = 1;
+        assert_eq!(schedule.next_issue_date(), Some("2025-02-28".to_owned()));
+        schedule.occurrences_done = 2;
+        assert_eq!(schedule.next_issue_date(), Some("2025-03-31".to_owned()));
+
+        schedule.end_date = Some("2025-03-30".to_owned());
+        assert_eq!(schedule.next_issue_date(), None);
+    }
+
+    #[test]
+    fn due_occurrence_skips_missed_ones() {
+        let mut schedule = get_schedule(BillTemplateInterval::Weekly, "2025-01-01");
+        assert_eq!(schedule.due_occurrence("2024-12-31"), None);
+        assert_eq!(schedule.due_occurrence("2025-01-01"), Some(0));
+        assert_eq!(schedule.due_occurrence("2025-01-20"), Some(2));
+
+        schedule.occurrences_done = 3;
+        assert_eq!(schedule.due_occurrence("2025-01-20"), None);
+
+        schedule.end_date = Some("2025-01-21".to_owned());
+        assert_eq!(schedule.due_occurrence("2025-02-20"), None);
+    }
+
+    #[test]
+    fn maturity_date_adds_the_term() {
+        let template = BillTemplate {
+            id: "template".to_owned(),
+            name: "Rent".to_owned(),
+            drawer_node_id: "drawer".to_owned(),
+            drawee_node_id: "drawee".to_owned(),
+            payee_node_id: "drawer".to_owned(),
+            sum: 500,
+            currency: "sat".to_owned(),
+            country_of_issuing: "AT".to_owned(),
+            city_of_issuing: "Vienna".to_owned(),
+            country_of_payment: "AT".to_owned(),
+            city_of_payment: "Vienna".to_owned(),
+            language: "en-UK".to_owned(),
+            term_days: 30,
+            schedule: None,
+            created_at: 1731593928,
+        };
+        assert_eq!(
+            template.maturity_date("2025-02-15"),
+            Some("2025-03-17".to_owned())
+        );
+        assert_eq!(template.maturity_date("15.02.2025"), None);
+    }
+}
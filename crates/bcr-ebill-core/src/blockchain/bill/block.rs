@@ -45,7 +45,7 @@ pub struct BillBlockDataToHash {
     op_code: BillOpCode,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillRejectBlockData {
     pub rejecter: BillIdentityBlockData,
     pub signatory: Option<BillSignatoryBlockData>,
@@ -53,7 +53,7 @@ pub struct BillRejectBlockData {
     pub signing_address: PostalAddress,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillIssueBlockData {
     pub id: String,
     pub country_of_issuing: String,
@@ -103,7 +103,7 @@ impl BillIssueBlockData {
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillAcceptBlockData {
     pub accepter: BillIdentityBlockData,
     pub signatory: Option<BillSignatoryBlockData>,
@@ -111,7 +111,7 @@ pub struct BillAcceptBlockData {
     pub signing_address: PostalAddress, // address of the accepter
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillRequestToPayBlockData {
     pub requester: BillIdentityBlockData,
     pub currency: String,
@@ -120,7 +120,7 @@ pub struct BillRequestToPayBlockData {
     pub signing_address: PostalAddress, // address of the requester
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillRequestToAcceptBlockData {
     pub requester: BillIdentityBlockData,
     pub signatory: Option<BillSignatoryBlockData>,
@@ -128,7 +128,7 @@ pub struct BillRequestToAcceptBlockData {
     pub signing_address: PostalAddress, // address of the requester
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillMintBlockData {
     pub endorser: BillIdentityBlockData,
     pub endorsee: BillIdentityBlockData,
@@ -139,7 +139,7 @@ pub struct BillMintBlockData {
     pub signing_address: PostalAddress, // address of the endorser
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillOfferToSellBlockData {
     pub seller: BillIdentityBlockData,
     pub buyer: BillIdentityBlockData,
@@ -151,7 +151,7 @@ pub struct BillOfferToSellBlockData {
     pub signing_address: PostalAddress, // address of the seller
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillSellBlockData {
     pub seller: BillIdentityBlockData,
     pub buyer: BillIdentityBlockData,
@@ -163,7 +163,7 @@ pub struct BillSellBlockData {
     pub signing_address: PostalAddress, // address of the seller
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillEndorseBlockData {
     pub endorser: BillIdentityBlockData,
     pub endorsee: BillIdentityBlockData,
@@ -172,7 +172,7 @@ pub struct BillEndorseBlockData {
     pub signing_address: PostalAddress, // address of the endorser
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillRequestRecourseBlockData {
     pub recourser: BillIdentityBlockData,
     pub recoursee: BillIdentityBlockData,
@@ -183,7 +183,7 @@ pub struct BillRequestRecourseBlockData {
     pub signing_address: PostalAddress, // address of the endorser
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillRecourseBlockData {
     pub recourser: BillIdentityBlockData,
     pub recoursee: BillIdentityBlockData,
@@ -195,7 +195,7 @@ pub struct BillRecourseBlockData {
 }
 
 /// Legal data for parties within a bill transaction
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct BillIdentityBlockData {
     pub t: ContactType,
     pub node_id: String,
@@ -236,7 +236,7 @@ impl From<BillIdentityBlockData> for LightIdentityPublicData {
 }
 
 /// The name and node_id of a company signatory
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct BillSignatoryBlockData {
     pub node_id: String,
     pub name: String,
@@ -776,15 +776,43 @@ impl BillBlock {
         &self,
         bill_keys: &BillKeys,
     ) -> Result<T> {
-        let bytes = util::base58_decode(&self.data)?;
-        let block_data: BillBlockData = from_slice(&bytes)?;
-        let decoded_data_bytes = util::base58_decode(&block_data.data)?;
+        let decoded_data_bytes = self.get_encrypted_block_bytes()?;
         let decrypted_bytes =
             util::crypto::decrypt_ecies(&decoded_data_bytes, &bill_keys.private_key)?;
         let deserialized = from_slice::<T>(&decrypted_bytes)?;
         Ok(deserialized)
     }
 
+    /// Returns the key of this block, which can be disclosed to decrypt only this block's data,
+    /// without disclosing the bill's private key
+    pub fn get_block_key(&self, bill_keys: &BillKeys) -> Result<String> {
+        let decoded_data_bytes = self.get_encrypted_block_bytes()?;
+        let block_key =
+            util::crypto::get_ecies_shared_key(&decoded_data_bytes, &bill_keys.private_key)?;
+        Ok(util::base58_encode(&block_key))
+    }
+
+    /// Decrypts the block data using the key of this block, as returned by [Self::get_block_key]
+    pub fn get_decrypted_block_bytes_with_block_key<T: borsh::BorshDeserialize>(
+        &self,
+        block_key: &str,
+    ) -> Result<T> {
+        let decoded_data_bytes = self.get_encrypted_block_bytes()?;
+        let decrypted_bytes = util::crypto::decrypt_ecies_with_shared_key(
+            &decoded_data_bytes,
+            &util::base58_decode(block_key)?,
+        )?;
+        let deserialized = from_slice::<T>(&decrypted_bytes)?;
+        Ok(deserialized)
+    }
+
+    fn get_encrypted_block_bytes(&self) -> Result<Vec<u8>> {
+        let bytes = util::base58_decode(&self.data)?;
+        let block_data: BillBlockData = from_slice(&bytes)?;
+        let decoded_data_bytes = util::base58_decode(&block_data.data)?;
+        Ok(decoded_data_bytes)
+    }
+
     /// Extracts a list of unique node IDs involved in a block operation.
     ///
     /// # Parameters
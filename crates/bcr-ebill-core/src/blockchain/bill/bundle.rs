@@ -0,0 +1,475 @@
+use super::super::{Error, Result};
+use super::block::{
+    BillAcceptBlockData, BillEndorseBlockData, BillIssueBlockData, BillMintBlockData,
+    BillOfferToSellBlockData, BillRecourseBlockData, BillRejectBlockData,
+    BillRequestRecourseBlockData, BillRequestToAcceptBlockData, BillRequestToPayBlockData,
+    BillSellBlockData,
+};
+use super::{BillBlock, BillBlockchain, BillOpCode};
+use crate::File;
+use crate::bill::BillKeys;
+use crate::blockchain::{Block, Blockchain, FIRST_BLOCK_ID};
+use crate::util::{self, BcrKeys};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The current version of the bill chain bundle format
+pub const BILL_CHAIN_BUNDLE_VERSION: u32 = 1;
+
+/// A self-contained export of a bill's chain, which can be handed to a third party, e.g. an
+/// auditor, and verified offline
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BillChainBundle {
+    pub version: u32,
+    pub bill_id: String,
+    pub exported_at: u64,
+    pub chain: BillBlockchain,
+    pub disclosure: BillChainDisclosure,
+    /// The decrypted files attached to the bill
+    pub files: Vec<BillChainBundleFile>,
+}
+
+/// What's needed to decrypt the blocks of a bundled chain
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BillChainDisclosure {
+    /// The bill keys, which allow decrypting all blocks
+    Full { bill_keys: BillKeys },
+    /// The keys of single blocks, which only allow decrypting these blocks
+    Selective { block_keys: Vec<BillChainBlockKey> },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BillChainBlockKey {
+    pub block_id: u64,
+    pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BillChainBundleFile {
+    pub name: String,
+    pub hash: String,
+    /// The base58 encoded, decrypted file content
+    pub content: String,
+}
+
+impl BillChainBundleFile {
+    pub fn new(name: &str, hash: &str, content: &[u8]) -> Self {
+        Self {
+            name: name.to_owned(),
+            hash: hash.to_owned(),
+            content: util::base58_encode(content),
+        }
+    }
+}
+
+/// The result of verifying a [BillChainBundle]
+#[derive(Serialize, Debug, Clone)]
+pub struct BillChainVerification {
+    pub bill_id: String,
+    /// If the chain starts with the issue block of the bill and every block follows its previous
+    /// block
+    pub chain_valid: bool,
+    /// If the disclosed bill keys belong to the bill, only set for full disclosures
+    pub keys_valid: Option<bool>,
+    pub blocks: Vec<BillBlockVerification>,
+    pub files: Vec<BillFileVerification>,
+}
+
+impl BillChainVerification {
+    /// Whether the chain, the disclosed keys, every block and every file are valid
+    pub fn is_valid(&self) -> bool {
+        self.chain_valid
+            && self.keys_valid != Some(false)
+            && self.blocks.iter().all(|b| b.is_valid())
+            && self.files.iter().all(|f| f.is_valid())
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BillBlockVerification {
+    pub id: u64,
+    pub op_code: BillOpCode,
+    pub timestamp: u64,
+    pub hash_valid: bool,
+    pub signature_valid: bool,
+    /// The decrypted block data, if the block was disclosed
+    pub data: Option<Value>,
+    /// Set, if the block was disclosed, but couldn't be decrypted
+    pub error: Option<String>,
+}
+
+impl BillBlockVerification {
+    pub fn is_valid(&self) -> bool {
+        self.hash_valid && self.signature_valid && self.error.is_none()
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BillFileVerification {
+    pub name: String,
+    pub hash: String,
+    /// If the file is referenced in the issue block
+    pub referenced: bool,
+    /// If the file content is part of the bundle
+    pub included: bool,
+    pub hash_valid: bool,
+}
+
+impl BillFileVerification {
+    pub fn is_valid(&self) -> bool {
+        !self.included || (self.referenced && self.hash_valid)
+    }
+}
+
+impl BillChainBundle {
+    /// Creates a bundle for the given chain, disclosing the bill keys, or, if block ids are
+    /// given, only the keys of these blocks
+    pub fn new(
+        chain: &BillBlockchain,
+        bill_keys: &BillKeys,
+        disclosed_block_ids: Option<&[u64]>,
+        files: Vec<BillChainBundleFile>,
+        exported_at: u64,
+    ) -> Result<Self> {
+        let disclosure = match disclosed_block_ids {
+            None => BillChainDisclosure::Full {
+                bill_keys: bill_keys.clone(),
+            },
+            Some(block_ids) => {
+                let mut block_keys = vec![];
+                for block in chain.blocks() {
+                    if block_ids.contains(&block.id) {
+                        block_keys.push(BillChainBlockKey {
+                            block_id: block.id,
+                            key: block.get_block_key(bill_keys)?,
+                        });
+                    }
+                }
+                BillChainDisclosure::Selective { block_keys }
+            }
+        };
+        Ok(Self {
+            version: BILL_CHAIN_BUNDLE_VERSION,
+            bill_id: chain.get_first_block().bill_id.clone(),
+            exported_at,
+            chain: chain.clone(),
+            disclosure,
+            files,
+        })
+    }
+
+    /// Re-validates the chain, checks the hash and signature of every block, decrypts the
+    /// disclosed blocks and checks the hashes of the bundled files
+    pub fn verify(&self) -> BillChainVerification {
+        let blocks = self.chain.blocks();
+        let chain_valid = match blocks.first() {
+            None => false,
+            Some(first) => {
+                first.id == FIRST_BLOCK_ID
+                    && first.op_code == BillOpCode::Issue
+                    && first.previous_hash == util::base58_encode(self.bill_id.as_bytes())
+                    && blocks.iter().all(|b| b.bill_id == self.bill_id)
+                    && self.chain.is_chain_valid()
+            }
+        };
+
+        let keys_valid = match self.disclosure {
+            BillChainDisclosure::Full { ref bill_keys } => Some(
+                BcrKeys::from_private_key(&bill_keys.private_key)
+                    .is_ok_and(|keys| keys.get_public_key() == bill_keys.public_key)
+                    && util::sha256_hash(bill_keys.public_key.as_bytes()) == self.bill_id,
+            ),
+            BillChainDisclosure::Selective { .. } => None,
+        };
+
+        let mut referenced_files = vec![];
+        let block_verifications = blocks
+            .iter()
+            .map(|block| {
+                let (data, error) = match self.get_block_decryption_key(block.id) {
+                    None => (None, None),
+                    Some(key) => match decode_block_data(block, &key) {
+                        Ok((data, files)) => {
+                            referenced_files.extend(files);
+                            (Some(data), None)
+                        }
+                        Err(e) => (None, Some(e.to_string())),
+                    },
+                };
+                BillBlockVerification {
+                    id: block.id,
+                    op_code: block.op_code.clone(),
+                    timestamp: block.timestamp,
+                    hash_valid: block.validate_hash(),
+                    signature_valid: block.verify(),
+                    data,
+                    error,
+                }
+            })
+            .collect();
+
+        let mut files: Vec<BillFileVerification> = self
+            .files
+            .iter()
+            .map(|file| BillFileVerification {
+                name: file.name.clone(),
+                hash: file.hash.clone(),
+                referenced: referenced_files
+                    .iter()
+                    .any(|f| f.name == file.name && f.hash == file.hash),
+                included: true,
+                hash_valid: util::base58_decode(&file.content)
+                    .is_ok_and(|content| util::sha256_hash(&content) == file.hash),
+            })
+            .collect();
+        for file in referenced_files {
+            if !files
+                .iter()
+                .any(|f| f.name == file.name && f.hash == file.hash)
+            {
+                files.push(BillFileVerification {
+                    name: file.name,
+                    hash: file.hash,
+                    referenced: true,
+                    included: false,
+                    hash_valid: false,
+                });
+            }
+        }
+
+        BillChainVerification {
+            bill_id: self.bill_id.clone(),
+            chain_valid,
+            keys_valid,
+            blocks: block_verifications,
+            files,
+        }
+    }
+
+    fn get_block_decryption_key(&self, block_id: u64) -> Option<BlockDecryptionKey<'_>> {
+        match self.disclosure {
+            BillChainDisclosure::Full { ref bill_keys } => {
+                Some(BlockDecryptionKey::Bill(bill_keys))
+            }
+            BillChainDisclosure::Selective { ref block_keys } => block_keys
+                .iter()
+                .find(|k| k.block_id == block_id)
+                .map(|k| BlockDecryptionKey::Block(&k.key)),
+        }
+    }
+}
+
+enum BlockDecryptionKey<'a> {
+    Bill(&'a BillKeys),
+    Block(&'a str),
+}
+
+fn decrypt<T: borsh::BorshDeserialize>(block: &BillBlock, key: &BlockDecryptionKey) -> Result<T> {
+    match key {
+        BlockDecryptionKey::Bill(bill_keys) => block.get_decrypted_block_bytes(bill_keys),
+        BlockDecryptionKey::Block(block_key) => {
+            block.get_decrypted_block_bytes_with_block_key(block_key)
+        }
+    }
+}
+
+fn to_json<T: Serialize>(data: T) -> Result<Value> {
+    serde_json::to_value(data).map_err(|e| Error::InvalidBlockdata(e.to_string()))
+}
+
+/// Decrypts the block data and returns it as JSON, together with the files referenced in it
+fn decode_block_data(block: &BillBlock, key: &BlockDecryptionKey) -> Result<(Value, Vec<File>)> {
+    let data = match block.op_code {
+        BillOpCode::Issue => {
+            let data: BillIssueBlockData = decrypt(block, key)?;
+            let files = data.files.clone();
+            return Ok((to_json(data)?, files));
+        }
+        BillOpCode::Accept => to_json(decrypt::<BillAcceptBlockData>(block, key)?)?,
+        BillOpCode::Endorse => to_json(decrypt::<BillEndorseBlockData>(block, key)?)?,
+        BillOpCode::RequestToAccept => {
+            to_json(decrypt::<BillRequestToAcceptBlockData>(block, key)?)?
+        }
+        BillOpCode::RequestToPay => to_json(decrypt::<BillRequestToPayBlockData>(block, key)?)?,
+        BillOpCode::OfferToSell => to_json(decrypt::<BillOfferToSellBlockData>(block, key)?)?,
+        BillOpCode::Sell => to_json(decrypt::<BillSellBlockData>(block, key)?)?,
+        BillOpCode::Mint => to_json(decrypt::<BillMintBlockData>(block, key)?)?,
+        BillOpCode::RejectToAccept
+        | BillOpCode::RejectToPay
+        | BillOpCode::RejectToBuy
+        | BillOpCode::RejectToPayRecourse => to_json(decrypt::<BillRejectBlockData>(block, key)?)?,
+        BillOpCode::RequestRecourse => {
+            to_json(decrypt::<BillRequestRecourseBlockData>(block, key)?)?
+        }
+        BillOpCode::Recourse => to_json(decrypt::<BillRecourseBlockData>(block, key)?)?,
+    };
+    Ok((data, vec![]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockchain::bill::tests::get_baseline_identity,
+        tests::tests::{
+            TEST_PUB_KEY_SECP, empty_bitcredit_bill, get_bill_keys,
+            identity_public_data_only_node_id,
+        },
+    };
+
+    const FILE_CONTENT: &[u8] = b"some file content";
+
+    fn get_bill_id() -> String {
+        util::sha256_hash(TEST_PUB_KEY_SECP.as_bytes())
+    }
+
+    fn get_chain() -> BillBlockchain {
+        let identity = get_baseline_identity();
+        let bill_keys = BcrKeys::from_private_key(&get_bill_keys().private_key).unwrap();
+        let mut bill = empty_bitcredit_bill();
+        bill.id = get_bill_id();
+        bill.files = vec![File {
+            name: "invoice.pdf".to_string(),
+            hash: util::sha256_hash(FILE_CONTENT),
+        }];
+        let mut chain = BillBlockchain::new(
+            &BillIssueBlockData::from(bill, None, 1731593928),
+            identity.key_pair.clone(),
+            None,
+            bill_keys.clone(),
+            1731593928,
+        )
+        .unwrap();
+        let endorser = identity_public_data_only_node_id(identity.identity.node_id.clone());
+        let endorsee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        let block = BillBlock::create_block_for_endorse(
+            get_bill_id(),
+            chain.get_latest_block(),
+            &BillEndorseBlockData {
+                endorser: endorser.clone().into(),
+                endorsee: endorsee.into(),
+                signatory: None,
+                signing_timestamp: 1731593929,
+                signing_address: endorser.postal_address,
+            },
+            &identity.key_pair,
+            None,
+            &bill_keys,
+            1731593929,
+        )
+        .unwrap();
+        assert!(chain.try_add_block(block));
+        chain
+    }
+
+    fn get_files() -> Vec<BillChainBundleFile> {
+        vec![BillChainBundleFile::new(
+            "invoice.pdf",
+            &util::sha256_hash(FILE_CONTENT),
+            FILE_CONTENT,
+        )]
+    }
+
+    #[test]
+    fn verify_full_disclosure() {
+        let bundle = BillChainBundle::new(
+            &get_chain(),
+            &get_bill_keys(),
+            None,
+            get_files(),
+            1731593930,
+        )
+        .unwrap();
+        // survives a round trip through the exported format
+        let bundle: BillChainBundle =
+            serde_json::from_str(&serde_json::to_string(&bundle).unwrap()).unwrap();
+        let result = bundle.verify();
+
+        assert!(result.is_valid());
+        assert_eq!(result.keys_valid, Some(true));
+        assert_eq!(result.blocks.len(), 2);
+        assert!(result.blocks.iter().all(|b| b.data.is_some()));
+        assert_eq!(
+            result.blocks[0].data.as_ref().unwrap()["id"],
+            Value::String(get_bill_id())
+        );
+        assert_eq!(result.files.len(), 1);
+        assert!(result.files[0].referenced);
+        assert!(result.files[0].hash_valid);
+    }
+
+    #[test]
+    fn verify_selective_disclosure() {
+        let bundle = BillChainBundle::new(
+            &get_chain(),
+            &get_bill_keys(),
+            Some(&[2]),
+            vec![],
+            1731593930,
+        )
+        .unwrap();
+        match bundle.disclosure {
+            BillChainDisclosure::Selective { ref block_keys } => {
+                assert_eq!(block_keys.len(), 1);
+                assert_eq!(block_keys[0].block_id, 2);
+            }
+            _ => panic!("expected a selective disclosure"),
+        }
+        let result = bundle.verify();
+
+        assert!(result.is_valid());
+        assert_eq!(result.keys_valid, None);
+        assert!(result.blocks[0].data.is_none());
+        assert!(result.blocks[1].data.is_some());
+        assert!(result.files.is_empty());
+    }
+
+    #[test]
+    fn verify_detects_tampered_block() {
+        let mut bundle =
+            BillChainBundle::new(&get_chain(), &get_bill_keys(), None, vec![], 1731593930).unwrap();
+        bundle.chain.blocks_mut()[1].timestamp += 1;
+        let result = bundle.verify();
+
+        assert!(!result.is_valid());
+        assert!(!result.chain_valid);
+        assert!(!result.blocks[1].hash_valid);
+    }
+
+    #[test]
+    fn verify_detects_wrong_keys() {
+        let mut bill_keys = get_bill_keys();
+        bill_keys.public_key = BcrKeys::new().get_public_key();
+        let bundle = BillChainBundle::new(&get_chain(), &get_bill_keys(), None, vec![], 0).unwrap();
+        let bundle = BillChainBundle {
+            disclosure: BillChainDisclosure::Full { bill_keys },
+            ..bundle
+        };
+        let result = bundle.verify();
+
+        assert!(!result.is_valid());
+        assert_eq!(result.keys_valid, Some(false));
+    }
+
+    #[test]
+    fn verify_detects_tampered_and_missing_files() {
+        let mut files = get_files();
+        files[0].content = util::base58_encode(b"other content");
+        files.push(BillChainBundleFile::new("other.pdf", "hash", b"content"));
+        let bundle =
+            BillChainBundle::new(&get_chain(), &get_bill_keys(), None, files, 1731593930).unwrap();
+        let result = bundle.verify();
+
+        assert!(!result.is_valid());
+        assert!(!result.files[0].hash_valid);
+        assert!(!result.files[1].referenced);
+
+        let bundle =
+            BillChainBundle::new(&get_chain(), &get_bill_keys(), None, vec![], 1731593930).unwrap();
+        let result = bundle.verify();
+        assert!(result.is_valid());
+        assert!(result.files[0].referenced);
+        assert!(!result.files[0].included);
+    }
+}
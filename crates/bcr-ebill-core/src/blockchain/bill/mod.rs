@@ -2,6 +2,7 @@ use borsh_derive::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 pub mod block;
+pub mod bundle;
 pub mod chain;
 
 pub use block::BillBlock;
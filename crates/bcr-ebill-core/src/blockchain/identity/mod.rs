@@ -383,6 +383,28 @@ impl IdentityBlockchain {
             blocks: vec![first_block],
         })
     }
+
+    /// Creates an identity chain from a vec of blocks
+    pub fn new_from_blocks(blocks_to_add: Vec<IdentityBlock>) -> Result<Self> {
+        match blocks_to_add.first() {
+            None => Err(super::Error::BlockchainInvalid),
+            Some(first) => {
+                if !first.verify() || !first.validate_hash() {
+                    return Err(super::Error::BlockchainInvalid);
+                }
+
+                let chain = Self {
+                    blocks: blocks_to_add,
+                };
+
+                if !chain.is_chain_valid() {
+                    return Err(super::Error::BlockchainInvalid);
+                }
+
+                Ok(chain)
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -499,5 +521,9 @@ mod tests {
 
         assert_eq!(chain.blocks().len(), 7);
         assert!(chain.is_chain_valid());
+
+        let new_chain_from_blocks = IdentityBlockchain::new_from_blocks(chain.blocks().to_owned());
+        assert!(new_chain_from_blocks.is_ok());
+        assert!(new_chain_from_blocks.as_ref().unwrap().is_chain_valid());
     }
 }
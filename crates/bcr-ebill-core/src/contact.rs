@@ -35,6 +35,82 @@ pub struct Contact {
     pub avatar_file: Option<File>,
     pub proof_document_file: Option<File>,
     pub nostr_relays: Vec<String>,
+    #[serde(default)]
+    pub verification_status: ContactVerificationStatus,
+    #[serde(default)]
+    pub trust_level: ContactTrustLevel,
+    /// A document proving the verification, e.g. a scanned passport or register extract
+    #[serde(default)]
+    pub verification_evidence_file: Option<File>,
+    /// Whether the details of the contact are kept up to date with its published profile.
+    /// It's turned off, once they are edited locally
+    #[serde(default)]
+    pub follows_profile: bool,
+    /// When the signed contact card or profile, the details were last taken from, was issued.
+    /// Older cards and profiles don't replace them
+    #[serde(default)]
+    pub details_issued_at: Option<u64>,
+}
+
+impl Contact {
+    pub fn is_verified(&self) -> bool {
+        self.verification_status != ContactVerificationStatus::Unverified
+    }
+}
+
+/// How the identity of a contact was verified
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContactVerificationStatus {
+    #[default]
+    Unverified,
+    /// The identity was verified when meeting in person
+    InPerson,
+    /// The identity was verified with an official document, e.g. a passport or a register extract
+    Document,
+}
+
+/// How much a contact is trusted, independent of whether its identity was verified
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContactTrustLevel {
+    #[default]
+    Unknown,
+    Low,
+    Medium,
+    High,
+}
+
+/// What happens, if a bill is issued, endorsed, sold, or recoursed to a counterparty, which isn't
+/// a verified contact
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContactVerificationPolicy {
+    /// Counterparties aren't checked
+    #[default]
+    Off,
+    /// The action is executed, but a warning is returned
+    Warn,
+    /// The action is refused
+    Refuse,
+}
+
+/// The outcome of importing several contacts at once, e.g. from a vCard file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContactImportResult {
+    /// The node ids of the imported contacts
+    pub imported: Vec<String>,
+    /// The node ids, which were skipped, since they're already a contact, or occur more than once
+    pub duplicates: Vec<String>,
+    /// The entries, which couldn't be imported
+    pub rejected: Vec<RejectedContactImport>,
+}
+
+/// An entry of a contact import, which couldn't be imported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedContactImport {
+    /// The position of the entry in the import, starting at 1
+    pub position: usize,
+    /// The name of the entry, if it had one
+    pub name: Option<String>,
+    pub reason: String,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
@@ -51,8 +127,8 @@ pub struct IdentityPublicData {
     pub postal_address: PostalAddress,
     /// email address of the identity
     pub email: Option<String>,
-    /// The preferred Nostr relay to deliver Nostr messages to
-    pub nostr_relay: Option<String>,
+    /// The Nostr relays to deliver Nostr messages to, the preferred one first
+    pub nostr_relays: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -102,7 +178,7 @@ impl From<Contact> for IdentityPublicData {
             name: value.name,
             postal_address: value.postal_address,
             email: Some(value.email),
-            nostr_relay: value.nostr_relays.first().cloned(),
+            nostr_relays: value.nostr_relays,
         }
     }
 }
@@ -115,7 +191,7 @@ impl From<Company> for IdentityPublicData {
             name: value.name,
             postal_address: value.postal_address,
             email: Some(value.email),
-            nostr_relay: None,
+            nostr_relays: vec![],
         }
     }
 }
@@ -129,7 +205,7 @@ impl IdentityPublicData {
                 name: identity.name,
                 postal_address,
                 email: Some(identity.email),
-                nostr_relay: identity.nostr_relay,
+                nostr_relays: identity.nostr_relay.into_iter().collect(),
             }),
             None => None,
         }
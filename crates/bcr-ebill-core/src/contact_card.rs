@@ -0,0 +1,162 @@
+use super::{
+    PostalAddress,
+    contact::ContactType,
+    util::{self, BcrKeys, crypto},
+};
+use borsh::to_vec;
+use borsh_derive::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The version of the contact card format we create, cards of other versions are rejected
+pub const CONTACT_CARD_VERSION: u32 = 1;
+
+/// The URI scheme contact cards are shared with, e.g. in QR codes
+pub const CONTACT_CARD_URI_SCHEME: &str = "bcr:";
+
+/// The signed content of a contact card
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactCardContent {
+    pub version: u32,
+    #[serde(rename = "type")]
+    pub t: ContactType,
+    pub node_id: String,
+    pub name: String,
+    pub postal_address: PostalAddress,
+    pub email: String,
+    /// The Nostr relays the identity or company receives messages on
+    pub nostr_relays: Vec<String>,
+    /// When the card was issued, so an older card can't replace newer details
+    pub issued_at: u64,
+}
+
+impl ContactCardContent {
+    /// The SHA-256 hash of the borsh-serialized content, which is signed
+    pub fn hash(&self) -> String {
+        let bytes = to_vec(self).expect("contact card content can be serialized");
+        util::sha256_hash(&bytes)
+    }
+}
+
+/// A contact card of an identity or company, signed with its keys, to exchange contact details
+/// in person, e.g. via QR code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContactCard {
+    pub content: ContactCardContent,
+    /// The Schnorr signature of the content hash, created with the keys of the node id
+    pub signature: String,
+}
+
+impl ContactCard {
+    /// Signs the content with the given keys, which have to be the ones of the node id
+    pub fn sign(content: ContactCardContent, keys: &BcrKeys) -> crypto::Result<Self> {
+        let signature = crypto::signature(&content.hash(), &keys.get_private_key_string())?;
+        Ok(Self { content, signature })
+    }
+
+    /// Returns true, if the card was signed by the keys of the node id it describes
+    pub fn verify(&self) -> crypto::Result<bool> {
+        crypto::verify(&self.content.hash(), &self.signature, &self.content.node_id)
+    }
+
+    /// Returns the card as `bcr:` URI, with the json of the card base58 encoded
+    pub fn to_uri(&self) -> String {
+        let json = serde_json::to_vec(self).expect("contact card can be serialized");
+        format!("{CONTACT_CARD_URI_SCHEME}{}", util::base58_encode(&json))
+    }
+}
+
+impl fmt::Display for ContactCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_uri())
+    }
+}
+
+impl FromStr for ContactCard {
+    type Err = String;
+
+    /// Parses a card from its `bcr:` URI, without verifying the signature
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = s
+            .trim()
+            .strip_prefix(CONTACT_CARD_URI_SCHEME)
+            .ok_or_else(|| {
+                format!("Contact card URI has to start with {CONTACT_CARD_URI_SCHEME}")
+            })?;
+        let json = util::base58_decode(encoded)
+            .map_err(|e| format!("Invalid contact card encoding: {e}"))?;
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid contact card: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_content(node_id: &str) -> ContactCardContent {
+        ContactCardContent {
+            version: CONTACT_CARD_VERSION,
+            t: ContactType::Company,
+            node_id: node_id.to_owned(),
+            name: "Hayek Ltd".to_owned(),
+            postal_address: PostalAddress {
+                country: "AT".to_owned(),
+                city: "Vienna".to_owned(),
+                zip: Some("1010".to_owned()),
+                address: "Kärntner Straße 1".to_owned(),
+            },
+            email: "office@example.com".to_owned(),
+            nostr_relays: vec!["ws://localhost:8080".to_owned()],
+            issued_at: 1000,
+        }
+    }
+
+    #[test]
+    fn signed_card_roundtrips_via_uri() {
+        let keys = BcrKeys::new();
+        let card = ContactCard::sign(get_content(&keys.get_public_key()), &keys).unwrap();
+        let uri = card.to_uri();
+        assert!(uri.starts_with(CONTACT_CARD_URI_SCHEME));
+
+        let parsed = ContactCard::from_str(&uri).unwrap();
+        assert_eq!(parsed, card);
+        assert!(parsed.verify().unwrap());
+    }
+
+    #[test]
+    fn tampered_or_foreign_cards_dont_verify() {
+        let keys = BcrKeys::new();
+        let mut card = ContactCard::sign(get_content(&keys.get_public_key()), &keys).unwrap();
+        card.content.name = "Someone else".to_owned();
+        assert!(!card.verify().unwrap());
+
+        let mut card = ContactCard::sign(get_content(&keys.get_public_key()), &keys).unwrap();
+        card.content.issued_at = 2000;
+        assert!(!card.verify().unwrap());
+
+        let other_keys = BcrKeys::new();
+        let card = ContactCard::sign(get_content(&keys.get_public_key()), &other_keys).unwrap();
+        assert!(!card.verify().unwrap());
+    }
+
+    #[test]
+    fn invalid_uris_are_rejected() {
+        assert!(ContactCard::from_str("https://example.com").is_err());
+        assert!(ContactCard::from_str("bcr:0OIl").is_err());
+        assert!(ContactCard::from_str("bcr:2NEpo7TZRRrLZSi2U").is_err());
+    }
+
+    #[test]
+    fn cards_without_issue_time_are_rejected() {
+        let keys = BcrKeys::new();
+        let card = ContactCard::sign(get_content(&keys.get_public_key()), &keys).unwrap();
+        let mut json = serde_json::to_value(&card).unwrap();
+        json["content"].as_object_mut().unwrap().remove("issued_at");
+        let uri = format!(
+            "{CONTACT_CARD_URI_SCHEME}{}",
+            util::base58_encode(&serde_json::to_vec(&json).unwrap())
+        );
+        assert!(ContactCard::from_str(&uri).is_err());
+    }
+}
@@ -5,16 +5,22 @@ use contact::Contact;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+pub mod api_token;
 pub mod bill;
+pub mod bill_template;
 pub mod blockchain;
 pub mod company;
 pub mod constants;
 pub mod contact;
+pub mod contact_card;
 pub mod identity;
 pub mod notification;
+pub mod profile;
+pub mod search;
 #[cfg(test)]
 mod tests;
 pub mod util;
+pub mod webhook;
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct PostalAddress {
@@ -72,9 +78,11 @@ pub struct GeneralSearchResult {
     pub bills: Vec<LightBitcreditBillResult>,
     pub contacts: Vec<Contact>,
     pub companies: Vec<Company>,
+    /// The ranked matches of all returned items, most relevant first
+    pub hits: Vec<search::SearchHit>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GeneralSearchFilterItemType {
     Company,
     Bill,
@@ -51,6 +51,16 @@ impl Notification {
     }
 }
 
+/// Whether the local identity, or company opted in to receive the events received for it via email
+/// additionally to Nostr. Without a preference, no emails are sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailPreference {
+    /// Id of the identity, which can be a personal identity, a company or a contact
+    pub node_id: String,
+    pub enabled: bool,
+    pub updated_at: u64,
+}
+
 /// The type/topic of a notification we show to the user
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NotificationType {
@@ -131,6 +141,39 @@ impl EventType {
             Self::BillQuoteApproved,
         ]
     }
+
+    /// The name of the event type, as it is serialized, e.g. for headers
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BillSigned => "BillSigned",
+            Self::BillAccepted => "BillAccepted",
+            Self::BillAcceptanceRequested => "BillAcceptanceRequested",
+            Self::BillAcceptanceRejected => "BillAcceptanceRejected",
+            Self::BillAcceptanceTimeout => "BillAcceptanceTimeout",
+            Self::BillAcceptanceRecourse => "BillAcceptanceRecourse",
+            Self::BillPaymentRequested => "BillPaymentRequested",
+            Self::BillPaymentRejected => "BillPaymentRejected",
+            Self::BillPaymentRecourse => "BillPaymentRecourse",
+            Self::BillRecourseRejected => "BillRecourseRejected",
+            Self::BillRecourseTimeout => "BillRecourseTimeout",
+            Self::BillPaymentTimeout => "BillPaymentTimeout",
+            Self::BillSellOffered => "BillSellOffered",
+            Self::BillBuyingRejected => "BillBuyingRejected",
+            Self::BillPaid => "BillPaid",
+            Self::BillRecoursePaid => "BillRecoursePaid",
+            Self::BillEndorsed => "BillEndorsed",
+            Self::BillSold => "BillSold",
+            Self::BillMintingRequested => "BillMintingRequested",
+            Self::BillNewQuote => "BillNewQuote",
+            Self::BillQuoteApproved => "BillQuoteApproved",
+        }
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl ActionType {
@@ -167,3 +210,18 @@ impl ActionType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_type_name_matches_serialized_name() {
+        for event_type in EventType::all() {
+            assert_eq!(
+                serde_json::to_string(&event_type).unwrap(),
+                format!("\"{}\"", event_type.as_str())
+            );
+        }
+    }
+}
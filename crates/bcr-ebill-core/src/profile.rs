@@ -0,0 +1,33 @@
+use super::{OptionalPostalAddress, contact::ContactType};
+use serde::{Deserialize, Serialize};
+
+/// The version of the profile format we publish, profiles of other versions are ignored
+pub const PROFILE_VERSION: u32 = 1;
+
+/// The public profile of an identity or a company, which is published via Nostr and signed
+/// with its keys, so it can be added as contact by its node id alone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicProfile {
+    pub version: u32,
+    #[serde(rename = "type")]
+    pub t: ContactType,
+    pub node_id: String,
+    pub name: String,
+    pub postal_address: OptionalPostalAddress,
+    /// The Nostr relays the identity or company receives messages on
+    pub nostr_relays: Vec<String>,
+    /// The SHA-256 hash of the proof of registration document of a company
+    pub proof_of_registration_hash: Option<String>,
+    pub published_at: u64,
+}
+
+impl PublicProfile {
+    /// Returns true, if the other profile has the same content, independent of when it was
+    /// published
+    pub fn has_same_content(&self, other: &PublicProfile) -> bool {
+        Self {
+            published_at: other.published_at,
+            ..self.clone()
+        } == *other
+    }
+}
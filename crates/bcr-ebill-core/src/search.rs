@@ -0,0 +1,373 @@
+use super::{
+    File, GeneralSearchFilterItemType, PostalAddress, bill::BitcreditBill, company::Company,
+    contact::Contact, contact::IdentityPublicData,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// The version of the tokenisation and the indexed fields - if it changes, the search index is
+/// rebuilt
+pub const SEARCH_INDEX_VERSION: u32 = 1;
+
+/// The fields of bills, contacts and companies, which are indexed for the full-text search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SearchField {
+    Id,
+    Name,
+    Email,
+    Address,
+    City,
+    Zip,
+    Country,
+    RegistrationNumber,
+    IdentificationNumber,
+    Drawee,
+    Drawer,
+    Payee,
+    Endorsee,
+    Participant,
+    FileName,
+}
+
+impl SearchField {
+    /// How relevant a match on the field is for the ranking
+    pub fn weight(&self) -> u32 {
+        match self {
+            SearchField::Name
+            | SearchField::Drawee
+            | SearchField::Drawer
+            | SearchField::Payee
+            | SearchField::Endorsee => 10,
+            SearchField::Id | SearchField::Email => 8,
+            SearchField::RegistrationNumber | SearchField::IdentificationNumber => 6,
+            SearchField::FileName => 4,
+            SearchField::City | SearchField::Country | SearchField::Participant => 3,
+            SearchField::Address | SearchField::Zip => 2,
+        }
+    }
+}
+
+/// A single indexed term of a document
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchTerm {
+    pub field: SearchField,
+    pub term: String,
+}
+
+/// A bill, contact, or company as it's indexed for the full-text search
+#[derive(Debug, Clone)]
+pub struct SearchDocument {
+    pub item_type: GeneralSearchFilterItemType,
+    pub item_id: String,
+    pub terms: Vec<SearchTerm>,
+}
+
+impl SearchDocument {
+    pub fn new(item_type: GeneralSearchFilterItemType, item_id: &str) -> Self {
+        let mut document = Self {
+            item_type,
+            item_id: item_id.to_owned(),
+            terms: vec![],
+        };
+        document.add(SearchField::Id, item_id);
+        document
+    }
+
+    /// Adds the tokens of the given text as terms of the given field
+    pub fn add(&mut self, field: SearchField, text: &str) {
+        for term in tokenize(text) {
+            let term = SearchTerm { field, term };
+            if !self.terms.contains(&term) {
+                self.terms.push(term);
+            }
+        }
+    }
+
+    fn add_opt(&mut self, field: SearchField, text: &Option<String>) {
+        if let Some(text) = text {
+            self.add(field, text);
+        }
+    }
+
+    fn add_address(&mut self, address: &PostalAddress) {
+        self.add(SearchField::Address, &address.address);
+        self.add(SearchField::City, &address.city);
+        self.add_opt(SearchField::Zip, &address.zip);
+        self.add(SearchField::Country, &address.country);
+    }
+
+    fn add_files<'a>(&mut self, files: impl IntoIterator<Item = &'a File>) {
+        for file in files {
+            self.add(SearchField::FileName, &file.name);
+        }
+    }
+
+    fn add_party(&mut self, field: SearchField, party: &IdentityPublicData) {
+        self.add(field, &party.name);
+        self.add(SearchField::Participant, &party.node_id);
+        self.add_opt(SearchField::Email, &party.email);
+        self.add_address(&party.postal_address);
+    }
+
+    pub fn for_contact(contact: &Contact) -> Self {
+        let mut document = Self::new(GeneralSearchFilterItemType::Contact, &contact.node_id);
+        document.add(SearchField::Name, &contact.name);
+        document.add(SearchField::Email, &contact.email);
+        document.add_address(&contact.postal_address);
+        document.add_opt(SearchField::City, &contact.city_of_birth_or_registration);
+        document.add_opt(
+            SearchField::Country,
+            &contact.country_of_birth_or_registration,
+        );
+        document.add_opt(
+            SearchField::IdentificationNumber,
+            &contact.identification_number,
+        );
+        document.add_files(
+            contact
+                .avatar_file
+                .iter()
+                .chain(contact.proof_document_file.iter()),
+        );
+        document
+    }
+
+    pub fn for_company(company: &Company) -> Self {
+        let mut document = Self::new(GeneralSearchFilterItemType::Company, &company.id);
+        document.add(SearchField::Name, &company.name);
+        document.add(SearchField::Email, &company.email);
+        document.add_address(&company.postal_address);
+        document.add_opt(SearchField::City, &company.city_of_registration);
+        document.add_opt(SearchField::Country, &company.country_of_registration);
+        document.add_opt(
+            SearchField::RegistrationNumber,
+            &company.registration_number,
+        );
+        document.add_files(
+            company
+                .logo_file
+                .iter()
+                .chain(company.proof_of_registration_file.iter()),
+        );
+        document
+    }
+
+    /// The document for the given bill - the participants are the node ids of everyone, who ever
+    /// took part in the bill
+    pub fn for_bill(bill: &BitcreditBill, participants: &[String]) -> Self {
+        let mut document = Self::new(GeneralSearchFilterItemType::Bill, &bill.id);
+        document.add_party(SearchField::Drawee, &bill.drawee);
+        document.add_party(SearchField::Drawer, &bill.drawer);
+        document.add_party(SearchField::Payee, &bill.payee);
+        if let Some(ref endorsee) = bill.endorsee {
+            document.add_party(SearchField::Endorsee, endorsee);
+        }
+        for participant in participants {
+            document.add(SearchField::Participant, participant);
+        }
+        document.add(SearchField::City, &bill.city_of_issuing);
+        document.add(SearchField::City, &bill.city_of_payment);
+        document.add(SearchField::Country, &bill.country_of_issuing);
+        document.add(SearchField::Country, &bill.country_of_payment);
+        document.add_files(bill.files.iter());
+        document
+    }
+}
+
+/// An indexed term, which matched a search token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPosting {
+    pub item_type: GeneralSearchFilterItemType,
+    pub item_id: String,
+    pub field: SearchField,
+    pub term: String,
+}
+
+/// A ranked search result with the fields the search matched on, most relevant first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub item_type: GeneralSearchFilterItemType,
+    pub item_id: String,
+    pub score: u32,
+    pub matched_fields: Vec<SearchField>,
+}
+
+/// Removes the diacritics of latin characters, e.g. `é` becomes `e` and `ß` becomes `ss`
+pub fn fold_diacritics(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        let replacement = match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+            'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+            'æ' => "ae",
+            'Æ' => "AE",
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+            'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+            'ď' | 'đ' | 'ð' => "d",
+            'Ď' | 'Đ' | 'Ð' => "D",
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+            'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+            'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+            'ĥ' | 'ħ' => "h",
+            'Ĥ' | 'Ħ' => "H",
+            'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+            'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+            'ĵ' => "j",
+            'Ĵ' => "J",
+            'ķ' => "k",
+            'Ķ' => "K",
+            'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+            'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+            'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+            'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+            'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+            'œ' => "oe",
+            'Œ' => "OE",
+            'ŕ' | 'ŗ' | 'ř' => "r",
+            'Ŕ' | 'Ŗ' | 'Ř' => "R",
+            'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+            'Ś' | 'Ŝ' | 'Ş' | 'Š' | 'Ș' => "S",
+            'ß' => "ss",
+            'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+            'Ţ' | 'Ť' | 'Ŧ' | 'Ț' => "T",
+            'þ' => "th",
+            'Þ' => "TH",
+            'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+            'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+            'ŵ' => "w",
+            'Ŵ' => "W",
+            'ý' | 'ÿ' | 'ŷ' => "y",
+            'Ý' | 'Ÿ' | 'Ŷ' => "Y",
+            'ź' | 'ż' | 'ž' => "z",
+            'Ź' | 'Ż' | 'Ž' => "Z",
+            _ => {
+                folded.push(c);
+                continue;
+            }
+        };
+        folded.push_str(replacement);
+    }
+    folded
+}
+
+/// Splits the given text into lowercase tokens without diacritics, on every character which
+/// is not alphanumeric
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = vec![];
+    for token in fold_diacritics(text)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+    {
+        if !tokens.iter().any(|t| t == token) {
+            tokens.push(token.to_owned());
+        }
+    }
+    tokens
+}
+
+/// Ranks the documents of the given postings for the given search tokens. Only documents
+/// matching every token are returned, exact matches of a term count more than prefix matches
+/// and the score of a match is weighted by its field.
+pub fn rank(tokens: &[String], postings: &[SearchPosting]) -> Vec<SearchHit> {
+    let mut documents: HashMap<(GeneralSearchFilterItemType, &str), Vec<&SearchPosting>> =
+        HashMap::new();
+    for posting in postings {
+        documents
+            .entry((posting.item_type, posting.item_id.as_str()))
+            .or_default()
+            .push(posting);
+    }
+
+    let mut hits = vec![];
+    for ((item_type, item_id), postings) in documents {
+        let mut score = 0;
+        let mut matched_fields: HashSet<SearchField> = HashSet::new();
+        let mut all_tokens_match = true;
+        for token in tokens {
+            let best = postings
+                .iter()
+                .filter(|p| p.term.starts_with(token.as_str()))
+                .map(|p| {
+                    let factor = if &p.term == token { 3 } else { 1 };
+                    (p.field.weight() * factor, p.field)
+                })
+                .max_by_key(|(score, _)| *score);
+            match best {
+                Some((token_score, field)) => {
+                    score += token_score;
+                    matched_fields.insert(field);
+                }
+                None => {
+                    all_tokens_match = false;
+                    break;
+                }
+            }
+        }
+        if all_tokens_match {
+            let mut matched_fields: Vec<SearchField> = matched_fields.into_iter().collect();
+            matched_fields.sort_by_key(|f| std::cmp::Reverse(f.weight()));
+            hits.push(SearchHit {
+                item_type,
+                item_id: item_id.to_owned(),
+                score,
+                matched_fields,
+            });
+        }
+    }
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.item_id.cmp(&b.item_id))
+    });
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posting(item_id: &str, field: SearchField, term: &str) -> SearchPosting {
+        SearchPosting {
+            item_type: GeneralSearchFilterItemType::Contact,
+            item_id: item_id.to_owned(),
+            field,
+            term: term.to_owned(),
+        }
+    }
+
+    #[test]
+    fn tokenize_folds_diacritics_and_splits() {
+        assert_eq!(
+            tokenize("Müller & Söhne GmbH, Straße 1 - jürgen@müller.at"),
+            vec!["muller", "sohne", "gmbh", "strasse", "1", "jurgen", "at"]
+        );
+        assert!(tokenize(" ,-- ").is_empty());
+    }
+
+    #[test]
+    fn rank_requires_all_tokens_and_prefers_exact_name_matches() {
+        let postings = vec![
+            posting("1", SearchField::Name, "hayek"),
+            posting("1", SearchField::City, "vienna"),
+            posting("2", SearchField::Address, "hayekgasse"),
+            posting("2", SearchField::City, "vienna"),
+            posting("3", SearchField::Name, "hayek"),
+        ];
+        let tokens = vec!["hayek".to_string(), "vienna".to_string()];
+        let hits = rank(&tokens, &postings);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].item_id, "1");
+        assert_eq!(
+            hits[0].matched_fields,
+            vec![SearchField::Name, SearchField::City]
+        );
+        assert_eq!(hits[1].item_id, "2");
+        assert_eq!(
+            hits[1].matched_fields,
+            vec![SearchField::City, SearchField::Address]
+        );
+    }
+}
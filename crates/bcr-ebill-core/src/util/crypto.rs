@@ -6,7 +6,7 @@ use bitcoin::{
     Network,
     secp256k1::{self, Keypair, Scalar, SecretKey, schnorr::Signature},
 };
-use nostr_sdk::ToBech32;
+use nostr_sdk::{FromBech32, ToBech32};
 use secp256k1::{Message, PublicKey, Secp256k1, rand};
 use thiserror::Error;
 
@@ -154,6 +154,14 @@ pub fn get_nostr_npub_as_hex_from_node_id(node_id: &str) -> Result<String> {
         .to_string())
 }
 
+/// Returns the npub as hex for the given node id, or for the given bech32 encoded npub
+pub fn get_nostr_npub_as_hex_from_node_id_or_npub(node_id_or_npub: &str) -> Result<String> {
+    if validate_pub_key(node_id_or_npub).is_ok() {
+        return get_nostr_npub_as_hex_from_node_id(node_id_or_npub);
+    }
+    Ok(nostr_sdk::PublicKey::from_bech32(node_id_or_npub)?.to_hex())
+}
+
 /// Checks if the given node_id and the given npub (as hex) are the same public key.
 /// This converts the node_id to an XOnlyPublicKey (which is the way nostr saves it's public key)
 /// and compares it to the given npub
@@ -168,6 +176,12 @@ pub fn is_node_id_nostr_hex_npub(node_id: &str, npub: &str) -> bool {
     }
 }
 
+/// Returns the node ids, which have the given npub (as hex). Since an npub is only the x
+/// coordinate of the public key, these are the compressed public keys for both parities
+pub fn get_node_ids_from_nostr_hex_npub(npub: &str) -> [String; 2] {
+    [format!("02{npub}"), format!("03{npub}")]
+}
+
 /// Generates a new keypair using the secp256k1 library
 fn generate_keypair() -> Keypair {
     let secp = Secp256k1::new();
@@ -272,6 +286,37 @@ pub fn decrypt_ecies(bytes: &[u8], private_key: &str) -> Result<Vec<u8>> {
     Ok(decrypted)
 }
 
+/// Derives the symmetric key the given ECIES encrypted bytes were encrypted with, using the given
+/// Secp256k1 key. Disclosing it allows decrypting only these bytes, not everything else that
+/// was encrypted for the same key.
+pub fn get_ecies_shared_key(bytes: &[u8], private_key: &str) -> Result<Vec<u8>> {
+    let keypair = BcrKeys::from_private_key(private_key)?;
+    let secret_key = ecies::SecretKey::parse_slice(keypair.inner.secret_bytes().as_slice())
+        .map_err(|e| Error::Ecies(e.to_string()))?;
+    let ephemeral_public_key = ecies::PublicKey::parse_slice(split_ecies_bytes(bytes)?.0, None)
+        .map_err(|e| Error::Ecies(e.to_string()))?;
+    let shared_key = ecies::utils::decapsulate(&ephemeral_public_key, &secret_key)
+        .map_err(|e| Error::Ecies(e.to_string()))?;
+    Ok(shared_key.to_vec())
+}
+
+/// Decrypt the given ECIES encrypted bytes with a symmetric key from [get_ecies_shared_key]
+pub fn decrypt_ecies_with_shared_key(bytes: &[u8], shared_key: &[u8]) -> Result<Vec<u8>> {
+    let (_, encrypted) = split_ecies_bytes(bytes)?;
+    ecies::symmetric::sym_decrypt(shared_key, encrypted)
+        .ok_or_else(|| Error::Ecies("could not decrypt with shared key".to_string()))
+}
+
+/// Splits ECIES encrypted bytes into the ephemeral public key and the symmetrically encrypted
+/// message
+fn split_ecies_bytes(bytes: &[u8]) -> Result<(&[u8], &[u8])> {
+    let key_size = ecies::config::get_ephemeral_key_size();
+    if bytes.len() < key_size {
+        return Err(Error::Ecies("encrypted message is too short".to_string()));
+    }
+    Ok(bytes.split_at(key_size))
+}
+
 // ------------------------ BIP39 ---------------------------
 
 /// Generate a new secp256k1 keypair using a 12 word seed phrase.
@@ -626,6 +671,23 @@ mod tests {
         assert_eq!(&msg, decrypted.as_ref().unwrap());
     }
 
+    #[test]
+    fn decrypt_ecies_with_shared_key_baseline() {
+        let msg = "Only this message".to_string().into_bytes();
+        let other_msg = "Not this one".to_string().into_bytes();
+        let keypair = BcrKeys::new();
+
+        let encrypted = encrypt_ecies(&msg, &keypair.get_public_key()).unwrap();
+        let other_encrypted = encrypt_ecies(&other_msg, &keypair.get_public_key()).unwrap();
+        let shared_key =
+            get_ecies_shared_key(&encrypted, &keypair.get_private_key_string()).unwrap();
+
+        let decrypted = decrypt_ecies_with_shared_key(&encrypted, &shared_key).unwrap();
+        assert_eq!(msg, decrypted);
+        assert!(decrypt_ecies_with_shared_key(&other_encrypted, &shared_key).is_err());
+        assert!(decrypt_ecies_with_shared_key(&[1, 2, 3], &shared_key).is_err());
+    }
+
     #[test]
     fn encrypt_decrypt_ecies_hardcoded_creds() {
         let msg = "Important!".to_string().into_bytes();
@@ -682,6 +744,17 @@ mod tests {
         assert_eq!(&companies_bytes, decrypted.as_ref().unwrap());
     }
 
+    #[test]
+    fn get_node_ids_from_nostr_hex_npub_base() {
+        let node_ids = get_node_ids_from_nostr_hex_npub(TEST_NODE_ID_SECP_AS_NPUB_HEX);
+        assert!(node_ids.contains(&TEST_NODE_ID_SECP.to_string()));
+        assert!(
+            node_ids
+                .iter()
+                .all(|node_id| is_node_id_nostr_hex_npub(node_id, TEST_NODE_ID_SECP_AS_NPUB_HEX))
+        );
+    }
+
     #[test]
     fn get_nostr_npub_as_hex_from_node_id_base() {
         let node_id = "0239a02d7aa976f4ef69173c271926d15fbff71e5b7d9e1adbb37fac2f3a370a70";
@@ -747,4 +820,19 @@ mod tests {
             TEST_NODE_ID_SECP_AS_NPUB_HEX
         ));
     }
+
+    #[test]
+    fn get_nostr_npub_as_hex_from_node_id_or_npub_base() {
+        let keys = BcrKeys::new();
+        let npub_as_hex = keys.get_nostr_npub_as_hex();
+        assert_eq!(
+            get_nostr_npub_as_hex_from_node_id_or_npub(&keys.get_public_key()).unwrap(),
+            npub_as_hex
+        );
+        assert_eq!(
+            get_nostr_npub_as_hex_from_node_id_or_npub(&keys.get_nostr_npub().unwrap()).unwrap(),
+            npub_as_hex
+        );
+        assert!(get_nostr_npub_as_hex_from_node_id_or_npub("invalid").is_err());
+    }
 }
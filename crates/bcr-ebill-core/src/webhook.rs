@@ -0,0 +1,71 @@
+use crate::notification::EventType;
+use serde::{Deserialize, Serialize};
+
+/// A subscription of an external system to events, which are POSTed to its URL and signed with
+/// its secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// The secret the HMAC signature of every delivery is created with
+    pub secret: String,
+    pub event_types: Vec<EventType>,
+    pub created_at: u64,
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event_type: &EventType) -> bool {
+        self.event_types.contains(event_type)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookDeliveryStatus {
+    /// Not delivered yet, it's (re-)tried at `next_attempt_at`
+    Pending,
+    Delivered,
+    /// All attempts failed
+    Failed,
+}
+
+/// A single event sent to a webhook, which is kept as log of the attempts to deliver it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event_type: EventType,
+    /// The JSON body, which is sent unchanged on every attempt, so its signature stays valid
+    pub payload: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    /// The HTTP status of the last attempt, if there was a response
+    pub last_response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub delivered_at: Option<u64>,
+}
+
+impl WebhookDelivery {
+    pub fn new(
+        id: &str,
+        webhook_id: &str,
+        event_type: EventType,
+        payload: &str,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            id: id.to_owned(),
+            webhook_id: webhook_id.to_owned(),
+            event_type,
+            payload: payload.to_owned(),
+            status: WebhookDeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: timestamp,
+            last_response_status: None,
+            last_error: None,
+            created_at: timestamp,
+            delivered_at: None,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use super::Result;
+use async_trait::async_trait;
+use bcr_ebill_core::api_token::ApiToken;
+
+/// Stores API tokens together with the hash of their secret
+#[async_trait]
+pub trait ApiTokenStoreApi: Send + Sync {
+    /// Stores a new API token with the hash of its secret
+    async fn insert(&self, token: &ApiToken, token_hash: &str) -> Result<()>;
+
+    /// Returns the API token with the given secret hash, if there is one
+    async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>>;
+
+    /// Returns all API tokens ordered by their creation time
+    async fn list(&self) -> Result<Vec<ApiToken>>;
+
+    /// Returns all API tokens together with the hash of their secret, e.g. for backups
+    async fn list_with_hashes(&self) -> Result<Vec<(ApiToken, String)>>;
+
+    /// Removes the API token with the given id
+    async fn remove(&self, id: &str) -> Result<()>;
+
+    /// Sets the time the API token with the given id was last used
+    async fn set_last_used(&self, id: &str, timestamp: u64) -> Result<()>;
+}
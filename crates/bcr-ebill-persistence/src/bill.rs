@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use super::Result;
 use async_trait::async_trait;
 use bcr_ebill_core::{
-    bill::BillKeys,
+    bill::{BillKeys, BillState, BillStateFilter, BillStateKey},
     blockchain::bill::{BillBlock, BillBlockchain, BillOpCode},
 };
 
@@ -23,6 +23,8 @@ pub trait BillStoreApi: Send + Sync {
     async fn is_paid(&self, id: &str) -> Result<bool>;
     /// Set the given bill to paid on the given payment address
     async fn set_to_paid(&self, id: &str, payment_address: &str) -> Result<()>;
+    /// Returns the payment address the given bill was paid on, if it was paid
+    async fn get_paid_payment_address(&self, id: &str) -> Result<Option<String>>;
     /// Gets all bills with a RequestToPay block, which are not paid already
     async fn get_bill_ids_waiting_for_payment(&self) -> Result<Vec<String>>;
     /// Gets all bills where the latest block is OfferToSell, which are still waiting for payment
@@ -36,6 +38,14 @@ pub trait BillStoreApi: Send + Sync {
         op_code: HashSet<BillOpCode>,
         since: u64,
     ) -> Result<Vec<String>>;
+    /// Replaces the materialised states of the given bill with the given ones
+    async fn save_bill_states(&self, id: &str, states: &[BillState]) -> Result<()>;
+    /// Removes the materialised states of the given bill, so they get rebuilt from the chain
+    async fn remove_bill_states(&self, id: &str) -> Result<()>;
+    /// Gets the keys of all materialised bill states
+    async fn get_bill_state_keys(&self) -> Result<Vec<BillStateKey>>;
+    /// Gets the materialised bill states matching the given filter, latest drawn first
+    async fn search_bill_states(&self, filter: &BillStateFilter) -> Result<Vec<BillState>>;
 }
 
 #[async_trait]
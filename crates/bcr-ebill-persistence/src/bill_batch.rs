@@ -0,0 +1,17 @@
+use super::Result;
+use async_trait::async_trait;
+use bcr_ebill_core::bill::BillBatchIssue;
+
+/// Stores which bills were issued for the rows of a batch
+#[async_trait]
+pub trait BillBatchStoreApi: Send + Sync {
+    /// Records that a bill was issued for a row of a batch
+    async fn add_issue(&self, issue: &BillBatchIssue) -> Result<()>;
+
+    /// Returns the bills issued for the rows of the given batch
+    async fn list_issues(&self, batch_id: &str) -> Result<Vec<BillBatchIssue>>;
+
+    /// Returns the bills issued for the rows of all batches, ordered by the time they were
+    /// issued
+    async fn get_all(&self) -> Result<Vec<BillBatchIssue>>;
+}
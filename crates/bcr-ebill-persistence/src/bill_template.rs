@@ -0,0 +1,31 @@
+use super::Result;
+use async_trait::async_trait;
+use bcr_ebill_core::bill_template::{BillTemplate, BillTemplateIssue};
+
+/// Stores bill templates and which bills were issued from them
+#[async_trait]
+pub trait BillTemplateStoreApi: Send + Sync {
+    /// Stores a new template
+    async fn insert(&self, template: &BillTemplate) -> Result<()>;
+
+    /// Replaces the stored template with the same id
+    async fn update(&self, template: &BillTemplate) -> Result<()>;
+
+    /// Returns the template with the given id, if there is one
+    async fn get(&self, id: &str) -> Result<Option<BillTemplate>>;
+
+    /// Returns all templates ordered by their creation time
+    async fn list(&self) -> Result<Vec<BillTemplate>>;
+
+    /// Removes the template with the given id, the record of bills issued from it is kept
+    async fn remove(&self, id: &str) -> Result<()>;
+
+    /// Records that a bill was issued from a template
+    async fn add_issue(&self, issue: &BillTemplateIssue) -> Result<()>;
+
+    /// Returns the bills issued from the given template, newest first
+    async fn list_issues(&self, template_id: &str) -> Result<Vec<BillTemplateIssue>>;
+
+    /// Returns the template issue of the given bill, if it was issued from a template
+    async fn get_issue_for_bill(&self, bill_id: &str) -> Result<Option<BillTemplateIssue>>;
+}
@@ -14,3 +14,20 @@ pub const DB_OP_CODE: &str = "op_code";
 pub const DB_COMPANY_ID: &str = "company_id";
 pub const DB_BILL_ID: &str = "bill_id";
 pub const DB_SEARCH_TERM: &str = "search_term";
+pub const DB_IDENTITY_NODE_ID: &str = "identity_node_id";
+pub const DB_ROLE: &str = "role";
+pub const DB_ISSUED_FROM: &str = "issued_from";
+pub const DB_ISSUED_TO: &str = "issued_to";
+pub const DB_MATURITY_FROM: &str = "maturity_from";
+pub const DB_MATURITY_TO: &str = "maturity_to";
+pub const DB_SUM_FROM: &str = "sum_from";
+pub const DB_SUM_TO: &str = "sum_to";
+pub const DB_CURRENCY: &str = "currency";
+pub const DB_COUNTERPARTY_NODE_ID: &str = "counterparty_node_id";
+pub const DB_HAS_ATTACHMENTS: &str = "has_attachments";
+pub const DB_CURSOR_VALUE: &str = "cursor_value";
+pub const DB_CURSOR_BILL_ID: &str = "cursor_bill_id";
+pub const DB_LIMIT: &str = "limit";
+pub const DB_ITEM_TYPE: &str = "item_type";
+pub const DB_ITEM_TYPES: &str = "item_types";
+pub const DB_ITEM_ID: &str = "item_id";
@@ -0,0 +1,188 @@
+use super::Result;
+use crate::api_token::ApiTokenStoreApi;
+use crate::constants::DB_TABLE;
+use async_trait::async_trait;
+use bcr_ebill_core::api_token::{ApiToken, ApiTokenScope};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any, sql::Thing};
+
+const DB_TOKEN_HASH: &str = "token_hash";
+const DB_LAST_USED_AT: &str = "last_used_at";
+const DB_ID: &str = "id";
+
+#[derive(Clone)]
+pub struct SurrealApiTokenStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealApiTokenStore {
+    const TABLE: &'static str = "api_tokens";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ApiTokenStoreApi for SurrealApiTokenStore {
+    async fn insert(&self, token: &ApiToken, token_hash: &str) -> Result<()> {
+        let entity = ApiTokenDb::from_token(token, token_hash);
+        let _: Option<ApiTokenDb> = self
+            .db
+            .create((Self::TABLE, token.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let result: Vec<ApiTokenDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE token_hash = $token_hash LIMIT 1")
+            .bind((DB_TABLE, Self::TABLE))
+            .bind((DB_TOKEN_HASH, token_hash.to_owned()))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().next().map(|t| t.into()))
+    }
+
+    async fn list(&self) -> Result<Vec<ApiToken>> {
+        let result: Vec<ApiTokenDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY created_at ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|t| t.into()).collect())
+    }
+
+    async fn list_with_hashes(&self) -> Result<Vec<(ApiToken, String)>> {
+        let result: Vec<ApiTokenDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY created_at ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result
+            .into_iter()
+            .map(|t| {
+                let token_hash = t.token_hash.clone();
+                (t.into(), token_hash)
+            })
+            .collect())
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let _: Option<ApiTokenDb> = self.db.delete((Self::TABLE, id)).await?;
+        Ok(())
+    }
+
+    async fn set_last_used(&self, id: &str, timestamp: u64) -> Result<()> {
+        let thing: Thing = (Self::TABLE, id).into();
+        self.db
+            .query("UPDATE $id SET last_used_at = $last_used_at")
+            .bind((DB_ID, thing))
+            .bind((DB_LAST_USED_AT, timestamp))
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ApiTokenDb {
+    pub id: Thing,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<ApiTokenScope>,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+}
+
+impl ApiTokenDb {
+    fn from_token(token: &ApiToken, token_hash: &str) -> Self {
+        Self {
+            id: (SurrealApiTokenStore::TABLE, token.id.as_str()).into(),
+            name: token.name.clone(),
+            token_hash: token_hash.to_owned(),
+            scopes: token.scopes.clone(),
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+        }
+    }
+}
+
+impl From<ApiTokenDb> for ApiToken {
+    fn from(value: ApiTokenDb) -> Self {
+        Self {
+            id: value.id.id.to_raw(),
+            name: value.name,
+            scopes: value.scopes,
+            created_at: value.created_at,
+            last_used_at: value.last_used_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+
+    async fn get_store() -> SurrealApiTokenStore {
+        let mem_db = get_memory_db("test", "api_tokens")
+            .await
+            .expect("could not create memory db");
+        SurrealApiTokenStore::new(mem_db)
+    }
+
+    fn get_token(id: &str, created_at: u64) -> ApiToken {
+        ApiToken {
+            id: id.to_string(),
+            name: format!("token {id}"),
+            scopes: vec![ApiTokenScope::BillsRead, ApiTokenScope::ContactsWrite],
+            created_at,
+            last_used_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insert_and_get_by_hash() {
+        let store = get_store().await;
+        store
+            .insert(&get_token("first", 1000), "hash")
+            .await
+            .expect("could not insert token");
+
+        let token = store.get_by_hash("hash").await.unwrap().unwrap();
+        assert_eq!(token, get_token("first", 1000));
+        assert!(store.get_by_hash("other hash").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_remove_and_set_last_used() {
+        let store = get_store().await;
+        store
+            .insert(&get_token("second", 2000), "hash2")
+            .await
+            .unwrap();
+        store
+            .insert(&get_token("first", 1000), "hash1")
+            .await
+            .unwrap();
+        store.set_last_used("second", 3000).await.unwrap();
+
+        let tokens = store.list().await.unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].id, "first");
+        assert_eq!(tokens[1].last_used_at, Some(3000));
+
+        let tokens = store.list_with_hashes().await.unwrap();
+        assert_eq!(tokens[0].1, "hash1");
+        assert_eq!(tokens[1].1, "hash2");
+
+        store.remove("first").await.unwrap();
+        let tokens = store.list().await.unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert!(store.get_by_hash("hash1").await.unwrap().is_none());
+    }
+}
@@ -1,11 +1,23 @@
 use std::collections::HashSet;
 
 use super::Result;
-use crate::constants::{DB_BILL_ID, DB_OP_CODE, DB_TABLE, DB_TIMESTAMP};
+use crate::constants::{
+    DB_BILL_ID, DB_COUNTERPARTY_NODE_ID, DB_CURRENCY, DB_CURSOR_BILL_ID, DB_CURSOR_VALUE,
+    DB_HAS_ATTACHMENTS, DB_IDENTITY_NODE_ID, DB_ISSUED_FROM, DB_ISSUED_TO, DB_LIMIT,
+    DB_MATURITY_FROM, DB_MATURITY_TO, DB_OP_CODE, DB_ROLE, DB_SEARCH_TERM, DB_SUM_FROM, DB_SUM_TO,
+    DB_TABLE, DB_TIMESTAMP,
+};
 use crate::{Error, bill::BillStoreApi};
 use async_trait::async_trait;
 use bcr_ebill_core::constants::{PAYMENT_DEADLINE_SECONDS, RECOURSE_DEADLINE_SECONDS};
-use bcr_ebill_core::{bill::BillKeys, blockchain::bill::BillOpCode, util};
+use bcr_ebill_core::{
+    bill::{
+        BillKeys, BillRole, BillSortField, BillState, BillStateFilter, BillStateKey, SortDirection,
+    },
+    blockchain::bill::BillOpCode,
+    contact::LightIdentityPublicData,
+    util,
+};
 use serde::{Deserialize, Serialize};
 use surrealdb::{Surreal, engine::any::Any, sql::Thing};
 
@@ -18,6 +30,7 @@ impl SurrealBillStore {
     const CHAIN_TABLE: &'static str = "bill_chain";
     const KEYS_TABLE: &'static str = "bill_keys";
     const PAID_TABLE: &'static str = "bill_paid";
+    const STATE_TABLE: &'static str = "bill_state";
 
     pub fn new(db: Surreal<Any>) -> Self {
         Self { db }
@@ -89,9 +102,20 @@ impl BillStoreApi for SurrealBillStore {
             .upsert((Self::PAID_TABLE, id))
             .content(entity)
             .await?;
+        self.db
+            .query("UPDATE type::table($table) SET paid = true WHERE bill_id = $bill_id")
+            .bind((DB_TABLE, Self::STATE_TABLE))
+            .bind((DB_BILL_ID, id.to_owned()))
+            .await?
+            .check()?;
         Ok(())
     }
 
+    async fn get_paid_payment_address(&self, id: &str) -> Result<Option<String>> {
+        let result: Option<BillPaidDb> = self.db.select((Self::PAID_TABLE, id)).await?;
+        Ok(result.map(|paid| paid.payment_address))
+    }
+
     async fn get_bill_ids_waiting_for_payment(&self) -> Result<Vec<String>> {
         let bill_ids_paid: Vec<BillPaidDb> = self.db.select(Self::PAID_TABLE).await?;
         let with_req_to_pay_bill_ids: Vec<BillIdDb> = self
@@ -174,6 +198,195 @@ impl BillStoreApi for SurrealBillStore {
             .await?.take(0)?;
         Ok(result.into_iter().map(|bid| bid.bill_id).collect())
     }
+
+    async fn save_bill_states(&self, id: &str, states: &[BillState]) -> Result<()> {
+        let entities: Vec<BillStateDb> = states.iter().map(|s| s.into()).collect();
+        self.db
+            .query(format!(
+                r#"BEGIN TRANSACTION;
+                DELETE FROM type::table($table) WHERE bill_id = $bill_id;
+                INSERT INTO {} $states;
+                COMMIT TRANSACTION;"#,
+                Self::STATE_TABLE
+            ))
+            .bind((DB_TABLE, Self::STATE_TABLE))
+            .bind((DB_BILL_ID, id.to_owned()))
+            .bind(("states", entities))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn remove_bill_states(&self, id: &str) -> Result<()> {
+        self.db
+            .query("DELETE FROM type::table($table) WHERE bill_id = $bill_id")
+            .bind((DB_TABLE, Self::STATE_TABLE))
+            .bind((DB_BILL_ID, id.to_owned()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn get_bill_state_keys(&self) -> Result<Vec<BillStateKey>> {
+        let keys: Vec<BillStateKeyDb> = self
+            .db
+            .query("SELECT bill_id, identity_node_id, participants FROM type::table($table)")
+            .bind((DB_TABLE, Self::STATE_TABLE))
+            .await?
+            .take(0)?;
+        Ok(keys.into_iter().map(|k| k.into()).collect())
+    }
+
+    async fn search_bill_states(&self, filter: &BillStateFilter) -> Result<Vec<BillState>> {
+        let query = &filter.query;
+        let mut conditions = vec!["identity_node_id = $identity_node_id"];
+        if query.role.bill_role().is_some() {
+            conditions.push("role = $role");
+        }
+        if query.search_term.is_some() {
+            conditions.push("string::contains(search_text, $search_term)");
+        }
+        // bills with an issue date we can't parse are never filtered out by date
+        if query.issued_from.is_some() {
+            conditions.push("(issue_timestamp = NONE OR issue_timestamp >= $issued_from)");
+        }
+        if query.issued_to.is_some() {
+            conditions.push("(issue_timestamp = NONE OR issue_timestamp <= $issued_to)");
+        }
+        if query.maturity_from.is_some() {
+            conditions.push("time_of_maturity >= $maturity_from");
+        }
+        if query.maturity_to.is_some() {
+            conditions.push("time_of_maturity <= $maturity_to");
+        }
+        if query.sum_from.is_some() {
+            conditions.push("sum >= $sum_from");
+        }
+        if query.sum_to.is_some() {
+            conditions.push("sum <= $sum_to");
+        }
+        if query.currency.is_some() {
+            conditions.push("currency = $currency");
+        }
+        if query.counterparty_node_id.is_some() {
+            conditions.push("participants CONTAINS $counterparty_node_id");
+        }
+        if query.has_attachments.is_some() {
+            conditions.push("has_attachments = $has_attachments");
+        }
+        match query.status.accepted {
+            Some(true) => conditions.push("accepted = true"),
+            Some(false) => conditions.push("accepted = false"),
+            None => (),
+        };
+        match query.status.paid {
+            Some(true) => conditions.push("paid = true"),
+            Some(false) => conditions.push("paid = false"),
+            None => (),
+        };
+        match query.status.waiting_for_payment {
+            Some(true) => conditions.push(
+                "(waiting_for_payment_until != NONE AND waiting_for_payment_until >= $timestamp)",
+            ),
+            Some(false) => conditions.push(
+                "(waiting_for_payment_until = NONE OR waiting_for_payment_until < $timestamp)",
+            ),
+            None => (),
+        };
+        match query.status.in_recourse {
+            Some(true) => {
+                conditions.push("(in_recourse_until != NONE AND in_recourse_until >= $timestamp)")
+            }
+            Some(false) => {
+                conditions.push("(in_recourse_until = NONE OR in_recourse_until < $timestamp)")
+            }
+            None => (),
+        };
+
+        let (sort_key, direction, cmp) = surreal_sort(query.sort, query.direction);
+        let cursor_condition = format!(
+            "({sort_key} {cmp} $cursor_value OR ({sort_key} = $cursor_value AND bill_id {cmp} $cursor_bill_id))"
+        );
+        if query.cursor.is_some() {
+            conditions.push(&cursor_condition);
+        }
+        let limit = if query.limit.is_some() {
+            " LIMIT $limit"
+        } else {
+            ""
+        };
+
+        let mut db_query = self
+            .db
+            .query(format!(
+                "SELECT *, {sort_key} AS sort_key FROM type::table($table) WHERE {} ORDER BY sort_key {direction}, bill_id {direction}{limit}",
+                conditions.join(" AND ")
+            ))
+            .bind((DB_TABLE, Self::STATE_TABLE))
+            .bind((DB_IDENTITY_NODE_ID, filter.identity_node_id.to_owned()))
+            .bind((DB_TIMESTAMP, filter.timestamp));
+        if let Some(role) = query.role.bill_role() {
+            db_query = db_query.bind((DB_ROLE, role));
+        }
+        if let Some(ref search_term) = query.search_term {
+            db_query = db_query.bind((DB_SEARCH_TERM, search_term.to_lowercase()));
+        }
+        if let Some(issued_from) = query.issued_from {
+            db_query = db_query.bind((DB_ISSUED_FROM, issued_from));
+        }
+        if let Some(issued_to) = query.issued_to {
+            db_query = db_query.bind((DB_ISSUED_TO, issued_to));
+        }
+        if let Some(maturity_from) = query.maturity_from {
+            db_query = db_query.bind((DB_MATURITY_FROM, maturity_from));
+        }
+        if let Some(maturity_to) = query.maturity_to {
+            db_query = db_query.bind((DB_MATURITY_TO, maturity_to));
+        }
+        if let Some(sum_from) = query.sum_from {
+            db_query = db_query.bind((DB_SUM_FROM, sum_from));
+        }
+        if let Some(sum_to) = query.sum_to {
+            db_query = db_query.bind((DB_SUM_TO, sum_to));
+        }
+        if let Some(ref currency) = query.currency {
+            db_query = db_query.bind((DB_CURRENCY, currency.to_owned()));
+        }
+        if let Some(ref counterparty_node_id) = query.counterparty_node_id {
+            db_query = db_query.bind((DB_COUNTERPARTY_NODE_ID, counterparty_node_id.to_owned()));
+        }
+        if let Some(has_attachments) = query.has_attachments {
+            db_query = db_query.bind((DB_HAS_ATTACHMENTS, has_attachments));
+        }
+        if let Some(ref cursor) = query.cursor {
+            db_query = db_query
+                .bind((DB_CURSOR_VALUE, cursor.sort_value))
+                .bind((DB_CURSOR_BILL_ID, cursor.bill_id.to_owned()));
+        }
+        if let Some(limit) = query.limit {
+            db_query = db_query.bind((DB_LIMIT, limit));
+        }
+        let result: Vec<BillStateDb> = db_query.await?.take(0)?;
+        Ok(result.into_iter().map(|s| s.into()).collect())
+    }
+}
+
+/// Returns the sort key expression, the sort direction and the comparison operator for the
+/// rows after the cursor
+fn surreal_sort(
+    sort: BillSortField,
+    direction: SortDirection,
+) -> (&'static str, &'static str, &'static str) {
+    let sort_key = match sort {
+        BillSortField::TimeOfDrawing => "time_of_drawing",
+        BillSortField::IssueDate => "(issue_timestamp ?? 0)",
+        BillSortField::MaturityDate => "time_of_maturity",
+        BillSortField::Sum => "sum",
+    };
+    match direction {
+        SortDirection::Ascending => (sort_key, "ASC", ">"),
+        SortDirection::Descending => (sort_key, "DESC", "<"),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -214,6 +427,115 @@ impl From<&BillKeys> for BillKeysDb {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillStateKeyDb {
+    pub bill_id: String,
+    pub identity_node_id: String,
+    pub participants: Vec<String>,
+}
+
+impl From<BillStateKeyDb> for BillStateKey {
+    fn from(value: BillStateKeyDb) -> Self {
+        Self {
+            bill_id: value.bill_id,
+            identity_node_id: value.identity_node_id,
+            participants: value.participants,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillStateDb {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Thing>,
+    pub bill_id: String,
+    pub identity_node_id: String,
+    pub role: BillRole,
+    pub drawee: LightIdentityPublicData,
+    pub drawer: LightIdentityPublicData,
+    pub payee: LightIdentityPublicData,
+    pub endorsee: Option<LightIdentityPublicData>,
+    pub holder_node_id: String,
+    pub buyer: Option<LightIdentityPublicData>,
+    pub seller: Option<LightIdentityPublicData>,
+    pub sum: u64,
+    pub currency: String,
+    pub issue_date: String,
+    pub issue_timestamp: Option<u64>,
+    pub time_of_drawing: u64,
+    pub time_of_maturity: u64,
+    pub accepted: bool,
+    pub paid: bool,
+    pub waiting_for_payment_until: Option<u64>,
+    pub in_recourse_until: Option<u64>,
+    pub has_attachments: bool,
+    pub participants: Vec<String>,
+    pub block_height: u64,
+    pub search_text: String,
+}
+
+impl From<BillStateDb> for BillState {
+    fn from(value: BillStateDb) -> Self {
+        Self {
+            bill_id: value.bill_id,
+            identity_node_id: value.identity_node_id,
+            role: value.role,
+            drawee: value.drawee,
+            drawer: value.drawer,
+            payee: value.payee,
+            endorsee: value.endorsee,
+            holder_node_id: value.holder_node_id,
+            buyer: value.buyer,
+            seller: value.seller,
+            sum: value.sum,
+            currency: value.currency,
+            issue_date: value.issue_date,
+            issue_timestamp: value.issue_timestamp,
+            time_of_drawing: value.time_of_drawing,
+            time_of_maturity: value.time_of_maturity,
+            accepted: value.accepted,
+            paid: value.paid,
+            waiting_for_payment_until: value.waiting_for_payment_until,
+            in_recourse_until: value.in_recourse_until,
+            has_attachments: value.has_attachments,
+            participants: value.participants,
+            block_height: value.block_height,
+        }
+    }
+}
+
+impl From<&BillState> for BillStateDb {
+    fn from(value: &BillState) -> Self {
+        Self {
+            id: None,
+            bill_id: value.bill_id.clone(),
+            identity_node_id: value.identity_node_id.clone(),
+            role: value.role,
+            drawee: value.drawee.clone(),
+            drawer: value.drawer.clone(),
+            payee: value.payee.clone(),
+            endorsee: value.endorsee.clone(),
+            holder_node_id: value.holder_node_id.clone(),
+            buyer: value.buyer.clone(),
+            seller: value.seller.clone(),
+            sum: value.sum,
+            currency: value.currency.clone(),
+            issue_date: value.issue_date.clone(),
+            issue_timestamp: value.issue_timestamp,
+            time_of_drawing: value.time_of_drawing,
+            time_of_maturity: value.time_of_maturity,
+            accepted: value.accepted,
+            paid: value.paid,
+            waiting_for_payment_until: value.waiting_for_payment_until,
+            in_recourse_until: value.in_recourse_until,
+            has_attachments: value.has_attachments,
+            participants: value.participants.clone(),
+            block_height: value.block_height,
+            search_text: value.search_text(),
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use std::collections::HashSet;
@@ -231,7 +553,10 @@ pub mod tests {
         },
     };
     use bcr_ebill_core::{
-        bill::BillKeys,
+        bill::{
+            BillKeys, BillRole, BillSearchCursor, BillSortField, BillState, BillStateFilter,
+            BillsFilterRole, SortDirection,
+        },
         blockchain::bill::{
             BillBlock, BillOpCode,
             block::{
@@ -277,6 +602,42 @@ pub mod tests {
         .unwrap()
     }
 
+    pub fn get_bill_state(bill_id: &str, identity_node_id: &str, role: BillRole) -> BillState {
+        let mut payee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        payee.name = "Hayek Ltd".to_string();
+        let drawee = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        let drawer = identity_public_data_only_node_id(BcrKeys::new().get_public_key());
+        BillState {
+            bill_id: bill_id.to_owned(),
+            identity_node_id: identity_node_id.to_owned(),
+            role,
+            participants: vec![
+                drawer.node_id.clone(),
+                drawee.node_id.clone(),
+                payee.node_id.clone(),
+            ],
+            drawee: drawee.into(),
+            drawer: drawer.into(),
+            holder_node_id: payee.node_id.clone(),
+            payee: payee.into(),
+            endorsee: None,
+            buyer: None,
+            seller: None,
+            sum: 500,
+            currency: "sat".to_string(),
+            issue_date: "2024-11-14".to_string(),
+            issue_timestamp: Some(1731542400),
+            time_of_drawing: 1731593928,
+            time_of_maturity: 4082227200,
+            accepted: false,
+            paid: false,
+            waiting_for_payment_until: None,
+            in_recourse_until: None,
+            has_attachments: false,
+            block_height: 1,
+        }
+    }
+
     #[tokio::test]
     async fn test_exists() {
         let db = get_db().await;
@@ -358,6 +719,12 @@ pub mod tests {
         let get_res_not_paid = store.is_paid("4321").await;
         assert!(get_res_not_paid.is_ok());
         assert!(!get_res_not_paid.as_ref().unwrap());
+
+        // payment address
+        let address = store.get_paid_payment_address("1234").await.unwrap();
+        assert_eq!(address, Some("1234paymentaddress".to_string()));
+        let no_address = store.get_paid_payment_address("4321").await.unwrap();
+        assert!(no_address.is_none());
     }
 
     #[tokio::test]
@@ -789,4 +1156,195 @@ pub mod tests {
         assert!(res.is_ok());
         assert_eq!(res.as_ref().unwrap().len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_save_and_search_bill_states() {
+        let store = get_store(get_db().await).await;
+        let mut other_state = get_bill_state("4321", TEST_PUB_KEY_SECP, BillRole::Payer);
+        other_state.issue_timestamp = Some(1000);
+        other_state.time_of_drawing = 1000;
+        store
+            .save_bill_states(
+                "1234",
+                &[
+                    get_bill_state("1234", TEST_PUB_KEY_SECP, BillRole::Payee),
+                    get_bill_state("1234", "other_identity", BillRole::Contingent),
+                ],
+            )
+            .await
+            .unwrap();
+        store
+            .save_bill_states("4321", &[other_state])
+            .await
+            .unwrap();
+
+        let all = store
+            .search_bill_states(&BillStateFilter::for_identity(TEST_PUB_KEY_SECP))
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        // latest drawn first
+        assert_eq!(all[0].bill_id, "1234");
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.role = BillsFilterRole::Payer;
+        let payer = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(payer.len(), 1);
+        assert_eq!(payer[0].bill_id, "4321");
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.search_term = Some("HAYEK".to_string());
+        filter.query.issued_from = Some(1731542400);
+        filter.query.issued_to = Some(1731542400);
+        let searched = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(searched.len(), 1);
+        assert_eq!(searched[0].bill_id, "1234");
+
+        let mut keys = store.get_bill_state_keys().await.unwrap();
+        keys.sort_by(|a, b| a.bill_id.cmp(&b.bill_id));
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].bill_id, "1234");
+        assert_eq!(keys[0].identity_node_id, TEST_PUB_KEY_SECP);
+        assert_eq!(keys[1].bill_id, "4321");
+    }
+
+    #[tokio::test]
+    async fn test_save_bill_states_replaces_and_remove() {
+        let store = get_store(get_db().await).await;
+        store
+            .save_bill_states(
+                "1234",
+                &[get_bill_state("1234", TEST_PUB_KEY_SECP, BillRole::Payee)],
+            )
+            .await
+            .unwrap();
+        let mut updated = get_bill_state("1234", TEST_PUB_KEY_SECP, BillRole::Contingent);
+        updated.block_height = 2;
+        store.save_bill_states("1234", &[updated]).await.unwrap();
+
+        let states = store
+            .search_bill_states(&BillStateFilter::for_identity(TEST_PUB_KEY_SECP))
+            .await
+            .unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].role, BillRole::Contingent);
+        assert_eq!(states[0].block_height, 2);
+
+        store.remove_bill_states("1234").await.unwrap();
+        assert!(store.get_bill_state_keys().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_to_paid_updates_bill_state() {
+        let store = get_store(get_db().await).await;
+        store
+            .save_bill_states(
+                "1234",
+                &[get_bill_state("1234", TEST_PUB_KEY_SECP, BillRole::Payee)],
+            )
+            .await
+            .unwrap();
+        store
+            .set_to_paid("1234", "1234paymentaddress")
+            .await
+            .unwrap();
+        let states = store
+            .search_bill_states(&BillStateFilter::for_identity(TEST_PUB_KEY_SECP))
+            .await
+            .unwrap();
+        assert!(states[0].paid);
+    }
+
+    #[tokio::test]
+    async fn test_search_bill_states_structured_query() {
+        let store = get_store(get_db().await).await;
+        let mut accepted = get_bill_state("1234", TEST_PUB_KEY_SECP, BillRole::Payer);
+        accepted.accepted = true;
+        accepted.sum = 1000;
+        accepted.has_attachments = true;
+        accepted.participants.push("counterparty".to_string());
+        let mut waiting = get_bill_state("4321", TEST_PUB_KEY_SECP, BillRole::Payee);
+        waiting.waiting_for_payment_until = Some(2000);
+        waiting.time_of_maturity = 1000;
+        store.save_bill_states("1234", &[accepted]).await.unwrap();
+        store.save_bill_states("4321", &[waiting]).await.unwrap();
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.sum_from = Some(600);
+        filter.query.currency = Some("sat".to_string());
+        filter.query.has_attachments = Some(true);
+        filter.query.status.accepted = Some(true);
+        filter.query.counterparty_node_id = Some("counterparty".to_string());
+        let result = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].bill_id, "1234");
+        assert!(result[0].accepted);
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.timestamp = 1500;
+        filter.query.status.waiting_for_payment = Some(true);
+        filter.query.maturity_to = Some(1000);
+        let result = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].bill_id, "4321");
+
+        // expired
+        filter.timestamp = 2500;
+        assert!(store.search_bill_states(&filter).await.unwrap().is_empty());
+        filter.query.status.waiting_for_payment = Some(false);
+        filter.query.maturity_to = None;
+        assert_eq!(store.search_bill_states(&filter).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_bill_states_sorted_and_paginated() {
+        let store = get_store(get_db().await).await;
+        for (id, sum) in [("a", 300), ("b", 100), ("c", 300), ("d", 200)] {
+            let mut state = get_bill_state(id, TEST_PUB_KEY_SECP, BillRole::Payee);
+            state.sum = sum;
+            store.save_bill_states(id, &[state]).await.unwrap();
+        }
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.sort = BillSortField::Sum;
+        filter.query.direction = SortDirection::Ascending;
+        filter.query.limit = Some(2);
+        let first_page = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(
+            first_page
+                .iter()
+                .map(|s| s.bill_id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["b", "d"]
+        );
+
+        filter.query.cursor = Some(BillSearchCursor::for_state(
+            &first_page[1],
+            BillSortField::Sum,
+            SortDirection::Ascending,
+        ));
+        let second_page = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(
+            second_page
+                .iter()
+                .map(|s| s.bill_id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["a", "c"]
+        );
+
+        filter.query.direction = SortDirection::Descending;
+        filter.query.cursor = Some(BillSearchCursor::for_state(
+            &second_page[1],
+            BillSortField::Sum,
+            SortDirection::Descending,
+        ));
+        let descending = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(
+            descending
+                .iter()
+                .map(|s| s.bill_id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["a", "d"]
+        );
+    }
 }
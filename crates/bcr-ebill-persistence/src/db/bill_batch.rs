@@ -0,0 +1,130 @@
+use super::Result;
+use crate::bill_batch::BillBatchStoreApi;
+use crate::constants::DB_TABLE;
+use async_trait::async_trait;
+use bcr_ebill_core::bill::BillBatchIssue;
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any};
+
+const DB_BATCH_ID: &str = "batch_id";
+
+#[derive(Clone)]
+pub struct SurrealBillBatchStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealBillBatchStore {
+    const ISSUES_TABLE: &'static str = "bill_batch_issues";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl BillBatchStoreApi for SurrealBillBatchStore {
+    async fn add_issue(&self, issue: &BillBatchIssue) -> Result<()> {
+        let entity: BillBatchIssueDb = issue.into();
+        // a row can only be issued once per batch, so the record id is made of both
+        let id = format!("{}_{}", issue.batch_id, issue.row_hash);
+        let _: Option<BillBatchIssueDb> = self
+            .db
+            .create((Self::ISSUES_TABLE, id))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_issues(&self, batch_id: &str) -> Result<Vec<BillBatchIssue>> {
+        let result: Vec<BillBatchIssueDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE batch_id = $batch_id ORDER BY issued_at ASC")
+            .bind((DB_TABLE, Self::ISSUES_TABLE))
+            .bind((DB_BATCH_ID, batch_id.to_owned()))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|i| i.into()).collect())
+    }
+
+    async fn get_all(&self) -> Result<Vec<BillBatchIssue>> {
+        let result: Vec<BillBatchIssueDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY issued_at ASC")
+            .bind((DB_TABLE, Self::ISSUES_TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|i| i.into()).collect())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct BillBatchIssueDb {
+    pub batch_id: String,
+    pub row_hash: String,
+    pub bill_id: String,
+    pub issued_at: u64,
+}
+
+impl From<&BillBatchIssue> for BillBatchIssueDb {
+    fn from(value: &BillBatchIssue) -> Self {
+        Self {
+            batch_id: value.batch_id.clone(),
+            row_hash: value.row_hash.clone(),
+            bill_id: value.bill_id.clone(),
+            issued_at: value.issued_at,
+        }
+    }
+}
+
+impl From<BillBatchIssueDb> for BillBatchIssue {
+    fn from(value: BillBatchIssueDb) -> Self {
+        Self {
+            batch_id: value.batch_id,
+            row_hash: value.row_hash,
+            bill_id: value.bill_id,
+            issued_at: value.issued_at,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+
+    async fn get_store() -> SurrealBillBatchStore {
+        let mem_db = get_memory_db("test", "bill_batch_issues")
+            .await
+            .expect("could not create memory db");
+        SurrealBillBatchStore::new(mem_db)
+    }
+
+    pub fn get_bill_batch_issue(batch_id: &str, row_hash: &str, issued_at: u64) -> BillBatchIssue {
+        BillBatchIssue {
+            batch_id: batch_id.to_string(),
+            row_hash: row_hash.to_string(),
+            bill_id: format!("bill_{row_hash}"),
+            issued_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issues() {
+        let store = get_store().await;
+        let second = get_bill_batch_issue("batch", "row2", 2000);
+        let first = get_bill_batch_issue("batch", "row1", 1000);
+        let other = get_bill_batch_issue("other", "row1", 3000);
+        store.add_issue(&second).await.unwrap();
+        store.add_issue(&first).await.unwrap();
+        store.add_issue(&other).await.unwrap();
+        // a row can't be issued twice for the same batch
+        assert!(store.add_issue(&first).await.is_err());
+
+        assert_eq!(
+            store.list_issues("batch").await.unwrap(),
+            vec![first.clone(), second.clone()]
+        );
+        assert!(store.list_issues("unknown").await.unwrap().is_empty());
+        assert_eq!(store.get_all().await.unwrap(), vec![first, second, other]);
+    }
+}
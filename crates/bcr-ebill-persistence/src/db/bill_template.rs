@@ -0,0 +1,288 @@
+use super::Result;
+use crate::bill_template::BillTemplateStoreApi;
+use crate::constants::DB_TABLE;
+use async_trait::async_trait;
+use bcr_ebill_core::bill_template::{BillTemplate, BillTemplateIssue, BillTemplateSchedule};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any, sql::Thing};
+
+const DB_TEMPLATE_ID: &str = "template_id";
+
+#[derive(Clone)]
+pub struct SurrealBillTemplateStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealBillTemplateStore {
+    const TABLE: &'static str = "bill_templates";
+    const ISSUES_TABLE: &'static str = "bill_template_issues";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl BillTemplateStoreApi for SurrealBillTemplateStore {
+    async fn insert(&self, template: &BillTemplate) -> Result<()> {
+        let entity: BillTemplateDb = template.into();
+        let _: Option<BillTemplateDb> = self
+            .db
+            .create((Self::TABLE, template.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, template: &BillTemplate) -> Result<()> {
+        let entity: BillTemplateDb = template.into();
+        let _: Option<BillTemplateDb> = self
+            .db
+            .update((Self::TABLE, template.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<BillTemplate>> {
+        let result: Option<BillTemplateDb> = self.db.select((Self::TABLE, id)).await?;
+        Ok(result.map(|t| t.into()))
+    }
+
+    async fn list(&self) -> Result<Vec<BillTemplate>> {
+        let result: Vec<BillTemplateDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY created_at ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|t| t.into()).collect())
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let _: Option<BillTemplateDb> = self.db.delete((Self::TABLE, id)).await?;
+        Ok(())
+    }
+
+    async fn add_issue(&self, issue: &BillTemplateIssue) -> Result<()> {
+        let entity: BillTemplateIssueDb = issue.into();
+        let _: Option<BillTemplateIssueDb> = self
+            .db
+            .create((Self::ISSUES_TABLE, issue.bill_id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_issues(&self, template_id: &str) -> Result<Vec<BillTemplateIssue>> {
+        let result: Vec<BillTemplateIssueDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE template_id = $template_id ORDER BY issued_at DESC")
+            .bind((DB_TABLE, Self::ISSUES_TABLE))
+            .bind((DB_TEMPLATE_ID, template_id.to_owned()))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|i| i.into()).collect())
+    }
+
+    async fn get_issue_for_bill(&self, bill_id: &str) -> Result<Option<BillTemplateIssue>> {
+        let result: Option<BillTemplateIssueDb> =
+            self.db.select((Self::ISSUES_TABLE, bill_id)).await?;
+        Ok(result.map(|i| i.into()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct BillTemplateDb {
+    pub id: Thing,
+    pub name: String,
+    pub drawer_node_id: String,
+    pub drawee_node_id: String,
+    pub payee_node_id: String,
+    pub sum: u64,
+    pub currency: String,
+    pub country_of_issuing: String,
+    pub city_of_issuing: String,
+    pub country_of_payment: String,
+    pub city_of_payment: String,
+    pub language: String,
+    pub term_days: u32,
+    pub schedule: Option<BillTemplateSchedule>,
+    pub created_at: u64,
+}
+
+impl From<&BillTemplate> for BillTemplateDb {
+    fn from(value: &BillTemplate) -> Self {
+        Self {
+            id: (SurrealBillTemplateStore::TABLE, value.id.as_str()).into(),
+            name: value.name.clone(),
+            drawer_node_id: value.drawer_node_id.clone(),
+            drawee_node_id: value.drawee_node_id.clone(),
+            payee_node_id: value.payee_node_id.clone(),
+            sum: value.sum,
+            currency: value.currency.clone(),
+            country_of_issuing: value.country_of_issuing.clone(),
+            city_of_issuing: value.city_of_issuing.clone(),
+            country_of_payment: value.country_of_payment.clone(),
+            city_of_payment: value.city_of_payment.clone(),
+            language: value.language.clone(),
+            term_days: value.term_days,
+            schedule: value.schedule.clone(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<BillTemplateDb> for BillTemplate {
+    fn from(value: BillTemplateDb) -> Self {
+        Self {
+            id: value.id.id.to_raw(),
+            name: value.name,
+            drawer_node_id: value.drawer_node_id,
+            drawee_node_id: value.drawee_node_id,
+            payee_node_id: value.payee_node_id,
+            sum: value.sum,
+            currency: value.currency,
+            country_of_issuing: value.country_of_issuing,
+            city_of_issuing: value.city_of_issuing,
+            country_of_payment: value.country_of_payment,
+            city_of_payment: value.city_of_payment,
+            language: value.language,
+            term_days: value.term_days,
+            schedule: value.schedule,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct BillTemplateIssueDb {
+    pub id: Thing,
+    pub template_id: String,
+    pub bill_id: String,
+    pub occurrence: Option<u32>,
+    pub issued_at: u64,
+}
+
+impl From<&BillTemplateIssue> for BillTemplateIssueDb {
+    fn from(value: &BillTemplateIssue) -> Self {
+        Self {
+            id: (
+                SurrealBillTemplateStore::ISSUES_TABLE,
+                value.bill_id.as_str(),
+            )
+                .into(),
+            template_id: value.template_id.clone(),
+            bill_id: value.bill_id.clone(),
+            occurrence: value.occurrence,
+            issued_at: value.issued_at,
+        }
+    }
+}
+
+impl From<BillTemplateIssueDb> for BillTemplateIssue {
+    fn from(value: BillTemplateIssueDb) -> Self {
+        Self {
+            template_id: value.template_id,
+            bill_id: value.bill_id,
+            occurrence: value.occurrence,
+            issued_at: value.issued_at,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+    use bcr_ebill_core::bill_template::BillTemplateInterval;
+
+    async fn get_store() -> SurrealBillTemplateStore {
+        let mem_db = get_memory_db("test", "bill_templates")
+            .await
+            .expect("could not create memory db");
+        SurrealBillTemplateStore::new(mem_db)
+    }
+
+    pub fn get_bill_template(id: &str, created_at: u64) -> BillTemplate {
+        BillTemplate {
+            id: id.to_string(),
+            name: "Monthly rent".to_string(),
+            drawer_node_id: "drawer".to_string(),
+            drawee_node_id: "drawee".to_string(),
+            payee_node_id: "drawer".to_string(),
+            sum: 500,
+            currency: "sat".to_string(),
+            country_of_issuing: "AT".to_string(),
+            city_of_issuing: "Vienna".to_string(),
+            country_of_payment: "AT".to_string(),
+            city_of_payment: "Vienna".to_string(),
+            language: "en-UK".to_string(),
+            term_days: 30,
+            schedule: Some(BillTemplateSchedule {
+                interval: BillTemplateInterval::Monthly,
+                start_date: "2025-01-01".to_string(),
+                end_date: None,
+                occurrences_done: 0,
+            }),
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_templates() {
+        let store = get_store().await;
+        store
+            .insert(&get_bill_template("second", 2000))
+            .await
+            .unwrap();
+        store
+            .insert(&get_bill_template("first", 1000))
+            .await
+            .unwrap();
+
+        let mut template = get_bill_template("second", 2000);
+        assert_eq!(store.get("second").await.unwrap(), Some(template.clone()));
+        template.schedule = None;
+        store.update(&template).await.unwrap();
+        assert_eq!(store.get("second").await.unwrap(), Some(template));
+
+        let templates = store.list().await.unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[0].id, "first");
+
+        store.remove("first").await.unwrap();
+        assert!(store.get("first").await.unwrap().is_none());
+        assert_eq!(store.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_issues() {
+        let store = get_store().await;
+        let first = BillTemplateIssue {
+            template_id: "template".to_string(),
+            bill_id: "bill1".to_string(),
+            occurrence: Some(0),
+            issued_at: 1000,
+        };
+        let second = BillTemplateIssue {
+            template_id: "template".to_string(),
+            bill_id: "bill2".to_string(),
+            occurrence: None,
+            issued_at: 2000,
+        };
+        store.add_issue(&first).await.unwrap();
+        store.add_issue(&second).await.unwrap();
+
+        assert_eq!(
+            store.list_issues("template").await.unwrap(),
+            vec![second.clone(), first]
+        );
+        assert!(store.list_issues("other").await.unwrap().is_empty());
+        assert_eq!(
+            store.get_issue_for_bill("bill2").await.unwrap(),
+            Some(second)
+        );
+        assert!(store.get_issue_for_bill("bill3").await.unwrap().is_none());
+    }
+}
@@ -9,7 +9,7 @@ use crate::{
     ContactStoreApi,
     constants::{DB_SEARCH_TERM, DB_TABLE},
 };
-use bcr_ebill_core::contact::{Contact, ContactType};
+use bcr_ebill_core::contact::{Contact, ContactTrustLevel, ContactType, ContactVerificationStatus};
 
 #[derive(Clone)]
 pub struct SurrealContactStore {
@@ -90,6 +90,16 @@ pub struct ContactDb {
     pub avatar_file: Option<FileDb>,
     pub proof_document_file: Option<FileDb>,
     pub nostr_relays: Vec<String>,
+    #[serde(default)]
+    pub verification_status: ContactVerificationStatus,
+    #[serde(default)]
+    pub trust_level: ContactTrustLevel,
+    #[serde(default)]
+    pub verification_evidence_file: Option<FileDb>,
+    #[serde(default)]
+    pub follows_profile: bool,
+    #[serde(default)]
+    pub details_issued_at: Option<u64>,
 }
 
 impl From<ContactDb> for Contact {
@@ -107,6 +117,11 @@ impl From<ContactDb> for Contact {
             avatar_file: contact.avatar_file.map(|f| f.into()),
             proof_document_file: contact.proof_document_file.map(|f| f.into()),
             nostr_relays: contact.nostr_relays,
+            verification_status: contact.verification_status,
+            trust_level: contact.trust_level,
+            verification_evidence_file: contact.verification_evidence_file.map(|f| f.into()),
+            follows_profile: contact.follows_profile,
+            details_issued_at: contact.details_issued_at,
         }
     }
 }
@@ -126,6 +141,11 @@ impl From<Contact> for ContactDb {
             avatar_file: contact.avatar_file.map(|f| f.into()),
             proof_document_file: contact.proof_document_file.map(|f| f.into()),
             nostr_relays: contact.nostr_relays,
+            verification_status: contact.verification_status,
+            trust_level: contact.trust_level,
+            verification_evidence_file: contact.verification_evidence_file.map(|f| f.into()),
+            follows_profile: contact.follows_profile,
+            details_issued_at: contact.details_issued_at,
         }
     }
 }
@@ -152,6 +172,11 @@ pub mod tests {
             avatar_file: None,
             proof_document_file: None,
             nostr_relays: vec![],
+            verification_status: ContactVerificationStatus::Unverified,
+            trust_level: ContactTrustLevel::Unknown,
+            verification_evidence_file: None,
+            follows_profile: false,
+            details_issued_at: None,
         }
     }
 
@@ -9,7 +9,7 @@ use crate::{
 use async_trait::async_trait;
 use bcr_ebill_core::blockchain::{
     Block,
-    identity::{IdentityBlock, IdentityOpCode},
+    identity::{IdentityBlock, IdentityBlockchain, IdentityOpCode},
 };
 use serde::{Deserialize, Serialize};
 use surrealdb::{Surreal, engine::any::Any};
@@ -129,6 +129,20 @@ impl IdentityChainStoreApi for SurrealIdentityChainStore {
             Err(e) => Err(e),
         }
     }
+
+    async fn get_chain(&self) -> Result<IdentityBlockchain> {
+        let result: Vec<IdentityBlockDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY block_id ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+
+        let blocks: Vec<IdentityBlock> = result.into_iter().map(|b| b.into()).collect();
+        let chain = IdentityBlockchain::new_from_blocks(blocks)?;
+
+        Ok(chain)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -181,7 +195,7 @@ mod tests {
         tests::tests::{empty_identity, empty_optional_address},
         util::BcrKeys,
     };
-    use bcr_ebill_core::blockchain::identity::IdentityUpdateBlockData;
+    use bcr_ebill_core::blockchain::{Blockchain, identity::IdentityUpdateBlockData};
 
     async fn get_store() -> SurrealIdentityChainStore {
         let mem_db = get_memory_db("test", "identity_chain")
@@ -226,5 +240,8 @@ mod tests {
         let last_block = store.get_latest_block().await;
         assert!(last_block.is_ok());
         assert_eq!(last_block.as_ref().unwrap().id, 2);
+
+        let chain = store.get_chain().await.unwrap();
+        assert_eq!(chain.blocks().len(), 2);
     }
 }
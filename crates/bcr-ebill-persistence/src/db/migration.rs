@@ -0,0 +1,229 @@
+use super::super::{Error, Result};
+use crate::util::date::{DateTimeUtc, now};
+use log::info;
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any};
+
+const SCHEMA_VERSION_TABLE: &str = "schema_version";
+const SCHEMA_VERSION_ID: &str = "current";
+
+/// A single schema migration, shared by the SurrealDB migrations in this module and the SQLite
+/// migrations in `sqlite::migration`.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    /// The schema version after the migration was applied
+    pub version: u32,
+    /// A short description of what the migration does
+    pub description: &'static str,
+    /// The statements to run, SurrealQL for SurrealDB and SQL for SQLite
+    pub query: &'static str,
+}
+
+/// All SurrealDB migrations, ordered by version. The queries have to be idempotent, since a
+/// migration can be interrupted after the query was applied, but before the new version was
+/// recorded. New migrations are appended with the next version.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "add indices for chain and notification lookups",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS bill_chain_bill_id ON TABLE bill_chain COLUMNS bill_id, block_id;
+        DEFINE INDEX IF NOT EXISTS company_chain_company_id ON TABLE company_chain COLUMNS company_id, block_id;
+        DEFINE INDEX IF NOT EXISTS notifications_reference ON TABLE notifications COLUMNS reference_id, notification_type, active;
+    "#,
+    },
+    Migration {
+        version: 2,
+        description: "add indices for the materialised bill state",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS bill_state_identity ON TABLE bill_state COLUMNS identity_node_id, role;
+        DEFINE INDEX IF NOT EXISTS bill_state_bill_id ON TABLE bill_state COLUMNS bill_id;
+    "#,
+    },
+    Migration {
+        version: 3,
+        description: "clear the bill state, so it's rebuilt with the fields for searching",
+        query: r#"
+        DELETE bill_state;
+    "#,
+    },
+    Migration {
+        version: 4,
+        description: "add indices for the full-text search index",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS search_index_term ON TABLE search_index COLUMNS term;
+        DEFINE INDEX IF NOT EXISTS search_index_item ON TABLE search_index COLUMNS item_type, item_id;
+    "#,
+    },
+    Migration {
+        version: 5,
+        description: "add a unique index for API token hashes",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS api_tokens_token_hash ON TABLE api_tokens COLUMNS token_hash UNIQUE;
+    "#,
+    },
+    Migration {
+        version: 6,
+        description: "add indexes for webhook deliveries",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS webhook_deliveries_webhook_id ON TABLE webhook_deliveries COLUMNS webhook_id;
+        DEFINE INDEX IF NOT EXISTS webhook_deliveries_due ON TABLE webhook_deliveries COLUMNS status, next_attempt_at;
+    "#,
+    },
+    Migration {
+        version: 7,
+        description: "add indexes for the Nostr outbox",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS nostr_outbox_due ON TABLE nostr_outbox COLUMNS status, next_attempt_at;
+        DEFINE INDEX IF NOT EXISTS nostr_outbox_created_at ON TABLE nostr_outbox COLUMNS created_at;
+    "#,
+    },
+    Migration {
+        version: 8,
+        description: "add an index for bills issued from templates",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS bill_template_issues_template_id ON TABLE bill_template_issues COLUMNS template_id, issued_at;
+    "#,
+    },
+    Migration {
+        version: 9,
+        description: "add an index for pending Nostr events",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS nostr_pending_events_received_at ON TABLE nostr_pending_events COLUMNS received_at;
+    "#,
+    },
+    Migration {
+        version: 10,
+        description: "add an index for bills issued from batches",
+        query: r#"
+        DEFINE INDEX IF NOT EXISTS bill_batch_issues_batch_id ON TABLE bill_batch_issues COLUMNS batch_id;
+    "#,
+    },
+];
+
+/// The schema version this binary expects the database to be in
+pub fn latest_schema_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Returns the currently applied schema version, 0 if no migration was applied yet
+pub async fn get_schema_version(db: &Surreal<Any>) -> Result<u32> {
+    let result: Option<SchemaVersionDb> =
+        db.select((SCHEMA_VERSION_TABLE, SCHEMA_VERSION_ID)).await?;
+    Ok(result.map(|v| v.version).unwrap_or(0))
+}
+
+async fn set_schema_version(db: &Surreal<Any>, version: u32) -> Result<()> {
+    let entity = SchemaVersionDb {
+        version,
+        applied_at: now(),
+    };
+    let _: Option<SchemaVersionDb> = db
+        .upsert((SCHEMA_VERSION_TABLE, SCHEMA_VERSION_ID))
+        .content(entity)
+        .await?;
+    Ok(())
+}
+
+/// Applies all migrations newer than the current schema version in order. Fails, if the
+/// database has a newer schema version than this binary supports.
+pub async fn run_migrations(db: &Surreal<Any>) -> Result<()> {
+    run_given_migrations(db, MIGRATIONS).await
+}
+
+async fn run_given_migrations(db: &Surreal<Any>, migrations: &[Migration]) -> Result<()> {
+    let current = get_schema_version(db).await?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        return Err(Error::SchemaVersionTooNew(current, latest));
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        info!(
+            "Running database migration {}: {}",
+            migration.version, migration.description
+        );
+        db.query(migration.query).await?.check()?;
+        set_schema_version(db, migration.version).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SchemaVersionDb {
+    pub version: u32,
+    pub applied_at: DateTimeUtc,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+
+    async fn get_db() -> Surreal<Any> {
+        get_memory_db("test", "migration")
+            .await
+            .expect("could not create memory db")
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let expected: Vec<u32> = (1..=MIGRATIONS.len() as u32).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_on_empty_db() {
+        let db = get_db().await;
+        assert_eq!(get_schema_version(&db).await.unwrap(), 0);
+        run_migrations(&db).await.expect("migrations failed");
+        assert_eq!(
+            get_schema_version(&db).await.unwrap(),
+            latest_schema_version()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_twice() {
+        let db = get_db().await;
+        run_migrations(&db).await.expect("migrations failed");
+        run_migrations(&db).await.expect("migrations failed");
+        assert_eq!(
+            get_schema_version(&db).await.unwrap(),
+            latest_schema_version()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_only_new_migrations() {
+        let db = get_db().await;
+        set_schema_version(&db, 1).await.unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "already applied",
+                query: "THROW 'should not run';",
+            },
+            Migration {
+                version: 2,
+                description: "new",
+                query: "CREATE migration_test CONTENT { done: true };",
+            },
+        ];
+        run_given_migrations(&db, &migrations)
+            .await
+            .expect("migrations failed");
+        assert_eq!(get_schema_version(&db).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_newer_schema_version() {
+        let db = get_db().await;
+        set_schema_version(&db, latest_schema_version() + 1)
+            .await
+            .unwrap();
+        let result = run_migrations(&db).await;
+        assert!(matches!(result, Err(Error::SchemaVersionTooNew(_, _))));
+    }
+}
@@ -7,16 +7,27 @@ use surrealdb::{
     engine::any::{Any, connect},
 };
 
+pub mod api_token;
 pub mod backup;
 pub mod bill;
+pub mod bill_batch;
 pub mod bill_chain;
+pub mod bill_template;
 pub mod company;
 pub mod company_chain;
 pub mod contact;
 pub mod identity;
 pub mod identity_chain;
+pub mod migration;
 pub mod nostr_event_offset;
+pub mod nostr_outbox;
+pub mod nostr_pending_event;
+pub mod nostr_quarantine;
 pub mod notification;
+pub mod search_index;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod webhook;
 
 /// Configuration for the SurrealDB connection string, namespace and
 /// database name
@@ -46,6 +57,14 @@ impl Default for SurrealDbConfig {
     }
 }
 
+/// Connection strings starting with this scheme are served by the SQLite backend
+pub const SQLITE_SCHEME: &str = "sqlite:";
+
+/// Returns true, if the given connection string points to a SQLite database instead of SurrealDB
+pub fn is_sqlite_connection(connection_string: &str) -> bool {
+    connection_string.starts_with(SQLITE_SCHEME)
+}
+
 /// Connect to the SurrealDB instance using the provided configuration.
 pub async fn get_surreal_db(config: &SurrealDbConfig) -> Result<Surreal<Any>> {
     let db = connect(&config.connection_string).await.map_err(|e| {
@@ -58,6 +77,17 @@ pub async fn get_surreal_db(config: &SurrealDbConfig) -> Result<Surreal<Any>> {
     Ok(db)
 }
 
+/// Connect to the SurrealDB instance using the provided configuration and bring the
+/// database schema up to date by running all pending migrations.
+pub async fn get_migrated_surreal_db(config: &SurrealDbConfig) -> Result<Surreal<Any>> {
+    let db = get_surreal_db(config).await?;
+    migration::run_migrations(&db).await.map_err(|e| {
+        error!("Error migrating SurrealDB with config: {config:?}. Error: {e}");
+        e
+    })?;
+    Ok(db)
+}
+
 /// This is handy for testing db queries. I have added the mem:// storage backend
 /// feature as a dev dependency in Cargo.toml. The mem storage backend is still a
 /// drag in terms of compile time but I think it is worth it for testing.
@@ -193,6 +223,26 @@ mod tests {
         let _ = get_surreal_db(&config).await.expect("could not create db");
     }
 
+    #[tokio::test]
+    async fn test_get_migrated_surreal_db() {
+        let config = SurrealDbConfig::new("mem://");
+        let db = get_migrated_surreal_db(&config)
+            .await
+            .expect("could not create db");
+        assert_eq!(
+            migration::get_schema_version(&db).await.unwrap(),
+            migration::latest_schema_version()
+        );
+    }
+
+    #[test]
+    fn test_is_sqlite_connection() {
+        assert!(is_sqlite_connection("sqlite://data/ebills.db"));
+        assert!(is_sqlite_connection("sqlite::memory:"));
+        assert!(!is_sqlite_connection("rocksdb://data/surrealdb"));
+        assert!(!is_sqlite_connection("ws://localhost:8800"));
+    }
+
     #[tokio::test]
     async fn test_get_memory_db() {
         let _ = get_memory_db("test", "test")
@@ -54,6 +54,16 @@ impl NostrEventOffsetStoreApi for SurrealNostrEventOffsetStore {
             .await?;
         Ok(())
     }
+
+    async fn get_all(&self) -> Result<Vec<NostrEventOffset>> {
+        let result: Vec<NostrEventOffsetDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY time ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|e| e.into()).collect())
+    }
 }
 
 /// A nostr event offset.
@@ -138,6 +148,28 @@ mod tests {
         assert!(is_processed, "existing event should be known");
     }
 
+    #[tokio::test]
+    async fn test_get_all() {
+        let store = get_store().await;
+        let all = store.get_all().await.expect("could not get all events");
+        assert!(all.is_empty());
+
+        for (event_id, time) in [("second", 2000), ("first", 1000)] {
+            store
+                .add_event(NostrEventOffset {
+                    event_id: event_id.to_string(),
+                    time,
+                    success: true,
+                })
+                .await
+                .expect("could not add event offset");
+        }
+        let all = store.get_all().await.expect("could not get all events");
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].event_id, "first");
+        assert_eq!(all[1].time, 2000);
+    }
+
     async fn get_store() -> SurrealNostrEventOffsetStore {
         let mem_db = get_memory_db("test", "nostr_event_offset")
             .await
@@ -0,0 +1,179 @@
+use super::Result;
+use crate::constants::{DB_LIMIT, DB_TABLE, DB_TIMESTAMP};
+use crate::nostr::{NostrOutboxEntry, NostrOutboxStatus, NostrOutboxStoreApi};
+use async_trait::async_trait;
+use bcr_ebill_core::notification::EventType;
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any, sql::Thing};
+
+#[derive(Clone)]
+pub struct SurrealNostrOutboxStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealNostrOutboxStore {
+    const TABLE: &'static str = "nostr_outbox";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl NostrOutboxStoreApi for SurrealNostrOutboxStore {
+    async fn insert(&self, entry: &NostrOutboxEntry) -> Result<()> {
+        let entity: NostrOutboxEntryDb = entry.into();
+        let _: Option<NostrOutboxEntryDb> = self
+            .db
+            .create((Self::TABLE, entry.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, entry: &NostrOutboxEntry) -> Result<()> {
+        let entity: NostrOutboxEntryDb = entry.into();
+        let _: Option<NostrOutboxEntryDb> = self
+            .db
+            .update((Self::TABLE, entry.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_due(&self, timestamp: u64, limit: u64) -> Result<Vec<NostrOutboxEntry>> {
+        let result: Vec<NostrOutboxEntryDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE status = 'Pending' AND next_attempt_at <= $timestamp ORDER BY next_attempt_at ASC LIMIT $limit")
+            .bind((DB_TABLE, Self::TABLE))
+            .bind((DB_TIMESTAMP, timestamp))
+            .bind((DB_LIMIT, limit))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn list_unsent(&self, limit: u64) -> Result<Vec<NostrOutboxEntry>> {
+        let result: Vec<NostrOutboxEntryDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE status != 'Sent' ORDER BY created_at DESC LIMIT $limit")
+            .bind((DB_TABLE, Self::TABLE))
+            .bind((DB_LIMIT, limit))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn get_all(&self) -> Result<Vec<NostrOutboxEntry>> {
+        let result: Vec<NostrOutboxEntryDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY created_at ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|e| e.into()).collect())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct NostrOutboxEntryDb {
+    pub id: Thing,
+    pub recipient: String,
+    pub recipient_relays: Vec<String>,
+    pub event_type: EventType,
+    pub payload: String,
+    pub status: NostrOutboxStatus,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub sent_at: Option<u64>,
+}
+
+impl From<&NostrOutboxEntry> for NostrOutboxEntryDb {
+    fn from(value: &NostrOutboxEntry) -> Self {
+        Self {
+            id: (SurrealNostrOutboxStore::TABLE, value.id.as_str()).into(),
+            recipient: value.recipient.clone(),
+            recipient_relays: value.recipient_relays.clone(),
+            event_type: value.event_type.clone(),
+            payload: value.payload.clone(),
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_error: value.last_error.clone(),
+            created_at: value.created_at,
+            sent_at: value.sent_at,
+        }
+    }
+}
+
+impl From<NostrOutboxEntryDb> for NostrOutboxEntry {
+    fn from(value: NostrOutboxEntryDb) -> Self {
+        Self {
+            id: value.id.id.to_raw(),
+            recipient: value.recipient,
+            recipient_relays: value.recipient_relays,
+            event_type: value.event_type,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            sent_at: value.sent_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+
+    async fn get_store() -> SurrealNostrOutboxStore {
+        let mem_db = get_memory_db("test", "nostr_outbox")
+            .await
+            .expect("could not create memory db");
+        SurrealNostrOutboxStore::new(mem_db)
+    }
+
+    fn get_entry(id: &str, timestamp: u64) -> NostrOutboxEntry {
+        NostrOutboxEntry::new(
+            id,
+            "recipient",
+            vec!["ws://localhost:8080".to_string()],
+            EventType::BillSigned,
+            "{}",
+            timestamp,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_outbox() {
+        let store = get_store().await;
+        let first = get_entry("first", 1000);
+        let mut second = get_entry("second", 2000);
+        let mut third = get_entry("third", 3000);
+        store.insert(&first).await.unwrap();
+        store.insert(&second).await.unwrap();
+        store.insert(&third).await.unwrap();
+
+        assert_eq!(store.get_due(1500, 10).await.unwrap(), vec![first.clone()]);
+
+        second.status = NostrOutboxStatus::Sent;
+        second.attempts = 1;
+        second.sent_at = Some(2100);
+        store.update(&second).await.unwrap();
+        third.status = NostrOutboxStatus::Failed;
+        third.last_error = Some("no relay confirmed".to_string());
+        store.update(&third).await.unwrap();
+        assert_eq!(store.get_due(5000, 10).await.unwrap(), vec![first.clone()]);
+
+        assert_eq!(
+            store.list_unsent(10).await.unwrap(),
+            vec![third.clone(), first.clone()]
+        );
+        assert_eq!(store.get_all().await.unwrap(), vec![first, second, third]);
+    }
+}
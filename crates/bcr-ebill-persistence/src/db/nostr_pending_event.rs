@@ -0,0 +1,124 @@
+use super::Result;
+use crate::constants::DB_TABLE;
+use crate::nostr::{NostrPendingEventStoreApi, PendingEvent};
+use async_trait::async_trait;
+use bcr_ebill_core::notification::EventType;
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any, sql::Thing};
+
+#[derive(Clone)]
+pub struct SurrealNostrPendingEventStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealNostrPendingEventStore {
+    const TABLE: &'static str = "nostr_pending_events";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl NostrPendingEventStoreApi for SurrealNostrPendingEventStore {
+    async fn add(&self, event: PendingEvent) -> Result<()> {
+        let id = event.id.to_owned();
+        let entity: PendingEventDb = event.into();
+        let _: Option<PendingEventDb> = self.db.upsert((Self::TABLE, id)).content(entity).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<PendingEvent>> {
+        let result: Vec<PendingEventDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY received_at ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let _: Option<PendingEventDb> = self.db.delete((Self::TABLE, id)).await?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct PendingEventDb {
+    pub id: Thing,
+    pub bill_id: String,
+    pub node_id: String,
+    pub sender: String,
+    pub event_type: EventType,
+    pub payload: String,
+    pub received_at: u64,
+}
+
+impl From<PendingEvent> for PendingEventDb {
+    fn from(value: PendingEvent) -> Self {
+        Self {
+            id: (SurrealNostrPendingEventStore::TABLE, value.id.as_str()).into(),
+            bill_id: value.bill_id,
+            node_id: value.node_id,
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            received_at: value.received_at,
+        }
+    }
+}
+
+impl From<PendingEventDb> for PendingEvent {
+    fn from(value: PendingEventDb) -> Self {
+        Self {
+            id: value.id.id.to_raw(),
+            bill_id: value.bill_id,
+            node_id: value.node_id,
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            received_at: value.received_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+
+    async fn get_store() -> SurrealNostrPendingEventStore {
+        let mem_db = get_memory_db("test", "nostr_pending_events")
+            .await
+            .expect("could not create memory db");
+        SurrealNostrPendingEventStore::new(mem_db)
+    }
+
+    fn get_event(id: &str, received_at: u64) -> PendingEvent {
+        PendingEvent {
+            id: id.to_string(),
+            bill_id: "1234".to_string(),
+            node_id: "node_id".to_string(),
+            sender: "sender".to_string(),
+            event_type: EventType::BillPaymentRequested,
+            payload: "{}".to_string(),
+            received_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_events() {
+        let store = get_store().await;
+        store.add(get_event("second", 2000)).await.unwrap();
+        store.add(get_event("first", 1000)).await.unwrap();
+
+        assert_eq!(
+            store.list().await.unwrap(),
+            vec![get_event("first", 1000), get_event("second", 2000)]
+        );
+
+        store.remove("first").await.unwrap();
+        assert_eq!(store.list().await.unwrap(), vec![get_event("second", 2000)]);
+    }
+}
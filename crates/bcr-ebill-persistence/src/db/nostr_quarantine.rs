@@ -0,0 +1,130 @@
+use super::Result;
+use crate::constants::DB_TABLE;
+use crate::nostr::{NostrQuarantineStoreApi, QuarantinedEvent};
+use async_trait::async_trait;
+use bcr_ebill_core::notification::EventType;
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any, sql::Thing};
+
+#[derive(Clone)]
+pub struct SurrealNostrQuarantineStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealNostrQuarantineStore {
+    const TABLE: &'static str = "nostr_quarantine";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl NostrQuarantineStoreApi for SurrealNostrQuarantineStore {
+    async fn add(&self, event: QuarantinedEvent) -> Result<()> {
+        let id = event.id.to_owned();
+        let entity: QuarantinedEventDb = event.into();
+        let _: Option<QuarantinedEventDb> =
+            self.db.upsert((Self::TABLE, id)).content(entity).await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<QuarantinedEvent>> {
+        let result: Option<QuarantinedEventDb> = self.db.select((Self::TABLE, id)).await?;
+        Ok(result.map(|e| e.into()))
+    }
+
+    async fn list(&self) -> Result<Vec<QuarantinedEvent>> {
+        let result: Vec<QuarantinedEventDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY received_at DESC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let _: Option<QuarantinedEventDb> = self.db.delete((Self::TABLE, id)).await?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct QuarantinedEventDb {
+    pub id: Thing,
+    pub sender: String,
+    pub event_type: EventType,
+    pub payload: String,
+    pub reason: String,
+    pub received_at: u64,
+}
+
+impl From<QuarantinedEvent> for QuarantinedEventDb {
+    fn from(value: QuarantinedEvent) -> Self {
+        Self {
+            id: (SurrealNostrQuarantineStore::TABLE, value.id.as_str()).into(),
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            reason: value.reason,
+            received_at: value.received_at,
+        }
+    }
+}
+
+impl From<QuarantinedEventDb> for QuarantinedEvent {
+    fn from(value: QuarantinedEventDb) -> Self {
+        Self {
+            id: value.id.id.to_raw(),
+            sender: value.sender,
+            event_type: value.event_type,
+            payload: value.payload,
+            reason: value.reason,
+            received_at: value.received_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+
+    async fn get_store() -> SurrealNostrQuarantineStore {
+        let mem_db = get_memory_db("test", "nostr_quarantine")
+            .await
+            .expect("could not create memory db");
+        SurrealNostrQuarantineStore::new(mem_db)
+    }
+
+    fn get_event(id: &str, received_at: u64) -> QuarantinedEvent {
+        QuarantinedEvent {
+            id: id.to_string(),
+            sender: "sender".to_string(),
+            event_type: EventType::BillSigned,
+            payload: "{}".to_string(),
+            reason: "unknown sender".to_string(),
+            received_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quarantine() {
+        let store = get_store().await;
+        store.add(get_event("first", 1000)).await.unwrap();
+        store.add(get_event("second", 2000)).await.unwrap();
+
+        assert_eq!(
+            store.get("first").await.unwrap(),
+            Some(get_event("first", 1000))
+        );
+        let events = store.list().await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id, "second");
+
+        store.remove("second").await.unwrap();
+        assert!(store.get("second").await.unwrap().is_none());
+        assert_eq!(store.list().await.unwrap().len(), 1);
+    }
+}
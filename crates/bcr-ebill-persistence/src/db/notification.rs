@@ -9,7 +9,7 @@ use crate::{
     util::date::{DateTimeUtc, now},
 };
 use bcr_ebill_core::notification::ActionType;
-use bcr_ebill_core::notification::{Notification, NotificationType};
+use bcr_ebill_core::notification::{EmailPreference, Notification, NotificationType};
 
 #[derive(Clone)]
 pub struct SurrealNotificationStore {
@@ -19,6 +19,7 @@ pub struct SurrealNotificationStore {
 impl SurrealNotificationStore {
     const TABLE: &'static str = "notifications";
     const SENT_TABLE: &'static str = "sent_notifications";
+    const EMAIL_PREFERENCES_TABLE: &'static str = "email_preferences";
 
     pub fn new(db: Surreal<Any>) -> Self {
         Self { db }
@@ -148,6 +149,24 @@ impl NotificationStoreApi for SurrealNotificationStore {
             .take(0)?;
         Ok(res.is_some())
     }
+
+    async fn get_email_preference(&self, node_id: &str) -> Result<Option<EmailPreference>> {
+        let result: Option<EmailPreferenceDb> = self
+            .db
+            .select((Self::EMAIL_PREFERENCES_TABLE, node_id))
+            .await?;
+        Ok(result.map(|p| p.into()))
+    }
+
+    async fn set_email_preference(&self, preference: &EmailPreference) -> Result<()> {
+        let entity: EmailPreferenceDb = preference.into();
+        let _: Option<EmailPreferenceDb> = self
+            .db
+            .upsert((Self::EMAIL_PREFERENCES_TABLE, preference.node_id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -196,6 +215,37 @@ impl From<Notification> for NotificationDb {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EmailPreferenceDb {
+    pub id: Thing,
+    pub enabled: bool,
+    pub updated_at: u64,
+}
+
+impl From<&EmailPreference> for EmailPreferenceDb {
+    fn from(value: &EmailPreference) -> Self {
+        Self {
+            id: (
+                SurrealNotificationStore::EMAIL_PREFERENCES_TABLE,
+                value.node_id.as_str(),
+            )
+                .into(),
+            enabled: value.enabled,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<EmailPreferenceDb> for EmailPreference {
+    fn from(value: EmailPreferenceDb) -> Self {
+        Self {
+            node_id: value.id.id.to_raw(),
+            enabled: value.enabled,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
 /// Tracks sending of notifications for a blockchain based resource.
 /// The block height is used to track the block in which the notification was sent.
 /// The same notification can be sent multiple times, but only if the underlying
@@ -263,6 +313,32 @@ mod tests {
         assert!(!sent);
     }
 
+    #[tokio::test]
+    async fn test_email_preference() {
+        let store = get_store().await;
+        assert!(
+            store
+                .get_email_preference("node_id")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let mut preference = EmailPreference {
+            node_id: "node_id".to_string(),
+            enabled: true,
+            updated_at: 1000,
+        };
+        store.set_email_preference(&preference).await.unwrap();
+        preference.enabled = false;
+        preference.updated_at = 2000;
+        store.set_email_preference(&preference).await.unwrap();
+        assert_eq!(
+            store.get_email_preference("node_id").await.unwrap(),
+            Some(preference)
+        );
+    }
+
     #[tokio::test]
     async fn test_inserts_and_queries_notification() {
         let store = get_store().await;
@@ -0,0 +1,285 @@
+use super::Result;
+use crate::constants::{DB_ITEM_ID, DB_ITEM_TYPE, DB_ITEM_TYPES, DB_TABLE};
+use crate::search_index::SearchIndexStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::{
+    GeneralSearchFilterItemType,
+    search::{SearchDocument, SearchField, SearchPosting},
+};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any};
+
+#[derive(Clone)]
+pub struct SurrealSearchIndexStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealSearchIndexStore {
+    const TABLE: &'static str = "search_index";
+    const VERSION_TABLE: &'static str = "search_index_version";
+    const VERSION_ID: &'static str = "current";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SearchIndexStoreApi for SurrealSearchIndexStore {
+    async fn index_document(&self, document: &SearchDocument) -> Result<()> {
+        let entities: Vec<SearchPostingDb> = document
+            .terms
+            .iter()
+            .map(|t| SearchPostingDb {
+                item_type: document.item_type,
+                item_id: document.item_id.clone(),
+                field: t.field,
+                term: t.term.clone(),
+            })
+            .collect();
+        self.db
+            .query(format!(
+                r#"BEGIN TRANSACTION;
+                DELETE FROM type::table($table) WHERE item_type = $item_type AND item_id = $item_id;
+                INSERT INTO {} $postings;
+                COMMIT TRANSACTION;"#,
+                Self::TABLE
+            ))
+            .bind((DB_TABLE, Self::TABLE))
+            .bind((DB_ITEM_TYPE, document.item_type))
+            .bind((DB_ITEM_ID, document.item_id.clone()))
+            .bind(("postings", entities))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn remove_document(
+        &self,
+        item_type: GeneralSearchFilterItemType,
+        item_id: &str,
+    ) -> Result<()> {
+        self.db
+            .query(
+                "DELETE FROM type::table($table) WHERE item_type = $item_type AND item_id = $item_id",
+            )
+            .bind((DB_TABLE, Self::TABLE))
+            .bind((DB_ITEM_TYPE, item_type))
+            .bind((DB_ITEM_ID, item_id.to_owned()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        tokens: &[String],
+        item_types: &[GeneralSearchFilterItemType],
+    ) -> Result<Vec<SearchPosting>> {
+        if tokens.is_empty() || item_types.is_empty() {
+            return Ok(vec![]);
+        }
+        let token_conditions: Vec<String> = (0..tokens.len())
+            .map(|i| format!("string::starts_with(term, $token_{i})"))
+            .collect();
+        let mut query = self
+            .db
+            .query(format!(
+                "SELECT * FROM type::table($table) WHERE item_type IN $item_types AND ({})",
+                token_conditions.join(" OR ")
+            ))
+            .bind((DB_TABLE, Self::TABLE))
+            .bind((DB_ITEM_TYPES, item_types.to_vec()));
+        for (i, token) in tokens.iter().enumerate() {
+            query = query.bind((format!("token_{i}"), token.to_owned()));
+        }
+        let result: Vec<SearchPostingDb> = query.await?.take(0)?;
+        Ok(result.into_iter().map(|p| p.into()).collect())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.db
+            .query("DELETE FROM type::table($table); DELETE FROM type::table($version_table);")
+            .bind((DB_TABLE, Self::TABLE))
+            .bind(("version_table", Self::VERSION_TABLE))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn get_index_version(&self) -> Result<u32> {
+        let result: Option<SearchIndexVersionDb> = self
+            .db
+            .select((Self::VERSION_TABLE, Self::VERSION_ID))
+            .await?;
+        Ok(result.map(|v| v.version).unwrap_or(0))
+    }
+
+    async fn set_index_version(&self, version: u32) -> Result<()> {
+        let _: Option<SearchIndexVersionDb> = self
+            .db
+            .upsert((Self::VERSION_TABLE, Self::VERSION_ID))
+            .content(SearchIndexVersionDb { version })
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPostingDb {
+    pub item_type: GeneralSearchFilterItemType,
+    pub item_id: String,
+    pub field: SearchField,
+    pub term: String,
+}
+
+impl From<SearchPostingDb> for SearchPosting {
+    fn from(value: SearchPostingDb) -> Self {
+        Self {
+            item_type: value.item_type,
+            item_id: value.item_id,
+            field: value.field,
+            term: value.term,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SearchIndexVersionDb {
+    pub version: u32,
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+    use bcr_ebill_core::search::{SearchTerm, tokenize};
+
+    async fn get_store() -> SurrealSearchIndexStore {
+        let mem_db = get_memory_db("test", "search_index")
+            .await
+            .expect("could not create memory db");
+        SurrealSearchIndexStore::new(mem_db)
+    }
+
+    pub fn get_document(
+        item_type: GeneralSearchFilterItemType,
+        item_id: &str,
+        name: &str,
+    ) -> SearchDocument {
+        let mut document = SearchDocument::new(item_type, item_id);
+        document.add(SearchField::Name, name);
+        document.add(SearchField::City, "Wien");
+        document
+    }
+
+    #[tokio::test]
+    async fn test_index_and_search() {
+        let store = get_store().await;
+        store
+            .index_document(&get_document(
+                GeneralSearchFilterItemType::Contact,
+                "contact1",
+                "Jürgen Müller",
+            ))
+            .await
+            .unwrap();
+        store
+            .index_document(&get_document(
+                GeneralSearchFilterItemType::Company,
+                "company1",
+                "Müller GmbH",
+            ))
+            .await
+            .unwrap();
+
+        let all = store
+            .search(
+                &tokenize("mül"),
+                &[
+                    GeneralSearchFilterItemType::Contact,
+                    GeneralSearchFilterItemType::Company,
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all.iter().all(|p| p.term == "muller"));
+
+        let contacts = store
+            .search(
+                &tokenize("muller jurg"),
+                &[GeneralSearchFilterItemType::Contact],
+            )
+            .await
+            .unwrap();
+        assert_eq!(contacts.len(), 2);
+        assert!(contacts.iter().all(|p| p.item_id == "contact1"));
+    }
+
+    #[tokio::test]
+    async fn test_index_replaces_and_remove() {
+        let store = get_store().await;
+        let mut document = get_document(GeneralSearchFilterItemType::Contact, "contact1", "Hayek");
+        store.index_document(&document).await.unwrap();
+        document.terms = vec![SearchTerm {
+            field: SearchField::Name,
+            term: "mises".to_string(),
+        }];
+        store.index_document(&document).await.unwrap();
+        let types = [GeneralSearchFilterItemType::Contact];
+        assert!(
+            store
+                .search(&tokenize("hayek"), &types)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            store
+                .search(&tokenize("mises"), &types)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        store
+            .remove_document(GeneralSearchFilterItemType::Contact, "contact1")
+            .await
+            .unwrap();
+        assert!(
+            store
+                .search(&tokenize("mises"), &types)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_index_version_and_clear() {
+        let store = get_store().await;
+        assert_eq!(store.get_index_version().await.unwrap(), 0);
+        store.set_index_version(1).await.unwrap();
+        store
+            .index_document(&get_document(
+                GeneralSearchFilterItemType::Bill,
+                "bill1",
+                "Hayek",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(store.get_index_version().await.unwrap(), 1);
+
+        store.clear().await.unwrap();
+        assert_eq!(store.get_index_version().await.unwrap(), 0);
+        assert!(
+            store
+                .search(&tokenize("hayek"), &[GeneralSearchFilterItemType::Bill])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
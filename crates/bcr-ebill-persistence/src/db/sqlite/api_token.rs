@@ -0,0 +1,187 @@
+use super::super::super::Result;
+use super::{SqliteDb, from_json, to_json};
+use crate::api_token::ApiTokenStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::api_token::ApiToken;
+use rusqlite::{OptionalExtension, Row, params};
+
+#[derive(Clone)]
+pub struct SqliteApiTokenStore {
+    db: SqliteDb,
+}
+
+impl SqliteApiTokenStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+const COLUMNS: &str = "id, name, scopes, created_at, last_used_at";
+
+/// Reads a token from a row of the selected columns, the scopes are parsed separately, since
+/// they can't fail with a rusqlite error
+fn read_row(row: &Row) -> rusqlite::Result<(ApiToken, String)> {
+    Ok((
+        ApiToken {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            scopes: vec![],
+            created_at: row.get(3)?,
+            last_used_at: row.get(4)?,
+        },
+        row.get(2)?,
+    ))
+}
+
+fn with_scopes((mut token, scopes): (ApiToken, String)) -> Result<ApiToken> {
+    token.scopes = from_json(&scopes)?;
+    Ok(token)
+}
+
+#[async_trait]
+impl ApiTokenStoreApi for SqliteApiTokenStore {
+    async fn insert(&self, token: &ApiToken, token_hash: &str) -> Result<()> {
+        let token = token.clone();
+        let token_hash = token_hash.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO api_tokens (id, name, token_hash, scopes, created_at, last_used_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        token.id,
+                        token.name,
+                        token_hash,
+                        to_json(&token.scopes)?,
+                        token.created_at,
+                        token.last_used_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let token_hash = token_hash.to_owned();
+        self.db
+            .call(move |conn| {
+                let row = conn
+                    .query_row(
+                        &format!("SELECT {COLUMNS} FROM api_tokens WHERE token_hash = ?1"),
+                        [&token_hash],
+                        read_row,
+                    )
+                    .optional()?;
+                row.map(with_scopes).transpose()
+            })
+            .await
+    }
+
+    async fn list(&self) -> Result<Vec<ApiToken>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {COLUMNS} FROM api_tokens ORDER BY created_at ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([], read_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(with_scopes).collect()
+            })
+            .await
+    }
+
+    async fn list_with_hashes(&self) -> Result<Vec<(ApiToken, String)>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {COLUMNS}, token_hash FROM api_tokens ORDER BY created_at ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([], |row| Ok((read_row(row)?, row.get::<_, String>(5)?)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter()
+                    .map(|(token, token_hash)| Ok((with_scopes(token)?, token_hash)))
+                    .collect()
+            })
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute("DELETE FROM api_tokens WHERE id = ?1", [&id])?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn set_last_used(&self, id: &str, timestamp: u64) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE api_tokens SET last_used_at = ?1 WHERE id = ?2",
+                    params![timestamp, id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::get_memory_sqlite_db;
+    use bcr_ebill_core::api_token::ApiTokenScope;
+
+    async fn get_store() -> SqliteApiTokenStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteApiTokenStore::new(db)
+    }
+
+    fn get_token(id: &str, created_at: u64) -> ApiToken {
+        ApiToken {
+            id: id.to_string(),
+            name: format!("token {id}"),
+            scopes: vec![ApiTokenScope::BillsWrite],
+            created_at,
+            last_used_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_api_tokens() {
+        let store = get_store().await;
+        store
+            .insert(&get_token("second", 2000), "hash2")
+            .await
+            .unwrap();
+        store
+            .insert(&get_token("first", 1000), "hash1")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_by_hash("hash2").await.unwrap(),
+            Some(get_token("second", 2000))
+        );
+        assert!(store.get_by_hash("unknown").await.unwrap().is_none());
+
+        store.set_last_used("first", 3000).await.unwrap();
+        let tokens = store.list().await.unwrap();
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].id, "first");
+        assert_eq!(tokens[0].last_used_at, Some(3000));
+        let tokens = store.list_with_hashes().await.unwrap();
+        assert_eq!(tokens[0].0.id, "first");
+        assert_eq!(tokens[0].1, "hash1");
+
+        store.remove("first").await.unwrap();
+        assert_eq!(store.list().await.unwrap().len(), 1);
+    }
+}
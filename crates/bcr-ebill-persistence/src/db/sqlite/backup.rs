@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use super::super::super::Result;
+use super::{SqliteDb, migration};
+use crate::backup::BackupStoreApi;
+use async_trait::async_trait;
+use rusqlite::{DatabaseName, backup::Progress};
+
+pub struct SqliteBackupStore {
+    db: SqliteDb,
+}
+
+impl SqliteBackupStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl BackupStoreApi for SqliteBackupStore {
+    /// returns a consistent copy of the SQLite database file as a byte vector
+    async fn backup(&self) -> Result<Vec<u8>> {
+        let file_path =
+            std::env::temp_dir().join(format!("bcr-backup-{}.db", uuid::Uuid::new_v4()));
+        self.db
+            .call(move |conn| {
+                conn.execute("VACUUM INTO ?1", [file_path.to_string_lossy().to_string()])?;
+                let bytes = std::fs::read(&file_path);
+                std::fs::remove_file(&file_path)?;
+                Ok(bytes?)
+            })
+            .await
+    }
+
+    /// Restores the database from the given SQLite database file
+    async fn restore(&self, file_path: &Path) -> Result<()> {
+        let file_path = file_path.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.restore(DatabaseName::Main, file_path, None::<fn(Progress)>)?;
+                // the restored file can come from an older version
+                migration::run_migrations(conn)
+            })
+            .await
+    }
+
+    /// SQLite has only one database per file, so this removes all data, keeping the schema
+    async fn drop_db(&self, _name: &str) -> Result<()> {
+        self.db
+            .call(|conn| {
+                let tx = conn.transaction()?;
+                let mut stmt = tx.prepare(
+                    "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+                )?;
+                let tables = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                drop(stmt);
+                for table in tables.iter() {
+                    tx.execute(&format!("DELETE FROM \"{table}\""), [])?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ContactStoreApi,
+        db::{
+            contact::tests::get_baseline_contact,
+            sqlite::{contact::SqliteContactStore, get_memory_sqlite_db},
+        },
+        tests::tests::TEST_NODE_ID_SECP,
+    };
+
+    #[tokio::test]
+    async fn test_backup_drop_and_restore() {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        let store = SqliteBackupStore::new(db.clone());
+        let contact_store = SqliteContactStore::new(db);
+        contact_store
+            .insert(TEST_NODE_ID_SECP, get_baseline_contact())
+            .await
+            .unwrap();
+
+        let bytes = store.backup().await.expect("could not create backup");
+        assert!(!bytes.is_empty());
+
+        store.drop_db("ebills").await.expect("could not drop db");
+        assert!(contact_store.get_map().await.unwrap().is_empty());
+
+        let file_path =
+            std::env::temp_dir().join(format!("bcr-restore-{}.db", uuid::Uuid::new_v4()));
+        std::fs::write(&file_path, bytes).unwrap();
+        store
+            .restore(&file_path)
+            .await
+            .expect("could not restore backup");
+        std::fs::remove_file(&file_path).unwrap();
+        assert!(
+            contact_store
+                .get(TEST_NODE_ID_SECP)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,706 @@
+use std::collections::HashSet;
+
+use super::super::super::{Error, Result};
+use super::{SqliteDb, enum_to_text, from_json, to_json};
+use crate::bill::BillStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::constants::{PAYMENT_DEADLINE_SECONDS, RECOURSE_DEADLINE_SECONDS};
+use bcr_ebill_core::{
+    bill::{BillKeys, BillSortField, BillState, BillStateFilter, BillStateKey, SortDirection},
+    blockchain::bill::BillOpCode,
+    util,
+};
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter, types::Value};
+
+#[derive(Clone)]
+pub struct SqliteBillStore {
+    db: SqliteDb,
+}
+
+impl SqliteBillStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+/// Returns the ids of all bills, where the latest block has the given op code and is newer
+/// than the given timestamp
+fn get_bill_ids_with_latest_op_code_since(
+    conn: &Connection,
+    op_code: &BillOpCode,
+    timestamp: i64,
+) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT c.bill_id FROM bill_chain c
+            JOIN (SELECT bill_id, MAX(block_id) AS block_id FROM bill_chain GROUP BY bill_id) latest
+            ON c.bill_id = latest.bill_id AND c.block_id = latest.block_id
+            WHERE c.timestamp > ?1 AND c.op_code = ?2"#,
+    )?;
+    let ids = stmt
+        .query_map(params![timestamp, enum_to_text(op_code)?], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(ids)
+}
+
+#[async_trait]
+impl BillStoreApi for SqliteBillStore {
+    async fn exists(&self, id: &str) -> bool {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                Ok(conn.query_row(
+                    r#"SELECT EXISTS(SELECT 1 FROM bill_chain WHERE bill_id = ?1)
+                        AND EXISTS(SELECT 1 FROM bill_keys WHERE id = ?1)"#,
+                    [&id],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+            .unwrap_or(false)
+    }
+
+    async fn get_ids(&self) -> Result<Vec<String>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare("SELECT DISTINCT bill_id FROM bill_chain")?;
+                let ids = stmt
+                    .query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(ids)
+            })
+            .await
+    }
+
+    async fn save_keys(&self, id: &str, key_pair: &BillKeys) -> Result<()> {
+        let id = id.to_owned();
+        let key_pair = key_pair.clone();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO bill_keys (id, public_key, private_key) VALUES (?1, ?2, ?3)",
+                    params![id, key_pair.public_key, key_pair.private_key],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_keys(&self, id: &str) -> Result<BillKeys> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let result = conn
+                    .query_row(
+                        "SELECT public_key, private_key FROM bill_keys WHERE id = ?1",
+                        [&id],
+                        |row| {
+                            Ok(BillKeys {
+                                public_key: row.get(0)?,
+                                private_key: row.get(1)?,
+                            })
+                        },
+                    )
+                    .optional()?;
+                result.ok_or(Error::NoSuchEntity("bill".to_string(), id))
+            })
+            .await
+    }
+
+    async fn is_paid(&self, id: &str) -> Result<bool> {
+        Ok(self.get_paid_payment_address(id).await?.is_some())
+    }
+
+    async fn set_to_paid(&self, id: &str, payment_address: &str) -> Result<()> {
+        let id = id.to_owned();
+        let payment_address = payment_address.to_owned();
+        self.db
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    r#"INSERT INTO bill_paid (id, payment_address) VALUES (?1, ?2)
+                        ON CONFLICT(id) DO UPDATE SET payment_address = excluded.payment_address"#,
+                    params![id, payment_address],
+                )?;
+                tx.execute("UPDATE bill_state SET paid = 1 WHERE bill_id = ?1", [&id])?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_paid_payment_address(&self, id: &str) -> Result<Option<String>> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT payment_address FROM bill_paid WHERE id = ?1",
+                        [&id],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })
+            .await
+    }
+
+    async fn get_bill_ids_waiting_for_payment(&self) -> Result<Vec<String>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    r#"SELECT DISTINCT bill_id FROM bill_chain
+                        WHERE op_code = ?1 AND bill_id NOT IN (SELECT id FROM bill_paid)"#,
+                )?;
+                let ids = stmt
+                    .query_map([enum_to_text(&BillOpCode::RequestToPay)?], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(ids)
+            })
+            .await
+    }
+
+    async fn get_bill_ids_waiting_for_sell_payment(&self) -> Result<Vec<String>> {
+        let timestamp_now_minus_payment_deadline =
+            util::date::now().timestamp() - PAYMENT_DEADLINE_SECONDS as i64;
+        self.db
+            .call(move |conn| {
+                get_bill_ids_with_latest_op_code_since(
+                    conn,
+                    &BillOpCode::OfferToSell,
+                    timestamp_now_minus_payment_deadline,
+                )
+            })
+            .await
+    }
+
+    async fn get_bill_ids_waiting_for_recourse_payment(&self) -> Result<Vec<String>> {
+        let timestamp_now_minus_payment_deadline =
+            util::date::now().timestamp() - RECOURSE_DEADLINE_SECONDS as i64;
+        self.db
+            .call(move |conn| {
+                get_bill_ids_with_latest_op_code_since(
+                    conn,
+                    &BillOpCode::RequestRecourse,
+                    timestamp_now_minus_payment_deadline,
+                )
+            })
+            .await
+    }
+
+    async fn get_bill_ids_with_op_codes_since(
+        &self,
+        op_codes: HashSet<BillOpCode>,
+        since: u64,
+    ) -> Result<Vec<String>> {
+        if op_codes.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut values = vec![Value::Integer(since as i64)];
+        for op_code in op_codes.iter() {
+            values.push(Value::Text(enum_to_text(op_code)?));
+        }
+        self.db
+            .call(move |conn| {
+                let placeholders = vec!["?"; values.len() - 1].join(", ");
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT DISTINCT bill_id FROM bill_chain WHERE timestamp >= ? AND op_code IN ({placeholders})"
+                ))?;
+                let ids = stmt
+                    .query_map(params_from_iter(values), |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(ids)
+            })
+            .await
+    }
+
+    async fn save_bill_states(&self, id: &str, states: &[BillState]) -> Result<()> {
+        let id = id.to_owned();
+        let states = states.to_vec();
+        self.db
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute("DELETE FROM bill_state WHERE bill_id = ?1", [&id])?;
+                for state in states.iter() {
+                    tx.execute(
+                        r#"INSERT INTO bill_state (bill_id, identity_node_id, role, search_text, issue_timestamp,
+                            time_of_drawing, time_of_maturity, sum, currency, accepted, paid, waiting_for_payment_until,
+                            in_recourse_until, has_attachments, participants, data)
+                            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"#,
+                        params![
+                            id,
+                            state.identity_node_id,
+                            enum_to_text(&state.role)?,
+                            state.search_text(),
+                            state.issue_timestamp,
+                            state.time_of_drawing,
+                            state.time_of_maturity,
+                            state.sum,
+                            state.currency,
+                            state.accepted,
+                            state.paid,
+                            state.waiting_for_payment_until,
+                            state.in_recourse_until,
+                            state.has_attachments,
+                            to_json(&state.participants)?,
+                            to_json(state)?
+                        ],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn remove_bill_states(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute("DELETE FROM bill_state WHERE bill_id = ?1", [&id])?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_bill_state_keys(&self) -> Result<Vec<BillStateKey>> {
+        self.db
+            .call(|conn| {
+                let mut stmt =
+                    conn.prepare("SELECT bill_id, identity_node_id, participants FROM bill_state")?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    })?
+                    .collect::<rusqlite::Result<Vec<(String, String, String)>>>()?;
+                rows.into_iter()
+                    .map(|(bill_id, identity_node_id, participants)| {
+                        Ok(BillStateKey {
+                            bill_id,
+                            identity_node_id,
+                            participants: from_json(&participants)?,
+                        })
+                    })
+                    .collect()
+            })
+            .await
+    }
+
+    async fn search_bill_states(&self, filter: &BillStateFilter) -> Result<Vec<BillState>> {
+        let query = &filter.query;
+        let mut conditions = vec!["identity_node_id = ?".to_string()];
+        let mut values = vec![Value::Text(filter.identity_node_id.clone())];
+        if let Some(ref role) = query.role.bill_role() {
+            conditions.push("role = ?".to_string());
+            values.push(Value::Text(enum_to_text(role)?));
+        }
+        if let Some(ref search_term) = query.search_term {
+            conditions.push("instr(search_text, ?) > 0".to_string());
+            values.push(Value::Text(search_term.to_lowercase()));
+        }
+        // bills with an issue date we can't parse are never filtered out by date
+        if let Some(issued_from) = query.issued_from {
+            conditions.push("(issue_timestamp IS NULL OR issue_timestamp >= ?)".to_string());
+            values.push(Value::Integer(issued_from as i64));
+        }
+        if let Some(issued_to) = query.issued_to {
+            conditions.push("(issue_timestamp IS NULL OR issue_timestamp <= ?)".to_string());
+            values.push(Value::Integer(issued_to as i64));
+        }
+        if let Some(maturity_from) = query.maturity_from {
+            conditions.push("time_of_maturity >= ?".to_string());
+            values.push(Value::Integer(maturity_from as i64));
+        }
+        if let Some(maturity_to) = query.maturity_to {
+            conditions.push("time_of_maturity <= ?".to_string());
+            values.push(Value::Integer(maturity_to as i64));
+        }
+        if let Some(sum_from) = query.sum_from {
+            conditions.push("sum >= ?".to_string());
+            values.push(Value::Integer(sum_from as i64));
+        }
+        if let Some(sum_to) = query.sum_to {
+            conditions.push("sum <= ?".to_string());
+            values.push(Value::Integer(sum_to as i64));
+        }
+        if let Some(ref currency) = query.currency {
+            conditions.push("currency = ?".to_string());
+            values.push(Value::Text(currency.clone()));
+        }
+        if let Some(ref counterparty_node_id) = query.counterparty_node_id {
+            conditions
+                .push("EXISTS (SELECT 1 FROM json_each(participants) WHERE value = ?)".to_string());
+            values.push(Value::Text(counterparty_node_id.clone()));
+        }
+        if let Some(has_attachments) = query.has_attachments {
+            conditions.push("has_attachments = ?".to_string());
+            values.push(Value::Integer(has_attachments as i64));
+        }
+        if let Some(accepted) = query.status.accepted {
+            conditions.push("accepted = ?".to_string());
+            values.push(Value::Integer(accepted as i64));
+        }
+        if let Some(paid) = query.status.paid {
+            conditions.push("paid = ?".to_string());
+            values.push(Value::Integer(paid as i64));
+        }
+        for (column, flag) in [
+            (
+                "waiting_for_payment_until",
+                query.status.waiting_for_payment,
+            ),
+            ("in_recourse_until", query.status.in_recourse),
+        ] {
+            match flag {
+                Some(true) => conditions.push(format!("IFNULL({column} >= ?, 0)")),
+                Some(false) => conditions.push(format!("NOT IFNULL({column} >= ?, 0)")),
+                None => continue,
+            };
+            values.push(Value::Integer(filter.timestamp as i64));
+        }
+
+        let sort_key = match query.sort {
+            BillSortField::TimeOfDrawing => "time_of_drawing",
+            BillSortField::IssueDate => "IFNULL(issue_timestamp, 0)",
+            BillSortField::MaturityDate => "time_of_maturity",
+            BillSortField::Sum => "sum",
+        };
+        let (direction, cmp) = match query.direction {
+            SortDirection::Ascending => ("ASC", ">"),
+            SortDirection::Descending => ("DESC", "<"),
+        };
+        if let Some(ref cursor) = query.cursor {
+            conditions.push(format!("({sort_key}, bill_id) {cmp} (?, ?)"));
+            values.push(Value::Integer(cursor.sort_value as i64));
+            values.push(Value::Text(cursor.bill_id.clone()));
+        }
+        let mut sql = format!(
+            "SELECT data, paid FROM bill_state WHERE {} ORDER BY {sort_key} {direction}, bill_id {direction}",
+            conditions.join(" AND ")
+        );
+        if let Some(limit) = query.limit {
+            sql.push_str(" LIMIT ?");
+            values.push(Value::Integer(limit as i64));
+        }
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&sql)?;
+                let rows = stmt
+                    .query_map(params_from_iter(values), |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<(String, bool)>>>()?;
+                rows.iter()
+                    .map(|(data, paid)| {
+                        // the paid flag is updated in place when the payment is detected
+                        let mut state: BillState = from_json(data)?;
+                        state.paid = *paid;
+                        Ok(state)
+                    })
+                    .collect::<Result<Vec<BillState>>>()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bill::BillChainStoreApi,
+        db::{
+            bill::tests::{get_bill_state, get_first_block},
+            sqlite::{bill_chain::SqliteBillChainStore, get_memory_sqlite_db},
+        },
+        tests::tests::{
+            TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP, empty_address, get_bill_keys,
+            identity_public_data_only_node_id,
+        },
+        util::BcrKeys,
+    };
+    use bcr_ebill_core::{
+        bill::{BillRole, BillSearchCursor, BillsFilterRole},
+        blockchain::bill::{
+            BillBlock,
+            block::{BillOfferToSellBlockData, BillRequestToPayBlockData},
+        },
+    };
+
+    async fn get_stores() -> (SqliteBillStore, SqliteBillChainStore) {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        (
+            SqliteBillStore::new(db.clone()),
+            SqliteBillChainStore::new(db),
+        )
+    }
+
+    fn get_request_to_pay_block(first_block: &BillBlock) -> BillBlock {
+        BillBlock::create_block_for_request_to_pay(
+            first_block.bill_id.clone(),
+            first_block,
+            &BillRequestToPayBlockData {
+                requester: identity_public_data_only_node_id(
+                    BcrKeys::from_private_key(TEST_PRIVATE_KEY_SECP)
+                        .unwrap()
+                        .get_public_key(),
+                )
+                .into(),
+                currency: "sat".to_string(),
+                signatory: None,
+                signing_timestamp: 1731593928,
+                signing_address: empty_address(),
+            },
+            &BcrKeys::from_private_key(TEST_PRIVATE_KEY_SECP).unwrap(),
+            None,
+            &BcrKeys::from_private_key(&get_bill_keys().private_key).unwrap(),
+            1731593928,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_exists_and_keys() {
+        let (store, chain_store) = get_stores().await;
+        assert!(!store.exists("1234").await);
+        chain_store
+            .add_block("1234", &get_first_block("1234"))
+            .await
+            .unwrap();
+        assert!(!store.exists("1234").await);
+        store
+            .save_keys(
+                "1234",
+                &BillKeys {
+                    private_key: TEST_PRIVATE_KEY_SECP.to_string(),
+                    public_key: TEST_PUB_KEY_SECP.to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert!(store.exists("1234").await);
+        assert_eq!(
+            store.get_keys("1234").await.unwrap().private_key,
+            TEST_PRIVATE_KEY_SECP
+        );
+        assert!(matches!(
+            store.get_keys("4321").await,
+            Err(Error::NoSuchEntity(_, _))
+        ));
+        assert_eq!(store.get_ids().await.unwrap(), vec!["1234".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_paid() {
+        let (store, _) = get_stores().await;
+        store
+            .set_to_paid("1234", "1234paymentaddress")
+            .await
+            .unwrap();
+        store
+            .set_to_paid("1234", "1234paymentaddress")
+            .await
+            .unwrap();
+        assert!(store.is_paid("1234").await.unwrap());
+        assert!(!store.is_paid("4321").await.unwrap());
+        assert_eq!(
+            store.get_paid_payment_address("1234").await.unwrap(),
+            Some("1234paymentaddress".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bills_waiting_for_payment() {
+        let (store, chain_store) = get_stores().await;
+        let first_block = get_first_block("1234");
+        chain_store
+            .add_block("4321", &get_first_block("4321"))
+            .await
+            .unwrap();
+        chain_store.add_block("1234", &first_block).await.unwrap();
+        chain_store
+            .add_block("1234", &get_request_to_pay_block(&first_block))
+            .await
+            .unwrap();
+
+        let res = store.get_bill_ids_waiting_for_payment().await.unwrap();
+        assert_eq!(res, vec!["1234".to_string()]);
+
+        let res = store
+            .get_bill_ids_with_op_codes_since(HashSet::from([BillOpCode::RequestToPay]), 0)
+            .await
+            .unwrap();
+        assert_eq!(res, vec!["1234".to_string()]);
+
+        store
+            .set_to_paid("1234", "1234paymentaddress")
+            .await
+            .unwrap();
+        let res = store.get_bill_ids_waiting_for_payment().await.unwrap();
+        assert!(res.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bills_waiting_for_payment_offer_to_sell() {
+        let (store, chain_store) = get_stores().await;
+        let now = util::date::now().timestamp() as u64;
+        let first_block = get_first_block("1234");
+        chain_store.add_block("1234", &first_block).await.unwrap();
+        assert!(
+            store
+                .get_bill_ids_waiting_for_sell_payment()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let second_block = BillBlock::create_block_for_offer_to_sell(
+            "1234".to_string(),
+            &first_block,
+            &BillOfferToSellBlockData {
+                seller: identity_public_data_only_node_id(
+                    BcrKeys::from_private_key(TEST_PRIVATE_KEY_SECP)
+                        .unwrap()
+                        .get_public_key(),
+                )
+                .into(),
+                buyer: identity_public_data_only_node_id(BcrKeys::new().get_public_key()).into(),
+                currency: "sat".to_string(),
+                sum: 15000,
+                payment_address: "1234paymentaddress".to_string(),
+                signatory: None,
+                signing_timestamp: now,
+                signing_address: empty_address(),
+            },
+            &BcrKeys::from_private_key(TEST_PRIVATE_KEY_SECP).unwrap(),
+            None,
+            &BcrKeys::from_private_key(&get_bill_keys().private_key).unwrap(),
+            now,
+        )
+        .unwrap();
+        chain_store.add_block("1234", &second_block).await.unwrap();
+
+        let res = store.get_bill_ids_waiting_for_sell_payment().await.unwrap();
+        assert_eq!(res, vec!["1234".to_string()]);
+        assert!(
+            store
+                .get_bill_ids_waiting_for_recourse_payment()
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_save_and_search_bill_states() {
+        let (store, _) = get_stores().await;
+        let mut other_state = get_bill_state("4321", TEST_PUB_KEY_SECP, BillRole::Payer);
+        other_state.issue_timestamp = None;
+        other_state.time_of_drawing = 1000;
+        store
+            .save_bill_states(
+                "1234",
+                &[
+                    get_bill_state("1234", TEST_PUB_KEY_SECP, BillRole::Payee),
+                    get_bill_state("1234", "other_identity", BillRole::Contingent),
+                ],
+            )
+            .await
+            .unwrap();
+        store
+            .save_bill_states("4321", &[other_state])
+            .await
+            .unwrap();
+
+        let all = store
+            .search_bill_states(&BillStateFilter::for_identity(TEST_PUB_KEY_SECP))
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].bill_id, "1234");
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.role = BillsFilterRole::Payer;
+        assert_eq!(store.search_bill_states(&filter).await.unwrap().len(), 1);
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.search_term = Some("HAYEK".to_string());
+        assert_eq!(store.search_bill_states(&filter).await.unwrap().len(), 2);
+
+        // bills without a parseable issue date are kept
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.issued_from = Some(1731542401);
+        let by_date = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(by_date.len(), 1);
+        assert_eq!(by_date[0].bill_id, "4321");
+
+        store.set_to_paid("1234", "paymentaddress").await.unwrap();
+        store.remove_bill_states("4321").await.unwrap();
+        let all = store
+            .search_bill_states(&BillStateFilter::for_identity(TEST_PUB_KEY_SECP))
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(all[0].paid);
+        let keys = store.get_bill_state_keys().await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].bill_id, "1234");
+        assert_eq!(keys[0].identity_node_id, TEST_PUB_KEY_SECP);
+    }
+
+    #[tokio::test]
+    async fn test_search_bill_states_structured_query_and_pagination() {
+        let (store, _) = get_stores().await;
+        for (id, sum) in [("a", 300), ("b", 100), ("c", 300), ("d", 200)] {
+            let mut state = get_bill_state(id, TEST_PUB_KEY_SECP, BillRole::Payee);
+            state.sum = sum;
+            state.accepted = id != "d";
+            if id == "a" {
+                state.participants.push("counterparty".to_string());
+                state.in_recourse_until = Some(2000);
+            }
+            store.save_bill_states(id, &[state]).await.unwrap();
+        }
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.timestamp = 1500;
+        filter.query.counterparty_node_id = Some("counterparty".to_string());
+        filter.query.status.in_recourse = Some(true);
+        let result = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].bill_id, "a");
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.timestamp = 1500;
+        filter.query.status.in_recourse = Some(false);
+        filter.query.status.accepted = Some(true);
+        filter.query.sum_to = Some(300);
+        filter.query.currency = Some("sat".to_string());
+        let result = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(result.len(), 2);
+
+        let mut filter = BillStateFilter::for_identity(TEST_PUB_KEY_SECP);
+        filter.query.sort = BillSortField::Sum;
+        filter.query.direction = SortDirection::Ascending;
+        filter.query.limit = Some(3);
+        let first_page = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(
+            first_page
+                .iter()
+                .map(|s| s.bill_id.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["b", "d", "a"]
+        );
+        filter.query.cursor = Some(BillSearchCursor::for_state(
+            &first_page[2],
+            BillSortField::Sum,
+            SortDirection::Ascending,
+        ));
+        let second_page = store.search_bill_states(&filter).await.unwrap();
+        assert_eq!(second_page.len(), 1);
+        assert_eq!(second_page[0].bill_id, "c");
+    }
+}
@@ -0,0 +1,111 @@
+use super::super::super::Result;
+use super::SqliteDb;
+use crate::bill_batch::BillBatchStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::bill::BillBatchIssue;
+use rusqlite::{Row, params};
+
+#[derive(Clone)]
+pub struct SqliteBillBatchStore {
+    db: SqliteDb,
+}
+
+impl SqliteBillBatchStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+const ISSUE_COLUMNS: &str = "batch_id, row_hash, bill_id, issued_at";
+
+fn read_issue_row(row: &Row) -> rusqlite::Result<BillBatchIssue> {
+    Ok(BillBatchIssue {
+        batch_id: row.get(0)?,
+        row_hash: row.get(1)?,
+        bill_id: row.get(2)?,
+        issued_at: row.get(3)?,
+    })
+}
+
+#[async_trait]
+impl BillBatchStoreApi for SqliteBillBatchStore {
+    async fn add_issue(&self, issue: &BillBatchIssue) -> Result<()> {
+        let issue = issue.clone();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO bill_batch_issues ({ISSUE_COLUMNS}) VALUES (?1, ?2, ?3, ?4)"
+                    ),
+                    params![
+                        issue.batch_id,
+                        issue.row_hash,
+                        issue.bill_id,
+                        issue.issued_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn list_issues(&self, batch_id: &str) -> Result<Vec<BillBatchIssue>> {
+        let batch_id = batch_id.to_owned();
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {ISSUE_COLUMNS} FROM bill_batch_issues WHERE batch_id = ?1 ORDER BY issued_at ASC"
+                ))?;
+                let issues = stmt
+                    .query_map([&batch_id], read_issue_row)?
+                    .collect::<rusqlite::Result<Vec<BillBatchIssue>>>()?;
+                Ok(issues)
+            })
+            .await
+    }
+
+    async fn get_all(&self) -> Result<Vec<BillBatchIssue>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {ISSUE_COLUMNS} FROM bill_batch_issues ORDER BY issued_at ASC"
+                ))?;
+                let issues = stmt
+                    .query_map([], read_issue_row)?
+                    .collect::<rusqlite::Result<Vec<BillBatchIssue>>>()?;
+                Ok(issues)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{bill_batch::tests::get_bill_batch_issue, sqlite::get_memory_sqlite_db};
+
+    async fn get_store() -> SqliteBillBatchStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteBillBatchStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_issues() {
+        let store = get_store().await;
+        let second = get_bill_batch_issue("batch", "row2", 2000);
+        let first = get_bill_batch_issue("batch", "row1", 1000);
+        let other = get_bill_batch_issue("other", "row1", 3000);
+        store.add_issue(&second).await.unwrap();
+        store.add_issue(&first).await.unwrap();
+        store.add_issue(&other).await.unwrap();
+        assert!(store.add_issue(&first).await.is_err());
+
+        assert_eq!(
+            store.list_issues("batch").await.unwrap(),
+            vec![first.clone(), second.clone()]
+        );
+        assert_eq!(store.get_all().await.unwrap(), vec![first, second, other]);
+    }
+}
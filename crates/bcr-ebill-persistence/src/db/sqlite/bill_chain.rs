@@ -0,0 +1,252 @@
+use super::super::super::{Error, Result};
+use super::{SqliteDb, enum_from_text, enum_to_text};
+use crate::bill::BillChainStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::blockchain::{
+    Block,
+    bill::{BillBlock, BillBlockchain},
+};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+
+const BLOCK_COLUMNS: &str =
+    "bill_id, block_id, hash, previous_hash, signature, timestamp, public_key, data, op_code";
+
+#[derive(Clone)]
+pub struct SqliteBillChainStore {
+    db: SqliteDb,
+}
+
+impl SqliteBillChainStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+fn get_latest_block(conn: &Connection, id: &str) -> Result<Option<BillBlock>> {
+    let row = conn
+        .query_row(
+            &format!(
+                "SELECT {BLOCK_COLUMNS} FROM bill_chain WHERE bill_id = ?1 ORDER BY block_id DESC LIMIT 1"
+            ),
+            [id],
+            BillBlockRow::from_row,
+        )
+        .optional()?;
+    row.map(|r| r.try_into()).transpose()
+}
+
+fn insert_block(conn: &Connection, block: &BillBlock) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO bill_chain ({BLOCK_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        ),
+        params![
+            block.bill_id,
+            block.id,
+            block.hash,
+            block.previous_hash,
+            block.signature,
+            block.timestamp,
+            block.public_key,
+            block.data,
+            enum_to_text(&block.op_code)?,
+        ],
+    )?;
+    Ok(())
+}
+
+#[async_trait]
+impl BillChainStoreApi for SqliteBillChainStore {
+    async fn get_latest_block(&self, id: &str) -> Result<BillBlock> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| get_latest_block(conn, &id)?.ok_or(Error::NoBillBlock))
+            .await
+    }
+
+    async fn add_block(&self, id: &str, block: &BillBlock) -> Result<()> {
+        let id = id.to_owned();
+        let block = block.clone();
+        self.db
+            .call(move |conn| {
+                // validation and insert happen in one transaction, so the chain stays valid
+                let tx = conn.transaction()?;
+                match get_latest_block(&tx, &id)? {
+                    None => {
+                        // if there is no latest block, ensure it's a valid first block
+                        if !(block.id == 1 && block.verify() && block.validate_hash()) {
+                            return Err(Error::AddBillBlock(format!(
+                                "First Block validation error: block id: {}",
+                                block.id
+                            )));
+                        }
+                    }
+                    Some(latest_block) => {
+                        // if there is a latest block, ensure it's a valid follow-up block
+                        if !block.validate_with_previous(&latest_block) {
+                            return Err(Error::AddBillBlock(format!(
+                                "Block validation error: block id: {}, latest block id: {}",
+                                block.id, latest_block.id
+                            )));
+                        }
+                    }
+                }
+                insert_block(&tx, &block)?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_chain(&self, id: &str) -> Result<BillBlockchain> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {BLOCK_COLUMNS} FROM bill_chain WHERE bill_id = ?1 ORDER BY block_id ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([&id], BillBlockRow::from_row)?
+                    .collect::<rusqlite::Result<Vec<BillBlockRow>>>()?;
+                let blocks = rows
+                    .into_iter()
+                    .map(|r| r.try_into())
+                    .collect::<Result<Vec<BillBlock>>>()?;
+                Ok(BillBlockchain::new_from_blocks(blocks)?)
+            })
+            .await
+    }
+}
+
+struct BillBlockRow {
+    bill_id: String,
+    block_id: u64,
+    hash: String,
+    previous_hash: String,
+    signature: String,
+    timestamp: u64,
+    public_key: String,
+    data: String,
+    op_code: String,
+}
+
+impl BillBlockRow {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            bill_id: row.get(0)?,
+            block_id: row.get(1)?,
+            hash: row.get(2)?,
+            previous_hash: row.get(3)?,
+            signature: row.get(4)?,
+            timestamp: row.get(5)?,
+            public_key: row.get(6)?,
+            data: row.get(7)?,
+            op_code: row.get(8)?,
+        })
+    }
+}
+
+impl TryFrom<BillBlockRow> for BillBlock {
+    type Error = Error;
+
+    fn try_from(value: BillBlockRow) -> Result<Self> {
+        Ok(Self {
+            bill_id: value.bill_id,
+            id: value.block_id,
+            hash: value.hash,
+            timestamp: value.timestamp,
+            data: value.data,
+            public_key: value.public_key,
+            previous_hash: value.previous_hash,
+            signature: value.signature,
+            op_code: enum_from_text(value.op_code)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{bill::tests::get_first_block, sqlite::get_memory_sqlite_db},
+        tests::tests::{empty_address, get_bill_keys},
+    };
+    use bcr_ebill_core::{
+        blockchain::{
+            Blockchain,
+            bill::block::{BillAcceptBlockData, BillIdentityBlockData},
+        },
+        contact::ContactType,
+        util::BcrKeys,
+    };
+
+    async fn get_store() -> SqliteBillChainStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteBillChainStore::new(db)
+    }
+
+    fn get_accept_block(first_block: &BillBlock) -> BillBlock {
+        BillBlock::create_block_for_accept(
+            "1234".to_string(),
+            first_block,
+            &BillAcceptBlockData {
+                accepter: BillIdentityBlockData {
+                    t: ContactType::Person,
+                    node_id: "555555".to_owned(),
+                    name: "some dude".to_owned(),
+                    postal_address: empty_address(),
+                },
+                signatory: None,
+                signing_timestamp: 1731593928,
+                signing_address: empty_address(),
+            },
+            &BcrKeys::new(),
+            None,
+            &BcrKeys::from_private_key(&get_bill_keys().private_key).unwrap(),
+            1731593928,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chain() {
+        let store = get_store().await;
+        let first_block = get_first_block("1234");
+        store
+            .add_block("1234", &first_block)
+            .await
+            .expect("could not add first block");
+        let last_block = store.get_latest_block("1234").await.unwrap();
+        assert_eq!(last_block.id, 1);
+
+        store
+            .add_block("1234", &get_accept_block(&first_block))
+            .await
+            .expect("could not add second block");
+        let chain = store.get_chain("1234").await.unwrap();
+        assert_eq!(chain.blocks().len(), 2);
+        assert_eq!(store.get_latest_block("1234").await.unwrap().id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_add_block_rejects_invalid_blocks() {
+        let store = get_store().await;
+        let first_block = get_first_block("1234");
+        let second_block = get_accept_block(&first_block);
+        assert!(store.add_block("1234", &second_block).await.is_err());
+
+        store.add_block("1234", &first_block).await.unwrap();
+        assert!(store.add_block("1234", &first_block).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_block_without_chain() {
+        let store = get_store().await;
+        assert!(matches!(
+            store.get_latest_block("1234").await,
+            Err(Error::NoBillBlock)
+        ));
+    }
+}
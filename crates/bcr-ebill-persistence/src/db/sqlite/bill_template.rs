@@ -0,0 +1,201 @@
+use super::super::super::Result;
+use super::{SqliteDb, from_json, to_json};
+use crate::bill_template::BillTemplateStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::bill_template::{BillTemplate, BillTemplateIssue};
+use rusqlite::{OptionalExtension, Row, params};
+
+#[derive(Clone)]
+pub struct SqliteBillTemplateStore {
+    db: SqliteDb,
+}
+
+impl SqliteBillTemplateStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+const ISSUE_COLUMNS: &str = "template_id, bill_id, occurrence, issued_at";
+
+fn read_issue_row(row: &Row) -> rusqlite::Result<BillTemplateIssue> {
+    Ok(BillTemplateIssue {
+        template_id: row.get(0)?,
+        bill_id: row.get(1)?,
+        occurrence: row.get(2)?,
+        issued_at: row.get(3)?,
+    })
+}
+
+#[async_trait]
+impl BillTemplateStoreApi for SqliteBillTemplateStore {
+    async fn insert(&self, template: &BillTemplate) -> Result<()> {
+        let id = template.id.clone();
+        let created_at = template.created_at;
+        let data = to_json(template)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO bill_templates (id, data, created_at) VALUES (?1, ?2, ?3)",
+                    params![id, data, created_at],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn update(&self, template: &BillTemplate) -> Result<()> {
+        let id = template.id.clone();
+        let data = to_json(template)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE bill_templates SET data = ?2 WHERE id = ?1",
+                    params![id, data],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<BillTemplate>> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let data: Option<String> = conn
+                    .query_row(
+                        "SELECT data FROM bill_templates WHERE id = ?1",
+                        [&id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                data.map(|d| from_json(&d)).transpose()
+            })
+            .await
+    }
+
+    async fn list(&self) -> Result<Vec<BillTemplate>> {
+        self.db
+            .call(|conn| {
+                let mut stmt =
+                    conn.prepare("SELECT data FROM bill_templates ORDER BY created_at ASC")?;
+                let rows = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                rows.iter()
+                    .map(|data| from_json(data))
+                    .collect::<Result<Vec<BillTemplate>>>()
+            })
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute("DELETE FROM bill_templates WHERE id = ?1", [&id])?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn add_issue(&self, issue: &BillTemplateIssue) -> Result<()> {
+        let issue = issue.clone();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO bill_template_issues ({ISSUE_COLUMNS}) VALUES (?1, ?2, ?3, ?4)"
+                    ),
+                    params![
+                        issue.template_id,
+                        issue.bill_id,
+                        issue.occurrence,
+                        issue.issued_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn list_issues(&self, template_id: &str) -> Result<Vec<BillTemplateIssue>> {
+        let template_id = template_id.to_owned();
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {ISSUE_COLUMNS} FROM bill_template_issues WHERE template_id = ?1 ORDER BY issued_at DESC"
+                ))?;
+                let issues = stmt
+                    .query_map([&template_id], read_issue_row)?
+                    .collect::<rusqlite::Result<Vec<BillTemplateIssue>>>()?;
+                Ok(issues)
+            })
+            .await
+    }
+
+    async fn get_issue_for_bill(&self, bill_id: &str) -> Result<Option<BillTemplateIssue>> {
+        let bill_id = bill_id.to_owned();
+        self.db
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        &format!(
+                            "SELECT {ISSUE_COLUMNS} FROM bill_template_issues WHERE bill_id = ?1"
+                        ),
+                        [&bill_id],
+                        read_issue_row,
+                    )
+                    .optional()?)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{bill_template::tests::get_bill_template, sqlite::get_memory_sqlite_db};
+
+    async fn get_store() -> SqliteBillTemplateStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteBillTemplateStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_templates_and_issues() {
+        let store = get_store().await;
+        store
+            .insert(&get_bill_template("second", 2000))
+            .await
+            .unwrap();
+        store
+            .insert(&get_bill_template("first", 1000))
+            .await
+            .unwrap();
+
+        let mut template = get_bill_template("second", 2000);
+        template.schedule = None;
+        store.update(&template).await.unwrap();
+        assert_eq!(store.get("second").await.unwrap(), Some(template));
+        assert_eq!(store.list().await.unwrap()[0].id, "first");
+
+        store.remove("first").await.unwrap();
+        assert!(store.get("first").await.unwrap().is_none());
+
+        let issue = BillTemplateIssue {
+            template_id: "second".to_string(),
+            bill_id: "bill".to_string(),
+            occurrence: Some(0),
+            issued_at: 3000,
+        };
+        store.add_issue(&issue).await.unwrap();
+        assert_eq!(
+            store.list_issues("second").await.unwrap(),
+            vec![issue.clone()]
+        );
+        assert_eq!(store.get_issue_for_bill("bill").await.unwrap(), Some(issue));
+    }
+}
@@ -0,0 +1,255 @@
+use super::super::super::{Error, Result};
+use super::{SqliteDb, from_json, to_json};
+use crate::company::CompanyStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::company::{Company, CompanyKeys};
+use rusqlite::{OptionalExtension, params};
+use std::collections::HashMap;
+
+#[derive(Clone)]
+pub struct SqliteCompanyStore {
+    db: SqliteDb,
+}
+
+impl SqliteCompanyStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl CompanyStoreApi for SqliteCompanyStore {
+    async fn search(&self, search_term: &str) -> Result<Vec<Company>> {
+        let search_term = search_term.to_owned();
+        self.db
+            .call(move |conn| {
+                let mut stmt =
+                    conn.prepare("SELECT data FROM company WHERE instr(lower(name), ?1) > 0")?;
+                let rows = stmt
+                    .query_map([&search_term], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                rows.iter()
+                    .map(|data| from_json(data))
+                    .collect::<Result<Vec<Company>>>()
+            })
+            .await
+    }
+
+    async fn exists(&self, id: &str) -> bool {
+        self.get(id).await.map(|_| true).unwrap_or(false)
+            && self.get_key_pair(id).await.map(|_| true).unwrap_or(false)
+    }
+
+    async fn get(&self, id: &str) -> Result<Company> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let data: Option<String> = conn
+                    .query_row("SELECT data FROM company WHERE id = ?1", [&id], |row| {
+                        row.get(0)
+                    })
+                    .optional()?;
+                match data {
+                    None => Err(Error::NoSuchEntity("company".to_string(), id)),
+                    Some(data) => from_json(&data),
+                }
+            })
+            .await
+    }
+
+    async fn get_all(&self) -> Result<HashMap<String, (Company, CompanyKeys)>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    r#"SELECT c.id, c.data, k.public_key, k.private_key FROM company c
+                        JOIN company_keys k ON c.id = k.id"#,
+                )?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            CompanyKeys {
+                                public_key: row.get(2)?,
+                                private_key: row.get(3)?,
+                            },
+                        ))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter()
+                    .map(|(id, data, keys)| Ok((id, (from_json(&data)?, keys))))
+                    .collect::<Result<HashMap<String, (Company, CompanyKeys)>>>()
+            })
+            .await
+    }
+
+    async fn insert(&self, data: &Company) -> Result<()> {
+        let id = data.id.to_owned();
+        let name = data.name.to_owned();
+        let data = to_json(data)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO company (id, name, data) VALUES (?1, ?2, ?3)",
+                    params![id, name, data],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn update(&self, id: &str, data: &Company) -> Result<()> {
+        let id = id.to_owned();
+        let name = data.name.to_owned();
+        let data = to_json(data)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE company SET name = ?2, data = ?3 WHERE id = ?1",
+                    params![id, name, data],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute("DELETE FROM company WHERE id = ?1", [&id])?;
+                tx.execute("DELETE FROM company_keys WHERE id = ?1", [&id])?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn save_key_pair(&self, id: &str, key_pair: &CompanyKeys) -> Result<()> {
+        let id = id.to_owned();
+        let key_pair = key_pair.clone();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO company_keys (id, public_key, private_key) VALUES (?1, ?2, ?3)",
+                    params![id, key_pair.public_key, key_pair.private_key],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_key_pair(&self, id: &str) -> Result<CompanyKeys> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let result = conn
+                    .query_row(
+                        "SELECT public_key, private_key FROM company_keys WHERE id = ?1",
+                        [&id],
+                        |row| {
+                            Ok(CompanyKeys {
+                                public_key: row.get(0)?,
+                                private_key: row.get(1)?,
+                            })
+                        },
+                    )
+                    .optional()?;
+                result.ok_or(Error::NoSuchEntity("company".to_string(), id))
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::sqlite::get_memory_sqlite_db,
+        tests::tests::{TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP, empty_address},
+    };
+
+    async fn get_store() -> SqliteCompanyStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteCompanyStore::new(db)
+    }
+
+    fn get_baseline_company() -> Company {
+        Company {
+            id: TEST_PUB_KEY_SECP.to_owned(),
+            name: "Some_Name".to_string(),
+            country_of_registration: Some("AT".to_string()),
+            city_of_registration: Some("Vienna".to_string()),
+            postal_address: empty_address(),
+            email: "company@example.com".to_string(),
+            registration_number: Some("some_number".to_string()),
+            registration_date: Some("2012-01-01".to_string()),
+            proof_of_registration_file: None,
+            logo_file: None,
+            signatories: vec!["1234".to_string()],
+        }
+    }
+
+    fn get_company_keys() -> CompanyKeys {
+        CompanyKeys {
+            private_key: TEST_PRIVATE_KEY_SECP.to_string(),
+            public_key: TEST_PUB_KEY_SECP.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exists() {
+        let store = get_store().await;
+        assert!(!store.exists(TEST_PUB_KEY_SECP).await);
+        store.insert(&get_baseline_company()).await.unwrap();
+        assert!(!store.exists(TEST_PUB_KEY_SECP).await);
+        store
+            .save_key_pair(TEST_PUB_KEY_SECP, &get_company_keys())
+            .await
+            .unwrap();
+        assert!(store.exists(TEST_PUB_KEY_SECP).await);
+    }
+
+    #[tokio::test]
+    async fn test_update_and_search() {
+        let store = get_store().await;
+        store.insert(&get_baseline_company()).await.unwrap();
+        assert_eq!(store.search("some").await.unwrap().len(), 1);
+        assert!(store.search("other").await.unwrap().is_empty());
+
+        let mut company = get_baseline_company();
+        company.name = "Other Name".to_string();
+        store.update(TEST_PUB_KEY_SECP, &company).await.unwrap();
+        assert_eq!(
+            store.get(TEST_PUB_KEY_SECP).await.unwrap().name,
+            "Other Name"
+        );
+        assert_eq!(store.search("other").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_and_remove() {
+        let store = get_store().await;
+        store.insert(&get_baseline_company()).await.unwrap();
+        store
+            .save_key_pair(TEST_PUB_KEY_SECP, &get_company_keys())
+            .await
+            .unwrap();
+        let all = store.get_all().await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(
+            all.get(TEST_PUB_KEY_SECP).unwrap().1.private_key,
+            TEST_PRIVATE_KEY_SECP
+        );
+
+        store.remove(TEST_PUB_KEY_SECP).await.unwrap();
+        assert!(matches!(
+            store.get(TEST_PUB_KEY_SECP).await,
+            Err(Error::NoSuchEntity(_, _))
+        ));
+        assert!(store.get_all().await.unwrap().is_empty());
+    }
+}
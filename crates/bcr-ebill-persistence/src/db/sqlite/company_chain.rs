@@ -0,0 +1,281 @@
+use super::super::super::{Error, Result};
+use super::{SqliteDb, enum_from_text, enum_to_text};
+use crate::company::CompanyChainStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::blockchain::{
+    Block,
+    company::{CompanyBlock, CompanyBlockchain},
+};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+
+const BLOCK_COLUMNS: &str = "company_id, block_id, hash, previous_hash, signature, timestamp, public_key, signatory_node_id, data, op_code";
+
+#[derive(Clone)]
+pub struct SqliteCompanyChainStore {
+    db: SqliteDb,
+}
+
+impl SqliteCompanyChainStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+fn get_latest_block(conn: &Connection, id: &str) -> Result<Option<CompanyBlock>> {
+    let row = conn
+        .query_row(
+            &format!(
+                "SELECT {BLOCK_COLUMNS} FROM company_chain WHERE company_id = ?1 ORDER BY block_id DESC LIMIT 1"
+            ),
+            [id],
+            CompanyBlockRow::from_row,
+        )
+        .optional()?;
+    row.map(|r| r.try_into()).transpose()
+}
+
+fn insert_block(conn: &Connection, block: &CompanyBlock) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO company_chain ({BLOCK_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+        ),
+        params![
+            block.company_id,
+            block.id,
+            block.hash,
+            block.previous_hash,
+            block.signature,
+            block.timestamp,
+            block.public_key,
+            block.signatory_node_id,
+            block.data,
+            enum_to_text(&block.op_code)?,
+        ],
+    )?;
+    Ok(())
+}
+
+#[async_trait]
+impl CompanyChainStoreApi for SqliteCompanyChainStore {
+    async fn get_latest_block(&self, id: &str) -> Result<CompanyBlock> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| get_latest_block(conn, &id)?.ok_or(Error::NoCompanyBlock))
+            .await
+    }
+
+    async fn add_block(&self, id: &str, block: &CompanyBlock) -> Result<()> {
+        let id = id.to_owned();
+        let block = block.clone();
+        self.db
+            .call(move |conn| {
+                // validation and insert happen in one transaction, so the chain stays valid
+                let tx = conn.transaction()?;
+                match get_latest_block(&tx, &id)? {
+                    None => {
+                        // if there is no latest block, ensure it's a valid first block
+                        if !(block.id == 1 && block.verify() && block.validate_hash()) {
+                            return Err(Error::AddCompanyBlock(format!(
+                                "First Block validation error: block id: {}",
+                                block.id
+                            )));
+                        }
+                    }
+                    Some(latest_block) => {
+                        // if there is a latest block, ensure it's a valid follow-up block
+                        if !block.validate_with_previous(&latest_block) {
+                            return Err(Error::AddCompanyBlock(format!(
+                                "Block validation error: block id: {}, latest block id: {}",
+                                block.id, latest_block.id
+                            )));
+                        }
+                    }
+                }
+                insert_block(&tx, &block)?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute("DELETE FROM company_chain WHERE company_id = ?1", [&id])?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_chain(&self, id: &str) -> Result<CompanyBlockchain> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {BLOCK_COLUMNS} FROM company_chain WHERE company_id = ?1 ORDER BY block_id ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([&id], CompanyBlockRow::from_row)?
+                    .collect::<rusqlite::Result<Vec<CompanyBlockRow>>>()?;
+                let blocks = rows
+                    .into_iter()
+                    .map(|r| r.try_into())
+                    .collect::<Result<Vec<CompanyBlock>>>()?;
+                Ok(CompanyBlockchain::new_from_blocks(blocks)?)
+            })
+            .await
+    }
+}
+
+struct CompanyBlockRow {
+    company_id: String,
+    block_id: u64,
+    hash: String,
+    previous_hash: String,
+    signature: String,
+    timestamp: u64,
+    public_key: String,
+    signatory_node_id: String,
+    data: String,
+    op_code: String,
+}
+
+impl CompanyBlockRow {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            company_id: row.get(0)?,
+            block_id: row.get(1)?,
+            hash: row.get(2)?,
+            previous_hash: row.get(3)?,
+            signature: row.get(4)?,
+            timestamp: row.get(5)?,
+            public_key: row.get(6)?,
+            signatory_node_id: row.get(7)?,
+            data: row.get(8)?,
+            op_code: row.get(9)?,
+        })
+    }
+}
+
+impl TryFrom<CompanyBlockRow> for CompanyBlock {
+    type Error = Error;
+
+    fn try_from(value: CompanyBlockRow) -> Result<Self> {
+        Ok(Self {
+            company_id: value.company_id,
+            id: value.block_id,
+            hash: value.hash,
+            timestamp: value.timestamp,
+            data: value.data,
+            public_key: value.public_key,
+            signatory_node_id: value.signatory_node_id,
+            previous_hash: value.previous_hash,
+            signature: value.signature,
+            op_code: enum_from_text(value.op_code)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::sqlite::get_memory_sqlite_db,
+        tests::tests::{
+            TEST_PRIVATE_KEY_SECP, TEST_PUB_KEY_SECP, empty_address, empty_optional_address,
+        },
+        util::BcrKeys,
+    };
+    use bcr_ebill_core::{
+        blockchain::{Blockchain, company::CompanyUpdateBlockData},
+        company::{Company, CompanyKeys},
+    };
+
+    async fn get_store() -> SqliteCompanyChainStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteCompanyChainStore::new(db)
+    }
+
+    fn get_company_keys() -> CompanyKeys {
+        CompanyKeys {
+            private_key: TEST_PRIVATE_KEY_SECP.to_string(),
+            public_key: TEST_PUB_KEY_SECP.to_string(),
+        }
+    }
+
+    fn get_first_block() -> CompanyBlock {
+        CompanyBlock::create_block_for_create(
+            "some_id".to_string(),
+            "genesis hash".to_string(),
+            &Company {
+                id: "some_id".to_string(),
+                name: "Hayek Ltd".to_string(),
+                country_of_registration: Some("AT".to_string()),
+                city_of_registration: Some("Vienna".to_string()),
+                postal_address: empty_address(),
+                email: "hayekltd@example.com".to_string(),
+                registration_number: Some("123124123".to_string()),
+                registration_date: Some("2024-01-01".to_string()),
+                proof_of_registration_file: None,
+                logo_file: None,
+                signatories: vec!["self".to_string()],
+            }
+            .into(),
+            &BcrKeys::new(),
+            &get_company_keys(),
+            1731593928,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_block() {
+        let store = get_store().await;
+        let block = get_first_block();
+        store.add_block("some_id", &block).await.unwrap();
+        assert_eq!(store.get_latest_block("some_id").await.unwrap().id, 1);
+
+        let block2 = CompanyBlock::create_block_for_update(
+            "some_id".to_string(),
+            &block,
+            &CompanyUpdateBlockData {
+                name: None,
+                email: None,
+                postal_address: empty_optional_address(),
+                country_of_registration: None,
+                city_of_registration: None,
+                registration_number: None,
+                registration_date: None,
+                logo_file: None,
+                proof_of_registration_file: None,
+            },
+            &BcrKeys::new(),
+            &get_company_keys(),
+            1731593928,
+        )
+        .unwrap();
+        store.add_block("some_id", &block2).await.unwrap();
+        assert_eq!(store.get_latest_block("some_id").await.unwrap().id, 2);
+        assert!(store.add_block("some_id", &block2).await.is_err());
+
+        let chain = store.get_chain("some_id").await.unwrap();
+        assert_eq!(chain.blocks().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_remove_blockchain() {
+        let store = get_store().await;
+        store
+            .add_block("some_id", &get_first_block())
+            .await
+            .unwrap();
+        store.remove("some_id").await.unwrap();
+        assert!(matches!(
+            store.get_latest_block("some_id").await,
+            Err(Error::NoCompanyBlock)
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+use super::super::super::Result;
+use super::{SqliteDb, from_json, to_json};
+use crate::contact::ContactStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::contact::Contact;
+use rusqlite::{OptionalExtension, params};
+use std::collections::HashMap;
+
+#[derive(Clone)]
+pub struct SqliteContactStore {
+    db: SqliteDb,
+}
+
+impl SqliteContactStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ContactStoreApi for SqliteContactStore {
+    async fn search(&self, search_term: &str) -> Result<Vec<Contact>> {
+        let search_term = search_term.to_owned();
+        self.db
+            .call(move |conn| {
+                let mut stmt =
+                    conn.prepare("SELECT data FROM contacts WHERE instr(lower(name), ?1) > 0")?;
+                let rows = stmt
+                    .query_map([&search_term], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                rows.iter()
+                    .map(|data| from_json(data))
+                    .collect::<Result<Vec<Contact>>>()
+            })
+            .await
+    }
+
+    async fn get_map(&self) -> Result<HashMap<String, Contact>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare("SELECT data FROM contacts")?;
+                let rows = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                let mut map = HashMap::new();
+                for data in rows.iter() {
+                    let contact: Contact = from_json(data)?;
+                    map.insert(contact.node_id.clone(), contact);
+                }
+                Ok(map)
+            })
+            .await
+    }
+
+    async fn get(&self, node_id: &str) -> Result<Option<Contact>> {
+        let node_id = node_id.to_owned();
+        self.db
+            .call(move |conn| {
+                let data: Option<String> = conn
+                    .query_row(
+                        "SELECT data FROM contacts WHERE node_id = ?1",
+                        [&node_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                data.map(|d| from_json(&d)).transpose()
+            })
+            .await
+    }
+
+    async fn insert(&self, node_id: &str, data: Contact) -> Result<()> {
+        let node_id = node_id.to_owned();
+        let name = data.name.to_owned();
+        let data = to_json(&data)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO contacts (node_id, name, data) VALUES (?1, ?2, ?3)",
+                    params![node_id, name, data],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn delete(&self, node_id: &str) -> Result<()> {
+        let node_id = node_id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute("DELETE FROM contacts WHERE node_id = ?1", [&node_id])?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn update(&self, node_id: &str, data: Contact) -> Result<()> {
+        let node_id = node_id.to_owned();
+        let name = data.name.to_owned();
+        let data = to_json(&data)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE contacts SET name = ?2, data = ?3 WHERE node_id = ?1",
+                    params![node_id, name, data],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::{contact::tests::get_baseline_contact, sqlite::get_memory_sqlite_db},
+        tests::tests::TEST_NODE_ID_SECP,
+    };
+
+    async fn get_store() -> SqliteContactStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteContactStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_insert_update_delete_contact() {
+        let store = get_store().await;
+        store
+            .insert(TEST_NODE_ID_SECP, get_baseline_contact())
+            .await
+            .expect("could not create contact");
+        let stored = store
+            .get(TEST_NODE_ID_SECP)
+            .await
+            .expect("could not query contact")
+            .expect("contact not found");
+        assert_eq!(stored.name, "some_name");
+
+        let mut contact = get_baseline_contact();
+        contact.name = "other_name".to_string();
+        store.update(TEST_NODE_ID_SECP, contact).await.unwrap();
+        assert_eq!(store.search("other").await.unwrap().len(), 1);
+        assert_eq!(store.get_map().await.unwrap().len(), 1);
+
+        store.delete(TEST_NODE_ID_SECP).await.unwrap();
+        assert!(store.get(TEST_NODE_ID_SECP).await.unwrap().is_none());
+    }
+}
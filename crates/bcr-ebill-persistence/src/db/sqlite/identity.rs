@@ -0,0 +1,168 @@
+use super::super::super::{Error, Result};
+use super::{SqliteDb, from_json, to_json};
+use crate::{identity::IdentityStoreApi, util::BcrKeys};
+use async_trait::async_trait;
+use bcr_ebill_core::identity::{Identity, IdentityWithAll};
+use rusqlite::{OptionalExtension, params};
+
+#[derive(Clone)]
+pub struct SqliteIdentityStore {
+    db: SqliteDb,
+}
+
+impl SqliteIdentityStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+
+    /// Returns the stored private key and seed phrase, if there are any
+    async fn get_db_keys(&self) -> Result<Option<(String, String)>> {
+        self.db
+            .call(|conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT key, seed_phrase FROM identity_key WHERE id = 1",
+                        [],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl IdentityStoreApi for SqliteIdentityStore {
+    async fn exists(&self) -> bool {
+        self.get().await.map(|_| true).unwrap_or(false)
+    }
+
+    async fn save(&self, identity: &Identity) -> Result<()> {
+        let data = to_json(identity)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    r#"INSERT INTO identity (id, data) VALUES (1, ?1)
+                        ON CONFLICT(id) DO UPDATE SET data = excluded.data"#,
+                    [data],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get(&self) -> Result<Identity> {
+        self.db
+            .call(|conn| {
+                let data: Option<String> = conn
+                    .query_row("SELECT data FROM identity WHERE id = 1", [], |row| {
+                        row.get(0)
+                    })
+                    .optional()?;
+                match data {
+                    None => Err(Error::NoIdentity),
+                    Some(data) => from_json(&data),
+                }
+            })
+            .await
+    }
+
+    async fn get_full(&self) -> Result<IdentityWithAll> {
+        let identity = self.get().await?;
+        let key_pair = self.get_key_pair().await?;
+        Ok(IdentityWithAll { identity, key_pair })
+    }
+
+    async fn save_key_pair(&self, key_pair: &BcrKeys, seed: &str) -> Result<()> {
+        let key = key_pair.get_private_key_string();
+        let seed = seed.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    r#"INSERT INTO identity_key (id, key, seed_phrase) VALUES (1, ?1, ?2)
+                        ON CONFLICT(id) DO UPDATE SET key = excluded.key, seed_phrase = excluded.seed_phrase"#,
+                    params![key, seed],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_key_pair(&self) -> Result<BcrKeys> {
+        match self.get_db_keys().await? {
+            None => Err(Error::NoIdentityKey),
+            Some((key, _)) => Ok(BcrKeys::from_private_key(&key)?),
+        }
+    }
+
+    async fn get_or_create_key_pair(&self) -> Result<BcrKeys> {
+        let keys = match self.get_key_pair().await {
+            Ok(keys) => keys,
+            _ => {
+                let (new_keys, seed) = BcrKeys::new_with_seed_phrase()?;
+                self.save_key_pair(&new_keys, &seed).await?;
+                new_keys
+            }
+        };
+        Ok(keys)
+    }
+
+    async fn get_seedphrase(&self) -> Result<String> {
+        match self.get_db_keys().await? {
+            Some((_, seed_phrase)) => Ok(seed_phrase),
+            None => Err(Error::NoSeedPhrase),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::sqlite::get_memory_sqlite_db, tests::tests::empty_identity};
+
+    async fn get_store() -> SqliteIdentityStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteIdentityStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_identity() {
+        let store = get_store().await;
+        assert!(!store.exists().await);
+        let mut identity = empty_identity();
+        identity.name = "Minka".to_string();
+        store.save(&identity).await.unwrap();
+        assert!(store.exists().await);
+        assert_eq!(store.get().await.unwrap(), identity);
+
+        identity.name = "Minka Updated".to_string();
+        store.save(&identity).await.unwrap();
+        assert_eq!(store.get().await.unwrap(), identity);
+    }
+
+    #[tokio::test]
+    async fn test_full_identity() {
+        let store = get_store().await;
+        let identity = empty_identity();
+        let (keys, seed) = BcrKeys::new_with_seed_phrase().expect("key could not be generated");
+        store.save(&identity).await.unwrap();
+        assert!(matches!(store.get_full().await, Err(Error::NoIdentityKey)));
+        store.save_key_pair(&keys, &seed).await.unwrap();
+        let fetched_full_identity = store.get_full().await.unwrap();
+        assert_eq!(
+            keys.get_public_key(),
+            fetched_full_identity.key_pair.get_public_key()
+        );
+        assert_eq!(store.get_seedphrase().await.unwrap(), seed);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_create_key_pair() {
+        let store = get_store().await;
+        let created = store.get_or_create_key_pair().await.unwrap();
+        let fetched = store.get_or_create_key_pair().await.unwrap();
+        assert_eq!(created.get_public_key(), fetched.get_public_key());
+    }
+}
@@ -0,0 +1,215 @@
+use super::super::super::{Error, Result};
+use super::{SqliteDb, enum_from_text, enum_to_text};
+use crate::identity::IdentityChainStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::blockchain::{
+    Block,
+    identity::{IdentityBlock, IdentityBlockchain},
+};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+
+const BLOCK_COLUMNS: &str =
+    "block_id, hash, previous_hash, signature, timestamp, public_key, data, op_code";
+
+#[derive(Clone)]
+pub struct SqliteIdentityChainStore {
+    db: SqliteDb,
+}
+
+impl SqliteIdentityChainStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+fn get_latest_block(conn: &Connection) -> Result<Option<IdentityBlock>> {
+    let row = conn
+        .query_row(
+            &format!("SELECT {BLOCK_COLUMNS} FROM identity_chain ORDER BY block_id DESC LIMIT 1"),
+            [],
+            IdentityBlockRow::from_row,
+        )
+        .optional()?;
+    row.map(|r| r.try_into()).transpose()
+}
+
+fn insert_block(conn: &Connection, block: &IdentityBlock) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO identity_chain ({BLOCK_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+        ),
+        params![
+            block.id,
+            block.hash,
+            block.previous_hash,
+            block.signature,
+            block.timestamp,
+            block.public_key,
+            block.data,
+            enum_to_text(&block.op_code)?,
+        ],
+    )?;
+    Ok(())
+}
+
+#[async_trait]
+impl IdentityChainStoreApi for SqliteIdentityChainStore {
+    async fn get_latest_block(&self) -> Result<IdentityBlock> {
+        self.db
+            .call(|conn| get_latest_block(conn)?.ok_or(Error::NoIdentityBlock))
+            .await
+    }
+
+    async fn add_block(&self, block: &IdentityBlock) -> Result<()> {
+        let block = block.clone();
+        self.db
+            .call(move |conn| {
+                // validation and insert happen in one transaction, so the chain stays valid
+                let tx = conn.transaction()?;
+                match get_latest_block(&tx)? {
+                    None => {
+                        // if there is no latest block, ensure it's a valid first block
+                        if !(block.id == 1 && block.verify() && block.validate_hash()) {
+                            return Err(Error::AddIdentityBlock(format!(
+                                "First Block validation error: block id: {}",
+                                block.id
+                            )));
+                        }
+                    }
+                    Some(latest_block) => {
+                        // if there is a latest block, ensure it's a valid follow-up block
+                        if !block.validate_with_previous(&latest_block) {
+                            return Err(Error::AddIdentityBlock(format!(
+                                "Block validation error: block id: {}, latest block id: {}",
+                                block.id, latest_block.id
+                            )));
+                        }
+                    }
+                }
+                insert_block(&tx, &block)?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_chain(&self) -> Result<IdentityBlockchain> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {BLOCK_COLUMNS} FROM identity_chain ORDER BY block_id ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([], IdentityBlockRow::from_row)?
+                    .collect::<rusqlite::Result<Vec<IdentityBlockRow>>>()?;
+                let blocks = rows
+                    .into_iter()
+                    .map(|r| r.try_into())
+                    .collect::<Result<Vec<IdentityBlock>>>()?;
+                Ok(IdentityBlockchain::new_from_blocks(blocks)?)
+            })
+            .await
+    }
+}
+
+struct IdentityBlockRow {
+    block_id: u64,
+    hash: String,
+    previous_hash: String,
+    signature: String,
+    timestamp: u64,
+    public_key: String,
+    data: String,
+    op_code: String,
+}
+
+impl IdentityBlockRow {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            block_id: row.get(0)?,
+            hash: row.get(1)?,
+            previous_hash: row.get(2)?,
+            signature: row.get(3)?,
+            timestamp: row.get(4)?,
+            public_key: row.get(5)?,
+            data: row.get(6)?,
+            op_code: row.get(7)?,
+        })
+    }
+}
+
+impl TryFrom<IdentityBlockRow> for IdentityBlock {
+    type Error = Error;
+
+    fn try_from(value: IdentityBlockRow) -> Result<Self> {
+        Ok(Self {
+            id: value.block_id,
+            hash: value.hash,
+            timestamp: value.timestamp,
+            data: value.data,
+            public_key: value.public_key,
+            previous_hash: value.previous_hash,
+            signature: value.signature,
+            op_code: enum_from_text(value.op_code)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::sqlite::get_memory_sqlite_db,
+        tests::tests::{empty_identity, empty_optional_address},
+        util::BcrKeys,
+    };
+    use bcr_ebill_core::blockchain::{Blockchain, identity::IdentityUpdateBlockData};
+
+    async fn get_store() -> SqliteIdentityChainStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteIdentityChainStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_add_block() {
+        let store = get_store().await;
+        assert!(matches!(
+            store.get_latest_block().await,
+            Err(Error::NoIdentityBlock)
+        ));
+        let block = IdentityBlock::create_block_for_create(
+            "genesis hash".to_string(),
+            &empty_identity().into(),
+            &BcrKeys::new(),
+            1731593928,
+        )
+        .unwrap();
+        store.add_block(&block).await.unwrap();
+        assert_eq!(store.get_latest_block().await.unwrap().id, 1);
+
+        let block2 = IdentityBlock::create_block_for_update(
+            &block,
+            &IdentityUpdateBlockData {
+                name: None,
+                email: None,
+                postal_address: empty_optional_address(),
+                date_of_birth: None,
+                country_of_birth: None,
+                city_of_birth: None,
+                identification_number: None,
+                profile_picture_file: None,
+                identity_document_file: None,
+            },
+            &BcrKeys::new(),
+            1731593928,
+        )
+        .unwrap();
+        store.add_block(&block2).await.unwrap();
+        assert_eq!(store.get_latest_block().await.unwrap().id, 2);
+
+        let chain = store.get_chain().await.unwrap();
+        assert_eq!(chain.blocks().len(), 2);
+    }
+}
@@ -0,0 +1,379 @@
+use super::super::super::{Error, Result};
+use super::super::migration::Migration;
+use log::info;
+use rusqlite::Connection;
+
+/// All SQLite migrations, ordered by version. New migrations are appended with the next
+/// version. Each query is a batch of SQL statements, which is applied in the same transaction
+/// that updates the `user_version` pragma of the database, where the applied version is tracked.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create initial tables",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS bill_chain (
+            bill_id TEXT NOT NULL,
+            block_id INTEGER NOT NULL,
+            hash TEXT NOT NULL,
+            previous_hash TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            public_key TEXT NOT NULL,
+            data TEXT NOT NULL,
+            op_code TEXT NOT NULL,
+            PRIMARY KEY (bill_id, block_id)
+        );
+        CREATE INDEX IF NOT EXISTS bill_chain_op_code ON bill_chain (op_code, timestamp);
+        CREATE TABLE IF NOT EXISTS bill_keys (
+            id TEXT PRIMARY KEY NOT NULL,
+            public_key TEXT NOT NULL,
+            private_key TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS bill_paid (
+            id TEXT PRIMARY KEY NOT NULL,
+            payment_address TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS company (
+            id TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            data TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS company_keys (
+            id TEXT PRIMARY KEY NOT NULL,
+            public_key TEXT NOT NULL,
+            private_key TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS company_chain (
+            company_id TEXT NOT NULL,
+            block_id INTEGER NOT NULL,
+            hash TEXT NOT NULL,
+            previous_hash TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            public_key TEXT NOT NULL,
+            signatory_node_id TEXT NOT NULL,
+            data TEXT NOT NULL,
+            op_code TEXT NOT NULL,
+            PRIMARY KEY (company_id, block_id)
+        );
+        CREATE TABLE IF NOT EXISTS contacts (
+            node_id TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            data TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS identity (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            data TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS identity_key (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            key TEXT NOT NULL,
+            seed_phrase TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS identity_chain (
+            block_id INTEGER PRIMARY KEY NOT NULL,
+            hash TEXT NOT NULL,
+            previous_hash TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            public_key TEXT NOT NULL,
+            data TEXT NOT NULL,
+            op_code TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS notifications (
+            id TEXT PRIMARY KEY NOT NULL,
+            node_id TEXT,
+            notification_type TEXT NOT NULL,
+            reference_id TEXT,
+            description TEXT NOT NULL,
+            datetime INTEGER NOT NULL,
+            active INTEGER NOT NULL,
+            payload TEXT
+        );
+        CREATE INDEX IF NOT EXISTS notifications_reference ON notifications (reference_id, notification_type, active);
+        CREATE TABLE IF NOT EXISTS sent_notifications (
+            notification_type TEXT NOT NULL,
+            reference_id TEXT NOT NULL,
+            block_height INTEGER NOT NULL,
+            action_type TEXT NOT NULL,
+            datetime INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS sent_notifications_reference ON sent_notifications (reference_id, block_height);
+        CREATE TABLE IF NOT EXISTS nostr_event_offset (
+            event_id TEXT PRIMARY KEY NOT NULL,
+            time INTEGER NOT NULL,
+            success INTEGER NOT NULL
+        );
+    "#,
+    },
+    Migration {
+        version: 2,
+        description: "create the materialised bill state table",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS bill_state (
+            bill_id TEXT NOT NULL,
+            identity_node_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            search_text TEXT NOT NULL,
+            issue_timestamp INTEGER,
+            time_of_drawing INTEGER NOT NULL,
+            time_of_maturity INTEGER NOT NULL,
+            sum INTEGER NOT NULL,
+            currency TEXT NOT NULL,
+            accepted INTEGER NOT NULL,
+            paid INTEGER NOT NULL,
+            waiting_for_payment_until INTEGER,
+            in_recourse_until INTEGER,
+            has_attachments INTEGER NOT NULL,
+            participants TEXT NOT NULL,
+            data TEXT NOT NULL,
+            PRIMARY KEY (bill_id, identity_node_id)
+        );
+        CREATE INDEX IF NOT EXISTS bill_state_identity ON bill_state (identity_node_id, role, time_of_drawing);
+    "#,
+    },
+    Migration {
+        version: 3,
+        description: "create the full-text search index tables",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS search_index (
+            item_type TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            field TEXT NOT NULL,
+            term TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS search_index_term ON search_index (term);
+        CREATE INDEX IF NOT EXISTS search_index_item ON search_index (item_type, item_id);
+        CREATE TABLE IF NOT EXISTS search_index_version (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            version INTEGER NOT NULL
+        );
+    "#,
+    },
+    Migration {
+        version: 4,
+        description: "create the API token table",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            id TEXT PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            scopes TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_used_at INTEGER
+        );
+    "#,
+    },
+    Migration {
+        version: 5,
+        description: "create the webhook tables",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id TEXT PRIMARY KEY NOT NULL,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            event_types TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id TEXT PRIMARY KEY NOT NULL,
+            webhook_id TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            next_attempt_at INTEGER NOT NULL,
+            last_response_status INTEGER,
+            last_error TEXT,
+            created_at INTEGER NOT NULL,
+            delivered_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id, created_at);
+        CREATE INDEX IF NOT EXISTS webhook_deliveries_due ON webhook_deliveries (status, next_attempt_at);
+    "#,
+    },
+    Migration {
+        version: 6,
+        description: "create the email preference table",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS email_preferences (
+            node_id TEXT PRIMARY KEY NOT NULL,
+            enabled INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+    "#,
+    },
+    Migration {
+        version: 7,
+        description: "create the quarantine table for rejected Nostr events",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS nostr_quarantine (
+            id TEXT PRIMARY KEY NOT NULL,
+            sender TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            received_at INTEGER NOT NULL
+        );
+    "#,
+    },
+    Migration {
+        version: 8,
+        description: "create the outbox table for outgoing Nostr events",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS nostr_outbox (
+            id TEXT PRIMARY KEY NOT NULL,
+            recipient TEXT NOT NULL,
+            recipient_relays TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL,
+            next_attempt_at INTEGER NOT NULL,
+            last_error TEXT,
+            created_at INTEGER NOT NULL,
+            sent_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS nostr_outbox_due ON nostr_outbox (status, next_attempt_at);
+        CREATE INDEX IF NOT EXISTS nostr_outbox_created_at ON nostr_outbox (created_at);
+    "#,
+    },
+    Migration {
+        version: 9,
+        description: "create the bill template tables",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS bill_templates (
+            id TEXT PRIMARY KEY NOT NULL,
+            data TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS bill_template_issues (
+            bill_id TEXT PRIMARY KEY NOT NULL,
+            template_id TEXT NOT NULL,
+            occurrence INTEGER,
+            issued_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS bill_template_issues_template_id ON bill_template_issues (template_id, issued_at);
+    "#,
+    },
+    Migration {
+        version: 10,
+        description: "create the table for bill events, that aren't confirmed by the chain yet",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS nostr_pending_events (
+            id TEXT PRIMARY KEY NOT NULL,
+            bill_id TEXT NOT NULL,
+            node_id TEXT NOT NULL,
+            sender TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            received_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS nostr_pending_events_received_at ON nostr_pending_events (received_at);
+    "#,
+    },
+    Migration {
+        version: 11,
+        description: "create the table for bills issued from batches",
+        query: r#"
+        CREATE TABLE IF NOT EXISTS bill_batch_issues (
+            batch_id TEXT NOT NULL,
+            row_hash TEXT NOT NULL,
+            bill_id TEXT NOT NULL,
+            issued_at INTEGER NOT NULL,
+            PRIMARY KEY (batch_id, row_hash)
+        );
+    "#,
+    },
+];
+
+/// The schema version this binary expects the database to be in
+pub fn latest_schema_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Returns the currently applied schema version, 0 for a new database
+pub fn get_schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Applies all migrations newer than the current schema version in order, each one in
+/// its own transaction. Fails, if the database has a newer schema version than this
+/// binary supports.
+pub fn run_migrations(conn: &mut Connection) -> Result<()> {
+    run_given_migrations(conn, MIGRATIONS)
+}
+
+fn run_given_migrations(conn: &mut Connection, migrations: &[Migration]) -> Result<()> {
+    let current = get_schema_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+    if current > latest {
+        return Err(Error::SchemaVersionTooNew(current, latest));
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        info!(
+            "Running SQLite migration {}: {}",
+            migration.version, migration.description
+        );
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.query)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let expected: Vec<u32> = (1..=MIGRATIONS.len() as u32).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[test]
+    fn test_run_migrations_twice() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), 0);
+        run_migrations(&mut conn).expect("migrations failed");
+        run_migrations(&mut conn).expect("migrations failed");
+        assert_eq!(get_schema_version(&conn).unwrap(), latest_schema_version());
+    }
+
+    #[test]
+    fn test_run_only_new_migrations() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "already applied",
+                query: "CREATE TABLE migration_test (done INTEGER);",
+            },
+            Migration {
+                version: 2,
+                description: "new",
+                query: "INSERT INTO migration_test (done) VALUES (1);",
+            },
+        ];
+        run_given_migrations(&mut conn, &migrations[..1]).expect("migrations failed");
+        run_given_migrations(&mut conn, &migrations).expect("migrations failed");
+        let count: u32 = conn
+            .query_row("SELECT COUNT(*) FROM migration_test", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(get_schema_version(&conn).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_refuses_newer_schema_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_schema_version() + 1)
+            .unwrap();
+        let result = run_migrations(&mut conn);
+        assert!(matches!(result, Err(Error::SchemaVersionTooNew(_, _))));
+    }
+}
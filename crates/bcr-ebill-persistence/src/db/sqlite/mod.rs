@@ -0,0 +1,157 @@
+use super::super::Result;
+use log::error;
+use rusqlite::Connection;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+pub mod api_token;
+pub mod backup;
+pub mod bill;
+pub mod bill_batch;
+pub mod bill_chain;
+pub mod bill_template;
+pub mod company;
+pub mod company_chain;
+pub mod contact;
+pub mod identity;
+pub mod identity_chain;
+pub mod migration;
+pub mod nostr_event_offset;
+pub mod nostr_outbox;
+pub mod nostr_pending_event;
+pub mod nostr_quarantine;
+pub mod notification;
+pub mod search_index;
+pub mod webhook;
+
+/// Path used to open a purely in-memory SQLite database, e.g. `sqlite::memory:`
+const MEMORY_PATH: &str = ":memory:";
+
+/// A shared SQLite connection. rusqlite is synchronous, so all statements are run on the
+/// blocking thread pool with exclusive access to the connection.
+#[derive(Clone)]
+pub struct SqliteDb {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDb {
+    fn new(conn: Connection) -> Self {
+        Self {
+            conn: Arc::new(Mutex::new(conn)),
+        }
+    }
+
+    /// Runs the given closure with the connection on the blocking thread pool
+    pub async fn call<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            // a panic while holding the lock doesn't leave the connection in an invalid state
+            let mut conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut conn)
+        })
+        .await?
+    }
+}
+
+/// Opens the SQLite database for the given connection string and brings its schema up to
+/// date. Supported are `sqlite://<path>` for a file based and `sqlite::memory:` for an
+/// in-memory database.
+pub async fn get_sqlite_db(connection_string: &str) -> Result<SqliteDb> {
+    let path = sqlite_path(connection_string).to_owned();
+    let conn = tokio::task::spawn_blocking(move || -> Result<Connection> {
+        let mut conn = if path.is_empty() || path == MEMORY_PATH {
+            Connection::open_in_memory()?
+        } else {
+            if let Some(parent) = Path::new(&path).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let conn = Connection::open(&path)?;
+            conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+            conn
+        };
+        migration::run_migrations(&mut conn)?;
+        Ok(conn)
+    })
+    .await?
+    .map_err(|e| {
+        error!("Error opening SQLite database {connection_string}. Error: {e}");
+        e
+    })?;
+    Ok(SqliteDb::new(conn))
+}
+
+fn sqlite_path(connection_string: &str) -> &str {
+    connection_string
+        .strip_prefix("sqlite://")
+        .or_else(|| connection_string.strip_prefix("sqlite:"))
+        .unwrap_or(connection_string)
+}
+
+/// Serializes the given value to a JSON string to store it in a text column
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn from_json<T: DeserializeOwned>(value: &str) -> Result<T> {
+    Ok(serde_json::from_str(value)?)
+}
+
+/// Stores unit enums like op codes by their variant name, so they can be used in queries
+fn enum_to_text<T: Serialize>(value: &T) -> Result<String> {
+    match serde_json::to_value(value)? {
+        Value::String(name) => Ok(name),
+        other => Ok(other.to_string()),
+    }
+}
+
+fn enum_from_text<T: DeserializeOwned>(value: String) -> Result<T> {
+    Ok(serde_json::from_value(Value::String(value))?)
+}
+
+#[cfg(test)]
+pub async fn get_memory_sqlite_db() -> Result<SqliteDb> {
+    get_sqlite_db("sqlite::memory:").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bcr_ebill_core::blockchain::bill::BillOpCode;
+
+    #[test]
+    fn test_sqlite_path() {
+        assert_eq!(sqlite_path("sqlite://data/ebills.db"), "data/ebills.db");
+        assert_eq!(sqlite_path("sqlite::memory:"), ":memory:");
+    }
+
+    #[test]
+    fn test_enum_text_roundtrip() {
+        let text = enum_to_text(&BillOpCode::RequestToPay).unwrap();
+        assert_eq!(text, "RequestToPay");
+        let op_code: BillOpCode = enum_from_text(text).unwrap();
+        assert_eq!(op_code, BillOpCode::RequestToPay);
+    }
+
+    #[tokio::test]
+    async fn test_get_file_sqlite_db() {
+        let dir = std::env::temp_dir().join(format!("bcr-sqlite-{}", uuid::Uuid::new_v4()));
+        let connection_string = format!("sqlite://{}", dir.join("ebills.db").display());
+        let db = get_sqlite_db(&connection_string)
+            .await
+            .expect("could not create db");
+        let version = db
+            .call(|conn| migration::get_schema_version(conn))
+            .await
+            .unwrap();
+        assert_eq!(version, migration::latest_schema_version());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+use super::super::super::Result;
+use super::SqliteDb;
+use crate::nostr::{NostrEventOffset, NostrEventOffsetStoreApi};
+use async_trait::async_trait;
+use rusqlite::params;
+
+#[derive(Clone)]
+pub struct SqliteNostrEventOffsetStore {
+    db: SqliteDb,
+}
+
+impl SqliteNostrEventOffsetStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl NostrEventOffsetStoreApi for SqliteNostrEventOffsetStore {
+    async fn current_offset(&self) -> Result<u64> {
+        self.db
+            .call(|conn| {
+                let offset: Option<u64> =
+                    conn.query_row("SELECT MAX(time) FROM nostr_event_offset", [], |row| {
+                        row.get(0)
+                    })?;
+                Ok(offset.unwrap_or(0))
+            })
+            .await
+    }
+
+    async fn is_processed(&self, event_id: &str) -> Result<bool> {
+        let event_id = event_id.to_owned();
+        self.db
+            .call(move |conn| {
+                Ok(conn.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM nostr_event_offset WHERE event_id = ?1)",
+                    [&event_id],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+    }
+
+    async fn add_event(&self, data: NostrEventOffset) -> Result<()> {
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO nostr_event_offset (event_id, time, success) VALUES (?1, ?2, ?3)",
+                    params![data.event_id, data.time, data.success],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_all(&self) -> Result<Vec<NostrEventOffset>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT event_id, time, success FROM nostr_event_offset ORDER BY time ASC",
+                )?;
+                let events = stmt
+                    .query_map([], |row| {
+                        Ok(NostrEventOffset {
+                            event_id: row.get(0)?,
+                            time: row.get(1)?,
+                            success: row.get(2)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<NostrEventOffset>>>()?;
+                Ok(events)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::get_memory_sqlite_db;
+
+    async fn get_store() -> SqliteNostrEventOffsetStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteNostrEventOffsetStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_get_offset_from_empty_table() {
+        let store = get_store().await;
+        assert_eq!(store.current_offset().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_add_events() {
+        let store = get_store().await;
+        store
+            .add_event(NostrEventOffset {
+                event_id: "second".to_string(),
+                time: 1000,
+                success: true,
+            })
+            .await
+            .unwrap();
+        store
+            .add_event(NostrEventOffset {
+                event_id: "first".to_string(),
+                time: 500,
+                success: false,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(store.current_offset().await.unwrap(), 1000);
+        assert!(store.is_processed("first").await.unwrap());
+        assert!(!store.is_processed("unknown").await.unwrap());
+
+        let all = store.get_all().await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].event_id, "first");
+        assert!(!all[0].success);
+    }
+}
@@ -0,0 +1,207 @@
+use super::super::super::Result;
+use super::{SqliteDb, enum_from_text, enum_to_text, from_json, to_json};
+use crate::nostr::{NostrOutboxEntry, NostrOutboxStoreApi};
+use async_trait::async_trait;
+use rusqlite::{Row, params};
+
+#[derive(Clone)]
+pub struct SqliteNostrOutboxStore {
+    db: SqliteDb,
+}
+
+impl SqliteNostrOutboxStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+const OUTBOX_COLUMNS: &str = "id, recipient, recipient_relays, event_type, payload, status, attempts, next_attempt_at, last_error, created_at, sent_at";
+
+struct OutboxRow {
+    id: String,
+    recipient: String,
+    recipient_relays: String,
+    event_type: String,
+    payload: String,
+    status: String,
+    attempts: u32,
+    next_attempt_at: u64,
+    last_error: Option<String>,
+    created_at: u64,
+    sent_at: Option<u64>,
+}
+
+fn read_row(row: &Row) -> rusqlite::Result<OutboxRow> {
+    Ok(OutboxRow {
+        id: row.get(0)?,
+        recipient: row.get(1)?,
+        recipient_relays: row.get(2)?,
+        event_type: row.get(3)?,
+        payload: row.get(4)?,
+        status: row.get(5)?,
+        attempts: row.get(6)?,
+        next_attempt_at: row.get(7)?,
+        last_error: row.get(8)?,
+        created_at: row.get(9)?,
+        sent_at: row.get(10)?,
+    })
+}
+
+impl TryFrom<OutboxRow> for NostrOutboxEntry {
+    type Error = crate::Error;
+
+    fn try_from(row: OutboxRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id,
+            recipient: row.recipient,
+            recipient_relays: from_json(&row.recipient_relays)?,
+            event_type: enum_from_text(row.event_type)?,
+            payload: row.payload,
+            status: enum_from_text(row.status)?,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            sent_at: row.sent_at,
+        })
+    }
+}
+
+#[async_trait]
+impl NostrOutboxStoreApi for SqliteNostrOutboxStore {
+    async fn insert(&self, entry: &NostrOutboxEntry) -> Result<()> {
+        let entry = entry.clone();
+        let recipient_relays = to_json(&entry.recipient_relays)?;
+        let event_type = enum_to_text(&entry.event_type)?;
+        let status = enum_to_text(&entry.status)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO nostr_outbox ({OUTBOX_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"
+                    ),
+                    params![
+                        entry.id,
+                        entry.recipient,
+                        recipient_relays,
+                        event_type,
+                        entry.payload,
+                        status,
+                        entry.attempts,
+                        entry.next_attempt_at,
+                        entry.last_error,
+                        entry.created_at,
+                        entry.sent_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn update(&self, entry: &NostrOutboxEntry) -> Result<()> {
+        let entry = entry.clone();
+        let status = enum_to_text(&entry.status)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE nostr_outbox SET status = ?1, attempts = ?2, next_attempt_at = ?3, last_error = ?4, sent_at = ?5 WHERE id = ?6",
+                    params![
+                        status,
+                        entry.attempts,
+                        entry.next_attempt_at,
+                        entry.last_error,
+                        entry.sent_at,
+                        entry.id
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_due(&self, timestamp: u64, limit: u64) -> Result<Vec<NostrOutboxEntry>> {
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {OUTBOX_COLUMNS} FROM nostr_outbox WHERE status = 'Pending' AND next_attempt_at <= ?1 ORDER BY next_attempt_at ASC LIMIT ?2"
+                ))?;
+                let rows = stmt
+                    .query_map(params![timestamp, limit], read_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(NostrOutboxEntry::try_from).collect()
+            })
+            .await
+    }
+
+    async fn list_unsent(&self, limit: u64) -> Result<Vec<NostrOutboxEntry>> {
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {OUTBOX_COLUMNS} FROM nostr_outbox WHERE status != 'Sent' ORDER BY created_at DESC LIMIT ?1"
+                ))?;
+                let rows = stmt
+                    .query_map([limit], read_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(NostrOutboxEntry::try_from).collect()
+            })
+            .await
+    }
+
+    async fn get_all(&self) -> Result<Vec<NostrOutboxEntry>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {OUTBOX_COLUMNS} FROM nostr_outbox ORDER BY created_at ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([], read_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(NostrOutboxEntry::try_from).collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::get_memory_sqlite_db;
+    use crate::nostr::NostrOutboxStatus;
+    use bcr_ebill_core::notification::EventType;
+
+    #[tokio::test]
+    async fn test_outbox() {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        let store = SqliteNostrOutboxStore::new(db);
+        let first = NostrOutboxEntry::new(
+            "first",
+            "recipient",
+            vec!["ws://localhost:8080".to_string()],
+            EventType::BillPaid,
+            "{}",
+            1000,
+        );
+        let mut second = NostrOutboxEntry::new(
+            "second",
+            "recipient",
+            vec![],
+            EventType::BillPaid,
+            "{}",
+            2000,
+        );
+        store.insert(&first).await.unwrap();
+        store.insert(&second).await.unwrap();
+        assert_eq!(store.get_due(1500, 10).await.unwrap(), vec![first.clone()]);
+
+        second.status = NostrOutboxStatus::Sent;
+        second.attempts = 1;
+        second.sent_at = Some(2100);
+        store.update(&second).await.unwrap();
+        assert_eq!(store.get_due(3000, 10).await.unwrap(), vec![first.clone()]);
+        assert_eq!(store.list_unsent(10).await.unwrap(), vec![first.clone()]);
+        assert_eq!(store.get_all().await.unwrap(), vec![first, second]);
+    }
+}
@@ -0,0 +1,136 @@
+use super::super::super::Result;
+use super::{SqliteDb, enum_from_text, enum_to_text};
+use crate::nostr::{NostrPendingEventStoreApi, PendingEvent};
+use async_trait::async_trait;
+use rusqlite::{Row, params};
+
+#[derive(Clone)]
+pub struct SqliteNostrPendingEventStore {
+    db: SqliteDb,
+}
+
+impl SqliteNostrPendingEventStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+const PENDING_EVENT_COLUMNS: &str =
+    "id, bill_id, node_id, sender, event_type, payload, received_at";
+
+struct PendingEventRow {
+    id: String,
+    bill_id: String,
+    node_id: String,
+    sender: String,
+    event_type: String,
+    payload: String,
+    received_at: u64,
+}
+
+fn read_row(row: &Row) -> rusqlite::Result<PendingEventRow> {
+    Ok(PendingEventRow {
+        id: row.get(0)?,
+        bill_id: row.get(1)?,
+        node_id: row.get(2)?,
+        sender: row.get(3)?,
+        event_type: row.get(4)?,
+        payload: row.get(5)?,
+        received_at: row.get(6)?,
+    })
+}
+
+impl TryFrom<PendingEventRow> for PendingEvent {
+    type Error = crate::Error;
+
+    fn try_from(row: PendingEventRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id,
+            bill_id: row.bill_id,
+            node_id: row.node_id,
+            sender: row.sender,
+            event_type: enum_from_text(row.event_type)?,
+            payload: row.payload,
+            received_at: row.received_at,
+        })
+    }
+}
+
+#[async_trait]
+impl NostrPendingEventStoreApi for SqliteNostrPendingEventStore {
+    async fn add(&self, event: PendingEvent) -> Result<()> {
+        let event_type = enum_to_text(&event.event_type)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT OR REPLACE INTO nostr_pending_events ({PENDING_EVENT_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+                    ),
+                    params![
+                        event.id,
+                        event.bill_id,
+                        event.node_id,
+                        event.sender,
+                        event_type,
+                        event.payload,
+                        event.received_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn list(&self) -> Result<Vec<PendingEvent>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {PENDING_EVENT_COLUMNS} FROM nostr_pending_events ORDER BY received_at ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([], read_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(PendingEvent::try_from).collect()
+            })
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute("DELETE FROM nostr_pending_events WHERE id = ?1", [&id])?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::get_memory_sqlite_db;
+    use bcr_ebill_core::notification::EventType;
+
+    #[tokio::test]
+    async fn test_pending_events() {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        let store = SqliteNostrPendingEventStore::new(db);
+        let event = PendingEvent {
+            id: "event".to_string(),
+            bill_id: "1234".to_string(),
+            node_id: "node_id".to_string(),
+            sender: "sender".to_string(),
+            event_type: EventType::BillPaymentRequested,
+            payload: "{}".to_string(),
+            received_at: 1000,
+        };
+        store.add(event.clone()).await.unwrap();
+        assert_eq!(store.list().await.unwrap(), vec![event]);
+
+        store.remove("event").await.unwrap();
+        assert!(store.list().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+use super::super::super::Result;
+use super::{SqliteDb, enum_from_text, enum_to_text};
+use crate::nostr::{NostrQuarantineStoreApi, QuarantinedEvent};
+use async_trait::async_trait;
+use rusqlite::{OptionalExtension, Row, params};
+
+#[derive(Clone)]
+pub struct SqliteNostrQuarantineStore {
+    db: SqliteDb,
+}
+
+impl SqliteNostrQuarantineStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+const QUARANTINE_COLUMNS: &str = "id, sender, event_type, payload, reason, received_at";
+
+struct QuarantinedEventRow {
+    id: String,
+    sender: String,
+    event_type: String,
+    payload: String,
+    reason: String,
+    received_at: u64,
+}
+
+fn read_row(row: &Row) -> rusqlite::Result<QuarantinedEventRow> {
+    Ok(QuarantinedEventRow {
+        id: row.get(0)?,
+        sender: row.get(1)?,
+        event_type: row.get(2)?,
+        payload: row.get(3)?,
+        reason: row.get(4)?,
+        received_at: row.get(5)?,
+    })
+}
+
+impl TryFrom<QuarantinedEventRow> for QuarantinedEvent {
+    type Error = crate::Error;
+
+    fn try_from(row: QuarantinedEventRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id,
+            sender: row.sender,
+            event_type: enum_from_text(row.event_type)?,
+            payload: row.payload,
+            reason: row.reason,
+            received_at: row.received_at,
+        })
+    }
+}
+
+#[async_trait]
+impl NostrQuarantineStoreApi for SqliteNostrQuarantineStore {
+    async fn add(&self, event: QuarantinedEvent) -> Result<()> {
+        let event_type = enum_to_text(&event.event_type)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT OR REPLACE INTO nostr_quarantine ({QUARANTINE_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+                    ),
+                    params![
+                        event.id,
+                        event.sender,
+                        event_type,
+                        event.payload,
+                        event.reason,
+                        event.received_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<QuarantinedEvent>> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let row = conn
+                    .query_row(
+                        &format!("SELECT {QUARANTINE_COLUMNS} FROM nostr_quarantine WHERE id = ?1"),
+                        [&id],
+                        read_row,
+                    )
+                    .optional()?;
+                row.map(QuarantinedEvent::try_from).transpose()
+            })
+            .await
+    }
+
+    async fn list(&self) -> Result<Vec<QuarantinedEvent>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {QUARANTINE_COLUMNS} FROM nostr_quarantine ORDER BY received_at DESC"
+                ))?;
+                let rows = stmt
+                    .query_map([], read_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(QuarantinedEvent::try_from).collect()
+            })
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute("DELETE FROM nostr_quarantine WHERE id = ?1", [&id])?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::get_memory_sqlite_db;
+    use bcr_ebill_core::notification::EventType;
+
+    #[tokio::test]
+    async fn test_quarantine() {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        let store = SqliteNostrQuarantineStore::new(db);
+        let event = QuarantinedEvent {
+            id: "event".to_string(),
+            sender: "sender".to_string(),
+            event_type: EventType::BillPaid,
+            payload: "{}".to_string(),
+            reason: "unknown sender".to_string(),
+            received_at: 1000,
+        };
+        store.add(event.clone()).await.unwrap();
+        assert_eq!(store.get("event").await.unwrap(), Some(event.clone()));
+        assert_eq!(store.list().await.unwrap(), vec![event]);
+
+        store.remove("event").await.unwrap();
+        assert!(store.get("event").await.unwrap().is_none());
+    }
+}
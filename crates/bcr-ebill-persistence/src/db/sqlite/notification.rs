@@ -0,0 +1,407 @@
+use super::super::super::{Error, Result};
+use super::{SqliteDb, enum_from_text, enum_to_text, from_json, to_json};
+use crate::notification::{NotificationFilter, NotificationStoreApi};
+use async_trait::async_trait;
+use bcr_ebill_core::{
+    notification::{ActionType, EmailPreference, Notification, NotificationType},
+    util::date::{DateTimeUtc, now},
+};
+use chrono::DateTime;
+use rusqlite::{OptionalExtension, Row, params, params_from_iter, types::Value};
+
+const NOTIFICATION_COLUMNS: &str =
+    "id, node_id, notification_type, reference_id, description, datetime, active, payload";
+
+#[derive(Clone)]
+pub struct SqliteNotificationStore {
+    db: SqliteDb,
+}
+
+impl SqliteNotificationStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+/// Builds the WHERE clause and the positional parameters for the given filter
+fn filter_clause(filter: &NotificationFilter) -> (String, Vec<Value>) {
+    let mut parts = vec![];
+    let mut values = vec![];
+    if let Some(active) = filter.active {
+        parts.push("active = ?");
+        values.push(Value::Integer(active as i64));
+    }
+    if let Some(reference_id) = filter.reference_id.as_ref() {
+        parts.push("reference_id = ?");
+        values.push(Value::Text(reference_id.to_owned()));
+    }
+    if let Some(notification_type) = filter.notification_type.as_ref() {
+        parts.push("notification_type = ?");
+        values.push(Value::Text(notification_type.to_owned()));
+    }
+    if parts.is_empty() {
+        (String::new(), values)
+    } else {
+        (format!("WHERE {}", parts.join(" AND ")), values)
+    }
+}
+
+#[async_trait]
+impl NotificationStoreApi for SqliteNotificationStore {
+    /// Stores a new notification into the database
+    async fn add(&self, notification: Notification) -> Result<Notification> {
+        let payload = notification.payload.as_ref().map(to_json).transpose()?;
+        let notification_type = enum_to_text(&notification.notification_type)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO notifications ({NOTIFICATION_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+                    ),
+                    params![
+                        notification.id,
+                        notification.node_id,
+                        notification_type,
+                        notification.reference_id,
+                        notification.description,
+                        notification.datetime.timestamp_micros(),
+                        notification.active,
+                        payload,
+                    ],
+                )?;
+                Ok(notification)
+            })
+            .await
+    }
+    /// Returns all currently active notifications from the database
+    async fn list(&self, filter: NotificationFilter) -> Result<Vec<Notification>> {
+        let (filters, mut values) = filter_clause(&filter);
+        values.push(Value::Integer(filter.get_limit()));
+        values.push(Value::Integer(filter.get_offset()));
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {NOTIFICATION_COLUMNS} FROM notifications {filters} ORDER BY datetime DESC, rowid DESC LIMIT ? OFFSET ?"
+                ))?;
+                let rows = stmt
+                    .query_map(params_from_iter(values), NotificationRow::from_row)?
+                    .collect::<rusqlite::Result<Vec<NotificationRow>>>()?;
+                rows.into_iter()
+                    .map(|r| r.try_into())
+                    .collect::<Result<Vec<Notification>>>()
+            })
+            .await
+    }
+    /// Returns the latest active notification for the given reference and notification type
+    async fn get_latest_by_reference(
+        &self,
+        reference: &str,
+        notification_type: NotificationType,
+    ) -> Result<Option<Notification>> {
+        let result = self
+            .list(NotificationFilter {
+                active: Some(true),
+                reference_id: Some(reference.to_owned()),
+                notification_type: Some(notification_type.to_string()),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .await?;
+        Ok(result.first().cloned())
+    }
+    /// Returns all notifications for the given reference and notification type that are active
+    async fn list_by_type(&self, notification_type: NotificationType) -> Result<Vec<Notification>> {
+        let result = self
+            .list(NotificationFilter {
+                active: Some(true),
+                notification_type: Some(notification_type.to_string()),
+                ..Default::default()
+            })
+            .await?;
+        Ok(result)
+    }
+    /// Marks an active notification as done
+    async fn mark_as_done(&self, notification_id: &str) -> Result<()> {
+        let notification_id = notification_id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE notifications SET active = 0 WHERE id = ?1",
+                    [&notification_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+    /// deletes a notification from the database
+    async fn delete(&self, notification_id: &str) -> Result<()> {
+        let notification_id = notification_id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "DELETE FROM notifications WHERE id = ?1",
+                    [&notification_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn set_bill_notification_sent(
+        &self,
+        bill_id: &str,
+        block_height: i32,
+        action_type: ActionType,
+    ) -> Result<()> {
+        let bill_id = bill_id.to_owned();
+        let notification_type = enum_to_text(&NotificationType::Bill)?;
+        let action_type = enum_to_text(&action_type)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    r#"INSERT INTO sent_notifications (notification_type, reference_id, block_height, action_type, datetime)
+                        VALUES (?1, ?2, ?3, ?4, ?5)"#,
+                    params![
+                        notification_type,
+                        bill_id,
+                        block_height,
+                        action_type,
+                        now().timestamp_micros()
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn bill_notification_sent(
+        &self,
+        bill_id: &str,
+        block_height: i32,
+        action_type: ActionType,
+    ) -> Result<bool> {
+        let bill_id = bill_id.to_owned();
+        let notification_type = enum_to_text(&NotificationType::Bill)?;
+        let action_type = enum_to_text(&action_type)?;
+        self.db
+            .call(move |conn| {
+                Ok(conn.query_row(
+                    r#"SELECT EXISTS(SELECT 1 FROM sent_notifications WHERE notification_type = ?1
+                        AND reference_id = ?2 AND block_height = ?3 AND action_type = ?4)"#,
+                    params![notification_type, bill_id, block_height, action_type],
+                    |row| row.get(0),
+                )?)
+            })
+            .await
+    }
+
+    async fn get_email_preference(&self, node_id: &str) -> Result<Option<EmailPreference>> {
+        let node_id = node_id.to_owned();
+        self.db
+            .call(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT node_id, enabled, updated_at FROM email_preferences WHERE node_id = ?1",
+                        [&node_id],
+                        |row| {
+                            Ok(EmailPreference {
+                                node_id: row.get(0)?,
+                                enabled: row.get(1)?,
+                                updated_at: row.get(2)?,
+                            })
+                        },
+                    )
+                    .optional()?)
+            })
+            .await
+    }
+
+    async fn set_email_preference(&self, preference: &EmailPreference) -> Result<()> {
+        let preference = preference.clone();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    r#"INSERT INTO email_preferences (node_id, enabled, updated_at) VALUES (?1, ?2, ?3)
+                        ON CONFLICT (node_id) DO UPDATE SET enabled = excluded.enabled, updated_at = excluded.updated_at"#,
+                    params![preference.node_id, preference.enabled, preference.updated_at],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+struct NotificationRow {
+    id: String,
+    node_id: Option<String>,
+    notification_type: String,
+    reference_id: Option<String>,
+    description: String,
+    datetime: DateTimeUtc,
+    active: bool,
+    payload: Option<String>,
+}
+
+impl NotificationRow {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let micros: i64 = row.get(5)?;
+        Ok(Self {
+            id: row.get(0)?,
+            node_id: row.get(1)?,
+            notification_type: row.get(2)?,
+            reference_id: row.get(3)?,
+            description: row.get(4)?,
+            datetime: DateTime::from_timestamp_micros(micros)
+                .ok_or(rusqlite::Error::IntegralValueOutOfRange(5, micros))?,
+            active: row.get(6)?,
+            payload: row.get(7)?,
+        })
+    }
+}
+
+impl TryFrom<NotificationRow> for Notification {
+    type Error = Error;
+
+    fn try_from(value: NotificationRow) -> Result<Self> {
+        Ok(Self {
+            id: value.id,
+            node_id: value.node_id,
+            notification_type: enum_from_text(value.notification_type)?,
+            reference_id: value.reference_id,
+            description: value.description,
+            datetime: value.datetime,
+            active: value.active,
+            payload: value.payload.map(|p| from_json(&p)).transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::get_memory_sqlite_db;
+    use serde_json::json;
+
+    async fn get_store() -> SqliteNotificationStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteNotificationStore::new(db)
+    }
+
+    fn test_notification(bill_id: &str) -> Notification {
+        Notification::new_bill_notification(
+            bill_id,
+            "node_id",
+            "test_notification",
+            Some(json!({ "Some": "value", "for": 66, "testing": true })),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_notification_sent() {
+        let store = get_store().await;
+        assert!(
+            !store
+                .bill_notification_sent("1234", 1, ActionType::PayBill)
+                .await
+                .unwrap()
+        );
+        store
+            .set_bill_notification_sent("1234", 1, ActionType::PayBill)
+            .await
+            .unwrap();
+        assert!(
+            store
+                .bill_notification_sent("1234", 1, ActionType::PayBill)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .bill_notification_sent("1234", 1, ActionType::AcceptBill)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_email_preference() {
+        let store = get_store().await;
+        assert!(
+            store
+                .get_email_preference("node_id")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let mut preference = EmailPreference {
+            node_id: "node_id".to_string(),
+            enabled: true,
+            updated_at: 1000,
+        };
+        store.set_email_preference(&preference).await.unwrap();
+        preference.enabled = false;
+        preference.updated_at = 2000;
+        store.set_email_preference(&preference).await.unwrap();
+        assert_eq!(
+            store.get_email_preference("node_id").await.unwrap(),
+            Some(preference)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inserts_and_queries_notification() {
+        let store = get_store().await;
+        let notification = test_notification("bill_id");
+        store.add(notification.clone()).await.unwrap();
+        let all = store.list(NotificationFilter::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, notification.id);
+        assert_eq!(all[0].payload, notification.payload);
+        assert_eq!(
+            all[0].datetime.timestamp_micros(),
+            notification.datetime.timestamp_micros()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_marks_done_and_no_longer_returns_by_reference() {
+        let store = get_store().await;
+        let first = store.add(test_notification("bill_id")).await.unwrap();
+        let second = store.add(test_notification("bill_id")).await.unwrap();
+
+        let latest = store
+            .get_latest_by_reference("bill_id", NotificationType::Bill)
+            .await
+            .unwrap()
+            .expect("notification found");
+        assert_eq!(latest.id, second.id);
+
+        store.mark_as_done(&second.id).await.unwrap();
+        let latest = store
+            .get_latest_by_reference("bill_id", NotificationType::Bill)
+            .await
+            .unwrap()
+            .expect("notification found");
+        assert_eq!(latest.id, first.id);
+        assert_eq!(
+            store
+                .list_by_type(NotificationType::Bill)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        store.delete(&first.id).await.unwrap();
+        assert!(
+            store
+                .get_latest_by_reference("bill_id", NotificationType::Bill)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
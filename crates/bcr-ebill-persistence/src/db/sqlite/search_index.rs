@@ -0,0 +1,213 @@
+use super::super::super::Result;
+use super::{SqliteDb, enum_from_text, enum_to_text};
+use crate::search_index::SearchIndexStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::{
+    GeneralSearchFilterItemType,
+    search::{SearchDocument, SearchPosting},
+};
+use rusqlite::{OptionalExtension, params, params_from_iter, types::Value};
+
+#[derive(Clone)]
+pub struct SqliteSearchIndexStore {
+    db: SqliteDb,
+}
+
+impl SqliteSearchIndexStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SearchIndexStoreApi for SqliteSearchIndexStore {
+    async fn index_document(&self, document: &SearchDocument) -> Result<()> {
+        let item_type = enum_to_text(&document.item_type)?;
+        let item_id = document.item_id.clone();
+        let mut terms = Vec::with_capacity(document.terms.len());
+        for term in document.terms.iter() {
+            terms.push((enum_to_text(&term.field)?, term.term.clone()));
+        }
+        self.db
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    "DELETE FROM search_index WHERE item_type = ?1 AND item_id = ?2",
+                    params![item_type, item_id],
+                )?;
+                for (field, term) in terms {
+                    tx.execute(
+                        "INSERT INTO search_index (item_type, item_id, field, term) VALUES (?1, ?2, ?3, ?4)",
+                        params![item_type, item_id, field, term],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn remove_document(
+        &self,
+        item_type: GeneralSearchFilterItemType,
+        item_id: &str,
+    ) -> Result<()> {
+        let item_type = enum_to_text(&item_type)?;
+        let item_id = item_id.to_owned();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "DELETE FROM search_index WHERE item_type = ?1 AND item_id = ?2",
+                    params![item_type, item_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn search(
+        &self,
+        tokens: &[String],
+        item_types: &[GeneralSearchFilterItemType],
+    ) -> Result<Vec<SearchPosting>> {
+        if tokens.is_empty() || item_types.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut values = Vec::with_capacity(item_types.len() + tokens.len() * 2);
+        for item_type in item_types {
+            values.push(Value::Text(enum_to_text(item_type)?));
+        }
+        for token in tokens {
+            values.push(Value::Text(token.clone()));
+            values.push(Value::Text(token.clone()));
+        }
+        let query = format!(
+            "SELECT item_type, item_id, field, term FROM search_index WHERE item_type IN ({}) AND ({})",
+            vec!["?"; item_types.len()].join(", "),
+            vec!["substr(term, 1, length(?)) = ?"; tokens.len()].join(" OR ")
+        );
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&query)?;
+                let rows = stmt
+                    .query_map(params_from_iter(values), |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, String>(3)?,
+                        ))
+                    })?
+                    .collect::<rusqlite::Result<Vec<(String, String, String, String)>>>()?;
+                rows.into_iter()
+                    .map(|(item_type, item_id, field, term)| {
+                        Ok(SearchPosting {
+                            item_type: enum_from_text(item_type)?,
+                            item_id,
+                            field: enum_from_text(field)?,
+                            term,
+                        })
+                    })
+                    .collect::<Result<Vec<SearchPosting>>>()
+            })
+            .await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.db
+            .call(|conn| {
+                conn.execute_batch("DELETE FROM search_index; DELETE FROM search_index_version;")?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_index_version(&self) -> Result<u32> {
+        self.db
+            .call(|conn| {
+                let version: Option<u32> = conn
+                    .query_row(
+                        "SELECT version FROM search_index_version WHERE id = 1",
+                        [],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                Ok(version.unwrap_or(0))
+            })
+            .await
+    }
+
+    async fn set_index_version(&self, version: u32) -> Result<()> {
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO search_index_version (id, version) VALUES (1, ?1)",
+                    [version],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{search_index::tests::get_document, sqlite::get_memory_sqlite_db};
+    use bcr_ebill_core::search::tokenize;
+
+    async fn get_store() -> SqliteSearchIndexStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteSearchIndexStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_index_search_and_remove() {
+        let store = get_store().await;
+        store
+            .index_document(&get_document(
+                GeneralSearchFilterItemType::Contact,
+                "contact1",
+                "Jürgen Müller",
+            ))
+            .await
+            .unwrap();
+        store
+            .index_document(&get_document(
+                GeneralSearchFilterItemType::Company,
+                "company1",
+                "Müller GmbH",
+            ))
+            .await
+            .unwrap();
+
+        let types = [
+            GeneralSearchFilterItemType::Contact,
+            GeneralSearchFilterItemType::Company,
+        ];
+        let all = store.search(&tokenize("mül"), &types).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all.iter().all(|p| p.term == "muller"));
+
+        store
+            .remove_document(GeneralSearchFilterItemType::Company, "company1")
+            .await
+            .unwrap();
+        let remaining = store.search(&tokenize("muller"), &types).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].item_id, "contact1");
+    }
+
+    #[tokio::test]
+    async fn test_index_version_and_clear() {
+        let store = get_store().await;
+        assert_eq!(store.get_index_version().await.unwrap(), 0);
+        store.set_index_version(1).await.unwrap();
+        store.set_index_version(2).await.unwrap();
+        assert_eq!(store.get_index_version().await.unwrap(), 2);
+        store.clear().await.unwrap();
+        assert_eq!(store.get_index_version().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,326 @@
+use super::super::super::Result;
+use super::{SqliteDb, enum_from_text, enum_to_text, from_json, to_json};
+use crate::webhook::WebhookStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::webhook::{Webhook, WebhookDelivery};
+use rusqlite::{OptionalExtension, Row, params};
+
+#[derive(Clone)]
+pub struct SqliteWebhookStore {
+    db: SqliteDb,
+}
+
+impl SqliteWebhookStore {
+    pub fn new(db: SqliteDb) -> Self {
+        Self { db }
+    }
+}
+
+const WEBHOOK_COLUMNS: &str = "id, url, secret, event_types, created_at";
+
+const DELIVERY_COLUMNS: &str = "id, webhook_id, event_type, payload, status, attempts, next_attempt_at, last_response_status, last_error, created_at, delivered_at";
+
+/// The raw columns of a webhook, the event types are parsed separately, since they can't fail
+/// with a rusqlite error
+struct WebhookRow {
+    id: String,
+    url: String,
+    secret: String,
+    event_types: String,
+    created_at: u64,
+}
+
+fn read_webhook_row(row: &Row) -> rusqlite::Result<WebhookRow> {
+    Ok(WebhookRow {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        secret: row.get(2)?,
+        event_types: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+impl TryFrom<WebhookRow> for Webhook {
+    type Error = crate::Error;
+
+    fn try_from(row: WebhookRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id,
+            url: row.url,
+            secret: row.secret,
+            event_types: from_json(&row.event_types)?,
+            created_at: row.created_at,
+        })
+    }
+}
+
+struct DeliveryRow {
+    id: String,
+    webhook_id: String,
+    event_type: String,
+    payload: String,
+    status: String,
+    attempts: u32,
+    next_attempt_at: u64,
+    last_response_status: Option<u16>,
+    last_error: Option<String>,
+    created_at: u64,
+    delivered_at: Option<u64>,
+}
+
+fn read_delivery_row(row: &Row) -> rusqlite::Result<DeliveryRow> {
+    Ok(DeliveryRow {
+        id: row.get(0)?,
+        webhook_id: row.get(1)?,
+        event_type: row.get(2)?,
+        payload: row.get(3)?,
+        status: row.get(4)?,
+        attempts: row.get(5)?,
+        next_attempt_at: row.get(6)?,
+        last_response_status: row.get(7)?,
+        last_error: row.get(8)?,
+        created_at: row.get(9)?,
+        delivered_at: row.get(10)?,
+    })
+}
+
+impl TryFrom<DeliveryRow> for WebhookDelivery {
+    type Error = crate::Error;
+
+    fn try_from(row: DeliveryRow) -> Result<Self> {
+        Ok(Self {
+            id: row.id,
+            webhook_id: row.webhook_id,
+            event_type: enum_from_text(row.event_type)?,
+            payload: row.payload,
+            status: enum_from_text(row.status)?,
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            last_response_status: row.last_response_status,
+            last_error: row.last_error,
+            created_at: row.created_at,
+            delivered_at: row.delivered_at,
+        })
+    }
+}
+
+#[async_trait]
+impl WebhookStoreApi for SqliteWebhookStore {
+    async fn insert(&self, webhook: &Webhook) -> Result<()> {
+        let webhook = webhook.clone();
+        let event_types = to_json(&webhook.event_types)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO webhooks ({WEBHOOK_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5)"
+                    ),
+                    params![
+                        webhook.id,
+                        webhook.url,
+                        webhook.secret,
+                        event_types,
+                        webhook.created_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Webhook>> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let row = conn
+                    .query_row(
+                        &format!("SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE id = ?1"),
+                        [&id],
+                        read_webhook_row,
+                    )
+                    .optional()?;
+                row.map(Webhook::try_from).transpose()
+            })
+            .await
+    }
+
+    async fn list(&self) -> Result<Vec<Webhook>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {WEBHOOK_COLUMNS} FROM webhooks ORDER BY created_at ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([], read_webhook_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(Webhook::try_from).collect()
+            })
+            .await
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let id = id.to_owned();
+        self.db
+            .call(move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    "DELETE FROM webhook_deliveries WHERE webhook_id = ?1",
+                    [&id],
+                )?;
+                tx.execute("DELETE FROM webhooks WHERE id = ?1", [&id])?;
+                tx.commit()?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn insert_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let delivery = delivery.clone();
+        let event_type = enum_to_text(&delivery.event_type)?;
+        let status = enum_to_text(&delivery.status)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO webhook_deliveries ({DELIVERY_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"
+                    ),
+                    params![
+                        delivery.id,
+                        delivery.webhook_id,
+                        event_type,
+                        delivery.payload,
+                        status,
+                        delivery.attempts,
+                        delivery.next_attempt_at,
+                        delivery.last_response_status,
+                        delivery.last_error,
+                        delivery.created_at,
+                        delivery.delivered_at
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn update_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let delivery = delivery.clone();
+        let status = enum_to_text(&delivery.status)?;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "UPDATE webhook_deliveries SET status = ?1, attempts = ?2, next_attempt_at = ?3, last_response_status = ?4, last_error = ?5, delivered_at = ?6 WHERE id = ?7",
+                    params![
+                        status,
+                        delivery.attempts,
+                        delivery.next_attempt_at,
+                        delivery.last_response_status,
+                        delivery.last_error,
+                        delivery.delivered_at,
+                        delivery.id
+                    ],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    async fn get_due_deliveries(&self, timestamp: u64, limit: u64) -> Result<Vec<WebhookDelivery>> {
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries WHERE status = 'Pending' AND next_attempt_at <= ?1 ORDER BY next_attempt_at ASC LIMIT ?2"
+                ))?;
+                let rows = stmt
+                    .query_map(params![timestamp, limit], read_delivery_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(WebhookDelivery::try_from).collect()
+            })
+            .await
+    }
+
+    async fn list_deliveries(&self, webhook_id: &str, limit: u64) -> Result<Vec<WebhookDelivery>> {
+        let webhook_id = webhook_id.to_owned();
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries WHERE webhook_id = ?1 ORDER BY created_at DESC LIMIT ?2"
+                ))?;
+                let rows = stmt
+                    .query_map(params![webhook_id, limit], read_delivery_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(WebhookDelivery::try_from).collect()
+            })
+            .await
+    }
+
+    async fn get_all_deliveries(&self) -> Result<Vec<WebhookDelivery>> {
+        self.db
+            .call(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {DELIVERY_COLUMNS} FROM webhook_deliveries ORDER BY created_at ASC"
+                ))?;
+                let rows = stmt
+                    .query_map([], read_delivery_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows.into_iter().map(WebhookDelivery::try_from).collect()
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::get_memory_sqlite_db;
+    use bcr_ebill_core::notification::EventType;
+    use bcr_ebill_core::webhook::WebhookDeliveryStatus;
+
+    async fn get_store() -> SqliteWebhookStore {
+        let db = get_memory_sqlite_db()
+            .await
+            .expect("could not create sqlite db");
+        SqliteWebhookStore::new(db)
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_and_deliveries() {
+        let store = get_store().await;
+        let webhook = Webhook {
+            id: "hook".to_string(),
+            url: "http://localhost:9000/hook".to_string(),
+            secret: "secret".to_string(),
+            event_types: vec![EventType::BillSigned],
+            created_at: 1000,
+        };
+        store.insert(&webhook).await.unwrap();
+        assert_eq!(store.get("hook").await.unwrap(), Some(webhook.clone()));
+        assert_eq!(store.list().await.unwrap(), vec![webhook]);
+
+        let first = WebhookDelivery::new("d1", "hook", EventType::BillSigned, "{}", 1000);
+        let mut second = WebhookDelivery::new("d2", "hook", EventType::BillSigned, "{}", 2000);
+        store.insert_delivery(&first).await.unwrap();
+        store.insert_delivery(&second).await.unwrap();
+        assert_eq!(
+            store.get_due_deliveries(1500, 10).await.unwrap(),
+            vec![first.clone()]
+        );
+
+        second.status = WebhookDeliveryStatus::Failed;
+        second.attempts = 8;
+        second.last_error = Some("connection refused".to_string());
+        store.update_delivery(&second).await.unwrap();
+        assert_eq!(
+            store.list_deliveries("hook", 10).await.unwrap(),
+            vec![second.clone(), first.clone()]
+        );
+        assert_eq!(
+            store.get_all_deliveries().await.unwrap(),
+            vec![first, second]
+        );
+
+        store.remove("hook").await.unwrap();
+        assert!(store.get("hook").await.unwrap().is_none());
+        assert!(store.list_deliveries("hook", 10).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use super::Result;
+use crate::constants::{DB_LIMIT, DB_TABLE, DB_TIMESTAMP};
+use crate::webhook::WebhookStoreApi;
+use async_trait::async_trait;
+use bcr_ebill_core::{
+    notification::EventType,
+    webhook::{Webhook, WebhookDelivery, WebhookDeliveryStatus},
+};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Surreal, engine::any::Any, sql::Thing};
+
+const DB_WEBHOOK_ID: &str = "webhook_id";
+
+#[derive(Clone)]
+pub struct SurrealWebhookStore {
+    db: Surreal<Any>,
+}
+
+impl SurrealWebhookStore {
+    const TABLE: &'static str = "webhooks";
+    const DELIVERIES_TABLE: &'static str = "webhook_deliveries";
+
+    pub fn new(db: Surreal<Any>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl WebhookStoreApi for SurrealWebhookStore {
+    async fn insert(&self, webhook: &Webhook) -> Result<()> {
+        let entity: WebhookDb = webhook.into();
+        let _: Option<WebhookDb> = self
+            .db
+            .create((Self::TABLE, webhook.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Webhook>> {
+        let result: Option<WebhookDb> = self.db.select((Self::TABLE, id)).await?;
+        Ok(result.map(|w| w.into()))
+    }
+
+    async fn list(&self) -> Result<Vec<Webhook>> {
+        let result: Vec<WebhookDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY created_at ASC")
+            .bind((DB_TABLE, Self::TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|w| w.into()).collect())
+    }
+
+    async fn remove(&self, id: &str) -> Result<()> {
+        let _: Option<WebhookDb> = self.db.delete((Self::TABLE, id)).await?;
+        self.db
+            .query("DELETE FROM type::table($table) WHERE webhook_id = $webhook_id")
+            .bind((DB_TABLE, Self::DELIVERIES_TABLE))
+            .bind((DB_WEBHOOK_ID, id.to_owned()))
+            .await?;
+        Ok(())
+    }
+
+    async fn insert_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let entity: WebhookDeliveryDb = delivery.into();
+        let _: Option<WebhookDeliveryDb> = self
+            .db
+            .create((Self::DELIVERIES_TABLE, delivery.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_delivery(&self, delivery: &WebhookDelivery) -> Result<()> {
+        let entity: WebhookDeliveryDb = delivery.into();
+        let _: Option<WebhookDeliveryDb> = self
+            .db
+            .update((Self::DELIVERIES_TABLE, delivery.id.to_owned()))
+            .content(entity)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_due_deliveries(&self, timestamp: u64, limit: u64) -> Result<Vec<WebhookDelivery>> {
+        let result: Vec<WebhookDeliveryDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE status = 'Pending' AND next_attempt_at <= $timestamp ORDER BY next_attempt_at ASC LIMIT $limit")
+            .bind((DB_TABLE, Self::DELIVERIES_TABLE))
+            .bind((DB_TIMESTAMP, timestamp))
+            .bind((DB_LIMIT, limit))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|d| d.into()).collect())
+    }
+
+    async fn list_deliveries(&self, webhook_id: &str, limit: u64) -> Result<Vec<WebhookDelivery>> {
+        let result: Vec<WebhookDeliveryDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) WHERE webhook_id = $webhook_id ORDER BY created_at DESC LIMIT $limit")
+            .bind((DB_TABLE, Self::DELIVERIES_TABLE))
+            .bind((DB_WEBHOOK_ID, webhook_id.to_owned()))
+            .bind((DB_LIMIT, limit))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|d| d.into()).collect())
+    }
+
+    async fn get_all_deliveries(&self) -> Result<Vec<WebhookDelivery>> {
+        let result: Vec<WebhookDeliveryDb> = self
+            .db
+            .query("SELECT * FROM type::table($table) ORDER BY created_at ASC")
+            .bind((DB_TABLE, Self::DELIVERIES_TABLE))
+            .await?
+            .take(0)?;
+        Ok(result.into_iter().map(|d| d.into()).collect())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct WebhookDb {
+    pub id: Thing,
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<EventType>,
+    pub created_at: u64,
+}
+
+impl From<&Webhook> for WebhookDb {
+    fn from(value: &Webhook) -> Self {
+        Self {
+            id: (SurrealWebhookStore::TABLE, value.id.as_str()).into(),
+            url: value.url.clone(),
+            secret: value.secret.clone(),
+            event_types: value.event_types.clone(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<WebhookDb> for Webhook {
+    fn from(value: WebhookDb) -> Self {
+        Self {
+            id: value.id.id.to_raw(),
+            url: value.url,
+            secret: value.secret,
+            event_types: value.event_types,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct WebhookDeliveryDb {
+    pub id: Thing,
+    pub webhook_id: String,
+    pub event_type: EventType,
+    pub payload: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub delivered_at: Option<u64>,
+}
+
+impl From<&WebhookDelivery> for WebhookDeliveryDb {
+    fn from(value: &WebhookDelivery) -> Self {
+        Self {
+            id: (SurrealWebhookStore::DELIVERIES_TABLE, value.id.as_str()).into(),
+            webhook_id: value.webhook_id.clone(),
+            event_type: value.event_type.clone(),
+            payload: value.payload.clone(),
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_response_status: value.last_response_status,
+            last_error: value.last_error.clone(),
+            created_at: value.created_at,
+            delivered_at: value.delivered_at,
+        }
+    }
+}
+
+impl From<WebhookDeliveryDb> for WebhookDelivery {
+    fn from(value: WebhookDeliveryDb) -> Self {
+        Self {
+            id: value.id.id.to_raw(),
+            webhook_id: value.webhook_id,
+            event_type: value.event_type,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_response_status: value.last_response_status,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            delivered_at: value.delivered_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::get_memory_db;
+
+    async fn get_store() -> SurrealWebhookStore {
+        let mem_db = get_memory_db("test", "webhooks")
+            .await
+            .expect("could not create memory db");
+        SurrealWebhookStore::new(mem_db)
+    }
+
+    fn get_webhook(id: &str, created_at: u64) -> Webhook {
+        Webhook {
+            id: id.to_string(),
+            url: "http://localhost:9000/hook".to_string(),
+            secret: "secret".to_string(),
+            event_types: vec![EventType::BillSigned, EventType::BillPaid],
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhooks() {
+        let store = get_store().await;
+        store.insert(&get_webhook("second", 2000)).await.unwrap();
+        store.insert(&get_webhook("first", 1000)).await.unwrap();
+
+        assert_eq!(
+            store.get("second").await.unwrap(),
+            Some(get_webhook("second", 2000))
+        );
+        let webhooks = store.list().await.unwrap();
+        assert_eq!(webhooks.len(), 2);
+        assert_eq!(webhooks[0].id, "first");
+
+        store.remove("first").await.unwrap();
+        assert!(store.get("first").await.unwrap().is_none());
+        assert_eq!(store.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deliveries() {
+        let store = get_store().await;
+        let first = WebhookDelivery::new("d1", "hook", EventType::BillSigned, "{}", 1000);
+        let mut second = WebhookDelivery::new("d2", "hook", EventType::BillPaid, "{}", 2000);
+        store.insert_delivery(&first).await.unwrap();
+        store.insert_delivery(&second).await.unwrap();
+
+        let due = store.get_due_deliveries(1500, 10).await.unwrap();
+        assert_eq!(due, vec![first.clone()]);
+
+        second.status = WebhookDeliveryStatus::Delivered;
+        second.attempts = 1;
+        second.last_response_status = Some(200);
+        store.update_delivery(&second).await.unwrap();
+        assert_eq!(store.get_due_deliveries(3000, 10).await.unwrap().len(), 1);
+
+        let deliveries = store.list_deliveries("hook", 10).await.unwrap();
+        assert_eq!(deliveries, vec![second.clone(), first.clone()]);
+        assert_eq!(
+            store.get_all_deliveries().await.unwrap(),
+            vec![first, second]
+        );
+
+        store.insert(&get_webhook("hook", 1000)).await.unwrap();
+        store.remove("hook").await.unwrap();
+        assert!(store.list_deliveries("hook", 10).await.unwrap().is_empty());
+    }
+}
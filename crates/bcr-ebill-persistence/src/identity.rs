@@ -2,7 +2,7 @@ use super::Result;
 use async_trait::async_trait;
 
 use bcr_ebill_core::{
-    blockchain::identity::IdentityBlock,
+    blockchain::identity::{IdentityBlock, IdentityBlockchain},
     identity::{Identity, IdentityWithAll},
     util::crypto::BcrKeys,
 };
@@ -34,4 +34,6 @@ pub trait IdentityChainStoreApi: Send + Sync {
     async fn get_latest_block(&self) -> Result<IdentityBlock>;
     /// Adds the block to the chain
     async fn add_block(&self, block: &IdentityBlock) -> Result<()>;
+    /// Get the whole blockchain
+    async fn get_chain(&self) -> Result<IdentityBlockchain>;
 }
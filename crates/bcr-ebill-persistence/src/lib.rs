@@ -1,5 +1,8 @@
+pub mod api_token;
 pub mod backup;
 pub mod bill;
+pub mod bill_batch;
+pub mod bill_template;
 pub mod company;
 pub mod constants;
 pub mod contact;
@@ -8,8 +11,10 @@ pub mod file_upload;
 pub mod identity;
 pub mod nostr;
 pub mod notification;
+pub mod search_index;
 #[cfg(test)]
 mod tests;
+pub mod webhook;
 
 use bcr_ebill_core::util;
 use log::error;
@@ -81,17 +86,59 @@ pub enum Error {
 
     #[error("No seed phrase available")]
     NoSeedPhrase,
+
+    #[error("database schema version {0} is newer than the supported version {1}")]
+    SchemaVersionTooNew(u32, u32),
+
+    #[error("json serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unsupported database backend: {0}")]
+    UnsupportedBackend(String),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite task error {0}")]
+    SqliteTask(#[from] tokio::task::JoinError),
 }
 
+pub use api_token::ApiTokenStoreApi;
 pub use backup::BackupStoreApi;
+pub use bill_batch::BillBatchStoreApi;
+pub use bill_template::BillTemplateStoreApi;
 pub use contact::ContactStoreApi;
+#[cfg(feature = "sqlite")]
+pub use db::sqlite::{
+    SqliteDb, api_token::SqliteApiTokenStore, backup::SqliteBackupStore, bill::SqliteBillStore,
+    bill_batch::SqliteBillBatchStore, bill_chain::SqliteBillChainStore,
+    bill_template::SqliteBillTemplateStore, company::SqliteCompanyStore,
+    company_chain::SqliteCompanyChainStore, contact::SqliteContactStore, get_sqlite_db,
+    identity::SqliteIdentityStore, identity_chain::SqliteIdentityChainStore,
+    nostr_event_offset::SqliteNostrEventOffsetStore, nostr_outbox::SqliteNostrOutboxStore,
+    nostr_pending_event::SqliteNostrPendingEventStore,
+    nostr_quarantine::SqliteNostrQuarantineStore, notification::SqliteNotificationStore,
+    search_index::SqliteSearchIndexStore, webhook::SqliteWebhookStore,
+};
 pub use db::{
-    SurrealDbConfig, backup::SurrealBackupStore, bill::SurrealBillStore,
-    bill_chain::SurrealBillChainStore, company::SurrealCompanyStore,
-    company_chain::SurrealCompanyChainStore, contact::SurrealContactStore, get_surreal_db,
-    identity::SurrealIdentityStore, identity_chain::SurrealIdentityChainStore,
-    nostr_event_offset::SurrealNostrEventOffsetStore, notification::SurrealNotificationStore,
+    SurrealDbConfig, api_token::SurrealApiTokenStore, backup::SurrealBackupStore,
+    bill::SurrealBillStore, bill_batch::SurrealBillBatchStore, bill_chain::SurrealBillChainStore,
+    bill_template::SurrealBillTemplateStore, company::SurrealCompanyStore,
+    company_chain::SurrealCompanyChainStore, contact::SurrealContactStore, get_migrated_surreal_db,
+    get_surreal_db, identity::SurrealIdentityStore, identity_chain::SurrealIdentityChainStore,
+    is_sqlite_connection, nostr_event_offset::SurrealNostrEventOffsetStore,
+    nostr_outbox::SurrealNostrOutboxStore, nostr_pending_event::SurrealNostrPendingEventStore,
+    nostr_quarantine::SurrealNostrQuarantineStore, notification::SurrealNotificationStore,
+    search_index::SurrealSearchIndexStore, webhook::SurrealWebhookStore,
 };
 pub use file_upload::FileUploadStore;
-pub use nostr::{NostrEventOffset, NostrEventOffsetStoreApi};
+pub use nostr::{
+    NostrEventOffset, NostrEventOffsetStoreApi, NostrOutboxEntry, NostrOutboxStatus,
+    NostrOutboxStoreApi, NostrPendingEventStoreApi, NostrQuarantineStoreApi, PendingEvent,
+    QuarantinedEvent,
+};
 pub use notification::NotificationStoreApi;
+pub use search_index::SearchIndexStoreApi;
+pub use webhook::WebhookStoreApi;
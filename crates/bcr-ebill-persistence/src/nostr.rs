@@ -1,5 +1,7 @@
 use super::Result;
 use async_trait::async_trait;
+use bcr_ebill_core::notification::EventType;
+use serde::{Deserialize, Serialize};
 
 /// Allows storing and retrieving time based offsets for subscriptions
 /// to Nostr relays. It will also store the event ids that have been
@@ -22,6 +24,9 @@ pub trait NostrEventOffsetStoreApi: Send + Sync {
 
     /// Stores the given event data in the store.
     async fn add_event(&self, data: NostrEventOffset) -> Result<()>;
+
+    /// Returns all stored events ordered by their timestamp.
+    async fn get_all(&self) -> Result<Vec<NostrEventOffset>>;
 }
 
 /// A simple struct to store the event id and the time it was received.
@@ -35,3 +40,138 @@ pub struct NostrEventOffset {
     /// Whether the event has been processed successfully on our side
     pub success: bool,
 }
+
+/// Keeps incoming events, whose sender wasn't authorised to send them, so they can be
+/// reviewed and either released to the event handlers, or discarded.
+#[async_trait]
+pub trait NostrQuarantineStoreApi: Send + Sync {
+    /// Stores the given rejected event
+    async fn add(&self, event: QuarantinedEvent) -> Result<()>;
+
+    /// Returns the quarantined event with the given id, if there is one
+    async fn get(&self, id: &str) -> Result<Option<QuarantinedEvent>>;
+
+    /// Returns all quarantined events, the latest received first
+    async fn list(&self) -> Result<Vec<QuarantinedEvent>>;
+
+    /// Removes the quarantined event with the given id
+    async fn remove(&self, id: &str) -> Result<()>;
+}
+
+/// An incoming event, which was rejected by the sender policy of its event type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedEvent {
+    /// The nostr event id
+    pub id: String,
+    /// The hex encoded Nostr public key of the sender
+    pub sender: String,
+    pub event_type: EventType,
+    /// The received event envelope as json
+    pub payload: String,
+    /// Why the sender was rejected
+    pub reason: String,
+    pub received_at: u64,
+}
+
+/// Keeps incoming bill events, which our copy of the bill's chain doesn't confirm yet, so they
+/// can be handled once the chain caught up, instead of getting lost
+#[async_trait]
+pub trait NostrPendingEventStoreApi: Send + Sync {
+    /// Stores the given unconfirmed event
+    async fn add(&self, event: PendingEvent) -> Result<()>;
+
+    /// Returns all pending events, the oldest received first
+    async fn list(&self) -> Result<Vec<PendingEvent>>;
+
+    /// Removes the pending event with the given id
+    async fn remove(&self, id: &str) -> Result<()>;
+}
+
+/// An incoming bill event, that is waiting for the chain of its bill to confirm it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEvent {
+    pub id: String,
+    pub bill_id: String,
+    /// The node id of the local identity, that received the event
+    pub node_id: String,
+    /// The hex encoded Nostr public key of the sender
+    pub sender: String,
+    pub event_type: EventType,
+    /// The received event envelope as json
+    pub payload: String,
+    pub received_at: u64,
+}
+
+/// Keeps outgoing events until a relay confirmed them, so they can be retried after
+/// failures instead of getting lost
+#[async_trait]
+pub trait NostrOutboxStoreApi: Send + Sync {
+    /// Stores a new outgoing event
+    async fn insert(&self, entry: &NostrOutboxEntry) -> Result<()>;
+
+    /// Updates the status and attempts of the given entry
+    async fn update(&self, entry: &NostrOutboxEntry) -> Result<()>;
+
+    /// Returns the pending entries, that are due to be sent at the given timestamp, oldest
+    /// first
+    async fn get_due(&self, timestamp: u64, limit: u64) -> Result<Vec<NostrOutboxEntry>>;
+
+    /// Returns the latest pending and failed entries, newest first
+    async fn list_unsent(&self, limit: u64) -> Result<Vec<NostrOutboxEntry>>;
+
+    /// Returns all entries, oldest first
+    async fn get_all(&self) -> Result<Vec<NostrOutboxEntry>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NostrOutboxStatus {
+    /// Not confirmed by a relay yet, it's (re-)tried at `next_attempt_at`
+    Pending,
+    Sent,
+    /// All attempts failed
+    Failed,
+}
+
+/// An outgoing event together with the state of its delivery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NostrOutboxEntry {
+    pub id: String,
+    /// The node id of the recipient, the event is encrypted for
+    pub recipient: String,
+    /// The relays of the recipient at the time the event was sent
+    pub recipient_relays: Vec<String>,
+    pub event_type: EventType,
+    /// The event envelope as json
+    pub payload: String,
+    pub status: NostrOutboxStatus,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub sent_at: Option<u64>,
+}
+
+impl NostrOutboxEntry {
+    pub fn new(
+        id: &str,
+        recipient: &str,
+        recipient_relays: Vec<String>,
+        event_type: EventType,
+        payload: &str,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            id: id.to_owned(),
+            recipient: recipient.to_owned(),
+            recipient_relays,
+            event_type,
+            payload: payload.to_owned(),
+            status: NostrOutboxStatus::Pending,
+            attempts: 0,
+            next_attempt_at: timestamp,
+            last_error: None,
+            created_at: timestamp,
+            sent_at: None,
+        }
+    }
+}
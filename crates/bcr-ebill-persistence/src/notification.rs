@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 
 use super::Result;
-use bcr_ebill_core::notification::{ActionType, Notification, NotificationType};
+use bcr_ebill_core::notification::{ActionType, EmailPreference, Notification, NotificationType};
 
 #[async_trait]
 pub trait NotificationStoreApi: Send + Sync {
@@ -37,6 +37,10 @@ pub trait NotificationStoreApi: Send + Sync {
         block_height: i32,
         action_type: ActionType,
     ) -> Result<bool>;
+    /// Returns the email preference of the given identity, if it ever set one
+    async fn get_email_preference(&self, node_id: &str) -> Result<Option<EmailPreference>>;
+    /// Creates or replaces the email preference of an identity
+    async fn set_email_preference(&self, preference: &EmailPreference) -> Result<()>;
 }
 
 #[derive(Default, Clone, PartialEq, Debug)]
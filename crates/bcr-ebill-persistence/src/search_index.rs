@@ -0,0 +1,36 @@
+use super::Result;
+use async_trait::async_trait;
+use bcr_ebill_core::{
+    GeneralSearchFilterItemType,
+    search::{SearchDocument, SearchPosting},
+};
+
+/// An inverted index of the terms of bills, contacts and companies for the full-text search
+#[async_trait]
+pub trait SearchIndexStoreApi: Send + Sync {
+    /// Replaces the indexed terms of the given document
+    async fn index_document(&self, document: &SearchDocument) -> Result<()>;
+
+    /// Removes all indexed terms of the given document
+    async fn remove_document(
+        &self,
+        item_type: GeneralSearchFilterItemType,
+        item_id: &str,
+    ) -> Result<()>;
+
+    /// Gets all indexed terms of the given item types, which start with one of the given tokens
+    async fn search(
+        &self,
+        tokens: &[String],
+        item_types: &[GeneralSearchFilterItemType],
+    ) -> Result<Vec<SearchPosting>>;
+
+    /// Removes all indexed terms and resets the index version
+    async fn clear(&self) -> Result<()>;
+
+    /// Returns the version the index was built with, 0 if it was never built
+    async fn get_index_version(&self) -> Result<u32>;
+
+    /// Sets the version the index was built with
+    async fn set_index_version(&self, version: u32) -> Result<()>;
+}
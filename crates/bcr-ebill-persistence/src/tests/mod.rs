@@ -49,7 +49,7 @@ pub mod tests {
             name: "".to_string(),
             postal_address: empty_address(),
             email: None,
-            nostr_relay: None,
+            nostr_relays: vec![],
         }
     }
 
@@ -60,7 +60,7 @@ pub mod tests {
             name: "".to_string(),
             postal_address: empty_address(),
             email: None,
-            nostr_relay: None,
+            nostr_relays: vec![],
         }
     }
 
@@ -0,0 +1,35 @@
+use super::Result;
+use async_trait::async_trait;
+use bcr_ebill_core::webhook::{Webhook, WebhookDelivery};
+
+/// Stores webhook subscriptions and the log of their deliveries
+#[async_trait]
+pub trait WebhookStoreApi: Send + Sync {
+    /// Stores a new webhook
+    async fn insert(&self, webhook: &Webhook) -> Result<()>;
+
+    /// Returns the webhook with the given id, if there is one
+    async fn get(&self, id: &str) -> Result<Option<Webhook>>;
+
+    /// Returns all webhooks ordered by their creation time
+    async fn list(&self) -> Result<Vec<Webhook>>;
+
+    /// Removes the webhook with the given id together with its deliveries
+    async fn remove(&self, id: &str) -> Result<()>;
+
+    /// Stores a new delivery
+    async fn insert_delivery(&self, delivery: &WebhookDelivery) -> Result<()>;
+
+    /// Updates the status and attempts of the given delivery
+    async fn update_delivery(&self, delivery: &WebhookDelivery) -> Result<()>;
+
+    /// Returns the pending deliveries, that are due to be attempted at the given timestamp,
+    /// oldest first
+    async fn get_due_deliveries(&self, timestamp: u64, limit: u64) -> Result<Vec<WebhookDelivery>>;
+
+    /// Returns the latest deliveries of the given webhook, newest first
+    async fn list_deliveries(&self, webhook_id: &str, limit: u64) -> Result<Vec<WebhookDelivery>>;
+
+    /// Returns the deliveries of all webhooks, oldest first
+    async fn get_all_deliveries(&self) -> Result<Vec<WebhookDelivery>>;
+}
@@ -0,0 +1,129 @@
+use bcr_ebill_core::blockchain::bill::bundle::{
+    BillChainBundle, BillChainDisclosure, BillChainVerification,
+};
+use bcr_ebill_core::util::date::DEFAULT_DATE_TIME_FORMAT;
+use chrono::{TimeZone, Utc};
+use clap::Parser;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Verifies an exported bill chain bundle offline, re-validating the chain, every block's hash
+/// and signature and the hashes of the bundled files, and prints the decoded history
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// The path of the bundle to verify
+    bundle: PathBuf,
+    /// Print the verification result as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+/// Exits with 0 if the bundle is valid, 1 if it's invalid and 2 if it couldn't be read
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match verify(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Could not verify {}: {e}", args.bundle.display());
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn verify(args: &Args) -> Result<bool, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(&args.bundle)?;
+    let bundle: BillChainBundle = serde_json::from_slice(&bytes)?;
+    let verification = bundle.verify();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&verification)?);
+    } else {
+        print!("{}", format_verification(&bundle, &verification)?);
+    }
+    Ok(verification.is_valid())
+}
+
+fn format_verification(
+    bundle: &BillChainBundle,
+    verification: &BillChainVerification,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut out = String::new();
+    writeln!(out, "Bill {}", verification.bill_id)?;
+    writeln!(
+        out,
+        "Exported at {} (bundle version {})",
+        format_timestamp(bundle.exported_at),
+        bundle.version
+    )?;
+    let disclosure = match bundle.disclosure {
+        BillChainDisclosure::Full { .. } => "all blocks".to_string(),
+        BillChainDisclosure::Selective { ref block_keys } => format!(
+            "{} of {} blocks",
+            block_keys.len(),
+            verification.blocks.len()
+        ),
+    };
+    writeln!(out, "Disclosed: {disclosure}")?;
+    writeln!(out, "Chain: {}", format_check(verification.chain_valid))?;
+    if let Some(keys_valid) = verification.keys_valid {
+        writeln!(out, "Bill keys: {}", format_check(keys_valid))?;
+    }
+
+    writeln!(out, "\nHistory:")?;
+    for block in verification.blocks.iter() {
+        writeln!(
+            out,
+            "#{} {:?} at {} - hash {}, signature {}",
+            block.id,
+            block.op_code,
+            format_timestamp(block.timestamp),
+            format_check(block.hash_valid),
+            format_check(block.signature_valid)
+        )?;
+        match (&block.data, &block.error) {
+            (Some(data), _) => {
+                for line in serde_json::to_string_pretty(data)?.lines() {
+                    writeln!(out, "    {line}")?;
+                }
+            }
+            (None, Some(error)) => writeln!(out, "    could not decrypt: {error}")?,
+            (None, None) => writeln!(out, "    not disclosed")?,
+        }
+    }
+
+    if !verification.files.is_empty() {
+        writeln!(out, "\nFiles:")?;
+        for file in verification.files.iter() {
+            let status = match (file.included, file.referenced) {
+                (false, _) => "not included".to_string(),
+                (true, false) => "not referenced in the bill".to_string(),
+                (true, true) => format!("hash {}", format_check(file.hash_valid)),
+            };
+            writeln!(out, "{} ({}) - {status}", file.name, file.hash)?;
+        }
+    }
+
+    writeln!(
+        out,
+        "\nResult: {}",
+        if verification.is_valid() {
+            "VALID"
+        } else {
+            "INVALID"
+        }
+    )?;
+    Ok(out)
+}
+
+fn format_check(valid: bool) -> &'static str {
+    if valid { "valid" } else { "INVALID" }
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    match Utc.timestamp_opt(timestamp as i64, 0).single() {
+        Some(date_time) => format!("{} UTC", date_time.format(DEFAULT_DATE_TIME_FORMAT)),
+        None => timestamp.to_string(),
+    }
+}
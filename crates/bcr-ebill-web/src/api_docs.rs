@@ -9,18 +9,44 @@ use utoipa::OpenApi;
         version = "1.0.0",
     ),
     paths(
+        handlers::auth::status,
+        handlers::auth::login,
+        handlers::auth::reauthenticate,
+        handlers::auth::logout,
+        handlers::auth::list_tokens,
+        handlers::auth::create_token,
+        handlers::auth::remove_token,
+        handlers::webhooks::list,
+        handlers::webhooks::create,
+        handlers::webhooks::remove,
+        handlers::webhooks::deliveries,
         handlers::notifications::list_notifications,
         handlers::notifications::mark_notification_done,
+        handlers::notifications::get_email_preference,
+        handlers::notifications::set_email_preference,
+        handlers::notifications::list_relays,
+        handlers::notifications::list_quarantined_events,
+        handlers::notifications::release_quarantined_event,
+        handlers::notifications::discard_quarantined_event,
+        handlers::notifications::list_outbox,
         handlers::notifications::websocket,
         handlers::notifications::sse,
         handlers::bill::list,
         handlers::bill::all_bills_from_all_identities,
         handlers::bill::list_light,
         handlers::bill::search,
+        handlers::bill::export,
+        handlers::bill::upload_batch_file,
+        handlers::bill::issue_batch,
+        handlers::bill::bill_pdf,
+        handlers::bill::chain_bundle,
+        handlers::bill::verify_chain_bundle,
         handlers::bill::bill_detail,
         handlers::bill::get_past_endorsees_for_bill,
         handlers::bill::get_endorsements_for_bill,
         handlers::identity::return_identity,
+        handlers::identity::contact_card,
+        handlers::identity::contact_card_qr_code,
         handlers::identity::create_identity,
         handlers::identity::change_identity,
         handlers::identity::active,
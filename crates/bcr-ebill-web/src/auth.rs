@@ -0,0 +1,348 @@
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use bcr_ebill_api::util::get_uuid_v4;
+use log::warn;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// The name of the cookie holding the session token
+pub const SESSION_COOKIE: &str = "bcr_ebill_session";
+
+/// How many wrong passwords a client can enter, before it's locked out
+const MAX_FAILED_LOGINS: u32 = 5;
+/// How long a client is locked out after too many wrong passwords, it's doubled for every
+/// further wrong password
+const LOGIN_LOCKOUT_BASE_SECONDS: u64 = 30;
+/// The maximum time a client is locked out for
+const LOGIN_LOCKOUT_MAX_SECONDS: u64 = 3600;
+
+/// A logged in session, timestamps are in seconds
+#[derive(Debug, Clone)]
+struct Session {
+    /// When the session was last used, it expires after the session timeout
+    last_seen: u64,
+    /// When the password was last entered for the session
+    authenticated_at: u64,
+}
+
+/// The wrong passwords entered by a client
+#[derive(Debug, Clone, Default)]
+struct FailedLogins {
+    count: u32,
+    locked_until: u64,
+}
+
+/// The state of an authenticated session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// The session is valid
+    Authenticated,
+    /// The session is valid and the password was entered within the re-authentication timeout,
+    /// which is required for sensitive endpoints
+    Reauthenticated,
+}
+
+/// The result of checking a password entered by a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCheck {
+    Valid,
+    Invalid,
+    /// The client entered too many wrong passwords, the password wasn't checked
+    LockedOut {
+        retry_after_seconds: u64,
+    },
+}
+
+/// Authentication for the HTTP API using a local password and in-memory sessions, which are
+/// lost on restart. If no password is configured, authentication is disabled
+pub struct AuthContext {
+    /// The salted Argon2 hash of the password in PHC string format
+    password_hash: Option<String>,
+    session_timeout_seconds: u64,
+    reauthentication_timeout_seconds: u64,
+    sessions: Mutex<HashMap<String, Session>>,
+    /// The wrong passwords entered by clients, by their IP address
+    failed_logins: Mutex<HashMap<String, FailedLogins>>,
+}
+
+impl AuthContext {
+    pub fn new(
+        password: Option<&str>,
+        session_timeout_seconds: u64,
+        reauthentication_timeout_seconds: u64,
+    ) -> Self {
+        Self {
+            password_hash: password.map(hash_password),
+            session_timeout_seconds,
+            reauthentication_timeout_seconds,
+            sessions: Mutex::new(HashMap::new()),
+            failed_logins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.password_hash.is_some()
+    }
+
+    pub fn session_timeout_seconds(&self) -> u64 {
+        self.session_timeout_seconds
+    }
+
+    /// Checks the password entered by the given client against the salted hash, which is
+    /// compared in constant time. After too many wrong passwords, the client is locked out for
+    /// an increasing time and its passwords aren't checked anymore, until the lockout is over
+    pub fn check_password(&self, client: &str, password: &str, now: u64) -> PasswordCheck {
+        let hash = match self.password_hash {
+            Some(ref hash) => hash,
+            None => return PasswordCheck::Invalid,
+        };
+        let locked_until = lock(&self.failed_logins)
+            .get(client)
+            .map(|failed| failed.locked_until)
+            .unwrap_or(0);
+        if locked_until > now {
+            return PasswordCheck::LockedOut {
+                retry_after_seconds: locked_until - now,
+            };
+        }
+
+        let valid = PasswordHash::new(hash)
+            .map(|parsed| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &parsed)
+                    .is_ok()
+            })
+            .unwrap_or(false);
+
+        let mut failed_logins = lock(&self.failed_logins);
+        if valid {
+            failed_logins.remove(client);
+            return PasswordCheck::Valid;
+        }
+        let failed = failed_logins.entry(client.to_owned()).or_default();
+        failed.count += 1;
+        if failed.count >= MAX_FAILED_LOGINS {
+            let exponent = (failed.count - MAX_FAILED_LOGINS).min(16);
+            failed.locked_until = now
+                + LOGIN_LOCKOUT_BASE_SECONDS
+                    .saturating_mul(2u64.pow(exponent))
+                    .min(LOGIN_LOCKOUT_MAX_SECONDS);
+            warn!(
+                "Client {client} entered {} wrong passwords - locked out until {}",
+                failed.count, failed.locked_until
+            );
+        }
+        PasswordCheck::Invalid
+    }
+
+    /// Creates a new session and returns its token
+    pub fn create_session(&self, now: u64) -> String {
+        let token = format!("{}{}", get_uuid_v4().simple(), get_uuid_v4().simple());
+        let mut sessions = lock(&self.sessions);
+        self.remove_expired(&mut sessions, now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                last_seen: now,
+                authenticated_at: now,
+            },
+        );
+        token
+    }
+
+    /// Checks the session for the given token and extends it, if it's valid
+    pub fn validate_session(&self, token: &str, now: u64) -> Option<SessionState> {
+        let mut sessions = lock(&self.sessions);
+        self.remove_expired(&mut sessions, now);
+        let session = sessions.get_mut(token)?;
+        session.last_seen = now;
+        if now.saturating_sub(session.authenticated_at) <= self.reauthentication_timeout_seconds {
+            Some(SessionState::Reauthenticated)
+        } else {
+            Some(SessionState::Authenticated)
+        }
+    }
+
+    /// Marks the password as entered for the session with the given token, returns false, if
+    /// there is no valid session for it
+    pub fn reauthenticate(&self, token: &str, now: u64) -> bool {
+        let mut sessions = lock(&self.sessions);
+        self.remove_expired(&mut sessions, now);
+        match sessions.get_mut(token) {
+            Some(session) => {
+                session.last_seen = now;
+                session.authenticated_at = now;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_session(&self, token: &str) {
+        lock(&self.sessions).remove(token);
+    }
+
+    fn remove_expired(&self, sessions: &mut HashMap<String, Session>, now: u64) {
+        sessions.retain(|_, session| {
+            now.saturating_sub(session.last_seen) <= self.session_timeout_seconds
+        });
+    }
+}
+
+fn hash_password(password: &str) -> String {
+    // the random part of a v4 UUID is enough for a salt
+    let salt =
+        SaltString::encode_b64(get_uuid_v4().as_bytes()).expect("a UUID has a valid salt length");
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("hashing a password with the default parameters can't fail")
+        .to_string()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // the sessions and failed logins can't be left in an inconsistent state, so we can recover
+    // from poisoning
+    mutex.lock().unwrap_or_else(|e| {
+        warn!("Authentication lock was poisoned - recovering");
+        e.into_inner()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: &str = "127.0.0.1";
+
+    fn get_auth() -> AuthContext {
+        AuthContext::new(Some("secret"), 100, 10)
+    }
+
+    #[test]
+    fn disabled_without_password() {
+        let auth = AuthContext::new(None, 100, 10);
+        assert!(!auth.is_enabled());
+        assert_eq!(
+            auth.check_password(CLIENT, "", 1000),
+            PasswordCheck::Invalid
+        );
+    }
+
+    #[test]
+    fn password_is_stored_salted() {
+        let first = get_auth();
+        let second = get_auth();
+        assert!(first.password_hash.as_ref().unwrap().starts_with("$argon2"));
+        assert_ne!(first.password_hash, second.password_hash);
+        assert_eq!(
+            first.check_password(CLIENT, "secret", 1000),
+            PasswordCheck::Valid
+        );
+        assert_eq!(
+            first.check_password(CLIENT, "Secret", 1000),
+            PasswordCheck::Invalid
+        );
+    }
+
+    #[test]
+    fn session_create_touch_and_expire() {
+        let auth = get_auth();
+        let token = auth.create_session(1000);
+        assert_eq!(
+            auth.validate_session(&token, 1005),
+            Some(SessionState::Reauthenticated)
+        );
+        // using the session extends it
+        assert_eq!(
+            auth.validate_session(&token, 1100),
+            Some(SessionState::Authenticated)
+        );
+        assert_eq!(
+            auth.validate_session(&token, 1200),
+            Some(SessionState::Authenticated)
+        );
+        assert_eq!(auth.validate_session(&token, 1301), None);
+        assert_eq!(auth.validate_session("unknown", 1000), None);
+    }
+
+    #[test]
+    fn session_removed() {
+        let auth = get_auth();
+        let token = auth.create_session(1000);
+        auth.remove_session(&token);
+        assert_eq!(auth.validate_session(&token, 1000), None);
+        assert!(!auth.reauthenticate(&token, 1000));
+    }
+
+    #[test]
+    fn reauthentication_window() {
+        let auth = get_auth();
+        let token = auth.create_session(1000);
+        assert_eq!(
+            auth.validate_session(&token, 1010),
+            Some(SessionState::Reauthenticated)
+        );
+        assert_eq!(
+            auth.validate_session(&token, 1011),
+            Some(SessionState::Authenticated)
+        );
+        assert!(auth.reauthenticate(&token, 1050));
+        assert_eq!(
+            auth.validate_session(&token, 1060),
+            Some(SessionState::Reauthenticated)
+        );
+        assert_eq!(
+            auth.validate_session(&token, 1061),
+            Some(SessionState::Authenticated)
+        );
+    }
+
+    #[test]
+    fn locks_out_after_too_many_wrong_passwords() {
+        let auth = get_auth();
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert_eq!(
+                auth.check_password(CLIENT, "wrong", 1000),
+                PasswordCheck::Invalid
+            );
+        }
+        // even the right password isn't checked during the lockout
+        assert_eq!(
+            auth.check_password(CLIENT, "secret", 1010),
+            PasswordCheck::LockedOut {
+                retry_after_seconds: LOGIN_LOCKOUT_BASE_SECONDS - 10
+            }
+        );
+        // other clients are not affected
+        assert_eq!(
+            auth.check_password("10.0.0.1", "secret", 1010),
+            PasswordCheck::Valid
+        );
+        // the lockout doubles with every further wrong password
+        let after_lockout = 1000 + LOGIN_LOCKOUT_BASE_SECONDS;
+        assert_eq!(
+            auth.check_password(CLIENT, "wrong", after_lockout),
+            PasswordCheck::Invalid
+        );
+        assert_eq!(
+            auth.check_password(CLIENT, "secret", after_lockout),
+            PasswordCheck::LockedOut {
+                retry_after_seconds: 2 * LOGIN_LOCKOUT_BASE_SECONDS
+            }
+        );
+        let after_second_lockout = after_lockout + 2 * LOGIN_LOCKOUT_BASE_SECONDS;
+        assert_eq!(
+            auth.check_password(CLIENT, "secret", after_second_lockout),
+            PasswordCheck::Valid
+        );
+        // the failed logins are reset after a valid password
+        assert_eq!(
+            auth.check_password(CLIENT, "wrong", after_second_lockout),
+            PasswordCheck::Invalid
+        );
+        assert_eq!(
+            auth.check_password(CLIENT, "secret", after_second_lockout),
+            PasswordCheck::Valid
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use clap::Parser;
+use anyhow::{Result, anyhow};
+use bcr_ebill_api::data::contact::ContactVerificationPolicy;
+use bcr_ebill_api::{EmailConfig, EmailTransportConfig, SendgridConfig, SmtpConfig, SmtpTls};
+use clap::{Parser, ValueEnum};
+use std::net::IpAddr;
 
 /// Configuration for the bitcredit application
 /// Allows to set the ports and addresses for the network connections
@@ -14,12 +18,19 @@ pub struct Config {
     pub data_dir: String,
     #[arg(default_value_t = String::from("ws://localhost:8800"), long, env = "SURREAL_DB_CONNECTION")]
     pub surreal_db_connection: String,
-    #[arg(default_value_t = false, long, env = "TERMINAL_CLIENT")]
-    pub terminal_client: bool,
     #[arg(default_value_t = String::from("testnet"),  long, env = "BITCOIN_NETWORK")]
     pub bitcoin_network: String,
-    #[arg(default_value_t = String::from("ws://localhost:8080"), long, env = "NOSTR_RELAY")]
-    pub nostr_relay: String,
+    /// Comma-separated Nostr relays, events are published to and received from
+    #[arg(
+        default_value = "ws://localhost:8080",
+        long,
+        env = "NOSTR_RELAY",
+        value_delimiter = ','
+    )]
+    pub nostr_relay: Vec<String>,
+    /// To how many of the relays an event has to be published
+    #[arg(default_value_t = 1, long, env = "NOSTR_WRITE_QUORUM")]
+    pub nostr_write_quorum: usize,
     #[arg(default_value_t = String::from("https://moksha.minibill.tech"), long, env = "MINT_URL")]
     pub mint_url: String,
     #[arg(default_value_t = 1, long, env = "JOB_RUNNER_INITIAL_DELAY_SECONDS")]
@@ -32,10 +43,177 @@ pub struct Config {
     pub frontend_url_path: String,
     #[arg(default_value_t = false, long, env = "LAUNCH_FRONTEND_AT_STARTUP")]
     pub launch_frontend_at_startup: bool,
+    /// The password to log in to the HTTP API with, authentication is disabled, if not set, which
+    /// is only allowed if the HTTP API listens on a loopback address
+    #[arg(long, env = "API_PASSWORD", hide_env_values = true)]
+    pub api_password: Option<String>,
+    #[arg(default_value_t = 3600, long, env = "SESSION_TIMEOUT_SECONDS")]
+    pub session_timeout_seconds: u64,
+    /// Sensitive endpoints, like the seed phrase, need the password within this time
+    #[arg(default_value_t = 300, long, env = "REAUTHENTICATION_TIMEOUT_SECONDS")]
+    pub reauthentication_timeout_seconds: u64,
+    /// Comma-separated origins, which can call the HTTP API from a browser besides the node's own
+    #[arg(long, env = "CORS_ALLOWED_ORIGINS", value_delimiter = ',')]
+    pub cors_allowed_origins: Vec<String>,
+    /// How received events are sent to the email address of the local identity and its companies,
+    /// if they opted in
+    #[arg(value_enum, default_value_t = EmailTransport::None, long, env = "EMAIL_TRANSPORT")]
+    pub email_transport: EmailTransport,
+    /// The sender address of emails
+    #[arg(long, env = "EMAIL_FROM")]
+    pub email_from: Option<String>,
+    #[arg(long, env = "SENDGRID_API_KEY", hide_env_values = true)]
+    pub sendgrid_api_key: Option<String>,
+    #[arg(default_value_t = String::from("https://api.sendgrid.com"), long, env = "SENDGRID_URL")]
+    pub sendgrid_url: String,
+    #[arg(long, env = "SMTP_HOST")]
+    pub smtp_host: Option<String>,
+    #[arg(default_value_t = 587, long, env = "SMTP_PORT")]
+    pub smtp_port: u16,
+    #[arg(long, env = "SMTP_USERNAME")]
+    pub smtp_username: Option<String>,
+    #[arg(long, env = "SMTP_PASSWORD", hide_env_values = true)]
+    pub smtp_password: Option<String>,
+    #[arg(value_enum, default_value_t = SmtpTlsMode::Starttls, long, env = "SMTP_TLS")]
+    pub smtp_tls: SmtpTlsMode,
+    /// How issuing, endorsing, selling and recoursing bills to contacts, which weren't verified,
+    /// is handled
+    #[arg(value_enum, default_value_t = ContactVerificationPolicyMode::Off, long, env = "CONTACT_VERIFICATION_POLICY")]
+    pub contact_verification_policy: ContactVerificationPolicyMode,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmailTransport {
+    /// Events are not sent via email
+    None,
+    Sendgrid,
+    Smtp,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmtpTlsMode {
+    /// Unencrypted, only meant for local SMTP catchers
+    None,
+    Starttls,
+    Tls,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactVerificationPolicyMode {
+    /// The verification status of counterparties isn't checked
+    Off,
+    /// Bills are issued, endorsed, sold and recoursed, but a warning is returned for unverified
+    /// counterparties
+    Warn,
+    /// Bills are not issued, endorsed, sold or recoursed to unverified counterparties
+    Refuse,
 }
 
 impl Config {
     pub fn http_listen_url(&self) -> String {
         format!("http://{}:{}", self.http_address, self.http_port)
     }
+
+    /// Whether the HTTP API only listens on the loopback interface
+    pub fn is_loopback_address(&self) -> bool {
+        self.http_address == "localhost"
+            || self
+                .http_address
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    }
+
+    /// Fails if no password is set, but the HTTP API is reachable from other hosts, since its
+    /// sensitive endpoints, like the seed phrase, would be open to anybody on the network
+    pub fn check_api_password(&self) -> Result<()> {
+        if self.api_password.is_none() && !self.is_loopback_address() {
+            return Err(anyhow!(
+                "API_PASSWORD has to be set, if HTTP_ADDRESS {} is not a loopback address",
+                self.http_address
+            ));
+        }
+        Ok(())
+    }
+
+    /// The origins allowed for CORS, which are the configured ones and the node's own
+    pub fn cors_origins(&self) -> Vec<String> {
+        let mut origins = vec![self.http_listen_url()];
+        if self.http_address == "127.0.0.1" {
+            origins.push(format!("http://localhost:{}", self.http_port));
+        }
+        origins.extend(
+            self.cors_allowed_origins
+                .iter()
+                .map(|o| o.trim().trim_end_matches('/').to_owned())
+                .filter(|o| !o.is_empty()),
+        );
+        origins
+    }
+
+    /// The email configuration for the API, fails if the chosen transport is missing settings
+    pub fn email_config(&self) -> Result<Option<EmailConfig>> {
+        let transport = match self.email_transport {
+            EmailTransport::None => return Ok(None),
+            EmailTransport::Sendgrid => EmailTransportConfig::Sendgrid(SendgridConfig {
+                api_key: self
+                    .sendgrid_api_key
+                    .clone()
+                    .ok_or_else(|| anyhow!("SENDGRID_API_KEY is required for sendgrid"))?,
+                url: self.sendgrid_url.trim_end_matches('/').to_owned(),
+            }),
+            EmailTransport::Smtp => EmailTransportConfig::Smtp(SmtpConfig {
+                host: self
+                    .smtp_host
+                    .clone()
+                    .ok_or_else(|| anyhow!("SMTP_HOST is required for smtp"))?,
+                port: self.smtp_port,
+                username: self.smtp_username.clone(),
+                password: self.smtp_password.clone(),
+                tls: match self.smtp_tls {
+                    SmtpTlsMode::None => SmtpTls::None,
+                    SmtpTlsMode::Starttls => SmtpTls::StartTls,
+                    SmtpTlsMode::Tls => SmtpTls::Tls,
+                },
+            }),
+        };
+        let from = self
+            .email_from
+            .clone()
+            .ok_or_else(|| anyhow!("EMAIL_FROM is required to send emails"))?;
+        Ok(Some(EmailConfig { from, transport }))
+    }
+
+    /// The contact verification policy for the API
+    pub fn contact_verification_policy(&self) -> ContactVerificationPolicy {
+        match self.contact_verification_policy {
+            ContactVerificationPolicyMode::Off => ContactVerificationPolicy::Off,
+            ContactVerificationPolicyMode::Warn => ContactVerificationPolicy::Warn,
+            ContactVerificationPolicyMode::Refuse => ContactVerificationPolicy::Refuse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(http_address: &str, api_password: Option<&str>) -> Config {
+        let mut conf = Config::parse_from(["bitcredit", "--http-address", http_address]);
+        conf.api_password = api_password.map(String::from);
+        conf
+    }
+
+    #[test]
+    fn api_password_required_for_non_loopback_address() {
+        assert!(config("127.0.0.1", None).check_api_password().is_ok());
+        assert!(config("::1", None).check_api_password().is_ok());
+        assert!(config("localhost", None).check_api_password().is_ok());
+        assert!(config("0.0.0.0", None).check_api_password().is_err());
+        assert!(config("192.168.1.10", None).check_api_password().is_err());
+        assert!(
+            config("0.0.0.0", Some("secret"))
+                .check_api_password()
+                .is_ok()
+        );
+    }
 }
@@ -1,22 +1,33 @@
 use async_trait::async_trait;
+use bcr_ebill_api::blockchain::bill::bundle::{
+    BillBlockVerification, BillChainVerification, BillFileVerification,
+};
 use bcr_ebill_api::data::{
     File, GeneralSearchFilterItemType, GeneralSearchResult, OptionalPostalAddress, PostalAddress,
     UploadFilesResult,
+    api_token::ApiToken,
     bill::{
-        BillCombinedBitcoinKey, BillsFilterRole, BitcreditBillResult, Endorsement,
-        LightBitcreditBillResult, LightSignedBy, PastEndorsee,
+        BillBatchResult, BillBatchRowResult, BillCombinedBitcoinKey, BillExportFormat,
+        BillSortField, BillStatusFilter, BillsFilterRole, BitcreditBillResult, Endorsement,
+        LightBitcreditBillResult, LightSignedBy, PastEndorsee, SortDirection,
     },
+    bill_template::{BillTemplate, BillTemplateInterval, BillTemplateIssue, BillTemplateSchedule},
     company::Company,
     contact::{
-        Contact, ContactType, IdentityPublicData, LightIdentityPublicData,
-        LightIdentityPublicDataWithAddress,
+        Contact, ContactImportResult, ContactTrustLevel, ContactType, ContactVerificationStatus,
+        IdentityPublicData, LightIdentityPublicData, LightIdentityPublicDataWithAddress,
+        RejectedContactImport,
     },
     identity::{Identity, IdentityType},
-    notification::{Notification, NotificationType},
+    notification::{EmailPreference, EventType, Notification, NotificationType},
+    search::{SearchField, SearchHit},
+    webhook::{Webhook, WebhookDelivery, WebhookDeliveryStatus},
 };
+use bcr_ebill_api::service::notification_service::RelayStats;
 use bcr_ebill_api::service::{Error, Result};
 use bcr_ebill_api::util::file::{UploadFileHandler, detect_content_type_for_bytes};
-use bcr_ebill_api::util::{BcrKeys, date::DateTimeUtc};
+use bcr_ebill_api::util::{BcrKeys, currency, date::DateTimeUtc};
+use bcr_ebill_api::{NostrOutboxEntry, NostrOutboxStatus, QuarantinedEvent};
 use rocket::FromForm;
 use rocket::fs::TempFile;
 use serde::{Deserialize, Serialize};
@@ -50,6 +61,23 @@ impl SuccessResponse {
     }
 }
 
+/// Signals success of a request, together with warnings the client should show, e.g. about
+/// counterparties, which aren't verified contacts
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SuccessWithWarningsResponse {
+    pub success: bool,
+    pub warnings: Vec<String>,
+}
+
+impl SuccessWithWarningsResponse {
+    pub fn new(warnings: Vec<String>) -> Self {
+        Self {
+            success: true,
+            warnings,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct EndorsementsResponse {
     pub endorsements: Vec<EndorsementWeb>,
@@ -65,6 +93,8 @@ pub struct GeneralSearchResponse {
     pub bills: Vec<LightBitcreditBillWeb>,
     pub contacts: Vec<ContactWeb>,
     pub companies: Vec<CompanyWeb>,
+    /// All matches, ordered by relevance
+    pub matches: Vec<SearchMatchWeb>,
 }
 
 impl IntoWeb<GeneralSearchResponse> for GeneralSearchResult {
@@ -73,6 +103,72 @@ impl IntoWeb<GeneralSearchResponse> for GeneralSearchResult {
             bills: self.bills.into_iter().map(|b| b.into_web()).collect(),
             contacts: self.contacts.into_iter().map(|c| c.into_web()).collect(),
             companies: self.companies.into_iter().map(|c| c.into_web()).collect(),
+            matches: self.hits.into_iter().map(|h| h.into_web()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchMatchWeb {
+    pub item_type: GeneralSearchFilterItemTypeWeb,
+    pub id: String,
+    pub score: u32,
+    /// The fields the search term matched on
+    pub matched_fields: Vec<SearchFieldWeb>,
+}
+
+impl IntoWeb<SearchMatchWeb> for SearchHit {
+    fn into_web(self) -> SearchMatchWeb {
+        SearchMatchWeb {
+            item_type: self.item_type.into_web(),
+            id: self.item_id,
+            score: self.score,
+            matched_fields: self
+                .matched_fields
+                .into_iter()
+                .map(|f| f.into_web())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub enum SearchFieldWeb {
+    Id,
+    Name,
+    Email,
+    Address,
+    City,
+    Zip,
+    Country,
+    RegistrationNumber,
+    IdentificationNumber,
+    Drawee,
+    Drawer,
+    Payee,
+    Endorsee,
+    Participant,
+    FileName,
+}
+
+impl IntoWeb<SearchFieldWeb> for SearchField {
+    fn into_web(self) -> SearchFieldWeb {
+        match self {
+            SearchField::Id => SearchFieldWeb::Id,
+            SearchField::Name => SearchFieldWeb::Name,
+            SearchField::Email => SearchFieldWeb::Email,
+            SearchField::Address => SearchFieldWeb::Address,
+            SearchField::City => SearchFieldWeb::City,
+            SearchField::Zip => SearchFieldWeb::Zip,
+            SearchField::Country => SearchFieldWeb::Country,
+            SearchField::RegistrationNumber => SearchFieldWeb::RegistrationNumber,
+            SearchField::IdentificationNumber => SearchFieldWeb::IdentificationNumber,
+            SearchField::Drawee => SearchFieldWeb::Drawee,
+            SearchField::Drawer => SearchFieldWeb::Drawer,
+            SearchField::Payee => SearchFieldWeb::Payee,
+            SearchField::Endorsee => SearchFieldWeb::Endorsee,
+            SearchField::Participant => SearchFieldWeb::Participant,
+            SearchField::FileName => SearchFieldWeb::FileName,
         }
     }
 }
@@ -82,11 +178,56 @@ pub struct BillsResponse<T: Serialize> {
     pub bills: Vec<T>,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillsSearchResponse {
+    pub bills: Vec<LightBitcreditBillWeb>,
+    /// The cursor to fetch the next page with, if there are more bills
+    pub next_cursor: Option<String>,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ContactsResponse<T: Serialize> {
     pub contacts: Vec<T>,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ContactImportResponse {
+    /// The node ids of the imported contacts
+    pub imported: Vec<String>,
+    /// The node ids, which were skipped, since they're already a contact, or occur more than once
+    pub duplicates: Vec<String>,
+    /// The entries, which couldn't be imported
+    pub rejected: Vec<RejectedContactImportWeb>,
+}
+
+impl IntoWeb<ContactImportResponse> for ContactImportResult {
+    fn into_web(self) -> ContactImportResponse {
+        ContactImportResponse {
+            imported: self.imported,
+            duplicates: self.duplicates,
+            rejected: self.rejected.into_iter().map(|r| r.into_web()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RejectedContactImportWeb {
+    /// The position of the entry in the import, starting at 1
+    pub position: usize,
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+impl IntoWeb<RejectedContactImportWeb> for RejectedContactImport {
+    fn into_web(self) -> RejectedContactImportWeb {
+        RejectedContactImportWeb {
+            position: self.position,
+            name: self.name,
+            reason: self.reason,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct CompaniesResponse<T: Serialize> {
     pub companies: Vec<T>,
@@ -114,6 +255,16 @@ impl FromWeb<GeneralSearchFilterItemTypeWeb> for GeneralSearchFilterItemType {
     }
 }
 
+impl IntoWeb<GeneralSearchFilterItemTypeWeb> for GeneralSearchFilterItemType {
+    fn into_web(self) -> GeneralSearchFilterItemTypeWeb {
+        match self {
+            GeneralSearchFilterItemType::Company => GeneralSearchFilterItemTypeWeb::Company,
+            GeneralSearchFilterItemType::Bill => GeneralSearchFilterItemTypeWeb::Bill,
+            GeneralSearchFilterItemType::Contact => GeneralSearchFilterItemTypeWeb::Contact,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct GeneralSearchFilter {
     pub search_term: String,
@@ -124,14 +275,196 @@ pub struct GeneralSearchFilter {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BillsSearchFilterPayload {
     pub filter: BillsSearchFilter,
+    pub sort: Option<BillsSortWeb>,
+    /// The `next_cursor` of the previous page, to fetch the next one - only valid with the same
+    /// sort order
+    pub cursor: Option<String>,
+    /// The maximum number of bills to return
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BillsExportPayload {
+    /// If not set, all bills are exported
+    pub filter: Option<BillsSearchFilter>,
+    pub sort: Option<BillsSortWeb>,
+    pub format: BillExportFormatWeb,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub enum BillExportFormatWeb {
+    Csv,
+    JsonLines,
+    Xlsx,
+}
+
+impl FromWeb<BillExportFormatWeb> for BillExportFormat {
+    fn from_web(value: BillExportFormatWeb) -> Self {
+        match value {
+            BillExportFormatWeb::Csv => BillExportFormat::Csv,
+            BillExportFormatWeb::JsonLines => BillExportFormat::JsonLines,
+            BillExportFormatWeb::Xlsx => BillExportFormat::Xlsx,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BillChainBundlePayload {
+    /// If set, only these blocks are disclosed, otherwise the bill keys are part of the bundle
+    pub disclosed_block_ids: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BillChainVerificationWeb {
+    pub bill_id: String,
+    /// If the chain, the disclosed keys, every block and every file are valid
+    pub valid: bool,
+    pub chain_valid: bool,
+    /// Only set, if the bill keys are disclosed
+    pub keys_valid: Option<bool>,
+    pub blocks: Vec<BillBlockVerificationWeb>,
+    pub files: Vec<BillFileVerificationWeb>,
+}
+
+impl IntoWeb<BillChainVerificationWeb> for BillChainVerification {
+    fn into_web(self) -> BillChainVerificationWeb {
+        BillChainVerificationWeb {
+            valid: self.is_valid(),
+            bill_id: self.bill_id,
+            chain_valid: self.chain_valid,
+            keys_valid: self.keys_valid,
+            blocks: self.blocks.into_iter().map(|b| b.into_web()).collect(),
+            files: self.files.into_iter().map(|f| f.into_web()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BillBlockVerificationWeb {
+    pub id: u64,
+    pub op_code: String,
+    pub timestamp: u64,
+    pub hash_valid: bool,
+    pub signature_valid: bool,
+    /// The decrypted block data, if the block is disclosed
+    pub data: Option<Value>,
+    pub error: Option<String>,
+}
+
+impl IntoWeb<BillBlockVerificationWeb> for BillBlockVerification {
+    fn into_web(self) -> BillBlockVerificationWeb {
+        BillBlockVerificationWeb {
+            id: self.id,
+            op_code: format!("{:?}", self.op_code),
+            timestamp: self.timestamp,
+            hash_valid: self.hash_valid,
+            signature_valid: self.signature_valid,
+            data: self.data,
+            error: self.error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BillFileVerificationWeb {
+    pub name: String,
+    pub hash: String,
+    pub referenced: bool,
+    pub included: bool,
+    pub hash_valid: bool,
+}
+
+impl IntoWeb<BillFileVerificationWeb> for BillFileVerification {
+    fn into_web(self) -> BillFileVerificationWeb {
+        BillFileVerificationWeb {
+            name: self.name,
+            hash: self.hash,
+            referenced: self.referenced,
+            included: self.included,
+            hash_valid: self.hash_valid,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BillsSearchFilter {
     pub search_term: Option<String>,
+    /// Range of the issue date
     pub date_range: Option<DateRange>,
+    pub maturity_date_range: Option<DateRange>,
+    pub sum_range: Option<SumRange>,
     pub role: BillsFilterRoleWeb,
-    pub currency: String,
+    pub currency: Option<String>,
+    pub status: Option<BillsStatusFilterWeb>,
+    /// Only bills the given node id is, or was a party of
+    pub counterparty_node_id: Option<String>,
+    pub has_attachments: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SumRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Each flag is ignored if not set, otherwise only bills with, or without the status match
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BillsStatusFilterWeb {
+    pub accepted: Option<bool>,
+    pub paid: Option<bool>,
+    pub in_recourse: Option<bool>,
+    pub waiting_for_payment: Option<bool>,
+}
+
+impl FromWeb<BillsStatusFilterWeb> for BillStatusFilter {
+    fn from_web(value: BillsStatusFilterWeb) -> Self {
+        Self {
+            accepted: value.accepted,
+            paid: value.paid,
+            in_recourse: value.in_recourse,
+            waiting_for_payment: value.waiting_for_payment,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BillsSortWeb {
+    pub field: BillSortFieldWeb,
+    pub direction: SortDirectionWeb,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub enum BillSortFieldWeb {
+    TimeOfDrawing,
+    IssueDate,
+    MaturityDate,
+    Sum,
+}
+
+impl FromWeb<BillSortFieldWeb> for BillSortField {
+    fn from_web(value: BillSortFieldWeb) -> Self {
+        match value {
+            BillSortFieldWeb::TimeOfDrawing => BillSortField::TimeOfDrawing,
+            BillSortFieldWeb::IssueDate => BillSortField::IssueDate,
+            BillSortFieldWeb::MaturityDate => BillSortField::MaturityDate,
+            BillSortFieldWeb::Sum => BillSortField::Sum,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub enum SortDirectionWeb {
+    Ascending,
+    Descending,
+}
+
+impl FromWeb<SortDirectionWeb> for SortDirection {
+    fn from_web(value: SortDirectionWeb) -> Self {
+        match value {
+            SortDirectionWeb::Ascending => SortDirection::Ascending,
+            SortDirectionWeb::Descending => SortDirection::Descending,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -168,31 +501,6 @@ pub struct CurrencyResponse {
     pub code: String,
 }
 
-#[repr(u8)]
-#[derive(
-    Debug, Clone, serde_repr::Serialize_repr, serde_repr::Deserialize_repr, PartialEq, Eq, ToSchema,
-)]
-pub enum BillType {
-    PromissoryNote = 0, // Drawer pays to payee
-    SelfDrafted = 1,    // Drawee pays to drawer
-    ThreeParties = 2,   // Drawee pays to payee
-}
-
-impl TryFrom<u64> for BillType {
-    type Error = Error;
-
-    fn try_from(value: u64) -> std::result::Result<Self, Error> {
-        match value {
-            0 => Ok(BillType::PromissoryNote),
-            1 => Ok(BillType::SelfDrafted),
-            2 => Ok(BillType::ThreeParties),
-            _ => Err(Error::Validation(format!(
-                "Invalid bill type found: {value}"
-            ))),
-        }
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BitcreditBillPayload {
     #[serde(rename = "type")]
@@ -225,12 +533,71 @@ pub struct UploadFileForm<'r> {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BillId {
     pub id: String,
+    /// Warnings about the counterparties, e.g. if they aren't verified contacts
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BillBatchPayload {
+    /// The upload id of the CSV or JSON file with the bills to issue
+    pub file_upload_id: String,
+    /// Identifies the batch across uploads, rows already issued for it are skipped. Defaults
+    /// to the upload id
+    pub batch_id: Option<String>,
+    /// Only validates the bills, without issuing them
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Only issues the bills, if all rows are valid, instead of issuing the valid rows
+    #[serde(default)]
+    pub all_or_nothing: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillBatchResultWeb {
+    pub dry_run: bool,
+    /// Whether the bills of all rows were issued
+    pub issued: bool,
+    pub rows: Vec<BillBatchRowResultWeb>,
+}
+
+impl IntoWeb<BillBatchResultWeb> for BillBatchResult {
+    fn into_web(self) -> BillBatchResultWeb {
+        BillBatchResultWeb {
+            dry_run: self.dry_run,
+            issued: self.issued,
+            rows: self.rows.into_iter().map(|r| r.into_web()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillBatchRowResultWeb {
+    /// The number of the row, starting with 1 for the first bill
+    pub row: usize,
+    pub bill_id: Option<String>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl IntoWeb<BillBatchRowResultWeb> for BillBatchRowResult {
+    fn into_web(self) -> BillBatchRowResultWeb {
+        BillBatchRowResultWeb {
+            row: self.row,
+            bill_id: self.bill_id,
+            errors: self.errors,
+            warnings: self.warnings,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BillNumbersToWordsForSum {
     pub sum: u64,
+    /// The sum in words, in the language of the bill
     pub sum_as_words: String,
+    /// The sum followed by the declined currency in words, in the language of the bill
+    pub sum_and_currency_as_words: String,
+    pub language: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -452,6 +819,26 @@ pub struct NewContactPayload {
     pub proof_document_file_upload_id: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewContactFromProfilePayload {
+    /// The node id or the bech32 encoded npub of the identity or company
+    pub node_id: String,
+    /// Profiles don't contain an email address, so it has to be given
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContactCardWeb {
+    /// The signed contact card as `bcr:` URI, to be shared e.g. as QR code
+    pub uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewContactFromCardPayload {
+    /// The signed contact card as `bcr:` URI
+    pub uri: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct EditContactPayload {
     pub node_id: String,
@@ -467,6 +854,15 @@ pub struct EditContactPayload {
     pub proof_document_file_upload_id: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContactVerificationPayload {
+    pub node_id: String,
+    pub verification_status: ContactVerificationStatusWeb,
+    pub trust_level: ContactTrustLevelWeb,
+    /// An optional document, which shows how the contact was verified, e.g. a scan of an ID
+    pub evidence_file_upload_id: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct UploadFilesResponse {
     pub file_upload_id: String,
@@ -480,6 +876,70 @@ impl IntoWeb<UploadFilesResponse> for UploadFilesResult {
     }
 }
 
+/// Payload for logging in to the HTTP API, or re-authenticating a session
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginPayload {
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginResponse {
+    /// The session token, which is also set as cookie. It can be used as bearer token by clients
+    /// without cookies
+    pub token: String,
+    /// The session expires, if it's not used within this time
+    pub session_timeout_seconds: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuthStatusResponse {
+    /// Whether the HTTP API requires a login
+    pub enabled: bool,
+    pub authenticated: bool,
+    /// Whether the password was entered recently enough for sensitive endpoints
+    pub reauthenticated: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiTokenPayload {
+    pub name: String,
+    /// The scopes of the token, e.g. `bills:read`, `bills:write`, `contacts:write`, or `admin`
+    pub scopes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateApiTokenResponse {
+    /// The secret of the token, which has to be sent as bearer token. It's only returned once
+    pub token: String,
+    pub api_token: ApiTokenWeb,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiTokensResponse {
+    pub api_tokens: Vec<ApiTokenWeb>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiTokenWeb {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+}
+
+impl IntoWeb<ApiTokenWeb> for ApiToken {
+    fn into_web(self) -> ApiTokenWeb {
+        ApiTokenWeb {
+            id: self.id,
+            name: self.name,
+            scopes: self.scopes.iter().map(|s| s.to_string()).collect(),
+            created_at: self.created_at,
+            last_used_at: self.last_used_at,
+        }
+    }
+}
+
 /// Response for a private key seeed backup
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SeedPhrase {
@@ -745,6 +1205,11 @@ pub struct ContactWeb {
     pub avatar_file: Option<FileWeb>,
     pub proof_document_file: Option<FileWeb>,
     pub nostr_relays: Vec<String>,
+    pub verification_status: ContactVerificationStatusWeb,
+    pub trust_level: ContactTrustLevelWeb,
+    pub verification_evidence_file: Option<FileWeb>,
+    /// Whether the details are kept up to date with the published profile of the contact
+    pub follows_profile: bool,
 }
 
 impl IntoWeb<ContactWeb> for Contact {
@@ -762,6 +1227,67 @@ impl IntoWeb<ContactWeb> for Contact {
             avatar_file: self.avatar_file.map(|f| f.into_web()),
             proof_document_file: self.proof_document_file.map(|f| f.into_web()),
             nostr_relays: self.nostr_relays,
+            verification_status: self.verification_status.into_web(),
+            trust_level: self.trust_level.into_web(),
+            verification_evidence_file: self.verification_evidence_file.map(|f| f.into_web()),
+            follows_profile: self.follows_profile,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub enum ContactVerificationStatusWeb {
+    Unverified,
+    InPerson,
+    Document,
+}
+
+impl IntoWeb<ContactVerificationStatusWeb> for ContactVerificationStatus {
+    fn into_web(self) -> ContactVerificationStatusWeb {
+        match self {
+            ContactVerificationStatus::Unverified => ContactVerificationStatusWeb::Unverified,
+            ContactVerificationStatus::InPerson => ContactVerificationStatusWeb::InPerson,
+            ContactVerificationStatus::Document => ContactVerificationStatusWeb::Document,
+        }
+    }
+}
+
+impl FromWeb<ContactVerificationStatusWeb> for ContactVerificationStatus {
+    fn from_web(value: ContactVerificationStatusWeb) -> Self {
+        match value {
+            ContactVerificationStatusWeb::Unverified => ContactVerificationStatus::Unverified,
+            ContactVerificationStatusWeb::InPerson => ContactVerificationStatus::InPerson,
+            ContactVerificationStatusWeb::Document => ContactVerificationStatus::Document,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub enum ContactTrustLevelWeb {
+    Unknown,
+    Low,
+    Medium,
+    High,
+}
+
+impl IntoWeb<ContactTrustLevelWeb> for ContactTrustLevel {
+    fn into_web(self) -> ContactTrustLevelWeb {
+        match self {
+            ContactTrustLevel::Unknown => ContactTrustLevelWeb::Unknown,
+            ContactTrustLevel::Low => ContactTrustLevelWeb::Low,
+            ContactTrustLevel::Medium => ContactTrustLevelWeb::Medium,
+            ContactTrustLevel::High => ContactTrustLevelWeb::High,
+        }
+    }
+}
+
+impl FromWeb<ContactTrustLevelWeb> for ContactTrustLevel {
+    fn from_web(value: ContactTrustLevelWeb) -> Self {
+        match value {
+            ContactTrustLevelWeb::Unknown => ContactTrustLevel::Unknown,
+            ContactTrustLevelWeb::Low => ContactTrustLevel::Low,
+            ContactTrustLevelWeb::Medium => ContactTrustLevel::Medium,
+            ContactTrustLevelWeb::High => ContactTrustLevel::High,
         }
     }
 }
@@ -935,7 +1461,10 @@ pub struct IdentityPublicDataWeb {
     #[serde(flatten)]
     pub postal_address: PostalAddressWeb,
     pub email: Option<String>,
+    /// The first of `nostr_relays`, kept for clients, which don't know `nostr_relays` yet
+    #[schema(deprecated)]
     pub nostr_relay: Option<String>,
+    pub nostr_relays: Vec<String>,
 }
 
 impl IntoWeb<IdentityPublicDataWeb> for IdentityPublicData {
@@ -946,7 +1475,8 @@ impl IntoWeb<IdentityPublicDataWeb> for IdentityPublicData {
             node_id: self.node_id,
             postal_address: self.postal_address.into_web(),
             email: self.email,
-            nostr_relay: self.nostr_relay,
+            nostr_relay: self.nostr_relays.first().cloned(),
+            nostr_relays: self.nostr_relays,
         }
     }
 }
@@ -1017,6 +1547,150 @@ impl IntoWeb<NotificationWeb> for Notification {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmailPreferenceWeb {
+    pub node_id: String,
+    /// Whether events sent to the identity are also sent to its email address
+    pub enabled: bool,
+    pub updated_at: u64,
+}
+
+impl IntoWeb<EmailPreferenceWeb> for EmailPreference {
+    fn into_web(self) -> EmailPreferenceWeb {
+        EmailPreferenceWeb {
+            node_id: self.node_id,
+            enabled: self.enabled,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EmailPreferencePayload {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RelaysResponse {
+    pub relays: Vec<RelayStatsWeb>,
+}
+
+/// The health of a Nostr relay, as this node experienced it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RelayStatsWeb {
+    pub url: String,
+    pub connected: bool,
+    /// Relays are unhealthy after several failed publishes in a row
+    pub healthy: bool,
+    /// How long the latest successful publish took in milliseconds
+    pub latency_ms: Option<u64>,
+    pub last_success: Option<u64>,
+    pub last_failure: Option<u64>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+impl IntoWeb<RelayStatsWeb> for RelayStats {
+    fn into_web(self) -> RelayStatsWeb {
+        RelayStatsWeb {
+            healthy: self.is_healthy(),
+            url: self.url,
+            connected: self.connected,
+            latency_ms: self.latency_ms,
+            last_success: self.last_success,
+            last_failure: self.last_failure,
+            last_error: self.last_error,
+            consecutive_failures: self.consecutive_failures,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuarantinedEventsResponse {
+    pub events: Vec<QuarantinedEventWeb>,
+}
+
+/// A Nostr event, whose sender wasn't authorized to send it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuarantinedEventWeb {
+    pub id: String,
+    /// The hex encoded Nostr public key of the sender
+    pub sender: String,
+    pub event_type: EventTypeWeb,
+    /// The received event as JSON
+    pub payload: String,
+    /// Why the sender wasn't authorized
+    pub reason: String,
+    pub received_at: u64,
+}
+
+impl IntoWeb<QuarantinedEventWeb> for QuarantinedEvent {
+    fn into_web(self) -> QuarantinedEventWeb {
+        QuarantinedEventWeb {
+            id: self.id,
+            sender: self.sender,
+            event_type: self.event_type.into_web(),
+            payload: self.payload,
+            reason: self.reason,
+            received_at: self.received_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NostrOutboxResponse {
+    pub entries: Vec<NostrOutboxEntryWeb>,
+}
+
+/// An outgoing Nostr event, which wasn't confirmed by a relay yet, or was given up
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NostrOutboxEntryWeb {
+    pub id: String,
+    /// The node id of the recipient
+    pub recipient: String,
+    pub recipient_relays: Vec<String>,
+    pub event_type: EventTypeWeb,
+    pub status: NostrOutboxStatusWeb,
+    pub attempts: u32,
+    /// When sending is attempted next, if it's still pending
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+}
+
+impl IntoWeb<NostrOutboxEntryWeb> for NostrOutboxEntry {
+    fn into_web(self) -> NostrOutboxEntryWeb {
+        NostrOutboxEntryWeb {
+            id: self.id,
+            recipient: self.recipient,
+            recipient_relays: self.recipient_relays,
+            event_type: self.event_type.into_web(),
+            status: self.status.into_web(),
+            attempts: self.attempts,
+            next_attempt_at: self.next_attempt_at,
+            last_error: self.last_error,
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub enum NostrOutboxStatusWeb {
+    Pending,
+    Sent,
+    Failed,
+}
+
+impl IntoWeb<NostrOutboxStatusWeb> for NostrOutboxStatus {
+    fn into_web(self) -> NostrOutboxStatusWeb {
+        match self {
+            NostrOutboxStatus::Pending => NostrOutboxStatusWeb::Pending,
+            NostrOutboxStatus::Sent => NostrOutboxStatusWeb::Sent,
+            NostrOutboxStatus::Failed => NostrOutboxStatusWeb::Failed,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum NotificationTypeWeb {
     General,
@@ -1032,6 +1706,347 @@ impl IntoWeb<NotificationTypeWeb> for NotificationType {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub enum EventTypeWeb {
+    BillSigned,
+    BillAccepted,
+    BillAcceptanceRequested,
+    BillAcceptanceRejected,
+    BillAcceptanceTimeout,
+    BillAcceptanceRecourse,
+    BillPaymentRequested,
+    BillPaymentRejected,
+    BillPaymentRecourse,
+    BillRecourseRejected,
+    BillRecourseTimeout,
+    BillPaymentTimeout,
+    BillSellOffered,
+    BillBuyingRejected,
+    BillPaid,
+    BillRecoursePaid,
+    BillEndorsed,
+    BillSold,
+    BillMintingRequested,
+    BillNewQuote,
+    BillQuoteApproved,
+}
+
+impl FromWeb<EventTypeWeb> for EventType {
+    fn from_web(value: EventTypeWeb) -> Self {
+        match value {
+            EventTypeWeb::BillSigned => EventType::BillSigned,
+            EventTypeWeb::BillAccepted => EventType::BillAccepted,
+            EventTypeWeb::BillAcceptanceRequested => EventType::BillAcceptanceRequested,
+            EventTypeWeb::BillAcceptanceRejected => EventType::BillAcceptanceRejected,
+            EventTypeWeb::BillAcceptanceTimeout => EventType::BillAcceptanceTimeout,
+            EventTypeWeb::BillAcceptanceRecourse => EventType::BillAcceptanceRecourse,
+            EventTypeWeb::BillPaymentRequested => EventType::BillPaymentRequested,
+            EventTypeWeb::BillPaymentRejected => EventType::BillPaymentRejected,
+            EventTypeWeb::BillPaymentRecourse => EventType::BillPaymentRecourse,
+            EventTypeWeb::BillRecourseRejected => EventType::BillRecourseRejected,
+            EventTypeWeb::BillRecourseTimeout => EventType::BillRecourseTimeout,
+            EventTypeWeb::BillPaymentTimeout => EventType::BillPaymentTimeout,
+            EventTypeWeb::BillSellOffered => EventType::BillSellOffered,
+            EventTypeWeb::BillBuyingRejected => EventType::BillBuyingRejected,
+            EventTypeWeb::BillPaid => EventType::BillPaid,
+            EventTypeWeb::BillRecoursePaid => EventType::BillRecoursePaid,
+            EventTypeWeb::BillEndorsed => EventType::BillEndorsed,
+            EventTypeWeb::BillSold => EventType::BillSold,
+            EventTypeWeb::BillMintingRequested => EventType::BillMintingRequested,
+            EventTypeWeb::BillNewQuote => EventType::BillNewQuote,
+            EventTypeWeb::BillQuoteApproved => EventType::BillQuoteApproved,
+        }
+    }
+}
+
+impl IntoWeb<EventTypeWeb> for EventType {
+    fn into_web(self) -> EventTypeWeb {
+        match self {
+            EventType::BillSigned => EventTypeWeb::BillSigned,
+            EventType::BillAccepted => EventTypeWeb::BillAccepted,
+            EventType::BillAcceptanceRequested => EventTypeWeb::BillAcceptanceRequested,
+            EventType::BillAcceptanceRejected => EventTypeWeb::BillAcceptanceRejected,
+            EventType::BillAcceptanceTimeout => EventTypeWeb::BillAcceptanceTimeout,
+            EventType::BillAcceptanceRecourse => EventTypeWeb::BillAcceptanceRecourse,
+            EventType::BillPaymentRequested => EventTypeWeb::BillPaymentRequested,
+            EventType::BillPaymentRejected => EventTypeWeb::BillPaymentRejected,
+            EventType::BillPaymentRecourse => EventTypeWeb::BillPaymentRecourse,
+            EventType::BillRecourseRejected => EventTypeWeb::BillRecourseRejected,
+            EventType::BillRecourseTimeout => EventTypeWeb::BillRecourseTimeout,
+            EventType::BillPaymentTimeout => EventTypeWeb::BillPaymentTimeout,
+            EventType::BillSellOffered => EventTypeWeb::BillSellOffered,
+            EventType::BillBuyingRejected => EventTypeWeb::BillBuyingRejected,
+            EventType::BillPaid => EventTypeWeb::BillPaid,
+            EventType::BillRecoursePaid => EventTypeWeb::BillRecoursePaid,
+            EventType::BillEndorsed => EventTypeWeb::BillEndorsed,
+            EventType::BillSold => EventTypeWeb::BillSold,
+            EventType::BillMintingRequested => EventTypeWeb::BillMintingRequested,
+            EventType::BillNewQuote => EventTypeWeb::BillNewQuote,
+            EventType::BillQuoteApproved => EventTypeWeb::BillQuoteApproved,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WebhookPayload {
+    /// The http(s) URL the events are POSTed to
+    pub url: String,
+    /// The secret the `X-Bcr-Signature` of every delivery is created with
+    pub secret: String,
+    pub event_types: Vec<EventTypeWeb>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhooksResponse {
+    pub webhooks: Vec<WebhookWeb>,
+}
+
+/// A webhook subscription, its secret is never returned
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookWeb {
+    pub id: String,
+    pub url: String,
+    pub event_types: Vec<EventTypeWeb>,
+    pub created_at: u64,
+}
+
+impl IntoWeb<WebhookWeb> for Webhook {
+    fn into_web(self) -> WebhookWeb {
+        WebhookWeb {
+            id: self.id,
+            url: self.url,
+            event_types: self.event_types.into_iter().map(|e| e.into_web()).collect(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveriesResponse {
+    pub deliveries: Vec<WebhookDeliveryWeb>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveryWeb {
+    pub id: String,
+    pub event_type: EventTypeWeb,
+    /// The JSON body, that is sent
+    pub payload: String,
+    pub status: WebhookDeliveryStatusWeb,
+    pub attempts: u32,
+    /// When the delivery is attempted next, if it's still pending
+    pub next_attempt_at: u64,
+    pub last_response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub delivered_at: Option<u64>,
+}
+
+impl IntoWeb<WebhookDeliveryWeb> for WebhookDelivery {
+    fn into_web(self) -> WebhookDeliveryWeb {
+        WebhookDeliveryWeb {
+            id: self.id,
+            event_type: self.event_type.into_web(),
+            payload: self.payload,
+            status: self.status.into_web(),
+            attempts: self.attempts,
+            next_attempt_at: self.next_attempt_at,
+            last_response_status: self.last_response_status,
+            last_error: self.last_error,
+            created_at: self.created_at,
+            delivered_at: self.delivered_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub enum WebhookDeliveryStatusWeb {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl IntoWeb<WebhookDeliveryStatusWeb> for WebhookDeliveryStatus {
+    fn into_web(self) -> WebhookDeliveryStatusWeb {
+        match self {
+            WebhookDeliveryStatus::Pending => WebhookDeliveryStatusWeb::Pending,
+            WebhookDeliveryStatus::Delivered => WebhookDeliveryStatusWeb::Delivered,
+            WebhookDeliveryStatus::Failed => WebhookDeliveryStatusWeb::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BillTemplatePayload {
+    pub name: String,
+    /// The node id of the drawee, which is the drawer for promissory notes
+    pub drawee: String,
+    /// The node id of the payee, which is the drawer for self-drafted bills
+    pub payee: String,
+    pub sum: String,
+    pub currency: String,
+    pub country_of_issuing: String,
+    pub city_of_issuing: String,
+    pub country_of_payment: String,
+    pub city_of_payment: String,
+    pub language: String,
+    /// The days between the issue date and the maturity date of the bills
+    pub term_days: u32,
+    /// Without a schedule, bills are only issued on demand
+    pub schedule: Option<BillTemplateSchedulePayload>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BillTemplateSchedulePayload {
+    pub interval: BillTemplateIntervalWeb,
+    pub start_date: String,
+    pub end_date: Option<String>,
+}
+
+impl FromWeb<BillTemplateSchedulePayload> for BillTemplateSchedule {
+    fn from_web(value: BillTemplateSchedulePayload) -> Self {
+        Self {
+            interval: BillTemplateInterval::from_web(value.interval),
+            start_date: value.start_date,
+            end_date: value.end_date,
+            occurrences_done: 0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct IssueFromTemplatePayload {
+    /// Defaults to the sum of the template
+    pub sum: Option<String>,
+    /// Defaults to today
+    pub issue_date: Option<String>,
+    pub file_upload_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillTemplatesResponse {
+    pub templates: Vec<BillTemplateWeb>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillTemplateWeb {
+    pub id: String,
+    pub name: String,
+    pub drawer: String,
+    pub drawee: String,
+    pub payee: String,
+    pub sum: String,
+    pub currency: String,
+    pub country_of_issuing: String,
+    pub city_of_issuing: String,
+    pub country_of_payment: String,
+    pub city_of_payment: String,
+    pub language: String,
+    pub term_days: u32,
+    pub schedule: Option<BillTemplateScheduleWeb>,
+    pub created_at: u64,
+}
+
+impl IntoWeb<BillTemplateWeb> for BillTemplate {
+    fn into_web(self) -> BillTemplateWeb {
+        BillTemplateWeb {
+            id: self.id,
+            name: self.name,
+            drawer: self.drawer_node_id,
+            drawee: self.drawee_node_id,
+            payee: self.payee_node_id,
+            sum: currency::sum_to_string(self.sum),
+            currency: self.currency,
+            country_of_issuing: self.country_of_issuing,
+            city_of_issuing: self.city_of_issuing,
+            country_of_payment: self.country_of_payment,
+            city_of_payment: self.city_of_payment,
+            language: self.language,
+            term_days: self.term_days,
+            schedule: self.schedule.map(|s| s.into_web()),
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillTemplateScheduleWeb {
+    pub interval: BillTemplateIntervalWeb,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub occurrences_done: u32,
+    /// The date the next bill is issued at, None if the schedule ended
+    pub next_issue_date: Option<String>,
+}
+
+impl IntoWeb<BillTemplateScheduleWeb> for BillTemplateSchedule {
+    fn into_web(self) -> BillTemplateScheduleWeb {
+        BillTemplateScheduleWeb {
+            next_issue_date: self.next_issue_date(),
+            interval: self.interval.into_web(),
+            start_date: self.start_date,
+            end_date: self.end_date,
+            occurrences_done: self.occurrences_done,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub enum BillTemplateIntervalWeb {
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl IntoWeb<BillTemplateIntervalWeb> for BillTemplateInterval {
+    fn into_web(self) -> BillTemplateIntervalWeb {
+        match self {
+            BillTemplateInterval::Weekly => BillTemplateIntervalWeb::Weekly,
+            BillTemplateInterval::Monthly => BillTemplateIntervalWeb::Monthly,
+            BillTemplateInterval::Quarterly => BillTemplateIntervalWeb::Quarterly,
+            BillTemplateInterval::Yearly => BillTemplateIntervalWeb::Yearly,
+        }
+    }
+}
+
+impl FromWeb<BillTemplateIntervalWeb> for BillTemplateInterval {
+    fn from_web(value: BillTemplateIntervalWeb) -> Self {
+        match value {
+            BillTemplateIntervalWeb::Weekly => BillTemplateInterval::Weekly,
+            BillTemplateIntervalWeb::Monthly => BillTemplateInterval::Monthly,
+            BillTemplateIntervalWeb::Quarterly => BillTemplateInterval::Quarterly,
+            BillTemplateIntervalWeb::Yearly => BillTemplateInterval::Yearly,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillTemplateIssuesResponse {
+    pub issues: Vec<BillTemplateIssueWeb>,
+}
+
+/// A bill, which was issued from a template
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BillTemplateIssueWeb {
+    pub template_id: String,
+    pub bill_id: String,
+    /// The occurrence of the schedule the bill was issued for, None if it was issued on demand
+    pub occurrence: Option<u32>,
+    pub issued_at: u64,
+}
+
+impl IntoWeb<BillTemplateIssueWeb> for BillTemplateIssue {
+    fn into_web(self) -> BillTemplateIssueWeb {
+        BillTemplateIssueWeb {
+            template_id: self.template_id,
+            bill_id: self.bill_id,
+            occurrence: self.occurrence,
+            issued_at: self.issued_at,
+        }
+    }
+}
+
 pub struct TempFileWrapper<'a>(pub &'a TempFile<'a>);
 
 #[async_trait]
@@ -19,4 +19,12 @@ pub enum Error {
     /// all errors originating from the bcr API notification service layer
     #[error("Bill Service error: {0}")]
     NotificationService(#[from] service::notification_service::Error),
+
+    /// errors from logging in to the HTTP API
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// a client entered too many wrong passwords and has to wait for the given seconds
+    #[error("Too many wrong passwords, retry in {0} seconds")]
+    TooManyLoginAttempts(u64),
 }
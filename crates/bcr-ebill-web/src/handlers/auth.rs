@@ -0,0 +1,413 @@
+use super::Result;
+use super::middleware::{Authenticated, Reauthenticated, session_token};
+use crate::auth::{AuthContext, PasswordCheck, SESSION_COOKIE, SessionState};
+use crate::data::{
+    ApiTokensResponse, AuthStatusResponse, CreateApiTokenPayload, CreateApiTokenResponse, IntoWeb,
+    LoginPayload, LoginResponse, SuccessResponse,
+};
+use crate::error::Error;
+use bcr_ebill_api::data::api_token::ApiTokenScope;
+use bcr_ebill_api::service::{self, ServiceContext};
+use bcr_ebill_api::util::date::now;
+use log::warn;
+use rocket::http::{Cookie, CookieJar, SameSite};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::serde::json::Json;
+use rocket::{State, delete, get, post, put};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+#[utoipa::path(
+    tag = "Authentication",
+    path = "/api/auth/status",
+    description = "Returns whether authentication is enabled and the state of the current session",
+    responses(
+        (status = 200, description = "The authentication status", body = AuthStatusResponse)
+    )
+)]
+#[get("/status")]
+pub async fn status(auth: &State<AuthContext>, session: SessionToken) -> Json<AuthStatusResponse> {
+    let state = session
+        .0
+        .and_then(|token| auth.validate_session(&token, now().timestamp() as u64));
+    Json(AuthStatusResponse {
+        enabled: auth.is_enabled(),
+        authenticated: !auth.is_enabled() || state.is_some(),
+        reauthenticated: !auth.is_enabled() || state == Some(SessionState::Reauthenticated),
+    })
+}
+
+#[utoipa::path(
+    tag = "Authentication",
+    path = "/api/auth/login",
+    description = "Logs in with the password and starts a session, which is set as cookie",
+    request_body(description = "The password of the node", content((LoginPayload))),
+    responses(
+        (status = 200, description = "The session", body = LoginResponse),
+        (status = 401, description = "The password is wrong"),
+        (status = 429, description = "Too many wrong passwords were entered from this client")
+    )
+)]
+#[post("/login", format = "json", data = "<login_payload>")]
+pub async fn login(
+    auth: &State<AuthContext>,
+    cookies: &CookieJar<'_>,
+    client_ip: Option<IpAddr>,
+    login_payload: Json<LoginPayload>,
+) -> Result<Json<LoginResponse>> {
+    if !auth.is_enabled() {
+        return Err(service::Error::Validation(String::from(
+            "Authentication is not enabled for this node",
+        ))
+        .into());
+    }
+    let timestamp = now().timestamp() as u64;
+    check_password(auth, client_ip, &login_payload.password, timestamp)?;
+    let token = auth.create_session(timestamp);
+    cookies.add(
+        Cookie::build((SESSION_COOKIE, token.clone()))
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .path("/"),
+    );
+    Ok(Json(LoginResponse {
+        token,
+        session_timeout_seconds: auth.session_timeout_seconds(),
+    }))
+}
+
+#[utoipa::path(
+    tag = "Authentication",
+    path = "/api/auth/reauthenticate",
+    description = "Enters the password again for the current session, which is required for sensitive endpoints, like the seed phrase",
+    request_body(description = "The password of the node", content((LoginPayload))),
+    responses(
+        (status = 200, description = "The session was re-authenticated"),
+        (status = 401, description = "The password is wrong, or there is no session"),
+        (status = 429, description = "Too many wrong passwords were entered from this client")
+    )
+)]
+#[put("/reauthenticate", format = "json", data = "<login_payload>")]
+pub async fn reauthenticate(
+    _auth: Authenticated,
+    auth: &State<AuthContext>,
+    session: SessionToken,
+    client_ip: Option<IpAddr>,
+    login_payload: Json<LoginPayload>,
+) -> Result<Json<SuccessResponse>> {
+    if !auth.is_enabled() {
+        return Ok(Json(SuccessResponse::new()));
+    }
+    let timestamp = now().timestamp() as u64;
+    check_password(auth, client_ip, &login_payload.password, timestamp)?;
+    match session.0 {
+        Some(token) if auth.reauthenticate(&token, timestamp) => Ok(Json(SuccessResponse::new())),
+        _ => Err(Error::Unauthorized(String::from("No valid session"))),
+    }
+}
+
+/// Checks the password entered by the client, failed attempts are counted per IP address
+fn check_password(
+    auth: &AuthContext,
+    client_ip: Option<IpAddr>,
+    password: &str,
+    timestamp: u64,
+) -> Result<()> {
+    let client = client_ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| String::from("unknown"));
+    match auth.check_password(&client, password, timestamp) {
+        PasswordCheck::Valid => Ok(()),
+        PasswordCheck::Invalid => {
+            warn!("Wrong password entered from {client}");
+            Err(Error::Unauthorized(String::from("Wrong password")))
+        }
+        PasswordCheck::LockedOut {
+            retry_after_seconds,
+        } => Err(Error::TooManyLoginAttempts(retry_after_seconds)),
+    }
+}
+
+#[utoipa::path(
+    tag = "Authentication",
+    path = "/api/auth/logout",
+    description = "Ends the current session",
+    responses(
+        (status = 200, description = "The session was ended")
+    )
+)]
+#[post("/logout")]
+pub async fn logout(
+    auth: &State<AuthContext>,
+    cookies: &CookieJar<'_>,
+    session: SessionToken,
+) -> Json<SuccessResponse> {
+    if let Some(token) = session.0 {
+        auth.remove_session(&token);
+    }
+    cookies.remove(Cookie::build(SESSION_COOKIE).path("/"));
+    Json(SuccessResponse::new())
+}
+
+#[utoipa::path(
+    tag = "Authentication",
+    path = "/api/auth/tokens",
+    description = "Returns all API tokens. Requires a re-authenticated session, API tokens are not allowed",
+    responses(
+        (status = 200, description = "The API tokens", body = ApiTokensResponse)
+    )
+)]
+#[get("/tokens")]
+pub async fn list_tokens(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+) -> Result<Json<ApiTokensResponse>> {
+    let api_tokens = state.api_token_service.list_tokens().await?;
+    Ok(Json(ApiTokensResponse {
+        api_tokens: api_tokens.into_iter().map(|t| t.into_web()).collect(),
+    }))
+}
+
+#[utoipa::path(
+    tag = "Authentication",
+    path = "/api/auth/tokens",
+    description = "Creates a named API token with the given scopes. Its secret is only returned in this response. Requires a re-authenticated session, API tokens are not allowed",
+    request_body(description = "The name and scopes of the token", content((CreateApiTokenPayload))),
+    responses(
+        (status = 200, description = "The created API token with its secret", body = CreateApiTokenResponse)
+    )
+)]
+#[post("/tokens", format = "json", data = "<create_api_token_payload>")]
+pub async fn create_token(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+    create_api_token_payload: Json<CreateApiTokenPayload>,
+) -> Result<Json<CreateApiTokenResponse>> {
+    let scopes = create_api_token_payload
+        .scopes
+        .iter()
+        .map(|s| ApiTokenScope::from_str(s))
+        .collect::<std::result::Result<Vec<ApiTokenScope>, String>>()
+        .map_err(service::Error::Validation)?;
+    let (api_token, token) = state
+        .api_token_service
+        .create_token(
+            &create_api_token_payload.name,
+            scopes,
+            now().timestamp() as u64,
+        )
+        .await?;
+    Ok(Json(CreateApiTokenResponse {
+        token,
+        api_token: api_token.into_web(),
+    }))
+}
+
+#[utoipa::path(
+    tag = "Authentication",
+    path = "/api/auth/tokens/{id}",
+    description = "Revokes the API token with the given id. Requires a re-authenticated session, API tokens are not allowed",
+    params(
+        ("id" = String, description = "The id of the API token")
+    ),
+    responses(
+        (status = 200, description = "The API token was revoked"),
+        (status = 404, description = "There is no API token with the given id")
+    )
+)]
+#[delete("/tokens/<id>")]
+pub async fn remove_token(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<SuccessResponse>> {
+    state.api_token_service.remove_token(id).await?;
+    Ok(Json(SuccessResponse::new()))
+}
+
+/// The session token of the request, if there is one
+pub struct SessionToken(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SessionToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(SessionToken(session_token(request)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::http::{ContentType, Header, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::{Build, Rocket, routes};
+
+    #[get("/protected")]
+    fn protected(_auth: Authenticated) -> &'static str {
+        "ok"
+    }
+
+    #[get("/sensitive")]
+    fn sensitive(_reauth: Reauthenticated) -> &'static str {
+        "ok"
+    }
+
+    fn rocket(password: Option<&str>) -> Rocket<Build> {
+        rocket::build()
+            .manage(AuthContext::new(password, 3600, 300))
+            .mount("/api/auth", routes![status, login, reauthenticate])
+            .mount("/", routes![protected, sensitive])
+    }
+
+    async fn client(password: Option<&str>) -> Client {
+        Client::tracked(rocket(password))
+            .await
+            .expect("valid rocket instance")
+    }
+
+    fn bearer(token: &str) -> Header<'static> {
+        Header::new("Authorization", format!("Bearer {token}"))
+    }
+
+    async fn login_status(client: &Client, password: &str) -> Status {
+        client
+            .post("/api/auth/login")
+            .header(ContentType::JSON)
+            .body(format!(r#"{{ "password": "{password}" }}"#))
+            .dispatch()
+            .await
+            .status()
+    }
+
+    #[rocket::async_test]
+    async fn everything_allowed_without_password() {
+        let client = client(None).await;
+        assert_eq!(
+            client.get("/protected").dispatch().await.status(),
+            Status::Ok
+        );
+        assert_eq!(
+            client.get("/sensitive").dispatch().await.status(),
+            Status::Ok
+        );
+    }
+
+    #[rocket::async_test]
+    async fn unauthorized_without_session() {
+        let client = client(Some("secret")).await;
+        assert_eq!(
+            client.get("/protected").dispatch().await.status(),
+            Status::Unauthorized
+        );
+        assert_eq!(
+            client.get("/sensitive").dispatch().await.status(),
+            Status::Unauthorized
+        );
+        assert_eq!(
+            client
+                .get("/protected")
+                .header(bearer("unknown"))
+                .dispatch()
+                .await
+                .status(),
+            Status::Unauthorized
+        );
+        assert_eq!(
+            client.get("/api/auth/status").dispatch().await.status(),
+            Status::Ok
+        );
+    }
+
+    #[rocket::async_test]
+    async fn login_with_wrong_password_is_unauthorized() {
+        let client = client(Some("secret")).await;
+        assert_eq!(login_status(&client, "wrong").await, Status::Unauthorized);
+        assert_eq!(
+            client.get("/protected").dispatch().await.status(),
+            Status::Unauthorized
+        );
+    }
+
+    #[rocket::async_test]
+    async fn login_starts_session() {
+        let client = client(Some("secret")).await;
+        assert_eq!(login_status(&client, "secret").await, Status::Ok);
+        // the tracked client sends the session cookie
+        assert_eq!(
+            client.get("/protected").dispatch().await.status(),
+            Status::Ok
+        );
+        assert_eq!(
+            client.get("/sensitive").dispatch().await.status(),
+            Status::Ok
+        );
+    }
+
+    #[rocket::async_test]
+    async fn sensitive_forbidden_without_recent_password() {
+        let client = client(Some("secret")).await;
+        let auth = client.rocket().state::<AuthContext>().unwrap();
+        let token = auth.create_session(now().timestamp() as u64 - 1000);
+        // keep the session alive, but outside of the re-authentication window
+        assert!(
+            auth.validate_session(&token, now().timestamp() as u64)
+                .is_some()
+        );
+
+        assert_eq!(
+            client
+                .get("/protected")
+                .header(bearer(&token))
+                .dispatch()
+                .await
+                .status(),
+            Status::Ok
+        );
+        assert_eq!(
+            client
+                .get("/sensitive")
+                .header(bearer(&token))
+                .dispatch()
+                .await
+                .status(),
+            Status::Forbidden
+        );
+
+        let reauthenticate_status = client
+            .put("/api/auth/reauthenticate")
+            .header(bearer(&token))
+            .header(ContentType::JSON)
+            .body(r#"{ "password": "secret" }"#)
+            .dispatch()
+            .await
+            .status();
+        assert_eq!(reauthenticate_status, Status::Ok);
+        assert_eq!(
+            client
+                .get("/sensitive")
+                .header(bearer(&token))
+                .dispatch()
+                .await
+                .status(),
+            Status::Ok
+        );
+    }
+
+    #[rocket::async_test]
+    async fn login_locked_out_after_too_many_wrong_passwords() {
+        let client = client(Some("secret")).await;
+        for _ in 0..5 {
+            assert_eq!(login_status(&client, "wrong").await, Status::Unauthorized);
+        }
+        let response = client
+            .post("/api/auth/login")
+            .header(ContentType::JSON)
+            .body(r#"{ "password": "secret" }"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::TooManyRequests);
+        assert!(response.headers().get_one("Retry-After").is_some());
+    }
+}
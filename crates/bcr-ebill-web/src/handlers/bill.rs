@@ -1,24 +1,31 @@
 use super::Result;
-use super::middleware::IdentityCheck;
+use super::identity::BinaryFileResponse;
+use super::middleware::{BillsRead, BillsWrite, IdentityCheck, Reauthenticated, Scoped};
 use crate::data::{
-    AcceptBitcreditBillPayload, BillCombinedBitcoinKeyWeb, BillId, BillNumbersToWordsForSum,
-    BillType, BillsResponse, BillsSearchFilterPayload, BitcreditBillPayload, BitcreditBillWeb,
+    AcceptBitcreditBillPayload, BillBatchPayload, BillBatchResultWeb, BillChainBundlePayload,
+    BillChainVerificationWeb, BillCombinedBitcoinKeyWeb, BillId, BillNumbersToWordsForSum,
+    BillsExportPayload, BillsResponse, BillsSearchFilter, BillsSearchFilterPayload,
+    BillsSearchResponse, BillsSortWeb, BitcreditBillPayload, BitcreditBillWeb, DateRange,
     EndorseBitcreditBillPayload, EndorsementsResponse, FromWeb, IntoWeb, LightBitcreditBillWeb,
     MintBitcreditBillPayload, OfferToSellBitcreditBillPayload, PastEndorseesResponse,
     RejectActionBillPayload, RequestRecourseForAcceptancePayload, RequestRecourseForPaymentPayload,
     RequestToAcceptBitcreditBillPayload, RequestToMintBitcreditBillPayload,
-    RequestToPayBitcreditBillPayload, SuccessResponse, TempFileWrapper, UploadBillFilesForm,
-    UploadFilesResponse,
+    RequestToPayBitcreditBillPayload, SuccessResponse, SuccessWithWarningsResponse,
+    TempFileWrapper, UploadBillFilesForm, UploadFileForm, UploadFilesResponse,
 };
 use bcr_ebill_api::service::ServiceContext;
 use bcr_ebill_api::util::file::{UploadFileHandler, detect_content_type_for_bytes};
+use bcr_ebill_api::util::numbers_to_words::{self, Language};
 use bcr_ebill_api::util::{self, BcrKeys};
 use bcr_ebill_api::{
     data::{
-        bill::{BillsFilterRole, LightBitcreditBillResult, RecourseReason},
+        bill::{
+            BillExportFormat, BillSearchCursor, BillSearchQuery, BillSortField, BillStatusFilter,
+            BillsFilterRole, LightBitcreditBillResult, RecourseReason, SortDirection,
+        },
         contact::IdentityPublicData,
     },
-    service::bill_service::BillAction,
+    service::bill_service::{BillAction, validate_bill_to_issue},
 };
 use bcr_ebill_api::{external, service};
 use log::{error, info};
@@ -84,6 +91,7 @@ pub async fn get_signer_public_data_and_keys(
 #[get("/endorsements/<id>")]
 pub async fn get_endorsements_for_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
     id: &str,
 ) -> Result<Json<EndorsementsResponse>> {
@@ -107,6 +115,7 @@ pub async fn get_endorsements_for_bill(
 #[get("/past_endorsees/<id>")]
 pub async fn get_past_endorsees_for_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
     id: &str,
 ) -> Result<Json<PastEndorseesResponse>> {
@@ -121,6 +130,7 @@ pub async fn get_past_endorsees_for_bill(
 
 #[get("/bitcoin_key/<id>")]
 pub async fn bitcoin_key(
+    _reauth: Reauthenticated,
     _identity: IdentityCheck,
     state: &State<ServiceContext>,
     id: &str,
@@ -136,6 +146,7 @@ pub async fn bitcoin_key(
 #[get("/attachment/<bill_id>/<file_name>")]
 pub async fn attachment(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
     bill_id: &str,
     file_name: &str,
@@ -158,46 +169,250 @@ pub async fn attachment(
     Ok((content_type, file_bytes))
 }
 
+#[utoipa::path(
+    tag = "Bill PDF",
+    path = "/bill/pdf/{id}",
+    description = "Renders the current state of the given bill as printable PDF, with the endorsements on the back",
+    params(
+        ("id" = String, Path, description = "The id of the bill")
+    ),
+    responses(
+        (status = 200, description = "The PDF of the bill"),
+        (status = 404, description = "Bill not found")
+    )
+)]
+#[get("/pdf/<id>")]
+pub async fn bill_pdf(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<BinaryFileResponse> {
+    let current_timestamp = util::date::now().timestamp() as u64;
+    let identity = state.identity_service.get_identity().await?;
+    let bytes = state
+        .bill_service
+        .get_bill_pdf(
+            id,
+            &identity,
+            &get_current_identity_node_id(state).await,
+            current_timestamp,
+        )
+        .await?;
+    Ok(BinaryFileResponse {
+        data: bytes,
+        name: format!("bill_{id}.pdf"),
+        content_type: ContentType::PDF,
+    })
+}
+
+#[utoipa::path(
+    tag = "Bill Chain Bundle",
+    path = "/bill/chain_bundle/{id}",
+    description = "Exports the chain of the given bill with its attached files as a self-contained bundle, which can be verified offline",
+    params(
+        ("id" = String, Path, description = "The id of the bill")
+    ),
+    request_body(description = "The blocks to disclose, or all, if not set", content((BillChainBundlePayload))),
+    responses(
+        (status = 200, description = "The JSON bundle of the bill chain"),
+        (status = 404, description = "Bill not found")
+    )
+)]
+#[post("/chain_bundle/<id>", format = "json", data = "<bundle_payload>")]
+pub async fn chain_bundle(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+    id: &str,
+    bundle_payload: Json<BillChainBundlePayload>,
+) -> Result<BinaryFileResponse> {
+    let current_timestamp = util::date::now().timestamp() as u64;
+    let bytes = state
+        .bill_service
+        .export_chain_bundle(
+            id,
+            bundle_payload.0.disclosed_block_ids,
+            &get_current_identity_node_id(state).await,
+            current_timestamp,
+        )
+        .await?;
+    Ok(BinaryFileResponse {
+        data: bytes,
+        name: format!("bill_{id}_chain.json"),
+        content_type: ContentType::JSON,
+    })
+}
+
+#[utoipa::path(
+    tag = "Bill Chain Bundle",
+    path = "/bill/verify_chain_bundle",
+    description = "Verifies an exported bill chain bundle, checking the chain, every block's hash and signature and the hashes of the bundled files",
+    request_body(content_type = "multipart/form-data", content = UploadFileForm),
+    responses(
+        (status = 200, description = "The verification result with the decoded history", body = BillChainVerificationWeb)
+    )
+)]
+#[post("/verify_chain_bundle", data = "<file_upload_form>")]
+pub async fn verify_chain_bundle(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+    file_upload_form: Form<UploadFileForm<'_>>,
+) -> Result<Json<BillChainVerificationWeb>> {
+    let bundle = TempFileWrapper(&file_upload_form.file)
+        .get_contents()
+        .await
+        .map_err(service::Error::Io)?;
+    let verification = state.bill_service.verify_chain_bundle(&bundle)?;
+    Ok(Json(verification.into_web()))
+}
+
 #[utoipa::path(
     tag = "Bills Search",
     path = "/bill/search",
-    description = "Get all bill details for the given filter",
+    description = "Get one page of the bills matching the given filter, in the given sort order",
+    request_body(description = "The filter, sort order and page", content((BillsSearchFilterPayload))),
     responses(
-        (status = 200, description = "Search for bills", body = BillsResponse<LightBitcreditBillWeb>)
+        (status = 200, description = "Search for bills", body = BillsSearchResponse)
     )
 )]
 #[post("/search", format = "json", data = "<bills_filter>")]
 pub async fn search(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
     bills_filter: Json<BillsSearchFilterPayload>,
-) -> Result<Json<BillsResponse<LightBitcreditBillWeb>>> {
-    let filter = bills_filter.0.filter;
-    let (from, to) = match filter.date_range {
+) -> Result<Json<BillsSearchResponse>> {
+    let payload = bills_filter.0;
+    let cursor = match payload.cursor {
+        None => None,
+        Some(cursor) => Some(BillSearchCursor::decode(&cursor).ok_or_else(|| {
+            service::Error::Validation(format!("invalid search cursor: {cursor}"))
+        })?),
+    };
+    let query = BillSearchQuery {
+        cursor,
+        limit: payload.limit,
+        ..to_bill_search_query(Some(payload.filter), payload.sort)?
+    };
+    let result = state
+        .bill_service
+        .search_bills(&query, &get_current_identity_node_id(state).await)
+        .await?;
+    Ok(Json(BillsSearchResponse {
+        bills: result.bills.into_iter().map(|b| b.into_web()).collect(),
+        next_cursor: result.next_cursor,
+    }))
+}
+
+#[utoipa::path(
+    tag = "Bills Export",
+    path = "/bill/export",
+    description = "Export all bills matching the given filter, including their endorsements, as CSV, JSON Lines, or XLSX",
+    request_body(description = "The filter, sort order and format", content((BillsExportPayload))),
+    responses(
+        (status = 200, description = "The file with the exported bills")
+    )
+)]
+#[post("/export", format = "json", data = "<export_payload>")]
+pub async fn export(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+    export_payload: Json<BillsExportPayload>,
+) -> Result<BinaryFileResponse> {
+    let payload = export_payload.0;
+    let query = to_bill_search_query(payload.filter, payload.sort)?;
+    let format = BillExportFormat::from_web(payload.format);
+    let bytes = state
+        .bill_service
+        .export_bills(&query, format, &get_current_identity_node_id(state).await)
+        .await?;
+    let content_type = ContentType::parse_flexible(format.content_type()).ok_or(
+        service::Error::Validation(String::from("invalid content type for the export format")),
+    )?;
+    Ok(BinaryFileResponse {
+        data: bytes,
+        name: format!(
+            "bills_{}.{}",
+            util::date::format_date_string(util::date::now()),
+            format.file_extension()
+        ),
+        content_type,
+    })
+}
+
+/// Converts the given web filter and sort order to a bill search query, without a page
+fn to_bill_search_query(
+    filter: Option<BillsSearchFilter>,
+    sort: Option<BillsSortWeb>,
+) -> Result<BillSearchQuery> {
+    let (sort, direction) = match sort {
+        None => (BillSortField::default(), SortDirection::default()),
+        Some(sort) => (
+            BillSortField::from_web(sort.field),
+            SortDirection::from_web(sort.direction),
+        ),
+    };
+    let Some(filter) = filter else {
+        return Ok(BillSearchQuery {
+            sort,
+            direction,
+            ..Default::default()
+        });
+    };
+    let (issued_from, issued_to) = date_range_to_timestamps(filter.date_range);
+    let (maturity_from, maturity_to) = date_range_to_timestamps(filter.maturity_date_range);
+    let (sum_from, sum_to) = match filter.sum_range {
+        None => (None, None),
+        Some(sum_range) => (
+            sum_range
+                .from
+                .map(|s| util::currency::parse_sum(&s))
+                .transpose()?,
+            sum_range
+                .to
+                .map(|s| util::currency::parse_sum(&s))
+                .transpose()?,
+        ),
+    };
+    Ok(BillSearchQuery {
+        search_term: filter.search_term,
+        role: BillsFilterRole::from_web(filter.role),
+        issued_from,
+        issued_to,
+        maturity_from,
+        maturity_to,
+        sum_from,
+        sum_to,
+        currency: filter.currency,
+        status: filter
+            .status
+            .map(BillStatusFilter::from_web)
+            .unwrap_or_default(),
+        counterparty_node_id: filter.counterparty_node_id,
+        has_attachments: filter.has_attachments,
+        sort,
+        direction,
+        cursor: None,
+        limit: None,
+    })
+}
+
+/// Converts the given date range to timestamps, the end of the range being the end of the day,
+/// so bills during the day are included as well
+fn date_range_to_timestamps(date_range: Option<DateRange>) -> (Option<u64>, Option<u64>) {
+    match date_range {
         None => (None, None),
         Some(date_range) => {
             let from: Option<u64> =
                 util::date::date_string_to_i64_timestamp(&date_range.from, None).map(|v| v as u64);
-            // Change the date to the end of the day, so we collect bills during the day as well
             let to: Option<u64> = util::date::date_string_to_i64_timestamp(&date_range.to, None)
                 .and_then(|v| util::date::end_of_day_as_timestamp(v as u64).map(|v| v as u64));
             (from, to)
         }
-    };
-    let bills = state
-        .bill_service
-        .search_bills(
-            &filter.currency,
-            &filter.search_term,
-            from,
-            to,
-            &BillsFilterRole::from_web(filter.role),
-            &get_current_identity_node_id(state).await,
-        )
-        .await?;
-    Ok(Json(BillsResponse {
-        bills: bills.into_iter().map(|b| b.into_web()).collect(),
-    }))
+    }
 }
 
 #[utoipa::path(
@@ -211,15 +426,13 @@ pub async fn search(
 #[get("/list/light")]
 pub async fn list_light(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
 ) -> Result<Json<BillsResponse<LightBitcreditBillWeb>>> {
     let bills: Vec<LightBitcreditBillResult> = state
         .bill_service
-        .get_bills(&get_current_identity_node_id(state).await)
-        .await?
-        .into_iter()
-        .map(|b| b.into())
-        .collect();
+        .get_light_bills(&get_current_identity_node_id(state).await)
+        .await?;
     Ok(Json(BillsResponse {
         bills: bills.into_iter().map(|b| b.into_web()).collect(),
     }))
@@ -236,6 +449,7 @@ pub async fn list_light(
 #[get("/list")]
 pub async fn list(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
 ) -> Result<Json<BillsResponse<BitcreditBillWeb>>> {
     let bills = state
@@ -258,6 +472,7 @@ pub async fn list(
 #[get("/list_all")]
 pub async fn all_bills_from_all_identities(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
 ) -> Result<Json<BillsResponse<BitcreditBillWeb>>> {
     let bills = state.bill_service.get_bills_from_all_identities().await?;
@@ -269,13 +484,21 @@ pub async fn all_bills_from_all_identities(
 #[get("/numbers_to_words_for_sum/<id>")]
 pub async fn numbers_to_words_for_sum(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
     id: &str,
 ) -> Result<Json<BillNumbersToWordsForSum>> {
     let bill = state.bill_service.get_bill(id).await?;
     let sum = bill.sum;
-    let sum_as_words = util::numbers_to_words::encode(&sum);
-    Ok(Json(BillNumbersToWordsForSum { sum, sum_as_words }))
+    let language = Language::from_code(&bill.language);
+    let sum_as_words = numbers_to_words::encode_in(&sum, language);
+    let sum_and_currency_as_words = numbers_to_words::sum_to_words(&sum, &bill.currency, language);
+    Ok(Json(BillNumbersToWordsForSum {
+        sum,
+        sum_as_words,
+        sum_and_currency_as_words,
+        language: bill.language,
+    }))
 }
 
 #[utoipa::path(
@@ -293,6 +516,7 @@ pub async fn numbers_to_words_for_sum(
 #[get("/detail/<id>")]
 pub async fn bill_detail(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     state: &State<ServiceContext>,
     id: &str,
 ) -> Result<Json<BitcreditBillWeb>> {
@@ -313,6 +537,7 @@ pub async fn bill_detail(
 #[get("/check_payment")]
 pub async fn check_payment(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
 ) -> Result<Json<SuccessResponse>> {
     if let Err(e) = state.bill_service.check_bills_payment().await {
@@ -329,6 +554,7 @@ pub async fn check_payment(
 #[post("/upload_files", data = "<files_upload_form>")]
 pub async fn upload_files(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     files_upload_form: Form<UploadBillFilesForm<'_>>,
 ) -> Result<Json<UploadFilesResponse>> {
@@ -365,112 +591,53 @@ pub async fn upload_files(
     Ok(Json(file_upload_response.into_web()))
 }
 
+async fn get_counterparty_from_contacts(
+    state: &State<ServiceContext>,
+    node_id: &str,
+    role: &str,
+) -> Result<IdentityPublicData> {
+    match state.contact_service.get_identity_by_node_id(node_id).await {
+        Ok(Some(identity)) => Ok(identity),
+        Ok(None) | Err(_) => Err(service::Error::Validation(format!(
+            "Can not get {role} identity from contacts."
+        ))
+        .into()),
+    }
+}
+
 #[post("/issue", format = "json", data = "<bill_payload>")]
 pub async fn issue_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     bill_payload: Json<BitcreditBillPayload>,
 ) -> Result<Json<BillId>> {
-    let sum = util::currency::parse_sum(&bill_payload.sum)?;
-
     util::file::validate_file_upload_id(&bill_payload.file_upload_id)?;
 
-    if util::date::date_string_to_i64_timestamp(&bill_payload.issue_date, None).is_none() {
-        return Err(service::Error::Validation(String::from("invalid issue date")).into());
-    }
-
-    if util::date::date_string_to_i64_timestamp(&bill_payload.maturity_date, None).is_none() {
-        return Err(service::Error::Validation(String::from("invalid maturity date")).into());
-    }
-
     let (drawer_public_data, drawer_keys) = get_signer_public_data_and_keys(state).await?;
 
-    let bill_type = BillType::try_from(bill_payload.t)?;
-
-    if bill_payload.drawee == bill_payload.payee {
-        return Err(service::Error::Validation(String::from(
-            "Drawee can't be Payee at the same time",
-        ))
-        .into());
-    }
-
-    let (public_data_drawee, public_data_payee) = match bill_type {
-        // Drawer is payee
-        BillType::SelfDrafted => {
-            let public_data_drawee = match state
-                .contact_service
-                .get_identity_by_node_id(&bill_payload.drawee)
-                .await
-            {
-                Ok(Some(drawee)) => drawee,
-                Ok(None) | Err(_) => {
-                    return Err(service::Error::Validation(String::from(
-                        "Can not get drawee identity from contacts.",
-                    ))
-                    .into());
-                }
-            };
-
-            let public_data_payee = drawer_public_data.clone();
-
-            (public_data_drawee, public_data_payee)
-        }
-        // Drawer is drawee
-        BillType::PromissoryNote => {
-            let public_data_drawee = drawer_public_data.clone();
-
-            let public_data_payee = match state
-                .contact_service
-                .get_identity_by_node_id(&bill_payload.payee)
-                .await
-            {
-                Ok(Some(drawee)) => drawee,
-                Ok(None) | Err(_) => {
-                    return Err(service::Error::Validation(String::from(
-                        "Can not get payee identity from contacts.",
-                    ))
-                    .into());
-                }
-            };
-
-            (public_data_drawee, public_data_payee)
-        }
-        // Drawer is neither drawee nor payee
-        BillType::ThreeParties => {
-            let public_data_drawee = match state
-                .contact_service
-                .get_identity_by_node_id(&bill_payload.drawee)
-                .await
-            {
-                Ok(Some(drawee)) => drawee,
-                Ok(None) | Err(_) => {
-                    return Err(service::Error::Validation(String::from(
-                        "Can not get drawee identity from contacts.",
-                    ))
-                    .into());
-                }
-            };
-
-            let public_data_payee = match state
-                .contact_service
-                .get_identity_by_node_id(&bill_payload.payee)
-                .await
-            {
-                Ok(Some(drawee)) => drawee,
-                Ok(None) | Err(_) => {
-                    return Err(service::Error::Validation(String::from(
-                        "Can not get payee identity from contacts.",
-                    ))
-                    .into());
-                }
-            };
+    let bill_to_issue = validate_bill_to_issue(
+        bill_payload.t,
+        &bill_payload.sum,
+        &bill_payload.issue_date,
+        &bill_payload.maturity_date,
+        &bill_payload.drawee,
+        &bill_payload.payee,
+        &drawer_public_data.node_id,
+    )
+    .map_err(|errors| service::Error::Validation(errors.join(", ")))?;
 
-            (public_data_drawee, public_data_payee)
-        }
+    let public_data_drawee = match bill_to_issue.drawee {
+        None => drawer_public_data.clone(),
+        Some(ref node_id) => get_counterparty_from_contacts(state, node_id, "drawee").await?,
+    };
+    let public_data_payee = match bill_to_issue.payee {
+        None => drawer_public_data.clone(),
+        Some(ref node_id) => get_counterparty_from_contacts(state, node_id, "payee").await?,
     };
 
     let timestamp = external::time::TimeApi::get_atomic_time().await.timestamp;
-    let bill = state
+    let issued = state
         .bill_service
         .issue_new_bill(
             bill_payload.country_of_issuing.to_owned(),
@@ -479,7 +646,7 @@ pub async fn issue_bill(
             bill_payload.maturity_date.to_owned(),
             public_data_drawee,
             public_data_payee,
-            sum,
+            bill_to_issue.sum,
             bill_payload.currency.to_owned(),
             bill_payload.country_of_payment.to_owned(),
             bill_payload.city_of_payment.to_owned(),
@@ -492,13 +659,72 @@ pub async fn issue_bill(
         .await?;
 
     Ok(Json(BillId {
-        id: bill.id.clone(),
+        id: issued.bill.id,
+        warnings: issued.warnings,
     }))
 }
 
+#[utoipa::path(
+    tag = "Bills Batch",
+    path = "/bill/batch/upload_file",
+    description = "Uploads a CSV or JSON file with bills to issue as a batch and returns its upload id",
+    request_body(content_type = "multipart/form-data", content = UploadFileForm),
+    responses(
+        (status = 200, description = "The upload id of the file", body = UploadFilesResponse)
+    )
+)]
+#[post("/batch/upload_file", data = "<file_upload_form>")]
+pub async fn upload_batch_file(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
+    state: &State<ServiceContext>,
+    file_upload_form: Form<UploadFileForm<'_>>,
+) -> Result<Json<UploadFilesResponse>> {
+    let file: &dyn UploadFileHandler = &TempFileWrapper(&file_upload_form.file);
+    state.file_upload_service.validate_data_file(file).await?;
+    let file_upload_response = state.file_upload_service.upload_files(vec![file]).await?;
+    Ok(Json(file_upload_response.into_web()))
+}
+
+#[utoipa::path(
+    tag = "Bills Batch",
+    path = "/bill/batch/issue",
+    description = "Validates every row of an uploaded batch file like a single bill to issue and issues the valid rows as bills drawn by the current identity. With all_or_nothing, the bills are only issued, if all rows are valid. Rows already issued for the batch are skipped. With dry_run, only the validation is done",
+    request_body(description = "The upload id of the batch file", content((BillBatchPayload))),
+    responses(
+        (status = 200, description = "The errors, warnings and issued bill of every row", body = BillBatchResultWeb)
+    )
+)]
+#[post("/batch/issue", format = "json", data = "<batch_payload>")]
+pub async fn issue_batch(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
+    state: &State<ServiceContext>,
+    batch_payload: Json<BillBatchPayload>,
+) -> Result<Json<BillBatchResultWeb>> {
+    let payload = batch_payload.0;
+    util::file::validate_file_upload_id(&Some(payload.file_upload_id.clone()))?;
+    let (drawer_public_data, drawer_keys) = get_signer_public_data_and_keys(state).await?;
+    let timestamp = external::time::TimeApi::get_atomic_time().await.timestamp;
+    let result = state
+        .bill_service
+        .issue_bill_batch(
+            &payload.file_upload_id,
+            payload.batch_id.as_deref(),
+            payload.dry_run,
+            payload.all_or_nothing,
+            &drawer_public_data,
+            &drawer_keys,
+            timestamp,
+        )
+        .await?;
+    Ok(Json(result.into_web()))
+}
+
 #[put("/offer_to_sell", format = "json", data = "<offer_to_sell_payload>")]
 pub async fn offer_to_sell_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     offer_to_sell_payload: Json<OfferToSellBitcreditBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -541,9 +767,10 @@ pub async fn offer_to_sell_bill(
 #[put("/endorse", format = "json", data = "<endorse_bill_payload>")]
 pub async fn endorse_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     endorse_bill_payload: Json<EndorseBitcreditBillPayload>,
-) -> Result<Json<SuccessResponse>> {
+) -> Result<Json<SuccessWithWarningsResponse>> {
     let public_data_endorsee = match state
         .contact_service
         .get_identity_by_node_id(&endorse_bill_payload.endorsee)
@@ -557,21 +784,20 @@ pub async fn endorse_bill(
             .into());
         }
     };
-
     let timestamp = external::time::TimeApi::get_atomic_time().await.timestamp;
     let (signer_public_data, signer_keys) = get_signer_public_data_and_keys(state).await?;
-    state
+    let warnings = state
         .bill_service
-        .execute_bill_action(
+        .endorse_bill(
             &endorse_bill_payload.bill_id,
-            BillAction::Endorse(public_data_endorsee.clone()),
+            public_data_endorsee,
             &signer_public_data,
             &signer_keys,
             timestamp,
         )
         .await?;
 
-    Ok(Json(SuccessResponse::new()))
+    Ok(Json(SuccessWithWarningsResponse::new(warnings)))
 }
 
 #[put(
@@ -581,6 +807,7 @@ pub async fn endorse_bill(
 )]
 pub async fn request_to_pay_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     request_to_pay_bill_payload: Json<RequestToPayBitcreditBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -608,6 +835,7 @@ pub async fn request_to_pay_bill(
 )]
 pub async fn request_to_accept_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     request_to_accept_bill_payload: Json<RequestToAcceptBitcreditBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -631,6 +859,7 @@ pub async fn request_to_accept_bill(
 #[put("/accept", format = "json", data = "<accept_bill_payload>")]
 pub async fn accept_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     accept_bill_payload: Json<AcceptBitcreditBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -658,6 +887,7 @@ pub async fn accept_bill(
 )]
 pub async fn request_to_mint_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     _state: &State<ServiceContext>,
     request_to_mint_bill_payload: Json<RequestToMintBitcreditBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -670,6 +900,7 @@ pub async fn request_to_mint_bill(
 #[put("/mint", format = "json", data = "<mint_bill_payload>")]
 pub async fn mint_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     mint_bill_payload: Json<MintBitcreditBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -709,6 +940,7 @@ pub async fn mint_bill(
 #[put("/reject_to_accept", format = "json", data = "<reject_payload>")]
 pub async fn reject_to_accept_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     reject_payload: Json<RejectActionBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -732,6 +964,7 @@ pub async fn reject_to_accept_bill(
 #[put("/reject_to_pay", format = "json", data = "<reject_payload>")]
 pub async fn reject_to_pay_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     reject_payload: Json<RejectActionBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -755,6 +988,7 @@ pub async fn reject_to_pay_bill(
 #[put("/reject_to_buy", format = "json", data = "<reject_payload>")]
 pub async fn reject_to_buy_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     reject_payload: Json<RejectActionBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -778,6 +1012,7 @@ pub async fn reject_to_buy_bill(
 #[put("/reject_to_pay_recourse", format = "json", data = "<reject_payload>")]
 pub async fn reject_to_pay_recourse_bill(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     reject_payload: Json<RejectActionBillPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -806,6 +1041,7 @@ pub async fn reject_to_pay_recourse_bill(
 )]
 pub async fn request_to_recourse_bill_payment(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     request_recourse_payload: Json<RequestRecourseForPaymentPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -826,6 +1062,7 @@ pub async fn request_to_recourse_bill_payment(
 )]
 pub async fn request_to_recourse_bill_acceptance(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     state: &State<ServiceContext>,
     request_recourse_payload: Json<RequestRecourseForAcceptancePayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -0,0 +1,190 @@
+use super::Result;
+use super::bill::get_current_identity_node_id;
+use super::middleware::{BillsRead, BillsWrite, IdentityCheck, Scoped};
+use crate::data::{
+    BillId, BillTemplateIssueWeb, BillTemplateIssuesResponse, BillTemplatePayload, BillTemplateWeb,
+    BillTemplatesResponse, FromWeb, IntoWeb, IssueFromTemplatePayload, SuccessResponse,
+};
+use bcr_ebill_api::data::bill_template::{BillTemplate, BillTemplateSchedule};
+use bcr_ebill_api::service::{self, ServiceContext};
+use bcr_ebill_api::{external, util};
+use rocket::serde::json::Json;
+use rocket::{State, delete, get, post, put};
+
+fn template_from_payload(
+    payload: BillTemplatePayload,
+    id: String,
+    drawer_node_id: String,
+    created_at: u64,
+) -> Result<BillTemplate> {
+    Ok(BillTemplate {
+        id,
+        name: payload.name,
+        drawer_node_id,
+        drawee_node_id: payload.drawee,
+        payee_node_id: payload.payee,
+        sum: util::currency::parse_sum(&payload.sum)?,
+        currency: payload.currency,
+        country_of_issuing: payload.country_of_issuing,
+        city_of_issuing: payload.city_of_issuing,
+        country_of_payment: payload.country_of_payment,
+        city_of_payment: payload.city_of_payment,
+        language: payload.language,
+        term_days: payload.term_days,
+        schedule: payload.schedule.map(BillTemplateSchedule::from_web),
+        created_at,
+    })
+}
+
+#[get("/list")]
+pub async fn list(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<BillTemplatesResponse>> {
+    let templates = state.bill_template_service.list_templates().await?;
+    Ok(Json(BillTemplatesResponse {
+        templates: templates.into_iter().map(|t| t.into_web()).collect(),
+    }))
+}
+
+#[get("/detail/<id>")]
+pub async fn detail(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<BillTemplateWeb>> {
+    let template = state.bill_template_service.get_template(id).await?;
+    Ok(Json(template.into_web()))
+}
+
+/// Creates a template for bills drawn by the currently selected identity
+#[post("/create", format = "json", data = "<bill_template_payload>")]
+pub async fn create(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
+    state: &State<ServiceContext>,
+    bill_template_payload: Json<BillTemplatePayload>,
+) -> Result<Json<BillTemplateWeb>> {
+    let timestamp = external::time::TimeApi::get_atomic_time().await.timestamp;
+    let template = template_from_payload(
+        bill_template_payload.into_inner(),
+        String::new(),
+        get_current_identity_node_id(state).await,
+        timestamp,
+    )?;
+    let template = state
+        .bill_template_service
+        .create_template(template, timestamp)
+        .await?;
+    Ok(Json(template.into_web()))
+}
+
+/// Replaces the details of the template, its drawer stays the same
+#[put("/edit/<id>", format = "json", data = "<bill_template_payload>")]
+pub async fn edit(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
+    state: &State<ServiceContext>,
+    id: &str,
+    bill_template_payload: Json<BillTemplatePayload>,
+) -> Result<Json<BillTemplateWeb>> {
+    let existing = state.bill_template_service.get_template(id).await?;
+    let template = template_from_payload(
+        bill_template_payload.into_inner(),
+        existing.id,
+        existing.drawer_node_id,
+        existing.created_at,
+    )?;
+    let template = state
+        .bill_template_service
+        .update_template(template)
+        .await?;
+    Ok(Json(template.into_web()))
+}
+
+#[delete("/remove/<id>")]
+pub async fn remove(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<SuccessResponse>> {
+    state.bill_template_service.remove_template(id).await?;
+    Ok(Json(SuccessResponse::new()))
+}
+
+#[post("/issue/<id>", format = "json", data = "<issue_payload>")]
+pub async fn issue(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
+    state: &State<ServiceContext>,
+    id: &str,
+    issue_payload: Json<IssueFromTemplatePayload>,
+) -> Result<Json<BillId>> {
+    let payload = issue_payload.into_inner();
+    let sum = match payload.sum {
+        Some(ref sum) => Some(util::currency::parse_sum(sum)?),
+        None => None,
+    };
+
+    util::file::validate_file_upload_id(&payload.file_upload_id)?;
+
+    if payload
+        .issue_date
+        .as_deref()
+        .is_some_and(|d| util::date::date_string_to_i64_timestamp(d, None).is_none())
+    {
+        return Err(service::Error::Validation(String::from("invalid issue date")).into());
+    }
+
+    let timestamp = external::time::TimeApi::get_atomic_time().await.timestamp;
+    let issued = state
+        .bill_template_service
+        .issue_bill_from_template(
+            id,
+            sum,
+            payload.issue_date,
+            payload.file_upload_id,
+            timestamp,
+        )
+        .await?;
+
+    Ok(Json(BillId {
+        id: issued.bill.id,
+        warnings: issued.warnings,
+    }))
+}
+
+/// The bills issued from the template, newest first
+#[get("/bills/<id>")]
+pub async fn issued_bills(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<BillTemplateIssuesResponse>> {
+    let issues = state.bill_template_service.list_issued_bills(id).await?;
+    Ok(Json(BillTemplateIssuesResponse {
+        issues: issues.into_iter().map(|i| i.into_web()).collect(),
+    }))
+}
+
+/// The template the given bill was issued from
+#[get("/for_bill/<bill_id>")]
+pub async fn for_bill(
+    _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
+    state: &State<ServiceContext>,
+    bill_id: &str,
+) -> Result<Json<BillTemplateIssueWeb>> {
+    match state
+        .bill_template_service
+        .get_template_issue_for_bill(bill_id)
+        .await?
+    {
+        Some(issue) => Ok(Json(issue.into_web())),
+        None => Err(service::Error::NotFound.into()),
+    }
+}
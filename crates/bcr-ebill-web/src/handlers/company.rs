@@ -1,9 +1,9 @@
 use super::Result;
-use super::middleware::IdentityCheck;
+use super::middleware::{CompaniesRead, CompaniesWrite, IdentityCheck, Scoped};
 use crate::data::{
-    AddSignatoryPayload, CompaniesResponse, CompanyWeb, CreateCompanyPayload, EditCompanyPayload,
-    FromWeb, IntoWeb, ListSignatoriesResponse, RemoveSignatoryPayload, SuccessResponse,
-    TempFileWrapper, UploadFileForm, UploadFilesResponse,
+    AddSignatoryPayload, CompaniesResponse, CompanyWeb, ContactCardWeb, CreateCompanyPayload,
+    EditCompanyPayload, FromWeb, IntoWeb, ListSignatoriesResponse, RemoveSignatoryPayload,
+    SuccessResponse, TempFileWrapper, UploadFileForm, UploadFilesResponse,
 };
 use bcr_ebill_api::data::{OptionalPostalAddress, PostalAddress};
 use bcr_ebill_api::util;
@@ -15,7 +15,10 @@ use bcr_ebill_api::{
 use rocket::{State, form::Form, get, http::ContentType, post, put, serde::json::Json};
 
 #[get("/list")]
-pub async fn list(state: &State<ServiceContext>) -> Result<Json<CompaniesResponse<CompanyWeb>>> {
+pub async fn list(
+    _scope: Scoped<CompaniesRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<CompaniesResponse<CompanyWeb>>> {
     let companies = state
         .company_service
         .get_list_of_companies()
@@ -28,6 +31,7 @@ pub async fn list(state: &State<ServiceContext>) -> Result<Json<CompaniesRespons
 
 #[get("/signatories/<id>")]
 pub async fn list_signatories(
+    _scope: Scoped<CompaniesRead>,
     state: &State<ServiceContext>,
     id: &str,
 ) -> Result<Json<ListSignatoriesResponse>> {
@@ -40,6 +44,7 @@ pub async fn list_signatories(
 #[get("/file/<id>/<file_name>")]
 pub async fn get_file(
     _identity: IdentityCheck,
+    _scope: Scoped<CompaniesRead>,
     state: &State<ServiceContext>,
     id: &str,
     file_name: &str,
@@ -72,6 +77,7 @@ pub async fn get_file(
 #[post("/upload_file", data = "<file_upload_form>")]
 pub async fn upload_file(
     _identity: IdentityCheck,
+    _scope: Scoped<CompaniesWrite>,
     state: &State<ServiceContext>,
     file_upload_form: Form<UploadFileForm<'_>>,
 ) -> Result<Json<UploadFilesResponse>> {
@@ -95,6 +101,7 @@ pub async fn upload_file(
 #[get("/<id>")]
 pub async fn detail(
     _identity: IdentityCheck,
+    _scope: Scoped<CompaniesRead>,
     state: &State<ServiceContext>,
     id: &str,
 ) -> Result<Json<CompanyWeb>> {
@@ -102,9 +109,33 @@ pub async fn detail(
     Ok(Json(company.into_web()))
 }
 
+#[get("/contact_card/<id>")]
+pub async fn contact_card(
+    _identity: IdentityCheck,
+    _scope: Scoped<CompaniesRead>,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<ContactCardWeb>> {
+    let card = state.company_service.get_contact_card(id).await?;
+    Ok(Json(ContactCardWeb { uri: card.to_uri() }))
+}
+
+#[get("/contact_card/<id>/qr/<format>")]
+pub async fn contact_card_qr_code(
+    _identity: IdentityCheck,
+    _scope: Scoped<CompaniesRead>,
+    state: &State<ServiceContext>,
+    id: &str,
+    format: &str,
+) -> Result<(ContentType, Vec<u8>)> {
+    let card = state.company_service.get_contact_card(id).await?;
+    super::contact_card_qr_code(&card.to_uri(), format)
+}
+
 #[post("/create", format = "json", data = "<create_company_payload>")]
 pub async fn create(
     _identity: IdentityCheck,
+    _scope: Scoped<CompaniesWrite>,
     state: &State<ServiceContext>,
     create_company_payload: Json<CreateCompanyPayload>,
 ) -> Result<Json<CompanyWeb>> {
@@ -136,6 +167,7 @@ pub async fn create(
 #[put("/edit", format = "json", data = "<edit_company_payload>")]
 pub async fn edit(
     _identity: IdentityCheck,
+    _scope: Scoped<CompaniesWrite>,
     state: &State<ServiceContext>,
     edit_company_payload: Json<EditCompanyPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -171,6 +203,7 @@ pub async fn edit(
 #[put("/add_signatory", format = "json", data = "<add_signatory_payload>")]
 pub async fn add_signatory(
     _identity: IdentityCheck,
+    _scope: Scoped<CompaniesWrite>,
     state: &State<ServiceContext>,
     add_signatory_payload: Json<AddSignatoryPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -191,6 +224,7 @@ pub async fn add_signatory(
 )]
 pub async fn remove_signatory(
     _identity: IdentityCheck,
+    _scope: Scoped<CompaniesWrite>,
     state: &State<ServiceContext>,
     remove_signatory_payload: Json<RemoveSignatoryPayload>,
 ) -> Result<Json<SuccessResponse>> {
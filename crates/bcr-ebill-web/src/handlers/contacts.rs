@@ -1,13 +1,17 @@
-use super::super::data::{EditContactPayload, NewContactPayload};
+use super::super::data::{
+    ContactVerificationPayload, EditContactPayload, NewContactFromCardPayload,
+    NewContactFromProfilePayload, NewContactPayload,
+};
 use super::Result;
-use super::middleware::IdentityCheck;
+use super::identity::BinaryFileResponse;
+use super::middleware::{ContactsRead, ContactsWrite, IdentityCheck, Scoped};
 use crate::data::{
-    ContactTypeWeb, ContactWeb, ContactsResponse, FromWeb, IntoWeb, SuccessResponse,
-    TempFileWrapper, UploadFileForm, UploadFilesResponse,
+    ContactImportResponse, ContactTypeWeb, ContactWeb, ContactsResponse, FromWeb, IntoWeb,
+    SuccessResponse, TempFileWrapper, UploadFileForm, UploadFilesResponse,
 };
 use bcr_ebill_api::data::{
     OptionalPostalAddress, PostalAddress,
-    contact::{Contact, ContactType},
+    contact::{Contact, ContactTrustLevel, ContactType, ContactVerificationStatus},
 };
 use bcr_ebill_api::service::{self, ServiceContext};
 use bcr_ebill_api::util;
@@ -20,6 +24,7 @@ use rocket::{State, delete, get, post, put};
 #[get("/file/<id>/<file_name>")]
 pub async fn get_file(
     _identity: IdentityCheck,
+    _scope: Scoped<ContactsRead>,
     state: &State<ServiceContext>,
     id: &str,
     file_name: &str,
@@ -53,6 +58,7 @@ pub async fn get_file(
 #[post("/upload_file", data = "<file_upload_form>")]
 pub async fn upload_file(
     _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
     state: &State<ServiceContext>,
     file_upload_form: Form<UploadFileForm<'_>>,
 ) -> Result<Json<UploadFilesResponse>> {
@@ -76,6 +82,7 @@ pub async fn upload_file(
 #[get("/list")]
 pub async fn return_contacts(
     _identity: IdentityCheck,
+    _scope: Scoped<ContactsRead>,
     state: &State<ServiceContext>,
 ) -> Result<Json<ContactsResponse<ContactWeb>>> {
     let contacts: Vec<Contact> = state.contact_service.get_contacts().await?;
@@ -87,6 +94,7 @@ pub async fn return_contacts(
 #[get("/detail/<node_id>")]
 pub async fn return_contact(
     _identity: IdentityCheck,
+    _scope: Scoped<ContactsRead>,
     state: &State<ServiceContext>,
     node_id: &str,
 ) -> Result<Json<ContactWeb>> {
@@ -97,6 +105,7 @@ pub async fn return_contact(
 #[delete("/remove/<node_id>")]
 pub async fn remove_contact(
     _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
     state: &State<ServiceContext>,
     node_id: &str,
 ) -> Result<Json<SuccessResponse>> {
@@ -107,6 +116,7 @@ pub async fn remove_contact(
 #[post("/create", format = "json", data = "<new_contact_payload>")]
 pub async fn new_contact(
     _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
     state: &State<ServiceContext>,
     new_contact_payload: Json<NewContactPayload>,
 ) -> Result<Json<ContactWeb>> {
@@ -134,9 +144,87 @@ pub async fn new_contact(
     Ok(Json(contact.into_web()))
 }
 
+#[post("/create_from_profile", format = "json", data = "<payload>")]
+pub async fn new_contact_from_profile(
+    _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
+    state: &State<ServiceContext>,
+    payload: Json<NewContactFromProfilePayload>,
+) -> Result<Json<ContactWeb>> {
+    let payload = payload.0;
+    let contact = state
+        .contact_service
+        .add_contact_from_profile(&payload.node_id, payload.email)
+        .await?;
+    Ok(Json(contact.into_web()))
+}
+
+#[post("/import_card", format = "json", data = "<payload>")]
+pub async fn new_contact_from_card(
+    _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
+    state: &State<ServiceContext>,
+    payload: Json<NewContactFromCardPayload>,
+) -> Result<Json<ContactWeb>> {
+    let contact = state
+        .contact_service
+        .add_contact_from_card(&payload.uri)
+        .await?;
+    Ok(Json(contact.into_web()))
+}
+
+#[get("/export/vcard")]
+pub async fn export_vcards(
+    _identity: IdentityCheck,
+    _scope: Scoped<ContactsRead>,
+    state: &State<ServiceContext>,
+) -> Result<BinaryFileResponse> {
+    let vcards = state.contact_service.export_vcards().await?;
+    Ok(BinaryFileResponse {
+        data: vcards.into_bytes(),
+        name: format!(
+            "contacts_{}.vcf",
+            util::date::format_date_string(util::date::now())
+        ),
+        content_type: ContentType::new("text", "vcard"),
+    })
+}
+
+#[post("/import/vcard", data = "<file_upload_form>")]
+pub async fn import_vcards(
+    _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
+    state: &State<ServiceContext>,
+    file_upload_form: Form<UploadFileForm<'_>>,
+) -> Result<Json<ContactImportResponse>> {
+    let upload_file_handler: &dyn UploadFileHandler =
+        &TempFileWrapper(&file_upload_form.file) as &dyn UploadFileHandler;
+    let bytes = upload_file_handler
+        .get_contents()
+        .await
+        .map_err(service::Error::Io)?;
+    let vcards = String::from_utf8(bytes).map_err(|_| {
+        service::Error::Validation(String::from("The vCard file has to be UTF-8 encoded"))
+    })?;
+    let result = state.contact_service.import_vcards(&vcards).await?;
+    Ok(Json(result.into_web()))
+}
+
+#[put("/refresh/<node_id>")]
+pub async fn refresh_contact(
+    _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
+    state: &State<ServiceContext>,
+    node_id: &str,
+) -> Result<Json<ContactWeb>> {
+    let contact = state.contact_service.refresh_contact(node_id).await?;
+    Ok(Json(contact.into_web()))
+}
+
 #[put("/edit", format = "json", data = "<edit_contact_payload>")]
 pub async fn edit_contact(
     _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
     state: &State<ServiceContext>,
     edit_contact_payload: Json<EditContactPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -158,3 +246,23 @@ pub async fn edit_contact(
         .await?;
     Ok(Json(SuccessResponse::new()))
 }
+
+#[put("/verification", format = "json", data = "<verification_payload>")]
+pub async fn set_verification(
+    _identity: IdentityCheck,
+    _scope: Scoped<ContactsWrite>,
+    state: &State<ServiceContext>,
+    verification_payload: Json<ContactVerificationPayload>,
+) -> Result<Json<ContactWeb>> {
+    let payload = verification_payload.0;
+    let contact = state
+        .contact_service
+        .set_verification(
+            &payload.node_id,
+            ContactVerificationStatus::from_web(payload.verification_status),
+            ContactTrustLevel::from_web(payload.trust_level),
+            payload.evidence_file_upload_id,
+        )
+        .await?;
+    Ok(Json(contact.into_web()))
+}
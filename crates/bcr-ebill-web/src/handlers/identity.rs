@@ -1,10 +1,11 @@
 use std::env;
 
 use super::Result;
-use super::middleware::IdentityCheck;
+use super::middleware::{Admin, IdentityCheck, IdentityRead, Reauthenticated, Scoped};
 use crate::data::{
-    ChangeIdentityPayload, FromWeb, IdentityWeb, IntoWeb, NewIdentityPayload, SeedPhrase,
-    SuccessResponse, SwitchIdentity, TempFileWrapper, UploadFileForm, UploadFilesResponse,
+    ChangeIdentityPayload, ContactCardWeb, FromWeb, IdentityWeb, IntoWeb, NewIdentityPayload,
+    SeedPhrase, SuccessResponse, SwitchIdentity, TempFileWrapper, UploadFileForm,
+    UploadFilesResponse,
 };
 use bcr_ebill_api::data::{OptionalPostalAddress, identity::IdentityType};
 use bcr_ebill_api::service::{Error, ServiceContext};
@@ -21,6 +22,7 @@ use rocket::{Response, Shutdown, State, get, post, put};
 #[get("/file/<file_name>")]
 pub async fn get_file(
     _identity: IdentityCheck,
+    _scope: Scoped<IdentityRead>,
     state: &State<ServiceContext>,
     file_name: &str,
 ) -> Result<(ContentType, Vec<u8>)> {
@@ -47,6 +49,7 @@ pub async fn get_file(
 
 #[post("/upload_file", data = "<file_upload_form>")]
 pub async fn upload_file(
+    _scope: Scoped<Admin>,
     state: &State<ServiceContext>,
     file_upload_form: Form<UploadFileForm<'_>>,
 ) -> Result<Json<UploadFilesResponse>> {
@@ -76,7 +79,10 @@ pub async fn upload_file(
     ),
 )]
 #[get("/detail")]
-pub async fn return_identity(state: &State<ServiceContext>) -> Result<Json<IdentityWeb>> {
+pub async fn return_identity(
+    _scope: Scoped<IdentityRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<IdentityWeb>> {
     let my_identity = if !state.identity_service.identity_exists().await {
         return Err(Error::NotFound.into());
     } else {
@@ -86,6 +92,46 @@ pub async fn return_identity(state: &State<ServiceContext>) -> Result<Json<Ident
     Ok(Json(my_identity))
 }
 
+#[utoipa::path(
+    tag = "Identity",
+    path = "/api/identity/contact_card",
+    description = "Returns the contact card of the identity, signed with its keys, as bcr: URI",
+    responses(
+        (status = 200, description = "The signed contact card", body = ContactCardWeb)
+    ),
+)]
+#[get("/contact_card")]
+pub async fn contact_card(
+    _identity: IdentityCheck,
+    _scope: Scoped<IdentityRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<ContactCardWeb>> {
+    let card = state.identity_service.get_contact_card().await?;
+    Ok(Json(ContactCardWeb { uri: card.to_uri() }))
+}
+
+#[utoipa::path(
+    tag = "Identity",
+    path = "/api/identity/contact_card/qr/{format}",
+    description = "Returns the signed contact card of the identity as QR code image",
+    params(
+        ("format" = String, Path, description = "The image format, png or svg")
+    ),
+    responses(
+        (status = 200, description = "The QR code image of the contact card")
+    ),
+)]
+#[get("/contact_card/qr/<format>")]
+pub async fn contact_card_qr_code(
+    _identity: IdentityCheck,
+    _scope: Scoped<IdentityRead>,
+    state: &State<ServiceContext>,
+    format: &str,
+) -> Result<(ContentType, Vec<u8>)> {
+    let card = state.identity_service.get_contact_card().await?;
+    super::contact_card_qr_code(&card.to_uri(), format)
+}
+
 #[utoipa::path(
     tag = "Identity",
     path = "/api/identity/create",
@@ -97,6 +143,7 @@ pub async fn return_identity(state: &State<ServiceContext>) -> Result<Json<Ident
 )]
 #[post("/create", format = "json", data = "<identity_payload>")]
 pub async fn create_identity(
+    _scope: Scoped<Admin>,
     state: &State<ServiceContext>,
     identity_payload: Json<NewIdentityPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -136,6 +183,7 @@ pub async fn create_identity(
 #[put("/change", format = "json", data = "<identity_payload>")]
 pub async fn change_identity(
     _identity: IdentityCheck,
+    _scope: Scoped<Admin>,
     state: &State<ServiceContext>,
     identity_payload: Json<ChangeIdentityPayload>,
 ) -> Result<Json<SuccessResponse>> {
@@ -180,7 +228,10 @@ pub async fn change_identity(
     )
 )]
 #[get("/active")]
-pub async fn active(state: &State<ServiceContext>) -> Result<Json<SwitchIdentity>> {
+pub async fn active(
+    _scope: Scoped<IdentityRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<SwitchIdentity>> {
     let current_identity_state = state.get_current_identity().await;
     let (node_id, t) = match current_identity_state.company {
         None => (current_identity_state.personal, IdentityType::Person),
@@ -203,6 +254,7 @@ pub async fn active(state: &State<ServiceContext>) -> Result<Json<SwitchIdentity
 )]
 #[put("/switch", format = "json", data = "<switch_identity_payload>")]
 pub async fn switch(
+    _scope: Scoped<Admin>,
     state: &State<ServiceContext>,
     switch_identity_payload: Json<SwitchIdentity>,
 ) -> Result<Json<SuccessResponse>> {
@@ -243,7 +295,10 @@ pub async fn switch(
     )
 )]
 #[get("/seed/backup")]
-pub async fn get_seed_phrase(state: &State<ServiceContext>) -> Result<Json<SeedPhrase>> {
+pub async fn get_seed_phrase(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+) -> Result<Json<SeedPhrase>> {
     let seed_phrase = state.identity_service.get_seedphrase().await?;
     Ok(Json(SeedPhrase { seed_phrase }))
 }
@@ -259,6 +314,7 @@ pub async fn get_seed_phrase(state: &State<ServiceContext>) -> Result<Json<SeedP
 )]
 #[put("/seed/recover", format = "json", data = "<payload>")]
 pub async fn recover_from_seed_phrase(
+    _reauth: Reauthenticated,
     state: &State<ServiceContext>,
     payload: Json<SeedPhrase>,
 ) -> Result<Json<SuccessResponse>> {
@@ -278,12 +334,16 @@ pub async fn recover_from_seed_phrase(
     ),
 )]
 #[get("/backup")]
-pub async fn backup_identity(state: &State<ServiceContext>) -> Result<BinaryFileResponse> {
+pub async fn backup_identity(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+) -> Result<BinaryFileResponse> {
     let file_name = format!("bitcredit_backup_{}.ecies", format_date_string(now()));
     let bytes = state.backup_service.backup().await?;
     Ok(BinaryFileResponse {
         data: bytes,
         name: file_name.to_string(),
+        content_type: ContentType::Binary,
     })
 }
 
@@ -298,6 +358,7 @@ pub async fn backup_identity(state: &State<ServiceContext>) -> Result<BinaryFile
 )]
 #[post("/restore", data = "<data>")]
 pub async fn restore_identity(
+    _reauth: Reauthenticated,
     state: &State<ServiceContext>,
     shutdown: Shutdown,
     mut data: Form<UploadFileForm<'_>>,
@@ -317,8 +378,9 @@ pub async fn restore_identity(
 
 /// Just a wrapper struct to allow setting a content disposition header
 pub struct BinaryFileResponse {
-    data: Vec<u8>,
-    name: String,
+    pub data: Vec<u8>,
+    pub name: String,
+    pub content_type: ContentType,
 }
 
 /// Needed to respond with a binary file that can set a content disposition header
@@ -326,7 +388,7 @@ pub struct BinaryFileResponse {
 impl Responder<'_, 'static> for BinaryFileResponse {
     fn respond_to(self, _: &rocket::Request<'_>) -> rocket::response::Result<'static> {
         Response::build()
-            .header(ContentType::Binary)
+            .header(self.content_type)
             .raw_header(
                 "Content-Disposition",
                 format!(r#"attachment; filename="{}""#, self.name),
@@ -1,6 +1,12 @@
+use crate::auth::{AuthContext, SESSION_COOKIE, SessionState};
+use bcr_ebill_api::data::api_token::{API_TOKEN_PREFIX, ApiToken, ApiTokenScope};
 use bcr_ebill_api::service::ServiceContext;
+use bcr_ebill_api::util::date::now;
+use log::error;
 use rocket::http::Status;
+use rocket::outcome::try_outcome;
 use rocket::request::{FromRequest, Outcome, Request};
+use std::marker::PhantomData;
 
 pub struct IdentityCheck;
 
@@ -9,6 +15,7 @@ impl<'r> FromRequest<'r> for IdentityCheck {
     type Error = Status;
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        try_outcome!(request.guard::<Authenticated>().await);
         let path = request.uri().path();
 
         if path.starts_with("/identity") {
@@ -26,3 +33,267 @@ impl<'r> FromRequest<'r> for IdentityCheck {
         Outcome::Success(IdentityCheck)
     }
 }
+
+/// How a request is authenticated, resolved once per request
+enum RequestAuth {
+    /// No password is configured and no API token was sent
+    Disabled,
+    /// A session started with the password
+    Session(SessionState),
+    /// A valid API token, which only allows what its scopes allow
+    ApiToken(ApiToken),
+    Unauthenticated,
+}
+
+/// Checks, that the request has a valid session, either via the session cookie, or as bearer
+/// token in the `Authorization` header, or a valid API token. Always succeeds, if authentication
+/// is disabled
+pub struct Authenticated;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Authenticated {
+    type Error = Status;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request_auth(request).await {
+            RequestAuth::Unauthenticated => {
+                Outcome::Error((Status::Unauthorized, Status::Unauthorized))
+            }
+            _ => Outcome::Success(Authenticated),
+        }
+    }
+}
+
+/// Checks, that the password was entered recently for the session of the request, which is
+/// required for sensitive endpoints, like the seed phrase, or the bitcoin keys. API tokens are
+/// never allowed, regardless of their scopes
+pub struct Reauthenticated;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Reauthenticated {
+    type Error = Status;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match check_reauthenticated(request_auth(request).await) {
+            Ok(()) => Outcome::Success(Reauthenticated),
+            Err(status) => Outcome::Error((status, status)),
+        }
+    }
+}
+
+fn check_reauthenticated(auth: &RequestAuth) -> Result<(), Status> {
+    match auth {
+        RequestAuth::Disabled | RequestAuth::Session(SessionState::Reauthenticated) => Ok(()),
+        RequestAuth::Session(SessionState::Authenticated) | RequestAuth::ApiToken(_) => {
+            Err(Status::Forbidden)
+        }
+        RequestAuth::Unauthenticated => Err(Status::Unauthorized),
+    }
+}
+
+/// A scope an endpoint requires from API tokens
+pub trait RequiredScope: Send + Sync {
+    const SCOPE: ApiTokenScope;
+}
+
+macro_rules! required_scopes {
+    ($($name:ident => $scope:ident),* $(,)?) => {
+        $(
+            pub struct $name;
+
+            impl RequiredScope for $name {
+                const SCOPE: ApiTokenScope = ApiTokenScope::$scope;
+            }
+        )*
+    };
+}
+
+required_scopes!(
+    BillsRead => BillsRead,
+    BillsWrite => BillsWrite,
+    ContactsRead => ContactsRead,
+    ContactsWrite => ContactsWrite,
+    CompaniesRead => CompaniesRead,
+    CompaniesWrite => CompaniesWrite,
+    IdentityRead => IdentityRead,
+    NotificationsRead => NotificationsRead,
+    NotificationsWrite => NotificationsWrite,
+    Admin => Admin,
+);
+
+/// Checks, that the request is authenticated and, if it uses an API token, that the token has
+/// the scope `S`. Sessions started with the password are allowed everything
+pub struct Scoped<S: RequiredScope>(PhantomData<S>);
+
+#[rocket::async_trait]
+impl<'r, S: RequiredScope> FromRequest<'r> for Scoped<S> {
+    type Error = Status;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request_auth(request).await {
+            RequestAuth::Disabled | RequestAuth::Session(_) => {
+                Outcome::Success(Scoped(PhantomData))
+            }
+            RequestAuth::ApiToken(token) if token.has_scope(S::SCOPE) => {
+                Outcome::Success(Scoped(PhantomData))
+            }
+            RequestAuth::ApiToken(_) => Outcome::Error((Status::Forbidden, Status::Forbidden)),
+            RequestAuth::Unauthenticated => {
+                Outcome::Error((Status::Unauthorized, Status::Unauthorized))
+            }
+        }
+    }
+}
+
+/// The scopes granted to an authenticated request, for endpoints that return different kinds of
+/// data and have to leave out what an API token isn't allowed to read. Sessions started with the
+/// password are granted every scope
+pub struct GrantedScopes(Option<ApiToken>);
+
+impl GrantedScopes {
+    pub fn has(&self, scope: ApiTokenScope) -> bool {
+        match self.0 {
+            None => true,
+            Some(ref token) => token.has_scope(scope),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for GrantedScopes {
+    type Error = Status;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match granted_scopes(request_auth(request).await) {
+            Ok(scopes) => Outcome::Success(scopes),
+            Err(status) => Outcome::Error((status, status)),
+        }
+    }
+}
+
+fn granted_scopes(auth: &RequestAuth) -> Result<GrantedScopes, Status> {
+    match auth {
+        RequestAuth::Disabled | RequestAuth::Session(_) => Ok(GrantedScopes(None)),
+        RequestAuth::ApiToken(token) => Ok(GrantedScopes(Some(token.clone()))),
+        RequestAuth::Unauthenticated => Err(Status::Unauthorized),
+    }
+}
+
+/// Returns the token of the request's session from the cookie, or the bearer token
+pub fn session_token(request: &Request<'_>) -> Option<String> {
+    if let Some(cookie) = request.cookies().get(SESSION_COOKIE) {
+        return Some(cookie.value().to_owned());
+    }
+    bearer_token(request).filter(|token| !token.starts_with(API_TOKEN_PREFIX))
+}
+
+fn bearer_token(request: &Request<'_>) -> Option<String> {
+    request
+        .headers()
+        .get_one("Authorization")
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_owned())
+}
+
+async fn request_auth<'r>(request: &'r Request<'_>) -> &'r RequestAuth {
+    request
+        .local_cache_async(async { resolve_request_auth(request).await })
+        .await
+}
+
+async fn resolve_request_auth(request: &Request<'_>) -> RequestAuth {
+    let timestamp = now().timestamp() as u64;
+    if let Some(token) = bearer_token(request).filter(|t| t.starts_with(API_TOKEN_PREFIX)) {
+        let context = match request.rocket().state::<ServiceContext>() {
+            Some(context) => context,
+            None => return RequestAuth::Unauthenticated,
+        };
+        return match context
+            .api_token_service
+            .authenticate(&token, timestamp)
+            .await
+        {
+            Ok(Some(api_token)) => RequestAuth::ApiToken(api_token),
+            Ok(None) => RequestAuth::Unauthenticated,
+            Err(e) => {
+                error!("Could not check API token: {e}");
+                RequestAuth::Unauthenticated
+            }
+        };
+    }
+
+    let auth = match request.rocket().state::<AuthContext>() {
+        Some(auth) => auth,
+        None => return RequestAuth::Unauthenticated,
+    };
+    if !auth.is_enabled() {
+        return RequestAuth::Disabled;
+    }
+    match session_token(request).and_then(|token| auth.validate_session(&token, timestamp)) {
+        Some(state) => RequestAuth::Session(state),
+        None => RequestAuth::Unauthenticated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_token(scopes: Vec<ApiTokenScope>) -> RequestAuth {
+        RequestAuth::ApiToken(ApiToken {
+            id: "id".to_string(),
+            name: "erp".to_string(),
+            scopes,
+            created_at: 1731593928,
+            last_used_at: None,
+        })
+    }
+
+    #[test]
+    fn reauthenticated_rejects_all_api_tokens() {
+        assert_eq!(
+            check_reauthenticated(&api_token(vec![ApiTokenScope::Admin])),
+            Err(Status::Forbidden)
+        );
+        assert_eq!(
+            check_reauthenticated(&api_token(ApiTokenScope::ALL.to_vec())),
+            Err(Status::Forbidden)
+        );
+        assert_eq!(
+            check_reauthenticated(&api_token(vec![ApiTokenScope::BillsRead])),
+            Err(Status::Forbidden)
+        );
+    }
+
+    #[test]
+    fn reauthenticated_sessions() {
+        assert_eq!(check_reauthenticated(&RequestAuth::Disabled), Ok(()));
+        assert_eq!(
+            check_reauthenticated(&RequestAuth::Session(SessionState::Reauthenticated)),
+            Ok(())
+        );
+        assert_eq!(
+            check_reauthenticated(&RequestAuth::Session(SessionState::Authenticated)),
+            Err(Status::Forbidden)
+        );
+        assert_eq!(
+            check_reauthenticated(&RequestAuth::Unauthenticated),
+            Err(Status::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn granted_scopes_of_tokens_and_sessions() {
+        let scopes = granted_scopes(&api_token(vec![ApiTokenScope::ContactsRead])).unwrap();
+        assert!(scopes.has(ApiTokenScope::ContactsRead));
+        assert!(!scopes.has(ApiTokenScope::BillsRead));
+        assert!(!scopes.has(ApiTokenScope::CompaniesRead));
+
+        let scopes = granted_scopes(&RequestAuth::Session(SessionState::Authenticated)).unwrap();
+        assert!(scopes.has(ApiTokenScope::BillsRead));
+        assert!(scopes.has(ApiTokenScope::CompaniesRead));
+        assert!(
+            granted_scopes(&RequestAuth::Unauthenticated).is_err_and(|s| s == Status::Unauthorized)
+        );
+    }
+}
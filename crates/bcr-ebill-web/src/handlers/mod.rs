@@ -6,12 +6,13 @@ use crate::data::{
 use crate::router::ErrorResponse;
 use crate::{CONFIG, constants::VALID_CURRENCIES};
 use bcr_ebill_api::{
-    data::GeneralSearchFilterItemType,
+    data::{GeneralSearchFilterItemType, api_token::ApiTokenScope},
     service::{Error, ServiceContext, bill_service},
-    util::file::detect_content_type_for_bytes,
+    util::{file::detect_content_type_for_bytes, qr_code},
 };
 use bill::get_current_identity_node_id;
 use log::error;
+use middleware::{Authenticated, BillsRead, GrantedScopes, Reauthenticated, Scoped};
 use rocket::Response;
 use rocket::{Shutdown, State, fs::NamedFile, get, http::ContentType, post, serde::json::Json};
 use rocket::{http::Status, response::Responder};
@@ -20,13 +21,16 @@ use std::path::{Path, PathBuf};
 
 pub type Result<T> = std::result::Result<T, crate::error::Error>;
 
+pub mod auth;
 pub mod bill;
+pub mod bill_templates;
 pub mod company;
 pub mod contacts;
 pub mod identity;
 pub mod middleware;
 pub mod notifications;
 pub mod quotes;
+pub mod webhooks;
 
 // Lowest prio, fall back to index.html if nothing matches
 #[get("/<_..>", rank = 10)]
@@ -56,6 +60,7 @@ pub async fn status() -> Result<Json<StatusResponse>> {
 
 #[get("/")]
 pub async fn exit(
+    _reauth: Reauthenticated,
     shutdown: Shutdown,
     state: &State<ServiceContext>,
 ) -> Result<Json<SuccessResponse>> {
@@ -66,7 +71,10 @@ pub async fn exit(
 }
 
 #[get("/")]
-pub async fn currencies(_state: &State<ServiceContext>) -> Result<Json<CurrenciesResponse>> {
+pub async fn currencies(
+    _auth: Authenticated,
+    _state: &State<ServiceContext>,
+) -> Result<Json<CurrenciesResponse>> {
     Ok(Json(CurrenciesResponse {
         currencies: VALID_CURRENCIES
             .iter()
@@ -79,6 +87,7 @@ pub async fn currencies(_state: &State<ServiceContext>) -> Result<Json<Currencie
 
 #[get("/<file_upload_id>")]
 pub async fn get_temp_file(
+    _auth: Authenticated,
     state: &State<ServiceContext>,
     file_upload_id: &str,
 ) -> Result<(ContentType, Vec<u8>)> {
@@ -108,6 +117,7 @@ pub async fn get_temp_file(
 
 #[get("/?<currency>")]
 pub async fn overview(
+    _scope: Scoped<BillsRead>,
     currency: &str,
     state: &State<ServiceContext>,
 ) -> Result<Json<OverviewResponse>> {
@@ -140,13 +150,14 @@ pub async fn overview(
 #[utoipa::path(
     tag = "General Search",
     path = "/search",
-    description = "Search bills, contacts and companies",
+    description = "Search bills, contacts and companies. API tokens only find the kinds of items their scopes allow reading",
     responses(
         (status = 200, description = "Search Result", body = GeneralSearchResponse)
     )
 )]
 #[post("/", format = "json", data = "<search_filter>")]
 pub async fn search(
+    scopes: GrantedScopes,
     state: &State<ServiceContext>,
     search_filter: Json<GeneralSearchFilterPayload>,
 ) -> Result<Json<GeneralSearchResponse>> {
@@ -156,6 +167,7 @@ pub async fn search(
         .item_types
         .into_iter()
         .map(GeneralSearchFilterItemType::from_web)
+        .filter(|item_type| scopes.has(read_scope(*item_type)))
         .collect();
     let result = state
         .search_service
@@ -170,12 +182,43 @@ pub async fn search(
     Ok(Json(result.into_web()))
 }
 
+/// The scope an API token needs to find items of the given type
+fn read_scope(item_type: GeneralSearchFilterItemType) -> ApiTokenScope {
+    match item_type {
+        GeneralSearchFilterItemType::Bill => ApiTokenScope::BillsRead,
+        GeneralSearchFilterItemType::Contact => ApiTokenScope::ContactsRead,
+        GeneralSearchFilterItemType::Company => ApiTokenScope::CompaniesRead,
+    }
+}
+
 impl<'r, 'o: 'r> Responder<'r, 'o> for crate::error::Error {
     fn respond_to(self, req: &rocket::Request) -> rocket::response::Result<'o> {
         match self {
             crate::error::Error::Service(e) => ServiceError(e).respond_to(req),
             crate::error::Error::BillService(e) => BillServiceError(e).respond_to(req),
             crate::error::Error::NotificationService(e) => ServiceError(e.into()).respond_to(req),
+            crate::error::Error::Unauthorized(msg) => {
+                let body = ErrorResponse::new("unauthorized", msg, 401).to_json_string();
+                Response::build()
+                    .status(Status::Unauthorized)
+                    .header(ContentType::JSON)
+                    .sized_body(body.len(), Cursor::new(body))
+                    .ok()
+            }
+            crate::error::Error::TooManyLoginAttempts(retry_after_seconds) => {
+                let body = ErrorResponse::new(
+                    "too_many_requests",
+                    format!("Too many wrong passwords, retry in {retry_after_seconds} seconds"),
+                    429,
+                )
+                .to_json_string();
+                Response::build()
+                    .status(Status::TooManyRequests)
+                    .header(ContentType::JSON)
+                    .raw_header("Retry-After", retry_after_seconds.to_string())
+                    .sized_body(body.len(), Cursor::new(body))
+                    .ok()
+            }
         }
     }
 }
@@ -217,6 +260,10 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for ServiceError {
                 error!("{e}");
                 Status::InternalServerError.respond_to(req)
             }
+            Error::Json(e) => {
+                error!("{e}");
+                Status::InternalServerError.respond_to(req)
+            }
             Error::CryptoUtil(e) => {
                 error!("{e}");
                 Status::InternalServerError.respond_to(req)
@@ -266,6 +313,9 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for BillServiceError {
             | bill_service::Error::RequestAlreadyRejected
             | bill_service::Error::CallerIsNotHolder
             | bill_service::Error::NoFileForFileUploadId
+            | bill_service::Error::CounterpartyNotVerified(_)
+            | bill_service::Error::SearchCursorSortMismatch
+            | bill_service::Error::BatchFile(_)
             | bill_service::Error::InvalidOperation => {
                 let body =
                     ErrorResponse::new("bad_request", self.0.to_string(), 400).to_json_string();
@@ -308,10 +358,27 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for BillServiceError {
                 error!("{e}");
                 Status::InternalServerError.respond_to(req)
             }
+            bill_service::Error::Export(e) => {
+                error!("{e}");
+                Status::InternalServerError.respond_to(req)
+            }
+            bill_service::Error::Pdf(e) => {
+                error!("{e}");
+                Status::InternalServerError.respond_to(req)
+            }
         }
     }
 }
 
+/// Renders the contact card URI as QR code image in the given format, `png` or `svg`
+fn contact_card_qr_code(uri: &str, format: &str) -> Result<(ContentType, Vec<u8>)> {
+    match format {
+        "png" => Ok((ContentType::PNG, qr_code::to_png(uri)?)),
+        "svg" => Ok((ContentType::SVG, qr_code::to_svg(uri)?.into_bytes())),
+        _ => Err(Error::Validation(format!("Unsupported QR code format: {format}")).into()),
+    }
+}
+
 fn build_validation_response<'o>(msg: String) -> rocket::response::Result<'o> {
     let err_resp = ErrorResponse::new("validation_error", msg, 400);
     let body = err_resp.to_json_string();
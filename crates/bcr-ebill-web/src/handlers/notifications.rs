@@ -1,11 +1,16 @@
 use super::Result;
-use crate::data::{IntoWeb, NotificationWeb, SuccessResponse};
+use super::middleware::{Admin, NotificationsRead, NotificationsWrite, Reauthenticated, Scoped};
+use crate::data::{
+    EmailPreferencePayload, EmailPreferenceWeb, IntoWeb, NostrOutboxResponse, NotificationWeb,
+    QuarantinedEventsResponse, RelaysResponse, SuccessResponse,
+};
 use bcr_ebill_api::NotificationFilter;
 use bcr_ebill_api::data::notification::Notification;
-use bcr_ebill_api::service::ServiceContext;
+use bcr_ebill_api::service::{self, ServiceContext};
+use bcr_ebill_api::util::date::now;
 use rocket::response::stream::{Event, EventStream};
 use rocket::serde::json::Json;
-use rocket::{State, get, post};
+use rocket::{State, delete, get, post, put};
 use rocket_ws::{Message, Stream, WebSocket};
 use serde_json::Value;
 
@@ -25,6 +30,7 @@ use serde_json::Value;
 )]
 #[get("/notifications?<active>&<reference_id>&<notification_type>&<limit>&<offset>")]
 pub async fn list_notifications(
+    _scope: Scoped<NotificationsRead>,
     state: &State<ServiceContext>,
     active: Option<bool>,
     reference_id: Option<String>,
@@ -59,6 +65,7 @@ pub async fn list_notifications(
 )]
 #[post("/notifications/<notification_id>/done")]
 pub async fn mark_notification_done(
+    _scope: Scoped<NotificationsWrite>,
     state: &State<ServiceContext>,
     notification_id: &str,
 ) -> Result<Json<SuccessResponse>> {
@@ -69,6 +76,181 @@ pub async fn mark_notification_done(
     Ok(Json(SuccessResponse::new()))
 }
 
+#[utoipa::path(
+    tag = "Notifications",
+    path = "/api/notifications/email_preferences/{node_id}",
+    description = "Returns whether the events received for the local identity, or company with the given node id are also sent to its email address",
+    params(
+        ("node_id" = String, description = "The node id of the local identity, or one of its companies")
+    ),
+    responses(
+        (status = 200, description = "The email preference", body = EmailPreferenceWeb)
+    )
+)]
+#[get("/notifications/email_preferences/<node_id>")]
+pub async fn get_email_preference(
+    _scope: Scoped<NotificationsRead>,
+    state: &State<ServiceContext>,
+    node_id: &str,
+) -> Result<Json<EmailPreferenceWeb>> {
+    let preference = state
+        .notification_service
+        .get_email_preference(node_id)
+        .await?;
+    Ok(Json(preference.into_web()))
+}
+
+#[utoipa::path(
+    tag = "Notifications",
+    path = "/api/notifications/email_preferences/{node_id}",
+    description = "Opts the local identity, or company with the given node id in or out of receiving the events received for it via email. Emails are only sent, if an email transport is configured and the identity, or company has an email address",
+    params(
+        ("node_id" = String, description = "The node id of the local identity, or one of its companies")
+    ),
+    request_body(description = "Whether to send emails", content((EmailPreferencePayload))),
+    responses(
+        (status = 200, description = "The updated email preference", body = EmailPreferenceWeb),
+        (status = 400, description = "The node id is not the one of the local identity, or one of its companies")
+    )
+)]
+#[put(
+    "/notifications/email_preferences/<node_id>",
+    format = "json",
+    data = "<email_preference_payload>"
+)]
+pub async fn set_email_preference(
+    _scope: Scoped<Admin>,
+    state: &State<ServiceContext>,
+    node_id: &str,
+    email_preference_payload: Json<EmailPreferencePayload>,
+) -> Result<Json<EmailPreferenceWeb>> {
+    // only the holder of an identity can decide to receive emails, so other nodes can't be opted in
+    let identity = state.identity_service.get_identity().await?;
+    let is_local = identity.node_id == node_id
+        || state
+            .company_service
+            .get_list_of_companies()
+            .await?
+            .iter()
+            .any(|company| company.id == node_id);
+    if !is_local {
+        return Err(service::Error::Validation(String::from(
+            "Emails can only be enabled for the local identity and its companies",
+        ))
+        .into());
+    }
+    let preference = state
+        .notification_service
+        .set_email_preference(
+            node_id,
+            email_preference_payload.enabled,
+            now().timestamp() as u64,
+        )
+        .await?;
+    Ok(Json(preference.into_web()))
+}
+
+#[utoipa::path(
+    tag = "Notifications",
+    path = "/api/notifications/relays",
+    description = "Returns the health, latency and last successful publish of the Nostr relays events are sent to",
+    responses(
+        (status = 200, description = "The relays", body = RelaysResponse)
+    )
+)]
+#[get("/notifications/relays")]
+pub async fn list_relays(
+    _scope: Scoped<NotificationsRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<RelaysResponse>> {
+    let relays = state.nostr_consumer.relay_stats();
+    Ok(Json(RelaysResponse {
+        relays: relays.into_iter().map(|r| r.into_web()).collect(),
+    }))
+}
+
+#[utoipa::path(
+    tag = "Notifications",
+    path = "/api/notifications/quarantine",
+    description = "Returns the incoming Nostr events, whose senders weren't authorized to send them, newest first",
+    responses(
+        (status = 200, description = "The quarantined events", body = QuarantinedEventsResponse)
+    )
+)]
+#[get("/notifications/quarantine")]
+pub async fn list_quarantined_events(
+    _scope: Scoped<NotificationsRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<QuarantinedEventsResponse>> {
+    let events = state.quarantine_service.list_events().await?;
+    Ok(Json(QuarantinedEventsResponse {
+        events: events.into_iter().map(|e| e.into_web()).collect(),
+    }))
+}
+
+#[utoipa::path(
+    tag = "Notifications",
+    path = "/api/notifications/quarantine/{id}/release",
+    description = "Handles the quarantined event with the given id, as if its sender was authorized to send it, and removes it from the quarantine. Requires a re-authenticated session, API tokens are not allowed",
+    params(
+        ("id" = String, description = "The id of the quarantined event")
+    ),
+    responses(
+        (status = 200, description = "The event was released"),
+        (status = 404, description = "There is no quarantined event with the given id")
+    )
+)]
+#[post("/notifications/quarantine/<id>/release")]
+pub async fn release_quarantined_event(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<SuccessResponse>> {
+    state.quarantine_service.release_event(id).await?;
+    Ok(Json(SuccessResponse::new()))
+}
+
+#[utoipa::path(
+    tag = "Notifications",
+    path = "/api/notifications/quarantine/{id}",
+    description = "Discards the quarantined event with the given id without handling it. Requires a re-authenticated session, API tokens are not allowed",
+    params(
+        ("id" = String, description = "The id of the quarantined event")
+    ),
+    responses(
+        (status = 200, description = "The event was discarded"),
+        (status = 404, description = "There is no quarantined event with the given id")
+    )
+)]
+#[delete("/notifications/quarantine/<id>")]
+pub async fn discard_quarantined_event(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<SuccessResponse>> {
+    state.quarantine_service.discard_event(id).await?;
+    Ok(Json(SuccessResponse::new()))
+}
+
+#[utoipa::path(
+    tag = "Notifications",
+    path = "/api/notifications/outbox",
+    description = "Returns the outgoing Nostr events, which weren't confirmed by a relay yet, or which were given up after too many failed attempts, newest first",
+    responses(
+        (status = 200, description = "The pending and failed outgoing events", body = NostrOutboxResponse)
+    )
+)]
+#[get("/notifications/outbox")]
+pub async fn list_outbox(
+    _scope: Scoped<NotificationsRead>,
+    state: &State<ServiceContext>,
+) -> Result<Json<NostrOutboxResponse>> {
+    let entries = state.nostr_outbox_service.list_unsent().await?;
+    Ok(Json(NostrOutboxResponse {
+        entries: entries.into_iter().map(|e| e.into_web()).collect(),
+    }))
+}
+
 #[utoipa::path(
     tag = "Push notifications",
     description = "Subscribe to push notifications via websocket",
@@ -77,7 +259,11 @@ pub async fn mark_notification_done(
     )
 )]
 #[get("/subscribe/websocket")]
-pub fn websocket(state: &State<ServiceContext>, _ws: WebSocket) -> Stream!['_] {
+pub fn websocket(
+    _scope: Scoped<NotificationsRead>,
+    state: &State<ServiceContext>,
+    _ws: WebSocket,
+) -> Stream!['_] {
     Stream! { _ws =>
         let mut receiver = state.push_service.subscribe().await;
         loop {
@@ -96,7 +282,10 @@ pub fn websocket(state: &State<ServiceContext>, _ws: WebSocket) -> Stream!['_] {
     )
 )]
 #[get("/subscribe/sse")]
-pub async fn sse(state: &State<ServiceContext>) -> EventStream![Event + '_] {
+pub async fn sse(
+    _scope: Scoped<NotificationsRead>,
+    state: &State<ServiceContext>,
+) -> EventStream![Event + '_] {
     EventStream! {
         let mut receiver = state.push_service.subscribe().await;
         loop {
@@ -109,6 +298,7 @@ pub async fn sse(state: &State<ServiceContext>) -> EventStream![Event + '_] {
 
 #[post("/send_sse", format = "json", data = "<msg>")]
 pub async fn trigger_msg(
+    _scope: Scoped<Admin>,
     state: &State<ServiceContext>,
     msg: Json<Value>,
 ) -> Result<Json<SuccessResponse>> {
@@ -1,5 +1,5 @@
 use super::Result;
-use super::middleware::IdentityCheck;
+use super::middleware::{BillsRead, BillsWrite, IdentityCheck, Scoped};
 use crate::data::BitcreditEbillQuote;
 use bcr_ebill_api::service::{Error, ServiceContext};
 use log::info;
@@ -9,6 +9,7 @@ use rocket::{State, get, put};
 #[get("/return/<id>")]
 pub async fn return_quote(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsRead>,
     _state: &State<ServiceContext>,
     id: String,
 ) -> Result<Json<BitcreditEbillQuote>> {
@@ -19,6 +20,7 @@ pub async fn return_quote(
 #[put("/accept/<id>")]
 pub async fn accept_quote(
     _identity: IdentityCheck,
+    _scope: Scoped<BillsWrite>,
     _state: &State<ServiceContext>,
     id: String,
 ) -> Result<Json<BitcreditEbillQuote>> {
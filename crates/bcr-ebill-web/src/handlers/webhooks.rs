@@ -0,0 +1,108 @@
+use super::Result;
+use super::middleware::{Admin, Reauthenticated, Scoped};
+use crate::data::{
+    FromWeb, IntoWeb, SuccessResponse, WebhookDeliveriesResponse, WebhookPayload, WebhookWeb,
+    WebhooksResponse,
+};
+use bcr_ebill_api::data::notification::EventType;
+use bcr_ebill_api::service::ServiceContext;
+use bcr_ebill_api::util::date::now;
+use rocket::serde::json::Json;
+use rocket::{State, delete, get, post};
+
+#[utoipa::path(
+    tag = "Webhooks",
+    path = "/api/webhooks",
+    description = "Returns all webhook subscriptions without their secrets",
+    responses(
+        (status = 200, description = "The webhooks", body = WebhooksResponse)
+    )
+)]
+#[get("/")]
+pub async fn list(
+    _scope: Scoped<Admin>,
+    state: &State<ServiceContext>,
+) -> Result<Json<WebhooksResponse>> {
+    let webhooks = state.webhook_service.list_webhooks().await?;
+    Ok(Json(WebhooksResponse {
+        webhooks: webhooks.into_iter().map(|w| w.into_web()).collect(),
+    }))
+}
+
+#[utoipa::path(
+    tag = "Webhooks",
+    path = "/api/webhooks",
+    description = "Subscribes a URL to the given event types. Every event is POSTed to it, signed with the secret in the X-Bcr-Signature header. Requires a re-authenticated session, API tokens are not allowed",
+    request_body(description = "The URL, secret and event types of the webhook", content((WebhookPayload))),
+    responses(
+        (status = 200, description = "The created webhook", body = WebhookWeb)
+    )
+)]
+#[post("/", format = "json", data = "<webhook_payload>")]
+pub async fn create(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+    webhook_payload: Json<WebhookPayload>,
+) -> Result<Json<WebhookWeb>> {
+    let webhook_payload = webhook_payload.into_inner();
+    let webhook = state
+        .webhook_service
+        .create_webhook(
+            &webhook_payload.url,
+            &webhook_payload.secret,
+            webhook_payload
+                .event_types
+                .into_iter()
+                .map(EventType::from_web)
+                .collect(),
+            now().timestamp() as u64,
+        )
+        .await?;
+    Ok(Json(webhook.into_web()))
+}
+
+#[utoipa::path(
+    tag = "Webhooks",
+    path = "/api/webhooks/{id}",
+    description = "Removes the webhook with the given id together with its delivery log. Requires a re-authenticated session, API tokens are not allowed",
+    params(
+        ("id" = String, description = "The id of the webhook")
+    ),
+    responses(
+        (status = 200, description = "The webhook was removed"),
+        (status = 404, description = "There is no webhook with the given id")
+    )
+)]
+#[delete("/<id>")]
+pub async fn remove(
+    _reauth: Reauthenticated,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<SuccessResponse>> {
+    state.webhook_service.remove_webhook(id).await?;
+    Ok(Json(SuccessResponse::new()))
+}
+
+#[utoipa::path(
+    tag = "Webhooks",
+    path = "/api/webhooks/{id}/deliveries",
+    description = "Returns the latest deliveries of the webhook with the given id and their attempts, newest first",
+    params(
+        ("id" = String, description = "The id of the webhook")
+    ),
+    responses(
+        (status = 200, description = "The delivery log", body = WebhookDeliveriesResponse),
+        (status = 404, description = "There is no webhook with the given id")
+    )
+)]
+#[get("/<id>/deliveries")]
+pub async fn deliveries(
+    _scope: Scoped<Admin>,
+    state: &State<ServiceContext>,
+    id: &str,
+) -> Result<Json<WebhookDeliveriesResponse>> {
+    let deliveries = state.webhook_service.list_deliveries(id).await?;
+    Ok(Json(WebhookDeliveriesResponse {
+        deliveries: deliveries.into_iter().map(|d| d.into_web()).collect(),
+    }))
+}
@@ -7,6 +7,10 @@ use tokio::{
     time::{interval, sleep},
 };
 
+/// Contacts are refreshed from their profiles less often than the other jobs run, since it
+/// queries the relays for every contact
+const CONTACT_REFRESH_INTERVAL_SECONDS: u64 = 6 * 60 * 60;
+
 pub async fn run(
     service_context: ServiceContext,
     mut shutdown_jobs_client_receiver: broadcast::Receiver<bool>,
@@ -20,12 +24,16 @@ pub async fn run(
     let mut check_interval_tick = interval(Duration::from_secs(
         CONFIG.job_runner_check_interval_seconds,
     ));
+    let mut contact_refresh_tick = interval(Duration::from_secs(CONTACT_REFRESH_INTERVAL_SECONDS));
 
     loop {
         tokio::select! {
                 _ = check_interval_tick.tick() => {
                     run_jobs(&service_context.clone()).await;
                 },
+                _ = contact_refresh_tick.tick() => {
+                    run_refresh_contacts_job(service_context.clone()).await;
+                },
                 _ = shutdown_jobs_client_receiver.recv() => {
                     info!("Shutting down job runner...");
                     break;
@@ -38,7 +46,12 @@ async fn run_jobs(service_context: &ServiceContext) {
     tokio::join!(
         run_check_bill_payment_job(service_context.clone()),
         run_check_bill_offer_to_sell_payment_job(service_context.clone()),
-        run_check_bill_recourse_payment_job(service_context.clone())
+        run_check_bill_recourse_payment_job(service_context.clone()),
+        run_webhook_delivery_job(service_context.clone()),
+        run_nostr_outbox_job(service_context.clone()),
+        run_pending_bill_events_job(service_context.clone()),
+        run_publish_profiles_job(service_context.clone()),
+        run_issue_recurring_bills_job(service_context.clone())
     );
     // explicitly not added to join! because we want to run this job after
     // all payment jobs are done and avoid any concurrency issues.
@@ -77,6 +90,79 @@ async fn run_check_bill_recourse_payment_job(service_context: ServiceContext) {
     info!("Finished running Check Bill Recourse Payment Job");
 }
 
+async fn run_webhook_delivery_job(service_context: ServiceContext) {
+    info!("Running Webhook Delivery Job");
+    let current_time = now().timestamp();
+    if let Err(e) = service_context
+        .webhook_service
+        .retry_due_deliveries(current_time as u64)
+        .await
+    {
+        error!("Error while running Webhook Delivery Job: {e}");
+    }
+    info!("Finished running Webhook Delivery Job");
+}
+
+async fn run_nostr_outbox_job(service_context: ServiceContext) {
+    info!("Running Nostr Outbox Job");
+    let current_time = now().timestamp();
+    if let Err(e) = service_context
+        .nostr_outbox_service
+        .retry_due_sends(current_time as u64)
+        .await
+    {
+        error!("Error while running Nostr Outbox Job: {e}");
+    }
+    info!("Finished running Nostr Outbox Job");
+}
+
+async fn run_pending_bill_events_job(service_context: ServiceContext) {
+    info!("Running Pending Bill Events Job");
+    let current_time = now().timestamp();
+    if let Err(e) = service_context
+        .nostr_consumer
+        .retry_pending_events(current_time as u64)
+        .await
+    {
+        error!("Error while running Pending Bill Events Job: {e}");
+    }
+    info!("Finished running Pending Bill Events Job");
+}
+
+async fn run_publish_profiles_job(service_context: ServiceContext) {
+    info!("Running Publish Profiles Job");
+    let current_time = now().timestamp();
+    if let Err(e) = service_context
+        .profile_service
+        .publish_profiles(current_time as u64)
+        .await
+    {
+        error!("Error while running Publish Profiles Job: {e}");
+    }
+    info!("Finished running Publish Profiles Job");
+}
+
+async fn run_issue_recurring_bills_job(service_context: ServiceContext) {
+    info!("Running Issue Recurring Bills Job");
+    let current_time = now().timestamp();
+    if let Err(e) = service_context
+        .bill_template_service
+        .issue_due_bills(current_time as u64)
+        .await
+    {
+        error!("Error while running Issue Recurring Bills Job: {e}");
+    }
+    info!("Finished running Issue Recurring Bills Job");
+}
+
+async fn run_refresh_contacts_job(service_context: ServiceContext) {
+    info!("Running Refresh Contacts Job");
+    if let Err(e) = service_context.contact_service.refresh_contacts().await {
+        error!("Error while running Refresh Contacts Job: {e}");
+    }
+    info!("Finished running Refresh Contacts Job");
+}
+
 async fn run_check_bill_timeouts(service_context: ServiceContext) {
     info!("Running Check Bill Timeouts Job");
     let current_time = now().timestamp();
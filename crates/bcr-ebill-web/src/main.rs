@@ -8,6 +8,7 @@ use log::{error, info};
 use tokio::{spawn, sync::broadcast};
 
 mod api_docs;
+mod auth;
 mod config;
 mod constants;
 mod data;
@@ -27,12 +28,16 @@ lazy_static! {
 async fn main() -> Result<()> {
     env_logger::init();
     let conf = CONFIG.clone();
+    conf.check_api_password()?;
     // Initialize the API
     let api_config = bcr_ebill_api::Config {
         bitcoin_network: conf.bitcoin_network.clone(),
-        nostr_relay: conf.nostr_relay.clone(),
+        nostr_relays: conf.nostr_relay.clone(),
+        nostr_write_quorum: conf.nostr_write_quorum,
         surreal_db_connection: conf.surreal_db_connection.clone(),
         data_dir: conf.data_dir.clone(),
+        email: conf.email_config()?,
+        contact_verification_policy: conf.contact_verification_policy(),
     };
     info!("Chosen Network: {:?}", api_config.bitcoin_network());
     bcr_ebill_api::init(api_config.clone())?;
@@ -1,7 +1,7 @@
 use crate::api_docs::ApiDocs;
 use crate::handlers;
 use bcr_ebill_api::service::ServiceContext;
-use log::info;
+use log::{info, warn};
 use rocket::http::Method;
 use rocket::{Build, Config, Request, Rocket, catch, catchers, routes};
 use rocket_cors::{AllowedHeaders, AllowedOrigins, CorsOptions};
@@ -10,6 +10,7 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::CONFIG;
+use crate::auth::AuthContext;
 use crate::constants::MAX_FILE_SIZE_BYTES;
 use rocket::data::ByteUnit;
 use rocket::figment::Figment;
@@ -50,7 +51,7 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
         .merge(("address", conf.http_address.to_owned()));
 
     let cors = CorsOptions::default()
-        .allowed_origins(AllowedOrigins::all())
+        .allowed_origins(AllowedOrigins::some_exact(&conf.cors_origins()))
         .allowed_headers(AllowedHeaders::all())
         .allowed_methods(
             vec![
@@ -69,14 +70,45 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
         .to_cors()
         .expect("Cors setup failed");
 
+    let auth = AuthContext::new(
+        conf.api_password.as_deref(),
+        conf.session_timeout_seconds,
+        conf.reauthentication_timeout_seconds,
+    );
+    if !auth.is_enabled() {
+        warn!("No API_PASSWORD set - the HTTP API is accessible without authentication locally");
+    }
+
     let rocket = rocket::custom(config)
         .attach(cors.clone())
         // catchers for CORS and API errors
         .mount("/api/", rocket_cors::catch_all_options_routes())
         .mount("/api/", routes![handlers::default_api_error_catcher])
-        .register("/api/", catchers![not_found])
+        .register("/api/", catchers![not_found, unauthorized, forbidden])
         .manage(context)
+        .manage(auth)
         .manage(cors)
+        .mount(
+            "/api/auth",
+            routes![
+                handlers::auth::status,
+                handlers::auth::login,
+                handlers::auth::reauthenticate,
+                handlers::auth::logout,
+                handlers::auth::list_tokens,
+                handlers::auth::create_token,
+                handlers::auth::remove_token,
+            ],
+        )
+        .mount(
+            "/api/webhooks",
+            routes![
+                handlers::webhooks::list,
+                handlers::webhooks::create,
+                handlers::webhooks::remove,
+                handlers::webhooks::deliveries,
+            ],
+        )
         .mount("/api/exit", routes![handlers::exit])
         .mount("/api/status", routes![handlers::status])
         .mount("/api/currencies", routes![handlers::currencies])
@@ -89,6 +121,8 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
                 handlers::identity::create_identity,
                 handlers::identity::change_identity,
                 handlers::identity::return_identity,
+                handlers::identity::contact_card,
+                handlers::identity::contact_card_qr_code,
                 handlers::identity::active,
                 handlers::identity::switch,
                 handlers::identity::get_seed_phrase,
@@ -103,7 +137,13 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
             "/api/contacts",
             routes![
                 handlers::contacts::new_contact,
+                handlers::contacts::new_contact_from_profile,
+                handlers::contacts::new_contact_from_card,
+                handlers::contacts::export_vcards,
+                handlers::contacts::import_vcards,
+                handlers::contacts::refresh_contact,
                 handlers::contacts::edit_contact,
+                handlers::contacts::set_verification,
                 handlers::contacts::remove_contact,
                 handlers::contacts::return_contacts,
                 handlers::contacts::return_contact,
@@ -116,6 +156,8 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
             routes![
                 handlers::company::list,
                 handlers::company::detail,
+                handlers::company::contact_card,
+                handlers::company::contact_card_qr_code,
                 handlers::company::get_file,
                 handlers::company::upload_file,
                 handlers::company::create,
@@ -130,6 +172,8 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
             routes![
                 handlers::bill::all_bills_from_all_identities,
                 handlers::bill::issue_bill,
+                handlers::bill::upload_batch_file,
+                handlers::bill::issue_batch,
                 handlers::bill::bill_detail,
                 handlers::bill::list,
                 handlers::bill::list_light,
@@ -146,6 +190,10 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
                 handlers::bill::bitcoin_key,
                 handlers::bill::numbers_to_words_for_sum,
                 handlers::bill::search,
+                handlers::bill::export,
+                handlers::bill::bill_pdf,
+                handlers::bill::chain_bundle,
+                handlers::bill::verify_chain_bundle,
                 handlers::bill::get_past_endorsees_for_bill,
                 handlers::bill::get_endorsements_for_bill,
                 handlers::bill::reject_to_accept_bill,
@@ -156,6 +204,19 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
                 handlers::bill::request_to_recourse_bill_acceptance,
             ],
         )
+        .mount(
+            "/api/bill_templates",
+            routes![
+                handlers::bill_templates::list,
+                handlers::bill_templates::detail,
+                handlers::bill_templates::create,
+                handlers::bill_templates::edit,
+                handlers::bill_templates::remove,
+                handlers::bill_templates::issue,
+                handlers::bill_templates::issued_bills,
+                handlers::bill_templates::for_bill,
+            ],
+        )
         .mount(
             "/api/quote",
             routes![
@@ -168,6 +229,13 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
             routes![
                 handlers::notifications::list_notifications,
                 handlers::notifications::mark_notification_done,
+                handlers::notifications::get_email_preference,
+                handlers::notifications::set_email_preference,
+                handlers::notifications::list_relays,
+                handlers::notifications::list_quarantined_events,
+                handlers::notifications::release_quarantined_event,
+                handlers::notifications::discard_quarantined_event,
+                handlers::notifications::list_outbox,
                 handlers::notifications::websocket,
                 handlers::notifications::sse,
                 handlers::notifications::trigger_msg,
@@ -203,6 +271,26 @@ pub fn rocket_main(conf: crate::Config, context: ServiceContext) -> Rocket<Build
     rocket
 }
 
+#[catch(401)]
+fn unauthorized() -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(
+        "unauthorized",
+        String::from("A valid session is required, log in via /api/auth/login"),
+        401,
+    ))
+}
+
+#[catch(403)]
+fn forbidden() -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(
+        "forbidden",
+        String::from(
+            "Not allowed, enter the password again via /api/auth/reauthenticate, or use an API token with the required scope",
+        ),
+        403,
+    ))
+}
+
 #[catch(404)]
 fn not_found(req: &Request) -> Json<ErrorResponse> {
     Json(ErrorResponse::new(